@@ -51,6 +51,11 @@ pub enum AcdError {
         path: String,
         reason: String
     },
+    #[error("Failed to decode '{path}'. It wasn't packed with the key for the '{key_source}' folder; the car folder may have been renamed")]
+    KeyMismatch {
+        path: String,
+        key_source: String
+    },
 }
 
 fn missing_parent_error(path: &Path) -> AcdError {
@@ -97,6 +102,12 @@ impl AcdArchive {
     pub fn load_from_acd_file_with_key(acd_path: &Path, in_key: &str) -> Result<AcdArchive> {
         let key = generate_acd_key(in_key)?;
         let contents = extract_acd(acd_path, &key)?;
+        if decoded_with_wrong_key(&contents.files) {
+            return Err(AcdError::KeyMismatch {
+                path: acd_path.display().to_string(),
+                key_source: in_key.to_owned()
+            });
+        }
         Ok(AcdArchive{
             acd_path: acd_path.to_path_buf(),
             extract_key: key,
@@ -135,6 +146,10 @@ impl AcdArchive {
         self.contents.files.contains_key(filename)
     }
 
+    pub fn file_names(&self) -> Vec<String> {
+        self.contents.files.keys().cloned().collect()
+    }
+
     pub fn update_file_data(&mut self, filename: String, data: Vec<u8>) -> Option<Vec<u8>> {
         self.contents.files.insert(filename, data)
     }
@@ -143,6 +158,18 @@ impl AcdArchive {
         self.contents.files.shift_remove(filename)
     }
 
+    pub fn contents_match(&self, other: &AcdArchive) -> bool {
+        if self.contents.files.len() != other.contents.files.len() {
+            return false;
+        }
+        self.contents.files.iter().all(|(filename, data)| {
+            match other.contents.files.get(filename) {
+                None => false,
+                Some(other_data) => data == other_data
+            }
+        })
+    }
+
     pub fn unpack(&self) -> Result<()> {
         self.unpack_to(self.acd_path.parent().ok_or(missing_parent_error(&self.acd_path))?.join("data").as_path())
     }
@@ -161,23 +188,52 @@ impl AcdArchive {
         self.write_to(self.acd_path.as_path())
     }
 
+    /// Pack the archive into `out_path`. The packed data is written to a temporary file and read
+    /// back to check its size and contents before it replaces anything at `out_path`
     pub fn write_to(&self, out_path: &Path) -> Result<()> {
         let parent_folder = get_parent_folder_str(out_path)?;
         let key = generate_acd_key(parent_folder)?;
-        let mut out_file = File::create(out_path)?;
+        let mut packed_data: Vec<u8> = Vec::new();
         for filename in self.contents.files.keys() {
             let mut key_byte_iter = key.chars().cycle();
             let filename_len = filename.len() as u32;
-            out_file.write(&filename_len.to_le_bytes())?;
-            out_file.write(filename.as_bytes())?;
+            packed_data.extend_from_slice(&filename_len.to_le_bytes());
+            packed_data.extend_from_slice(filename.as_bytes());
             let data_len = self.contents.files[filename].len() as u32;
-            out_file.write(&data_len.to_le_bytes())?;
+            packed_data.extend_from_slice(&data_len.to_le_bytes());
             for byte in &self.contents.files[filename] {
-                let out_byte = byte + u32::from(key_byte_iter.next().unwrap()) as u8;
-                out_file.write(&[out_byte, 0, 0, 0])?;
+                let out_byte = byte.wrapping_add(u32::from(key_byte_iter.next().unwrap()) as u8);
+                packed_data.extend_from_slice(&[out_byte, 0, 0, 0]);
             }
         }
-        out_file.flush()?;
+
+        let tmp_path = out_path.with_extension("acd.tmp");
+        {
+            let mut out_file = File::create(&tmp_path)?;
+            out_file.write_all(&packed_data)?;
+            out_file.flush()?;
+        }
+        if let Err(e) = self.verify_packed_file(&tmp_path, packed_data.len(), &key) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+        fs::rename(&tmp_path, out_path)?;
+        Ok(())
+    }
+
+    fn verify_packed_file(&self, packed_path: &Path, expected_size: usize, key: &str) -> Result<()> {
+        let encode_error = |reason: String| AcdError::EncodeError {
+            path: packed_path.display().to_string(),
+            reason
+        };
+        let written_size = fs::metadata(packed_path)?.len();
+        if written_size != expected_size as u64 {
+            return Err(encode_error(format!("Wrote {} bytes but expected {}", written_size, expected_size)));
+        }
+        let written_contents = extract_acd(packed_path, key)?;
+        if written_contents.files != self.contents.files {
+            return Err(encode_error("Written data doesn't match the archive contents".to_string()));
+        }
         Ok(())
     }
 }
@@ -247,6 +303,21 @@ pub fn generate_acd_key(folder_name: &str) -> Result<String> {
     Ok(key_list.join("-"))
 }
 
+/// Whether extracted archive contents look like they were decoded with the wrong key.
+/// Text packed with one key and decoded with another ends up with control characters scattered
+/// through it, so this is the case when every non-empty ini file contains one
+fn decoded_with_wrong_key(files: &IndexMap<String, Vec<u8>>) -> bool {
+    let mut ini_files = files.iter().filter(|(filename, data)| {
+        filename.ends_with(".ini") && !data.is_empty()
+    }).peekable();
+    if ini_files.peek().is_none() {
+        return false;
+    }
+    ini_files.all(|(_, data)| data.iter().any(|byte| {
+        (*byte < 0x20 && !matches!(*byte, b'\t' | b'\n' | b'\r')) || *byte == 0x7f
+    }))
+}
+
 // If first 4 bytes -> [A9, FB, FF, FF] signifies that the car is DLC
 const DLC_BYTE_MARKER: &'static[u8] = &[0xA9, 0xFB, 0xFF, 0xFF];
 
@@ -417,7 +488,7 @@ mod tests {
     use std::fs::File;
     use std::io::Read;
     use std::path::Path;
-    use crate::car::acd_utils::{AcdArchive, generate_acd_key};
+    use crate::car::acd_utils::{AcdArchive, AcdError, generate_acd_key};
 
     #[test]
     fn derive_acd_key() {
@@ -434,6 +505,21 @@ mod tests {
         println!("{}", generate_acd_key("dallara_f312").unwrap())
     }
 
+    #[test]
+    fn wrong_key_is_detected() {
+        let fixture_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-turbo-with-ctrls/data");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("acd_key_check");
+        std::fs::create_dir_all(&car_path).unwrap();
+        let acd_path = car_path.join("data.acd");
+        AcdArchive::create_from_data_dir(&fixture_data).unwrap().write_to(&acd_path).unwrap();
+
+        let archive = AcdArchive::load_from_acd_file(&acd_path).unwrap();
+        assert!(archive.contents_match(&AcdArchive::create_from_data_dir(&fixture_data).unwrap()));
+        let wrong_key_result = AcdArchive::load_from_acd_file_with_key(&acd_path, "some_other_car");
+        assert!(matches!(wrong_key_result, Err(AcdError::KeyMismatch { .. })));
+    }
+
     #[test]
     fn extract_acd() {
         ///~/Downloads/car/RSS_Formula_RSS_4_2024-Assetto_Corsa-v1/content/cars/rss_formula_rss_4_2024
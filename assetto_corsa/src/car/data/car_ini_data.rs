@@ -25,6 +25,7 @@ use crate::car::Car;
 use crate::ini_utils::Ini;
 use crate::error::{Error, ErrorKind, PropertyParseError, Result};
 use crate::ini_utils;
+use crate::traits::{CarDataFile, DataInterface};
 
 
 #[derive(Debug)]
@@ -103,6 +104,21 @@ impl<'a> CarIniData<'a> {
     }
 }
 
+impl<'a> CarDataFile for CarIniData<'a> {
+    fn ini_data(&self) -> &Ini {
+        &self.ini_config
+    }
+    fn mut_ini_data(&mut self) -> &mut Ini {
+        &mut self.ini_config
+    }
+    fn data_interface(&self) -> &dyn DataInterface {
+        self.car.data_interface()
+    }
+    fn mut_data_interface(&mut self) -> &mut dyn DataInterface {
+        self.car.mut_data_interface()
+    }
+}
+
 #[derive(Debug)]
 pub enum CarVersion {
     One,
@@ -35,6 +35,8 @@ pub struct DigitalInstruments<'a> {
 impl<'a> DigitalInstruments<'a> {
     pub const INI_FILENAME: &'static str = "digital_instruments.ini";
 
+    /// Load the digital instruments of `car`. Returns None if the car doesn't have a
+    /// digital_instruments.ini as the file is optional
     pub fn from_car(car: &'a mut Car) -> Result<Option<DigitalInstruments<'a>>> {
         match car.data_interface.get_original_file_data(DigitalInstruments::INI_FILENAME)? {
             None => Ok(None),
@@ -71,3 +73,56 @@ impl<'a> CarDataFile for DigitalInstruments<'a> {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::car::Car;
+    use crate::car::data::digital_instruments::DigitalInstruments;
+    use crate::car::data::digital_instruments::shift_lights::ShiftLights;
+    use crate::traits::{CarDataFile, OptionalDataSection, update_car_data};
+
+    fn create_test_car(root: &Path, digital_instruments_fixture: Option<&str>) -> std::path::PathBuf {
+        let car_path = root.join("car");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        if let Some(fixture) = digital_instruments_fixture {
+            let fixture_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("src/test-data")
+                .join(fixture)
+                .join(DigitalInstruments::INI_FILENAME);
+            std::fs::copy(fixture_path, car_path.join("data").join(DigitalInstruments::INI_FILENAME)).unwrap();
+        }
+        car_path
+    }
+
+    #[test]
+    fn missing_file_is_skipped() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path(), None);
+        let mut car = Car::load_from_path(&car_path).unwrap();
+        let digital_instruments = DigitalInstruments::from_car(&mut car).unwrap();
+        assert!(digital_instruments.is_none());
+    }
+
+    #[test]
+    fn shift_lights_updated_with_duplicate_led_sections() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path(), Some("duplicate-led-sections"));
+        let mut car = Car::load_from_path(&car_path).unwrap();
+        {
+            let mut digital_instruments = DigitalInstruments::from_car(&mut car).unwrap().unwrap();
+            let mut shift_lights = ShiftLights::load_from_parent(&digital_instruments).unwrap().unwrap();
+            assert_eq!(shift_lights.num_leds(), 2);
+            shift_lights.update_limiter(7000, 8000);
+            update_car_data(&mut digital_instruments, &shift_lights).unwrap();
+            digital_instruments.write().unwrap();
+        }
+        let digital_instruments = DigitalInstruments::from_car(&mut car).unwrap().unwrap();
+        let ini_data = digital_instruments.ini_data();
+        assert_eq!(ini_data.get_value("LED_0", "RPM_SWITCH"), Some(String::from("6800")));
+        assert_eq!(ini_data.get_value("LED_0", "EMISSIVE"), Some(String::from("0,100,0")));
+        assert_eq!(ini_data.get_value("LED_0", "BLINK_HZ"), Some(String::from("0")));
+        assert_eq!(ini_data.get_value("LED_1", "BLINK_SWITCH"), Some(String::from("8000")));
+        assert_eq!(ini_data.get_value("ITEM_0", "TYPE"), Some(String::from("SPEED")));
+    }
+}
@@ -106,10 +106,9 @@ impl CarDataUpdater for Gearbox {
         let ini_data = car_data.mut_ini_data();
         let current_count_opt: Option<i32> = ini_utils::get_value(ini_data, "GEARS", "COUNT");
         if let Some(current_count) = current_count_opt {
-            if current_count != self.gear_count {
-                for gear_num in 1..current_count+1 {
-                    ini_data.remove_value("GEARS", Gearbox::create_gear_key(gear_num).as_str());
-                }
+            // Gears that still exist are updated in place so that they keep their position
+            for gear_num in self.gear_count+1..current_count+1 {
+                ini_data.remove_value("GEARS", Gearbox::create_gear_key(gear_num).as_str());
             }
         }
         ini_data.set_value("GEARS", "COUNT", self.gear_count.to_string());
@@ -67,7 +67,7 @@ impl<'a> Drivetrain<'a> {
         }?;
         Ok(Drivetrain {
             car,
-            ini_data: Ini::load_from_string(String::from_utf8_lossy(file_data.as_slice()).into_owned())
+            ini_data: Ini::load_from_bytes(file_data.as_slice())
         })
     }
 
@@ -141,7 +141,7 @@ mod tests {
     use crate::Car;
     use crate::car::data::drivetrain::{Drivetrain, Gearbox, Traction};
     use crate::car::data::drivetrain::traction::DriveType;
-    use crate::traits::{CarDataUpdater, MandatoryDataSection};
+    use crate::traits::{CarDataFile, CarDataUpdater, MandatoryDataSection};
 
     const TEST_DATA_PATH: &'static str = "test_data";
     const TEMP_TEST_CAR_NAME_PREFIX: &'static str = "tmp_car";
@@ -215,6 +215,53 @@ mod tests {
     //     Ok(())
     // }
 
+    #[test]
+    fn mechanical_efficiency_blends_awd_torque_split() {
+        use crate::car::data::drivetrain::traction::AwdTorqueSplit;
+
+        let traction = |drive_type, front_share: Option<f64>| Traction {
+            drive_type,
+            awd_split: front_share.map(|front_share| AwdTorqueSplit { front_share })
+        };
+        assert_eq!(traction(DriveType::RWD, None).mechanical_efficiency(), 0.85);
+        assert_eq!(traction(DriveType::FWD, None).mechanical_efficiency(), 0.9);
+        assert_eq!(traction(DriveType::AWD, None).mechanical_efficiency(), 0.75);
+        assert!((traction(DriveType::AWD, Some(0.5)).mechanical_efficiency() - 0.7525).abs() < 1e-9);
+        assert!((traction(DriveType::AWD2, Some(0.3)).mechanical_efficiency() - 0.7439).abs() < 1e-9);
+    }
+
+    #[test]
+    fn awd2_split_not_read_from_awd_section() {
+        let fixture_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-turbo-with-ctrls");
+        let mut car = Car::load_from_path(&fixture_path).unwrap();
+        let drivetrain = Drivetrain::from_car(&mut car).unwrap();
+        let traction = Traction::load_from_parent(&drivetrain).unwrap();
+        assert_eq!(traction.drive_type, DriveType::AWD2);
+        // The fixture's FRONT_SHARE is in [AWD], which AC doesn't use for AWD2 cars
+        assert_eq!(traction.awd_split, None);
+        assert_eq!(traction.mechanical_efficiency(), DriveType::AWD2.mechanical_efficiency());
+    }
+
+    #[test]
+    fn gear_ratio_edit_only_changes_one_line() {
+        let fixture_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/car/data/drivetrain/test_data/six-gears");
+        let original = std::fs::read_to_string(fixture_path.join("data").join(Drivetrain::INI_FILENAME)).unwrap();
+        let mut car = Car::load_from_path(&fixture_path).unwrap();
+        let mut drivetrain = Drivetrain::from_car(&mut car).unwrap();
+        let mut gearbox = Gearbox::load_from_parent(&drivetrain).unwrap();
+        let mut ratios = gearbox.gear_ratios().clone();
+        ratios[1] = 1.98;
+        gearbox.update_gears(ratios);
+        gearbox.update_car_data(&mut drivetrain).unwrap();
+
+        let updated = drivetrain.ini_data().to_string();
+        let original_lines: Vec<&str> = original.lines().collect();
+        let updated_lines: Vec<&str> = updated.lines().collect();
+        assert_eq!(original_lines.len(), updated_lines.len());
+        let changed: Vec<(&str, &str)> = original_lines.into_iter().zip(updated_lines).filter(|(a, b)| a != b).collect();
+        assert_eq!(changed, vec![("GEAR_2=1.94", "GEAR_2=1.980")]);
+    }
+
     #[test]
     fn update_traction() -> Result<(), String> {
         let update_drive_type = DriveType::FWD;
@@ -79,16 +79,61 @@ impl Display for DriveType {
     }
 }
 
+/// The efficiency of the centre differential or transfer case of an AWD car
+const CENTRE_DIFF_EFFICIENCY: f64 = 0.86;
+
+/// How the torque of an AWD car is split between the front and rear axles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AwdTorqueSplit {
+    /// The fraction of the torque sent to the front axle, between 0 and 1
+    pub front_share: f64
+}
+
+impl AwdTorqueSplit {
+    pub const AWD_SECTION_NAME: &'static str = "AWD";
+    pub const AWD2_SECTION_NAME: &'static str = "AWD2";
+
+    /// Read the split from the section that AC uses for `drive_type`. AWD and AWD2 cars are
+    /// configured by separate sections so the [AWD] values are ignored for AWD2 cars
+    fn load_from_ini(ini_data: &ini_utils::Ini, drive_type: DriveType) -> Option<AwdTorqueSplit> {
+        let section_name = match drive_type {
+            DriveType::AWD => Self::AWD_SECTION_NAME,
+            DriveType::AWD2 => Self::AWD2_SECTION_NAME,
+            _ => return None
+        };
+        let front_share: f64 = ini_utils::get_value(ini_data, section_name, "FRONT_SHARE")?;
+        Some(AwdTorqueSplit { front_share: front_share.clamp(0.0, 1.0) })
+    }
+}
+
 #[derive(Debug)]
 pub struct Traction {
-    pub drive_type: DriveType
+    pub drive_type: DriveType,
+    /// The torque split from the [AWD] or [AWD2] section matching the drive type, if it has one
+    pub awd_split: Option<AwdTorqueSplit>
+}
+
+impl Traction {
+    /// The mechanical efficiency of the drivetrain. For AWD cars with a known torque split this
+    /// blends the efficiency of the front and rear axles by their share of the torque and applies
+    /// the centre differential losses. Otherwise the constant for the drive type is used
+    pub fn mechanical_efficiency(&self) -> f64 {
+        match (self.drive_type, &self.awd_split) {
+            (DriveType::AWD | DriveType::AWD2, Some(split)) => {
+                let axle_efficiency = split.front_share * DriveType::FWD.mechanical_efficiency() +
+                    (1.0 - split.front_share) * DriveType::RWD.mechanical_efficiency();
+                axle_efficiency * CENTRE_DIFF_EFFICIENCY
+            }
+            _ => self.drive_type.mechanical_efficiency()
+        }
+    }
 }
 
 impl MandatoryDataSection for Traction {
     fn load_from_parent(parent_data: &dyn CarDataFile) -> Result<Self> where Self: Sized {
-        Ok(Traction{
-            drive_type: get_mandatory_field(parent_data.ini_data(), "TRACTION", "TYPE")?
-        })
+        let drive_type = get_mandatory_field(parent_data.ini_data(), "TRACTION", "TYPE")?;
+        let awd_split = AwdTorqueSplit::load_from_ini(parent_data.ini_data(), drive_type);
+        Ok(Traction{ drive_type, awd_split })
     }
 }
 
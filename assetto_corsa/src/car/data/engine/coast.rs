@@ -45,6 +45,18 @@ impl CoastCurve {
             non_linearity
         }
     }
+
+    pub fn reference_rpm(&self) -> i32 {
+        self.reference_rpm
+    }
+
+    pub fn torque(&self) -> i32 {
+        self.torque
+    }
+
+    pub fn non_linearity(&self) -> f64 {
+        self.non_linearity
+    }
 }
 
 impl MandatoryDataSection for CoastCurve {
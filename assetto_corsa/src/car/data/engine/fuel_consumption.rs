@@ -90,7 +90,8 @@ struct FuelConsumptionEfficiency {
 pub struct FuelConsumptionFlowRate {
     base_data: ExtendedFuelConsumptionBaseData,
     max_fuel_flow_lut: Option<LutProperty<i32, i32>>,
-    max_fuel_flow: i32
+    max_fuel_flow: i32,
+    log_fuel_flow: bool
 }
 
 impl FuelConsumptionFlowRate {
@@ -113,7 +114,20 @@ impl FuelConsumptionFlowRate {
                 Some(lut_vec) => {
                     Some(FuelConsumptionFlowRate::create_flow_limit_lut(lut_vec))
                 }},
-            max_fuel_flow
+            max_fuel_flow,
+            log_fuel_flow: false
+        }
+    }
+
+    /// Remove the CSP extended physics fuel consumption data from `car_data` so that the base
+    /// game fuel consumption in car.ini is used
+    pub fn remove_from(car_data: &mut dyn CarDataFile) {
+        let ini_data = car_data.mut_ini_data();
+        ini_data.remove_section(Self::SECTION_NAME);
+        for key in ["IDLE_THROTTLE", "IDLE_CUTOFF", "MECHANICAL_EFFICIENCY"] {
+            if ini_data.section_contains_property(ExtendedFuelConsumptionBaseData::SECTION_NAME, key) {
+                ini_data.remove_value(ExtendedFuelConsumptionBaseData::SECTION_NAME, key);
+            }
         }
     }
 
@@ -144,10 +158,13 @@ impl FuelConsumptionFlowRate {
         if let Some(val) = ini_utils::get_value(ini_data, Self::SECTION_NAME, "MAX_FUEL_FLOW") {
             max_fuel_flow = val;
         }
+        let log_fuel_flow = ini_utils::get_value::<i32>(ini_data, Self::SECTION_NAME, "LOG_FUEL_FLOW")
+            .map_or(false, |val| val != 0);
         Ok(Some(FuelConsumptionFlowRate{
             base_data: ExtendedFuelConsumptionBaseData::load_from_ini(ini_data)?,
             max_fuel_flow_lut,
-            max_fuel_flow
+            max_fuel_flow,
+            log_fuel_flow
         }))
     }
 
@@ -161,6 +178,11 @@ impl FuelConsumptionFlowRate {
         lut_data.into_iter().collect()
     }
 
+    /// Whether AC should log the fuel flow to its log file while driving
+    pub fn set_log_fuel_flow(&mut self, log_fuel_flow: bool) {
+        self.log_fuel_flow = log_fuel_flow;
+    }
+
     pub fn update_max_fuel_flow_lut(&mut self, fuel_flow_vec: Vec<(i32, i32)>) -> Option<Vec<(i32, i32)>> {
         match &mut self.max_fuel_flow_lut {
             None => {
@@ -180,7 +202,7 @@ impl CarDataUpdater for FuelConsumptionFlowRate {
         let ini_data = car_data.mut_ini_data();
         ini_data.remove_section(Self::SECTION_NAME);
         ini_utils::set_value(ini_data, Self::SECTION_NAME, "MAX_FUEL_FLOW", self.max_fuel_flow);
-        ini_utils::set_value(ini_data, Self::SECTION_NAME, "LOG_FUEL_FLOW", 0);
+        ini_utils::set_value(ini_data, Self::SECTION_NAME, "LOG_FUEL_FLOW", self.log_fuel_flow as i32);
         if let Some(flow_lut) = &self.max_fuel_flow_lut {
             flow_lut.update_car_data(car_data)?;
         }
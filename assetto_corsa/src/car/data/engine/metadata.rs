@@ -26,8 +26,9 @@ use std::path::Path;
 use toml::Value;
 use toml::value::Table;
 use tracing::error;
-use crate::error::{Result};
+use crate::error::{Error, ErrorKind, Result};
 use crate::car::lut_utils::load_lut_from_path;
+use crate::Car;
 
 
 pub enum Source {
@@ -68,6 +69,8 @@ pub struct Metadata {
 
 #[allow(dead_code)]
 impl Metadata {
+    pub const FILENAME: &'static str = "engine-metadata.toml";
+
     pub fn new() -> Metadata {
         Metadata {
             toml_config: toml::Value::Table(toml::map::Map::new()),
@@ -77,7 +80,7 @@ impl Metadata {
     }
 
     fn load_from_dir(data_dir: &Path) -> Result<Option<Metadata>>{
-        let metadata_path = Path::new(data_dir).join("engine-metadata.toml");
+        let metadata_path = Path::new(data_dir).join(Metadata::FILENAME);
         if !metadata_path.exists() {
             return Ok(None);
         }
@@ -177,6 +180,39 @@ impl Metadata {
         self.set_int_value(String::from("mass_kg"), mass);
     }
 
+    pub fn engine_name(&self) -> Option<&str> {
+        self.toml_config.get("engine_name").and_then(|name| name.as_str())
+    }
+
+    pub fn set_engine_name(&mut self, name: String) {
+        self.set_string_value(String::from("engine_name"), name);
+    }
+
+    /// The sha256 hash, as a hex string, of the file the engine was built from
+    pub fn source_file_hash(&self) -> Option<&str> {
+        self.toml_config.get("source_file_sha256").and_then(|hash| hash.as_str())
+    }
+
+    pub fn set_source_file_hash(&mut self, hash: String) {
+        self.set_string_value(String::from("source_file_sha256"), hash);
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        match toml::to_string(&self.toml_config) {
+            Ok(toml_string) => Ok(toml_string.into_bytes()),
+            Err(e) => Err(Error::new(ErrorKind::UpdateError,
+                                     format!("Failed to encode {}. {}", Metadata::FILENAME, e.to_string())))
+        }
+    }
+
+    /// Write the metadata into the data folder/data.acd of `car`
+    pub fn write_to_car(&self, car: &mut Car) -> Result<()> {
+        let data_interface = car.mut_data_interface();
+        data_interface.update_file_data(Metadata::FILENAME, self.to_bytes()?);
+        data_interface.write()?;
+        Ok(())
+    }
+
     fn boost_curve_data(&self) -> Option<&Vec<(i32, f64)>> {
         match &self.boost_curve_data {
             Some(data) => Some(data),
@@ -218,3 +254,28 @@ impl Metadata {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::car::data::engine::metadata::{Metadata, Source};
+
+    #[test]
+    fn metadata_round_trips_through_toml() {
+        let mut metadata = Metadata::new();
+        metadata.set_version(2);
+        metadata.set_source(Source::Automation);
+        metadata.set_engine_name(String::from("Test Engine - Race"));
+        metadata.set_source_file_hash(String::from("abcd1234"));
+
+        let toml_string = String::from_utf8(metadata.to_bytes().unwrap()).unwrap();
+        let reloaded = Metadata {
+            toml_config: toml_string.parse::<toml::Value>().unwrap(),
+            boost_curve_data: None,
+            fuel_flow_data: None
+        };
+        assert_eq!(reloaded.version(), 2);
+        assert!(matches!(reloaded.source(), Some(Source::Automation)));
+        assert_eq!(reloaded.engine_name(), Some("Test Engine - Race"));
+        assert_eq!(reloaded.source_file_hash(), Some("abcd1234"));
+    }
+}
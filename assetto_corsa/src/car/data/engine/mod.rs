@@ -27,13 +27,14 @@ pub mod damage;
 pub mod coast;
 pub mod turbo_ctrl;
 pub mod turbo;
+pub mod throttle_map;
 
 use std::collections::HashMap;
 use std::path::Path;
 use crate::car::Car;
 use crate::error::{Result, Error, ErrorKind};
 use crate::ini_utils::Ini;
-use crate::traits::{CarDataFile, DataInterface};
+use crate::traits::{CarDataFile, DataInterface, extract_mandatory_section};
 
 pub use metadata::Metadata;
 pub use engine_data::EngineData;
@@ -43,6 +44,7 @@ pub use damage::Damage;
 pub use coast::CoastCurve;
 pub use turbo::Turbo;
 pub use turbo_ctrl::TurboControllerFile;
+pub use throttle_map::ThrottleMap;
 
 
 #[derive(Debug)]
@@ -78,7 +80,7 @@ impl<'a> Engine<'a> {
         }?;
         Ok(Engine {
             car,
-            ini_data: Ini::load_from_string(String::from_utf8_lossy(file_data.as_slice()).into_owned())
+            ini_data: Ini::load_from_bytes(file_data.as_slice())
         })
     }
 
@@ -99,6 +101,36 @@ impl<'a> Engine<'a> {
         self.ini_data.write_to_file(&dir.join(Engine::INI_FILENAME))?;
         Ok(())
     }
+
+    /// The number of `TURBO_n` sections defined in the engine.ini
+    pub fn turbo_count(&self) -> usize {
+        Turbo::count_turbo_sections(&self.ini_data)
+    }
+
+    pub fn is_forced_induction(&self) -> bool {
+        self.turbo_count() > 0
+    }
+}
+
+/// Write the engine.ini, power curve lut and any turbo controller files of `car` into `dir`
+/// without modifying the car itself
+pub fn export_engine_files(car: &mut Car, dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(Error::new(ErrorKind::ArgumentError,
+                              format!("{} is not a directory", dir.display())));
+    }
+    {
+        let mut engine = Engine::from_car(car)?;
+        engine.write_to_dir(dir)?;
+        let power_curve = extract_mandatory_section::<PowerCurve>(&engine)?;
+        power_curve.write_to_dir(dir)?;
+    }
+    let mut idx = 0;
+    while let Some(ctrl_file) = TurboControllerFile::from_car(car, idx)? {
+        ctrl_file.write_to_dir(dir)?;
+        idx += 1;
+    }
+    Ok(())
 }
 
 impl<'a> CarDataFile for Engine<'a> {
@@ -118,6 +150,95 @@ impl<'a> CarDataFile for Engine<'a> {
 
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+    use crate::Car;
+    use crate::car::data::Engine;
+    use crate::car::data::engine::{PowerCurve, ThrottleMap};
+    use crate::car::lut_utils::LutType;
+    use crate::traits::{extract_mandatory_section, extract_optional_section, update_car_data};
+
+    #[test]
+    fn turbo_count_from_car() {
+        let car_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-turbo-with-ctrls");
+        let mut car = Car::load_from_path(&car_path).unwrap();
+        let engine = Engine::from_car(&mut car).unwrap();
+        assert_eq!(engine.turbo_count(), 2);
+        assert!(engine.is_forced_induction());
+    }
+
+    #[test]
+    fn inline_power_curve_round_trip() {
+        let fixture_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-inline-power-curve/data");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("inline_power_curve");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        std::fs::copy(fixture_data.join(Engine::INI_FILENAME), car_path.join("data").join(Engine::INI_FILENAME)).unwrap();
+
+        let new_curve = vec![(0, 60.0), (1500, 220.5), (4000, 310.0), (7500, 250.0)];
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let mut engine = Engine::from_car(&mut car).unwrap();
+            let mut power_curve = extract_mandatory_section::<PowerCurve>(&engine).unwrap();
+            assert_eq!(power_curve.get_curve_data().get(&4000), Some(&275.0));
+            power_curve.update(new_curve.clone());
+            update_car_data(&mut engine, &power_curve).unwrap();
+            engine.write().unwrap();
+        }
+        let reloaded_curve = {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let engine = Engine::from_car(&mut car).unwrap();
+            let power_curve = extract_mandatory_section::<PowerCurve>(&engine).unwrap();
+            assert!(matches!(power_curve.get_lut(), LutType::Inline(_)));
+            power_curve.get_curve_data()
+        };
+        let lut_file_created = car_path.join("data").join("power.lut").exists();
+        assert!(!lut_file_created);
+        assert_eq!(reloaded_curve.into_iter().collect::<Vec<_>>(), new_curve);
+    }
+
+    #[test]
+    fn windows_1252_engine_ini_written_back_unchanged() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/windows-1252").join(Engine::INI_FILENAME);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("windows_1252_engine");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        std::fs::copy(&fixture, car_path.join("data").join(Engine::INI_FILENAME)).unwrap();
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let mut engine = Engine::from_car(&mut car).unwrap();
+            engine.write().unwrap();
+        }
+        let written = std::fs::read(car_path.join("data").join(Engine::INI_FILENAME)).unwrap();
+        assert_eq!(written, std::fs::read(&fixture).unwrap());
+    }
+
+    #[test]
+    fn throttle_map_round_trip() {
+        let fixture_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-inline-power-curve/data");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("throttle_map");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        std::fs::copy(fixture_data.join(Engine::INI_FILENAME), car_path.join("data").join(Engine::INI_FILENAME)).unwrap();
+
+        let throttle_lut = vec![(0, 0), (25, 12), (50, 35), (75, 65), (100, 100)];
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let mut engine = Engine::from_car(&mut car).unwrap();
+            assert!(extract_optional_section::<ThrottleMap>(&engine).unwrap().is_none());
+            update_car_data(&mut engine, &ThrottleMap::new(throttle_lut.clone(), 7000)).unwrap();
+            engine.write().unwrap();
+        }
+        let throttle_map = {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let engine = Engine::from_car(&mut car).unwrap();
+            extract_optional_section::<ThrottleMap>(&engine).unwrap()
+        };
+        let lut_file_created = car_path.join("data").join(ThrottleMap::LUT_FILENAME).exists();
+        assert!(lut_file_created);
+        let throttle_map = throttle_map.unwrap();
+        assert_eq!(throttle_map.rpm_max(), 7000);
+        assert_eq!(throttle_map.get_lut_data().into_iter().collect::<Vec<_>>(), throttle_lut);
+    }
 
     const TURBO_NO_CTRL_DATA: &'static str = r#"
 [HEADER]
@@ -20,6 +20,7 @@
  */
 
 use std::collections::BTreeMap;
+use std::path::Path;
 use crate::car::lut_utils::LutType;
 use crate::car::structs::LutProperty;
 use crate::traits::{CarDataFile, CarDataUpdater, MandatoryDataSection};
@@ -45,6 +46,13 @@ impl PowerCurve {
     pub fn get_lut(&self) -> &LutType<i32, f64> {
         self.power_lut.get_type()
     }
+
+    pub fn write_to_dir(&self, dir: &Path) -> Result<()> {
+        self.power_lut.write_to_dir(dir).map_err(|e| {
+            Error::new(ErrorKind::IOError,
+                       format!("Failed to write power curve to {}. {}", dir.display(), e))
+        })
+    }
 }
 
 impl MandatoryDataSection for PowerCurve {
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeMap;
+use crate::car::lut_utils::{LutFile, LutType};
+use crate::car::structs::LutProperty;
+use crate::traits::{CarDataFile, CarDataUpdater, OptionalDataSection};
+use crate::error::{Result, Error, ErrorKind};
+use crate::ini_utils;
+
+/// Maps the throttle pedal position to the throttle opening, both in percent. AC uses the lut
+/// up to RPM_MAX and blends back to a linear response above it
+#[derive(Debug)]
+pub struct ThrottleMap {
+    lut: LutProperty<i32, i32>,
+    rpm_max: i32
+}
+
+impl ThrottleMap {
+    pub const SECTION_NAME: &'static str = "THROTTLE_RESPONSE";
+    pub const LUT_FILENAME: &'static str = "throttle.lut";
+
+    pub fn new(lut: Vec<(i32, i32)>, rpm_max: i32) -> ThrottleMap {
+        ThrottleMap {
+            lut: LutProperty::new(
+                LutType::File(LutFile::new(String::from(Self::LUT_FILENAME), lut)),
+                String::from(Self::SECTION_NAME),
+                String::from("LUT")
+            ),
+            rpm_max
+        }
+    }
+
+    pub fn get_lut_data(&self) -> BTreeMap<i32, i32> {
+        self.lut.to_vec().into_iter().collect()
+    }
+
+    pub fn rpm_max(&self) -> i32 {
+        self.rpm_max
+    }
+
+    /// Remove the section and any lut file it refers to from `car_data`
+    pub fn delete_from_car_data(&self, car_data: &mut dyn CarDataFile) {
+        self.lut.delete_from_car_data(car_data);
+        car_data.mut_ini_data().remove_section(Self::SECTION_NAME);
+    }
+}
+
+impl OptionalDataSection for ThrottleMap {
+    fn load_from_parent(parent_data: &dyn CarDataFile) -> Result<Option<Self>> where Self: Sized {
+        let ini_data = parent_data.ini_data();
+        if !ini_data.contains_section(Self::SECTION_NAME) {
+            return Ok(None);
+        }
+        let lut = LutProperty::<i32, i32>::mandatory_from_ini(
+            String::from(Self::SECTION_NAME),
+            String::from("LUT"),
+            ini_data,
+            parent_data.data_interface()
+        ).map_err(|e| {
+            Error::new(ErrorKind::InvalidCar, format!("Failed to load throttle response lut. {}", e))
+        })?;
+        Ok(Some(ThrottleMap {
+            lut,
+            rpm_max: ini_utils::get_mandatory_property(ini_data, Self::SECTION_NAME, "RPM_MAX")?
+        }))
+    }
+}
+
+impl CarDataUpdater for ThrottleMap {
+    fn update_car_data(&self, car_data: &mut dyn CarDataFile) -> Result<()> {
+        self.lut.update_car_data(car_data)?;
+        ini_utils::set_value(car_data.mut_ini_data(), Self::SECTION_NAME, "RPM_MAX", self.rpm_max);
+        Ok(())
+    }
+}
@@ -72,6 +72,10 @@ impl Turbo {
         self.sections.push(section)
     }
 
+    pub fn sections(&self) -> &[TurboSection] {
+        &self.sections
+    }
+
     pub fn clear_sections(&mut self) {
         self.sections.clear()
     }
@@ -180,6 +184,29 @@ impl TurboSection {
         })
     }
 
+    /// The boost the turbo settles at with the throttle held open at `rpm`, ignoring lag and any
+    /// turbo controllers. Boost rises towards MAX_BOOST as the rpm approaches REFERENCE_RPM and
+    /// is capped by the WASTEGATE when one is set
+    pub fn steady_state_boost(&self, rpm: f64) -> f64 {
+        let spool = match self.reference_rpm > 0 {
+            true => (rpm / self.reference_rpm as f64).clamp(0.0, 1.0).powf(self.gamma),
+            false => 1.0
+        };
+        let boost = self.max_boost * spool;
+        match self.wastegate > 0.0 {
+            true => boost.min(self.wastegate),
+            false => boost
+        }
+    }
+
+    /// The highest boost the turbo can produce; MAX_BOOST capped by the WASTEGATE when one is set
+    pub fn boost_limit(&self) -> f64 {
+        match self.wastegate > 0.0 {
+            true => self.max_boost.min(self.wastegate),
+            false => self.max_boost
+        }
+    }
+
     pub fn section_name(&self) -> String {
         TurboSection::get_ini_section_name(self.index)
     }
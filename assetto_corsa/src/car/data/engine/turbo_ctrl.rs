@@ -20,9 +20,10 @@
  */
 
 use std::fmt::{Display, Formatter};
+use std::path::Path;
 
 use std::str::FromStr;
-
+use tracing::warn;
 
 use crate::traits::{CarDataFile, CarDataUpdater, DataInterface};
 use crate::error::{Error, ErrorKind, PropertyParseError, Result};
@@ -88,7 +89,10 @@ impl<'a> TurboControllerFile<'a> {
 
     pub fn delete_from_car(car: &mut Car, turbo_index: usize) -> Result<()> {
         if let Some(mut ctrl_file) = TurboControllerFile::from_car(car, turbo_index)? {
-            ctrl_file.delete_all_controller_sections()?;
+            // The file is removed regardless, this only cleans up LUT files the controllers use
+            if let Err(e) = ctrl_file.delete_all_controller_sections() {
+                warn!("Failed to delete the controllers in {}. {}", ctrl_file.filename(), e.to_string());
+            }
         }
         car.mut_data_interface().remove_file(&TurboControllerFile::get_controller_ini_filename(turbo_index));
         Ok(())
@@ -103,6 +107,11 @@ impl<'a> TurboControllerFile<'a> {
         Ok(())
     }
 
+    pub fn write_to_dir(&self, dir: &Path) -> Result<()> {
+        self.ini_data.write_to_file(&dir.join(self.filename()))?;
+        Ok(())
+    }
+
     pub fn filename(&self) -> String {
         TurboControllerFile::get_controller_ini_filename(self.turbo_index)
     }
@@ -110,13 +119,21 @@ impl<'a> TurboControllerFile<'a> {
     pub fn num_controller_sections(&self) -> usize {
         let mut count: usize = 0;
         loop {
-            if self.ini_data.contains_section(&TurboController::get_controller_section_name(count)) {
+            if !self.ini_data.contains_section(&TurboController::get_controller_section_name(count)) {
                 return count;
             }
             count += 1;
         }
     }
 
+    /// Add `controller` after the controllers already in the file, updating its index to match.
+    /// Returns the index the controller was added with
+    pub fn add_controller(&mut self, controller: &TurboController) -> Result<usize> {
+        let index = self.num_controller_sections();
+        controller.with_index(index).update_car_data(self)?;
+        Ok(index)
+    }
+
     pub fn delete_all_controller_sections(&mut self) -> Result<()> {
         for section_index in 0..self.num_controller_sections() {
             self.delete_controller_section(section_index)?;
@@ -206,6 +223,37 @@ impl TurboController {
         self.index
     }
 
+    pub fn input(&self) -> ControllerInput {
+        self.input
+    }
+
+    pub fn combinator(&self) -> ControllerCombinator {
+        self.combinator
+    }
+
+    pub fn filter(&self) -> f64 {
+        self.filter
+    }
+
+    pub fn up_limit(&self) -> f64 {
+        self.up_limit
+    }
+
+    pub fn down_limit(&self) -> f64 {
+        self.down_limit
+    }
+
+    /// A copy of this controller that is written to the section for `index`
+    pub fn with_index(&self, index: usize) -> TurboController {
+        TurboController::new(index,
+                             self.input,
+                             self.combinator,
+                             self.lut.to_vec(),
+                             self.filter,
+                             self.up_limit,
+                             self.down_limit)
+    }
+
     pub fn delete(&self, controller_file: &mut TurboControllerFile) {
         self.lut.delete_from_car_data(controller_file);
         controller_file.mut_ini_data().remove_section(&self.section_name())
@@ -219,6 +267,10 @@ impl TurboController {
         &self.lut
     }
 
+    pub fn update_lut(&mut self, lut: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+        self.lut.update(lut)
+    }
+
     fn get_controller_section_name(index: usize) -> String {
         format!("CONTROLLER_{}", index)
     }
@@ -229,8 +281,8 @@ impl CarDataUpdater for TurboController {
         {
             let ini_data = car_data.mut_ini_data();
             let section_name = self.section_name();
-            ini_utils::set_value(ini_data, &section_name, "INPUT", &self.input);
-            ini_utils::set_value(ini_data, &section_name, "COMBINATOR", &self.combinator);
+            ini_utils::set_value(ini_data, &section_name, "INPUT", self.input);
+            ini_utils::set_value(ini_data, &section_name, "COMBINATOR", self.combinator);
             ini_utils::set_float(ini_data, &section_name, "FILTER", self.filter, 3);
             ini_utils::set_value(ini_data, &section_name, "UP_LIMIT", self.up_limit);
             ini_utils::set_value(ini_data, &section_name, "DOWN_LIMIT", self.down_limit);
@@ -240,7 +292,7 @@ impl CarDataUpdater for TurboController {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ControllerInput {
     Rpms,
     Gas,
@@ -283,7 +335,7 @@ impl Display for ControllerInput {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ControllerCombinator {
     Add,
     Mult
@@ -26,8 +26,10 @@ pub mod digital_instruments;
 pub mod ai;
 pub mod setup;
 pub mod tyres;
+pub mod suspensions;
 
 pub use car_ini_data::CarIniData;
 pub use engine::Engine;
 pub use drivetrain::Drivetrain;
+pub use suspensions::Suspensions;
 pub use digital_instruments::DigitalInstruments;
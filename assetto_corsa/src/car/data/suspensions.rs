@@ -0,0 +1,139 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::car::Car;
+use crate::error::Result;
+use crate::ini_utils;
+use crate::ini_utils::Ini;
+use crate::traits::{CarDataFile, DataInterface};
+
+pub const INI_FILENAME: &str = "suspensions.ini";
+
+/// The fraction of a car's weight carried by the front axle, from the CG_LOCATION in the [BASIC]
+/// section of suspensions.ini. None if the car doesn't define one
+pub fn front_weight_fraction(car: &Car) -> Result<Option<f64>> {
+    Ok(match car.data_interface().get_original_file_data(INI_FILENAME)? {
+        None => None,
+        Some(data) => {
+            ini_utils::get_value(&Ini::load_from_bytes(data.as_slice()), "BASIC", "CG_LOCATION")
+        }
+    })
+}
+
+#[derive(Debug)]
+pub struct Suspensions<'a> {
+    car: &'a mut Car,
+    ini_data: Ini,
+}
+
+impl<'a> Suspensions<'a> {
+    pub const INI_FILENAME: &'static str = INI_FILENAME;
+
+    pub fn from_car(car: &'a mut Car) -> Result<Option<Suspensions<'a>>> {
+        match car.data_interface.get_original_file_data(Suspensions::INI_FILENAME)? {
+            None => Ok(None),
+            Some(file_data) => {
+                Ok(Some(Suspensions {
+                    car,
+                    ini_data: Ini::load_from_bytes(file_data.as_slice())
+                }))
+            }
+        }
+    }
+
+    /// The fraction of the car's weight carried by the front axle. AC applies TOTALMASS from
+    /// car.ini at this CG location so changing the mass alone doesn't move the weight distribution
+    pub fn front_weight_fraction(&self) -> Option<f64> {
+        ini_utils::get_value(&self.ini_data, "BASIC", "CG_LOCATION")
+    }
+
+    pub fn set_front_weight_fraction(&mut self, front_fraction: f64) {
+        ini_utils::set_float(&mut self.ini_data, "BASIC", "CG_LOCATION", front_fraction.clamp(0.0, 1.0), 3);
+    }
+
+    pub fn write(&mut self) -> Result<()> {
+        let data_interface = self.car.mut_data_interface();
+        data_interface.update_file_data(Suspensions::INI_FILENAME,
+                                        self.ini_data.to_bytes());
+        data_interface.write()?;
+        Ok(())
+    }
+}
+
+impl<'a> CarDataFile for Suspensions<'a> {
+    fn ini_data(&self) -> &Ini {
+        &self.ini_data
+    }
+    fn mut_ini_data(&mut self) -> &mut Ini {
+        &mut self.ini_data
+    }
+    fn data_interface(&self) -> &dyn DataInterface {
+        self.car.data_interface()
+    }
+    fn mut_data_interface(&mut self) -> &mut dyn DataInterface {
+        self.car.mut_data_interface()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Car;
+    use crate::car::data::suspensions::{front_weight_fraction, Suspensions};
+
+    #[test]
+    fn front_weight_fraction_round_trips_through_suspensions_ini() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("suspensions");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        std::fs::write(car_path.join("data").join("suspensions.ini"), "[BASIC]\nWHEELBASE=2.5\nCG_LOCATION=0.55\n").unwrap();
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            assert_eq!(front_weight_fraction(&car).unwrap(), Some(0.55));
+            let mut suspensions = Suspensions::from_car(&mut car).unwrap().unwrap();
+            assert_eq!(suspensions.front_weight_fraction(), Some(0.55));
+            suspensions.set_front_weight_fraction(0.575);
+            suspensions.write().unwrap();
+        }
+        let car = Car::load_from_path(&car_path).unwrap();
+        assert_eq!(front_weight_fraction(&car).unwrap(), Some(0.575));
+        let suspensions_ini = std::fs::read_to_string(car_path.join("data").join("suspensions.ini")).unwrap();
+        assert!(suspensions_ini.contains("WHEELBASE=2.5"), "{}", suspensions_ini);
+    }
+
+    #[test]
+    fn windows_1252_suspensions_ini_keeps_its_encoding() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("windows_1252_suspensions");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        // 0xE9 is an e acute in Windows-1252 and isn't valid UTF-8 on its own
+        let original = b"[HEADER]\n; R\xE9glage d'usine\n[BASIC]\nCG_LOCATION=0.55\n";
+        std::fs::write(car_path.join("data").join("suspensions.ini"), original).unwrap();
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            assert_eq!(front_weight_fraction(&car).unwrap(), Some(0.55));
+            let mut suspensions = Suspensions::from_car(&mut car).unwrap().unwrap();
+            suspensions.set_front_weight_fraction(0.575);
+            suspensions.write().unwrap();
+        }
+        let written = std::fs::read(car_path.join("data").join("suspensions.ini")).unwrap();
+        assert_eq!(written, b"[HEADER]\n; R\xE9glage d'usine\n[BASIC]\nCG_LOCATION=0.575\n");
+    }
+}
@@ -19,12 +19,13 @@
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::fs::File;
 use std::{fs, io};
 use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use crate::car::acd_utils::AcdArchive;
 use crate::traits::{_DataInterfaceI, DataInterface, DataInterfaceResult};
 use crate::error::{Error, ErrorKind, Result};
@@ -80,6 +81,17 @@ impl _DataInterfaceI for DataFolderInterface {
         Ok(Some(data))
     }
 
+    fn original_file_names(&self) -> DataInterfaceResult<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.data_folder_path)? {
+            let entry = entry?;
+            if entry.path().is_file() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        Ok(names)
+    }
+
     fn contains_file(&self, filename: &str) -> bool {
         match self.outstanding_data_updates.get(filename) {
             None => {
@@ -133,6 +145,10 @@ impl _DataInterfaceI for AcdDataInterface {
         Ok(self.acd_archive.get_file_data(filename))
     }
 
+    fn original_file_names(&self) -> DataInterfaceResult<Vec<String>> {
+        Ok(self.acd_archive.file_names())
+    }
+
     fn contains_file(&self, filename: &str) -> bool {
         self.acd_archive.contains_file(filename)
     }
@@ -145,6 +161,8 @@ impl _DataInterfaceI for AcdDataInterface {
         self.acd_archive.delete_file(filename);
     }
 
+    /// Repack the archive into data.acd. The original file is only replaced once the repacked
+    /// data has been read back and matches the archive contents
     fn write(&mut self) -> DataInterfaceResult<()> {
         self.acd_archive.write()?;
         Ok(())
@@ -152,3 +170,82 @@ impl _DataInterfaceI for AcdDataInterface {
 }
 
 impl DataInterface for AcdDataInterface {}
+
+/// The data file changes captured by a [`RecordingDataInterface`]. Files that would be deleted
+/// are recorded as None
+#[derive(Debug, Clone, Default)]
+pub struct DataChangeRecord {
+    changes: Arc<Mutex<BTreeMap<String, Option<Vec<u8>>>>>
+}
+
+impl DataChangeRecord {
+    /// The contents each changed file would have been written with, keyed by filename
+    pub fn changes(&self) -> BTreeMap<String, Option<Vec<u8>>> {
+        self.changes.lock().unwrap().clone()
+    }
+
+    fn get(&self, filename: &str) -> Option<Option<Vec<u8>>> {
+        self.changes.lock().unwrap().get(filename).cloned()
+    }
+
+    fn record(&self, updates: HashMap<String, Option<Vec<u8>>>) {
+        self.changes.lock().unwrap().extend(updates);
+    }
+}
+
+/// Wraps the data interface of a car so that writes are recorded in memory instead of being made.
+/// Files read after a write see the recorded data, as they would if it had been written
+#[derive(Debug)]
+pub struct RecordingDataInterface {
+    inner: Box<dyn DataInterface>,
+    outstanding_data_updates: HashMap<String, Option<Vec<u8>>>,
+    record: DataChangeRecord
+}
+
+impl RecordingDataInterface {
+    pub fn new(inner: Box<dyn DataInterface>, record: DataChangeRecord) -> Self {
+        RecordingDataInterface { inner, outstanding_data_updates: HashMap::new(), record }
+    }
+}
+
+impl _DataInterfaceI for RecordingDataInterface {
+    fn get_original_file_data(&self, filename: &str) -> DataInterfaceResult<Option<Vec<u8>>> {
+        match self.record.get(filename) {
+            Some(data) => Ok(data),
+            None => self.inner.get_original_file_data(filename)
+        }
+    }
+
+    fn original_file_names(&self) -> DataInterfaceResult<Vec<String>> {
+        let mut names = self.inner.original_file_names()?;
+        for (filename, data) in self.record.changes() {
+            names.retain(|name| *name != filename);
+            if data.is_some() {
+                names.push(filename);
+            }
+        }
+        Ok(names)
+    }
+
+    fn contains_file(&self, filename: &str) -> bool {
+        match self.outstanding_data_updates.get(filename).cloned().or_else(|| self.record.get(filename)) {
+            Some(data) => data.is_some(),
+            None => self.inner.contains_file(filename)
+        }
+    }
+
+    fn update_file_data(&mut self, filename: &str, data: Vec<u8>) {
+        self.outstanding_data_updates.insert(filename.to_owned(), Some(data));
+    }
+
+    fn remove_file(&mut self, filename: &str) {
+        self.outstanding_data_updates.insert(filename.to_owned(), None);
+    }
+
+    fn write(&mut self) -> DataInterfaceResult<()> {
+        self.record.record(std::mem::take(&mut self.outstanding_data_updates));
+        Ok(())
+    }
+}
+
+impl DataInterface for RecordingDataInterface {}
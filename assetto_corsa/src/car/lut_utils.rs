@@ -122,7 +122,10 @@ impl<K, V> InlineLut<K, V>
     }
 
     pub fn from_property_value(property_value: String) -> Result<InlineLut<K, V>, String> {
-        let data_slice = &property_value[1..(property_value.len() - 1)];
+        let data_slice = match property_value.trim().strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+            Some(data_slice) => data_slice,
+            None => return Err(format!("{} is not a valid inline lut", property_value))
+        };
         let data = load_lut_from_reader::<K, V, _>(data_slice.as_bytes(), b'=', Terminator::Any(b'|'))?;
         Ok(InlineLut { data })
     }
@@ -181,7 +184,7 @@ impl<K, V> LutType<K, V>
     }
 
     pub fn load_from_property_value(property_value: String, data_source: &dyn DataInterface) -> Result<LutType<K, V>, String>{
-        return match property_value.starts_with("(") {
+        return match property_value.trim_start().starts_with("(") {
             true => {
                 Ok(LutType::Inline(InlineLut::from_property_value(property_value)?))
             }
@@ -425,6 +428,15 @@ pub fn write_lut_to_property_value<K, V>(data: &Vec<(K,V)>, delimiter: u8, termi
     };
 }
 
+#[test]
+fn inline_lut_from_property_value() {
+    let lut = InlineLut::<i32, f64>::from_property_value(String::from(" (|0=50|1000=180.5|) ")).unwrap();
+    assert_eq!(lut.to_vec(), vec![(0, 50.0), (1000, 180.5)]);
+    assert_eq!(lut.to_string(), "(0=50|1000=180.5)");
+    assert!(InlineLut::<i32, f64>::from_property_value(String::from("(")).is_err());
+    assert!(InlineLut::<i32, f64>::from_property_value(String::from("(|0=50|1000=180")).is_err());
+}
+
 #[test]
 fn interpolator_test() {
     let data = vec![
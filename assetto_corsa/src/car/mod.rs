@@ -27,9 +27,11 @@ pub mod lut_utils;
 pub(crate) mod structs;
 mod max_speed_est;
 pub mod model;
+mod package;
 
 use crate::ini_utils::Comment;
-pub use data_interface::DataFolderInterface;
+pub use data_interface::{DataChangeRecord, DataFolderInterface, RecordingDataInterface};
+pub use package::{export_car_package, PackageOptions};
 
 use std::fmt::Debug;
 use std::fs::File;
@@ -51,10 +53,19 @@ use crate::car::ui::CarUiData;
 
 pub const ENGINE_CRANE_CAR_TAG: &'static str = "engine crane";
 
+/// The stages of a car clone that are passed to the `progress` callback of [`clone_existing_car`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CloneStage {
+    CopyingFiles,
+    UnpackingData,
+    PackingData
+}
+
 pub fn clone_existing_car(ac_installation: &Installation,
                           existing_car_path: &Path,
                           new_car_path: &Path,
-                          unpack_data_dir: bool) -> Result<()> {
+                          unpack_data_dir: bool,
+                          progress: &dyn Fn(CloneStage)) -> Result<()> {
     let existing_car_name = get_final_path_part(existing_car_path)?;
 
     if existing_car_path == new_car_path {
@@ -72,6 +83,7 @@ pub fn clone_existing_car(ac_installation: &Installation,
     }
 
     let clone_actions = || -> Result<()> {
+        progress(CloneStage::CopyingFiles);
         let mut copy_options = fs_extra::dir::CopyOptions::new();
         copy_options.content_only = true;
         fs_extra::dir::copy(&existing_car_path,
@@ -86,11 +98,15 @@ pub fn clone_existing_car(ac_installation: &Installation,
                                       format!("{} doesn't contain a data dir or data.acd file", existing_car_path.display())));
             }
             info!("No data dir present in {}. Data will be extracted from data.acd", new_car_path.display());
+            progress(CloneStage::UnpackingData);
             AcdArchive::load_from_acd_file_with_key(acd_path.as_path(), &existing_car_name)?.unpack()?;
         }
 
         fix_car_specific_filenames(new_car_path, &existing_car_name)?;
         update_car_sfx(ac_installation, new_car_path, &existing_car_name)?;
+        if let Err(e) = validate_car_sfx(new_car_path) {
+            warn!("{}. The car may not have any sound", e.to_string());
+        }
 
         match unpack_data_dir {
             true => {
@@ -101,6 +117,7 @@ pub fn clone_existing_car(ac_installation: &Installation,
             }
             false => {
                 info!("Packing {} into an .acd file", data_path.display());
+                progress(CloneStage::PackingData);
                 AcdArchive::create_from_data_dir(&data_path)?.write()?;
                 if data_path.exists() {
                     info!("Deleting {} as data will be invalid after clone completion", data_path.display());
@@ -125,29 +142,93 @@ pub fn clone_existing_car(ac_installation: &Installation,
 
 pub const UNPACK_DATA_BIT: u64 = 1 << 0;
 pub const AC_CAR_TUNER_COMPAT_BIT: u64 = 1 << 1;
+/// Link a car created outside of the installation into its cars folder so that AC can load it
+pub const LINK_INTO_INSTALLATION_BIT: u64 = 1 << 2;
 
 pub fn create_new_car_spec(ac_installation: &Installation,
                            existing_car_path: &PathBuf,
                            spec_name: &str,
-                           opt_flags: u64) -> Result<PathBuf> {
-    
+                           opt_flags: u64,
+                           progress: &dyn Fn(CloneStage)) -> Result<PathBuf> {
+    create_new_car_spec_in(ac_installation,
+                           existing_car_path,
+                           get_parent_path_part(existing_car_path)?,
+                           spec_name,
+                           opt_flags,
+                           progress)
+}
+
+/// The longest car folder path that will be created on Windows. This leaves room under the 260
+/// character MAX_PATH limit for the files inside the car folder
+const MAX_WINDOWS_CAR_PATH_LENGTH: usize = 180;
+
+/// The suffix added to the folder name of a new car spec called `spec_name`. The name is
+/// lowercased, whitespace is replaced with underscores and any characters that can't be used in a
+/// folder name are removed. Names are always sanitized for Windows as that is where AC runs
+pub fn spec_folder_suffix(spec_name: &str) -> String {
+    let path_suffix = spec_name.to_lowercase().split_whitespace().collect::<Vec<&str>>().join("_");
+    sanitize_filename::sanitize_with_options(path_suffix, sanitize_filename::Options {
+        windows: true,
+        ..Default::default()
+    })
+}
+
+/// The name of the folder that will be created for a new spec of `existing_car_name` called
+/// `spec_name`
+pub fn new_car_spec_folder_name(existing_car_name: &str, spec_name: &str) -> String {
+    format!("{}_{}", existing_car_name, spec_folder_suffix(spec_name))
+}
+
+/// Create a new spec of the car at `existing_car_path` in the `output_dir` folder rather than
+/// next to the existing car. See [`LINK_INTO_INSTALLATION_BIT`] for making the new car
+/// available in game when `output_dir` is outside the installation
+pub fn create_new_car_spec_in(ac_installation: &Installation,
+                              existing_car_path: &PathBuf,
+                              output_dir: &Path,
+                              spec_name: &str,
+                              opt_flags: u64,
+                              progress: &dyn Fn(CloneStage)) -> Result<PathBuf> {
     let opt_is_set = |opt_bit| {(opt_flags & opt_bit) > 0};
     let existing_car_name = get_final_path_part(existing_car_path)?;
     if !existing_car_path.exists() {
         return Err(Error::new(ErrorKind::NoSuchCar, existing_car_name));
     }
-    let path_suffix = spec_name.to_lowercase().split_whitespace().collect::<Vec<&str>>().join("_");
-    let new_car_name = format!("{}_{}", existing_car_name, path_suffix);
-    let new_car_path = get_parent_path_part(existing_car_path)?.join(&new_car_name);
+    let path_suffix = spec_folder_suffix(spec_name);
+    if path_suffix.is_empty() {
+        return Err(Error::new(ErrorKind::ArgumentError,
+                              format!("Spec name '{}' doesn't contain any characters that can be used in a folder name", spec_name)));
+    }
+    let new_car_name = new_car_spec_folder_name(&existing_car_name, spec_name);
+    let new_car_path = output_dir.join(&new_car_name);
+    if cfg!(target_os = "windows") {
+        let path_length = new_car_path.as_os_str().len();
+        if path_length > MAX_WINDOWS_CAR_PATH_LENGTH {
+            return Err(Error::new(ErrorKind::ArgumentError,
+                                  format!("The new car path {} is {} characters long, the limit is {}. Please use a shorter spec name",
+                                          new_car_path.display(), path_length, MAX_WINDOWS_CAR_PATH_LENGTH)));
+        }
+    }
     if new_car_path.exists() {
         return Err(Error::new(ErrorKind::CarAlreadyExists, new_car_name));
     }
+    let link_path = ac_installation.get_installed_car_path().join(&new_car_name);
+    let create_link = opt_is_set(LINK_INTO_INSTALLATION_BIT) && link_path != new_car_path;
+    if create_link && fs::symlink_metadata(&link_path).is_ok() {
+        return Err(Error::new(ErrorKind::CarAlreadyExists,
+                              format!("{} already exists in the installation", new_car_name)));
+    }
     info!("Cloning {} to {}", existing_car_path.display(), new_car_path.display());
     let unpack_data_dif = opt_is_set(UNPACK_DATA_BIT) || opt_is_set(AC_CAR_TUNER_COMPAT_BIT);
     clone_existing_car(ac_installation,
                        existing_car_path.as_path(),
                        new_car_path.as_path(),
-                       unpack_data_dif)?;
+                       unpack_data_dif,
+                       progress).map_err(|e| {
+        match e.kind() {
+            ErrorKind::PermissionDenied => crate::write_permission_error(output_dir),
+            _ => e
+        }
+    })?;
     update_car_ui_data(new_car_path.as_path(), &existing_car_path, spec_name)?;
     if opt_is_set(AC_CAR_TUNER_COMPAT_BIT) {
         match create_x_tuned_file(&new_car_path, &path_suffix) {
@@ -184,6 +265,17 @@ pub fn create_new_car_spec(ac_installation: &Installation,
             Err(e) => warn!("Car appears to be missing a drivetrain.ini file. {}", e.to_string())
         }
     }
+    if create_link {
+        info!("Linking {} to {}", link_path.display(), new_car_path.display());
+        if let Err(e) = utils::filesystem::link_directory(&new_car_path, &link_path) {
+            if let Err(remove_err) = std::fs::remove_dir_all(&new_car_path) {
+                warn!("Failed to remove {}. {}", new_car_path.display(), remove_err.to_string())
+            }
+            return Err(Error::new(ErrorKind::IOError,
+                                  format!("Couldn't link {} into the installation at {}. The filesystem refused to create the link. {}",
+                                          new_car_name, link_path.display(), e)));
+        }
+    }
     Ok(new_car_path)
 }
 
@@ -206,6 +298,23 @@ pub fn delete_car(ac_installation: &Installation, car_folder_name: &Path) -> std
     std::fs::remove_dir_all(path)
 }
 
+/// Delete the car at `car_path`. If the car is outside the installation then any link to it
+/// in the installation's cars folder is removed as well
+pub fn delete_car_at(ac_installation: &Installation, car_path: &Path) -> std::io::Result<()> {
+    let car_folder_name = car_path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                            format!("Couldn't get car folder name from {}", car_path.display()))
+    })?;
+    let installed_path = ac_installation.get_installed_car_path().join(car_folder_name);
+    if installed_path != car_path {
+        let is_link = fs::symlink_metadata(&installed_path).map_or(false, |m| m.file_type().is_symlink());
+        if is_link && fs::canonicalize(&installed_path).ok() == fs::canonicalize(car_path).ok() {
+            utils::filesystem::remove_directory_link(&installed_path)?;
+        }
+    }
+    std::fs::remove_dir_all(car_path)
+}
+
 fn fix_car_specific_filenames(car_path: &Path, name_to_change: &str) -> Result<()> {
     let new_car_name = get_final_path_part(car_path)?;
     let mut paths_to_update: Vec<PathBuf> = Vec::new();
@@ -335,17 +444,147 @@ fn update_car_sfx(ac_installation: &Installation,
         }).collect();
     } else {
         info!("Generating new '{}' with contents from the installation sfx data", guids_file_path.display());
-        updated_lines = ac_installation.load_sfx_data()?.generate_clone_guid_info(name_to_change, &car_name);
+        updated_lines = match generate_guid_lines(ac_installation, name_to_change, &car_name) {
+            Ok(lines) => lines,
+            Err(e) => {
+                warn!("Skipping generation of '{}'. {}", guids_file_path.display(), e.to_string());
+                return Ok(());
+            }
+        };
+    }
+    write_guid_lines(&guids_file_path, updated_lines)
+}
+
+/// Check that a car that has a sound bank also has a GUIDs.txt that references it.
+/// Without this the car will load in game but won't make any sound
+pub fn validate_car_sfx(car_path: &Path) -> Result<()> {
+    let sfx_path = car_path.join("sfx");
+    let car_name = get_final_path_part(car_path)?;
+    let has_bank = fs::read_dir(&sfx_path).map(|entries| {
+        entries.filter_map(|e| e.ok()).any(|e| e.path().extension().map_or(false, |ext| ext == "bank"))
+    }).unwrap_or(false);
+    if !has_bank {
+        return Ok(());
+    }
+
+    let guids_file_path = sfx_path.join("GUIDs.txt");
+    let contents = fs::read_to_string(&guids_file_path).map_err(|_| {
+        Error::new(ErrorKind::InvalidCar,
+                   format!("{} is missing", guids_file_path.display()))
+    })?;
+    let bank_ref = format!("bank:/{}", car_name);
+    match contents.lines().any(|line| line.split_whitespace().nth(1) == Some(bank_ref.as_str())) {
+        true => Ok(()),
+        false => Err(Error::new(ErrorKind::InvalidCar,
+                                format!("{} doesn't reference {}", guids_file_path.display(), bank_ref)))
+    }
+}
+
+/// Regenerate the GUIDs.txt of a car from the installation sfx data. The sounds are taken from
+/// the car's parent as recorded in its ui data
+pub fn repair_car_sfx(ac_installation: &Installation, car_path: &Path) -> Result<()> {
+    let car_name = get_final_path_part(car_path)?;
+    let mut car = Car::load_from_path(car_path)?;
+    let source_car_name = match CarUiData::from_car(&mut car)?.ui_info.parent() {
+        None => return Err(Error::new(ErrorKind::InvalidCar,
+                                      format!("Can't determine which car {} was cloned from", car_name))),
+        Some(parent) => parent.to_string()
+    };
+    let sfx_path = car_path.join("sfx");
+    if !sfx_path.is_dir() {
+        fs::create_dir(&sfx_path)?;
     }
+    info!("Regenerating sfx GUIDs for {} using the sounds from {}", car_name, source_car_name);
+    let lines = generate_guid_lines(ac_installation, &source_car_name, &car_name)?;
+    write_guid_lines(&sfx_path.join("GUIDs.txt"), lines)
+}
+
+pub(crate) fn generate_guid_lines(ac_installation: &Installation,
+                       source_car_name: &str,
+                       car_name: &str) -> Result<Vec<String>> {
+    let sfx_data = ac_installation.load_sfx_data().map_err(|e| {
+        Error::new(ErrorKind::NotInstalled,
+                   format!("Couldn't load the Assetto Corsa sfx data needed to generate GUIDs.txt. {}", e.to_string()))
+    })?;
+    let lines = sfx_data.generate_clone_guid_info(source_car_name, car_name);
+    match lines.is_empty() {
+        true => Err(Error::new(ErrorKind::InvalidCar,
+                               format!("No sfx data found for {} in the Assetto Corsa sfx data", source_car_name))),
+        false => Ok(lines)
+    }
+}
 
-    let file = File::create(&guids_file_path)?;
+pub(crate) fn write_guid_lines(guids_file_path: &Path, lines: Vec<String>) -> Result<()> {
+    let file = File::create(guids_file_path)?;
     let mut file = LineWriter::new(file);
-    for line in updated_lines {
+    for line in lines {
         write!(file, "{}\n", line)?;
     }
     Ok(())
 }
 
+/// Describes where the data for a car is being stored
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DataSourceState {
+    /// Only a `data` folder is present
+    DataFolder,
+    /// Only a `data.acd` archive is present
+    DataAcd,
+    /// Both a `data` folder and a `data.acd` archive are present. The game will read from the
+    /// `data.acd` so any edits made to the `data` folder won't take effect unless they are in sync
+    Both { in_sync: bool },
+    /// Neither a `data` folder nor a `data.acd` archive are present
+    Missing
+}
+
+impl DataSourceState {
+    pub fn from_car_path(car_folder_path: &Path) -> DataSourceState {
+        let data_dir_path = car_folder_path.join("data");
+        let data_file_path = car_folder_path.join("data.acd");
+        match (data_dir_path.is_dir(), data_file_path.is_file()) {
+            (true, true) => DataSourceState::Both {
+                in_sync: data_sources_in_sync(&data_dir_path, &data_file_path)
+            },
+            (true, false) => DataSourceState::DataFolder,
+            (false, true) => DataSourceState::DataAcd,
+            (false, false) => DataSourceState::Missing
+        }
+    }
+
+    pub fn needs_reconcile(&self) -> bool {
+        match self {
+            DataSourceState::Both { in_sync } => !*in_sync,
+            _ => false
+        }
+    }
+}
+
+/// The actions that can be taken to resolve a car that has both a `data` folder and `data.acd`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DataSourceReconcile {
+    /// Repack the contents of the `data` folder into `data.acd`
+    PackFolderIntoAcd,
+    /// Delete `data.acd` so that the game uses the `data` folder
+    DeleteAcd
+}
+
+fn data_sources_in_sync(data_dir_path: &Path, data_file_path: &Path) -> bool {
+    let acd_archive = match AcdArchive::load_from_acd_file(data_file_path) {
+        Ok(archive) => archive,
+        Err(e) => {
+            warn!("Failed to load {} for comparison. {}", data_file_path.display(), e.to_string());
+            return false;
+        }
+    };
+    match AcdArchive::create_from_data_dir(data_dir_path) {
+        Ok(folder_archive) => acd_archive.contents_match(&folder_archive),
+        Err(e) => {
+            warn!("Failed to load {} for comparison. {}", data_dir_path.display(), e.to_string());
+            false
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Car {
     root_path: PathBuf,
@@ -365,14 +604,32 @@ impl Car {
     }
 
     pub fn load_from_path(car_folder_path: &Path) -> Result<Car> {
-        let data_dir_path = car_folder_path.join("data");
-        let data_file_path = car_folder_path.join("data.acd");
         Ok(Car{
             root_path: car_folder_path.to_path_buf(),
-            data_interface: match data_dir_path.is_dir() {
-                true => Box::new(DataFolderInterface::from(&data_dir_path)?),
-                false => Box::new(AcdDataInterface::new(&data_file_path)?),
-            }
+            data_interface: Car::open_data_interface(car_folder_path)?
+        })
+    }
+
+    /// Load the car at `car_folder_path` such that changes to its data files are recorded in the
+    /// returned [`DataChangeRecord`] rather than written to disk
+    pub fn load_recording_changes(car_folder_path: &Path) -> Result<(Car, DataChangeRecord)> {
+        let record = DataChangeRecord::default();
+        let data_interface = RecordingDataInterface::new(Car::open_data_interface(car_folder_path)?,
+                                                         record.clone());
+        Ok((Car{ root_path: car_folder_path.to_path_buf(), data_interface: Box::new(data_interface) }, record))
+    }
+
+    fn open_data_interface(car_folder_path: &Path) -> Result<Box<dyn DataInterface>> {
+        let data_dir_path = car_folder_path.join("data");
+        let data_file_path = car_folder_path.join("data.acd");
+        if data_dir_path.is_dir() && data_file_path.is_file() {
+            warn!("{} contains both a data folder and data.acd. The data folder will be used but \
+                   the game will read data.acd; changes won't take effect unless these are reconciled",
+                  car_folder_path.display());
+        }
+        Ok(match data_dir_path.is_dir() {
+            true => Box::new(DataFolderInterface::from(&data_dir_path)?),
+            false => Box::new(AcdDataInterface::new(&data_file_path)?),
         })
     }
 
@@ -391,9 +648,58 @@ impl Car {
     pub fn is_ac_car_tuner_tune(&self) -> bool {
         self.root_path.join("x.tuned").is_file()
     }
+
+    /// Whether the car carries a marker showing it is a modified copy that is safe to edit.
+    /// i.e. it was created by engine-crane or is an AC Car Tuner tune
+    pub fn has_edit_marker(&mut self) -> bool {
+        if self.is_ac_car_tuner_tune() {
+            return true;
+        }
+        match CarUiData::from_car(self) {
+            Ok(ui_data) => ui_data.ui_info.has_tag(ENGINE_CRANE_CAR_TAG),
+            Err(_) => false
+        }
+    }
+
+    /// Check that the car can be written to without invalidating a base game car for online play.
+    /// Cars without an edit marker are refused unless `allow_unmarked` is set
+    pub fn check_editable(&mut self, allow_unmarked: bool) -> Result<()> {
+        if allow_unmarked || self.has_edit_marker() {
+            return Ok(());
+        }
+        Err(Error::new(ErrorKind::ProtectedCar,
+                       format!("{} isn't marked as an engine-crane or AC Car Tuner car. Editing it would stop it working online",
+                               self.root_path.display())))
+    }
+
+    pub fn data_source_state(&self) -> DataSourceState {
+        DataSourceState::from_car_path(&self.root_path)
+    }
+
+    /// Resolve a car that has both a `data` folder and `data.acd` so that only one source of
+    /// data remains authoritative. Any outstanding data updates should be written beforehand
+    pub fn reconcile_data_sources(&mut self, action: DataSourceReconcile) -> Result<()> {
+        let data_dir_path = self.root_path.join("data");
+        if !data_dir_path.is_dir() {
+            return Err(Error::new(ErrorKind::InvalidUpdate,
+                                  format!("{} doesn't contain a data folder to reconcile with",
+                                          self.root_path.display())));
+        }
+        match action {
+            DataSourceReconcile::PackFolderIntoAcd => {
+                info!("Packing {} into data.acd", data_dir_path.display());
+                AcdArchive::create_from_data_dir(&data_dir_path)?.write()?;
+            }
+            DataSourceReconcile::DeleteAcd => {
+                info!("Deleting data.acd from {}", self.root_path.display());
+                delete_data_acd_file(&self.root_path)?;
+            }
+        }
+        Ok(())
+    }
 }
 
-fn get_final_path_part(full_path: &Path) -> Result<String> {
+pub(crate) fn get_final_path_part(full_path: &Path) -> Result<String> {
     return match full_path.file_name() {
         Some(n) => { Ok(n.to_string_lossy().to_string()) }
         None => {
@@ -420,7 +726,7 @@ mod tests {
     use std::fs::File;
     use std::io::Write;
     use std::path::{Path};
-    use crate::car::{Car, create_new_car_spec};
+    use crate::car::{Car, create_new_car_spec, create_new_car_spec_in, delete_car_at, LINK_INTO_INSTALLATION_BIT, new_car_spec_folder_name, spec_folder_suffix, UNPACK_DATA_BIT};
     use crate::car::data::CarIniData;
     use crate::car::ui::CarUiData;
     use crate::Installation;
@@ -446,6 +752,121 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn edit_gear_ratio_in_data_acd() {
+        use crate::car::acd_utils::AcdArchive;
+        use crate::car::data::Drivetrain;
+        use crate::car::data::drivetrain::Gearbox;
+        use crate::traits::{extract_mandatory_section, update_car_data};
+
+        let fixture_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-turbo-with-ctrls/data");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("acd_gear_edit");
+        std::fs::create_dir_all(&car_path).unwrap();
+        AcdArchive::create_from_data_dir(&fixture_data).unwrap().write_to(&car_path.join("data.acd")).unwrap();
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let mut drivetrain = Drivetrain::from_car(&mut car).unwrap();
+            let mut gearbox = extract_mandatory_section::<Gearbox>(&drivetrain).unwrap();
+            let mut ratios = gearbox.gear_ratios().clone();
+            ratios[0] = 3.5;
+            gearbox.update_gears(ratios);
+            update_car_data(&mut drivetrain, &gearbox).unwrap();
+            drivetrain.write().unwrap();
+        }
+        let gear_ratios = {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let drivetrain = Drivetrain::from_car(&mut car).unwrap();
+            extract_mandatory_section::<Gearbox>(&drivetrain).unwrap().gear_ratios().clone()
+        };
+        let data_folder_created = car_path.join("data").exists();
+        assert!(!data_folder_created);
+        assert_eq!(gear_ratios, vec![3.5, 2.29, 1.58, 1.19, 0.94, 0.79, 0.62]);
+    }
+
+    #[test]
+    fn recorded_changes_are_not_written() {
+        use crate::car::data::Drivetrain;
+        use crate::car::data::drivetrain::Gearbox;
+        use crate::traits::{extract_mandatory_section, update_car_data};
+
+        let fixture_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-turbo-with-ctrls/data");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("recorded_gear_edit");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        std::fs::copy(fixture_data.join(Drivetrain::INI_FILENAME), car_path.join("data").join(Drivetrain::INI_FILENAME)).unwrap();
+        let original_data = std::fs::read(car_path.join("data").join(Drivetrain::INI_FILENAME)).unwrap();
+
+        let (mut car, record) = Car::load_recording_changes(&car_path).unwrap();
+        {
+            let mut drivetrain = Drivetrain::from_car(&mut car).unwrap();
+            let mut gearbox = extract_mandatory_section::<Gearbox>(&drivetrain).unwrap();
+            let mut ratios = gearbox.gear_ratios().clone();
+            ratios[0] = 3.5;
+            gearbox.update_gears(ratios);
+            update_car_data(&mut drivetrain, &gearbox).unwrap();
+            drivetrain.write().unwrap();
+        }
+        let recorded_ratios = {
+            let drivetrain = Drivetrain::from_car(&mut car).unwrap();
+            extract_mandatory_section::<Gearbox>(&drivetrain).unwrap().gear_ratios().clone()
+        };
+        let data_on_disk = std::fs::read(car_path.join("data").join(Drivetrain::INI_FILENAME)).unwrap();
+
+        assert_eq!(data_on_disk, original_data);
+        assert_eq!(recorded_ratios[0], 3.5);
+        let changes = record.changes();
+        assert_eq!(changes.keys().collect::<Vec<_>>(), vec![Drivetrain::INI_FILENAME]);
+        assert_ne!(changes[Drivetrain::INI_FILENAME].as_ref().unwrap(), &original_data);
+    }
+
+    #[test]
+    fn create_spec_in_output_folder_and_link() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("output_folder_spec");
+        let ac_install = Installation::from_path(test_path.join("ac"));
+        let existing_car_path = ac_install.get_installed_car_path().join("test_car");
+        std::fs::create_dir_all(existing_car_path.join("data")).unwrap();
+        std::fs::create_dir_all(existing_car_path.join("ui")).unwrap();
+        std::fs::write(existing_car_path.join("data").join("car.ini"), "[INFO]\nSCREEN_NAME=Test Car\n").unwrap();
+        std::fs::write(existing_car_path.join("ui").join("ui_car.json"), "{\"name\": \"Test Car\"}").unwrap();
+        let output_path = test_path.join("output");
+        std::fs::create_dir_all(&output_path).unwrap();
+
+        let new_car_path = create_new_car_spec_in(&ac_install,
+                                                  &existing_car_path,
+                                                  &output_path,
+                                                  "Output Spec",
+                                                  UNPACK_DATA_BIT | LINK_INTO_INSTALLATION_BIT,
+                                                  &|_| {}).unwrap();
+        assert_eq!(new_car_path, output_path.join("test_car_output_spec"));
+        assert!(new_car_path.join("data").join("car.ini").is_file());
+        let link_path = ac_install.get_installed_car_path().join("test_car_output_spec");
+        assert!(std::fs::symlink_metadata(&link_path).unwrap().file_type().is_symlink());
+        assert!(link_path.join("data").join("car.ini").is_file());
+
+        delete_car_at(&ac_install, &new_car_path).unwrap();
+        assert!(!new_car_path.exists());
+        assert!(std::fs::symlink_metadata(&link_path).is_err());
+        assert!(existing_car_path.is_dir());
+    }
+
+    #[test]
+    fn spec_names_sanitized_for_folder_names() {
+        assert_eq!(spec_folder_suffix("Turbo  V8 Spec"), "turbo_v8_spec");
+        assert_eq!(spec_folder_suffix("A/B: test."), "ab_test");
+        assert_eq!(new_car_spec_folder_name("test_car", "Race*Spec?"), "test_car_racespec");
+        assert!(spec_folder_suffix("/:?").is_empty());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("invalid_spec_name");
+        let ac_install = Installation::from_path(test_path.join("ac"));
+        let existing_car_path = ac_install.get_installed_car_path().join("test_car");
+        std::fs::create_dir_all(&existing_car_path).unwrap();
+        let res = create_new_car_spec(&ac_install, &existing_car_path, "<>|", 0, &|_| {});
+        assert!(res.is_err());
+    }
+
     #[test]
     fn clone_car() {
         let ac_install = Installation::new();
@@ -454,7 +875,8 @@ mod tests {
         let new_car_path = create_new_car_spec(&ac_install,
                                                &ac_install.get_installed_car_path().join("urd_radical_sr3xxr_2023"),
                                                "test",
-                                               opts).unwrap();
+                                               opts,
+                                               &|_| {}).unwrap();
         println!("{}", new_car_path.display());
     }
 
@@ -20,6 +20,7 @@
  */
 
 use std::collections::BTreeMap;
+use utils::numeric::round_float_to;
 use crate::Car;
 use crate::{Result, Error, ErrorKind};
 use crate::car::data::{Drivetrain, Engine};
@@ -30,6 +31,11 @@ use crate::car::data::tyres::tyre_sets::TyreCompounds;
 use crate::car::data::tyres::Tyres;
 use crate::traits::MandatoryDataSection;
 
+/// The largest ratio between first and top gear that will be suggested
+const MAX_SUGGESTED_RATIO_SPREAD: f64 = 4.5;
+/// The smallest step between adjacent gears that will be suggested
+const MIN_SUGGESTED_RATIO_STEP: f64 = 1.05;
+
 pub struct GearingCalculator {
     power_curve_data: BTreeMap<i32, f64>,
     idle_rpm: i32,
@@ -125,6 +131,47 @@ impl GearingCalculator {
             (60.0 * ratio * self.final_drive)) * 3.6
     }
 
+    /// Get the gear ratio that would reach the provided speed (KM/H) at the limiter
+    ///
+    /// - `speed`: The target speed in KM/H
+    pub fn ratio_for_max_speed(&self, speed: f64) -> f64 {
+        (self.max_rpm() as f64 * 2.0 * std::f64::consts::PI * self.drive_wheel_radius * 3.6) /
+            (60.0 * speed * self.final_drive)
+    }
+
+    /// Suggest gear ratios that form a geometric progression with the top gear reaching
+    /// `top_speed` (KM/H) at the limiter. The step between gears is chosen so that an upshift at
+    /// the limiter drops the engine to its peak torque rpm, capped so the spread between first
+    /// and top gear doesn't exceed `MAX_SUGGESTED_RATIO_SPREAD`
+    ///
+    /// - `top_speed`: The target top speed in KM/H
+    /// - `num_gears`: The number of forward gears
+    pub fn suggest_gear_ratios(&self, top_speed: f64, num_gears: usize) -> Vec<f64> {
+        if num_gears == 0 || top_speed <= 0.0 {
+            return Vec::new();
+        }
+        let top_ratio = self.ratio_for_max_speed(top_speed);
+        if num_gears == 1 {
+            return vec![round_float_to(top_ratio, 3)];
+        }
+        let shifts = (num_gears - 1) as f64;
+        let drop_rpm = self.peak_torque_rpm().max(self.min_rpm()).max(1);
+        let max_step = MAX_SUGGESTED_RATIO_SPREAD.powf(1.0 / shifts);
+        let step = (self.max_rpm() as f64 / drop_rpm as f64).clamp(MIN_SUGGESTED_RATIO_STEP, max_step.max(MIN_SUGGESTED_RATIO_STEP));
+        (0..num_gears).map(|gear_idx| {
+            round_float_to(top_ratio * step.powf(shifts - gear_idx as f64), 3)
+        }).collect()
+    }
+
+    /// The rpm at which the engine produces the most torque according to the power curve
+    pub fn peak_torque_rpm(&self) -> i32 {
+        self.power_curve_data.iter()
+            .filter(|(rpm, _)| **rpm <= self.rpm_limit)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(rpm, _)| *rpm)
+            .unwrap_or(self.idle_rpm)
+    }
+
     pub fn max_speed(&self) -> f64 {
         let mut max_speed: f64 = 0.0;
         self.gear_ratios.iter().for_each(|ratio|{
@@ -200,3 +247,39 @@ impl GearingCalculator {
         prev_torque
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use super::GearingCalculator;
+
+    fn test_calculator() -> GearingCalculator {
+        let power_curve_data = BTreeMap::from([(1000, 200.0), (3000, 300.0), (4500, 350.0), (6000, 320.0), (7000, 280.0)]);
+        GearingCalculator {
+            power_curve_data,
+            idle_rpm: 900,
+            rpm_limit: 7000,
+            gear_ratios: vec![3.5, 2.2, 1.6, 1.2, 1.0, 0.8],
+            final_drive: 3.9,
+            drive_wheel_radius: 0.32
+        }
+    }
+
+    #[test]
+    fn suggested_ratios_reach_top_speed() {
+        let mut calc = test_calculator();
+        assert_eq!(calc.peak_torque_rpm(), 4500);
+        let ratios = calc.suggest_gear_ratios(280.0, 6);
+        assert_eq!(ratios.len(), 6);
+        assert!(ratios.windows(2).all(|w| w[0] > w[1]), "{:?}", ratios);
+        assert!((calc.max_speed_for_ratio(ratios[5]) - 280.0).abs() < 0.5, "{:?}", ratios);
+        assert!(ratios[0] / ratios[5] <= 4.5 + 0.01, "{:?}", ratios);
+        let steps: Vec<f64> = ratios.windows(2).map(|w| w[0] / w[1]).collect();
+        assert!(steps.iter().all(|step| (step - steps[0]).abs() < 0.01), "{:?}", steps);
+
+        calc.set_gear_ratios(ratios.clone());
+        assert!((calc.max_speed() - 280.0).abs() < 0.5);
+        assert!(calc.suggest_gear_ratios(280.0, 0).is_empty());
+        assert_eq!(calc.suggest_gear_ratios(280.0, 1), vec![ratios[5]]);
+    }
+}
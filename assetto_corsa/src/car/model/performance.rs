@@ -0,0 +1,322 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeMap;
+use tracing::debug;
+use crate::Car;
+use crate::{Result, Error, ErrorKind};
+use crate::car::data::{CarIniData, Drivetrain, Engine};
+use crate::car::data::suspensions::front_weight_fraction;
+use crate::car::data::drivetrain::{Gearbox, Traction};
+use crate::car::data::drivetrain::traction::DriveType;
+use crate::car::data::engine::{EngineData, PowerCurve};
+use crate::car::data::engine::turbo::TurboSection;
+use crate::car::data::tyres::tyre_sets::TyreCompounds;
+use crate::car::data::tyres::Tyres;
+use crate::car::lut_utils::{load_lut_from_property_value, LutInterpolator};
+use crate::car::model::SpeedApproximator;
+use crate::ini_utils;
+use crate::ini_utils::Ini;
+use crate::traits::MandatoryDataSection;
+
+pub const AERO_INI_FILENAME: &str = "aero.ini";
+
+/// The drag area (drag coefficient * frontal area in m²) used for cars without aero.ini drag data.
+/// Typical of a modern road car
+const DEFAULT_DRAG_AREA: f64 = 0.7;
+const ROLLING_RESISTANCE_COEFFICIENT: f64 = 0.015;
+/// The longitudinal grip of a road tyre used to limit the force the driven wheels can put down
+const TYRE_GRIP_COEFFICIENT: f64 = 1.1;
+/// Seconds without drive during each gear change
+const SHIFT_TIME: f64 = 0.2;
+const TIME_STEP: f64 = 0.01;
+/// Acceleration runs that take longer than this many seconds are treated as unable to reach the target
+const MAX_ACCELERATION_TIME: f64 = 60.0;
+/// The speed resolution (m/s) used when searching for the top speed in a gear
+const SPEED_STEP: f64 = 0.1;
+
+/// Estimates the top speed and straight line acceleration of a car from its engine, gearing,
+/// tyres, mass and drag using a [`SpeedApproximator`]. Turbo boost is included at its steady
+/// state level and the car is assumed to launch at peak torque and change up at the limiter
+pub struct PerformanceEstimator {
+    speed: SpeedApproximator,
+    /// Pairs of rpm, torque (Nm) from the power curve with any turbo boost applied
+    torque_curve: Vec<(f64, f64)>,
+    rpm_limit: f64,
+    gear_count: usize,
+    /// The fraction of the car's weight carried by the driven wheels
+    driven_weight_fraction: f64
+}
+
+impl PerformanceEstimator {
+    pub fn new(torque_curve: Vec<(f64, f64)>,
+               rpm_limit: f64,
+               gear_ratios: Vec<f64>,
+               final_drive: f64,
+               wheel_radius: f64,
+               mass: f64,
+               drag_area: f64,
+               driven_weight_fraction: f64) -> PerformanceEstimator {
+        let gear_ratios: Vec<f64> = gear_ratios.into_iter().filter(|ratio| *ratio > 0.0).collect();
+        let gear_count = gear_ratios.len();
+        // The drag area already includes the drag coefficient
+        let speed = SpeedApproximator::new(torque_curve.iter().map(|(rpm, _)| *rpm).collect(),
+                                           torque_curve.iter().map(|(_, torque)| *torque).collect(),
+                                           gear_ratios,
+                                           final_drive,
+                                           mass,
+                                           wheel_radius,
+                                           ROLLING_RESISTANCE_COEFFICIENT,
+                                           drag_area,
+                                           1.0);
+        PerformanceEstimator {
+            speed,
+            torque_curve,
+            rpm_limit,
+            gear_count,
+            driven_weight_fraction: driven_weight_fraction.clamp(0.0, 1.0)
+        }
+    }
+
+    /// Load the estimator inputs from the data of `car`. Returns an error naming the first
+    /// required input that couldn't be found. The drag area from aero.ini is optional; a typical
+    /// road car value is used if it's missing
+    pub fn from_car(car: &mut Car) -> Result<PerformanceEstimator> {
+        let missing = |input: &str, reason: String| {
+            Error::new(ErrorKind::InvalidCar, format!("missing {}. {}", input, reason))
+        };
+
+        let (power_curve_data, rpm_limit, turbos) = {
+            let engine = Engine::from_car(car)?;
+            let power_curve = PowerCurve::load_from_parent(&engine).map_err(|e| missing("power curve", e.to_string()))?;
+            let engine_data = EngineData::load_from_parent(&engine).map_err(|e| missing("engine rpm limits", e.to_string()))?;
+            let turbos = (0..engine.turbo_count()).map(|idx| {
+                TurboSection::load_from_parent(idx, &engine)
+            }).collect::<Result<Vec<TurboSection>>>().map_err(|e| missing("turbo data", e.to_string()))?;
+            (power_curve.get_curve_data(), engine_data.limiter, turbos)
+        };
+
+        let (gear_ratios, final_drive, drive_type) = {
+            let drivetrain = Drivetrain::from_car(car)?;
+            let gearbox = Gearbox::load_from_parent(&drivetrain).map_err(|e| missing("gear ratios", e.to_string()))?;
+            let traction = Traction::load_from_parent(&drivetrain).map_err(|e| missing("drive type", e.to_string()))?;
+            (gearbox.gear_ratios().clone(), gearbox.final_drive(), traction.drive_type)
+        };
+
+        let wheel_radius = {
+            let tyres = Tyres::from_car(car).map_err(|e| missing("tyre data", e.to_string()))?;
+            let compounds = TyreCompounds::load_from_parent(&tyres)?;
+            let tyre_set = compounds.get_default_set().ok_or_else(|| {
+                missing("tyre radius", "Couldn't find default tyre set".to_string())
+            })?;
+            match drive_type {
+                DriveType::FWD => tyre_set.front_data().radius(),
+                DriveType::RWD | DriveType::AWD | DriveType::AWD2 => tyre_set.rear_data().radius()
+            }
+        };
+
+        let mass = CarIniData::from_car(car)?.total_mass().ok_or_else(|| {
+            missing("car mass", format!("No TOTALMASS in {}", CarIniData::FILENAME))
+        })?;
+
+        let drag_area = drag_area_from_aero_ini(car).unwrap_or_else(|| {
+            debug!("No drag data in {}. Using a drag area of {}m²", AERO_INI_FILENAME, DEFAULT_DRAG_AREA);
+            DEFAULT_DRAG_AREA
+        });
+        let front_fraction = front_weight_fraction(car).ok().flatten().unwrap_or(0.5);
+        let driven_weight_fraction = match drive_type {
+            DriveType::FWD => front_fraction,
+            DriveType::RWD => 1.0 - front_fraction,
+            DriveType::AWD | DriveType::AWD2 => 1.0
+        };
+
+        let rpm_limit = match rpm_limit > 0 {
+            true => rpm_limit as f64,
+            false => power_curve_data.keys().last().map_or(0.0, |rpm| *rpm as f64)
+        };
+        Ok(PerformanceEstimator::new(
+            boosted_torque_curve(&power_curve_data, &turbos),
+            rpm_limit,
+            gear_ratios,
+            final_drive,
+            wheel_radius,
+            mass as f64,
+            drag_area,
+            driven_weight_fraction
+        ))
+    }
+
+    /// The speed (KM/H) the car settles at in each gear, either at the limiter or where the
+    /// drive force no longer overcomes drag and rolling resistance
+    pub fn gear_top_speeds_kmh(&self) -> Vec<f64> {
+        (0..self.gear_count).map(|gear_idx| {
+            let mut speed = self.speed.engine_rpm_to_wheel_speed(self.rpm_limit, gear_idx);
+            while speed > 0.0 && self.drive_force(speed, gear_idx) < self.resistance_force(speed) {
+                speed -= SPEED_STEP;
+            }
+            speed.max(0.0) * 3.6
+        }).collect()
+    }
+
+    /// The estimated top speed (KM/H) of the car
+    pub fn top_speed_kmh(&self) -> f64 {
+        self.gear_top_speeds_kmh().into_iter().fold(0.0, f64::max)
+    }
+
+    /// The estimated time in seconds to accelerate from a standstill to `target_kmh`.
+    /// None if the car can't reach the target speed
+    pub fn time_to_speed(&self, target_kmh: f64) -> Option<f64> {
+        let target = target_kmh / 3.6;
+        let mass = self.speed.mass();
+        let traction_limit = TYRE_GRIP_COEFFICIENT * self.speed.gravitational_force() * self.driven_weight_fraction;
+        let launch_rpm = self.peak_torque_rpm();
+        let (mut speed, mut time, mut gear_idx) = (0.0, 0.0, 0);
+        while speed < target {
+            if time > MAX_ACCELERATION_TIME || gear_idx >= self.gear_count {
+                return None;
+            }
+            let rpm = self.speed.wheel_speed_to_engine_rpm(speed, gear_idx);
+            if rpm >= self.rpm_limit {
+                gear_idx += 1;
+                time += SHIFT_TIME;
+                continue;
+            }
+            // The clutch is slipped to hold the engine at its peak torque when pulling away
+            let engine_rpm = match gear_idx {
+                0 => rpm.max(launch_rpm),
+                _ => rpm
+            };
+            let force = self.speed.wheel_force_at(engine_rpm, gear_idx).min(traction_limit) - self.resistance_force(speed);
+            if force <= 0.0 {
+                return None;
+            }
+            speed += (force / mass) * TIME_STEP;
+            time += TIME_STEP;
+        }
+        Some(time)
+    }
+
+    fn peak_torque_rpm(&self) -> f64 {
+        self.torque_curve.iter()
+            .filter(|(rpm, _)| *rpm <= self.rpm_limit)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(0.0, |(rpm, _)| *rpm)
+    }
+
+    fn drive_force(&self, speed: f64, gear_idx: usize) -> f64 {
+        let rpm = self.speed.wheel_speed_to_engine_rpm(speed, gear_idx);
+        match rpm > self.rpm_limit {
+            true => 0.0,
+            false => self.speed.wheel_force_at(rpm, gear_idx)
+        }
+    }
+
+    fn resistance_force(&self, speed: f64) -> f64 {
+        self.speed.drag_force_at(speed) + self.speed.rolling_resistance_force()
+    }
+}
+
+/// Apply the steady state boost of each turbo to the power curve. AC multiplies the torque by
+/// 1 + the total boost of all turbos
+fn boosted_torque_curve(power_curve_data: &BTreeMap<i32, f64>, turbos: &[TurboSection]) -> Vec<(f64, f64)> {
+    power_curve_data.iter().map(|(rpm, torque)| {
+        let rpm = *rpm as f64;
+        let boost: f64 = turbos.iter().map(|turbo| turbo.steady_state_boost(rpm)).sum();
+        (rpm, torque * (1.0 + boost))
+    }).collect()
+}
+
+/// The drag area (m²) at zero angle of attack of the wings in aero.ini, which include the body.
+/// None if the car has no aero.ini or none of its wings have drag data
+fn drag_area_from_aero_ini(car: &Car) -> Option<f64> {
+    let data = car.data_interface().get_original_file_data(AERO_INI_FILENAME).ok()??;
+    let ini = Ini::load_from_bytes(&data);
+    let mut drag_area = None;
+    for (_, section_name) in ini.get_section_names_with_prefix("WING_") {
+        let chord: Option<f64> = ini_utils::get_value(&ini, section_name, "CHORD");
+        let span: Option<f64> = ini_utils::get_value(&ini, section_name, "SPAN");
+        let lut: Option<String> = ini_utils::get_value(&ini, section_name, "LUT_AOA_CD");
+        let (chord, span, lut) = match (chord, span, lut) {
+            (Some(chord), Some(span), Some(lut)) => (chord, span, lut),
+            _ => continue
+        };
+        let cd = match load_lut_from_property_value::<f64, f64>(lut, car.data_interface()) {
+            Ok(lut_data) => LutInterpolator::from_vec(lut_data).get_value(0.0),
+            Err(e) => {
+                debug!("Failed to load drag data for {}. {}", section_name, e);
+                None
+            }
+        };
+        if let Some(cd) = cd {
+            let cd_gain = ini_utils::get_value(&ini, section_name, "CD_GAIN").unwrap_or(1.0);
+            *drag_area.get_or_insert(0.0) += chord * span * cd * cd_gain;
+        }
+    }
+    drag_area
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use crate::Car;
+    use super::PerformanceEstimator;
+
+    #[test]
+    fn estimates_close_to_shipped_specs() {
+        // The fixture is derived from the Porsche 911 Turbo S which ships with specs of
+        // 330km/h and 2.9s 0-100. Its tyres.ini isn't included so the rear tyre is added here
+        let fixture_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-turbo-with-ctrls/data");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("performance_estimate");
+        fs::create_dir_all(car_path.join("data")).unwrap();
+        for entry in fs::read_dir(&fixture_data).unwrap() {
+            let path = entry.unwrap().path();
+            fs::copy(&path, car_path.join("data").join(path.file_name().unwrap())).unwrap();
+        }
+        fs::write(car_path.join("data").join("tyres.ini"),
+                  "[FRONT]\nNAME=Street\nWIDTH=0.245\nRADIUS=0.3305\nRIM_RADIUS=0.254\n\n\
+                   [REAR]\nNAME=Street\nWIDTH=0.305\nRADIUS=0.3455\nRIM_RADIUS=0.254\n").unwrap();
+
+        let estimator = {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            PerformanceEstimator::from_car(&mut car)
+        };
+        let estimator = estimator.unwrap();
+
+        let top_speed = estimator.top_speed_kmh();
+        assert!((top_speed - 330.0).abs() < 330.0 * 0.08, "top speed {}", top_speed);
+        let zero_to_hundred = estimator.time_to_speed(100.0).unwrap();
+        assert!((zero_to_hundred - 2.9).abs() < 2.9 * 0.15, "0-100 {}", zero_to_hundred);
+
+        let gear_speeds = estimator.gear_top_speeds_kmh();
+        assert_eq!(gear_speeds.len(), 7);
+        assert!(gear_speeds.windows(2).take(4).all(|speeds| speeds[0] < speeds[1]), "{:?}", gear_speeds);
+    }
+
+    #[test]
+    fn missing_power_prevents_acceleration_estimate() {
+        let estimator = PerformanceEstimator::new(
+            vec![(1000.0, 20.0), (6000.0, 20.0)], 6000.0, vec![3.0, 1.0], 3.5, 0.3, 1200.0, 0.7, 0.5
+        );
+        assert_eq!(estimator.time_to_speed(100.0), None);
+    }
+}
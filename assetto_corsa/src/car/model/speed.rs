@@ -100,8 +100,7 @@ impl SpeedApproximator {
 
     pub fn wheel_torque_at(&self, rpm: f64, gear_index: usize) -> f64 {
         let engine_torque_at_rpm = self.interpolate_engine_torque(rpm);
-        let wheel_torque = (engine_torque_at_rpm * self.gear_ratios[gear_index] * self.final_drive) / self.wheel_radius;
-        wheel_torque
+        engine_torque_at_rpm * self.gear_ratios[gear_index] * self.final_drive
     }
 
     pub fn wheel_force_at(&self, rpm: f64, gear_index: usize) -> f64 {
@@ -115,6 +114,14 @@ impl SpeedApproximator {
         vehicle_speed
     }
 
+    pub fn wheel_speed_to_engine_rpm(&self, speed: f64, gear_ratio_idx: usize) -> f64 {
+        (speed * 60.0 * self.gear_ratios[gear_ratio_idx] * self.final_drive) / (2.0 * std::f64::consts::PI * self.wheel_radius)
+    }
+
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
     pub fn gravitational_force(&self) -> f64 {
         // gravitational_force represents the force due to gravity and is calculated as the product of the car's mass (kg)
         // and the gravitational acceleration (m/s^2). Therefore, its units are also N (Newtons).
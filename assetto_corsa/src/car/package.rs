@@ -0,0 +1,261 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fs;
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+
+use crate::car::acd_utils::AcdArchive;
+use crate::car::get_final_path_part;
+use crate::error::{Error, ErrorKind, Result};
+
+const DATA_FOLDER_NAME: &str = "data";
+const DATA_ACD_FILENAME: &str = "data.acd";
+
+/// Options controlling what goes into a car package created by [`export_car_package`]
+#[derive(Debug, Clone, Default)]
+pub struct PackageOptions {
+    /// Pack the car's data folder into a data.acd in the package. The car folder isn't modified
+    pub repack_data: bool,
+    /// Names of files or folders in the car root that are left out of the package
+    pub excluded_paths: Vec<String>
+}
+
+impl PackageOptions {
+    pub fn new(repack_data: bool) -> PackageOptions {
+        PackageOptions { repack_data, excluded_paths: Vec::new() }
+    }
+
+    pub fn exclude(mut self, name: &str) -> PackageOptions {
+        self.excluded_paths.push(name.to_string());
+        self
+    }
+}
+
+enum PackageWriter {
+    Zip(Box<zip::ZipWriter<File>>),
+    SevenZ(sevenz_rust::SevenZWriter<File>)
+}
+
+impl PackageWriter {
+    fn create(out_file: &Path) -> Result<PackageWriter> {
+        let is_7z = out_file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("7z"));
+        Ok(match is_7z {
+            true => PackageWriter::SevenZ(sevenz_rust::SevenZWriter::create(out_file).map_err(package_error)?),
+            false => PackageWriter::Zip(Box::new(zip::ZipWriter::new(File::create(out_file)?)))
+        })
+    }
+
+    fn add_file(&mut self, entry_name: &str, source_path: &Path) -> Result<()> {
+        match self {
+            PackageWriter::Zip(writer) => {
+                writer.start_file(entry_name, SimpleFileOptions::default()).map_err(package_error)?;
+                writer.write_all(&fs::read(source_path)?)?;
+            }
+            PackageWriter::SevenZ(writer) => {
+                let entry = sevenz_rust::SevenZArchiveEntry::from_path(source_path, entry_name.to_string());
+                writer.push_archive_entry(entry, Some(File::open(source_path)?)).map_err(package_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            PackageWriter::Zip(writer) => {
+                writer.finish().map_err(package_error)?.flush()?;
+            }
+            PackageWriter::SevenZ(writer) => {
+                writer.finish()?.rewind()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn package_error(e: impl ToString) -> Error {
+    Error::new(ErrorKind::IOError, e.to_string())
+}
+
+/// Package the car at `car_path` into a zip archive at `out_file` that can be shared and installed
+/// with Content Manager. A 7z archive is written instead if `out_file` has a .7z extension.
+/// The car folder is the top-level directory of the archive. The unpacked data folder is left
+/// out whenever the package contains a data.acd
+pub fn export_car_package(car_path: &Path, out_file: &Path, opts: &PackageOptions) -> Result<()> {
+    if !car_path.is_dir() {
+        return Err(Error::new(ErrorKind::NoSuchCar,
+                              format!("{} isn't a car folder", car_path.display())));
+    }
+    let car_name = get_final_path_part(car_path)?;
+    let data_path = car_path.join(DATA_FOLDER_NAME);
+
+    // The acd key is generated from the name of the folder it is written into so it has to be
+    // staged in a folder with the same name as the car
+    let staging_dir = tempfile::tempdir()?;
+    let repacked_acd = match opts.repack_data && data_path.is_dir() {
+        false => None,
+        true => {
+            let acd_path = staging_dir.path().join(&car_name).join(DATA_ACD_FILENAME);
+            fs::create_dir_all(acd_path.parent().unwrap())?;
+            AcdArchive::create_from_data_dir(&data_path)?.write_to(&acd_path)?;
+            Some(acd_path)
+        }
+    };
+    write_package(car_path, &car_name, out_file, opts, repacked_acd)
+}
+
+fn write_package(car_path: &Path,
+                 car_name: &str,
+                 out_file: &Path,
+                 opts: &PackageOptions,
+                 repacked_acd: Option<PathBuf>) -> Result<()> {
+    let skip_data_folder = repacked_acd.is_some() || car_path.join(DATA_ACD_FILENAME).is_file();
+    let mut writer = PackageWriter::create(out_file)?;
+    let walker = WalkDir::new(car_path).min_depth(1).sort_by_file_name().into_iter().filter_entry(|entry| {
+        if entry.depth() != 1 {
+            return true;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if opts.excluded_paths.iter().any(|excluded| *excluded == name) {
+            return false;
+        }
+        !(skip_data_folder && name == DATA_FOLDER_NAME && entry.file_type().is_dir())
+    });
+    for entry in walker {
+        let entry = entry.map_err(package_error)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative_path = entry.path().strip_prefix(car_path).map_err(package_error)?;
+        if relative_path == Path::new(DATA_ACD_FILENAME) && repacked_acd.is_some() {
+            continue;
+        }
+        let entry_name = relative_path.components().fold(car_name.to_string(), |name, component| {
+            format!("{}/{}", name, component.as_os_str().to_string_lossy())
+        });
+        writer.add_file(&entry_name, entry.path())?;
+    }
+    if let Some(acd_path) = &repacked_acd {
+        writer.add_file(&format!("{}/{}", car_name, DATA_ACD_FILENAME), acd_path)?;
+    }
+    writer.finish()?;
+    info!("Exported {} to {}", car_path.display(), out_file.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+    use crate::car::acd_utils::AcdArchive;
+    use crate::car::package::{export_car_package, PackageOptions};
+
+    fn create_test_car(root: &Path, name: &str) -> PathBuf {
+        let car_path = root.join(name);
+        let fixture_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-turbo-with-ctrls/data");
+        fs::create_dir_all(car_path.join("data")).unwrap();
+        for entry in fs::read_dir(fixture_data).unwrap() {
+            let entry = entry.unwrap();
+            fs::copy(entry.path(), car_path.join("data").join(entry.file_name())).unwrap();
+        }
+        fs::create_dir_all(car_path.join("skins").join("red")).unwrap();
+        fs::write(car_path.join("skins").join("red").join("ui_skin.json"), "{}").unwrap();
+        fs::create_dir_all(car_path.join("sfx")).unwrap();
+        fs::write(car_path.join("sfx").join("GUIDs.txt"), "").unwrap();
+        fs::create_dir_all(car_path.join("ui")).unwrap();
+        fs::write(car_path.join("ui").join("ui_car.json"), "{}").unwrap();
+        fs::create_dir_all(car_path.join(".engine-crane-backup")).unwrap();
+        fs::write(car_path.join(".engine-crane-backup").join("manifest.json"), "{}").unwrap();
+        fs::write(car_path.join("engine_crane_swap_report.json"), "{}").unwrap();
+        car_path
+    }
+
+    fn zip_entry_names(zip_path: &Path) -> Vec<String> {
+        let archive = zip::ZipArchive::new(File::open(zip_path).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn package_has_car_folder_as_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path(), "engine_crane_package_car");
+        let out_file = temp_dir.path().join("engine_crane_package_car.zip");
+        let opts = PackageOptions::new(true)
+            .exclude(".engine-crane-backup")
+            .exclude("engine_crane_swap_report.json");
+        export_car_package(&car_path, &out_file, &opts).unwrap();
+
+        assert_eq!(zip_entry_names(&out_file), vec![
+            "engine_crane_package_car/data.acd",
+            "engine_crane_package_car/sfx/GUIDs.txt",
+            "engine_crane_package_car/skins/red/ui_skin.json",
+            "engine_crane_package_car/ui/ui_car.json"
+        ]);
+        assert!(!car_path.join("data.acd").exists());
+
+        let unpack_path = temp_dir.path().join("unpacked");
+        zip::ZipArchive::new(File::open(&out_file).unwrap()).unwrap().extract(&unpack_path).unwrap();
+        let acd = AcdArchive::load_from_acd_file(&unpack_path.join("engine_crane_package_car").join("data.acd")).unwrap();
+        assert_eq!(acd.get_file_data("engine.ini").unwrap(), fs::read(car_path.join("data").join("engine.ini")).unwrap());
+    }
+
+    #[test]
+    fn unpacked_data_kept_without_repack() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path(), "engine_crane_package_unpacked_car");
+        let out_file = temp_dir.path().join("engine_crane_package_unpacked_car.zip");
+        export_car_package(&car_path, &out_file, &PackageOptions::new(false)).unwrap();
+
+        let names = zip_entry_names(&out_file);
+        assert!(names.contains(&"engine_crane_package_unpacked_car/data/engine.ini".to_string()));
+        assert!(names.contains(&"engine_crane_package_unpacked_car/engine_crane_swap_report.json".to_string()));
+        assert!(names.contains(&"engine_crane_package_unpacked_car/.engine-crane-backup/manifest.json".to_string()));
+        assert!(!names.iter().any(|name| name.ends_with("data.acd")));
+    }
+
+    #[test]
+    fn seven_zip_package_written_for_7z_extension() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path(), "engine_crane_package_7z_car");
+        let out_file = temp_dir.path().join("engine_crane_package_7z_car.7z");
+        export_car_package(&car_path, &out_file, &PackageOptions::new(true).exclude(".engine-crane-backup")).unwrap();
+
+        let archive = sevenz_rust::Archive::read(&mut File::open(&out_file).unwrap(),
+                                                 fs::metadata(&out_file).unwrap().len(),
+                                                 &[]).unwrap();
+        let mut names: Vec<&str> = archive.files.iter().map(|entry| entry.name()).collect();
+        names.sort();
+        assert_eq!(names, vec![
+            "engine_crane_package_7z_car/data.acd",
+            "engine_crane_package_7z_car/engine_crane_swap_report.json",
+            "engine_crane_package_7z_car/sfx/GUIDs.txt",
+            "engine_crane_package_7z_car/skins/red/ui_skin.json",
+            "engine_crane_package_7z_car/ui/ui_car.json"
+        ]);
+    }
+}
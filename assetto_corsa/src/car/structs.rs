@@ -21,7 +21,7 @@
 
 use std::{fmt};
 use std::fmt::Display;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use crate::ini_utils;
 use crate::ini_utils::Ini;
@@ -130,6 +130,15 @@ impl<K,V> LutProperty<K, V>
         &self.lut
     }
 
+    /// Write the lut data to `dir` if it is stored in its own file. Inline and path-only
+    /// luts have nothing to write so this is a no-op for them
+    pub fn write_to_dir(&self, dir: &Path) -> Result<(), String> {
+        match &self.lut {
+            LutType::File(lut_file) => lut_file.write_to_dir(dir),
+            _ => Ok(())
+        }
+    }
+
     pub fn get_mut_type(&mut self) -> &mut LutType<K, V> {
         &mut self.lut
     }
@@ -28,6 +28,7 @@ use serde_json::{json, Map, Value};
 
 use crate::car::Car;
 use crate::error::{Error, ErrorKind, Result};
+use crate::ini_utils::TextEncoding;
 
 
 #[derive(Debug)]
@@ -60,8 +61,15 @@ pub struct UiInfo {
 }
 
 impl UiInfo {
+    /// Load the ui info of the car in `car_path` without loading any of the car's data files
+    pub fn from_car_path(car_path: &Path) -> Result<UiInfo> {
+        UiInfo::load(car_path.join(["ui", "ui_car.json"].iter().collect::<PathBuf>()).as_path())
+    }
+
+    /// Load the ui json at `ui_json_path`. Files that aren't valid UTF-8 are decoded in the same
+    /// way as ini files; the json is always written back as UTF-8
     fn load(ui_json_path: &Path) -> Result<UiInfo> {
-        let ui_info_string = fs::read_to_string(ui_json_path)?;
+        let (ui_info_string, _) = TextEncoding::decode(&fs::read(ui_json_path)?);
         let json_config: serde_json::Value = serde_json::from_str(ui_info_string
             .replace("\r\n", "\n")
             .replace("\n", " ")
@@ -104,6 +112,10 @@ impl UiInfo {
         self.get_json_string("description")
     }
 
+    pub fn set_description(&mut self, description: String) {
+        self.set_json_string("description", description);
+    }
+
     pub fn class(&self) -> Option<&str> {
         self.get_json_string("class")
     }
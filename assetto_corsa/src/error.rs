@@ -35,6 +35,10 @@ impl Error {
     pub(crate) fn new(kind: ErrorKind, details: String) -> Error {
         Error{ kind, details }
     }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
 }
 
 impl Display for Error {
@@ -45,16 +49,24 @@ impl Display for Error {
 
 impl error::Error for Error {}
 
+fn io_error_kind(e: &io::Error) -> ErrorKind {
+    match e.kind() {
+        io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+        _ => ErrorKind::IOError
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Self {
-        Error::new(ErrorKind::IOError, format!("{}. {}", e.to_string(), e.kind().to_string()))
+        Error::new(io_error_kind(&e), format!("{}. {}", e.to_string(), e.kind().to_string()))
     }
 }
 
 impl From<fs_extra::error::Error> for Error {
     fn from(e: fs_extra::error::Error) -> Self {
         return match e.kind {
-            fs_extra::error::ErrorKind::Io(io_error) => { Error::new(ErrorKind::IOError, format!("{}. {}", io_error.to_string(), io_error.kind().to_string())) }
+            fs_extra::error::ErrorKind::Io(io_error) => { Error::new(io_error_kind(&io_error), format!("{}. {}", io_error.to_string(), io_error.kind().to_string())) }
+            fs_extra::error::ErrorKind::PermissionDenied => { Error::new(ErrorKind::PermissionDenied, e.to_string()) }
             _ => { Error::new(ErrorKind::IOError, e.to_string()) }
         }
     }
@@ -93,12 +105,14 @@ pub enum ErrorKind {
     InvalidUpdate,
     NotInstalled,
     IOError,
+    PermissionDenied,
     JsonDecodeError,
     TomlDecodeError,
     AcdError,
     UpdateError,
     ArgumentError,
     IniParseError,
+    ProtectedCar,
     Uncategorized
 }
 
@@ -111,11 +125,13 @@ impl ErrorKind {
             ErrorKind::InvalidUpdate => "requested update is invalid",
             ErrorKind::NotInstalled => "not installed",
             ErrorKind::IOError => "io error",
+            ErrorKind::PermissionDenied => "permission denied",
             ErrorKind::JsonDecodeError => "json decode error",
             ErrorKind::TomlDecodeError => "toml decode error",
             ErrorKind::AcdError => "acd decode error",
             ErrorKind::ArgumentError => "argument error",
             ErrorKind::IniParseError => "ini parse error",
+            ErrorKind::ProtectedCar => "car is protected from editing",
             ErrorKind::Uncategorized => "uncategorized error",
             ErrorKind::UpdateError => "update error"
         }
@@ -30,6 +30,7 @@ use std::collections::{BTreeMap, HashSet};
 use std::io::Write;
 use std::path::Path;
 use indexmap::IndexMap;
+use tracing::debug;
 use crate::error::{Error, ErrorKind};
 
 pub trait IniUpdater {
@@ -164,10 +165,43 @@ pub fn set_value<T: std::fmt::Display>(ini: &mut Ini,
     ini.set_value(section, key, val.to_string())
 }
 
+/// Write `val` with `precision` decimal places unless the [`FloatPrecision`] policy of `ini`
+/// overrides the precision for `key`. An existing value that is numerically equal to the value
+/// that would be written is left as it is so that its formatting is preserved
 pub fn set_float(ini: &mut Ini, section: &str, key: &str, val: f64, precision: usize) -> Option<String> {
-    ini.set_value(section,
-                  key,
-                  format!("{number:.prec$}", number=val, prec=precision))
+    let precision = ini.float_precision.precision_for(key, precision);
+    let formatted = format!("{number:.prec$}", number=val, prec=precision);
+    if let Some(existing) = ini.get_value(section, key) {
+        if existing.parse::<f64>().ok() == formatted.parse::<f64>().ok() {
+            return Some(existing);
+        }
+    }
+    ini.set_value(section, key, formatted)
+}
+
+/// How many decimal places float values are written to an [`Ini`] with by [`set_float`].
+/// Keys with an override use that, otherwise the default is used if set. With neither, the
+/// precision chosen by the caller of [`set_float`] is used
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct FloatPrecision {
+    default: Option<usize>,
+    overrides: BTreeMap<String, usize>
+}
+
+impl FloatPrecision {
+    pub fn with_default(mut self, precision: usize) -> FloatPrecision {
+        self.default = Some(precision);
+        self
+    }
+
+    pub fn with_override(mut self, key: &str, precision: usize) -> FloatPrecision {
+        self.overrides.insert(key.to_string(), precision);
+        self
+    }
+
+    pub fn precision_for(&self, key: &str, fallback: usize) -> usize {
+        self.overrides.get(key).copied().or(self.default).unwrap_or(fallback)
+    }
 }
 
 pub fn validate_section_exists(ini: &Ini, section_name: &str) -> Result<(), MissingSection> {
@@ -184,6 +218,7 @@ pub struct Section {
     property_map: IndexMap<String, Property>,
     name_comment: Option<Comment>,
     comments: Vec<Comment>,
+    blank_line_count: usize,
     ordering: IndexMap<String, LineType>
 }
 
@@ -195,6 +230,7 @@ impl Section {
             property_map: IndexMap::new(),
             name_comment: None,
             comments: Vec::new(),
+            blank_line_count: 0,
             ordering: IndexMap::new()
         }
     }
@@ -225,6 +261,7 @@ impl Section {
                             property_map: IndexMap::new(),
                             name_comment,
                             comments: Vec::new(),
+                            blank_line_count: 0,
                             ordering: IndexMap::new()
                         })
                     }
@@ -250,6 +287,18 @@ impl Section {
         self.property_map.insert(property.key.clone(), property);
     }
 
+    /// Add a property that isn't in the section yet after the last non-blank line of the
+    /// section so that the blank lines separating it from the next section are kept
+    fn add_new_property(&mut self, property: Property) {
+        let trailing_blank_lines = self.ordering.values().rev().take_while(|line_type| {
+            **line_type == LineType::Blank
+        }).count();
+        self.ordering.shift_insert(self.ordering.len() - trailing_blank_lines,
+                                   property.key.clone(),
+                                   LineType::KeyValue);
+        self.property_map.insert(property.key.clone(), property);
+    }
+
     pub fn remove_propery(&mut self, key: &str) -> Option<Property> {
         self.ordering.shift_remove_entry(key);
         let (_, val) = self.property_map.shift_remove_entry(key)?;
@@ -261,36 +310,71 @@ impl Section {
         self.ordering.insert(format!("comment-{}", self.comments.len().to_string()),
                              LineType::Comment);
     }
+
+    pub fn add_blank_line(&mut self) {
+        self.blank_line_count += 1;
+        self.ordering.insert(format!("blank-{}", self.blank_line_count), LineType::Blank);
+    }
+
+    /// Make sure the section ends with a blank line so that a section added after it is
+    /// separated from it. Sections with no content are left alone
+    fn ensure_trailing_blank_line(&mut self) {
+        if self.name.is_empty() && self.ordering.is_empty() {
+            return;
+        }
+        if self.ordering.values().last() != Some(&LineType::Blank) {
+            self.add_blank_line();
+        }
+    }
+
+    /// Append the properties and comments of `other` to this section. A property in `other`
+    /// replaces the value of a property with the same key in this section
+    fn merge(&mut self, other: Section) {
+        let mut properties = other.property_map.into_values();
+        let mut comments = other.comments.into_iter();
+        for line_type in other.ordering.values() {
+            match line_type {
+                LineType::KeyValue => if let Some(property) = properties.next() {
+                    self.add_property(property);
+                }
+                LineType::Comment => if let Some(comment) = comments.next() {
+                    self.add_comment(comment);
+                }
+                LineType::Blank => self.add_blank_line(),
+                LineType::Ignore | LineType::SectionName => {}
+            }
+        }
+    }
 }
 
 impl ToString for Section {
     fn to_string(&self) -> String {
-        let mut out = String::new();
+        let mut lines = Vec::new();
         if !self.name.is_empty() {
-            out += &format!("[{}]", &self.name);
+            let mut name_line = format!("[{}]", &self.name);
             if let Some(comment) = &self.name_comment {
-                out += &comment.to_string();
+                name_line += &comment.to_string();
             }
-            out += "\n";
+            lines.push(name_line);
         }
-        let mut property_iter = self.property_map.values();
         let mut comment_iter = self.comments.iter();
-
-        let kv_strings: Vec<String> = self.ordering.iter().filter_map(|(_, line_type)| {
+        lines.extend(self.ordering.iter().filter_map(|(key, line_type)| {
             return match line_type {
                 LineType::KeyValue => {
-                    Some(property_iter.next().unwrap().to_string())
+                    Some(self.property_map[key].to_string())
                 }
                 LineType::Comment => {
                     Some(comment_iter.next().unwrap().to_string())
                 }
+                LineType::Blank => {
+                    Some(String::new())
+                }
                 LineType::Ignore | LineType::SectionName => {
                     None
                 }
             }
-        }).collect();
-        out += &kv_strings.join("\n");
-        out
+        }));
+        lines.join("\n")
     }
 }
 
@@ -299,6 +383,7 @@ pub struct Property {
     key: String,
     value: String,
     indentation: Option<String>,
+    value_indentation: Option<String>,
     comment: Option<Comment>
 }
 
@@ -316,21 +401,22 @@ impl Property {
                     None => { Err(String::from("Cannot find valid property value")) }
                     Some(delimiter_pos) => {
                         let key = String::from(&line[key_start_pos..delimiter_pos]);
-                        let mut value = String::new();
-                        let mut comment = None;
-                        match line.find(|c: char| comment_symbols.contains(&c)) {
-                            None => {
-                                value += &line[delimiter_pos+1..].trim();
-                            }
+                        let remainder = &line[delimiter_pos+1..];
+                        let (raw_value, comment) = match remainder.find(|c: char| comment_symbols.contains(&c)) {
+                            None => (remainder.trim_end(), None),
                             Some(comment_start_pos) => {
-                                value += &line[delimiter_pos+1..comment_start_pos].trim();
-                                comment = Comment::from_line(
-                                    &line[delimiter_pos+1+value.len()..],
-                                    comment_symbols
-                                );
+                                let raw_value = remainder[..comment_start_pos].trim_end();
+                                // The whitespace between the value and the comment symbol is kept
+                                // as the indentation of the comment
+                                (raw_value, Comment::from_line(&remainder[raw_value.len()..], comment_symbols))
                             }
-                        }
-                        Ok(Property{ key, value, indentation, comment })
+                        };
+                        let value = raw_value.trim_start();
+                        let value_indentation = match raw_value.len() - value.len() {
+                            0 => None,
+                            value_start_pos => Some(String::from(&raw_value[..value_start_pos]))
+                        };
+                        Ok(Property{ key, value: String::from(value), indentation, value_indentation, comment })
                     }
                 }
             }
@@ -362,6 +448,9 @@ impl ToString for Property {
         }
         out += &self.key;
         out += "=";
+        if let Some(value_indentation) = &self.value_indentation {
+            out += value_indentation;
+        }
         out += &self.value;
         if let Some(comment) = &self.comment {
             out += &comment.to_string();
@@ -417,6 +506,7 @@ pub (crate) enum LineType {
     SectionName,
     KeyValue,
     Comment,
+    Blank,
     Ignore
 }
 
@@ -425,6 +515,9 @@ pub (crate) enum LineType {
 pub struct Ini {
     sections: IndexMap<String, Section>,
     comment_symbols: HashSet<char>,
+    float_precision: FloatPrecision,
+    line_ending: LineEnding,
+    encoding: TextEncoding
 }
 
 impl Ini {
@@ -433,21 +526,39 @@ impl Ini {
     pub fn new() -> Ini {
         Ini {
             sections: IndexMap::new(),
-            comment_symbols: HashSet::from([';', '#'])
+            comment_symbols: HashSet::from([';', '#']),
+            float_precision: FloatPrecision::default(),
+            line_ending: LineEnding::Lf,
+            encoding: TextEncoding::Utf8
         }
     }
 
+    pub fn set_float_precision(&mut self, float_precision: FloatPrecision) {
+        self.float_precision = float_precision;
+    }
+
     pub fn load_from_string(ini_data: String) -> Ini {
         let mut ini = Ini::new();
         ini.parse(ini_data);
         ini
     }
 
+    /// Load ini data that may not be UTF-8 encoded. The data is written back out with the
+    /// encoding it was loaded with, see [`TextEncoding::decode`]
+    pub fn load_from_bytes(ini_data: &[u8]) -> Ini {
+        let (text, encoding) = TextEncoding::decode(ini_data);
+        let mut ini = Ini::load_from_string(text);
+        ini.encoding = encoding;
+        ini
+    }
+
     pub fn load_from_file(path: &Path) -> io::Result<Ini> {
-        Ok(Ini::load_from_string(fs::read_to_string(path)?))
+        Ok(Ini::load_from_bytes(&fs::read(path)?))
     }
 
     pub fn parse(&mut self, input: String) {
+        self.line_ending = LineEnding::detect(&input);
+        let input = normalise_input(&input);
         let mut current_section= Section::new(String::from(""));
         for (_num, line) in input.lines().enumerate() {
             match self.get_expected_line_type(line) {
@@ -465,6 +576,9 @@ impl Ini {
                         Comment::from_line(line, &self.comment_symbols).unwrap()
                     );
                 }
+                LineType::Blank => {
+                    current_section.add_blank_line();
+                }
                 LineType::Ignore => {}
             }
         }
@@ -476,7 +590,7 @@ impl Ini {
     }
 
     pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
-        fs::write(path, self.to_string())
+        fs::write(path, self.to_bytes())
     }
     
     pub fn write_to_buf(&self, buf: &mut Vec<u8>) -> io::Result<()> {
@@ -484,7 +598,7 @@ impl Ini {
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        self.to_string().into_bytes()
+        self.encoding.encode(&self.to_string())
     }
 
     pub fn get_value(&self, section_name: &str, property_name: &str) -> Option<String> {
@@ -526,15 +640,19 @@ impl Ini {
                      property_key: &str,
                      property_value: String) -> Option<String> {
         if !self.sections.contains_key(section_name) {
+            if let Some((_, last_section)) = self.sections.last_mut() {
+                last_section.ensure_trailing_blank_line();
+            }
             self.sections.insert(String::from(section_name),
                                  Section::new(String::from(section_name)));
         }
         let section = self.sections.get_mut(section_name).unwrap();
         if !section.contains_property(property_key) {
-            section.add_property(Property {
+            section.add_new_property(Property {
                 key: String::from(property_key),
                 value: property_value,
                 indentation: section.indentation.clone(),
+                value_indentation: None,
                 comment: None
             });
             None
@@ -588,6 +706,51 @@ impl Ini {
         }
     }
 
+    /// Re-parses the output of `to_string` and checks that every section, key and value survives
+    /// the trip. Comments and whitespace aren't compared. Returns a description of each difference
+    /// found if the round-trip isn't lossless
+    pub fn validate_roundtrip(&self) -> Result<(), Vec<String>> {
+        let reparsed = Ini::load_from_string(self.to_string());
+        let mut differences = Vec::new();
+        for (section_name, section) in &self.sections {
+            let reparsed_section = match reparsed.sections.get(section_name) {
+                None => {
+                    if !section.property_map.is_empty() {
+                        differences.push(format!("Section [{}] was lost", section_name));
+                    }
+                    continue;
+                }
+                Some(s) => s
+            };
+            for (key, property) in &section.property_map {
+                match reparsed_section.property_map.get(key) {
+                    None => differences.push(format!("[{}] {} was lost", section_name, key)),
+                    Some(reparsed_property) => {
+                        if reparsed_property.value != property.value {
+                            differences.push(format!("[{}] {} changed from '{}' to '{}'",
+                                                     section_name, key,
+                                                     property.value, reparsed_property.value));
+                        }
+                    }
+                }
+            }
+            for key in reparsed_section.property_map.keys() {
+                if !section.property_map.contains_key(key) {
+                    differences.push(format!("[{}] {} was added", section_name, key));
+                }
+            }
+        }
+        for (section_name, section) in &reparsed.sections {
+            if !self.sections.contains_key(section_name) && !section.property_map.is_empty() {
+                differences.push(format!("Section [{}] was added", section_name));
+            }
+        }
+        match differences.is_empty() {
+            true => Ok(()),
+            false => Err(differences)
+        }
+    }
+
     fn finish_section(&mut self, section: Section) {
         let key;
         if section.name.is_empty() {
@@ -595,14 +758,22 @@ impl Ini {
         } else {
             key = section.name.clone();
         }
-        self.sections.insert(key, section);
+        match self.sections.get_mut(&key) {
+            Some(existing_section) => {
+                debug!("Merging duplicate section [{}]", key);
+                existing_section.merge(section);
+            }
+            None => {
+                self.sections.insert(key, section);
+            }
+        }
     }
 
     /// Essentially "what delimiting character comes first?"
     fn get_expected_line_type(&self, line: &str) -> LineType {
         let trimmed = line.trim();
         if trimmed.is_empty() {
-            return LineType::Ignore;
+            return LineType::Blank;
         } else if trimmed.starts_with('=') {
             return LineType::Ignore;
         }
@@ -674,12 +845,86 @@ impl Display for Ini {
                 None
             }
         }).collect();
-        out += &section_strings.join("\n\n");
+        out += &section_strings.join("\n");
         out += "\n";
+        if self.line_ending == LineEnding::CrLf {
+            out = out.replace('\n', "\r\n");
+        }
         write!(f, "{}", out)
     }
 }
 
+/// The line ending style of an ini file. Files are written back out with the style they were
+/// loaded with
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum LineEnding {
+    Lf,
+    CrLf
+}
+
+impl LineEnding {
+    fn detect(input: &str) -> LineEnding {
+        match input.contains("\r\n") {
+            true => LineEnding::CrLf,
+            false => LineEnding::Lf
+        }
+    }
+}
+
+/// The text encoding of an ini file. Files that aren't valid UTF-8 are assumed to be
+/// Windows-1252, which older Kunos and mod cars use for symbols like '°' in comments
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TextEncoding {
+    Utf8,
+    Windows1252
+}
+
+impl TextEncoding {
+    /// Characters for bytes 0x80 to 0x9F in Windows-1252. The bytes the encoding leaves
+    /// undefined map to the control character of the same value so that they round-trip
+    const WINDOWS_1252_HIGH: [char; 32] = [
+        '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+        '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+        '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+        '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}'
+    ];
+
+    /// Decode `data` as UTF-8, falling back to Windows-1252 if it isn't valid UTF-8.
+    /// A leading UTF-8 byte order mark is removed; files are never written back with one
+    pub fn decode(data: &[u8]) -> (String, TextEncoding) {
+        let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+        match std::str::from_utf8(data) {
+            Ok(text) => (text.to_string(), TextEncoding::Utf8),
+            Err(_) => {
+                debug!("Ini data isn't valid UTF-8. Decoding as Windows-1252");
+                let text = data.iter().map(|byte| match byte {
+                    0x80..=0x9f => Self::WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+                    _ => *byte as char
+                }).collect();
+                (text, TextEncoding::Windows1252)
+            }
+        }
+    }
+
+    /// Encode `text` with this encoding. Text that can't be represented in Windows-1252 is
+    /// written as UTF-8 instead so that nothing is lost
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            TextEncoding::Utf8 => text.as_bytes().to_vec(),
+            TextEncoding::Windows1252 => {
+                let encoded: Option<Vec<u8>> = text.chars().map(|c| match c as u32 {
+                    0x00..=0x7f | 0xa0..=0xff => Some(c as u8),
+                    _ => Self::WINDOWS_1252_HIGH.iter().position(|high| *high == c).map(|idx| 0x80 + idx as u8)
+                }).collect();
+                encoded.unwrap_or_else(|| {
+                    debug!("Ini data can't be represented in Windows-1252. Writing as UTF-8");
+                    text.as_bytes().to_vec()
+                })
+            }
+        }
+    }
+}
+
 fn section_name_to_idx(section_prefix: &str, name: &str) -> Option<usize> {
     match name.strip_prefix(section_prefix) {
         None => None,
@@ -697,9 +942,129 @@ fn section_name_to_idx(section_prefix: &str, name: &str) -> Option<usize> {
     }
 }
 
+/// Strip a leading UTF-8 byte order mark and convert CRLF or lone CR line endings to LF.
+/// Some Windows tools save ini files this way and the BOM would otherwise end up in the
+/// name of the first section
+fn normalise_input(input: &str) -> String {
+    input.strip_prefix('\u{feff}').unwrap_or(input).replace("\r\n", "\n").replace('\r', "\n")
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::ini_utils::section_name_to_idx;
+    use std::fs;
+    use std::path::Path;
+    use crate::ini_utils::{FloatPrecision, Ini, section_name_to_idx, set_float};
+
+    #[test]
+    fn roundtrip_detects_lost_values() {
+        let mut ini = Ini::load_from_string(String::from("[HEADER]\nVERSION=1 ; comment\n\n[ENGINE]\nLIMITER=7000\n"));
+        assert_eq!(ini.validate_roundtrip(), Ok(()));
+
+        ini.set_value("ENGINE", "LIMITER", String::from("7500 ; [trailing"));
+        ini.set_value("ENGINE", "NEW=KEY", String::from("1"));
+        let differences = ini.validate_roundtrip().unwrap_err();
+        assert!(!differences.is_empty());
+    }
+
+    #[test]
+    fn car_fixtures_roundtrip() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/car-with-turbo-with-ctrls/data");
+        for entry in fs::read_dir(data_dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(false, |ext| ext == "ini") {
+                let ini = Ini::load_from_file(&path).unwrap();
+                assert_eq!(ini.validate_roundtrip(), Ok(()), "{} didn't round-trip", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn bom_and_crlf_are_ignored() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/windows-authored/engine.ini");
+        let ini = Ini::load_from_file(&path).unwrap();
+        assert_eq!(ini.get_value("HEADER", "VERSION"), Some(String::from("1")));
+        assert_eq!(ini.get_value("HEADER", "POWER_CURVE"), Some(String::from("power.lut")));
+        assert_eq!(ini.get_value("ENGINE_DATA", "LIMITER"), Some(String::from("7200")));
+        assert_eq!(ini.validate_roundtrip(), Ok(()));
+    }
+
+    #[test]
+    fn bom_removed_when_written() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/windows-authored/engine.ini");
+        let original = fs::read(&path).unwrap();
+        let ini = Ini::load_from_bytes(&original);
+        assert_eq!(ini.get_value("HEADER", "VERSION"), Some(String::from("1")));
+        assert_eq!(ini.to_bytes(), original[3..].to_vec());
+    }
+
+    #[test]
+    fn windows_1252_comments_round_trip() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test-data/windows-1252/engine.ini");
+        let original = fs::read(&path).unwrap();
+        let mut ini = Ini::load_from_file(&path).unwrap();
+        assert_eq!(ini.get_value("OVERHEAT", "MAX_TEMP"), Some(String::from("110")));
+        assert!(ini.to_string().contains("; Temperatures in \u{b0}C \u{2013} see the \u{201c}cooling\u{201d} notes"));
+        assert_eq!(ini.to_bytes(), original);
+
+        ini.set_value("OVERHEAT", "MAX_TEMP", String::from("120"));
+        let mut expected = original.clone();
+        let value_pos = original.windows(12).position(|w| w == b"MAX_TEMP=110").unwrap() + 9;
+        expected[value_pos..value_pos + 3].copy_from_slice(b"120");
+        assert_eq!(ini.to_bytes(), expected);
+    }
+
+    #[test]
+    fn comments_order_and_line_endings_preserved() {
+        let input = "; top comment\r\n\r\n[GEARS]  ; gears\r\nCOUNT= 2\t\t; forward gears\r\n; ratios\r\nGEAR_1=2.5\r\nGEAR_2=1.5\r\n\r\nFINAL=3.10 ; final\r\n\r\n\r\n[CLUTCH]\r\nMAX_TORQUE=400\r\n";
+        let mut ini = Ini::load_from_string(String::from(input));
+        assert_eq!(ini.to_string(), input);
+
+        assert_eq!(ini.get_value("GEARS", "COUNT"), Some(String::from("2")));
+        set_float(&mut ini, "GEARS", "FINAL", 3.1, 3);
+        set_float(&mut ini, "GEARS", "GEAR_1", 2.25, 3);
+        assert_eq!(ini.to_string(), input.replace("GEAR_1=2.5", "GEAR_1=2.250"));
+
+        ini.set_value("GEARS", "GEAR_3", String::from("1.1"));
+        ini.set_value("DIFFERENTIAL", "POWER", String::from("0.1"));
+        assert_eq!(ini.to_string(), input
+            .replace("GEAR_1=2.5", "GEAR_1=2.250")
+            .replace("FINAL=3.10 ; final\r\n", "FINAL=3.10 ; final\r\nGEAR_3=1.1\r\n")
+            + "\r\n[DIFFERENTIAL]\r\nPOWER=0.1\r\n");
+    }
+
+    #[test]
+    fn duplicate_sections_are_merged() {
+        let ini = Ini::load_from_string(String::from(
+            "[LED_0]\nOBJECT_NAME=LED_A\nRPM_SWITCH=6000\n\n[LED_1]\nRPM_SWITCH=6500\n\n[LED_0]\nRPM_SWITCH=6200 ; fixed\n"
+        ));
+        assert_eq!(ini.get_value("LED_0", "OBJECT_NAME"), Some(String::from("LED_A")));
+        assert_eq!(ini.get_value("LED_0", "RPM_SWITCH"), Some(String::from("6200")));
+        assert_eq!(ini.get_value("LED_1", "RPM_SWITCH"), Some(String::from("6500")));
+        assert_eq!(ini.to_string().matches("[LED_0]").count(), 1);
+    }
+
+    #[test]
+    fn float_precision_overrides_round_trip() {
+        let mut ini = Ini::new();
+        set_float(&mut ini, "TURBO_0", "MAX_BOOST", 1.23456, 2);
+        set_float(&mut ini, "ENGINE_DATA", "INERTIA", 0.123456, 3);
+        assert_eq!(ini.get_value("TURBO_0", "MAX_BOOST"), Some(String::from("1.23")));
+        assert_eq!(ini.get_value("ENGINE_DATA", "INERTIA"), Some(String::from("0.123")));
+
+        ini.set_float_precision(FloatPrecision::default().with_override("MAX_BOOST", 4));
+        set_float(&mut ini, "TURBO_0", "MAX_BOOST", 1.23456, 2);
+        set_float(&mut ini, "ENGINE_DATA", "INERTIA", 0.123456, 3);
+        let reparsed = Ini::load_from_string(ini.to_string());
+        let max_boost: f64 = reparsed.get_value("TURBO_0", "MAX_BOOST").unwrap().parse().unwrap();
+        assert!((max_boost - 1.23456).abs() <= 0.5e-4);
+        assert_eq!(reparsed.get_value("ENGINE_DATA", "INERTIA"), Some(String::from("0.123")));
+
+        ini.set_float_precision(FloatPrecision::default().with_default(1).with_override("INERTIA", 5));
+        set_float(&mut ini, "TURBO_0", "MAX_BOOST", 1.23456, 2);
+        set_float(&mut ini, "ENGINE_DATA", "INERTIA", 0.123456, 3);
+        assert_eq!(ini.get_value("TURBO_0", "MAX_BOOST"), Some(String::from("1.2")));
+        assert_eq!(ini.get_value("ENGINE_DATA", "INERTIA"), Some(String::from("0.12346")));
+    }
 
     #[test]
     fn section_name_idx_extraction() {
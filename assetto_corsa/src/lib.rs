@@ -30,8 +30,8 @@ use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::{PathBuf};
-use tracing::info;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
 use crate::error::{Error, ErrorKind, Result};
 use steam;
 
@@ -50,6 +50,13 @@ pub fn get_default_installed_cars_path() -> PathBuf {
     install_path
 }
 
+/// The error returned when Engine Crane doesn't have permission to create files in `path`
+pub(crate) fn write_permission_error(path: &Path) -> Error {
+    Error::new(ErrorKind::PermissionDenied,
+               format!("Engine Crane can't write to {}. Run Engine Crane as administrator or set a Car output path outside of the Assetto Corsa installation in the settings tab",
+                       path.display()))
+}
+
 pub struct Installation {
     base_path: PathBuf
 }
@@ -71,6 +78,30 @@ impl Installation {
         (&self.base_path).join(PathBuf::from_iter(["content", "cars"]))
     }
 
+    /// Check that new cars can be created in the installation cars folder by creating and
+    /// removing a small probe file. Installations under a protected location such as
+    /// `Program Files` fail this check unless Engine Crane is run with elevated permissions
+    pub fn check_cars_folder_writable(&self) -> Result<()> {
+        let car_path = self.get_installed_car_path();
+        if !car_path.is_dir() {
+            return Err(Error::new(ErrorKind::NotInstalled,
+                                  String::from("Assetto Corsa isn't installed")));
+        }
+        let probe_path = car_path.join(format!(".engine-crane-write-check-{}", std::process::id()));
+        match File::create(&probe_path) {
+            Ok(_) => {
+                if let Err(e) = fs::remove_file(&probe_path) {
+                    warn!("Failed to remove {}. {}", probe_path.display(), e.to_string());
+                }
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                Err(write_permission_error(&car_path))
+            }
+            Err(e) => Err(e.into())
+        }
+    }
+
     pub fn get_list_of_installed_cars(&self) -> Result<Vec<PathBuf>> {
         let car_path = self.get_installed_car_path();
         return match car_path.is_dir() {
@@ -100,6 +131,39 @@ impl Installation {
         }
     }
 
+    /// Replace the sound of `target_car` with the sound of `donor_car`. The donor's `.bank` file
+    /// is copied into the target's sfx folder and a GUIDs.txt is written that points the donor's
+    /// sound events at the target car. The donor GUIDs are taken from the donor car's own
+    /// GUIDs.txt if it has one, otherwise from the installation sfx data
+    pub fn copy_sound_from(&self, donor_car: &Path, target_car: &Path) -> Result<()> {
+        let donor_name = car::get_final_path_part(donor_car)?;
+        let target_name = car::get_final_path_part(target_car)?;
+        let donor_bank_path = donor_car.join("sfx").join(format!("{}.bank", donor_name));
+        if !donor_bank_path.is_file() {
+            return Err(Error::new(ErrorKind::InvalidCar,
+                                  format!("{} doesn't have a sound bank", donor_name)));
+        }
+
+        let donor_guids_path = donor_car.join("sfx").join("GUIDs.txt");
+        let guid_lines = match donor_guids_path.is_file() {
+            true => {
+                fs::read_to_string(&donor_guids_path)?.lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| rename_guid_line_car(line, &donor_name, &target_name))
+                    .collect()
+            }
+            false => car::generate_guid_lines(self, &donor_name, &target_name)?
+        };
+
+        let target_sfx_path = target_car.join("sfx");
+        if !target_sfx_path.is_dir() {
+            fs::create_dir(&target_sfx_path)?;
+        }
+        info!("Copying sound from {} into {}", donor_name, target_name);
+        fs::copy(&donor_bank_path, target_sfx_path.join(format!("{}.bank", target_name)))?;
+        car::write_guid_lines(&target_sfx_path.join("GUIDs.txt"), guid_lines)
+    }
+
     pub fn load_sfx_data(&self) -> Result<SfxData> {
         let sfx_guid_file_path = self.get_root_sfx_path()?.join("GUIDs.txt");
         let file = File::open(&sfx_guid_file_path).map_err(|err|{
@@ -107,31 +171,10 @@ impl Installation {
                        String::from(
                            format!("Couldn't open {}. {}", sfx_guid_file_path.display(), err.to_string())))
         })?;
-
-        let mut sfx_data = SfxData {
-            sfx_by_folder_map: HashMap::new(),
-            sfx_bank_map: HashMap::new()
-        };
-        for line_res in BufReader::new(file).lines() {
-            match line_res {
-                Ok(line) => {
-                    let line_data: Vec<_> = line.split_whitespace().collect();
-                    let guid = line_data[0];
-                    let sfx_line = line_data[1];
-                    if sfx_line.starts_with("event") {
-                        let temp: Vec<_> = sfx_line.split(":").collect::<Vec<_>>()[1].split("/").collect();
-                        let folder_name = temp[2];
-                        let data_vec = sfx_data.sfx_by_folder_map.entry(folder_name.to_string()).or_insert_with(|| Vec::new());
-                        data_vec.push(line);
-                    } else if sfx_line.starts_with("bank") {
-                        sfx_data.sfx_bank_map.insert(String::from(sfx_line.split("/").collect::<Vec<_>>()[1]),
-                                                     String::from(guid));
-                    }
-                }
-                Err(_) => { continue }
-            }
-        }
-        Ok(sfx_data)
+        SfxData::from_reader(BufReader::new(file)).map_err(|_| {
+            Error::new(ErrorKind::NotInstalled,
+                       format!("No usable sfx GUID entries found in {}", sfx_guid_file_path.display()))
+        })
     }
 }
 
@@ -173,24 +216,148 @@ pub struct SfxData {
 }
 
 impl SfxData {
+    /// Parse the lines of a GUIDs.txt. Blank lines, comments and lines that don't look like a
+    /// GUID followed by an event or bank path are skipped. Fails only if no usable lines are found
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<SfxData> {
+        let mut sfx_data = SfxData {
+            sfx_by_folder_map: HashMap::new(),
+            sfx_bank_map: HashMap::new()
+        };
+        let mut skipped_lines = 0;
+        for line_res in reader.lines() {
+            let line = match line_res {
+                Ok(line) => line.trim().to_string(),
+                Err(_) => { continue }
+            };
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") || line.starts_with(';') {
+                continue;
+            }
+            let mut line_data = line.split_whitespace();
+            let (guid, sfx_line) = match (line_data.next(), line_data.next()) {
+                (Some(guid), Some(sfx_line)) => (guid, sfx_line),
+                _ => {
+                    skipped_lines += 1;
+                    continue;
+                }
+            };
+            if let Some(event_path) = sfx_line.strip_prefix("event:") {
+                // Event paths are normally of the form /cars/<car name>/<event>
+                let folder_name = match event_path.split('/').nth(2) {
+                    Some(name) if !name.is_empty() => name.to_string(),
+                    _ => event_path.to_string()
+                };
+                sfx_data.sfx_by_folder_map.entry(folder_name).or_default().push(line.clone());
+            } else if let Some(bank_path) = sfx_line.strip_prefix("bank:") {
+                sfx_data.sfx_bank_map.insert(bank_path.trim_start_matches('/').to_string(), guid.to_string());
+            } else {
+                skipped_lines += 1;
+            }
+        }
+        if skipped_lines > 0 {
+            warn!("Skipped {} unrecognised lines in the sfx GUID data", skipped_lines);
+        }
+        match sfx_data.sfx_by_folder_map.is_empty() && sfx_data.sfx_bank_map.is_empty() {
+            true => Err(Error::new(ErrorKind::NotInstalled, "No usable sfx GUID entries found".to_string())),
+            false => Ok(sfx_data)
+        }
+    }
+
+    /// Whether the installation sfx data contains the sound bank for `car_name`
+    pub fn contains_car(&self, car_name: &str) -> bool {
+        self.sfx_bank_map.contains_key(car_name)
+    }
+
     pub fn generate_clone_guid_info(&self, existing_car_name: &str, new_car_name: &str) -> Vec<String> {
         let mut out: Vec<String> = Vec::new();
         if self.sfx_bank_map.contains_key(existing_car_name) {
             out.push(format!("{} bank:/{}",
                              self.sfx_bank_map.get(existing_car_name).unwrap(),
                              new_car_name));
-            for entry in self.sfx_by_folder_map.get(existing_car_name).unwrap() {
-                out.push(entry.replace(existing_car_name, new_car_name));
+            for entry in self.sfx_by_folder_map.get(existing_car_name).into_iter().flatten() {
+                out.push(rename_guid_line_car(entry, existing_car_name, new_car_name));
             }
         }
         out
     }
 }
 
+/// Point a GUIDs.txt line at `new_car_name` if its bank or event path belongs to
+/// `existing_car_name`. Only whole path segments are compared so the entries of other cars whose
+/// names contain `existing_car_name` are left unchanged
+fn rename_guid_line_car(line: &str, existing_car_name: &str, new_car_name: &str) -> String {
+    let mut line_data = line.split_whitespace();
+    let (guid, sfx_line) = match (line_data.next(), line_data.next()) {
+        (Some(guid), Some(sfx_line)) => (guid, sfx_line),
+        _ => return line.to_string()
+    };
+    if let Some(bank_path) = sfx_line.strip_prefix("bank:/") {
+        if bank_path == existing_car_name {
+            return format!("{} bank:/{}", guid, new_car_name);
+        }
+    } else if let Some(event_path) = sfx_line.strip_prefix("event:") {
+        let mut parts: Vec<&str> = event_path.split('/').collect();
+        if parts.len() > 2 && parts[1] == "cars" && parts[2] == existing_car_name {
+            parts[2] = new_car_name;
+            return format!("{} event:{}", guid, parts.join("/"));
+        }
+    }
+    line.to_string()
+}
+
 
 #[cfg(test)]
 mod tests {
-    use crate::{Installation};
+    use std::fs;
+    use crate::{Installation, rename_guid_line_car, SfxData};
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn messy_guids_file_parsed() {
+        let guids = "\r\n\
+            # Sound GUIDs\r\n\
+            {11111111-1111-1111-1111-111111111111} bank:/abarth500\r\n\
+            \r\n\
+            {22222222-2222-2222-2222-222222222222} event:/cars/abarth500/engine_ext\r\n\
+            {33333333-3333-3333-3333-333333333333}\r\n\
+            {44444444-4444-4444-4444-444444444444} event:/ui_sound\r\n\
+            {55555555-5555-5555-5555-555555555555} snapshot:/pause\r\n\
+            {66666666-6666-6666-6666-666666666666} event:/cars/abarth500/gear_int\r\n\
+            // trailing comment";
+        let sfx_data = SfxData::from_reader(guids.as_bytes()).unwrap();
+        assert!(sfx_data.contains_car("abarth500"));
+        assert!(sfx_data.sfx_by_folder_map.contains_key("/ui_sound"));
+        assert_eq!(sfx_data.generate_clone_guid_info("abarth500", "abarth500_swap"), vec![
+            "{11111111-1111-1111-1111-111111111111} bank:/abarth500_swap",
+            "{22222222-2222-2222-2222-222222222222} event:/cars/abarth500_swap/engine_ext",
+            "{66666666-6666-6666-6666-666666666666} event:/cars/abarth500_swap/gear_int"
+        ]);
+
+        assert!(SfxData::from_reader("\n# nothing here\n{1234}\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn guid_lines_only_renamed_for_whole_car_name() {
+        assert_eq!(rename_guid_line_car("{1111} bank:/bmw_m3", "bmw_m3", "bmw_m3_swap"),
+                   "{1111} bank:/bmw_m3_swap");
+        assert_eq!(rename_guid_line_car("{2222} event:/cars/bmw_m3/bmw_m3_horn", "bmw_m3", "bmw_m3_swap"),
+                   "{2222} event:/cars/bmw_m3_swap/bmw_m3_horn");
+        for line in ["{3333} bank:/bmw_m3_e30", "{4444} event:/cars/bmw_m3_e30/engine_ext", "{5555} event:/ui/bmw_m3"] {
+            assert_eq!(rename_guid_line_car(line, "bmw_m3", "bmw_m3_swap"), line);
+        }
+    }
+
+    #[test]
+    fn cars_folder_write_check() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base_path = temp_dir.path().join("ac_cars_folder_write_check");
+        let install = Installation::from_path(base_path.clone());
+        assert_eq!(install.check_cars_folder_writable().unwrap_err().kind(), ErrorKind::NotInstalled);
+
+        let car_path = install.get_installed_car_path();
+        fs::create_dir_all(&car_path).unwrap();
+        install.check_cars_folder_writable().unwrap();
+        assert_eq!(fs::read_dir(&car_path).unwrap().count(), 0);
+    }
 
     #[test]
     fn sfx_test() -> Result<(), String> {
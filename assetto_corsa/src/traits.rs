@@ -66,7 +66,7 @@ pub enum DataInterfaceError {
         #[from]
         source: io::Error
     },
-    #[error("acd error")]
+    #[error("acd error. {source}")]
     AcdError {
         #[from]
         source: AcdError
@@ -75,6 +75,8 @@ pub enum DataInterfaceError {
 
 pub trait _DataInterfaceI {
     fn get_original_file_data(&self, filename: &str) -> DataInterfaceResult<Option<Vec<u8>>>;
+    /// The names of the files that [`_DataInterfaceI::get_original_file_data`] can return
+    fn original_file_names(&self) -> DataInterfaceResult<Vec<String>>;
     fn contains_file(&self, filename: &str) -> bool;
     fn update_file_data(&mut self, filename: &str, data: Vec<u8>);
     fn remove_file(&mut self, filename: &str);
@@ -24,6 +24,7 @@ use std::collections::HashMap;
 use std::default::Default;
 use std::fmt::{Display, Formatter};
 use std::mem;
+use serde_json::{Map, Value};
 use utils::numeric::round_float_to;
 
 
@@ -101,6 +102,23 @@ impl AttributeValue {
             _ => { Err(String::from("Not a number")) }
         }
     }
+
+    pub fn to_json_value(&self) -> Value {
+        match self {
+            AttributeValue::Blob(blob) => {
+                Value::String(blob.iter().map(|byte| format!("{:02x}", byte)).collect())
+            }
+            AttributeValue::Text(t) => { Value::String(t.clone()) }
+            AttributeValue::Number(num) => {
+                match serde_json::Number::from_f64(*num) {
+                    None => Value::String(num.to_string()),
+                    Some(n) => Value::Number(n)
+                }
+            }
+            AttributeValue::False => { Value::Bool(false) }
+            AttributeValue::True => { Value::Bool(true) }
+        }
+    }
 }
 
 impl Display for AttributeValue {
@@ -180,6 +198,10 @@ impl Section {
         self.attributes.keys()
     }
 
+    pub fn to_json_value(&self) -> Value {
+        attributes_and_sections_to_json(&self.attributes, &self.sections)
+    }
+
     fn finalise_sections_if_complete(&mut self) {
         while !self.stack.is_empty() && self.get_current_section().is_complete() {
             self.stack.pop();
@@ -211,6 +233,22 @@ impl Section {
     }
 }
 
+fn attributes_and_sections_to_json(attributes: &HashMap<String, Attribute>,
+                                    sections: &HashMap<String, Section>) -> Value {
+    let mut attribute_map = Map::new();
+    for (name, attr) in attributes {
+        attribute_map.insert(name.clone(), attr.value.to_json_value());
+    }
+    let mut section_map = Map::new();
+    for (name, section) in sections {
+        section_map.insert(name.clone(), section.to_json_value());
+    }
+    let mut out_map = Map::new();
+    out_map.insert(String::from("attributes"), Value::Object(attribute_map));
+    out_map.insert(String::from("sections"), Value::Object(section_map));
+    Value::Object(out_map)
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct Blob {
@@ -255,6 +293,25 @@ impl CarFile {
         Ok(c)
     }
 
+    /// The first `GameVersion` found in the raw data. This is available even if the sections
+    /// weren't laid out as expected
+    pub fn game_version(&self) -> Option<f64> {
+        Self::peek_game_version(&self.byte_stream)
+    }
+
+    /// Scan the raw .car data for the first `GameVersion` number attribute without parsing
+    /// the section structure
+    pub fn peek_game_version(byte_stream: &[u8]) -> Option<f64> {
+        const NAME: &[u8] = b"GameVersion";
+        let mut pattern = vec![TypeIdentifier::Text as u8];
+        pattern.extend((NAME.len() as u32).to_le_bytes());
+        pattern.extend(NAME);
+        pattern.push(TypeIdentifier::Number as u8);
+        let start = byte_stream.windows(pattern.len()).position(|w| w == pattern.as_slice())? + pattern.len();
+        let number_bytes = byte_stream.get(start..start + mem::size_of::<f64>())?;
+        Some(f64::from_le_bytes(number_bytes.try_into().ok()?))
+    }
+
     pub fn get_section(&self, section_name: &str) -> Option<&Section> {
         self.sections.get(section_name)
     }
@@ -263,6 +320,18 @@ impl CarFile {
         self.sections.keys()
     }
 
+    /// Convert the full section tree into a nested JSON value. Each level is represented as an
+    /// object containing an `attributes` object and a `sections` object. Blob data is hex encoded
+    pub fn to_json_value(&self) -> Value {
+        attributes_and_sections_to_json(&self.attributes, &self.sections)
+    }
+
+    pub fn to_json_string_pretty(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.to_json_value()).map_err(|e| {
+            format!("Failed to encode car file as json. {}", e.to_string())
+        })
+    }
+
     fn parse_opening_blob_mark(&mut self) -> Result<(), String> {
         if self.byte_stream[self.current_pos] != TypeIdentifier::BlobMark as u8 {
             return Err(String::from("Stream doesn't open with expected blob mark - is it valid .car data?"))
@@ -389,7 +458,7 @@ impl CarFile {
     }
 
     fn parse_text(&mut self, len: usize) -> String {
-        let str = String::from_utf8(Vec::from(self.peek_byte_slice(len))).expect("Failed to parse UTF-8 text");
+        let str = String::from_utf8_lossy(self.peek_byte_slice(len)).into_owned();
         self.increment_position(len);
         return str;
     }
@@ -448,4 +517,103 @@ impl CarFile {
             self.get_current_section().add_section(section);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::car::CarFile;
+
+    fn push_text_attribute(bytes: &mut Vec<u8>, name: &str, value: &str) {
+        bytes.push(83);
+        bytes.extend((name.len() as u32).to_le_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.push(83);
+        bytes.extend((value.len() as u32).to_le_bytes());
+        bytes.extend(value.as_bytes());
+    }
+
+    fn push_number_attribute(bytes: &mut Vec<u8>, name: &str, value: f64) {
+        bytes.push(83);
+        bytes.extend((name.len() as u32).to_le_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.push(78);
+        bytes.extend(value.to_le_bytes());
+    }
+
+    fn push_section(bytes: &mut Vec<u8>, name: &str, num_children: u32) {
+        bytes.push(83);
+        bytes.extend((name.len() as u32).to_le_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.push(84);
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(num_children.to_le_bytes());
+    }
+
+    #[test]
+    fn game_version_read_without_parsing_sections() -> Result<(), String> {
+        let mut bytes: Vec<u8> = vec![1, 0];
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(1u32.to_le_bytes());
+        push_section(&mut bytes, "Car", 1);
+        push_section(&mut bytes, "Variant", 2);
+        push_text_attribute(&mut bytes, "UID", "abcdef0123");
+        push_number_attribute(&mut bytes, "GameVersion", 2412240000.0);
+        assert_eq!(CarFile::peek_game_version(&bytes), Some(2412240000.0));
+        assert_eq!(CarFile::peek_game_version(&bytes[..bytes.len() - 1]), None);
+
+        let car_file = CarFile::from_bytes(bytes)?;
+        assert_eq!(car_file.game_version(), Some(2412240000.0));
+        Ok(())
+    }
+
+    #[test]
+    fn text_attributes_decoded_as_utf8() -> Result<(), String> {
+        let mut bytes: Vec<u8> = vec![1, 0];
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(1u32.to_le_bytes());
+        push_section(&mut bytes, "Variant", 2);
+        push_text_attribute(&mut bytes, "Name", "Türbo 怪物");
+        bytes.push(83);
+        bytes.extend(6u32.to_le_bytes());
+        bytes.extend("Family".as_bytes());
+        bytes.push(83);
+        bytes.extend(4u32.to_le_bytes());
+        bytes.extend(b"M\xfcll");
+
+        let car_file = CarFile::from_bytes(bytes)?;
+        let variant = car_file.get_section("Car").and_then(|car| car.get_section("Variant"))
+            .ok_or("Missing Car.Variant")?;
+        assert_eq!(variant.get_attribute("Name").unwrap().value.as_str(), "Türbo 怪物");
+        assert_eq!(variant.get_attribute("Family").unwrap().value.as_str(), "M\u{fffd}ll");
+        Ok(())
+    }
+
+    #[test]
+    fn car_file_to_json() -> Result<(), String> {
+        let mut bytes: Vec<u8> = vec![1, 0];
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.push(83);
+        bytes.extend(4u32.to_le_bytes());
+        bytes.extend("Test".as_bytes());
+        bytes.push(78);
+        bytes.extend(1.5f64.to_le_bytes());
+        bytes.push(83);
+        bytes.extend(4u32.to_le_bytes());
+        bytes.extend("Flag".as_bytes());
+        bytes.push(49);
+
+        let car_file = CarFile::from_bytes(bytes)?;
+        assert_eq!(car_file.to_json_value(), json!({
+            "attributes": {},
+            "sections": {
+                "Car": {
+                    "attributes": { "Test": 1.5, "Flag": true },
+                    "sections": {}
+                }
+            }
+        }));
+        Ok(())
+    }
+}
@@ -21,10 +21,14 @@
 use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fmt::{Display, Formatter};
-use std::path::{PathBuf};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
 use rusqlite::{Connection, Row};
+use rusqlite::types::FromSql;
 use sha2::{Sha256, Digest};
-use tracing::info;
+use tracing::{debug, info, warn};
 use serde::{Serialize,Deserialize};
 
 #[cfg(target_os = "windows")]
@@ -35,6 +39,7 @@ use crate::STEAM_GAME_ID;
 
 use utils::numeric::round_float_to;
 use crate::types::{AspirationType, BlockConfig, HeadConfig, Valves};
+use crate::FIRST_AL_RIMA_VERSION_NUM;
 
 pub struct SandboxLookupData {
     pub path: PathBuf,
@@ -47,6 +52,7 @@ impl SandboxLookupData {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct SandboxFinder {
     legacy_userdata_path: PathBuf,
     userdata_path: PathBuf
@@ -78,7 +84,18 @@ impl SandboxFinder {
     }
 
     pub fn find_sandbox_db_for_version(&self, version_num: u64) -> SandboxLookupData {
-        let version =  SandboxVersion::from_version_number(version_num);
+        self.find_sandbox_db(SandboxVersion::from_version_number(version_num))
+    }
+
+    /// Lookup data for every known sandbox db. The db that `version_num` maps to comes first
+    pub fn find_all_sandbox_dbs(&self, version_num: u64) -> Vec<SandboxLookupData> {
+        let preferred = SandboxVersion::from_version_number(version_num);
+        let mut versions = vec![preferred];
+        versions.extend(SandboxVersion::ALL.into_iter().filter(|v| *v != preferred));
+        versions.into_iter().map(|v| self.find_sandbox_db(v)).collect()
+    }
+
+    pub fn find_sandbox_db(&self, version: SandboxVersion) -> SandboxLookupData {
         if version == SandboxVersion::Legacy {
             return SandboxLookupData {
                 path: self.legacy_userdata_path.join(version.get_path()),
@@ -90,9 +107,24 @@ impl SandboxFinder {
             version
         }
     }
+
+    /// The newest variant GameVersion held in any of the sandbox dbs that can be found.
+    /// Engines exported from a newer version of Automation than this won't be in any of them
+    pub fn newest_game_version(&self) -> Option<u64> {
+        SandboxVersion::ALL.into_iter()
+            .map(|v| self.find_sandbox_db(v))
+            .filter(SandboxLookupData::found)
+            .filter_map(|sandbox_lookup| {
+                query_newest_game_version(&sandbox_lookup.path).unwrap_or_else(|e| {
+                    warn!("Couldn't read the newest game version from {}. {}", sandbox_lookup.path.display(), e);
+                    None
+                })
+            })
+            .max()
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SandboxVersion {
     Legacy,
     FourDotTwo,
@@ -100,6 +132,12 @@ pub enum SandboxVersion {
 }
 
 impl SandboxVersion {
+    pub const ALL: [SandboxVersion; 3] = [
+        SandboxVersion::Legacy,
+        SandboxVersion::FourDotTwo,
+        SandboxVersion::Ellisbury
+    ];
+
     pub fn from_version_number(version_num: u64) -> SandboxVersion {
         if version_num >= 2312150000 {
             return SandboxVersion::Ellisbury;
@@ -247,15 +285,68 @@ pub struct EngineV1 {
     pub torque_curve: Vec<f64>,
     pub boost_curve: Vec<f64>,
     pub econ_curve: Vec<f64>,
-    pub econ_eff_curve: Vec<f64>
+    pub econ_eff_curve: Vec<f64>,
+    /// Skipped by serde so that crate engines serialised before these fields existed still load.
+    /// Crate engines store them separately, after the rest of the engine data
+    #[serde(skip)]
+    pub al_rima: AlRimaEngineData
+}
+
+/// The engine fields added to the sandbox db in Automation 4.3 (Al Rima). These are left at
+/// their defaults for engines built in earlier versions of the game
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AlRimaEngineData {
+    /// The kind of charger chosen for each aspiration item, e.g. `AspirationSubType_EBoost`
+    pub aspiration_subtype_1: Option<String>,
+    pub aspiration_subtype_2: Option<String>,
+    /// The boost an e-boosted charger holds across the rev range in bar
+    pub eboost_max_boost: Option<f64>,
+    /// The friction torque at the rpm limit in Nm, as revised in 4.3
+    pub friction_torque: Option<f64>
+}
+
+impl AlRimaEngineData {
+    pub fn load_from_row(row: &Row) -> rusqlite::Result<AlRimaEngineData> {
+        Ok(AlRimaEngineData {
+            aspiration_subtype_1: AlRimaEngineData::optional_column(row, "AspirationSubType_1")?,
+            aspiration_subtype_2: AlRimaEngineData::optional_column(row, "AspirationSubType_2")?,
+            eboost_max_boost: AlRimaEngineData::optional_column(row, "EBoostMaxBoost")?,
+            friction_torque: AlRimaEngineData::optional_column(row, "FrictionTorque")?
+        })
+    }
+
+    /// The value of `column`, or None if it is null or the query didn't select it. Any other
+    /// error, such as the column holding an unexpected type, is returned
+    fn optional_column<T: FromSql>(row: &Row, column: &str) -> rusqlite::Result<Option<T>> {
+        match row.get(column) {
+            Err(rusqlite::Error::InvalidColumnName(_)) => Ok(None),
+            res => res
+        }
+    }
+
+    /// Whether the charger for aspiration item `charger_idx` (starting at 0) is driven by an
+    /// electric motor rather than exhaust gas
+    pub fn is_eboosted(&self, charger_idx: usize) -> bool {
+        let subtype = match charger_idx {
+            0 => self.aspiration_subtype_1.as_deref(),
+            1 => self.aspiration_subtype_2.as_deref(),
+            _ => None
+        };
+        matches!(subtype, Some(subtype) if subtype.starts_with("AspirationSubType_EBoost"))
+    }
 }
 
 impl EngineV1 {
     pub fn load_from_row(row: &Row) -> rusqlite::Result<EngineV1> {
+        let variant_version: u64 = row.get("v_version")?;
+        let al_rima = match variant_version >= FIRST_AL_RIMA_VERSION_NUM as u64 {
+            true => AlRimaEngineData::load_from_row(row)?,
+            false => AlRimaEngineData::default()
+        };
         Ok(EngineV1 {
             uuid: row.get("v_uuid")?,
             family_version: row.get("f_version")?,
-            variant_version: row.get("v_version")?,
+            variant_version,
             family_uuid: row.get("f_uuid")?,
             family_name: row.get("f_name")?,
             variant_name: row.get("v_name")?,
@@ -360,7 +451,8 @@ impl EngineV1 {
             torque_curve: EngineV1::decode_graph_data(row, "TorqueCurve")?,
             boost_curve: EngineV1::decode_graph_data(row, "BoostCurve")?,
             econ_curve: EngineV1::decode_graph_data(row, "EconCurve")?,
-            econ_eff_curve: EngineV1::decode_graph_data(row, "EconEffCurve")?
+            econ_eff_curve: EngineV1::decode_graph_data(row, "EconEffCurve")?,
+            al_rima
         })
     }
 
@@ -376,6 +468,14 @@ impl EngineV1 {
         internal_days_to_year(self.variant_game_days)
     }
 
+    /// The idle rpm chosen in the engine designer, if one has been populated
+    pub fn get_idle_rpm(&self) -> Option<f64> {
+        match self.idle_speed.is_finite() && self.idle_speed > 0.0 {
+            true => Some(self.idle_speed),
+            false => None
+        }
+    }
+
     pub fn get_capacity_cc(&self) -> u32 {
         (self.capacity * 1000.0).round() as u32
     }
@@ -553,6 +653,21 @@ impl Display for EngineV1 {
     }
 }
 
+/// The identifying details of an engine variant stored in a sandbox db
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SandboxEngineListing {
+    pub family_name: String,
+    pub variant_name: String,
+    pub build_year: u16,
+    pub uuid: String
+}
+
+impl Display for SandboxEngineListing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {} ({})", self.family_name, self.variant_name, self.build_year)
+    }
+}
+
 fn _sha256_data_to_string(data: Vec<u8>) -> String {
     let mut hash = String::new();
     for byte in data {
@@ -579,14 +694,114 @@ pub fn load_engines() -> HashMap<String, EngineV1> {
     out_map
 }
 
+/// The number of engine rows kept by the sandbox engine cache
+const ENGINE_CACHE_CAPACITY: usize = 128;
+
+struct EngineCacheEntry {
+    modified: Option<SystemTime>,
+    engine: Option<EngineV1>,
+    last_used: u64
+}
+
+/// Engines loaded from sandbox dbs keyed by db path and engine uuid. The row for a uuid includes
+/// the variant version so a re-exported engine is picked up when the db is written. All entries
+/// for a db are dropped when its modified time changes and the least recently used entry is
+/// dropped once the cache is full
+struct EngineCache {
+    entries: HashMap<(PathBuf, String), EngineCacheEntry>,
+    capacity: usize,
+    use_counter: u64,
+    hits: u64,
+    misses: u64
+}
+
+impl EngineCache {
+    fn new(capacity: usize) -> EngineCache {
+        EngineCache { entries: HashMap::new(), capacity, use_counter: 0, hits: 0, misses: 0 }
+    }
+
+    /// The cached result of loading `uuid` from `db_path`, if there is one for the db as it was
+    /// at `modified`
+    fn get(&mut self, db_path: &Path, uuid: &str, modified: Option<SystemTime>) -> Option<Option<EngineV1>> {
+        self.use_counter += 1;
+        let key = (db_path.to_path_buf(), uuid.to_string());
+        match self.entries.get_mut(&key) {
+            Some(entry) if entry.modified == modified => {
+                entry.last_used = self.use_counter;
+                self.hits += 1;
+                debug!("Sandbox engine cache hit for {} ({} hits, {} misses)", uuid, self.hits, self.misses);
+                Some(entry.engine.clone())
+            }
+            Some(_) => {
+                debug!("{} has changed. Invalidating its cached engines", db_path.display());
+                self.entries.retain(|(path, _), _| path != db_path);
+                self.record_miss(uuid)
+            }
+            None => self.record_miss(uuid)
+        }
+    }
+
+    fn record_miss(&mut self, uuid: &str) -> Option<Option<EngineV1>> {
+        self.misses += 1;
+        debug!("Sandbox engine cache miss for {} ({} hits, {} misses)", uuid, self.hits, self.misses);
+        None
+    }
+
+    fn insert(&mut self, db_path: &Path, uuid: &str, modified: Option<SystemTime>, engine: Option<EngineV1>) {
+        if self.entries.len() >= self.capacity {
+            let least_recently_used = self.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone());
+            if let Some(key) = least_recently_used {
+                self.entries.remove(&key);
+            }
+        }
+        self.use_counter += 1;
+        self.entries.insert((db_path.to_path_buf(), uuid.to_string()),
+                            EngineCacheEntry { modified, engine, last_used: self.use_counter });
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn engine_cache() -> &'static Mutex<EngineCache> {
+    static ENGINE_CACHE: OnceLock<Mutex<EngineCache>> = OnceLock::new();
+    ENGINE_CACHE.get_or_init(|| Mutex::new(EngineCache::new(ENGINE_CACHE_CAPACITY)))
+}
+
+/// Drop every engine held by the cache used by [`load_engine_by_uuid`]
+pub fn clear_engine_cache() {
+    if let Ok(mut cache) = engine_cache().lock() {
+        cache.clear();
+    }
+}
+
+/// Load the engine with `uuid` from the sandbox db described by `sandbox_lookup`. Results are
+/// cached for as long as the db file isn't modified so that repeated loads don't query the db
 pub fn load_engine_by_uuid(uuid: &str, sandbox_lookup: SandboxLookupData) -> Result<Option<EngineV1>, String> {
     if !sandbox_lookup.found() {
         return Err(format!("No sandbox db file available for {}. Expected to find it at {}",
                            sandbox_lookup.version.as_str(), sandbox_lookup.path.display()));
     }
     let db_path = sandbox_lookup.path;
-    info!("Loading {} from {}", uuid, PathBuf::from(&db_path).display());
-    let conn = Connection::open(&db_path).map_err(|e|{
+    let modified = fs::metadata(&db_path).and_then(|metadata| metadata.modified()).ok();
+    // The lock isn't held while the db is queried. Two threads loading the same engine at once
+    // will both query the db, which is harmless
+    if let Ok(mut cache) = engine_cache().lock() {
+        if let Some(engine) = cache.get(&db_path, uuid, modified) {
+            return Ok(engine);
+        }
+    }
+    let engine = query_engine_by_uuid(uuid, &db_path)?;
+    if let Ok(mut cache) = engine_cache().lock() {
+        cache.insert(&db_path, uuid, modified, engine.clone());
+    }
+    Ok(engine)
+}
+
+fn query_engine_by_uuid(uuid: &str, db_path: &Path) -> Result<Option<EngineV1>, String> {
+    info!("Loading {} from {}", uuid, db_path.display());
+    let conn = Connection::open(db_path).map_err(|e|{
         format!("Failed to connect to {}. {}", db_path.to_string_lossy(), e.to_string())
     })?;
     let mut stmt = conn.prepare(load_engine_by_uuid_query()).map_err(|e|{
@@ -604,6 +819,115 @@ pub fn load_engine_by_uuid(uuid: &str, sandbox_lookup: SandboxLookupData) -> Res
     Ok(None)
 }
 
+fn query_newest_game_version(db_path: &Path) -> Result<Option<u64>, String> {
+    let conn = Connection::open(db_path).map_err(|e|{
+        format!("Failed to connect to {}. {}", db_path.display(), e)
+    })?;
+    conn.query_row(r#"select max(GameVersion) from "Variants""#, [], |row| row.get(0)).map_err(|e|{
+        format!("Failed to query sandbox db for the newest game version. {}", e)
+    })
+}
+
+/// An engine found by [`search_sandboxes_for_engine`]
+pub struct SandboxEngineMatch {
+    pub engine: EngineV1,
+    /// The sandbox db that the engine was loaded from
+    pub version: SandboxVersion,
+    searched: Vec<String>
+}
+
+impl SandboxEngineMatch {
+    /// A description of each sandbox db that was checked and the engine version it contained
+    pub fn searched_dbs(&self) -> String {
+        self.searched.join("; ")
+    }
+}
+
+/// Search every sandbox db known to `sandbox_finder` for the engine with `uuid`.
+/// The same engine can be present in more than one db with different data so the copy whose
+/// variant GameVersion matches `variant_version` is preferred. If no copy matches then the one
+/// in the db that `variant_version` maps to is used, followed by any other copy found
+pub fn search_sandboxes_for_engine(uuid: &str,
+                                   variant_version: u64,
+                                   sandbox_finder: &SandboxFinder) -> Result<SandboxEngineMatch, String> {
+    let mut searched = Vec::new();
+    let mut candidates = Vec::new();
+    for sandbox_lookup in sandbox_finder.find_all_sandbox_dbs(variant_version) {
+        let db_description = format!("{} ({})", sandbox_lookup.version.get_path(), sandbox_lookup.version);
+        if !sandbox_lookup.found() {
+            searched.push(format!("{} not present", db_description));
+            continue;
+        }
+        let version = sandbox_lookup.version;
+        match load_engine_by_uuid(uuid, sandbox_lookup) {
+            Ok(Some(engine)) => {
+                searched.push(format!("{} has engine version {}", db_description, engine.variant_version));
+                candidates.push((version, engine));
+            }
+            Ok(None) => searched.push(format!("{} doesn't contain the engine", db_description)),
+            Err(e) => searched.push(format!("{} couldn't be read. {}", db_description, e))
+        }
+    }
+
+    let idx = match candidates.iter().position(|(_, engine)| engine.variant_version == variant_version) {
+        Some(idx) => idx,
+        None => {
+            if candidates.is_empty() {
+                return Err(format!("No engine found with uuid {}. Checked: {}", uuid, searched.join("; ")));
+            }
+            warn!("No copy of engine {} with version {} found. Using the copy from {}. Checked: {}",
+                  uuid, variant_version, candidates[0].0.get_path(), searched.join("; "));
+            0
+        }
+    };
+    let (version, engine) = candidates.swap_remove(idx);
+    info!("Using engine {} from {}", uuid, version.get_path());
+    Ok(SandboxEngineMatch { engine, version, searched })
+}
+
+/// List the engine variants in a sandbox db that have results and curve data available,
+/// i.e. those that can be loaded with [`load_engine_by_uuid`]
+pub fn list_engines(sandbox_lookup: &SandboxLookupData) -> Result<Vec<SandboxEngineListing>, String> {
+    if !sandbox_lookup.found() {
+        return Err(format!("No sandbox db file available for {}. Expected to find it at {}",
+                           sandbox_lookup.version.as_str(), sandbox_lookup.path.display()));
+    }
+    let db_path = &sandbox_lookup.path;
+    info!("Listing engines in {}", db_path.display());
+    let conn = Connection::open(db_path).map_err(|e|{
+        format!("Failed to connect to {}. {}", db_path.display(), e.to_string())
+    })?;
+    let mut stmt = conn.prepare(list_engines_query()).map_err(|e|{
+        format!("Failed to prepare engine list statement. {}", e.to_string())
+    })?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SandboxEngineListing {
+            family_name: row.get("f_name")?,
+            variant_name: row.get("v_name")?,
+            build_year: internal_days_to_year(row.get("v_days")?),
+            uuid: row.get("v_uuid")?
+        })
+    }).map_err(|e|{
+        format!("Failed to query sandbox db for engine list. {}", e.to_string())
+    })?;
+    let mut engines = Vec::new();
+    for row in rows {
+        engines.push(row.map_err(|err|{
+            format!("Failed to read sandbox.db. {}", err.to_string())
+        })?);
+    }
+    Ok(engines)
+}
+
+fn list_engines_query() -> &'static str {
+    r#"select f.name as f_name, v.name as v_name, v.InternalDays as v_days, v.uid as v_uuid
+    from "Variants" as v
+    join "Families" as f on v.FUID = f.UID
+    join "EngineResults" as r using(uid)
+    join "EngineCurves" as c using(uid)
+    order by f.name, v.name;"#
+}
+
 fn load_all_engines_query() -> &'static str {
     r#"select f.GameVersion as f_version, v.GameVersion as v_version, f.uuid as f.uuid, f.name as f_name, f.InternalDays as f_days, f.Bore as MaxBore, f.Stroke as MaxStroke, f.*,
     v.uid as v_uuid, v.name as v_name, v.InternalDays as v_days, v.Bore as VBore, v.Stroke as VStroke, v.*,
@@ -769,7 +1093,7 @@ fn sandbox_dir_ellisbury() -> Vec<&'static str> {
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
-    use crate::sandbox::{get_default_legacy_db_path, get_default_db_path_4_2, SandboxVersion, SandboxLookupData};
+    use crate::sandbox::{AlRimaEngineData, get_default_legacy_db_path, get_default_db_path_4_2, SandboxFinder, SandboxVersion, SandboxLookupData};
 
     #[test]
     fn get_sandbox_db_path() -> Result<(), String> {
@@ -792,4 +1116,137 @@ mod tests {
         println!("Sandbox path is {}", path.display());
         Ok(())
     }
+
+    #[test]
+    fn deduced_sandbox_db_is_searched_first() {
+        let finder = SandboxFinder::default();
+        let versions: Vec<SandboxVersion> = finder.find_all_sandbox_dbs(2312150000).iter().map(|l| l.version).collect();
+        assert_eq!(versions, vec![SandboxVersion::Ellisbury, SandboxVersion::Legacy, SandboxVersion::FourDotTwo]);
+        let versions: Vec<SandboxVersion> = finder.find_all_sandbox_dbs(2111220000).iter().map(|l| l.version).collect();
+        assert_eq!(versions, vec![SandboxVersion::FourDotTwo, SandboxVersion::Legacy, SandboxVersion::Ellisbury]);
+    }
+
+    #[test]
+    fn engine_cache_invalidated_when_db_modified() {
+        use std::path::Path;
+        use std::time::{Duration, SystemTime};
+        use crate::sandbox::EngineCache;
+
+        let mut cache = EngineCache::new(2);
+        let db = Path::new("sandbox.db");
+        let modified = Some(SystemTime::UNIX_EPOCH);
+        assert!(cache.get(db, "A", modified).is_none());
+        cache.insert(db, "A", modified, None);
+        cache.insert(db, "B", modified, None);
+        assert_eq!(cache.get(db, "A", modified).map(|engine| engine.is_none()), Some(true));
+        assert_eq!((cache.hits, cache.misses), (1, 1));
+
+        // B is the least recently used so makes way for C
+        cache.insert(Path::new("other.db"), "C", modified, None);
+        assert!(cache.get(db, "B", modified).is_none());
+        assert!(cache.get(Path::new("other.db"), "C", modified).is_some());
+
+        let later = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+        assert!(cache.get(db, "A", later).is_none());
+        assert!(!cache.entries.keys().any(|(path, _)| path == db));
+        assert!(cache.get(Path::new("other.db"), "C", modified).is_some());
+    }
+
+    #[test]
+    fn missing_engine_error_lists_checked_dbs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let empty_path = temp_dir.path().join("no-sandbox-dbs");
+        let mut finder = SandboxFinder::default();
+        finder.set_userdata_path(empty_path.clone());
+        finder.set_legacy_userdata_path(empty_path);
+        let err = crate::sandbox::search_sandboxes_for_engine("ABCDEF", 2111220000, &finder).err().unwrap();
+        for version in SandboxVersion::ALL {
+            assert!(err.contains(&format!("{} ({}) not present", version.get_path(), version)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn newest_game_version_across_sandbox_dbs() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let userdata_path = temp_dir.path().join("newest-game-version");
+        let legacy_path = userdata_path.join("legacy");
+        std::fs::create_dir_all(&legacy_path).map_err(|e| e.to_string())?;
+        let mut finder = SandboxFinder::default();
+        finder.set_userdata_path(userdata_path.clone());
+        finder.set_legacy_userdata_path(legacy_path.clone());
+        for (path, versions) in [
+            (legacy_path.join(SandboxVersion::Legacy.get_path()), "(2008020000), (2104230000)"),
+            (userdata_path.join(SandboxVersion::FourDotTwo.get_path()), "(2206210000), (2111220000)")
+        ] {
+            let _ = std::fs::remove_file(&path);
+            let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+            conn.execute_batch(&format!(
+                r#"create table "Variants" (GameVersion integer); insert into "Variants" values {};"#, versions
+            )).map_err(|e| e.to_string())?;
+        }
+        let newest = finder.newest_game_version();
+        assert_eq!(newest, Some(2206210000));
+        Ok(())
+    }
+
+    #[test]
+    fn list_engines_with_results() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let path = temp_dir.path().join("list-engines-test.db");
+        {
+            let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+            conn.execute_batch(r#"
+                create table "Families" (UID text, name text);
+                create table "Variants" (UID text, FUID text, name text, InternalDays integer);
+                create table "EngineResults" (uid text);
+                create table "EngineCurves" (uid text);
+                insert into "Families" values ('F1', 'Straight Six');
+                insert into "Variants" values ('V2', 'F1', 'Turbo', 18000), ('V1', 'F1', 'Base', 10800), ('V3', 'F1', 'Unbuilt', 0);
+                insert into "EngineResults" values ('V1'), ('V2');
+                insert into "EngineCurves" values ('V1'), ('V2');
+            "#).map_err(|e| e.to_string())?;
+        }
+        let engines = crate::sandbox::list_engines(
+            &SandboxLookupData { path: path.clone(), version: SandboxVersion::Ellisbury }
+        );
+        let names: Vec<String> = engines?.iter().map(|e| format!("{} {}", e, e.uuid)).collect();
+        assert_eq!(names, vec!["Straight Six - Base (1970) V1", "Straight Six - Turbo (1990) V2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn al_rima_fields_read_from_4_3_sandbox() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let path = temp_dir.path().join("al-rima-sandbox-test.db");
+        {
+            let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+            conn.execute_batch(include_str!("test-data/al_rima_sandbox.sql")).map_err(|e| e.to_string())?;
+        }
+        let load = |uuid: &str| crate::sandbox::load_engine_by_uuid(
+            uuid, SandboxLookupData { path: path.clone(), version: SandboxVersion::Ellisbury }
+        );
+        let al_rima_engine = load("V43");
+        let older_engine = load("V42");
+
+        let al_rima_engine = al_rima_engine?.unwrap();
+        assert_eq!(al_rima_engine.rpm_curve, vec![1000.0, 2500.0, 4000.0, 5500.0, 7000.0]);
+        assert_eq!(al_rima_engine.al_rima.aspiration_subtype_1.as_deref(), Some("AspirationSubType_EBoost_Name"));
+        assert_eq!(al_rima_engine.al_rima.eboost_max_boost, Some(1.2));
+        assert_eq!(al_rima_engine.al_rima.friction_torque, Some(64.0));
+        assert!(al_rima_engine.al_rima.is_eboosted(0));
+        assert!(!al_rima_engine.al_rima.is_eboosted(1));
+
+        // Only engines built in 4.3 or later have the 4.3 columns read
+        let older_engine = older_engine?.unwrap();
+        assert_eq!(older_engine.al_rima, AlRimaEngineData::default());
+
+        // A value of the wrong type is an error rather than being read as missing
+        {
+            let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+            conn.execute("update Variants set EBoostMaxBoost = 'high' where UID = 'V43'", [])
+                .map_err(|e| e.to_string())?;
+        }
+        assert!(crate::sandbox::query_engine_by_uuid("V43", &path).is_err());
+        Ok(())
+    }
 }
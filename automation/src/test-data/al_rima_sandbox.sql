@@ -0,0 +1,14 @@
+-- Engine tables of a sandbox db written by Automation 4.3 (Al Rima), trimmed to the columns
+-- engine-crane reads. V43 was built in 4.3 with an e-boosted turbo. V42 is an older
+-- variant of the same family whose rows happen to contain values in the 4.3 columns
+create table "Families" (UID text, Name text, GameVersion integer, InternalDays integer, Bore real, Stroke real, BlockConfig text, BlockMaterial text, BlockType text, Head text, HeadMaterial text, Valves text, QualityFamily integer);
+create table "Variants" (UID text, FUID text, Name text, GameVersion integer, InternalDays integer, Bore real, Stroke real, VVL text, Crank text, Conrods text, Pistons text, VVT text, AspirationType text, IntercoolerSetting real, FuelSystemType text, FuelSystem text, FuelType text, FuelLeaded integer, IntakeManifold text, Intake text, Headers text, ExhaustCount text, ExhaustBypassValves text, Cat text, Muffler1 text, Muffler2 text, Capacity real, Compression real, CamProfileSetting real, VVLCamProfileSetting real, AFR real, AFRLean real, RPMLimit real, IgnitionTimingSetting real, ExhaustDiameter real, QualityBottomEnd integer, QualityTopEnd integer, QualityAspiration integer, QualityFuelSystem integer, QualityExhaust integer, AspirationSetup text, AspirationItemOption_1 text, AspirationItemOption_2 text, AspirationItemSubOption_1 text, AspirationItemSubOption_2 text, AspirationBoostControl text, ChargerSize_1 real, ChargerTune_1 real, ChargerMaxBoost_1 real, TurbineSize_1 real, AspirationSubType_1 text, AspirationSubType_2 text, EBoostPower real, EBoostMaxBoost real);
+create table "EngineResults" (uid text, AdjustedAFR real, AverageCruiseEcon real, CoolingRequired real, Econ real, EconEff real, MinEcon real, WorstEcon real, Emissions real, EngineeringCost real, EngineeringTime real, Idle real, IdleSpeed real, MTTF real, ManHours real, MaterialCost real, Noise real, PeakBoost real, PeakBoostRPM real, PerformanceIndex real, RON real, ReliabilityPostEngineering real, Responsiveness real, ServiceCost real, Smoothness real, ToolingCosts real, TotalCost real, Weight real, PeakTorqueRPM real, PeakTorque real, PeakPower real, PeakPowerRPM real, MaxRPM real, FrictionTorque real);
+create table "EngineCurves" (uid text, RPMCurve blob, PowerCurve blob, TorqueCurve blob, BoostCurve blob, EconCurve blob, EconEffCurve blob);
+insert into "Families" values ('F43', 'Al Rima I4', 2412240000, 30600, 86.0, 86.0, 'EngBlock_Inl4_Name', 'Material_Aluminium_Name', 'BlockType_Normal_Name', 'Head_DuelOHC_Name', 'Material_Aluminium_Name', 'ValveCount_4_Name', 2);
+insert into "Variants" values ('V43', 'F43', 'E-Turbo', 2412240000, 30600, 86.0, 86.0, 'NoOption_Name', 'Crank_Forged_Name', 'Conrods_HeavyDuty_Name', 'Pistons_Forged_Name', 'VVT_Intake_Exhaust_Name', 'Aspiration_Turbo_Name', 0.5, 'FuelSystemType_Injection_Name', 'FuelSystem_Direct_Name', 'Fuel_Gasoline_Name', 0, 'IntakeManifold_Standard_Name', 'Intake_Standard_Name', 'Headers_Tubular_Name', 'ExhaustCount_Single_Name', 'NoOption_Name', 'Cat_ThreeWay_Name', 'Muffler_Reverse_Name', 'NoOption_Name', 1.998, 9.5, 50.0, 0.0, 12.5, 14.7, 7000.0, 50.0, 70.0, 1, 2, 3, 0, -1, 'Turbo_Single_Name', 'Turbo_BallBearing_Name', 'NoOption_Name', 'NoOption_Name', 'NoOption_Name', 'BoostControl_Electronic_Name', 55.0, 50.0, 1.2, 50.0, 'AspirationSubType_EBoost_Name', null, 12.0, 1.2);
+insert into "Variants" values ('V42', 'F43', 'Turbo', 2312150000, 28800, 86.0, 86.0, 'NoOption_Name', 'Crank_Forged_Name', 'Conrods_HeavyDuty_Name', 'Pistons_Forged_Name', 'VVT_Intake_Exhaust_Name', 'Aspiration_Turbo_Name', 0.5, 'FuelSystemType_Injection_Name', 'FuelSystem_Direct_Name', 'Fuel_Gasoline_Name', 0, 'IntakeManifold_Standard_Name', 'Intake_Standard_Name', 'Headers_Tubular_Name', 'ExhaustCount_Single_Name', 'NoOption_Name', 'Cat_ThreeWay_Name', 'Muffler_Reverse_Name', 'NoOption_Name', 1.998, 9.5, 50.0, 0.0, 12.5, 14.7, 7000.0, 50.0, 70.0, 1, 2, 3, 0, -1, 'Turbo_Single_Name', 'Turbo_BallBearing_Name', 'NoOption_Name', 'NoOption_Name', 'NoOption_Name', 'BoostControl_Electronic_Name', 55.0, 50.0, 1.2, 50.0, 'AspirationSubType_EBoost_Name', null, 12.0, 1.2);
+insert into "EngineResults" values ('V43', 12.3, 8.1, 30.0, 300.0, 0.3, 250.0, 450.0, 80.0, 10000.0, 20.0, 0.8, 900.0, 60000.0, 40.0, 2000.0, 20.0, 1.2, 4000.0, 60.0, 98.0, 70.0, 40.0, 300.0, 50.0, 100000.0, 3000.0, 140.0, 4000.0, 382.0, 220.0, 7000.0, 7000.0, 64.0);
+insert into "EngineCurves" values ('V43', X'00000500000000000000000000000000000000000000000000408F4000000000000000000000000000000088A34000000000000000000000000000000040AF400000000000000000000000000000007CB54000000000000000000000000000000058BB40', X'00000500000000000000000000000000000000000000000000003E40000000000000000000000000000000C05740000000000000000000000000000000006440000000000000000000000000000000A06940000000000000000000000000000000806B40', X'00000500000000000000000000000000000000000000000000207240000000000000000000000000000000D07640000000000000000000000000000000E07740000000000000000000000000000000407640000000000000000000000000000000C07240', X'0000050000000000000000000000000000000000333333333333F33F00000000000000000000333333333333F33F00000000000000000000333333333333F33F00000000000000000000333333333333F33F00000000000000000000333333333333F33F', X'00000500000000000000000000000000000000000000000000007940000000000000000000000000000000C07240000000000000000000000000000000807140000000000000000000000000000000207240000000000000000000000000000000007440', X'00000500000000000000000000000000000000009A9999999999C93F00000000000000000000EC51B81E85EBD13F000000000000000000007B14AE47E17AD43F00000000000000000000333333333333D33F0000000000000000000048E17A14AE47D13F');
+insert into "EngineResults" values ('V42', 12.3, 8.1, 30.0, 300.0, 0.3, 250.0, 450.0, 80.0, 10000.0, 20.0, 0.8, 900.0, 60000.0, 40.0, 2000.0, 20.0, 1.2, 4000.0, 60.0, 98.0, 70.0, 40.0, 300.0, 50.0, 100000.0, 3000.0, 140.0, 4000.0, 358.0, 215.0, 7000.0, 7000.0, 64.0);
+insert into "EngineCurves" values ('V42', X'00000500000000000000000000000000000000000000000000408F4000000000000000000000000000000088A34000000000000000000000000000000040AF400000000000000000000000000000007CB54000000000000000000000000000000058BB40', X'00000500000000000000000000000000000000000000000000003440000000000000000000000000000000805140000000000000000000000000000000C06240000000000000000000000000000000006940000000000000000000000000000000E06A40', X'00000500000000000000000000000000000000000000000000C06740000000000000000000000000000000B07040000000000000000000000000000000607640000000000000000000000000000000B07540000000000000000000000000000000507240', X'00000500000000000000000000000000000000009A9999999999B93F00000000000000000000333333333333E33F00000000000000000000333333333333F33F00000000000000000000333333333333F33F000000000000000000009A9999999999F13F', X'00000500000000000000000000000000000000000000000000007940000000000000000000000000000000C07240000000000000000000000000000000807140000000000000000000000000000000207240000000000000000000000000000000007440', X'00000500000000000000000000000000000000009A9999999999C93F00000000000000000000EC51B81E85EBD13F000000000000000000007B14AE47E17AD43F00000000000000000000333333333333D33F0000000000000000000048E17A14AE47D13F');
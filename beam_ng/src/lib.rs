@@ -22,7 +22,7 @@
 pub mod jbeam;
 
 use std::collections::hash_map::Keys;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::fs::File;
 use std::io::Read;
@@ -31,16 +31,17 @@ use serde_hjson::{Map, Value};
 use steam;
 use tracing::{debug, info, warn};
 
+use directories::BaseDirs;
+
 #[cfg(target_os = "windows")]
-use {
-    directories::BaseDirs,
-    parselnk::Lnk
-};
+use parselnk::Lnk;
 
 
 pub const STEAM_GAME_NAME: &str = "BeamNG.drive";
 pub const STEAM_GAME_ID: i64 = 284160;
 pub const AUTOMATION_STEAM_GAME_ID: i64 = 293760;
+const MAIN_ENGINE_KEY: &str = "Camso_Engine";
+const MAIN_ENGINE_SECTION: &str = "mainEngine";
 
 #[cfg(target_os = "windows")]
 pub fn get_default_mod_path() -> PathBuf {
@@ -69,29 +70,158 @@ pub fn get_default_mod_path() -> PathBuf {
         false => {}
     }
     mod_path_buf.push("mods");
+    info!("Using BeamNG mod path {}", mod_path_buf.display());
     mod_path_buf
 }
 
+/// The folders that BeamNG mods may be stored in. On Windows this is only ever the
+/// [`get_default_mod_path`]
+#[cfg(target_os = "windows")]
+pub fn get_candidate_mod_paths() -> Vec<PathBuf> {
+    vec![get_default_mod_path()]
+}
+
+/// Returns the first of [`get_candidate_mod_paths`] that exists. If none of them do then the mod
+/// folder in the Automation proton prefix is returned
 #[cfg(target_os = "linux")]
 pub fn get_default_mod_path() -> PathBuf {
-    let mut mod_path_buf: PathBuf = steam::get_wine_prefix_dir(AUTOMATION_STEAM_GAME_ID);
-    for path in ["users", "steamuser", "AppData", "Local", "BeamNG.drive", "mods"] {
-        mod_path_buf.push(path);
+    match get_candidate_mod_paths().into_iter().find(|path| path.is_dir()) {
+        Some(path) => {
+            info!("Using BeamNG mod path {}", path.display());
+            path
+        }
+        None => {
+            let path = get_proton_mod_path(AUTOMATION_STEAM_GAME_ID);
+            info!("No BeamNG mod folder found. Defaulting to {}", path.display());
+            path
+        }
     }
-    mod_path_buf
 }
 
+/// The folders that BeamNG mods may be stored in, in the order they are checked by
+/// [`get_default_mod_path`]. These are the BeamNG proton prefix, the Automation proton prefix and
+/// the data folder of a native Linux BeamNG install
+#[cfg(target_os = "linux")]
+pub fn get_candidate_mod_paths() -> Vec<PathBuf> {
+    let mut candidates = vec![
+        get_proton_mod_path(STEAM_GAME_ID),
+        get_proton_mod_path(AUTOMATION_STEAM_GAME_ID)
+    ];
+    if let Some(base_dirs) = BaseDirs::new() {
+        candidates.push(base_dirs.data_dir().join(STEAM_GAME_NAME).join("mods"));
+    }
+    candidates
+}
+
+#[cfg(target_os = "linux")]
+fn get_proton_mod_path(game_id: i64) -> PathBuf {
+    steam::get_wine_appdata_local_dir(game_id).join(STEAM_GAME_NAME).join("mods")
+}
+
+/// How many levels of subfolders below the mod folder are searched for mods by default. One level
+/// picks up BeamNG's `repo` folder and the mods in the `unpacked` folder
+pub const DEFAULT_MOD_SEARCH_DEPTH: usize = 1;
+
 pub fn get_mod_list_in(path: &PathBuf) -> Vec<PathBuf> {
+    get_mod_list_in_with_depth(path, DEFAULT_MOD_SEARCH_DEPTH)
+}
+
+/// Find mod archives and unpacked mod folders in `path` and up to `max_depth` levels of
+/// subfolders below it. A `max_depth` of 0 only searches `path` itself
+pub fn get_mod_list_in_with_depth(path: &Path, max_depth: usize) -> Vec<PathBuf> {
     info!("Looking for BeamNG mods in {}", path.display());
-    read_mods_in_path(&path)
+    match ModFolderSummary::from_path(path) {
+        Ok(summary) => info!("{} contains {}", path.display(), summary),
+        Err(e) => warn!("Couldn't summarise contents of {}. {}", path.display(), e.to_string())
+    }
+    let mut mods = Vec::new();
+    find_mods_in_path(path, max_depth, &mut mods);
+    mods
+}
+
+/// The name of the mod index file BeamNG writes into the root of its `mods` folder
+pub const MOD_DB_FILENAME: &str = "db.json";
+
+/// A summary of what a configured mod path contains, used to explain an empty mod list
+#[derive(Debug, Default)]
+pub struct ModFolderSummary {
+    pub archive_count: usize,
+    pub other_file_count: usize,
+    pub has_mod_db: bool,
+    pub subdirectories: Vec<PathBuf>
+}
+
+impl ModFolderSummary {
+    pub fn from_path(path: &Path) -> std::io::Result<ModFolderSummary> {
+        let mut summary = ModFolderSummary::default();
+        for entry in fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                summary.subdirectories.push(entry_path);
+            } else if is_mod_archive(&entry_path) {
+                summary.archive_count += 1;
+            } else if entry_path.file_name().map_or(false, |name| name.eq(MOD_DB_FILENAME)) {
+                summary.has_mod_db = true;
+            } else {
+                summary.other_file_count += 1;
+            }
+        }
+        summary.subdirectories.sort();
+        Ok(summary)
+    }
+
+    /// Find the subfolder that most likely contains the mod zips. BeamNG stores downloaded
+    /// repository mods in `mods/repo` so that is preferred over any other subfolder
+    pub fn find_mod_subfolder(&self) -> Option<PathBuf> {
+        let dirs_with_zips: Vec<&PathBuf> = self.subdirectories.iter().filter(|dir| {
+            !read_mods_in_path(dir).is_empty()
+        }).collect();
+        dirs_with_zips.iter()
+            .find(|dir| dir.file_name().map_or(false, |name| name.eq("repo")))
+            .or(dirs_with_zips.first())
+            .map(|dir| (*dir).clone())
+    }
+}
+
+impl std::fmt::Display for ModFolderSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} mod archive(s), {} other file(s), {} folder(s){}",
+               self.archive_count,
+               self.other_file_count,
+               self.subdirectories.len(),
+               if self.has_mod_db { " and a mod index" } else { "" })
+    }
+}
+
+/// Check whether `path` looks like a BeamNG mod index folder rather than the folder that holds
+/// the mod zips. Returns a message describing the likely correct folder if so
+pub fn check_mod_path(path: &Path) -> Option<String> {
+    let summary = ModFolderSummary::from_path(path).ok()?;
+    if summary.archive_count > 0 {
+        return None;
+    }
+    match summary.find_mod_subfolder() {
+        Some(suggested_path) => {
+            let reason = match summary.has_mod_db {
+                true => "contains the BeamNG mod index but no mod zips",
+                false => "contains no mod zips"
+            };
+            Some(format!("{} {}. Try setting the path to {}",
+                         path.display(), reason, suggested_path.display()))
+        }
+        None if summary.has_mod_db => {
+            Some(format!("{} contains the BeamNG mod index but no mod zips. Mods may be unpacked or stored in another folder",
+                         path.display()))
+        }
+        None => None
+    }
 }
 
 pub fn get_mod_list() -> Vec<PathBuf> {
     let mod_dir = get_default_mod_path();
     return match mod_dir.is_dir() {
         true => {
-            info!("Looking for BeamNG mods in {}", mod_dir.display());
-            read_mods_in_path(&mod_dir)
+            get_mod_list_in(&mod_dir)
         }
         false => {
             warn!("The provided BeamNG mod path {} does not exist", mod_dir.display());
@@ -100,6 +230,16 @@ pub fn get_mod_list() -> Vec<PathBuf> {
     }
 }
 
+/// The file extensions of the archive formats that mods can be packaged in
+pub const MOD_ARCHIVE_EXTENSIONS: [&str; 2] = ["zip", "7z"];
+
+pub fn is_mod_archive(path: &Path) -> bool {
+    match path.extension() {
+        None => false,
+        Some(ext) => MOD_ARCHIVE_EXTENSIONS.iter().any(|archive_ext| ext.eq_ignore_ascii_case(archive_ext))
+    }
+}
+
 fn read_mods_in_path(path: &PathBuf) -> Vec<PathBuf> {
     let dir_entries = match fs::read_dir(path) {
         Ok(entry_list) => entry_list,
@@ -109,18 +249,10 @@ fn read_mods_in_path(path: &PathBuf) -> Vec<PathBuf> {
     dir_entries.filter_map(|e| {
         match e {
             Ok(dir_entry) => {
-                if dir_entry.path().is_file() {
-                    match dir_entry.path().extension() {
-                        Some(ext) => {
-                            if ext.ne("zip") {
-                                return None
-                            }
-                        },
-                        None => return None
-                    }
-                    Some(dir_entry.path())
-                } else {
-                    None
+                let entry_path = dir_entry.path();
+                match entry_path.is_file() && is_mod_archive(&entry_path) {
+                    true => Some(entry_path),
+                    false => None
                 }
             },
             _ => None
@@ -128,26 +260,321 @@ fn read_mods_in_path(path: &PathBuf) -> Vec<PathBuf> {
     }).collect()
 }
 
+fn find_mods_in_path(path: &Path, remaining_depth: usize, mods: &mut Vec<PathBuf>) {
+    let dir_entries = match fs::read_dir(path) {
+        Ok(entry_list) => entry_list,
+        Err(e) => {
+            warn!("Couldn't read {}. {}", path.display(), e.to_string());
+            return;
+        }
+    };
+
+    for dir_entry in dir_entries.flatten() {
+        let entry_path = dir_entry.path();
+        if entry_path.is_file() {
+            if is_mod_archive(&entry_path) {
+                mods.push(entry_path);
+            }
+        } else if entry_path.is_dir() {
+            if is_unpacked_mod_dir(&entry_path) {
+                mods.push(entry_path);
+            } else if remaining_depth > 0 {
+                find_mods_in_path(&entry_path, remaining_depth - 1, mods);
+            }
+        }
+    }
+}
+
+/// Check whether `path` is the root of an unpacked mod. This is a folder that holds an info.json
+/// and .jbeam files, either directly or within one of its `vehicles` subfolders
+pub fn is_unpacked_mod_dir(path: &Path) -> bool {
+    if contains_vehicle_data(path) {
+        return true;
+    }
+    match fs::read_dir(path.join("vehicles")) {
+        Ok(entries) => entries.flatten().any(|entry| {
+            let entry_path = entry.path();
+            entry_path.is_dir() && contains_vehicle_data(&entry_path)
+        }),
+        Err(_) => false
+    }
+}
+
+fn contains_vehicle_data(path: &Path) -> bool {
+    if !path.join("info.json").is_file() {
+        return false;
+    }
+    match fs::read_dir(path) {
+        Ok(entries) => entries.flatten().any(|entry| {
+            entry.path().extension().is_some_and(|ext| ext.eq("jbeam"))
+        }),
+        Err(_) => false
+    }
+}
+
+/// The archive a mod is packaged in
+enum ModArchive {
+    Zip(zip::ZipArchive<File>),
+    /// 7z archives are commonly solid so files can't be read individually; the contents are
+    /// decompressed when the archive is opened
+    SevenZ(BTreeMap<String, Vec<u8>>),
+    /// A mod that has been extracted into a folder; files are read from the filesystem
+    Unpacked(PathBuf)
+}
+
+impl std::fmt::Debug for ModArchive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModArchive::Zip(archive) => write!(f, "Zip({} files)", archive.len()),
+            ModArchive::SevenZ(files) => write!(f, "SevenZ({} files)", files.len()),
+            ModArchive::Unpacked(path) => write!(f, "Unpacked({})", path.display())
+        }
+    }
+}
+
+impl ModArchive {
+    fn open(mod_path: &Path) -> Result<ModArchive, String> {
+        let is_7z = mod_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("7z"));
+        let archive_file = fs::File::open(mod_path).map_err(|err| {
+            format!("Failed to open {}. {}", mod_path.display(), err.to_string())
+        })?;
+
+        info!("Extracting {}", mod_path.display());
+        match is_7z {
+            false => {
+                let archive = zip::ZipArchive::new(archive_file).map_err(|err| {
+                    format!("Failed to read archive {}. {}", mod_path.display(), err.to_string())
+                })?;
+                Ok(ModArchive::Zip(archive))
+            }
+            true => {
+                Self::read_7z_contents(archive_file).map(ModArchive::SevenZ).map_err(|err| {
+                    format!("Failed to read archive {}. {}", mod_path.display(), err.to_string())
+                })
+            }
+        }
+    }
+
+    fn read_7z_contents(archive_file: File) -> Result<BTreeMap<String, Vec<u8>>, sevenz_rust::Error> {
+        let len = archive_file.metadata().map_err(sevenz_rust::Error::io)?.len();
+        let mut reader = sevenz_rust::SevenZReader::new(archive_file, len, sevenz_rust::Password::empty())?;
+        let mut files = BTreeMap::new();
+        reader.for_each_entries(|entry, entry_reader| {
+            if !entry.is_directory() {
+                let mut data = Vec::new();
+                entry_reader.read_to_end(&mut data).map_err(sevenz_rust::Error::io)?;
+                files.insert(entry.name().replace('\\', "/"), data);
+            }
+            Ok(true)
+        })?;
+        Ok(files)
+    }
+
+    fn file_names(&self) -> Vec<String> {
+        match self {
+            ModArchive::Zip(archive) => archive.file_names().map(String::from).collect(),
+            ModArchive::SevenZ(files) => files.keys().cloned().collect(),
+            ModArchive::Unpacked(root) => {
+                let mut file_names = Vec::new();
+                collect_unpacked_file_names(root, root, &mut file_names);
+                file_names
+            }
+        }
+    }
+
+    fn read_file(&mut self, file_path: &str) -> Result<Vec<u8>, String> {
+        match self {
+            ModArchive::Zip(archive) => {
+                let mut data: Vec<u8> = Vec::new();
+                match archive.by_name(file_path) {
+                    Ok(mut file) => {
+                        debug!("Found engine data at {}", file_path);
+                        file.read_to_end(&mut data).map_err(|e|{
+                            format!("Read to end of {} failed. {}", file_path, e.to_string())
+                        })?;
+                        Ok(data)
+                    },
+                    Err(err) => {
+                        Err(format!("Failed to read {}. {}", file_path, err.to_string()))
+                    }
+                }
+            }
+            ModArchive::SevenZ(files) => {
+                match files.get(file_path) {
+                    Some(data) => {
+                        debug!("Found engine data at {}", file_path);
+                        Ok(data.clone())
+                    }
+                    None => Err(format!("Failed to read {}. specified file not found in archive", file_path))
+                }
+            }
+            ModArchive::Unpacked(root) => {
+                let full_path = root.join(file_path);
+                let data = fs::read(&full_path).map_err(|err| {
+                    format!("Failed to read {}. {}", full_path.display(), err.to_string())
+                })?;
+                debug!("Found engine data at {}", full_path.display());
+                Ok(data)
+            }
+        }
+    }
+}
+
+/// Collect the paths of all files below `dir` relative to `root`, using `/` separators to match
+/// the names of files within an archive
+fn collect_unpacked_file_names(root: &Path, dir: &Path, file_names: &mut Vec<String>) {
+    let dir_entries = match fs::read_dir(dir) {
+        Ok(entry_list) => entry_list,
+        Err(e) => {
+            warn!("Couldn't read {}. {}", dir.display(), e.to_string());
+            return;
+        }
+    };
+    for dir_entry in dir_entries.flatten() {
+        let entry_path = dir_entry.path();
+        if entry_path.is_dir() {
+            collect_unpacked_file_names(root, &entry_path, file_names);
+        } else if let Ok(relative_path) = entry_path.strip_prefix(root) {
+            let name_parts: Vec<String> = relative_path.components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            file_names.push(name_parts.join("/"));
+        }
+    }
+}
+
+/// The details of a mod needed to list it. Only the mod's info.json is read; the name of the
+/// .car file comes from the archive's file listing so nothing else is extracted
+#[derive(Debug)]
+pub struct ModInfo {
+    path: PathBuf,
+    info_json: serde_json::Map<String, serde_json::Value>,
+    car_file_name: Option<String>
+}
+
+impl ModInfo {
+    /// Read the info for the mod at `mod_path`. This can be either a mod archive or an unpacked
+    /// mod folder. 7z archives have no central directory so are read in full
+    pub fn from_path(mod_path: &Path) -> Result<ModInfo, String> {
+        let is_zip = mod_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+        if !mod_path.is_dir() && is_zip {
+            let archive_file = fs::File::open(mod_path).map_err(|err| {
+                format!("Failed to open {}. {}", mod_path.display(), err)
+            })?;
+            return Self::from_zip_reader(mod_path, archive_file);
+        }
+        let mut archive = match mod_path.is_dir() {
+            true => ModArchive::Unpacked(mod_path.to_path_buf()),
+            false => ModArchive::open(mod_path)?
+        };
+        Self::from_archive(mod_path, &mut archive)
+    }
+
+    fn from_zip_reader<R: Read + std::io::Seek>(mod_path: &Path, reader: R) -> Result<ModInfo, String> {
+        let mut archive = zip::ZipArchive::new(reader).map_err(|err| {
+            format!("Failed to read archive {}. {}", mod_path.display(), err)
+        })?;
+        let mut info_json_path = None;
+        let mut car_file_name = None;
+        for file_path in archive.file_names() {
+            if file_path.ends_with("info.json") {
+                info_json_path = Some(file_path.to_string());
+            } else if file_path.ends_with(".car") {
+                car_file_name = Some(file_path.to_string());
+            }
+        }
+        let info_json_path = info_json_path.ok_or_else(|| {
+            format!("No info.json found in {}", mod_path.display())
+        })?;
+        let mut data = Vec::new();
+        archive.by_name(&info_json_path)
+            .map_err(|err| format!("Failed to read {}. {}", info_json_path, err))?
+            .read_to_end(&mut data)
+            .map_err(|err| format!("Read to end of {} failed. {}", info_json_path, err))?;
+        let info_json = serde_json::from_slice(&data).map_err(|e| e.to_string())?;
+        Ok(ModInfo { path: mod_path.to_path_buf(), info_json, car_file_name })
+    }
+
+    fn from_archive(mod_path: &Path, archive: &mut ModArchive) -> Result<ModInfo, String> {
+        let mut info_json_path = None;
+        let mut car_file_name = None;
+        for file_path in archive.file_names() {
+            if file_path.ends_with("info.json") {
+                info_json_path = Some(file_path);
+            } else if file_path.ends_with(".car") {
+                car_file_name = Some(file_path);
+            }
+        }
+        let info_json_path = info_json_path.ok_or_else(|| {
+            format!("No info.json found in {}", mod_path.display())
+        })?;
+        let info_json = _extract_json_data_from_archive(archive, &info_json_path)?;
+        Ok(ModInfo { path: mod_path.to_path_buf(), info_json, car_file_name })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get_info_json_map(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.info_json
+    }
+
+    /// The name of the mod as shown in BeamNG, if info.json has one
+    pub fn name(&self) -> Option<&str> {
+        self.info_json.get("Name").and_then(|v| v.as_str())
+    }
+
+    /// The path within the mod of the exported Automation .car file, if the mod contains one
+    pub fn car_file_name(&self) -> Option<&str> {
+        self.car_file_name.as_deref()
+    }
+
+    /// Read the exported Automation .car file from the mod, if it contains one. Only that file is
+    /// extracted from zip archives; 7z archives are read in full
+    pub fn read_car_file_data(&self) -> Result<Option<Vec<u8>>, String> {
+        let car_file_name = match &self.car_file_name {
+            Some(car_file_name) => car_file_name,
+            None => return Ok(None)
+        };
+        let mut archive = match self.path.is_dir() {
+            true => ModArchive::Unpacked(self.path.clone()),
+            false => ModArchive::open(&self.path)?
+        };
+        archive.read_file(car_file_name).map(Some)
+    }
+}
+
 #[derive(Debug)]
 pub struct ModData {
     info_json: serde_json::Map<String, serde_json::Value>,
     jbeam_file_data: HashMap<String, Vec<u8>>,
     car_file_data: Option<Vec<u8>>,
     license_data: Option<Vec<u8>>,
-    archive_data: zip::ZipArchive<File>
+    archive_data: ModArchive
 }
 
 impl ModData {
+    /// Load the mod at `mod_path`. This can be either a mod archive or an unpacked mod folder
     pub fn from_path(mod_path: &Path) -> Result<ModData, String> {
+        if mod_path.is_dir() {
+            return Self::from_unpacked_dir(mod_path);
+        }
         info!("Opening {}", mod_path.display());
-        let zipfile = fs::File::open(mod_path).map_err(|err| {
-            format!("Failed to open {}. {}", mod_path.display(), err.to_string())
-        })?;
+        let archive = ModArchive::open(mod_path)?;
+        Self::from_archive(mod_path, archive)
+    }
 
-        info!("Extracting {}", mod_path.display());
-        let mut archive = zip::ZipArchive::new(zipfile).map_err(|err| {
-            format!("Failed to read archive {}. {}", mod_path.display(), err.to_string())
-        })?;
+    /// Load a mod that has been extracted into a folder, e.g. one in BeamNG's `mods/unpacked`
+    pub fn from_unpacked_dir(mod_dir: &Path) -> Result<ModData, String> {
+        if !mod_dir.is_dir() {
+            return Err(format!("{} is not a folder", mod_dir.display()));
+        }
+        info!("Reading unpacked mod {}", mod_dir.display());
+        Self::from_archive(mod_dir, ModArchive::Unpacked(mod_dir.to_path_buf()))
+    }
+
+    fn from_archive(mod_path: &Path, mut archive: ModArchive) -> Result<ModData, String> {
 
         let mut info_json_path = String::new();
         let mut license_data_path = None;
@@ -155,16 +582,16 @@ impl ModData {
         let mut jbeam_file_list = Vec::new();
         for file_path in archive.file_names() {
             if file_path.ends_with(".jbeam") {
-                jbeam_file_list.push(String::from(file_path));
+                jbeam_file_list.push(file_path);
             }
             else if file_path.ends_with(".car") {
-                car_data_path = Some(String::from(file_path));
+                car_data_path = Some(file_path);
             }
             else  if file_path.ends_with("info.json") {
-                info_json_path = String::from(file_path);
+                info_json_path = file_path;
             }
             else if file_path.ends_with("license.txt") {
-                license_data_path = Some(String::from(file_path));
+                license_data_path = Some(file_path);
             }
         }
 
@@ -257,6 +684,39 @@ impl ModData {
         std::mem::take(&mut self.jbeam_file_data)
     }
 
+    /// Find the jbeam file containing the main engine data by looking inside each file rather
+    /// than relying on the filename. Used when none of the known filenames are present
+    pub fn find_main_engine_jbeam_file(&self) -> Option<String> {
+        let mut filenames: Vec<&String> = self.jbeam_file_data.keys().collect();
+        filenames.sort();
+        filenames.into_iter()
+            .find(|filename| contains_main_engine_data(&self.jbeam_file_data[*filename]))
+            .cloned()
+    }
+
+    fn jbeam_file_has_main_engine_data(&self, file_path: &str) -> bool {
+        let filename = PathBuf::from(file_path).file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| file_path.to_string());
+        self.jbeam_file_data.get(&filename).is_some_and(|data| contains_main_engine_data(data))
+    }
+
+    /// Log the engine values in the mod's jbeam files that aren't part of the main engine map in
+    /// `main_filename` and so are ignored
+    pub fn log_ignored_engine_data(&self, main_filename: &str) {
+        let mut filenames: Vec<&String> = self.jbeam_file_data.keys().collect();
+        filenames.sort();
+        for filename in filenames {
+            if let Ok(jbeam_data) = jbeam::from_slice(&self.jbeam_file_data[filename]) {
+                let accepted_key = match filename == main_filename {
+                    true => main_engine_key(&jbeam_data).cloned(),
+                    false => None
+                };
+                log_ignored_engine_values(filename, &jbeam_data, accepted_key.as_deref());
+            }
+        }
+    }
+
     pub fn get_engine_jbeam_data(&mut self, expected_eng_key: Option<&str>) -> Result<Map<String, Value>, String> {
         let mut expected_filename: Option<String> = None;
         let mut found_filename : Option<String> = None;
@@ -265,38 +725,53 @@ impl ModData {
             info!("Expect to find engine data in {}", expected_filename.as_ref().unwrap());
         }
 
-        for filename in self.archive_data.file_names() {
+        let archive_filenames = self.archive_data.file_names();
+        for filename in &archive_filenames {
             if let Some(expected_name) = &expected_filename {
                 if filename.ends_with(expected_name) {
                     found_filename = Some(filename.to_string());
-                    info!("Found expected engine.jbeam file at {}", filename);
+                    info!("Found expected engine.jbeam file at {}. Matched by expected filename", filename);
                     break;
                 }
             }
             if filename.ends_with("camso_engine.jbeam") {
                 found_filename = Some(filename.to_string());
-                info!("Found legacy engine.jbeam file at {}", filename);
+                info!("Found legacy engine.jbeam file at {}. Matched by legacy filename", filename);
                 break;
             }
         }
 
         if found_filename.is_none() {
-            for filename in self.archive_data.file_names() {
+            for filename in &archive_filenames {
                 if filename.contains("camso_engine_") {
                     if !filename.contains("structure") &&
                         !filename.contains("internals") &&
-                        !filename.contains("balancing") {
+                        !filename.contains("balancing") &&
+                        self.jbeam_file_has_main_engine_data(filename) {
                         found_filename = Some(filename.to_string());
-                        info!("Found engine.jbeam file at {}", filename);
+                        info!("Found engine.jbeam file at {}. Matched by filename pattern", filename);
                         break;
                     }
                 }
             }
         }
 
+        if found_filename.is_none() {
+            if let Some(filename) = self.find_main_engine_jbeam_file() {
+                info!("Found engine.jbeam file {}. Matched by {} data in the file contents", filename, MAIN_ENGINE_KEY);
+                self.log_ignored_engine_data(&filename);
+                return jbeam::from_slice(&self.jbeam_file_data[&filename]).map_err(|e| {
+                    format!("Failed to read {}. {}", &filename, e)
+                });
+            }
+        }
+
         if let Some(name) = found_filename {
             return match _extract_jbeam_data_from_archive(&mut self.archive_data, &name) {
                 Ok(jbeam_map) => {
+                    if let Some(filename) = PathBuf::from(&name).file_name() {
+                        self.log_ignored_engine_data(&filename.to_string_lossy());
+                    }
                     Ok(jbeam_map)
                 }
                 Err(e) => {
@@ -317,26 +792,64 @@ pub fn load_mod_data(mod_name: &str) -> Result<ModData, String> {
     ModData::from_path(mod_path.as_path())
 }
 
-fn _extract_file_data_from_archive(archive: &mut zip::ZipArchive<fs::File>,
+/// The `mainEngine` values that are read from the main engine map
+pub const MAIN_ENGINE_VALUE_KEYS: [&str; 4] = ["idleRPM", "revLimiterRPM", "maxRPM", "inertia"];
+
+/// Whether `key` names the main engine map; `Camso_Engine` or `Camso_Engine_<id>`.
+/// Keys with further parts such as `Camso_Engine_structure_<id>` or `Camso_Engine_ECU_<id>`
+/// belong to secondary parts
+pub fn is_main_engine_key(key: &str) -> bool {
+    match key.strip_prefix(MAIN_ENGINE_KEY) {
+        Some("") => true,
+        Some(suffix) => suffix.strip_prefix('_').is_some_and(|id| !id.is_empty() && !id.contains('_')),
+        None => false
+    }
+}
+
+/// Find the key of the main engine map in `jbeam_data`; a main engine key whose object has a
+/// `mainEngine` section
+pub fn main_engine_key(jbeam_data: &Map<String, Value>) -> Option<&String> {
+    jbeam_data.iter().find(|(key, value)| {
+        is_main_engine_key(key) &&
+            value.as_object().is_some_and(|engine_data| engine_data.contains_key(MAIN_ENGINE_SECTION))
+    }).map(|(key, _)| key)
+}
+
+/// Log any engine values in `jbeam_data` that are outside the object with `accepted_key` and
+/// so are ignored. Pass None for files that the engine data isn't read from
+pub fn log_ignored_engine_values(source: &str, jbeam_data: &Map<String, Value>, accepted_key: Option<&str>) {
+    for (key, value) in jbeam_data {
+        if Some(key.as_str()) == accepted_key {
+            continue;
+        }
+        let engine_section = match value.as_object().and_then(|data| data.get(MAIN_ENGINE_SECTION)) {
+            Some(Value::Object(section)) => section,
+            _ => continue
+        };
+        for value_key in MAIN_ENGINE_VALUE_KEYS {
+            if let Some(ignored) = engine_section.get(value_key) {
+                debug!("Ignoring {}={} from {} in {}. Not the main engine map", value_key, ignored, key, source);
+            }
+        }
+    }
+}
+
+/// Whether `jbeam_data` contains the main engine map
+pub fn contains_main_engine_data(jbeam_data: &[u8]) -> bool {
+    match jbeam::from_slice(jbeam_data) {
+        Ok(data_map) => main_engine_key(&data_map).is_some(),
+        Err(_) => false
+    }
+}
+
+fn _extract_file_data_from_archive(archive: &mut ModArchive,
                                    file_path: &str)
     -> Result<Vec<u8>, String>
 {
-    let mut data: Vec<u8> = Vec::new();
-    match archive.by_name(file_path) {
-        Ok(mut file) => {
-            debug!("Found engine data at {}", file_path);
-            file.read_to_end(&mut data).map_err(|e|{
-                format!("Read to end of {} failed. {}", file_path, e.to_string())
-            })?;
-            Ok(data)
-        },
-        Err(err) => {
-            return Err(format!("Failed to read {}. {}", file_path, err.to_string()));
-        }
-    }
+    archive.read_file(file_path)
 }
 
-fn _extract_jbeam_data_from_archive(archive: &mut zip::ZipArchive<fs::File>,
+fn _extract_jbeam_data_from_archive(archive: &mut ModArchive,
                                     file_path: &str) -> Result<Map<String, Value>, String> {
     let jbeam_data: Vec<u8> = _extract_file_data_from_archive(archive, file_path)?;
     jbeam::from_slice(&*jbeam_data).map_err(|e| {
@@ -344,7 +857,7 @@ fn _extract_jbeam_data_from_archive(archive: &mut zip::ZipArchive<fs::File>,
     })
 }
 
-fn _extract_json_data_from_archive(archive: &mut zip::ZipArchive<fs::File>,
+fn _extract_json_data_from_archive(archive: &mut ModArchive,
                                    file_path: &str) -> Result<serde_json::Map<String, serde_json::Value>, String> {
     let file_data: Vec<u8> = _extract_file_data_from_archive(archive, file_path)?;
     serde_json::from_slice(&*file_data).map_err(|e| {
@@ -383,4 +896,214 @@ mod tests {
         let _mod_data = load_mod_data("turbo_boy_modifed.zip")?;
         Ok(())
     }
+
+    #[test]
+    fn check_mod_path_suggests_repo() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mod_path = temp_dir.path().join("beam_ng_mod_path_check");
+        let repo_path = mod_path.join("repo");
+        std::fs::create_dir_all(&repo_path).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(mod_path.join("unpacked")).map_err(|e| e.to_string())?;
+        std::fs::write(mod_path.join(crate::MOD_DB_FILENAME), b"{}").map_err(|e| e.to_string())?;
+        std::fs::write(repo_path.join("engine.zip"), b"").map_err(|e| e.to_string())?;
+
+        let hint = crate::check_mod_path(&mod_path);
+        assert!(hint.is_some());
+        assert!(hint.unwrap().ends_with(&format!("{}", repo_path.display())));
+        assert!(crate::check_mod_path(&repo_path).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn mod_list_includes_7z_archives() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mod_path = temp_dir.path().join("beam_ng_7z_mod_list");
+        std::fs::create_dir_all(&mod_path).map_err(|e| e.to_string())?;
+        std::fs::write(mod_path.join("zipped.zip"), b"").map_err(|e| e.to_string())?;
+        std::fs::write(mod_path.join("compressed.7z"), b"").map_err(|e| e.to_string())?;
+        std::fs::write(mod_path.join("notes.txt"), b"").map_err(|e| e.to_string())?;
+
+        let mut mods = crate::read_mods_in_path(&mod_path);
+        mods.sort();
+        assert_eq!(mods, vec![mod_path.join("compressed.7z"), mod_path.join("zipped.zip")]);
+        Ok(())
+    }
+
+    #[test]
+    fn read_files_from_7z_mod() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mod_path = temp_dir.path().join("beam_ng_7z_read");
+        let src_path = mod_path.join("src");
+        std::fs::create_dir_all(src_path.join("vehicles/test_car")).map_err(|e| e.to_string())?;
+        std::fs::write(src_path.join("vehicles/test_car/info.json"), br#"{"Name": "Test"}"#).map_err(|e| e.to_string())?;
+        let archive_path = mod_path.join("test_car.7z");
+        sevenz_rust::compress_to_path(&src_path, &archive_path).map_err(|e| e.to_string())?;
+
+        let mut archive = crate::ModArchive::open(&archive_path)?;
+        assert_eq!(archive.file_names(), vec![String::from("vehicles/test_car/info.json")]);
+        let info = crate::_extract_json_data_from_archive(&mut archive, "vehicles/test_car/info.json")?;
+        assert_eq!(info.get("Name").and_then(|v| v.as_str()), Some("Test"));
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_7z_mod_reports_archive_error() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mod_path = temp_dir.path().join("beam_ng_7z_corrupt");
+        std::fs::create_dir_all(&mod_path).map_err(|e| e.to_string())?;
+        let archive_path = mod_path.join("broken.7z");
+        std::fs::write(&archive_path, b"not a 7z archive").map_err(|e| e.to_string())?;
+
+        let err = crate::ModData::from_path(&archive_path).unwrap_err();
+        assert!(err.starts_with(&format!("Failed to read archive {}.", archive_path.display())));
+        Ok(())
+    }
+
+    #[test]
+    fn mod_list_includes_repo_and_unpacked_mods() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mod_path = temp_dir.path().join("beam_ng_nested_mod_list");
+        let unpacked_mod = mod_path.join("unpacked").join("test_car");
+        let vehicle_path = unpacked_mod.join("vehicles").join("test_car");
+        std::fs::create_dir_all(&vehicle_path).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(mod_path.join("repo")).map_err(|e| e.to_string())?;
+        std::fs::write(mod_path.join("top.zip"), b"").map_err(|e| e.to_string())?;
+        std::fs::write(mod_path.join("repo").join("downloaded.zip"), b"").map_err(|e| e.to_string())?;
+        std::fs::write(vehicle_path.join("info.json"), br#"{"Name": "Test"}"#).map_err(|e| e.to_string())?;
+        std::fs::write(vehicle_path.join("camso_engine_test.jbeam"), b"{}").map_err(|e| e.to_string())?;
+
+        let mut mods = crate::get_mod_list_in(&mod_path);
+        mods.sort();
+        assert_eq!(mods, vec![mod_path.join("repo").join("downloaded.zip"), mod_path.join("top.zip"), unpacked_mod.clone()]);
+        assert_eq!(crate::get_mod_list_in_with_depth(&mod_path, 0), vec![mod_path.join("top.zip")]);
+
+        let mod_data = crate::ModData::from_path(&unpacked_mod)?;
+        assert_eq!(mod_data.get_info_json_map().get("Name").and_then(|v| v.as_str()), Some("Test"));
+        assert!(mod_data.contains_jbeam_file("camso_engine_test.jbeam"));
+        Ok(())
+    }
+
+    #[test]
+    fn engine_jbeam_found_by_contents() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mod_path = temp_dir.path().join("beam_ng_engine_by_contents");
+        let vehicle_path = mod_path.join("vehicles").join("test_car");
+        std::fs::create_dir_all(&vehicle_path).map_err(|e| e.to_string())?;
+        std::fs::write(vehicle_path.join("info.json"), br#"{"Name": "Test"}"#).map_err(|e| e.to_string())?;
+        std::fs::write(vehicle_path.join("camso_engine_structure_abcde.jbeam"),
+                       br#"{"Camso_Engine_structure_abcde": {"slotType": "Camso_Engine_structure"}}"#).map_err(|e| e.to_string())?;
+        std::fs::write(vehicle_path.join("camso_engine_internals_abcde.jbeam"),
+                       br#"{"Camso_Engine_abcde": {"mainEngine": {"idleRPM": 900}}}"#).map_err(|e| e.to_string())?;
+
+        let mut mod_data = crate::ModData::from_path(&mod_path)?;
+        assert_eq!(mod_data.find_main_engine_jbeam_file(), Some(String::from("camso_engine_internals_abcde.jbeam")));
+        let engine_data = mod_data.get_engine_jbeam_data(Some("fghij"))?;
+        assert!(engine_data.contains_key("Camso_Engine_abcde"));
+        assert!(!crate::contains_main_engine_data(br#"{"Camso_Engine_structure_abcde": {}}"#));
+        assert!(crate::contains_main_engine_data(br#"{"Camso_Engine": {"mainEngine": {}}}"#));
+        Ok(())
+    }
+
+    #[test]
+    fn decoy_ecu_engine_values_ignored() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mod_path = temp_dir.path().join("beam_ng_decoy_ecu");
+        let vehicle_path = mod_path.join("vehicles").join("test_car");
+        std::fs::create_dir_all(&vehicle_path).map_err(|e| e.to_string())?;
+        std::fs::write(vehicle_path.join("info.json"), br#"{"Name": "Test"}"#).map_err(|e| e.to_string())?;
+        std::fs::write(vehicle_path.join("camso_engine_ecu_abcde.jbeam"),
+                       br#"{"Camso_Engine_ECU_abcde": {"slotType": "Camso_ECU", "mainEngine": {"revLimiterRPM": 9500, "idleRPM": 1200}}}"#).map_err(|e| e.to_string())?;
+        std::fs::write(vehicle_path.join("camso_engine_abcde.jbeam"), br#"{
+            "Camso_Engine_ECU_race_abcde": {"slotType": "Camso_ECU", "mainEngine": {"revLimiterRPM": 9800, "inertia": 0.05}},
+            "Camso_Engine_abcde": {"slotType": "Camso_Engine", "mainEngine": {"revLimiterRPM": 7200, "idleRPM": 900, "inertia": 0.15}}
+        }"#).map_err(|e| e.to_string())?;
+
+        let mut mod_data = crate::ModData::from_path(&mod_path)?;
+        let engine_data = mod_data.get_engine_jbeam_data(Some("fghij"));
+        let engine_data = engine_data?;
+        let main_key = crate::main_engine_key(&engine_data).cloned();
+        assert_eq!(main_key, Some(String::from("Camso_Engine_abcde")));
+        let main_engine = engine_data[&main_key.unwrap()].find("mainEngine").and_then(|v| v.as_object()).unwrap();
+        assert_eq!(main_engine["revLimiterRPM"].as_f64(), Some(7200.0));
+        assert_eq!(main_engine["idleRPM"].as_f64(), Some(900.0));
+        assert_eq!(main_engine["inertia"].as_f64(), Some(0.15));
+
+        assert!(crate::is_main_engine_key("Camso_Engine"));
+        assert!(crate::is_main_engine_key("Camso_Engine_abcde"));
+        assert!(!crate::is_main_engine_key("Camso_Engine_ECU_abcde"));
+        assert!(!crate::is_main_engine_key("Camso_Engine_"));
+        assert!(!crate::contains_main_engine_data(br#"{"Camso_Engine_ECU_abcde": {"mainEngine": {}}}"#));
+        Ok(())
+    }
+
+    #[test]
+    fn mod_info_only_reads_info_json() -> Result<(), String> {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        /// Counts the bytes read through it so tests can check how much of an archive was touched
+        struct CountingReader<R> {
+            inner: R,
+            bytes_read: std::rc::Rc<std::cell::Cell<u64>>
+        }
+
+        impl<R: std::io::Read> std::io::Read for CountingReader<R> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let count = self.inner.read(buf)?;
+                self.bytes_read.set(self.bytes_read.get() + count as u64);
+                Ok(count)
+            }
+        }
+
+        impl<R: std::io::Seek> std::io::Seek for CountingReader<R> {
+            fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+                self.inner.seek(pos)
+            }
+        }
+
+        const JBEAM_SIZE: usize = 4 * 1024 * 1024;
+        let mut zips = Vec::new();
+        for idx in 0..5 {
+            let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            let stored = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+            writer.start_file(format!("vehicles/car_{}/camso_engine_{}.jbeam", idx, idx), stored).map_err(|e| e.to_string())?;
+            writer.write_all(&vec![b' '; JBEAM_SIZE]).map_err(|e| e.to_string())?;
+            writer.start_file(format!("vehicles/car_{}/info.json", idx), stored).map_err(|e| e.to_string())?;
+            writer.write_all(format!(r#"{{"Name": "Car {}"}}"#, idx).as_bytes()).map_err(|e| e.to_string())?;
+            writer.start_file(format!("vehicles/car_{}/car_{}.car", idx, idx), stored).map_err(|e| e.to_string())?;
+            writer.write_all(&vec![0; JBEAM_SIZE]).map_err(|e| e.to_string())?;
+            zips.push(writer.finish().map_err(|e| e.to_string())?.into_inner());
+        }
+
+        for (idx, zip_data) in zips.into_iter().enumerate() {
+            let bytes_read = std::rc::Rc::new(std::cell::Cell::new(0));
+            let reader = CountingReader { inner: std::io::Cursor::new(zip_data), bytes_read: bytes_read.clone() };
+            let mod_path = PathBuf::from(format!("car_{}.zip", idx));
+            let mod_info = crate::ModInfo::from_zip_reader(&mod_path, reader)?;
+            assert_eq!(mod_info.name(), Some(format!("Car {}", idx).as_str()));
+            assert_eq!(mod_info.car_file_name(), Some(format!("vehicles/car_{}/car_{}.car", idx, idx).as_str()));
+            // Only the central directory and info.json should have been read, not the large entries
+            assert!(bytes_read.get() < 64 * 1024, "read {} bytes of the archive", bytes_read.get());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn mod_info_reads_car_file_on_request() -> Result<(), String> {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mod_path = temp_dir.path().join("mod-info-car-file.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&mod_path).map_err(|e| e.to_string())?);
+        writer.start_file("vehicles/car/info.json", SimpleFileOptions::default()).map_err(|e| e.to_string())?;
+        writer.write_all(br#"{"Name": "Car"}"#).map_err(|e| e.to_string())?;
+        writer.start_file("vehicles/car/car.car", SimpleFileOptions::default()).map_err(|e| e.to_string())?;
+        writer.write_all(b"car data").map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+
+        let car_data = crate::ModInfo::from_path(&mod_path).and_then(|info| info.read_car_file_data());
+        assert_eq!(car_data?, Some(b"car data".to_vec()));
+        Ok(())
+    }
 }
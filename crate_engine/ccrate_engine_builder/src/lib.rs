@@ -30,7 +30,7 @@ use windows::{Win32::Foundation::*, Win32::System::SystemServices::*, };
 
 use crate_engine;
 use crate_engine::{CrateEngine};
-use crate_engine::direct_export::DataV1;
+use crate_engine::direct_export::{check_script_version, DataV1};
 
 #[no_mangle]
 #[allow(non_snake_case, unused_variables)]
@@ -83,9 +83,14 @@ pub extern fn get_last_error() -> *const c_char {
 
 #[no_mangle]
 pub extern fn init(script_version: u32) -> *mut DataV1 {
-    let mut data: Box<DataV1> = Box::new(DataV1::new());
-    data.exporter_script_version = script_version;
-    Box::into_raw(data)
+    clear_last_error();
+    match DataV1::for_script_version(script_version) {
+        Ok(data) => Box::into_raw(Box::new(data)),
+        Err(e) => {
+            set_last_error(&e);
+            std::ptr::null_mut()
+        }
+    }
 }
 
 #[no_mangle]
@@ -132,7 +137,15 @@ pub extern fn dump_json(instance: *mut DataV1,
                         path_char: *const c_char) -> bool
 {
     clear_last_error();
+    if instance.is_null() {
+        set_last_error("No exporter data container. Check the exporter script version is supported");
+        return false;
+    }
     let data = unsafe { &mut*(instance) };
+    if let Err(e) = check_script_version(data.exporter_script_version) {
+        set_last_error(&e);
+        return false;
+    }
     let path_cstr = unsafe { CStr::from_ptr(path_char) };
     let path_str = String::from_utf8_lossy(path_cstr.to_bytes()).to_string();
     let mut parent_path = PathBuf::from(path_str);
@@ -172,12 +185,79 @@ pub extern fn dump_json(instance: *mut DataV1,
     }
 }
 
+#[no_mangle]
+pub extern fn dump_jbeam(instance: *mut DataV1,
+                         path_char: *const c_char) -> bool
+{
+    clear_last_error();
+    if instance.is_null() {
+        set_last_error("No exporter data container. Check the exporter script version is supported");
+        return false;
+    }
+    let data = unsafe { &mut*(instance) };
+    if let Err(e) = check_script_version(data.exporter_script_version) {
+        set_last_error(&e);
+        return false;
+    }
+    let path_cstr = unsafe { CStr::from_ptr(path_char) };
+    let path_str = String::from_utf8_lossy(path_cstr.to_bytes()).to_string();
+    let mut parent_path = PathBuf::from(path_str);
+    if parent_path.is_file() {
+        parent_path = match parent_path.parent() {
+            None => PathBuf::new(),
+            Some(p) => PathBuf::from(p)
+        }
+    }
+    match fs::create_dir_all(&parent_path) {
+        Ok(_) => {}
+        Err(e) => {
+            set_last_error(&format!("Failed to create directory ({}) to write jbeam to. {}",
+                                    parent_path.display(), e.to_string()));
+            return false;
+        }
+    }
+    let jbeam_data = match data.engine_jbeam() {
+        Ok(jbeam_data) => jbeam_data,
+        Err(e) => {
+            set_last_error(&format!("Failed to build engine jbeam. {}", e));
+            return false;
+        }
+    };
+    // BeamNG can't read the quoteless strings produced by the Hjson serializer. Jbeam is a
+    // superset of json so write it out as that instead
+    let jbeam_string = match serde_json::to_string_pretty(&jbeam_data) {
+        Ok(s) => s,
+        Err(e) => {
+            set_last_error(&format!("Failed to encode data as jbeam. {}",
+                                    e.to_string()));
+            return false;
+        }
+    };
+    let file_path = parent_path.join(format!("{}.jbeam", data.engine_jbeam_key().to_lowercase()));
+    match fs::write(file_path, jbeam_string) {
+        Ok(_) => true,
+        Err(e) => {
+            set_last_error(&format!("Failed to write jbeam file. {}",
+                                    e.to_string()));
+            false
+        }
+    }
+}
+
 #[no_mangle]
 pub extern fn finalise(instance: *mut DataV1,
                        path_char: *const c_char) -> bool
 {
     clear_last_error();
+    if instance.is_null() {
+        set_last_error("No exporter data container. Check the exporter script version is supported");
+        return false;
+    }
     let data = unsafe {Box::from_raw(instance)};
+    if let Err(e) = check_script_version(data.exporter_script_version) {
+        set_last_error(&e);
+        return false;
+    }
     let path_cstr = unsafe { CStr::from_ptr(path_char) };
     let path_str = String::from_utf8_lossy(path_cstr.to_bytes()).to_string();
     let mut path = PathBuf::from(path_str);
@@ -210,7 +290,10 @@ pub extern fn finalise(instance: *mut DataV1,
                 }
             }
         }
-        Err(_) => false
+        Err(e) => {
+            set_last_error(&format!("Failed to create crate engine. {}", e));
+            false
+        }
     }
 }
 
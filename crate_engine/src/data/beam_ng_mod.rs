@@ -28,12 +28,12 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tracing::{info, warn};
 
-use automation::sandbox::{EngineV1, SandboxFinder};
+use automation::sandbox::{AlRimaEngineData, EngineV1, SandboxFinder};
 use automation::validation::{AutomationSandboxCrossChecker};
 use utils::hash::create_sha256_hash_array;
-use crate::CrateEngineMetadata;
+use crate::{CrateEngineError, CrateEngineMetadata};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CreationOptions {
     pub xref_mod_with_sandbox: bool
 }
@@ -46,67 +46,89 @@ impl CreationOptions {
 
 #[derive(Debug, Clone)]
 pub enum Data {
-    V1(DataV1)
+    V1(DataV1),
+    /// [`DataV1`] followed by the engine fields added in Automation 4.3 (Al Rima)
+    V2(DataV1)
 }
 
 impl Data {
+    pub const AL_RIMA_VERSION: u16 = 2;
+
     pub fn from_beamng_mod_zip(mod_path: &Path, options: CreationOptions) -> Result<Data, String> {
-        Ok(Data::V1(DataV1::from_beamng_mod_zip(mod_path, options)?))
+        Ok(Data::V2(DataV1::from_beamng_mod_zip(mod_path, options)?))
     }
 
-    pub fn from_reader(_metadata: &CrateEngineMetadata, reader: &mut impl Read) -> Result<Data, String> {
-        let internal_data =
-            deserialize_from(reader).map_err(|e| {
-                format!("Failed to deserialise {} crate engine. {}", 1, e.to_string())
-            })?;
-        Ok(Data::V1(internal_data))
+    pub fn from_reader(metadata: &CrateEngineMetadata, reader: &mut impl Read) -> Result<Data, CrateEngineError> {
+        let mut internal_data: DataV1 =
+            deserialize_from(&mut *reader).map_err(|e| CrateEngineError::from_bincode("BeamNG mod", e))?;
+        match metadata.data_version() {
+            DataV1::VERSION => Ok(Data::V1(internal_data)),
+            Data::AL_RIMA_VERSION => {
+                let al_rima_data: AlRimaEngineData = deserialize_from(reader)
+                    .map_err(|e| CrateEngineError::from_bincode("Automation 4.3 sandbox", e))?;
+                internal_data.automation_variant_data.al_rima = al_rima_data;
+                Ok(Data::V2(internal_data))
+            }
+            version => Err(CrateEngineError::UnknownDataVersion(version))
+        }
     }
 
     pub fn version_int(&self) -> u16 {
         match &self {
-            Data::V1(d) => d.version()
+            Data::V1(d) => d.version(),
+            Data::V2(_) => Data::AL_RIMA_VERSION
         }
     }
 
     pub fn serialize_into(&self, writer: &mut impl Write) -> bincode::Result<()> {
         match self {
-            Data::V1(d) => serialize_into(writer, d)
+            Data::V1(d) => serialize_into(writer, d),
+            Data::V2(d) => {
+                serialize_into(&mut *writer, d)?;
+                serialize_into(writer, &d.automation_variant_data.al_rima)
+            }
         }
     }
 
     pub fn jbeam_data(&self) -> &HashMap<String, Vec<u8>> {
         match self {
-            Data::V1(d) => d.jbeam_data()
+            Data::V1(d) | Data::V2(d) => d.jbeam_data()
         }
     }
 
     pub fn main_engine_jbeam_data(&self) -> Option<&Vec<u8>> {
         match self {
-            Data::V1(d) => d.main_engine_jbeam_data()
+            Data::V1(d) | Data::V2(d) => d.main_engine_jbeam_data()
         }
     }
 
     pub fn automation_data(&self) -> &EngineV1 {
         match self {
-            Data::V1(d) => d.automation_data()
+            Data::V1(d) | Data::V2(d) => d.automation_data()
         }
     }
 
     pub fn automation_data_hash(&self) -> Option<[u8; 32]> {
         match self {
-            Data::V1(d) => d.automation_data_hash()
+            Data::V1(d) | Data::V2(d) => d.automation_data_hash()
         }
     }
 
     pub fn jbeam_data_hash(&self) -> Option<[u8; 32]> {
         match self {
-            Data::V1(d) => d.jbeam_data_hash()
+            Data::V1(d) | Data::V2(d) => d.jbeam_data_hash()
         }
     }
 
     pub fn car_file_data(&self) -> &Vec<u8> {
         match self {
-            Data::V1(d) => d.car_file_data()
+            Data::V1(d) | Data::V2(d) => d.car_file_data()
+        }
+    }
+
+    pub fn write_mod_zip(&self, zip_path: &Path, vehicle_name: &str) -> Result<(), String> {
+        match self {
+            Data::V1(d) | Data::V2(d) => d.write_mod_zip(zip_path, vehicle_name)
         }
     }
 }
@@ -167,7 +189,8 @@ impl DataV1 {
                 {
                     if !name.contains("structure") &&
                         !name.contains("internals") &&
-                        !name.contains("balancing")
+                        !name.contains("balancing") &&
+                        mod_data.get_jbeam_file_data(name).is_some_and(|data| beam_ng::contains_main_engine_data(data))
                     {
                         main_engine_data_file = Some(name.clone());
                         break;
@@ -176,27 +199,37 @@ impl DataV1 {
             }
         }
 
+        if main_engine_data_file.is_none() {
+            main_engine_data_file = mod_data.find_main_engine_jbeam_file();
+            if let Some(name) = &main_engine_data_file {
+                info!("Found main engine data in {} by searching jbeam file contents", name);
+            }
+        }
+
         let main_engine_jbeam_filename =
             main_engine_data_file.ok_or("Failed to find the main engine data".to_string())?;
         info!("Found main engine data file: {}", main_engine_jbeam_filename);
+        mod_data.log_ignored_engine_data(&main_engine_jbeam_filename);
 
         let version = _get_engine_version_from_car_file(&automation_car_file)?;
         info!("Engine version number: {}", version);
         let sandbox_finder = SandboxFinder::default();
-        let sandbox_data = sandbox_finder.find_sandbox_db_for_version(version);
-        info!("Deduced as {}", sandbox_data.version);
-        let automation_variant_data = match automation::sandbox::load_engine_by_uuid(&uid, sandbox_data)? {
-            None => {
-                return Err(format!("No engine found with uuid {}", uid));
+        let engine_match = automation::sandbox::search_sandboxes_for_engine(&uid, version, &sandbox_finder).map_err(|err| {
+            match sandbox_finder.newest_game_version() {
+                Some(newest) => format!("{}. The mod was exported from Automation version {}; the newest sandbox db \
+                                         available has version {}", err, version, newest),
+                None => format!("{}. The mod was exported from Automation version {}; no sandbox db was found", err, version)
             }
-            Some(eng) => { eng }
-        };
+        })?;
+        info!("Loaded engine from the {} sandbox", engine_match.version);
 
         if options.xref_mod_with_sandbox {
-            AutomationSandboxCrossChecker::new(&automation_car_file, &automation_variant_data).validate().map_err(|err|{
-                format!("{}. The BeamNG mod may be out-of-date; try recreating a mod with the latest engine version", err)
+            AutomationSandboxCrossChecker::new(&automation_car_file, &engine_match.engine).validate().map_err(|err|{
+                format!("{}. The BeamNG mod may be out-of-date; try recreating a mod with the latest engine version. \
+                         Sandbox dbs checked: {}", err, engine_match.searched_dbs())
             })?;
         }
+        let automation_variant_data = engine_match.engine;
 
         let mod_info_json_data = match mod_data.get_info_json() {
             Ok(data_str) => {
@@ -248,6 +281,20 @@ impl DataV1 {
         &self._car_file_data
     }
 
+    /// Write the stored mod files into a zip at `zip_path` that can be loaded as a BeamNG mod.
+    /// The folder layout of the original mod isn't stored, so the files are placed under
+    /// `vehicles/<vehicle_name>`
+    pub fn write_mod_zip(&self, zip_path: &Path, vehicle_name: &str) -> Result<(), String> {
+        let info_json_data = match &self.mod_info_json_data {
+            Some(data) => data.clone(),
+            None => {
+                warn!("No info.json stored for {}; writing a minimal one", vehicle_name);
+                serde_json::to_vec(&serde_json::json!({ "Name": vehicle_name })).map_err(|e| e.to_string())?
+            }
+        };
+        write_mod_zip(zip_path, vehicle_name, &self.jbeam_file_data, &self._car_file_data, &info_json_data, self.license_data.as_ref())
+    }
+
     pub fn from_eng_file(file_path: &Path) -> bincode::Result<DataV1> {
         let mut file = File::open(file_path)?;
         let mut buffer = Vec::new();
@@ -261,6 +308,39 @@ impl DataV1 {
     }
 }
 
+pub(crate) fn write_mod_zip(zip_path: &Path,
+                            vehicle_name: &str,
+                            jbeam_file_data: &HashMap<String, Vec<u8>>,
+                            car_file_data: &[u8],
+                            info_json_data: &[u8],
+                            license_data: Option<&Vec<u8>>) -> Result<(), String>
+{
+    let zip_err = |e: zip::result::ZipError| format!("Failed to write {}. {}", zip_path.display(), e.to_string());
+    let io_err = |e: std::io::Error| format!("Failed to write {}. {}", zip_path.display(), e.to_string());
+
+    let vehicle_dir = format!("vehicles/{}", vehicle_name);
+    let mut mod_files: Vec<(String, &[u8])> = vec![
+        (format!("{}/info.json", vehicle_dir), info_json_data),
+        (format!("{}/{}.car", vehicle_dir, vehicle_name), car_file_data)
+    ];
+    let mut jbeam_filenames: Vec<&String> = jbeam_file_data.keys().collect();
+    jbeam_filenames.sort();
+    for filename in jbeam_filenames {
+        mod_files.push((format!("{}/{}", vehicle_dir, filename), &jbeam_file_data[filename]));
+    }
+    if let Some(data) = license_data {
+        mod_files.push(("license.txt".to_string(), data));
+    }
+
+    let mut zip = zip::ZipWriter::new(File::create(zip_path).map_err(io_err)?);
+    for (name, data) in mod_files {
+        zip.start_file(name, zip::write::SimpleFileOptions::default()).map_err(zip_err)?;
+        zip.write_all(data).map_err(io_err)?;
+    }
+    zip.finish().map_err(zip_err)?;
+    Ok(())
+}
+
 fn _is_legacy_main_engine_data_file(filename: &str) -> bool {
     if filename.ends_with("camso_engine.jbeam") {
         return true;
@@ -280,8 +360,16 @@ fn _is_legacy_main_engine_data_file(filename: &str) -> bool {
 fn _get_variant_section_from_car_file(automation_car_file: &automation::car::CarFile)
                                       -> Result<&automation::car::Section, String>
 {
-    Ok(automation_car_file
-        .get_section("Car").ok_or("Failed to find Car section in .car file".to_string())?
+    let car_section = automation_car_file.get_section("Car").ok_or_else(|| {
+        match automation_car_file.game_version() {
+            Some(version) if version >= automation::FIRST_AL_RIMA_VERSION_NUM as f64 => {
+                format!("Failed to find Car section in .car file. \
+                         Data from Automation version {} isn't supported yet", version as u64)
+            }
+            _ => "Failed to find Car section in .car file".to_string()
+        }
+    })?;
+    Ok(car_section
         .get_section("Variant").ok_or("Failed to find Car.Variant section in .car file".to_string())?)
 }
 
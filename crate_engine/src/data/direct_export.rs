@@ -20,10 +20,15 @@
  */
 
 use std::collections::BTreeMap;
+use std::fs;
 use std::io::{Read, Write};
+use std::path::Path;
 use bincode::{deserialize_from, serialize_into};
 use serde::{Deserialize, Serialize};
-use crate::CrateEngineMetadata;
+use serde_hjson::{Map, Value};
+use utils::numeric::normal_lerp;
+use utils::units::calculate_power_kw;
+use crate::{CrateEngineError, CrateEngineMetadata};
 
 #[derive(Debug)]
 pub struct CreationOptions {
@@ -47,11 +52,9 @@ impl Data {
         }
     }
 
-    pub fn from_reader(_metadata: &CrateEngineMetadata, reader: &mut impl Read) -> Result<Data, String> {
+    pub fn from_reader(_metadata: &CrateEngineMetadata, reader: &mut impl Read) -> Result<Data, CrateEngineError> {
         let internal_data =
-            deserialize_from(reader).map_err(|e| {
-                format!("Failed to deserialise {} crate engine. {}", 1, e.to_string())
-            })?;
+            deserialize_from(reader).map_err(|e| CrateEngineError::from_bincode("direct export", e))?;
         Ok(Data::V1(internal_data))
     }
 
@@ -62,6 +65,75 @@ impl Data {
             }
         }
     }
+
+    pub fn torque_curve(&self) -> Vec<(f64, f64)> {
+        match self {
+            Data::V1(d) => d.torque_curve()
+        }
+    }
+
+    pub fn power_curve(&self) -> Vec<(f64, f64)> {
+        match self {
+            Data::V1(d) => d.power_curve()
+        }
+    }
+
+    /// Load exporter data from a JSON file previously written by dump_json. The exporter script
+    /// version and data are validated so that it can be used to create a crate engine
+    pub fn from_json_file(path: &Path) -> Result<Data, String> {
+        let file_data = fs::read(path).map_err(|e| {
+            format!("Failed to read {}. {}", path.display(), e.to_string())
+        })?;
+        let data: DataV1 = serde_json::from_slice(&file_data).map_err(|e| {
+            format!("Failed to parse exporter data from {}. {}", path.display(), e.to_string())
+        })?;
+        check_script_version(data.exporter_script_version).map_err(|e| {
+            format!("Can't import {}. {}", path.display(), e)
+        })?;
+        data.validate()?;
+        Ok(Data::V1(data))
+    }
+}
+
+const BAR_TO_PSI: f64 = 14.5038;
+
+/// The oldest version of the Automation exporter script that this library can read data from
+pub const MIN_SUPPORTED_SCRIPT_VERSION: u32 = 1;
+/// The newest version of the Automation exporter script that this library can read data from.
+/// Bump this when the script starts sending data in a different way
+pub const MAX_SUPPORTED_SCRIPT_VERSION: u32 = 1;
+
+/// Check that data sent by version `script_version` of the exporter script can be understood
+pub fn check_script_version(script_version: u32) -> Result<(), String> {
+    if script_version < MIN_SUPPORTED_SCRIPT_VERSION {
+        return Err(format!("Exporter script version {} is too old. Versions {} to {} are supported; please update the exporter script",
+                           script_version, MIN_SUPPORTED_SCRIPT_VERSION, MAX_SUPPORTED_SCRIPT_VERSION));
+    }
+    if script_version > MAX_SUPPORTED_SCRIPT_VERSION {
+        return Err(format!("Exporter script version {} is too new. Versions {} to {} are supported; please update engine-crane",
+                           script_version, MIN_SUPPORTED_SCRIPT_VERSION, MAX_SUPPORTED_SCRIPT_VERSION));
+    }
+    Ok(())
+}
+
+/// The string data that must be present to create a crate engine from exporter data
+const REQUIRED_STRING_DATA: [(&str, &str); 6] = [
+    ("Info", "FamilyName"), ("Info", "VariantName"), ("Parts", "BlockType"),
+    ("Parts", "HeadType"), ("Parts", "Aspiration"), ("Fuel", "Type")
+];
+
+/// The float data that must be present to create a crate engine from exporter data
+const REQUIRED_FLOAT_DATA: [(&str, &str); 10] = [
+    ("Info", "GameVersion"), ("Info", "VariantYear"), ("Parts", "IntakeValves"),
+    ("Parts", "ExhaustValves"), ("Tune", "Displacement"), ("Results", "PeakPower"),
+    ("Results", "PeakPowerRPM"), ("Results", "PeakTorque"), ("Results", "PeakTorqueRPM"),
+    ("Results", "MaxRPM")
+];
+
+fn jbeam_table(header: [&str; 2], rows: impl Iterator<Item=(f64, f64)>) -> Value {
+    let mut table = vec![Value::Array(header.iter().map(|h| Value::String(h.to_string())).collect())];
+    table.extend(rows.map(|(x, y)| Value::Array(vec![Value::F64(x.round()), Value::F64(y)])));
+    Value::Array(table)
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -83,6 +155,13 @@ impl DataV1 {
         DataV1::default()
     }
 
+    /// Create an empty container for data sent by version `script_version` of the exporter script.
+    /// Returns an error if that version isn't supported
+    pub fn for_script_version(script_version: u32) -> Result<DataV1, String> {
+        check_script_version(script_version)?;
+        Ok(DataV1 { exporter_script_version: script_version, ..DataV1::default() })
+    }
+
     pub fn add_string(&mut self, group_name: String, key: String, value: String) {
         if !self.string_data.contains_key(&group_name) {
             self.string_data.insert(group_name.clone(), BTreeMap::new());
@@ -104,6 +183,148 @@ impl DataV1 {
         curve_map.insert(index, value);
     }
 
+    /// Return a vector containing pairs of RPM, Torque (Nm) from the exported "RPM" and
+    /// "Torque" curves. Points missing from either curve are skipped
+    pub fn torque_curve(&self) -> Vec<(f64, f64)> {
+        match (self.curve_data.get("RPM"), self.curve_data.get("Torque")) {
+            (Some(rpm_map), Some(torque_map)) => rpm_map.iter().filter_map(|(idx, rpm)| {
+                torque_map.get(idx).map(|torque| (*rpm as f64, *torque as f64))
+            }).collect(),
+            _ => Vec::new()
+        }
+    }
+
+    /// Return a vector containing pairs of RPM, Power (kW) calculated from the torque curve
+    pub fn power_curve(&self) -> Vec<(f64, f64)> {
+        self.torque_curve().into_iter().map(|(rpm, torque)| {
+            (rpm, calculate_power_kw(rpm as f32, torque as f32) as f64)
+        }).collect()
+    }
+
+    fn curve_pairs(&self, curve_name: &str) -> Vec<(f64, f64)> {
+        match (self.curve_data.get("RPM"), self.curve_data.get(curve_name)) {
+            (Some(rpm_map), Some(value_map)) => rpm_map.iter().filter_map(|(idx, rpm)| {
+                value_map.get(idx).map(|value| (*rpm as f64, *value as f64))
+            }).collect(),
+            _ => Vec::new()
+        }
+    }
+
+    /// The data required to create a crate engine that isn't present, in the form Group.Key
+    pub fn missing_fields(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        for (group, key) in REQUIRED_STRING_DATA {
+            if self.string_data.get(group).and_then(|group_data| group_data.get(key)).is_none() {
+                missing.push(format!("{}.{}", group, key));
+            }
+        }
+        for (group, key) in REQUIRED_FLOAT_DATA {
+            if self.float_data.get(group).and_then(|group_data| group_data.get(key)).is_none() {
+                missing.push(format!("{}.{}", group, key));
+            }
+        }
+        missing
+    }
+
+    /// Check that all the data required to create a crate engine is present
+    pub fn validate(&self) -> Result<(), String> {
+        let missing = self.missing_fields();
+        match missing.is_empty() {
+            true => Ok(()),
+            false => Err(format!("Exporter data is missing {}", missing.join(", ")))
+        }
+    }
+
+    fn float(&self, group: &str, key: &str) -> Result<f64, String> {
+        self.float_data.get(group).and_then(|group_data| group_data.get(key)).map(|val| *val as f64).ok_or_else(|| {
+            format!("Exporter data is missing {}.{}", group, key)
+        })
+    }
+
+    fn is_naturally_aspirated(&self) -> bool {
+        self.string_data.get("Parts")
+            .and_then(|parts| parts.get("Aspiration"))
+            .map_or(true, |aspiration| aspiration.starts_with("Aspiration_Natural"))
+    }
+
+    /// The key of the Camso_Engine part in the jbeam returned by [`DataV1::engine_jbeam`]. This uses
+    /// the variant UID when the exporter provides one and the engine name otherwise
+    pub fn engine_jbeam_key(&self) -> String {
+        let id = match self.string_data.get("Info").and_then(|info| info.get("UID")) {
+            Some(uid) => uid.clone(),
+            None => utils::filesystem::sanitize_name(&self.deduce_engine_name())
+        };
+        format!("Camso_Engine_{}", id)
+    }
+
+    /// Build a minimal BeamNG engine jbeam containing the main engine torque curve, idle and
+    /// redline speeds, inertia, friction and, for forced induction engines, the boost curve.
+    /// The torque curve excludes boost as BeamNG adds that from the turbocharger section
+    pub fn engine_jbeam(&self) -> Result<Map<String, Value>, String> {
+        let naturally_aspirated = self.is_naturally_aspirated();
+        let boost_curve = match naturally_aspirated {
+            true => Vec::new(),
+            false => self.curve_pairs("Boost")
+        };
+        let boost_at = |idx: usize| boost_curve.get(idx).map_or(0.0, |(_, boost)| f64::max(*boost, 0.0));
+        let torque_curve = self.torque_curve();
+        if torque_curve.is_empty() {
+            return Err("Exporter data has no torque curve".to_string());
+        }
+        let max_rpm = self.float("Results", "MaxRPM")?;
+        // The same inertia range used when swapping an exported engine into AC
+        let responsiveness = self.float("Results", "Responsiveness")?;
+        let inertia = normal_lerp(0.32, 0.07, (responsiveness / 100.0) as f32, 0.2) as f64;
+
+        let friction_curve = self.curve_pairs("Friction");
+        let (friction, dynamic_friction) = match (friction_curve.first(), friction_curve.last()) {
+            (Some((first_rpm, first_friction)), Some((last_rpm, last_friction))) if last_rpm > first_rpm => {
+                let rpm_to_av = std::f64::consts::PI / 30.0;
+                let dynamic_friction = (last_friction - first_friction) / ((last_rpm - first_rpm) * rpm_to_av);
+                (first_friction - (first_rpm * rpm_to_av * dynamic_friction), dynamic_friction)
+            }
+            _ => {
+                let displacement_litres = self.float("Tune", "Displacement")?;
+                ((100_000.0 * (displacement_litres / 1000.0)) / (4.0 * std::f64::consts::PI), 0.0)
+            }
+        };
+
+        let mut main_engine = Map::new();
+        main_engine.insert("torque".to_string(), jbeam_table(
+            ["rpm", "torque"],
+            torque_curve.iter().enumerate().map(|(idx, (rpm, torque))| (*rpm, (torque / (1.0 + boost_at(idx))).round()))
+        ));
+        main_engine.insert("idleRPM".to_string(), Value::F64(self.float("Results", "IdleRPM")?.round()));
+        main_engine.insert("maxRPM".to_string(), Value::F64((max_rpm + 500.0).round()));
+        main_engine.insert("revLimiterRPM".to_string(), Value::F64(max_rpm.round()));
+        main_engine.insert("inertia".to_string(), Value::F64(inertia));
+        main_engine.insert("friction".to_string(), Value::F64(friction.max(0.0)));
+        main_engine.insert("dynamicFriction".to_string(), Value::F64(dynamic_friction.max(0.0)));
+
+        let mut information = Map::new();
+        information.insert("authors".to_string(), Value::String("Automation".to_string()));
+        information.insert("name".to_string(), Value::String(self.deduce_engine_name()));
+
+        let mut engine_part = Map::new();
+        engine_part.insert("information".to_string(), Value::Object(information));
+        engine_part.insert("slotType".to_string(), Value::String("Camso_Engine".to_string()));
+        engine_part.insert("mainEngine".to_string(), Value::Object(main_engine));
+        if !naturally_aspirated && !boost_curve.is_empty() {
+            let max_boost = boost_curve.iter().fold(0.0, |max, (_, boost)| f64::max(max, *boost));
+            let mut turbocharger = Map::new();
+            turbocharger.insert("pressurePSI".to_string(), jbeam_table(
+                ["rpm", "psi"],
+                boost_curve.iter().map(|(rpm, boost)| (*rpm, (boost.max(0.0) * BAR_TO_PSI * 100.0).round() / 100.0))
+            ));
+            turbocharger.insert("wastegateStart".to_string(), Value::F64((max_boost * BAR_TO_PSI * 100.0).round() / 100.0));
+            engine_part.insert("turbocharger".to_string(), Value::Object(turbocharger));
+        }
+
+        let mut jbeam = Map::new();
+        jbeam.insert(self.engine_jbeam_key(), Value::Object(engine_part));
+        Ok(jbeam)
+    }
+
     pub fn deduce_engine_name(&self) -> String {
         let backup_fam_name = String::from("UnknownFamily");
         let backup_var_name = String::from("UnknownVariant");
@@ -117,3 +338,112 @@ impl DataV1 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_hjson::Value;
+    use crate::direct_export::{Data, DataV1, MAX_SUPPORTED_SCRIPT_VERSION, MIN_SUPPORTED_SCRIPT_VERSION, check_script_version};
+
+    fn create_exporter_data(aspiration: &str) -> DataV1 {
+        let mut data = DataV1::new();
+        for (group, key, value) in [("Info", "FamilyName", "Test"), ("Info", "VariantName", "I4"),
+                                    ("Parts", "Aspiration", aspiration)] {
+            data.add_string(group.to_string(), key.to_string(), value.to_string());
+        }
+        for (group, key, value) in [("Results", "MaxRPM", 7000.0), ("Results", "IdleRPM", 900.0),
+                                    ("Results", "Responsiveness", 50.0), ("Tune", "Displacement", 2.0)] {
+            data.add_float(group.to_string(), key.to_string(), value);
+        }
+        for (idx, (rpm, torque, boost)) in [(1000.0, 150.0, 0.0), (4000.0, 300.0, 1.0), (7000.0, 250.0, 1.0)].into_iter().enumerate() {
+            data.add_curve_data("RPM".to_string(), idx, rpm);
+            data.add_curve_data("Torque".to_string(), idx, torque);
+            data.add_curve_data("Boost".to_string(), idx, boost);
+        }
+        data
+    }
+
+    #[test]
+    fn engine_jbeam_contains_main_engine_keys() -> Result<(), String> {
+        let data = create_exporter_data("Aspiration_Natural");
+        let jbeam = data.engine_jbeam()?;
+        assert_eq!(beam_ng::main_engine_key(&jbeam).map(String::as_str), Some("Camso_Engine_Test-I4"));
+        let engine_part = jbeam["Camso_Engine_Test-I4"].as_object().unwrap();
+        assert_eq!(engine_part["slotType"].as_str(), Some("Camso_Engine"));
+        assert!(!engine_part.contains_key("turbocharger"));
+        let main_engine = engine_part["mainEngine"].as_object().unwrap();
+        // The fabricator also reads the torque table and friction values
+        for key in beam_ng::MAIN_ENGINE_VALUE_KEYS.into_iter().chain(["torque", "friction", "dynamicFriction"]) {
+            assert!(main_engine.contains_key(key), "mainEngine is missing {}", key);
+        }
+        assert_eq!(main_engine["revLimiterRPM"].as_f64(), Some(7000.0));
+        // Responsiveness of 50 is the middle of the inertia range
+        let inertia = main_engine["inertia"].as_f64().unwrap();
+        assert!((inertia - 0.195).abs() < 1e-6, "{}", inertia);
+        let torque = main_engine["torque"].as_array().unwrap();
+        assert_eq!(torque.len(), 4);
+        assert_eq!(torque[2], Value::Array(vec![Value::F64(4000.0), Value::F64(300.0)]));
+        Ok(())
+    }
+
+    #[test]
+    fn turbo_engine_jbeam_excludes_boost_from_torque() -> Result<(), String> {
+        let data = create_exporter_data("Aspiration_Turbo");
+        let jbeam = data.engine_jbeam()?;
+        let engine_part = jbeam["Camso_Engine_Test-I4"].as_object().unwrap();
+        let torque = engine_part["mainEngine"].as_object().unwrap()["torque"].as_array().unwrap();
+        assert_eq!(torque[2], Value::Array(vec![Value::F64(4000.0), Value::F64(150.0)]));
+        let turbocharger = engine_part["turbocharger"].as_object().unwrap();
+        assert_eq!(turbocharger["wastegateStart"].as_f64(), Some(14.5));
+        assert_eq!(turbocharger["pressurePSI"].as_array().unwrap().len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn exporter_data_loaded_from_json_dump() -> Result<(), String> {
+        let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let json_path = temp_dir.path().join("exporter_dump.json");
+        let mut data = create_exporter_data("Aspiration_Natural");
+        std::fs::write(&json_path, serde_json::to_vec(&data).unwrap()).unwrap();
+        let err = Data::from_json_file(&json_path).unwrap_err();
+        assert!(err.contains("too old"), "{}", err);
+        data.exporter_script_version = MAX_SUPPORTED_SCRIPT_VERSION + 1;
+        std::fs::write(&json_path, serde_json::to_vec(&data).unwrap()).unwrap();
+        let err = Data::from_json_file(&json_path).unwrap_err();
+        assert!(err.contains("too new"), "{}", err);
+
+        data.exporter_script_version = MAX_SUPPORTED_SCRIPT_VERSION;
+        std::fs::write(&json_path, serde_json::to_vec(&data).unwrap()).unwrap();
+        let err = Data::from_json_file(&json_path).unwrap_err();
+        assert!(err.contains("Info.GameVersion"), "{}", err);
+        assert!(err.contains("Parts.BlockType"), "{}", err);
+        assert!(!err.contains("Tune.Displacement"), "{}", err);
+
+        for (group, key, value) in [("Parts", "BlockType", "EngBlock_Inline4_Name"), ("Parts", "HeadType", "Head_DAOHC_Name"),
+                                    ("Fuel", "Type", "Petrol")] {
+            data.add_string(group.to_string(), key.to_string(), value.to_string());
+        }
+        for (group, key, value) in [("Info", "GameVersion", 2310.0), ("Info", "VariantYear", 2020.0),
+                                    ("Parts", "IntakeValves", 2.0), ("Parts", "ExhaustValves", 2.0),
+                                    ("Results", "PeakPower", 150.0), ("Results", "PeakPowerRPM", 6500.0),
+                                    ("Results", "PeakTorque", 300.0), ("Results", "PeakTorqueRPM", 4000.0)] {
+            data.add_float(group.to_string(), key.to_string(), value);
+        }
+        std::fs::write(&json_path, serde_json::to_vec(&data).unwrap()).unwrap();
+        let Data::V1(loaded) = Data::from_json_file(&json_path)?;
+        assert!(loaded.missing_fields().is_empty());
+        assert_eq!(loaded.torque_curve(), data.torque_curve());
+        assert_eq!(loaded.string_data, data.string_data);
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_script_versions_rejected() {
+        let too_old = DataV1::for_script_version(MIN_SUPPORTED_SCRIPT_VERSION - 1).unwrap_err();
+        assert!(too_old.contains("too old"), "{}", too_old);
+        let current = DataV1::for_script_version(MAX_SUPPORTED_SCRIPT_VERSION).unwrap();
+        assert_eq!(current.exporter_script_version, MAX_SUPPORTED_SCRIPT_VERSION);
+        assert!(check_script_version(current.exporter_script_version).is_ok());
+        let too_new = DataV1::for_script_version(MAX_SUPPORTED_SCRIPT_VERSION + 1).unwrap_err();
+        assert!(too_new.contains("too new"), "{}", too_new);
+    }
+}
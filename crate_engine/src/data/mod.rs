@@ -21,16 +21,19 @@
 
 pub mod beam_ng_mod;
 pub mod direct_export;
+pub mod sandbox;
 
 use std::io::{Read, Write};
 use std::path::Path;
-use crate::source::{BEAM_NG_MOD_SOURCE_ID, DIRECT_EXPORT_SOURCE_ID};
-use crate::CrateEngineMetadata;
+use utils::numeric::interpolate_curve;
+use crate::source::{BEAM_NG_MOD_SOURCE_ID, DIRECT_EXPORT_SOURCE_ID, SANDBOX_SOURCE_ID};
+use crate::{CrateEngineError, CrateEngineMetadata};
 
 #[derive(Debug, Clone)]
 pub enum CrateEngineData {
     BeamNGMod(beam_ng_mod::Data),
-    DirectExport(direct_export::Data)
+    DirectExport(direct_export::Data),
+    Sandbox(sandbox::Data)
 }
 
 impl CrateEngineData {
@@ -38,7 +41,7 @@ impl CrateEngineData {
         Ok(CrateEngineData::BeamNGMod(beam_ng_mod::Data::from_beamng_mod_zip(mod_path, options)?))
     }
 
-    pub fn from_reader(metadata: &CrateEngineMetadata, reader: &mut impl Read) -> Result<CrateEngineData, String> {
+    pub fn from_reader(metadata: &CrateEngineMetadata, reader: &mut impl Read) -> Result<CrateEngineData, CrateEngineError> {
         let source = metadata.get_source();
         match source.source_id {
             BEAM_NG_MOD_SOURCE_ID => {
@@ -47,14 +50,18 @@ impl CrateEngineData {
             DIRECT_EXPORT_SOURCE_ID=> {
                 Ok(CrateEngineData::DirectExport(direct_export::Data::from_reader(metadata, reader)?))
             },
-            i => Err(format!("Unknown data source with id {}", i))
+            SANDBOX_SOURCE_ID => {
+                Ok(CrateEngineData::Sandbox(sandbox::Data::from_reader(metadata, reader)?))
+            },
+            i => Err(CrateEngineError::UnknownSource(i))
         }
     }
 
     pub fn version_int(&self) -> u16 {
         match self {
             CrateEngineData::BeamNGMod(d) => d.version_int(),
-            CrateEngineData::DirectExport(d) => d.version_int()
+            CrateEngineData::DirectExport(d) => d.version_int(),
+            CrateEngineData::Sandbox(d) => d.version_int()
         }
     }
 
@@ -62,6 +69,64 @@ impl CrateEngineData {
         match self {
             CrateEngineData::BeamNGMod(d) => d.serialize_into(writer),
             CrateEngineData::DirectExport(d) => d.serialise_into(writer),
+            CrateEngineData::Sandbox(d) => d.serialize_into(writer),
+        }
+    }
+
+    /// Return a vector containing pairs of RPM, Torque (Nm). This will be empty if the engine
+    /// has no curve data
+    pub fn torque_curve(&self) -> Vec<(f64, f64)> {
+        match self {
+            CrateEngineData::BeamNGMod(d) => {
+                pair_curve_data(&d.automation_data().rpm_curve, &d.automation_data().torque_curve)
+            },
+            CrateEngineData::DirectExport(d) => d.torque_curve(),
+            CrateEngineData::Sandbox(d) => {
+                pair_curve_data(&d.automation_data().rpm_curve, &d.automation_data().torque_curve)
+            }
+        }
+    }
+
+    /// Return a vector containing pairs of RPM, Power (kW). This will be empty if the engine
+    /// has no curve data
+    pub fn power_curve(&self) -> Vec<(f64, f64)> {
+        match self {
+            CrateEngineData::BeamNGMod(d) => {
+                pair_curve_data(&d.automation_data().rpm_curve, &d.automation_data().power_curve)
+            },
+            CrateEngineData::DirectExport(d) => d.power_curve(),
+            CrateEngineData::Sandbox(d) => {
+                pair_curve_data(&d.automation_data().rpm_curve, &d.automation_data().power_curve)
+            }
         }
     }
+
+    /// Return pairs of RPM, torque change (Nm) going from this engine to `other` at each RPM of
+    /// this engine's torque curve that `other` also covers. This will be empty if either engine
+    /// has no curve data
+    pub fn torque_curve_delta(&self, other: &CrateEngineData) -> Vec<(f64, f64)> {
+        let other_curve = other.torque_curve();
+        self.torque_curve().into_iter().filter_map(|(rpm, torque)| {
+            interpolate_curve(&other_curve, rpm, false).map(|other_torque| (rpm, other_torque - torque))
+        }).collect()
+    }
+}
+
+impl CrateEngineData {
+    /// The hashes of the data sections that are recorded in the metadata source, in the same
+    /// order, paired with a name for the section
+    pub fn section_hashes(&self) -> Vec<(&'static str, Option<[u8; 32]>)> {
+        match self {
+            CrateEngineData::BeamNGMod(d) => vec![
+                ("engine jbeam", d.jbeam_data_hash()),
+                ("Automation engine", d.automation_data_hash())
+            ],
+            CrateEngineData::DirectExport(_) => Vec::new(),
+            CrateEngineData::Sandbox(d) => vec![("Automation engine", d.automation_data_hash())]
+        }
+    }
+}
+
+fn pair_curve_data(rpm_curve: &[f64], value_curve: &[f64]) -> Vec<(f64, f64)> {
+    rpm_curve.iter().cloned().zip(value_curve.iter().cloned()).collect()
 }
@@ -0,0 +1,109 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::{Read, Write};
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use automation::sandbox::{AlRimaEngineData, EngineV1};
+use utils::hash::create_sha256_hash_array;
+use crate::{CrateEngineError, CrateEngineMetadata};
+
+/// Crate engine data loaded directly from an Automation sandbox db, without a BeamNG mod export
+#[derive(Debug, Clone)]
+pub enum Data {
+    V1(DataV1),
+    /// [`DataV1`] followed by the engine fields added in Automation 4.3 (Al Rima)
+    V2(DataV1)
+}
+
+impl Data {
+    pub const AL_RIMA_VERSION: u16 = 2;
+
+    pub fn from_engine(automation_variant_data: EngineV1) -> Data {
+        Data::V2(DataV1 { automation_variant_data })
+    }
+
+    pub fn from_reader(metadata: &CrateEngineMetadata, reader: &mut impl Read) -> Result<Data, CrateEngineError> {
+        let mut internal_data: DataV1 =
+            deserialize_from(&mut *reader).map_err(|e| CrateEngineError::from_bincode("sandbox", e))?;
+        match metadata.data_version() {
+            DataV1::VERSION => Ok(Data::V1(internal_data)),
+            Data::AL_RIMA_VERSION => {
+                let al_rima_data: AlRimaEngineData = deserialize_from(reader)
+                    .map_err(|e| CrateEngineError::from_bincode("Automation 4.3 sandbox", e))?;
+                internal_data.automation_variant_data.al_rima = al_rima_data;
+                Ok(Data::V2(internal_data))
+            }
+            version => Err(CrateEngineError::UnknownDataVersion(version))
+        }
+    }
+
+    pub fn version_int(&self) -> u16 {
+        match &self {
+            Data::V1(d) => d.version(),
+            Data::V2(_) => Data::AL_RIMA_VERSION
+        }
+    }
+
+    pub fn serialize_into(&self, writer: &mut impl Write) -> bincode::Result<()> {
+        match self {
+            Data::V1(d) => serialize_into(writer, d),
+            Data::V2(d) => {
+                serialize_into(&mut *writer, d)?;
+                serialize_into(writer, &d.automation_variant_data.al_rima)
+            }
+        }
+    }
+
+    pub fn automation_data(&self) -> &EngineV1 {
+        match self {
+            Data::V1(d) | Data::V2(d) => &d.automation_variant_data
+        }
+    }
+
+    pub fn automation_data_hash(&self) -> Option<[u8; 32]> {
+        match self {
+            Data::V1(d) | Data::V2(d) => d.automation_data_hash()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DataV1 {
+    automation_variant_data: EngineV1
+}
+
+impl DataV1 {
+    pub const VERSION: u16 = 1;
+
+    pub fn version(&self) -> u16 {
+        Self::VERSION
+    }
+
+    pub fn automation_data_hash(&self) -> Option<[u8; 32]> {
+        let mut auto_hasher = Sha256::new();
+        auto_hasher.update(&self.automation_variant_data.family_data_checksum_data());
+        auto_hasher.update(&self.automation_variant_data.variant_data_checksum_data());
+        auto_hasher.update(&self.automation_variant_data.result_data_checksum_data());
+        create_sha256_hash_array(auto_hasher)
+    }
+}
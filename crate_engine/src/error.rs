@@ -0,0 +1,63 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io;
+
+/// The reasons a crate engine file can fail to load
+#[derive(thiserror::Error, Debug)]
+pub enum CrateEngineError {
+    #[error("unknown metadata version {0}")]
+    UnknownVersion(u16),
+    #[error("unknown data version {0}")]
+    UnknownDataVersion(u16),
+    #[error("unknown data source with id {0}")]
+    UnknownSource(u16),
+    #[error("the file is truncated. {0}")]
+    TruncatedData(String),
+    #[error("the {0} data doesn't match the hash stored in the metadata")]
+    HashMismatch(String),
+    #[error("the {0} data is corrupt. {1}")]
+    CorruptData(String, String),
+    #[error("io error. {0}")]
+    IoError(#[from] io::Error)
+}
+
+impl CrateEngineError {
+    /// Classify a deserialisation failure of the named section. Running out of data partway
+    /// through is reported as [`CrateEngineError::TruncatedData`]
+    pub(crate) fn from_bincode(section: &str, err: bincode::Error) -> CrateEngineError {
+        match *err {
+            bincode::ErrorKind::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                CrateEngineError::TruncatedData(format!("Ran out of data reading the {} section", section))
+            }
+            e => CrateEngineError::CorruptData(section.to_string(), e.to_string())
+        }
+    }
+
+    pub(crate) fn from_header_read(err: io::Error) -> CrateEngineError {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                CrateEngineError::TruncatedData("Ran out of data reading the version header".to_string())
+            }
+            _ => CrateEngineError::IoError(err)
+        }
+    }
+}
@@ -22,7 +22,9 @@
 pub mod metadata;
 pub mod source;
 mod data;
+mod error;
 
+use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -31,16 +33,22 @@ use tracing::{warn};
 use unwrap_infallible::UnwrapInfallible;
 use beam_ng::jbeam;
 
+pub use error::CrateEngineError;
 pub use metadata::CrateEngineMetadata;
 pub use data::CrateEngineData;
 pub use data::beam_ng_mod;
 pub use data::direct_export;
+pub use data::sandbox;
 
 pub type FromBeamNGModOptions = beam_ng_mod::CreationOptions;
 
 
 pub const CRATE_ENGINE_FILE_SUFFIX: &'static str = "eng";
 
+/// The fractional difference between the stored peak power and the peak of the power curve
+/// above which a crate engine's data is considered inconsistent
+const PEAK_POWER_TOLERANCE: f64 = 0.05;
+
 
 pub struct CrateEngine {
     metadata: CrateEngineMetadata,
@@ -52,7 +60,7 @@ impl CrateEngine {
         let crate_data = CrateEngineData::from_beamng_mod_zip(mod_path, options)?;
         let data = match crate_data {
             CrateEngineData::BeamNGMod(ref d) => match d {
-                beam_ng_mod::Data::V1(d) => d
+                beam_ng_mod::Data::V1(d) | beam_ng_mod::Data::V2(d) => d
             }
             _ => return Err("Should have created crate engine from beamng data".to_string())
         };
@@ -81,12 +89,14 @@ impl CrateEngine {
             None => "Unknown".to_string(),
             Some(f) => f.clone()
         };
+        warn_on_peak_power_mismatch(&name, data.automation_data().peak_power, &data.automation_data().power_curve);
         let metadata = metadata::CurrentMetadataType {
+            source: source::DataSource::from_beam_ng_mod(vec![engine_jbeam_hash, automation_data_hash]),
             data_version: crate_data.version_int(),
             automation_version: data.automation_data().variant_version,
             name,
-            automation_data_hash,
-            engine_jbeam_hash,
+            family_name: data.automation_data().family_name.clone(),
+            variant_name: data.automation_data().variant_name.clone(),
             build_year: data.automation_data().get_variant_build_year(),
             block_config: data.automation_data().get_block_config(),
             head_config: data.automation_data().get_head_config(),
@@ -98,7 +108,9 @@ impl CrateEngine {
             peak_power_rpm: data.automation_data().peak_power_rpm.round() as u32,
             peak_torque: data.automation_data().peak_torque.round() as u32,
             peak_torque_rpm: data.automation_data().peak_torque_rpm.round() as u32,
-            max_rpm: data.automation_data().max_rpm.round() as u32
+            max_rpm: data.automation_data().max_rpm.round() as u32,
+            quality: Some(metadata::QualitySummary::from_engine(data.automation_data())),
+            exporter_script_version: None
         };
 
         Ok(CrateEngine{
@@ -110,6 +122,7 @@ impl CrateEngine {
     pub fn from_exporter_data(data_type: direct_export::Data) -> Result<CrateEngine, String> {
         let metadata = match &data_type {
             direct_export::Data::V1(data) => {
+                data.validate()?;
                 let automation_version = data.float_data["Info"]["GameVersion"].round() as u64;
                 let name = format!("{} {}", data.string_data["Info"]["FamilyName"], data.string_data["Info"]["VariantName"]);
                 let build_year = data.float_data["Info"]["VariantYear"].round() as u16;
@@ -120,12 +133,16 @@ impl CrateEngine {
                 ).unwrap_infallible();
                 let capacity = (data.float_data["Tune"]["Displacement"] * 1000.0).round() as u32;
                 let aspiration = automation::AspirationType::from_str(&data.string_data["Parts"]["Aspiration"]).unwrap_infallible();
+                let power_curve: Vec<f64> = data.power_curve().into_iter().map(|(_, power)| power).collect();
+                warn_on_peak_power_mismatch(&name, data.float_data["Results"]["PeakPower"] as f64, &power_curve);
 
-                metadata::MetadataV2 {
+                metadata::CurrentMetadataType {
                     source: source::DataSource::from_direct_export(),
                     data_version: data.version_int(),
                     automation_version,
                     name,
+                    family_name: data.string_data["Info"]["FamilyName"].clone(),
+                    variant_name: data.string_data["Info"]["VariantName"].clone(),
                     build_year,
                     block_config,
                     head_config,
@@ -137,19 +154,78 @@ impl CrateEngine {
                     peak_power_rpm: data.float_data["Results"]["PeakPowerRPM"].round() as u32,
                     peak_torque: data.float_data["Results"]["PeakTorque"].round() as u32,
                     peak_torque_rpm: data.float_data["Results"]["PeakTorqueRPM"].round() as u32,
-                    max_rpm: data.float_data["Results"]["MaxRPM"].round() as u32
+                    max_rpm: data.float_data["Results"]["MaxRPM"].round() as u32,
+                    quality: metadata::QualitySummary::from_float_data(&data.float_data),
+                    exporter_script_version: Some(data.exporter_script_version)
                 }
             }
         };
         Ok(CrateEngine{
-            metadata: CrateEngineMetadata::MetadataV2(metadata),
+            metadata: CrateEngineMetadata::from_current_version(metadata),
             data: CrateEngineData::DirectExport(data_type)
         })
     }
 
+    /// Create a crate engine from engine data loaded from an Automation sandbox db. No BeamNG
+    /// mod export is needed but there is also no jbeam or .car file data available
+    pub fn from_sandbox_engine(engine: automation::sandbox::EngineV1) -> Result<CrateEngine, String> {
+        let crate_data = sandbox::Data::from_engine(engine);
+        let automation_data_hash = crate_data.automation_data_hash();
+        if automation_data_hash.is_none() {
+            warn!("Failed to calculate automation data hash");
+        }
+        let data = crate_data.automation_data();
+        let fuel = match data.fuel_type.as_ref() {
+            None => "Unknown".to_string(),
+            Some(f) => f.clone()
+        };
+        warn_on_peak_power_mismatch(&data.friendly_name(), data.peak_power, &data.power_curve);
+        let metadata = metadata::CurrentMetadataType {
+            source: source::DataSource::from_sandbox(automation_data_hash),
+            data_version: crate_data.version_int(),
+            automation_version: data.variant_version,
+            name: data.friendly_name(),
+            family_name: data.family_name.clone(),
+            variant_name: data.variant_name.clone(),
+            build_year: data.get_variant_build_year(),
+            block_config: data.get_block_config(),
+            head_config: data.get_head_config(),
+            valves: data.get_valve_type(),
+            capacity: data.get_capacity_cc(),
+            aspiration: data.get_aspiration(),
+            fuel,
+            peak_power: data.peak_power.round() as u32,
+            peak_power_rpm: data.peak_power_rpm.round() as u32,
+            peak_torque: data.peak_torque.round() as u32,
+            peak_torque_rpm: data.peak_torque_rpm.round() as u32,
+            max_rpm: data.max_rpm.round() as u32,
+            quality: Some(metadata::QualitySummary::from_engine(data)),
+            exporter_script_version: None
+        };
+        Ok(CrateEngine{
+            metadata: CrateEngineMetadata::from_current_version(metadata),
+            data: CrateEngineData::Sandbox(crate_data)
+        })
+    }
+
     pub fn deserialize_from(reader: &mut impl Read) -> Result<CrateEngine, String> {
-        let metadata = CrateEngineMetadata::from_reader(reader)?;
+        Self::validate_from_reader(reader).map_err(|e| e.to_string())
+    }
+
+    /// Read a crate engine from `reader` and check that the data sections match the hashes
+    /// stored in its metadata
+    pub fn validate_from_reader(reader: &mut impl Read) -> Result<CrateEngine, CrateEngineError> {
+        let metadata = CrateEngineMetadata::quick_check(reader)?;
         let data = CrateEngineData::from_reader(&metadata, reader)?;
+        let expected_hashes = metadata.get_source().hashes;
+        for ((section_name, actual), expected) in data.section_hashes().into_iter().zip(expected_hashes) {
+            // A hash that couldn't be calculated when the engine was created can't be checked
+            if let (Some(expected), Some(actual)) = (expected, actual) {
+                if expected != actual {
+                    return Err(CrateEngineError::HashMismatch(section_name.to_string()));
+                }
+            }
+        }
         Ok(CrateEngine { metadata, data })
     }
 
@@ -170,17 +246,127 @@ impl CrateEngine {
         &self.data
     }
 
+    pub fn metadata(&self) -> &CrateEngineMetadata {
+        &self.metadata
+    }
+
+    pub fn into_metadata(self) -> CrateEngineMetadata {
+        self.metadata
+    }
+
+    /// Recreate a BeamNG mod zip in `out_dir` from the data stored in the crate engine. Only
+    /// crate engines that were created from a BeamNG mod contain the data needed to do this
+    pub fn export_to_beamng_zip(&self, out_dir: &Path) -> Result<PathBuf, String> {
+        let mod_data = match &self.data {
+            CrateEngineData::BeamNGMod(d) => d,
+            CrateEngineData::DirectExport(_) => {
+                return Err(format!("{} was created from a direct Automation export so has no BeamNG mod data to export", self.name()))
+            }
+            CrateEngineData::Sandbox(_) => {
+                return Err(format!("{} was created from the Automation sandbox so has no BeamNG mod data to export", self.name()))
+            }
+        };
+        if !out_dir.is_dir() {
+            return Err(format!("Output path {} not found", out_dir.display()));
+        }
+        let zip_path = utils::filesystem::create_safe_filename_in_path(out_dir, self.name(), "zip");
+        let vehicle_name = zip_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        mod_data.write_mod_zip(&zip_path, &vehicle_name)?;
+        Ok(zip_path)
+    }
+
     pub fn write_to_path(&self, path: PathBuf) -> bincode::Result<PathBuf> {
         if !path.is_dir() {
             return Err(bincode::Error::from(
                 bincode::ErrorKind::Custom(format!("Output path {} not found", path.display()))
             ))
         }
-        let crate_path = utils::filesystem::create_safe_filename_in_path(&path, self.name(), CRATE_ENGINE_FILE_SUFFIX);
+        let crate_path = utils::filesystem::create_safe_filename_in_path(&path,
+                                                                         &crate_engine_file_stem(self.name()),
+                                                                         CRATE_ENGINE_FILE_SUFFIX);
         let mut f = File::create(&crate_path)?;
         self.serialize_to(&mut f)?;
         Ok(crate_path)
     }
+
+    /// Change the name of the crate engine stored in the file at `path`. Only the metadata is
+    /// rewritten; the engine data is copied across byte for byte. The file is renamed to match
+    /// the new name in the same way as [`CrateEngine::write_to_path`] names files, but the rename
+    /// is refused rather than picking a unique name if another file already has that name.
+    /// Returns the new path of the file
+    pub fn rename_file(path: &Path, new_name: &str) -> Result<PathBuf, String> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(String::from("The new name can't be empty"));
+        }
+        let file_data = fs::read(path).map_err(|e| {
+            format!("Failed to read {}. {}", path.display(), e.to_string())
+        })?;
+        let mut crate_eng = CrateEngine::deserialize_from(&mut file_data.as_slice())?;
+        let mut data_section = file_data.as_slice();
+        CrateEngineMetadata::quick_check(&mut data_section).map_err(|e| e.to_string())?;
+
+        let parent = path.parent().ok_or_else(|| {
+            format!("Couldn't find the folder containing {}", path.display())
+        })?;
+        let new_path = parent.join(format!("{}.{}",
+                                           crate_engine_file_stem(new_name),
+                                           CRATE_ENGINE_FILE_SUFFIX));
+        if new_path != path && new_path.exists() {
+            return Err(format!("{} already exists", new_path.display()));
+        }
+
+        crate_eng.metadata.set_name(new_name.to_string());
+        let mut new_data = Vec::new();
+        crate_eng.metadata.serialize_into(&mut new_data).map_err(|e| e.to_string())?;
+        new_data.extend_from_slice(data_section);
+
+        let tmp_path = new_path.with_extension(format!("{}.tmp", CRATE_ENGINE_FILE_SUFFIX));
+        let write_result = fs::write(&tmp_path, new_data).and_then(|_| fs::rename(&tmp_path, &new_path));
+        if let Err(e) = write_result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(format!("Failed to write {}. {}", new_path.display(), e.to_string()));
+        }
+        if new_path != path {
+            fs::remove_file(path).map_err(|e| {
+                format!("Failed to remove {}. {}", path.display(), e.to_string())
+            })?;
+        }
+        Ok(new_path)
+    }
+}
+
+/// Returns the fractional difference between `stored_peak_kw` and the highest value in
+/// `power_curve_kw`, or `None` if the curve is empty
+fn peak_power_divergence(stored_peak_kw: f64, power_curve_kw: &[f64]) -> Option<f64> {
+    let curve_peak = power_curve_kw.iter().cloned().fold(None, |max: Option<f64>, val| {
+        Some(max.map_or(val, |m| m.max(val)))
+    })?;
+    if stored_peak_kw <= 0.0 {
+        return Some(if curve_peak > 0.0 { 1.0 } else { 0.0 });
+    }
+    Some((curve_peak - stored_peak_kw).abs() / stored_peak_kw)
+}
+
+fn warn_on_peak_power_mismatch(name: &str, stored_peak_kw: f64, power_curve_kw: &[f64]) {
+    match peak_power_divergence(stored_peak_kw, power_curve_kw) {
+        None => warn!("{} has no power curve data", name),
+        Some(divergence) if divergence > PEAK_POWER_TOLERANCE => {
+            warn!("{} peak power of {:.0}kW differs from the peak of its power curve by {:.1}%. \
+                   The power curve data may be missing or mismatched", name, stored_peak_kw, divergence * 100.0)
+        }
+        Some(_) => {}
+    }
+}
+
+/// The filename, without the suffix, used for a crate engine called `name`. Names made up only
+/// of characters that can't be used in a filename get a name made from a hash of `name` instead
+fn crate_engine_file_stem(name: &str) -> String {
+    let stem = utils::filesystem::sanitize_name(name);
+    match stem.trim_matches('_').is_empty() {
+        true => format!("crate_engine_{}", &utils::hash::sha256_hex(name.as_bytes())[..12]),
+        false => stem
+    }
 }
 
 fn _get_name_from_jbeam_data(engine_data: &Vec<u8>) -> Option<String> {
@@ -202,6 +388,346 @@ fn _get_name_from_jbeam_data(engine_data: &Vec<u8>) -> Option<String> {
 }
 
 
+#[test]
+fn peak_power_divergence_from_curve() {
+    assert_eq!(peak_power_divergence(100.0, &[]), None);
+    assert_eq!(peak_power_divergence(100.0, &[50.0, 100.0, 90.0]), Some(0.0));
+    assert!(peak_power_divergence(100.0, &[50.0, 103.0, 90.0]).unwrap() <= PEAK_POWER_TOLERANCE);
+    assert!(peak_power_divergence(100.0, &[50.0, 80.0, 70.0]).unwrap() > PEAK_POWER_TOLERANCE);
+    assert_eq!(peak_power_divergence(0.0, &[0.0]), Some(0.0));
+}
+
+#[test]
+fn direct_export_curves_pair_rpm_with_values() {
+    let mut data = direct_export::DataV1::new();
+    for (idx, (rpm, torque)) in [(1000.0, 100.0), (4000.0, 200.0), (7000.0, 150.0)].into_iter().enumerate() {
+        data.add_curve_data("RPM".to_string(), idx, rpm);
+        data.add_curve_data("Torque".to_string(), idx, torque);
+    }
+    data.add_curve_data("RPM".to_string(), 3, 7500.0);
+
+    let engine_data = CrateEngineData::DirectExport(direct_export::Data::V1(data));
+    assert_eq!(engine_data.torque_curve(), vec![(1000.0, 100.0), (4000.0, 200.0), (7000.0, 150.0)]);
+    let power_curve = engine_data.power_curve();
+    assert_eq!(power_curve.len(), 3);
+    assert_eq!(power_curve[1].0, 4000.0);
+    assert!((power_curve[1].1 - utils::units::calculate_power_kw(4000.0, 200.0) as f64).abs() < 1e-6);
+}
+
+/// The metadata of a direct export I4 engine for tests to build on
+#[cfg(test)]
+fn test_metadata() -> metadata::MetadataV2 {
+    use automation::{AspirationType, BlockConfig, HeadConfig, Valves};
+
+    metadata::MetadataV2 {
+        source: source::DataSource::from_direct_export(),
+        data_version: direct_export::DataV1::VERSION,
+        automation_version: 2412240000,
+        name: "Test I4".to_string(),
+        build_year: 2020,
+        block_config: BlockConfig::I4,
+        head_config: HeadConfig::DOHC,
+        valves: Valves::Four,
+        capacity: 2000,
+        aspiration: AspirationType::NA,
+        fuel: "Premium".to_string(),
+        peak_power: 150,
+        peak_power_rpm: 6500,
+        peak_torque: 200,
+        peak_torque_rpm: 4500,
+        max_rpm: 7000
+    }
+}
+
+#[test]
+fn validate_reports_truncated_and_unknown_data() -> Result<(), String> {
+    let crate_eng = CrateEngine {
+        metadata: CrateEngineMetadata::MetadataV2(test_metadata()),
+        data: CrateEngineData::DirectExport(direct_export::Data::V1(direct_export::DataV1::new()))
+    };
+    let mut bytes = Vec::new();
+    crate_eng.serialize_to(&mut bytes).map_err(|e| e.to_string())?;
+
+    let loaded = CrateEngine::validate_from_reader(&mut bytes.as_slice()).map_err(|e| e.to_string())?;
+    assert_eq!(loaded.name(), "Test I4");
+    assert!(loaded.export_to_beamng_zip(&std::env::temp_dir()).unwrap_err().contains("direct Automation export"));
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(matches!(CrateEngine::validate_from_reader(&mut &truncated[..]), Err(CrateEngineError::TruncatedData(_))));
+    assert!(matches!(CrateEngineMetadata::quick_check(&mut &bytes[..1]), Err(CrateEngineError::TruncatedData(_))));
+
+    let mut unknown_version = bytes.clone();
+    unknown_version[0] = 99;
+    assert!(matches!(CrateEngineMetadata::quick_check(&mut unknown_version.as_slice()), Err(CrateEngineError::UnknownVersion(99))));
+    Ok(())
+}
+
+#[test]
+fn rename_file_only_changes_metadata() -> Result<(), String> {
+    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let out_dir = temp_dir.path().join("crate_rename");
+    fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+    let mut data = direct_export::DataV1::new();
+    for (idx, (rpm, torque)) in [(1000.0, 100.0), (4000.0, 200.0), (7000.0, 150.0)].into_iter().enumerate() {
+        data.add_curve_data("RPM".to_string(), idx, rpm);
+        data.add_curve_data("Torque".to_string(), idx, torque);
+    }
+    let crate_eng = CrateEngine {
+        metadata: CrateEngineMetadata::MetadataV2(test_metadata()),
+        data: CrateEngineData::DirectExport(direct_export::Data::V1(data))
+    };
+    let original_path = crate_eng.write_to_path(out_dir.clone()).map_err(|e| e.to_string())?;
+    let data_section_hash = |path: &Path| -> Result<String, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let mut data_section = bytes.as_slice();
+        CrateEngineMetadata::quick_check(&mut data_section).map_err(|e| e.to_string())?;
+        Ok(utils::hash::sha256_hex(data_section))
+    };
+    let original_hash = data_section_hash(&original_path)?;
+
+    let renamed_path = CrateEngine::rename_file(&original_path, "Renamed I4")?;
+    assert_eq!(renamed_path, out_dir.join("Renamed_I4.eng"));
+    assert!(!original_path.exists());
+    assert_eq!(data_section_hash(&renamed_path)?, original_hash);
+    let renamed = CrateEngine::deserialize_from(&mut File::open(&renamed_path).map_err(|e| e.to_string())?)?;
+    assert_eq!(renamed.name(), "Renamed I4");
+    assert_eq!(renamed.metadata().peak_power(), 150);
+
+    let other_path = crate_eng.write_to_path(out_dir.clone()).map_err(|e| e.to_string())?;
+    assert!(CrateEngine::rename_file(&other_path, "Renamed I4").is_err());
+    assert!(CrateEngine::rename_file(&other_path, "  ").is_err());
+    assert_eq!(data_section_hash(&renamed_path)?, original_hash);
+
+    let unicode_path = CrateEngine::rename_file(&other_path, "Türbo 怪物")?;
+    assert_eq!(unicode_path, out_dir.join("Türbo_怪物.eng"));
+    let hashed_path = CrateEngine::rename_file(&unicode_path, "???")?;
+    let hashed_name = hashed_path.file_name().unwrap().to_string_lossy().into_owned();
+    assert!(hashed_name.starts_with("crate_engine_") && hashed_name.ends_with(".eng"), "{}", hashed_name);
+    let renamed = CrateEngine::deserialize_from(&mut File::open(&hashed_path).map_err(|e| e.to_string())?)?;
+    assert_eq!(renamed.name(), "???");
+    Ok(())
+}
+
+#[test]
+fn written_mod_zip_can_be_read_by_beam_ng() -> Result<(), String> {
+    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let out_dir = temp_dir.path().join("crate_mod_export");
+    std::fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+    let mut jbeam_files = std::collections::HashMap::new();
+    jbeam_files.insert("camso_engine_abcde.jbeam".to_string(), br#"{"Camso_Engine_abcde": {}}"#.to_vec());
+    jbeam_files.insert("camso_engine_structure_abcde.jbeam".to_string(), b"{}".to_vec());
+    let zip_path = out_dir.join("Test_I4.zip");
+    beam_ng_mod::write_mod_zip(&zip_path, "Test_I4", &jbeam_files, b"car data", br#"{"Name": "Test I4"}"#, Some(&b"license".to_vec()))?;
+
+    let mut mod_data = beam_ng::ModData::from_path(&zip_path)?;
+    assert_eq!(mod_data.get_info_json_map().get("Name").and_then(|v| v.as_str()), Some("Test I4"));
+    assert_eq!(mod_data.get_automation_car_file_data(), Some(&b"car data".to_vec()));
+    assert_eq!(mod_data.take_license_data(), Some(b"license".to_vec()));
+    assert_eq!(mod_data.take_jbeam_file_data(), jbeam_files);
+    Ok(())
+}
+
+#[test]
+fn metadata_json_has_consistent_fields() -> Result<(), String> {
+    use automation::{AspirationType, BlockConfig, HeadConfig, Valves};
+    use metadata::{MetadataV1, MetadataV2};
+    use source::BEAM_NG_MOD_SOURCE_ID;
+
+    let v1 = CrateEngineMetadata::MetadataV1(MetadataV1 {
+        data_version: 2,
+        automation_version: 2211220000,
+        name: "Test V6".to_string(),
+        engine_jbeam_hash: None,
+        automation_data_hash: None,
+        build_year: 1990,
+        block_config: BlockConfig::V6_60,
+        head_config: HeadConfig::DOHC,
+        valves: Valves::Four,
+        capacity: 3000,
+        aspiration: AspirationType::NA,
+        fuel: "Premium".to_string(),
+        peak_power: 200,
+        peak_power_rpm: 7000,
+        peak_torque: 300,
+        peak_torque_rpm: 5000,
+        max_rpm: 7500
+    });
+    let v2 = CrateEngineMetadata::MetadataV2(MetadataV2 {
+        data_version: 2,
+        aspiration: AspirationType::Turbo,
+        peak_power: 250,
+        peak_torque: 400,
+        peak_torque_rpm: 3500,
+        ..test_metadata()
+    });
+
+    let parse = |m: &CrateEngineMetadata| -> Result<serde_json::Value, String> {
+        serde_json::from_str(&m.to_json()?).map_err(|e| e.to_string())
+    };
+    let v1_json = parse(&v1)?;
+    let v2_json = parse(&v2)?;
+    let keys = |v: &serde_json::Value| v.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+    assert_eq!(keys(&v1_json), keys(&v2_json));
+    assert_eq!(v1_json["metadata_version"], 1);
+    assert_eq!(v1_json["source"]["id"], BEAM_NG_MOD_SOURCE_ID);
+    assert!(v1_json["quality"].is_null());
+    assert_eq!(v2_json["metadata_version"], 2);
+    assert_eq!(v2_json["block_config"], "Inline 4");
+    Ok(())
+}
+
+#[test]
+fn exported_quality_sliders_stored_in_metadata() -> Result<(), String> {
+    let mut data = direct_export::DataV1::new();
+    for (group, key, value) in [("Info", "FamilyName", "Test"), ("Info", "VariantName", "I4"),
+                                ("Parts", "BlockType", "Inline 4"), ("Parts", "HeadType", "DOHC"),
+                                ("Parts", "Aspiration", "NA"), ("Fuel", "Type", "Premium")] {
+        data.add_string(group.to_string(), key.to_string(), value.to_string());
+    }
+    for (group, key, value) in [("Info", "GameVersion", 2412240000.0), ("Info", "VariantYear", 2020.0),
+                                ("Parts", "IntakeValves", 2.0), ("Parts", "ExhaustValves", 2.0),
+                                ("Tune", "Displacement", 2.0), ("Results", "PeakPower", 150.0),
+                                ("Results", "PeakPowerRPM", 6500.0), ("Results", "PeakTorque", 200.0),
+                                ("Results", "PeakTorqueRPM", 4500.0), ("Results", "MaxRPM", 7000.0),
+                                ("Quality", "QualityFamily", 1.0), ("Quality", "QualityBottomEnd", -2.0),
+                                ("Quality", "QualityTopEnd", 3.0), ("Quality", "QualityFuelSystem", 5.0)] {
+        data.add_float(group.to_string(), key.to_string(), value);
+    }
+    let crate_eng = CrateEngine::from_exporter_data(direct_export::Data::V1(data))?;
+    let mut bytes = Vec::new();
+    crate_eng.serialize_to(&mut bytes).map_err(|e| e.to_string())?;
+
+    let loaded = CrateEngine::deserialize_from(&mut bytes.as_slice())?;
+    assert_eq!(loaded.metadata().get_metadata_version_u16(), 3);
+    assert_eq!((loaded.metadata().family_name(), loaded.metadata().variant_name()), ("Test", "I4"));
+    assert_eq!(loaded.metadata().quality(), Some(&metadata::QualitySummary {
+        family: Some(1),
+        bottom_end: Some(-2),
+        top_end: Some(3),
+        aspiration: None,
+        fuel_system: Some(5),
+        exhaust: None
+    }));
+    Ok(())
+}
+
+#[test]
+fn quality_unknown_without_exported_sliders() {
+    let mut float_data = std::collections::BTreeMap::new();
+    float_data.insert("Results".to_string(), std::collections::BTreeMap::from([("PeakPower".to_string(), 150.0)]));
+    assert_eq!(metadata::QualitySummary::from_float_data(&float_data), None);
+
+    float_data.get_mut("Results").unwrap().insert("QualityExhaust".to_string(), -1.4);
+    let quality = metadata::QualitySummary::from_float_data(&float_data).unwrap();
+    assert_eq!(quality.exhaust, Some(-1));
+    assert_eq!(quality.areas().iter().filter(|(_, value)| value.is_none()).count(), 5);
+    assert_eq!(metadata::QualitySummary::format_area(quality.family), "Unknown");
+    assert_eq!(metadata::QualitySummary::format_area(quality.exhaust), "-1");
+}
+
+#[test]
+fn metadata_diff_marks_changed_fields() {
+    use automation::{AspirationType, BlockConfig, HeadConfig, Valves};
+    use metadata::{MetadataV2, MetadataV3, QualitySummary};
+    use utils::units::{PowerUnit, TorqueUnit};
+
+    let v2 = CrateEngineMetadata::MetadataV2(MetadataV2 {
+        name: "Dawn V6".to_string(),
+        block_config: BlockConfig::V6_60,
+        capacity: 3000,
+        peak_power: 220,
+        peak_power_rpm: 7000,
+        peak_torque: 330,
+        peak_torque_rpm: 5000,
+        max_rpm: 7500,
+        ..test_metadata()
+    });
+    let v3 = CrateEngineMetadata::MetadataV3(MetadataV3 {
+        source: source::DataSource::from_direct_export(),
+        data_version: 1,
+        automation_version: 2412240000,
+        name: "Dawn V6".to_string(),
+        family_name: "Dawn".to_string(),
+        variant_name: "V6".to_string(),
+        build_year: 2020,
+        block_config: BlockConfig::V6_60,
+        head_config: HeadConfig::DOHC,
+        valves: Valves::Four,
+        capacity: 3000,
+        aspiration: AspirationType::Turbo,
+        fuel: "Premium".to_string(),
+        peak_power: 290,
+        peak_power_rpm: 6500,
+        peak_torque: 480,
+        peak_torque_rpm: 4000,
+        max_rpm: 7500,
+        quality: Some(QualitySummary { top_end: Some(2), ..QualitySummary::default() }),
+        exporter_script_version: None
+    });
+
+    let diff = v2.diff(&v3, PowerUnit::Bhp, TorqueUnit::LbFt);
+    let changed: Vec<&str> = diff.iter().filter(|entry| entry.is_changed()).map(|entry| entry.field.as_str()).collect();
+    assert_eq!(&changed[..3], &["Aspiration", "Peak power", "Peak torque"]);
+    assert_eq!(&changed[3..], &["Top end quality"]);
+    let power = diff.iter().find(|entry| entry.field == "Peak power").unwrap();
+    assert_eq!(power.value, "295bhp @ 7000 rpm");
+    let torque = diff.iter().find(|entry| entry.field == "Peak torque").unwrap();
+    assert_eq!(torque.value, "243lb-ft @ 5000 rpm");
+    let top_end = diff.iter().find(|entry| entry.field == "Top end quality").unwrap();
+    assert_eq!((top_end.value.as_str(), top_end.other_value.as_str()), ("Unknown", "+2"));
+    assert!(v3.diff(&v3, PowerUnit::Kw, TorqueUnit::Nm).iter().all(|entry| !entry.is_changed()));
+}
+
+#[test]
+fn family_and_variant_derived_from_name_for_older_metadata() {
+    let mut v2 = CrateEngineMetadata::MetadataV2(metadata::MetadataV2 {
+        name: "Dawn - V6 Turbo".to_string(),
+        ..test_metadata()
+    });
+    assert_eq!((v2.family_name(), v2.variant_name()), ("Dawn", "V6 Turbo"));
+    v2.set_name("Dawn V6".to_string());
+    assert_eq!((v2.family_name(), v2.variant_name()), ("Dawn V6", ""));
+}
+
+#[test]
+fn torque_curve_delta_covers_shared_rpm_range() {
+    let curve_data = |points: &[(f32, f32)]| {
+        let mut data = direct_export::DataV1::new();
+        for (idx, (rpm, torque)) in points.iter().enumerate() {
+            data.add_curve_data("RPM".to_string(), idx, *rpm);
+            data.add_curve_data("Torque".to_string(), idx, *torque);
+        }
+        CrateEngineData::DirectExport(direct_export::Data::V1(data))
+    };
+    let base = curve_data(&[(1000.0, 100.0), (4000.0, 200.0), (7000.0, 150.0), (8000.0, 120.0)]);
+    let tuned = curve_data(&[(1000.0, 120.0), (5000.0, 260.0), (7000.0, 170.0)]);
+    assert_eq!(base.torque_curve_delta(&tuned), vec![(1000.0, 20.0), (4000.0, 25.0), (7000.0, 20.0)]);
+    assert!(base.torque_curve_delta(&curve_data(&[])).is_empty());
+}
+
+#[test]
+fn sandbox_engine_keeps_al_rima_fields() -> Result<(), String> {
+    let mut engine: automation::sandbox::EngineV1 = serde_json::from_str(
+        include_str!("test-data/bi_turbo_engine.json")
+    ).map_err(|e| e.to_string())?;
+    engine.al_rima.aspiration_subtype_1 = Some("AspirationSubType_EBoost_Name".to_string());
+    engine.al_rima.eboost_max_boost = Some(1.2);
+    engine.al_rima.friction_torque = Some(64.0);
+    let expected = engine.al_rima.clone();
+
+    let crate_eng = CrateEngine::from_sandbox_engine(engine)?;
+    let mut bytes = Vec::new();
+    crate_eng.serialize_to(&mut bytes).map_err(|e| e.to_string())?;
+
+    let loaded = CrateEngine::deserialize_from(&mut bytes.as_slice())?;
+    assert_eq!(loaded.version(), sandbox::Data::AL_RIMA_VERSION);
+    match loaded.data() {
+        CrateEngineData::Sandbox(data) => assert_eq!(data.automation_data().al_rima, expected),
+        _ => panic!("Expected sandbox data")
+    }
+    Ok(())
+}
+
 #[test]
 fn create_crate_engine() -> Result<(), String> {
     let path = PathBuf::from("C:/Users/zephy/AppData/Local/BeamNG.drive/mods/dawnv6.zip");
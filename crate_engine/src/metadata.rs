@@ -19,40 +19,55 @@
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
 use std::mem;
+use std::path::Path;
 use bincode::{deserialize_from, serialize_into};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 use automation::{AspirationType, BlockConfig, HeadConfig, Valves};
+use automation::sandbox::EngineV1;
+use utils::units::{Power, PowerUnit, Torque, TorqueUnit};
+use crate::error::CrateEngineError;
 use crate::source::DataSource;
 
 
-pub(crate) type CurrentMetadataType = MetadataV1;
+pub(crate) type CurrentMetadataType = MetadataV3;
 
 pub enum CrateEngineMetadata {
     MetadataV1(MetadataV1),
-    MetadataV2(MetadataV2)
+    MetadataV2(MetadataV2),
+    MetadataV3(MetadataV3)
 }
 
 impl CrateEngineMetadata {
     pub fn from_current_version(inner_type: CurrentMetadataType) -> CrateEngineMetadata {
-        return CrateEngineMetadata::MetadataV1(inner_type)
+        return CrateEngineMetadata::MetadataV3(inner_type)
     }
 
     pub fn from_reader(reader: &mut impl Read) -> Result<CrateEngineMetadata, String> {
+        Self::quick_check(reader).map_err(|e| format!("Failed to read metadata. {}", e.to_string()))
+    }
+
+    /// Read the version header and metadata from `reader`, reporting why they couldn't be read
+    /// if the file is invalid. The engine data that follows the metadata isn't checked
+    pub fn quick_check(reader: &mut impl Read) -> Result<CrateEngineMetadata, CrateEngineError> {
         let mut buf = [0u8; mem::size_of::<u16>()];
-        reader.read_exact(&mut buf).map_err(|e| format!("Failed to read metadata. {}", e.to_string()))?;
+        reader.read_exact(&mut buf).map_err(CrateEngineError::from_header_read)?;
         let metadata_version = u16::from_le_bytes(buf);
+        let to_error = |e| CrateEngineError::from_bincode("metadata", e);
         match metadata_version {
             MetadataV1::VERSION_U16 => {
-                let metadata = deserialize_from(reader).map_err(|e| format!("Failed to deserialize metadata. {}", e.to_string()))?;
-                Ok(CrateEngineMetadata::MetadataV1(metadata))
+                Ok(CrateEngineMetadata::MetadataV1(deserialize_from(reader).map_err(to_error)?))
             },
             MetadataV2::VERSION_U16 => {
-                let metadata = deserialize_from(reader).map_err(|e| format!("Failed to deserialize metadata. {}", e.to_string()))?;
-                Ok(CrateEngineMetadata::MetadataV2(metadata))
+                Ok(CrateEngineMetadata::MetadataV2(deserialize_from(reader).map_err(to_error)?))
             },
-            _ => Err(format!("Unknown metadata version {}", metadata_version))
+            MetadataV3::VERSION_U16 => {
+                Ok(CrateEngineMetadata::MetadataV3(deserialize_from(reader).map_err(to_error)?))
+            },
+            _ => Err(CrateEngineError::UnknownVersion(metadata_version))
         }
     }
 
@@ -61,14 +76,16 @@ impl CrateEngineMetadata {
             CrateEngineMetadata::MetadataV1(m) => {
                 DataSource::from_beam_ng_mod(vec![m.engine_jbeam_hash, m.automation_data_hash])
             },
-            CrateEngineMetadata::MetadataV2(m) => m.source.clone()
+            CrateEngineMetadata::MetadataV2(m) => m.source.clone(),
+            CrateEngineMetadata::MetadataV3(m) => m.source.clone()
         }
     }
 
     pub fn get_metadata_version_u16(&self) -> u16 {
         match self {
             CrateEngineMetadata::MetadataV1(m) => m.get_version_u16(),
-            CrateEngineMetadata::MetadataV2(m) => m.get_version_u16()
+            CrateEngineMetadata::MetadataV2(m) => m.get_version_u16(),
+            CrateEngineMetadata::MetadataV3(m) => m.get_version_u16()
         }
     }
 
@@ -77,6 +94,7 @@ impl CrateEngineMetadata {
         match self {
             CrateEngineMetadata::MetadataV1(m) => serialize_into(writer, &m),
             CrateEngineMetadata::MetadataV2(m) => serialize_into(writer, &m),
+            CrateEngineMetadata::MetadataV3(m) => serialize_into(writer, &m),
         }
     }
 
@@ -84,6 +102,15 @@ impl CrateEngineMetadata {
         match self {
             CrateEngineMetadata::MetadataV1(d) => { &d.name }
             CrateEngineMetadata::MetadataV2(d) => { &d.name }
+            CrateEngineMetadata::MetadataV3(d) => { &d.name }
+        }
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        match self {
+            CrateEngineMetadata::MetadataV1(d) => { d.name = name }
+            CrateEngineMetadata::MetadataV2(d) => { d.name = name }
+            CrateEngineMetadata::MetadataV3(d) => { d.name = name }
         }
     }
 
@@ -91,6 +118,7 @@ impl CrateEngineMetadata {
         match self {
             CrateEngineMetadata::MetadataV1(d) => { *&d.data_version }
             CrateEngineMetadata::MetadataV2(d) => { *&d.data_version }
+            CrateEngineMetadata::MetadataV3(d) => { *&d.data_version }
         }
     }
 
@@ -98,90 +126,305 @@ impl CrateEngineMetadata {
         match self {
             CrateEngineMetadata::MetadataV1(m) => { m.automation_version }
             CrateEngineMetadata::MetadataV2(m) => { m.automation_version }
+            CrateEngineMetadata::MetadataV3(m) => { m.automation_version }
         }
     }
 
     pub fn build_year(&self) -> u16 {
         match self {
             CrateEngineMetadata::MetadataV1(m) => m.build_year,
-            CrateEngineMetadata::MetadataV2(m) => m.build_year
+            CrateEngineMetadata::MetadataV2(m) => m.build_year,
+            CrateEngineMetadata::MetadataV3(m) => m.build_year
         }
     }
 
     pub fn block_config(&self) -> &BlockConfig {
         match self {
             CrateEngineMetadata::MetadataV1(m) => &m.block_config,
-            CrateEngineMetadata::MetadataV2(m) => &m.block_config
+            CrateEngineMetadata::MetadataV2(m) => &m.block_config,
+            CrateEngineMetadata::MetadataV3(m) => &m.block_config
         }
     }
 
     pub fn head_config(&self) -> &HeadConfig {
         match self {
             CrateEngineMetadata::MetadataV1(m) => &m.head_config,
-            CrateEngineMetadata::MetadataV2(m) => &m.head_config
+            CrateEngineMetadata::MetadataV2(m) => &m.head_config,
+            CrateEngineMetadata::MetadataV3(m) => &m.head_config
         }
     }
 
     pub fn valves(&self) -> &Valves {
         match self {
             CrateEngineMetadata::MetadataV1(m) => &m.valves,
-            CrateEngineMetadata::MetadataV2(m) => &m.valves
+            CrateEngineMetadata::MetadataV2(m) => &m.valves,
+            CrateEngineMetadata::MetadataV3(m) => &m.valves
         }
     }
 
     pub fn capacity(&self) -> u32 {
         match self {
             CrateEngineMetadata::MetadataV1(m) => m.capacity,
-            CrateEngineMetadata::MetadataV2(m) => m.capacity
+            CrateEngineMetadata::MetadataV2(m) => m.capacity,
+            CrateEngineMetadata::MetadataV3(m) => m.capacity
         }
     }
 
     pub fn aspiration(&self) -> &AspirationType {
         match self {
             CrateEngineMetadata::MetadataV1(m) => &m.aspiration,
-            CrateEngineMetadata::MetadataV2(m) => &m.aspiration
+            CrateEngineMetadata::MetadataV2(m) => &m.aspiration,
+            CrateEngineMetadata::MetadataV3(m) => &m.aspiration
         }
     }
 
     pub fn fuel(&self) -> &str {
         match self {
             CrateEngineMetadata::MetadataV1(m) => &m.fuel,
-            CrateEngineMetadata::MetadataV2(m) => &m.fuel
+            CrateEngineMetadata::MetadataV2(m) => &m.fuel,
+            CrateEngineMetadata::MetadataV3(m) => &m.fuel
         }
     }
 
     pub fn peak_power(&self) -> u32 {
         match self {
             CrateEngineMetadata::MetadataV1(m) => m.peak_power,
-            CrateEngineMetadata::MetadataV2(m) => m.peak_power
+            CrateEngineMetadata::MetadataV2(m) => m.peak_power,
+            CrateEngineMetadata::MetadataV3(m) => m.peak_power
         }
     }
 
+    /// The peak power. Automation records this in kW
+    pub fn peak_power_figure(&self) -> Power {
+        Power::from_kw(self.peak_power() as f64)
+    }
+
+    pub fn peak_power_kw(&self) -> f64 {
+        self.peak_power_figure().kw()
+    }
+
+    pub fn peak_power_bhp(&self) -> f64 {
+        self.peak_power_figure().bhp()
+    }
+
     pub fn peak_power_rpm(&self) -> u32 {
         match self {
             CrateEngineMetadata::MetadataV1(m) => m.peak_power_rpm,
-            CrateEngineMetadata::MetadataV2(m) => m.peak_power_rpm
+            CrateEngineMetadata::MetadataV2(m) => m.peak_power_rpm,
+            CrateEngineMetadata::MetadataV3(m) => m.peak_power_rpm
         }
     }
 
     pub fn peak_torque(&self) -> u32 {
         match self {
             CrateEngineMetadata::MetadataV1(m) => m.peak_torque,
-            CrateEngineMetadata::MetadataV2(m) => m.peak_torque
+            CrateEngineMetadata::MetadataV2(m) => m.peak_torque,
+            CrateEngineMetadata::MetadataV3(m) => m.peak_torque
         }
     }
 
+    /// The peak torque. Automation records this in Nm
+    pub fn peak_torque_figure(&self) -> Torque {
+        Torque::from_nm(self.peak_torque() as f64)
+    }
+
+    pub fn peak_torque_nm(&self) -> f64 {
+        self.peak_torque_figure().nm()
+    }
+
+    pub fn peak_torque_lb_ft(&self) -> f64 {
+        self.peak_torque_figure().lb_ft()
+    }
+
     pub fn peak_torque_rpm(&self) -> u32 {
         match self {
             CrateEngineMetadata::MetadataV1(m) => m.peak_torque_rpm,
-            CrateEngineMetadata::MetadataV2(m) => m.peak_torque_rpm
+            CrateEngineMetadata::MetadataV2(m) => m.peak_torque_rpm,
+            CrateEngineMetadata::MetadataV3(m) => m.peak_torque_rpm
         }
     }
 
     pub fn max_rpm(&self) -> u32 {
         match self {
             CrateEngineMetadata::MetadataV1(m) => m.max_rpm,
-            CrateEngineMetadata::MetadataV2(m) => m.max_rpm
+            CrateEngineMetadata::MetadataV2(m) => m.max_rpm,
+            CrateEngineMetadata::MetadataV3(m) => m.max_rpm
+        }
+    }
+
+    /// Peak power per litre of capacity in bhp/L. Returns `None` if the capacity isn't known
+    pub fn specific_output_bhp_per_litre(&self) -> Option<f64> {
+        self.specific_output_per_litre(PowerUnit::Bhp)
+    }
+
+    /// The peak power per litre of capacity in `unit`, or `None` if the capacity isn't known
+    pub fn specific_output_per_litre(&self, unit: PowerUnit) -> Option<f64> {
+        match self.capacity() {
+            0 => None,
+            capacity_cc => Some(self.peak_power_figure().value_in(unit) / (capacity_cc as f64 / 1000.0))
+        }
+    }
+
+    /// The Automation quality slider settings the engine was built with, if the data it was
+    /// created from provided them. Engines packaged before this was captured will return `None`
+    pub fn quality(&self) -> Option<&QualitySummary> {
+        match self {
+            CrateEngineMetadata::MetadataV3(m) => m.quality.as_ref(),
+            _ => None
+        }
+    }
+
+    /// The version of the Automation exporter script that produced the engine data. Only engines
+    /// created from a direct export since this was captured will return a value
+    pub fn exporter_script_version(&self) -> Option<u32> {
+        match self {
+            CrateEngineMetadata::MetadataV3(m) => m.exporter_script_version,
+            _ => None
+        }
+    }
+
+    /// The name of the Automation family the engine belongs to. Engines packaged before this was
+    /// captured derive it from the engine name
+    pub fn family_name(&self) -> &str {
+        match self {
+            CrateEngineMetadata::MetadataV3(m) => &m.family_name,
+            _ => split_family_and_variant(self.name()).0
+        }
+    }
+
+    /// The name of the Automation variant within the engine's family. Engines packaged before this
+    /// was captured derive it from the engine name, so it may be empty
+    pub fn variant_name(&self) -> &str {
+        match self {
+            CrateEngineMetadata::MetadataV3(m) => &m.variant_name,
+            _ => split_family_and_variant(self.name()).1
+        }
+    }
+
+    /// Compare the descriptive fields of this engine with `other`. Every field is returned so that
+    /// the values that match can be shown alongside those that changed
+    pub fn diff(&self, other: &CrateEngineMetadata, power_unit: PowerUnit, torque_unit: TorqueUnit) -> Vec<DiffEntry> {
+        let describe = |m: &CrateEngineMetadata| -> Vec<(String, String)> {
+            let mut fields = vec![
+                ("Name", m.name().to_string()),
+                ("Year", m.build_year().to_string()),
+                ("Capacity", format!("{} cc", m.capacity())),
+                ("Config", format!("{} {} {}", m.block_config(), m.head_config(), m.valves())),
+                ("Aspiration", m.aspiration().to_string()),
+                ("Fuel", m.fuel().to_string()),
+                ("Peak power", format!("{} @ {} rpm", m.peak_power_figure().format_as(power_unit, 0), m.peak_power_rpm())),
+                ("Peak torque", format!("{} @ {} rpm", m.peak_torque_figure().format_as(torque_unit, 0), m.peak_torque_rpm())),
+                ("Limiter", format!("{} rpm", m.max_rpm()))
+            ].into_iter().map(|(field, value)| (field.to_string(), value)).collect::<Vec<_>>();
+            let quality_areas = m.quality().map(|quality| quality.areas());
+            for (idx, (area, _)) in QualitySummary::default().areas().into_iter().enumerate() {
+                let value = QualitySummary::format_area(quality_areas.and_then(|areas| areas[idx].1));
+                fields.push((format!("{} quality", area), value));
+            }
+            fields
+        };
+        describe(self).into_iter().zip(describe(other)).map(|((field, value), (_, other_value))| {
+            DiffEntry { field, value, other_value }
+        }).collect()
+    }
+
+    /// Serialise the metadata into the JSON schema described by [`MetadataJson`]. Every metadata
+    /// version produces the same set of fields
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&MetadataJson::from(self)).map_err(|e| {
+            format!("Failed to serialise metadata for {}. {}", self.name(), e.to_string())
+        })
+    }
+
+    pub fn write_json_to_path(&self, path: &Path) -> Result<(), String> {
+        let json = self.to_json()?;
+        std::fs::write(path, json).map_err(|e| {
+            format!("Failed to write {}. {}", path.display(), e.to_string())
+        })
+    }
+}
+
+/// A field of [`CrateEngineMetadata`] compared between two crate engines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub field: String,
+    pub value: String,
+    pub other_value: String
+}
+
+impl DiffEntry {
+    pub fn is_changed(&self) -> bool {
+        self.value != self.other_value
+    }
+}
+
+/// The JSON representation of [`CrateEngineMetadata`] used for external cataloguing.
+/// Fields that the stored metadata version doesn't record are written as `null`
+#[derive(Debug, Serialize)]
+pub struct MetadataJson<'a> {
+    pub schema_version: u16,
+    pub metadata_version: u16,
+    pub data_version: u16,
+    pub source: MetadataSourceJson,
+    pub automation_version: u64,
+    pub name: &'a str,
+    pub family_name: &'a str,
+    pub variant_name: &'a str,
+    pub build_year: u16,
+    pub block_config: String,
+    pub head_config: String,
+    pub valves: String,
+    pub capacity_cc: u32,
+    pub aspiration: String,
+    pub fuel: &'a str,
+    pub peak_power_kw: u32,
+    pub peak_power_rpm: u32,
+    pub peak_torque_nm: u32,
+    pub peak_torque_rpm: u32,
+    pub max_rpm: u32,
+    pub specific_output_bhp_per_litre: Option<f64>,
+    pub quality: Option<&'a QualitySummary>,
+    pub exporter_script_version: Option<u32>
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetadataSourceJson {
+    pub id: u16,
+    pub name: String
+}
+
+impl MetadataJson<'_> {
+    /// Bump this if fields are renamed or removed so that consumers can tell the formats apart
+    pub const SCHEMA_VERSION: u16 = 1;
+}
+
+impl<'a> From<&'a CrateEngineMetadata> for MetadataJson<'a> {
+    fn from(m: &'a CrateEngineMetadata) -> Self {
+        let source = m.get_source();
+        MetadataJson {
+            schema_version: MetadataJson::SCHEMA_VERSION,
+            metadata_version: m.get_metadata_version_u16(),
+            data_version: m.data_version(),
+            source: MetadataSourceJson { id: source.source_id, name: source.source_name() },
+            automation_version: m.automation_version(),
+            name: m.name(),
+            family_name: m.family_name(),
+            variant_name: m.variant_name(),
+            build_year: m.build_year(),
+            block_config: m.block_config().to_string(),
+            head_config: m.head_config().to_string(),
+            valves: m.valves().to_string(),
+            capacity_cc: m.capacity(),
+            aspiration: m.aspiration().to_string(),
+            fuel: m.fuel(),
+            peak_power_kw: m.peak_power(),
+            peak_power_rpm: m.peak_power_rpm(),
+            peak_torque_nm: m.peak_torque(),
+            peak_torque_rpm: m.peak_torque_rpm(),
+            max_rpm: m.max_rpm(),
+            specific_output_bhp_per_litre: m.specific_output_bhp_per_litre(),
+            quality: m.quality(),
+            exporter_script_version: m.exporter_script_version()
         }
     }
 }
@@ -241,6 +484,120 @@ impl MetadataV2 {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MetadataV3 {
+    pub source: DataSource,
+    pub data_version: u16,
+    pub automation_version: u64,
+    pub name: String,
+    pub family_name: String,
+    pub variant_name: String,
+    pub build_year: u16,
+    pub block_config: BlockConfig,
+    pub head_config: HeadConfig,
+    pub valves: Valves,
+    pub capacity: u32,
+    pub aspiration: AspirationType,
+    pub fuel: String,
+    pub peak_power: u32,
+    pub peak_power_rpm: u32,
+    pub peak_torque: u32,
+    pub peak_torque_rpm: u32,
+    pub max_rpm: u32,
+    pub quality: Option<QualitySummary>,
+    /// The version of the Automation exporter script that produced the data, if it came from one
+    pub exporter_script_version: Option<u32>
+}
+
+impl MetadataV3 {
+    const VERSION_U16: u16 = 3_u16;
+    pub fn get_version_u16(&self) -> u16 {
+        Self::VERSION_U16
+    }
+}
+
+/// The Automation quality sliders of the engine. Each area ranges from -5 to 5, with higher
+/// values trading cost and engineering time for better results. Areas are None if the data the
+/// engine was created from didn't include them
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct QualitySummary {
+    pub family: Option<i32>,
+    pub bottom_end: Option<i32>,
+    pub top_end: Option<i32>,
+    pub aspiration: Option<i32>,
+    pub fuel_system: Option<i32>,
+    pub exhaust: Option<i32>
+}
+
+impl QualitySummary {
+    pub fn from_engine(engine: &EngineV1) -> QualitySummary {
+        QualitySummary {
+            family: Some(engine.family_quality),
+            bottom_end: Some(engine.quality_bottom_end),
+            top_end: Some(engine.quality_top_end),
+            aspiration: Some(engine.quality_aspiration),
+            fuel_system: Some(engine.quality_fuel_system),
+            exhaust: Some(engine.quality_exhaust)
+        }
+    }
+
+    /// The Automation names of each area, as used by the sandbox database and the .car Variant
+    /// and Family sections
+    const AUTOMATION_KEYS: [&'static str; 6] = ["QualityFamily", "QualityBottomEnd", "QualityTopEnd",
+                                                 "QualityAspiration", "QualityFuelSystem", "QualityExhaust"];
+
+    /// Read the quality sliders from the direct export float data. Each area is looked up by its
+    /// Automation name in every group, as the group the exporter script writes them to isn't
+    /// known. Areas that aren't found are left as None and logged. Returns `None` if no area is
+    /// found at all
+    pub fn from_float_data(float_data: &BTreeMap<String, BTreeMap<String, f32>>) -> Option<QualitySummary> {
+        let lookup = |key: &str| {
+            float_data.values().find_map(|group| group.get(key)).map(|val| val.round() as i32)
+        };
+        let [family, bottom_end, top_end, aspiration, fuel_system, exhaust] = Self::AUTOMATION_KEYS.map(lookup);
+        let quality = QualitySummary { family, bottom_end, top_end, aspiration, fuel_system, exhaust };
+        let missing: Vec<&str> = Self::AUTOMATION_KEYS.into_iter().zip(quality.areas())
+            .filter(|(_, (_, value))| value.is_none())
+            .map(|(key, _)| key)
+            .collect();
+        match missing.len() {
+            0 => Some(quality),
+            len if len == Self::AUTOMATION_KEYS.len() => None,
+            _ => {
+                warn!("Exported data has no {} values", missing.join(", "));
+                Some(quality)
+            }
+        }
+    }
+
+    /// The name and value of each quality area
+    pub fn areas(&self) -> [(&'static str, Option<i32>); 6] {
+        [
+            ("Family", self.family),
+            ("Bottom end", self.bottom_end),
+            ("Top end", self.top_end),
+            ("Aspiration", self.aspiration),
+            ("Fuel system", self.fuel_system),
+            ("Exhaust", self.exhaust)
+        ]
+    }
+
+    /// Format the value of a quality area for display
+    pub fn format_area(value: Option<i32>) -> String {
+        value.map_or(String::from("Unknown"), |value| format!("{:+}", value))
+    }
+}
+
+/// Split an engine name into family and variant parts for metadata that didn't record them.
+/// The default names are "<family> - <variant>" so split on the first separator if there is one,
+/// otherwise treat the whole name as the family
+fn split_family_and_variant(name: &str) -> (&str, &str) {
+    match name.split_once(" - ") {
+        Some((family, variant)) => (family.trim(), variant.trim()),
+        None => (name.trim(), "")
+    }
+}
+
 fn _deserialize_metadata<R, T>(reader: R) -> Result<T, String>
     where
         R: Read,
@@ -22,6 +22,7 @@ use serde::{Deserialize, Serialize};
 
 pub const BEAM_NG_MOD_SOURCE_ID: u16 = 1;
 pub const DIRECT_EXPORT_SOURCE_ID: u16 = 2;
+pub const SANDBOX_SOURCE_ID: u16 = 3;
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct DataSource {
@@ -38,10 +39,24 @@ impl DataSource {
         DataSource { source_id: DIRECT_EXPORT_SOURCE_ID, hashes: Vec::new() }
     }
 
+    pub fn from_sandbox(automation_data_hash: Option<[u8; 32]>) -> Self {
+        DataSource { source_id: SANDBOX_SOURCE_ID, hashes: vec![automation_data_hash] }
+    }
+
+    /// The hash of the Automation engine data that the crate engine was created from, if known
+    pub fn automation_data_hash(&self) -> Option<[u8; 32]> {
+        match self.source_id {
+            BEAM_NG_MOD_SOURCE_ID => self.hashes.get(1).copied().flatten(),
+            SANDBOX_SOURCE_ID => self.hashes.first().copied().flatten(),
+            _ => None
+        }
+    }
+
     pub fn source_name(&self) -> String {
         match self.source_id {
             BEAM_NG_MOD_SOURCE_ID => String::from("BeamNG Mod"),
             DIRECT_EXPORT_SOURCE_ID => String::from("Direct Automation Export"),
+            SANDBOX_SOURCE_ID => String::from("Automation Sandbox"),
             _ => String::from("Unknown")
         }
     }
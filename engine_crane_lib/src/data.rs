@@ -22,35 +22,48 @@
 
 use std::collections::BTreeMap;
 use std::fs::File;
+use std::io::{self, BufReader};
 use std::path::{Path, PathBuf};
 use directories::BaseDirs;
-use iced::futures::io;
 use tracing::warn;
 
 use utils::filesystem::get_filetypes_in_path;
 
-pub use crate_engine::{CrateEngine, CrateEngineMetadata, CrateEngineData, FromBeamNGModOptions};
+pub use crate_engine::{CrateEngine, CrateEngineError, CrateEngineMetadata, CrateEngineData, FromBeamNGModOptions};
 
 const LOCAL_DATA_DIRNAME: &'static str = "EngineCrane";
 const DEFAULT_CRATE_ENGINE_DIRNAME: &'static str = "crate";
 
-pub fn find_crate_engines_in_path(path: &Path) -> io::Result<BTreeMap<PathBuf, CrateEngineMetadata>> {
-    let mut found_metadata = BTreeMap::new();
+/// The crate engine files found in a folder. Files that couldn't be loaded are kept with the
+/// reason so that they can be reported rather than skipped
+#[derive(Default)]
+pub struct CrateEngineScan {
+    pub engines: BTreeMap<PathBuf, CrateEngineMetadata>,
+    pub invalid_engines: BTreeMap<PathBuf, CrateEngineError>
+}
+
+pub fn find_crate_engines_in_path(path: &Path) -> io::Result<CrateEngineScan> {
+    let mut scan = CrateEngineScan::default();
     let paths = get_filetypes_in_path(path, crate_engine::CRATE_ENGINE_FILE_SUFFIX)?;
     for path in paths.into_iter() {
-        match File::open(&path) {
-            Ok(mut f) => match CrateEngineMetadata::from_reader(&mut f) {
-                Ok(m) => {
-                    found_metadata.insert(path, m);
-                }
-                Err(e) => {
-                    warn!("Error occurred for {}. {}", path.display(), e);
-                }
+        match validate_crate_engine_file(&path) {
+            Ok(m) => {
+                scan.engines.insert(path, m);
+            }
+            Err(e) => {
+                warn!("Error occurred for {}. {}", path.display(), e);
+                scan.invalid_engines.insert(path, e);
             }
-            Err(e) => warn!("Couldn't open {}. {}", path.display(), e.to_string())
         }
     }
-    Ok(found_metadata)
+    Ok(scan)
+}
+
+/// Fully read the crate engine at `path`, checking the data against the hashes stored in its
+/// metadata. Returns the metadata if the file is valid
+pub fn validate_crate_engine_file(path: &Path) -> Result<CrateEngineMetadata, CrateEngineError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(CrateEngine::validate_from_reader(&mut reader)?.into_metadata())
 }
 
 #[cfg(target_os = "windows")]
@@ -25,15 +25,15 @@ use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::path::Path;
 use itertools::Itertools;
+use serde::Serialize;
 use tracing::{debug, info, warn};
-use statrs::distribution::{ContinuousCDF, Normal};
 
 use assetto_corsa::car::data;
 use assetto_corsa::car::data::engine;
 use assetto_corsa::car::data::engine::FuelConsumptionFlowRate;
 use automation::car::CarFile;
-use automation::FIRST_AL_RIMA_VERSION_NUM;
-use automation::sandbox::{EngineV1, load_engine_by_uuid, SandboxFinder};
+use automation::{AspirationType, BlockConfig, FIRST_AL_RIMA_VERSION_NUM, HeadConfig};
+use automation::sandbox::{EngineV1, search_sandboxes_for_engine, SandboxFinder};
 use utils::units::{calculate_power_kw, kw_to_bhp};
 use automation::validation::AutomationSandboxCrossChecker;
 use crate_engine::{CrateEngine, CrateEngineData};
@@ -41,10 +41,101 @@ use crate_engine::beam_ng_mod;
 use crate_engine::direct_export::Data;
 use crate::fabricator::{FabricationError};
 use crate::fabricator::FabricationError::{InvalidData, MissingDataSection};
-use crate::utils::numeric::{round_float_to};
+use crate::utils::numeric::{interpolate_curve, normal_lerp, round_float_to};
 
 pub(crate) const UPGRADE_ICON_BYTES: &[u8] = include_bytes!("resources/upgrade.png");
 
+/// Where the engine used in a swap came from. Recorded in the swapped car so it can be identified later
+#[derive(Debug, Clone, Default)]
+pub struct EngineProvenance {
+    pub name: String,
+    pub automation_version: Option<u64>,
+    pub build_year: Option<u16>,
+    pub configuration: Option<String>,
+    pub aspiration: Option<String>,
+    pub capacity_cc: Option<u32>,
+    pub block_config: Option<BlockConfig>,
+    pub aspiration_type: Option<AspirationType>
+}
+
+/// Where the torque values written for a swap came from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum TorqueCurveSource {
+    /// The torque curve of the engine data was used as-is
+    EngineCurve,
+    /// The peak of the jbeam torque table didn't match the engine's peak torque so the torque
+    /// curve of the Automation data was used instead
+    AutomationCurve { jbeam_peak: i32, peak_torque: i32 },
+    /// The donor car's power.lut and ui curves were kept
+    DonorCurve,
+    /// The torque curve was loaded from a csv file
+    Csv { path: String }
+}
+
+impl Default for TorqueCurveSource {
+    fn default() -> Self {
+        TorqueCurveSource::EngineCurve
+    }
+}
+
+/// Choose the torque curve to use given the peak of the jbeam torque table, if there is one, and
+/// the peak torque of the Automation data. The Automation curve is preferred if the peaks are
+/// more than `tolerance`, a fraction of `peak_torque`, apart
+pub(crate) fn choose_torque_curve_source(jbeam_peak: Option<i32>, peak_torque: i32, tolerance: f64) -> TorqueCurveSource {
+    let jbeam_peak = match jbeam_peak {
+        Some(jbeam_peak) if jbeam_peak > 0 && peak_torque > 0 => jbeam_peak,
+        _ => return TorqueCurveSource::EngineCurve
+    };
+    match (jbeam_peak - peak_torque).abs() as f64 / peak_torque as f64 > tolerance {
+        true => TorqueCurveSource::AutomationCurve { jbeam_peak, peak_torque },
+        false => TorqueCurveSource::EngineCurve
+    }
+}
+
+/// Read a jbeam torque table of `["rpm", "torque"]` rows into RPM, Torque (NM) pairs. The header
+/// row and any other rows that aren't a pair of numbers are skipped
+pub(crate) fn torque_table_curve(torque_table: &serde_hjson::Value) -> Vec<(i32, i32)> {
+    torque_table.as_array().map_or(Vec::new(), |rows| {
+        rows.iter().filter_map(|row| {
+            let row = row.as_array()?;
+            Some((row.first()?.as_f64()?.round() as i32, row.get(1)?.as_f64()?.round() as i32))
+        }).collect()
+    })
+}
+
+/// Extend a power.lut torque curve by two rpm steps so that it tapers down to 0
+fn taper_torque_curve(curve: &mut Vec<(i32, f64)>) {
+    if curve.len() < 2 {
+        return;
+    }
+    let (last_rpm, last_torque) = curve[curve.len() - 1];
+    let rpm_increment = last_rpm - curve[curve.len() - 2].0;
+    curve.push((last_rpm + rpm_increment, last_torque / 2f64));
+    curve.push((last_rpm + rpm_increment * 2, 0f64));
+}
+
+impl EngineProvenance {
+    /// A one line description of the engine e.g. "1995 2997cc Inline 6 DOHC Turbocharged".
+    /// Any unknown details are left out
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(year) = self.build_year {
+            parts.push(year.to_string());
+        }
+        if let Some(capacity) = self.capacity_cc {
+            parts.push(format!("{}cc", capacity));
+        }
+        if let Some(configuration) = &self.configuration {
+            parts.push(configuration.clone());
+        }
+        if let Some(aspiration) = &self.aspiration {
+            parts.push(aspiration.clone());
+        }
+        parts.join(" ")
+    }
+}
+
 pub enum EngineParameterCalculator {
     V1(EngineParameterCalculatorV1),
     V2(EngineParameterCalculatorV2)
@@ -62,7 +153,7 @@ impl EngineParameterCalculator {
 
         match crate_eng.data() {
             CrateEngineData::BeamNGMod(data_version) => match data_version {
-                beam_ng_mod::Data::V1(data) => {
+                beam_ng_mod::Data::V1(data) | beam_ng_mod::Data::V2(data) => {
                     info!("Loading Automation car file");
                     let automation_car_file_data = data.car_file_data().clone();
                     if automation_car_file_data.is_empty() {
@@ -77,8 +168,8 @@ impl EngineParameterCalculator {
                         MissingDataSource("Main engine JBeam file".to_string())
                     })?;
                     Ok(EngineParameterCalculator::V1(EngineParameterCalculatorV1 {
-                        automation_car_file,
-                        engine_jbeam_data: serde_hjson::from_slice(engine_jbeam_bytes)?,
+                        automation_car_file: Some(automation_car_file),
+                        engine_jbeam_data: Some(serde_hjson::from_slice(engine_jbeam_bytes)?),
                         engine_sqlite_data: data.automation_data().clone()
                     }))
                 }
@@ -88,9 +179,28 @@ impl EngineParameterCalculator {
                     EngineParameterCalculatorV2 { eng_data: eng_data.clone() }
                 ))
             }
+            CrateEngineData::Sandbox(data) => {
+                EngineParameterCalculator::from_sandbox_engine(data.automation_data().clone())
+            }
         }
     }
 
+    /// Create a calculator from engine data loaded straight out of an Automation sandbox db.
+    /// There is no BeamNG jbeam or Automation car file available in this case so the values that
+    /// are normally read from them are estimated from the sandbox data instead
+    pub fn from_sandbox_engine(engine: EngineV1) -> Result<EngineParameterCalculator, FabricationError> {
+        info!("Creating AC parameter calculator for Automation engine {} ({})", engine.friendly_name(), engine.uuid);
+        if engine.rpm_curve.is_empty() {
+            return Err(MissingDataSection("curve data".to_string(), format!("Automation engine {}", engine.uuid)));
+        }
+        warn!("No BeamNG data available for {}. Inertia, idle and coast values will be estimated", engine.friendly_name());
+        Ok(EngineParameterCalculator::V1(EngineParameterCalculatorV1 {
+            automation_car_file: None,
+            engine_jbeam_data: None,
+            engine_sqlite_data: engine
+        }))
+    }
+
     pub fn from_beam_ng_mod(beam_ng_mod_path: &Path, sandbox_finder: SandboxFinder) -> Result<EngineParameterCalculator, FabricationError> {
         use crate::fabricator::FabricationError::*;
         info!("Creating AC parameter calculator for BeamNG mod {}", beam_ng_mod_path.to_path_buf().display());
@@ -120,8 +230,6 @@ impl EngineParameterCalculator {
         })?;
 
         info!("Engine version number: {}", version_num);
-        let sandbox_lookup_data = sandbox_finder.find_sandbox_db_for_version(version_num as u64);
-        info!("Deduced as {}", sandbox_lookup_data.version);
 
         let uid_attr = variant_info.get_attribute("UID").ok_or_else(||{
             MissingDataSection("'Car.Variant.UID'".to_string(), format!("Automation .car file in {}", beam_ng_mod_path.display()))
@@ -133,24 +241,25 @@ impl EngineParameterCalculator {
             FailedToLoad("Main engine JBeam".to_string(), e)
         })?;
 
-        let engine_sqlite_data = load_engine_by_uuid(uid, sandbox_lookup_data).map_err(|e|{
+        let engine_match = search_sandboxes_for_engine(uid, version_num as u64, &sandbox_finder).map_err(|e|{
             FailedToLoad(format!("Sandbox db engine {}", uid), e)
-        })?.ok_or_else(||{
-            MissingDataSection(format!("engine {}", uid), format!("sandbox db"))
         })?;
+        info!("Loaded engine from the {} sandbox", engine_match.version);
 
         {
-            AutomationSandboxCrossChecker::new(&automation_car_file, &engine_sqlite_data).validate().map_err(|err|{
+            AutomationSandboxCrossChecker::new(&automation_car_file, &engine_match.engine).validate().map_err(|err|{
                 ValidationError(format!("{}. The engine data saved in Automation doesn't match the BeamNG mod data.\
-                                         The mod may be out-of-date; try recreating a mod with the latest engine version", err))
+                                         The mod may be out-of-date; try recreating a mod with the latest engine version. \
+                                         Sandbox dbs checked: {}", err, engine_match.searched_dbs()))
             })?;
         }
+        let engine_sqlite_data = engine_match.engine;
         if engine_sqlite_data.rpm_curve.is_empty() {
             return Err(MissingDataSection("curve data".to_string(), "sandbox db".to_string()));
         }
         Ok(EngineParameterCalculator::V1(EngineParameterCalculatorV1 {
-            automation_car_file,
-            engine_jbeam_data: engine_jbeam_data.clone(),
+            automation_car_file: Some(automation_car_file),
+            engine_jbeam_data: Some(engine_jbeam_data.clone()),
             engine_sqlite_data
         }))
     }
@@ -176,6 +285,27 @@ impl EngineParameterCalculator {
         }
     }
 
+    pub fn automation_idle_rpm(&self) -> Option<f64> {
+        match self {
+            EngineParameterCalculator::V1(c) => c.automation_idle_rpm(),
+            EngineParameterCalculator::V2(c) => c.automation_idle_rpm()
+        }
+    }
+
+    pub fn jbeam_idle_rpm(&self) -> Option<f64> {
+        match self {
+            EngineParameterCalculator::V1(c) => c.jbeam_idle_rpm(),
+            EngineParameterCalculator::V2(_) => None
+        }
+    }
+
+    pub fn curve_min_rpm(&self) -> Option<f64> {
+        match self {
+            EngineParameterCalculator::V1(c) => c.curve_min_rpm(),
+            EngineParameterCalculator::V2(c) => c.curve_min_rpm()
+        }
+    }
+
     pub fn limiter(&self) -> f64 {
         match self {
             EngineParameterCalculator::V1(c) => c.limiter(),
@@ -183,6 +313,13 @@ impl EngineParameterCalculator {
         }
     }
 
+    pub fn jbeam_limiter(&self) -> Option<f64> {
+        match self {
+            EngineParameterCalculator::V1(c) => c.jbeam_limiter(),
+            EngineParameterCalculator::V2(_) => None
+        }
+    }
+
     pub fn basic_fuel_consumption(&self) -> f64 {
         match self {
             EngineParameterCalculator::V1(c) => c.basic_fuel_consumption(),
@@ -215,6 +352,30 @@ impl EngineParameterCalculator {
         }
     }
 
+    /// The torque table of the main engine jbeam, if the engine came from a BeamNG mod
+    pub fn jbeam_torque_curve(&self) -> Option<Vec<(i32, i32)>> {
+        match self {
+            EngineParameterCalculator::V1(c) => c.jbeam_torque_curve(),
+            EngineParameterCalculator::V2(_) => None
+        }
+    }
+
+    /// Check the peak of the jbeam torque table against the peak torque recorded in the
+    /// Automation data. Some mods contain a torque table measured after the driveline, which
+    /// would have the drivetrain losses applied a second time if it were used. If the peaks
+    /// differ by more than `tolerance`, a fraction of the peak torque, the Automation curve is used
+    pub fn check_torque_curve(&self, tolerance: f64) -> TorqueCurveSource {
+        let jbeam_peak = self.jbeam_torque_curve().and_then(|curve| {
+            curve.into_iter().map(|(_, torque)| torque).max()
+        });
+        let source = choose_torque_curve_source(jbeam_peak, self.peak_torque(), tolerance);
+        if let TorqueCurveSource::AutomationCurve { jbeam_peak, peak_torque } = &source {
+            warn!("The jbeam torque table peaks at {}Nm but the engine's peak torque is {}Nm. Using the Automation torque curve",
+                  jbeam_peak, peak_torque);
+        }
+        source
+    }
+
     pub fn engine_bhp_power_curve(&self) -> Vec<(i32, i32)> {
         match self {
             EngineParameterCalculator::V1(c) => c.engine_bhp_power_curve(),
@@ -222,6 +383,13 @@ impl EngineParameterCalculator {
         }
     }
 
+    pub fn peak_power_rpm(&self) -> f64 {
+        match self {
+            EngineParameterCalculator::V1(c) => c.peak_power_rpm(),
+            EngineParameterCalculator::V2(c) => c.peak_power_rpm() as f64
+        }
+    }
+
     pub fn peak_bhp(&self) -> i32 {
         match self {
             EngineParameterCalculator::V1(c) => c.peak_bhp(),
@@ -240,6 +408,27 @@ impl EngineParameterCalculator {
         }
     }
 
+    /// Return a vector containing pairs of RPM, Boost (bar). Empty for naturally aspirated engines
+    pub fn boost_curve(&self) -> Vec<(f64, f64)> {
+        match self {
+            EngineParameterCalculator::V1(c) => c.boost_curve(),
+            EngineParameterCalculator::V2(c) => c.boost_curve()
+        }
+    }
+
+    /// Convert a crank `torque_curve` of RPM, Torque (NM) pairs that replaces the one in the
+    /// engine data into the curve written to power.lut. The engine's boost is removed from the
+    /// torque in the same way as [`EngineParameterCalculator::naturally_aspirated_wheel_torque_curve`]
+    pub fn wheel_torque_curve_from(&self, torque_curve: &[(f64, f64)], drivetrain_efficiency: f64) -> Vec<(i32, f64)> {
+        let boost_curve = self.boost_curve();
+        let mut out_vec: Vec<(i32, f64)> = torque_curve.iter().map(|(rpm, torque)| {
+            let boost_pressure = interpolate_curve(&boost_curve, *rpm, true).unwrap_or(0.0).max(0.0);
+            (rpm.round() as i32, ((torque / (1.0 + boost_pressure)) * drivetrain_efficiency).round())
+        }).collect();
+        taper_torque_curve(&mut out_vec);
+        out_vec
+    }
+
     #[allow(dead_code)]
     pub fn get_max_boost_params(&self, decimal_place_precision: u32) -> (i32, f64) {
         match self {
@@ -259,24 +448,39 @@ impl EngineParameterCalculator {
         }
     }
 
-    pub fn create_turbo_controller(&self) -> Option<engine::turbo_ctrl::TurboController> {
+    /// Create a boost controller for each turbo section returned by `create_turbo`.
+    /// The controller at index N belongs in the ctrl_turboN.ini file
+    pub fn create_turbo_controllers(&self) -> Vec<engine::turbo_ctrl::TurboController> {
         match self {
-            EngineParameterCalculator::V1(c) => c.create_turbo_controller(),
-            EngineParameterCalculator::V2(c) => c.create_turbo_controller()
+            EngineParameterCalculator::V1(c) => c.create_turbo_controllers(),
+            EngineParameterCalculator::V2(c) => c.create_turbo_controllers()
         }
     }
 
-    pub fn coast_data(&self) -> Result<engine::CoastCurve, FabricationError> {
+    /// The engine braking of the engine, referenced to the `limiter` the engine will be given
+    pub fn coast_data(&self, limiter: f64) -> Result<engine::CoastCurve, FabricationError> {
         match self {
-            EngineParameterCalculator::V1(c) => c.coast_data(),
-            EngineParameterCalculator::V2(c) => c.coast_data()
+            EngineParameterCalculator::V1(c) => c.coast_data(limiter),
+            EngineParameterCalculator::V2(c) => c.coast_data(limiter)
         }
     }
 
-    pub fn damage(&self) -> engine::Damage {
+    /// A throttle map suited to the engine's fuel system and intake or None if nothing is known
+    /// about its throttle response, in which case the existing map should be left alone.
+    /// The map is applied up to `rpm_max`
+    pub fn throttle_map(&self, rpm_max: i32) -> Option<engine::ThrottleMap> {
+        let exponent = match self {
+            EngineParameterCalculator::V1(c) => c.throttle_response_exponent(),
+            EngineParameterCalculator::V2(c) => c.throttle_response_exponent()
+        }?;
+        Some(engine::ThrottleMap::new(throttle_response_lut(exponent), rpm_max))
+    }
+
+    /// Engine damage limits for the engine when it is given the rev `limiter`
+    pub fn damage(&self, limiter: f64) -> engine::Damage {
         match self {
-            EngineParameterCalculator::V1(c) => c.damage(),
-            EngineParameterCalculator::V2(c) => c.damage()
+            EngineParameterCalculator::V1(c) => c.damage(limiter),
+            EngineParameterCalculator::V2(c) => c.damage(limiter)
         }
     }
 
@@ -286,12 +490,19 @@ impl EngineParameterCalculator {
             EngineParameterCalculator::V2(c) => c.create_metadata()
         }
     }
+
+    pub fn provenance(&self) -> EngineProvenance {
+        match self {
+            EngineParameterCalculator::V1(c) => c.provenance(),
+            EngineParameterCalculator::V2(c) => c.provenance()
+        }
+    }
 }
 
 #[derive(Debug)]
-pub(crate) struct EngineParameterCalculatorV1 {
-    automation_car_file: CarFile,
-    engine_jbeam_data: serde_hjson::Map<String, serde_hjson::Value>,
+pub struct EngineParameterCalculatorV1 {
+    automation_car_file: Option<CarFile>,
+    engine_jbeam_data: Option<serde_hjson::Map<String, serde_hjson::Value>>,
     engine_sqlite_data: EngineV1
 }
 
@@ -300,23 +511,18 @@ impl EngineParameterCalculatorV1 {
         self.engine_sqlite_data.weight.round() as u32
     }
 
-    fn get_engine_jbeam_key(&self) -> String {
-        let mut engine_key = String::from("Camso_Engine");
-        let test_key = String::from(engine_key.clone() + "_");
-        for key in self.engine_jbeam_data.keys() {
-            if key.starts_with(&test_key) {
-                engine_key = String::from(key);
-                break;
-            }
-        }
-        engine_key
-    }
-
     fn get_main_engine_jbeam_map(&self) -> Result<&serde_hjson::Map<String, serde_hjson::Value>, FabricationError> {
-        let section_name = self.get_engine_jbeam_key();
+        let engine_jbeam_data = self.engine_jbeam_data.as_ref().ok_or_else(||{
+            FabricationError::MissingDataSource("main jbeam engine file".to_string())
+        })?;
+        // Only the Camso_Engine main map is used; ECU and other variant parts in the same
+        // file can contain their own mainEngine values
+        let section_name = beam_ng::main_engine_key(engine_jbeam_data).ok_or_else(||{
+            MissingDataSection("Camso_Engine".to_string(), "main jbeam engine file".to_string())
+        })?;
         let eng_section_object = get_object_from_jbeam_map(
-            &self.engine_jbeam_data,
-            &section_name,
+            engine_jbeam_data,
+            section_name,
             "main jbeam engine file"
         )?;
         Ok(get_object_from_jbeam_map(
@@ -327,6 +533,10 @@ impl EngineParameterCalculatorV1 {
     }
 
     pub fn inertia(&self) -> Result<f64, FabricationError> {
+        if self.engine_jbeam_data.is_none() {
+            warn!("No jbeam data available. Estimating inertia from engine responsiveness");
+            return Ok(normal_lerp(0.32, 0.07, (self.engine_sqlite_data.responsiveness / 100.0) as f32, 0.2) as f64);
+        }
         let eng_map = self.get_main_engine_jbeam_map()?;
         let inertia_val = eng_map.get("inertia").ok_or_else(||{
             MissingDataSection("inertia".to_string(), "mainEngine".to_string())
@@ -355,10 +565,43 @@ impl EngineParameterCalculatorV1 {
         values.into_iter().max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
     }
 
+    pub fn automation_idle_rpm(&self) -> Option<f64> {
+        self.engine_sqlite_data.get_idle_rpm()
+    }
+
+    /// The lowest rpm of the Automation torque curve
+    pub fn curve_min_rpm(&self) -> Option<f64> {
+        self.engine_sqlite_data.rpm_curve.first().copied()
+    }
+
+    /// Return the idle rpm defined in the main engine jbeam data, if there is one
+    pub fn jbeam_idle_rpm(&self) -> Option<f64> {
+        let eng_map = self.get_main_engine_jbeam_map().ok()?;
+        match get_f64_from_jbeam_map(eng_map, "idleRPM", "mainEngine") {
+            Ok(val) => Some(val),
+            Err(e) => {
+                debug!("Couldn't read idle rpm from jbeam. {}", e.to_string());
+                None
+            }
+        }
+    }
+
     pub fn limiter(&self) -> f64 {
         self.engine_sqlite_data.max_rpm
     }
 
+    /// Return the rev limit defined in the main engine jbeam data, if there is one
+    pub fn jbeam_limiter(&self) -> Option<f64> {
+        let eng_map = self.get_main_engine_jbeam_map().ok()?;
+        for key in ["revLimiterRPM", "maxRPM"] {
+            match get_f64_from_jbeam_map(eng_map, key, "mainEngine") {
+                Ok(val) => return Some(val),
+                Err(e) => debug!("Couldn't read limiter from jbeam. {}", e.to_string())
+            }
+        }
+        None
+    }
+
     pub fn basic_fuel_consumption(&self) -> f64 {
         // From https://buildingclub.info/calculator/g-kwh-to-l-h-online-from-gram-kwh-to-liters-per-hour/
         // Fuel Use (l/h) = (Engine Power (kW) * BSFC@Power) / Fuel density kg/m3
@@ -421,6 +664,24 @@ impl EngineParameterCalculatorV1 {
         self.engine_sqlite_data.peak_torque.round() as i32
     }
 
+    /// Return the RPM, Torque (NM) pairs of the torque table in the main engine jbeam data, if
+    /// there is one
+    pub fn jbeam_torque_curve(&self) -> Option<Vec<(i32, i32)>> {
+        let eng_map = self.get_main_engine_jbeam_map().ok()?;
+        let curve = torque_table_curve(eng_map.get("torque")?);
+        match curve.is_empty() {
+            true => None,
+            false => Some(curve)
+        }
+    }
+
+    pub fn boost_curve(&self) -> Vec<(f64, f64)> {
+        if self.engine_sqlite_data.aspiration.starts_with("Aspiration_Natural") {
+            return Vec::new();
+        }
+        self.engine_sqlite_data.rpm_curve.iter().copied().zip(self.engine_sqlite_data.boost_curve.iter().copied()).collect()
+    }
+
     /// Return a vector containing pairs of RPM, Power (BHP)
     pub fn engine_bhp_power_curve(&self) -> Vec<(i32, i32)> {
         let mut out_vec = Vec::new();
@@ -434,6 +695,10 @@ impl EngineParameterCalculatorV1 {
         kw_to_bhp(self.engine_sqlite_data.peak_power).round() as i32
     }
 
+    fn peak_power_rpm(&self) -> f64 {
+        self.engine_sqlite_data.peak_power_rpm
+    }
+
     pub fn naturally_aspirated_wheel_torque_curve(&self, drivetrain_efficiency: f64) -> Vec<(i32, f64)> {
         let mut out_vec: Vec<(i32, f64)> = Vec::new();
         if self.engine_sqlite_data.aspiration.starts_with("Aspiration_Natural") {
@@ -453,15 +718,7 @@ impl EngineParameterCalculatorV1 {
                 out_vec.push(((*rpm as i32), adjusted_value));
             }
         }
-        // Taper curve down to 0
-        let rpm_increment;
-        {
-            let last_item = out_vec.last().unwrap();
-            let penultimate_item = out_vec.get(out_vec.len()-2).unwrap();
-            rpm_increment = last_item.0 - penultimate_item.0;
-        }
-        out_vec.push((out_vec.last().unwrap().0 + rpm_increment, out_vec.last().unwrap().1/2f64));
-        out_vec.push((out_vec.last().unwrap().0 + rpm_increment, 0f64));
+        taper_torque_curve(&mut out_vec);
         out_vec
     }
 
@@ -485,68 +742,109 @@ impl EngineParameterCalculatorV1 {
          round_float_to(max_boost, decimal_place_precision))
     }
 
+    /// The max boost set for each forced induction charger on the engine
+    fn charger_max_boosts(&self) -> Vec<Option<f64>> {
+        let data = &self.engine_sqlite_data;
+        match is_second_charger_option(data.aspiration_item_2.as_deref()) {
+            true => vec![data.charger_max_boost_1, data.charger_max_boost_2],
+            false => vec![data.charger_max_boost_1]
+        }
+    }
+
+    /// Whether each forced induction charger on the engine is electrically assisted. Only
+    /// engines from Automation 4.3 (Al Rima) onwards can have e-boosted chargers
+    fn charger_eboosted(&self) -> Vec<bool> {
+        let al_rima = &self.engine_sqlite_data.al_rima;
+        (0..self.charger_max_boosts().len()).map(|idx| al_rima.is_eboosted(idx)).collect()
+    }
+
     pub fn create_turbo(&self) -> Option<engine::Turbo> {
         if self.engine_sqlite_data.aspiration.starts_with("Aspiration_Natural") {
             return None;
         }
         // todo update this to take into account the boost amount set and ignore any overboost that may skew the turbo section calculation
         let (ref_rpm, max_boost) = self.get_max_boost_params(3);
-        let mut t = engine::Turbo::new();
-        t.add_section(engine::turbo::TurboSection::new(
-            0,
-            // TODO work out how to better approximate these
-            0.99,
-            0.965,
-            max_boost,
-            max_boost,
-            (max_boost * 10_f64).ceil() / 10_f64,
-            ref_rpm,
-            2.5,
-            0)
-        );
-        Some(t)
+        Some(create_turbo_sections(ref_rpm,
+                                   max_boost,
+                                   &charger_boost_fractions(&self.charger_max_boosts()),
+                                   &self.charger_eboosted()))
     }
 
-    pub fn create_turbo_controller(&self) -> Option<engine::turbo_ctrl::TurboController> {
+    pub fn create_turbo_controllers(&self) -> Vec<engine::turbo_ctrl::TurboController> {
         if self.engine_sqlite_data.aspiration.starts_with("Aspiration_Natural") {
-            return None;
+            return Vec::new();
         }
 
-        let mut lut: Vec<(f64, f64)> = Vec::new();
-        for (idx, rpm) in self.engine_sqlite_data.rpm_curve.iter().enumerate() {
-            let mut boost_val = 0.0;
-            if self.engine_sqlite_data.boost_curve[idx] > boost_val {
-                boost_val = round_float_to(self.engine_sqlite_data.boost_curve[idx], 3);
+        let eboosted = self.charger_eboosted();
+        let eboost_max_boost = match self.engine_sqlite_data.al_rima.eboost_max_boost {
+            Some(eboost_max_boost) => eboost_max_boost,
+            None => self.get_max_boost_params(3).1
+        };
+        charger_boost_fractions(&self.charger_max_boosts()).into_iter().enumerate().map(|(charger_idx, fraction)| {
+            let mut lut: Vec<(f64, f64)> = Vec::new();
+            for (idx, rpm) in self.engine_sqlite_data.rpm_curve.iter().enumerate() {
+                // An e-boosted charger is spun up electrically so makes its full boost from idle
+                let curve_boost = match eboosted[charger_idx] {
+                    true => eboost_max_boost,
+                    false => self.engine_sqlite_data.boost_curve[idx]
+                };
+                let mut boost_val = 0.0;
+                if curve_boost > boost_val {
+                    boost_val = round_float_to(curve_boost * fraction, 3);
+                }
+                lut.push((*rpm, boost_val));
             }
-            lut.push((*rpm, boost_val));
-        }
-        let controller = engine::turbo_ctrl::TurboController::new(
-            0,
-            engine::turbo_ctrl::ControllerInput::Rpms,
-            engine::turbo_ctrl::ControllerCombinator::Add,
-            lut,
-            0.95,
-            10000_f64,
-            0_f64
-        );
-        Some(controller)
+            create_boost_controller(lut)
+        }).collect()
+    }
+
+    fn game_version(&self) -> u64 {
+        match &self.automation_car_file {
+            Some(car_file) => {
+                let variant_info = car_file.get_section("Car").unwrap().get_section("Variant").unwrap();
+                variant_info.get_attribute("GameVersion").unwrap().value.as_num().unwrap() as u64
+            }
+            None => self.engine_sqlite_data.variant_version
+        }
     }
 
-    pub fn coast_data(&self) -> Result<engine::CoastCurve, FabricationError> {
-        let variant_info = self.automation_car_file.get_section("Car").unwrap().get_section("Variant").unwrap();
-        let version_num = variant_info.get_attribute("GameVersion").unwrap().value.as_num().unwrap() as u64;
+    /// The sandbox data only has the friction torque at the limiter, not a per rpm friction
+    /// curve, so the coast curve comes from the BeamNG friction values where they're available
+    pub fn coast_data(&self, limiter: f64) -> Result<engine::CoastCurve, FabricationError> {
+        if self.engine_jbeam_data.is_none() {
+            warn!("No jbeam data available. Estimating coast data from engine displacement");
+            return Ok(self.coast_data_estimate(limiter));
+        }
+        let version_num = self.game_version();
         if version_num < 2209220000 {
             info!("Using v1 coast calculation for version {}", version_num);
-            return self.coast_data_v1();
+            return self.coast_data_v1(limiter);
         } else if version_num >= 2301100000 {
             info!("Using v3 coast calculation for version {}", version_num);
-            return self.coast_data_v3();
+            return self.coast_data_v3(limiter);
         }
         info!("Using v2 coast calculation for version {}", version_num);
-        return self.coast_data_v2();
+        return self.coast_data_v2(limiter);
+    }
+
+    /// Approximate the engine braking torque at the limiter. Uses the friction torque reported
+    /// by Automation 4.3+ if there is one, otherwise estimates it from displacement alone. Used
+    /// when there are no BeamNG friction values to work from
+    pub fn coast_data_estimate(&self, limiter: f64) -> engine::CoastCurve {
+        let engine_brake_torque = match self.engine_sqlite_data.al_rima.friction_torque {
+            Some(friction_torque) if friction_torque > 0.0 => friction_torque,
+            _ => {
+                // (100,000 Pa * displacement m^3) / (2 * pi * 2) for a 4-stroke engine
+                let displacement_m3 = self.engine_sqlite_data.get_capacity_cc() as f64 / 1_000_000f64;
+                (100_000f64 * displacement_m3) / (2.0 * std::f64::consts::PI * 2.0)
+            }
+        };
+        engine::CoastCurve::new_from_coast_ref(limiter.round() as i32,
+                                               engine_brake_torque.round() as i32,
+                                               0.0)
     }
 
-    pub fn coast_data_v1(&self) -> Result<engine::CoastCurve, FabricationError> {
+    pub fn coast_data_v1(&self, limiter: f64) -> Result<engine::CoastCurve, FabricationError> {
         //   The following data is available from the engine.jbeam exported file
         //   The dynamic friction torque on the engine in Nm/s.
         //   This is a friction torque which increases proportional to engine AV (rad/s).
@@ -559,30 +857,30 @@ impl EngineParameterCalculatorV1 {
         let eng_map = self.get_main_engine_jbeam_map()?;
         let dynamic_friction = get_f64_from_jbeam_map(eng_map, "dynamicFriction", "mainEngine")?;
         let static_friction = get_f64_from_jbeam_map(eng_map, "friction", "mainEngine")?;
-        let angular_velocity_at_max_rpm = (self.engine_sqlite_data.max_rpm * 2_f64 * std::f64::consts::PI) / 60_f64;
-        let friction_torque = (angular_velocity_at_max_rpm * dynamic_friction) + (2_f64 * static_friction);
-        Ok(engine::CoastCurve::new_from_coast_ref(self.engine_sqlite_data.max_rpm.round() as i32,
+        let angular_velocity_at_limiter = (limiter * 2_f64 * std::f64::consts::PI) / 60_f64;
+        let friction_torque = (angular_velocity_at_limiter * dynamic_friction) + (2_f64 * static_friction);
+        Ok(engine::CoastCurve::new_from_coast_ref(limiter.round() as i32,
                                                   friction_torque.round() as i32,
                                                   0.0))
     }
 
-    pub fn coast_data_v2(&self) -> Result<engine::CoastCurve, FabricationError> {
+    pub fn coast_data_v2(&self, limiter: f64) -> Result<engine::CoastCurve, FabricationError> {
         let eng_map = self.get_main_engine_jbeam_map()?;
         let dynamic_friction = get_f64_from_jbeam_map(eng_map, "dynamicFriction", "mainEngine")?;
         // Not sure if this is set correctly in the outputted jbeam files but the best we can work with atm
         let static_friction = get_f64_from_jbeam_map(eng_map, "engineBrakeTorque", "mainEngine")?;
-        let angular_velocity_at_max_rpm = (self.engine_sqlite_data.max_rpm / 60_f64) * 2_f64 * std::f64::consts::PI;
+        let angular_velocity_at_limiter = (limiter / 60_f64) * 2_f64 * std::f64::consts::PI;
         // TODO Assuming the jbeam files are correct I think this should be:
         // friction + dynamicFriction * engineAV + engineBrakeTorque
         // however friction and engineBrakeTorque are the same in the output jbeam files which
         // would result in too high a value. Add only engineBrakeTorque for now
-        let friction_torque = (angular_velocity_at_max_rpm * dynamic_friction) + static_friction;
-        Ok(engine::CoastCurve::new_from_coast_ref(self.engine_sqlite_data.max_rpm.round() as i32,
+        let friction_torque = (angular_velocity_at_limiter * dynamic_friction) + static_friction;
+        Ok(engine::CoastCurve::new_from_coast_ref(limiter.round() as i32,
                                                   friction_torque.round() as i32,
                                                   0.0))
     }
 
-    pub fn coast_data_v3(&self) -> Result<engine::CoastCurve, FabricationError> {
+    pub fn coast_data_v3(&self, limiter: f64) -> Result<engine::CoastCurve, FabricationError> {
         //   The following data is available from the engine.jbeam exported file
         //   The dynamic friction torque on the engine in Nm/s.
         //   This is a friction torque which increases proportional to engine AV (rad/s).
@@ -597,17 +895,17 @@ impl EngineParameterCalculatorV1 {
         // Not sure if this is set correctly in the outputted jbeam files but the best we can work with atm
         let static_friction = get_f64_from_jbeam_map(eng_map, "friction", "mainEngine")?;
         let engine_brake_torque = get_f64_from_jbeam_map(eng_map, "engineBrakeTorque", "mainEngine")?;
-        let angular_velocity_at_max_rpm = (self.engine_sqlite_data.max_rpm * 2_f64 * std::f64::consts::PI) / 60_f64;
-        let friction_torque = (angular_velocity_at_max_rpm * dynamic_friction) + engine_brake_torque + static_friction;
-        Ok(engine::CoastCurve::new_from_coast_ref(self.engine_sqlite_data.max_rpm.round() as i32,
+        let angular_velocity_at_limiter = (limiter * 2_f64 * std::f64::consts::PI) / 60_f64;
+        let friction_torque = (angular_velocity_at_limiter * dynamic_friction) + engine_brake_torque + static_friction;
+        Ok(engine::CoastCurve::new_from_coast_ref(limiter.round() as i32,
                                                   friction_torque.round() as i32,
                                                   0.0))
     }
 
-    pub fn damage(&self) -> engine::Damage {
+    pub fn damage(&self, limiter: f64) -> engine::Damage {
         let (_, max_boost) = self.get_max_boost_params(2);
         engine::Damage::new(
-            (self.limiter()+200_f64).round() as i32,
+            (limiter+200_f64).round() as i32,
             1,
             Some(max_boost.ceil()),
             match self.engine_sqlite_data.aspiration.as_str() {
@@ -617,7 +915,6 @@ impl EngineParameterCalculatorV1 {
         )
     }
 
-    #[allow(dead_code)]
     pub fn create_metadata(&self) -> engine::Metadata {
         let mut m = engine::Metadata::new();
         m.set_version(2);
@@ -625,30 +922,114 @@ impl EngineParameterCalculatorV1 {
         m.set_mass_kg(self.engine_sqlite_data.weight.round() as i64);
         m
     }
+
+    fn throttle_response_exponent(&self) -> Option<f64> {
+        let data = &self.engine_sqlite_data;
+        throttle_response_exponent(&data.fuel_system_type,
+                                   &data.fuel_system,
+                                   &data.intake,
+                                   Some(data.get_variant_build_year()))
+    }
+
+    pub fn provenance(&self) -> EngineProvenance {
+        let data = &self.engine_sqlite_data;
+        EngineProvenance {
+            name: data.friendly_name(),
+            automation_version: Some(self.game_version()),
+            build_year: Some(data.get_variant_build_year()),
+            configuration: Some(format!("{} {}", data.get_block_config(), data.get_head_config())),
+            aspiration: Some(data.get_aspiration().to_string()),
+            capacity_cc: Some(data.get_capacity_cc()),
+            block_config: Some(data.get_block_config()),
+            aspiration_type: Some(data.get_aspiration())
+        }
+    }
 }
 
 #[derive(Debug)]
-pub(crate) struct EngineParameterCalculatorV2 {
+pub struct EngineParameterCalculatorV2 {
     eng_data: crate_engine::direct_export::Data
 }
 
-fn lerp(a: f32, b: f32, t: f32) -> f32 {
-    a + t * (b - a)
+/// Parts that mark an intake or fuel system as a race part, matched ignoring case
+const RACE_PART_MARKERS: [&str; 2] = ["race", "itb"];
+
+/// The exponent applied to the pedal position to get the throttle opening of an engine with the
+/// given parts, or None if they don't say anything about its throttle response.
+/// Race intakes and fuel systems get a linear response. Carburetted engines get a softened low end
+/// that is strongest for the oldest engines
+pub(crate) fn throttle_response_exponent(fuel_system_type: &str,
+                                         fuel_system: &str,
+                                         intake: &str,
+                                         build_year: Option<u16>) -> Option<f64> {
+    let contains_any = |part: &str, markers: &[&str]| {
+        let part = part.to_lowercase();
+        markers.iter().any(|marker| part.contains(marker))
+    };
+    if contains_any(intake, &RACE_PART_MARKERS) || contains_any(fuel_system, &RACE_PART_MARKERS) {
+        return Some(1.0);
+    }
+    if !contains_any(fuel_system_type, &["carb"]) && !contains_any(fuel_system, &["carb"]) {
+        return None;
+    }
+    match build_year {
+        Some(year) if year < 1960 => Some(1.6),
+        Some(year) if year < 1980 => Some(1.4),
+        _ => Some(1.25)
+    }
 }
 
-fn normal_lerp(min: f32, max: f32, input: f32, standard_deviation: f64) -> f32 {
-    // Ensure t is between 0 and 1
-    let t = input.clamp(0.0, 1.0);
-
-    // Define the normal distribution with mean = 0.5 and standard deviation = 0.15
-    // These values can be adjusted based on how spread out you want the distribution
-    let normal = Normal::new(0.5, standard_deviation).unwrap();
+/// A pedal to throttle lut in percent, in steps of 10%, where throttle = pedal ^ `exponent`
+pub(crate) fn throttle_response_lut(exponent: f64) -> Vec<(i32, i32)> {
+    (0..=100).step_by(10).map(|pedal| {
+        (pedal, (100.0 * (pedal as f64 / 100.0).powf(exponent)).round() as i32)
+    }).collect()
+}
 
-    // Compute the CDF to map t to a normal distribution
-    let mapped_t = normal.cdf(t as f64) as f32;
+const MAX_COAST_NON_LINEARITY: f64 = 1.0;
+
+/// Create a COAST_REF from a curve of (rpm, friction torque Nm) points.
+/// AC applies the coast torque from zero at 0rpm up to TORQUE at the reference rpm. The shape in
+/// between is taken to be `TORQUE * (rpm / RPM)^(1 + NON_LINEARITY)` so NON_LINEARITY comes from
+/// a least squares fit of the friction curve in log space. The torque at `reference_rpm` is
+/// interpolated from the curve, or extrapolated with the fit if the curve doesn't reach it, and
+/// `additional_torque` is added to it for losses that the friction data doesn't include.
+/// Returns None if there aren't at least two usable points
+pub(crate) fn coast_curve_from_friction(friction_curve: &[(f64, f64)],
+                                        reference_rpm: f64,
+                                        additional_torque: f64) -> Option<engine::CoastCurve> {
+    let points: Vec<(f64, f64)> = friction_curve.iter()
+        .filter(|(rpm, torque)| rpm.is_finite() && torque.is_finite() && *rpm > 0.0 && *torque > 0.0)
+        .cloned()
+        .sorted_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+        .collect();
+    let (first, last) = (points.first()?, points.last()?);
+    if last.0 <= first.0 {
+        return None;
+    }
+
+    let log_points: Vec<(f64, f64)> = points.iter().map(|(rpm, torque)| (rpm.ln(), torque.ln())).collect();
+    let count = log_points.len() as f64;
+    let mean_x = log_points.iter().map(|p| p.0).sum::<f64>() / count;
+    let mean_y = log_points.iter().map(|p| p.1).sum::<f64>() / count;
+    let covariance: f64 = log_points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = log_points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let exponent = covariance / variance;
+    let scale = mean_y - (exponent * mean_x);
+
+    let friction_at_reference = match points.windows(2).find(|w| w[0].0 <= reference_rpm && reference_rpm <= w[1].0) {
+        Some(w) => w[0].1 + ((reference_rpm - w[0].0) / (w[1].0 - w[0].0)) * (w[1].1 - w[0].1),
+        None => (scale + (exponent * reference_rpm.ln())).exp()
+    };
+    let non_linearity = round_float_to((exponent - 1.0).clamp(0.0, MAX_COAST_NON_LINEARITY), 2);
+    debug!("Friction curve fit exponent {}. Coast torque {} at {}rpm", exponent, friction_at_reference, reference_rpm);
+    Some(engine::CoastCurve::new_from_coast_ref(reference_rpm.round() as i32,
+                                                (friction_at_reference + additional_torque).round() as i32,
+                                                non_linearity))
+}
 
-    // Perform the interpolation
-    min + mapped_t * (max - min)
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
 }
 
 
@@ -682,6 +1063,19 @@ impl EngineParameterCalculatorV2 {
         values.into_iter().max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
     }
 
+    pub fn automation_idle_rpm(&self) -> Option<f64> {
+        let idle = self.lookup_float_data("Results", "IdleRPM").ok()? as f64;
+        match idle.is_finite() && idle > 0.0 {
+            true => Some(idle),
+            false => None
+        }
+    }
+
+    /// The lowest rpm of the exported torque curve
+    pub fn curve_min_rpm(&self) -> Option<f64> {
+        self.lookup_curve_data("RPM").ok()?.get(&1).map(|rpm| *rpm as f64)
+    }
+
     pub fn limiter(&self) -> f64 {
         self.lookup_float_data("Results", "MaxRPM").unwrap() as f64
     }
@@ -741,6 +1135,19 @@ impl EngineParameterCalculatorV2 {
         self.lookup_float_data("Results", "PeakTorque").unwrap().round() as i32
     }
 
+    pub fn boost_curve(&self) -> Vec<(f64, f64)> {
+        if self.is_naturally_aspirated() {
+            return Vec::new();
+        }
+        let (rpm_map, boost_map) = match (self.lookup_curve_data("RPM"), self.lookup_curve_data("Boost")) {
+            (Ok(rpm_map), Ok(boost_map)) => (rpm_map, boost_map),
+            _ => return Vec::new()
+        };
+        rpm_map.iter().filter_map(|(idx, rpm)| {
+            boost_map.get(idx).map(|boost| (*rpm as f64, *boost as f64))
+        }).collect()
+    }
+
     /// Return a vector containing pairs of RPM, Power (BHP)
     pub fn engine_bhp_power_curve(&self) -> Vec<(i32, i32)> {
         let rpm_map = self.lookup_curve_data("RPM").unwrap();
@@ -788,15 +1195,7 @@ impl EngineParameterCalculatorV2 {
                 out_vec.push(((*rpm as i32), adjusted_value as f64));
             }
         }
-        // Taper curve down to 0
-        let rpm_increment;
-        {
-            let last_item = out_vec.last().unwrap();
-            let penultimate_item = out_vec.get(out_vec.len()-2).unwrap();
-            rpm_increment = last_item.0 - penultimate_item.0;
-        }
-        out_vec.push((out_vec.last().unwrap().0 + rpm_increment, out_vec.last().unwrap().1/2f64));
-        out_vec.push((out_vec.last().unwrap().0 + rpm_increment, 0f64));
+        taper_torque_curve(&mut out_vec);
         out_vec
     }
 
@@ -808,19 +1207,30 @@ impl EngineParameterCalculatorV2 {
         false
     }
 
-    pub fn get_max_boost_al_rima(&self, decimal_place_precision: u32) -> (i32, f64) {
-        let num_chargers = match self.lookup_string_data("Parts", "AspirationItem2") {
+    fn num_chargers(&self) -> usize {
+        match self.lookup_string_data("Parts", "AspirationItem2") {
             Ok(item2_string) => {
                 match item2_string.starts_with("NoOption_Name") {
                     true => 1,
                     false => 2
                 }
             }
-            Err(e) => {
+            Err(_) => {
                 warn!("Failed to determine number of forced induction chargers; assuming 1");
                 1
             }
-        };
+        }
+    }
+
+    /// The max boost set for each forced induction charger on the engine
+    fn charger_max_boosts(&self) -> Vec<Option<f64>> {
+        (1..=self.num_chargers()).map(|charger| {
+            self.lookup_float_data("Tune", &format!("ChargerMaxBoost{}", charger)).ok().map(|boost| boost as f64)
+        }).collect()
+    }
+
+    pub fn get_max_boost_al_rima(&self, decimal_place_precision: u32) -> (i32, f64) {
+        let num_chargers = self.num_chargers();
         let target_boost: Option<f32> = match self.lookup_float_data("Tune", "ChargerMaxBoost1") {
             Ok(charger1_boost) => {
                 match num_chargers > 1 {
@@ -904,47 +1314,27 @@ impl EngineParameterCalculatorV2 {
         }
         // todo update this to take into account the boost amount set and ignore any overboost that may skew the turbo section calculation
         let (ref_rpm, max_boost) = self.get_max_boost_params(3);
-        let mut t = engine::Turbo::new();
-        t.add_section(engine::turbo::TurboSection::new(
-            0,
-            // TODO work out how to better approximate these
-            0.99,
-            0.965,
-            max_boost,
-            max_boost,
-            (max_boost * 10_f64).ceil() / 10_f64,
-            ref_rpm,
-            2.5,
-            0)
-        );
-        Some(t)
+        Some(create_turbo_sections(ref_rpm, max_boost, &charger_boost_fractions(&self.charger_max_boosts()), &[]))
     }
 
-    pub fn create_turbo_controller(&self) -> Option<engine::turbo_ctrl::TurboController> {
+    pub fn create_turbo_controllers(&self) -> Vec<engine::turbo_ctrl::TurboController> {
         if self.is_naturally_aspirated() {
-            return None;
+            return Vec::new();
         }
 
         let rpm_map = self.lookup_curve_data("RPM").unwrap();
         let boost_map = self.lookup_curve_data("Boost").unwrap();
-        let mut lut: Vec<(f64, f64)> = Vec::new();
-        for (idx, rpm) in rpm_map {
-            let mut boost_val = 0.0;
-            if boost_map[idx] > boost_val {
-                boost_val = round_float_to(boost_map[idx] as f64, 3) as f32;
+        charger_boost_fractions(&self.charger_max_boosts()).into_iter().map(|fraction| {
+            let mut lut: Vec<(f64, f64)> = Vec::new();
+            for (idx, rpm) in rpm_map {
+                let mut boost_val = 0.0;
+                if boost_map[idx] > boost_val {
+                    boost_val = round_float_to(boost_map[idx] as f64 * fraction, 3) as f32;
+                }
+                lut.push((*rpm as f64, boost_val as f64));
             }
-            lut.push((*rpm as f64, boost_val as f64));
-        }
-        let controller = engine::turbo_ctrl::TurboController::new(
-            0,
-            engine::turbo_ctrl::ControllerInput::Rpms,
-            engine::turbo_ctrl::ControllerCombinator::Add,
-            lut,
-            0.95,
-            10000_f64,
-            0_f64
-        );
-        Some(controller)
+            create_boost_controller(lut)
+        }).collect()
     }
 
     // From https://www.eng-tips.com/viewthread.cfm?qid=338422
@@ -962,19 +1352,32 @@ impl EngineParameterCalculatorV2 {
         self.lookup_float_data("Tune", "Displacement").unwrap()
     }
 
-    pub fn coast_data(&self) -> Result<engine::CoastCurve, FabricationError> {
-        let friction_map = self.lookup_curve_data("Friction").unwrap();
+    pub fn coast_data(&self, limiter: f64) -> Result<engine::CoastCurve, FabricationError> {
+        let friction_map = self.lookup_curve_data("Friction")?;
+        let friction_curve: Vec<(f64, f64)> = match self.lookup_curve_data("RPM") {
+            Ok(rpm_map) => rpm_map.iter().filter_map(|(idx, rpm)| {
+                friction_map.get(idx).map(|friction| (*rpm as f64, *friction as f64))
+            }).collect(),
+            Err(_) => Vec::new()
+        };
+        if let Some(coast_curve) = coast_curve_from_friction(&friction_curve,
+                                                             limiter,
+                                                             self.calculate_approx_engine_brake_force()) {
+            return Ok(coast_curve);
+        }
+
+        warn!("Couldn't fit a coast curve to the friction data. Using the friction at the last point");
         let (_, max_friction) = friction_map.last_key_value().ok_or_else(||MissingDataSection("Friction".to_string(), "curve_data".to_string() ))?;
         let engine_brake_torque = *max_friction as f64 + self.calculate_approx_engine_brake_force();
-        Ok(engine::CoastCurve::new_from_coast_ref(self.limiter() as i32,
+        Ok(engine::CoastCurve::new_from_coast_ref(limiter.round() as i32,
                                                   engine_brake_torque.round() as i32,
                                                   0.0))
     }
 
-    pub fn damage(&self) -> engine::Damage {
+    pub fn damage(&self, limiter: f64) -> engine::Damage {
         let (_, max_boost) = self.get_max_boost_params(2);
         engine::Damage::new(
-            (self.limiter()+200_f64).round() as i32,
+            (limiter+200_f64).round() as i32,
             1,
             Some(max_boost.ceil()),
             match self.is_naturally_aspirated() {
@@ -984,7 +1387,6 @@ impl EngineParameterCalculatorV2 {
         )
     }
 
-    #[allow(dead_code)]
     pub fn create_metadata(&self) -> engine::Metadata {
         let mut m = engine::Metadata::new();
         m.set_version(2);
@@ -993,6 +1395,39 @@ impl EngineParameterCalculatorV2 {
         m
     }
 
+    fn throttle_response_exponent(&self) -> Option<f64> {
+        throttle_response_exponent(self.lookup_string_data("Parts", "FuelSystemType").unwrap_or_default(),
+                                   self.lookup_string_data("Parts", "FuelSystem").unwrap_or_default(),
+                                   self.lookup_string_data("Parts", "Intake").unwrap_or_default(),
+                                   self.lookup_float_data("Info", "VariantYear").ok().map(|v| v.round() as u16))
+    }
+
+    pub fn provenance(&self) -> EngineProvenance {
+        let name = match &self.eng_data {
+            Data::V1(data) => data.deduce_engine_name()
+        };
+        let block_config = self.lookup_string_data("Parts", "BlockType").ok().map(|block| {
+            block.parse::<BlockConfig>().unwrap()
+        });
+        let configuration = match (&block_config, self.lookup_string_data("Parts", "HeadType")) {
+            (Some(block), Ok(head)) => Some(format!("{} {}", block, head.parse::<HeadConfig>().unwrap())),
+            _ => None
+        };
+        let aspiration_type = self.lookup_string_data("Parts", "Aspiration").ok().map(|a| {
+            a.parse::<AspirationType>().unwrap()
+        });
+        EngineProvenance {
+            name,
+            automation_version: self.lookup_float_data("Info", "GameVersion").ok().map(|v| v.round() as u64),
+            build_year: self.lookup_float_data("Info", "VariantYear").ok().map(|v| v.round() as u16),
+            configuration,
+            aspiration: aspiration_type.as_ref().map(|a| a.to_string()),
+            capacity_cc: self.lookup_float_data("Tune", "Displacement").ok().map(|d| (d * 1000.0).round() as u32),
+            block_config,
+            aspiration_type
+        }
+    }
+
     fn lookup_float_data(&self, group: &str, key: &str) -> Result<f32, FabricationError> {
         match &self.eng_data {
             Data::V1(data) => {
@@ -1058,6 +1493,83 @@ impl Display for AssettoCorsaPhysicsLevel {
     }
 }
 
+/// LAG_DN and LAG_UP values for the primary turbo section
+// TODO work out how to better approximate these
+const PRIMARY_TURBO_LAG: (f64, f64) = (0.99, 0.965);
+/// LAG_DN and LAG_UP values for any additional turbo sections. These spool up slower than the
+/// primary as the second stage of a bi-turbo setup comes in later
+const SECONDARY_TURBO_LAG: (f64, f64) = (0.99, 0.98);
+/// LAG_DN and LAG_UP values for an e-boosted charger. The electric motor keeps the charger
+/// spun up so there is effectively no lag
+const EBOOST_TURBO_LAG: (f64, f64) = (0.0, 0.0);
+
+/// Whether an Automation "AspirationItemOption_2" value describes a second charger
+fn is_second_charger_option(item: Option<&str>) -> bool {
+    match item {
+        None => false,
+        Some(item) => !item.is_empty() && !item.starts_with("NoOption")
+    }
+}
+
+/// Split the total boost of an engine between its chargers in proportion to the max boost set
+/// for each of them. The split is even if any of the max boost values are unknown
+pub(crate) fn charger_boost_fractions(charger_max_boosts: &[Option<f64>]) -> Vec<f64> {
+    if charger_max_boosts.len() < 2 {
+        return vec![1.0];
+    }
+    let known_boosts: Option<Vec<f64>> = charger_max_boosts.iter().map(|boost| {
+        boost.filter(|val| val.is_finite() && *val > 0.0)
+    }).collect();
+    match known_boosts {
+        Some(boosts) => {
+            let total: f64 = boosts.iter().sum();
+            boosts.iter().map(|boost| boost / total).collect()
+        }
+        None => {
+            warn!("Max boost not known for all {} chargers; splitting boost evenly", charger_max_boosts.len());
+            vec![1.0 / charger_max_boosts.len() as f64; charger_max_boosts.len()]
+        }
+    }
+}
+
+/// Create a turbo with a TURBO_N section for each charger, each providing its share of `max_boost`.
+/// Chargers flagged in `eboosted` get no lag; any charger missing from `eboosted` is a plain turbo
+fn create_turbo_sections(ref_rpm: i32, max_boost: f64, boost_fractions: &[f64], eboosted: &[bool]) -> engine::Turbo {
+    let mut t = engine::Turbo::new();
+    for (idx, fraction) in boost_fractions.iter().enumerate() {
+        let (lag_dn, lag_up) = match (idx, eboosted.get(idx).copied().unwrap_or(false)) {
+            (_, true) => EBOOST_TURBO_LAG,
+            (0, false) => PRIMARY_TURBO_LAG,
+            (_, false) => SECONDARY_TURBO_LAG
+        };
+        let section_boost = max_boost * fraction;
+        t.add_section(engine::turbo::TurboSection::new(
+            idx,
+            lag_dn,
+            lag_up,
+            section_boost,
+            section_boost,
+            (section_boost * 10_f64).ceil() / 10_f64,
+            ref_rpm,
+            2.5,
+            0)
+        );
+    }
+    t
+}
+
+fn create_boost_controller(lut: Vec<(f64, f64)>) -> engine::turbo_ctrl::TurboController {
+    engine::turbo_ctrl::TurboController::new(
+        0,
+        engine::turbo_ctrl::ControllerInput::Rpms,
+        engine::turbo_ctrl::ControllerCombinator::Add,
+        lut,
+        0.95,
+        10000_f64,
+        0_f64
+    )
+}
+
 pub fn normalise_boost_value(boost_value: f64, decimal_places: u32) -> f64 {
     round_float_to(vec![0.0, boost_value].into_iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap(), decimal_places)
 }
@@ -1087,3 +1599,45 @@ fn get_f64_from_jbeam_map<'a>(map: &'a serde_hjson::Map<String, serde_hjson::Val
         InvalidData(key.to_string(), "expected to be an f64".to_string())
     })?)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate_engine::direct_export::{Data, DataV1};
+    use crate::fabricator::assetto_corsa::{coast_curve_from_friction, EngineParameterCalculatorV2};
+
+    #[test]
+    fn coast_curve_fitted_to_friction_data() {
+        // Friction rising with the square of rpm: 10Nm at 1000rpm up to 490Nm at 7000rpm
+        let quadratic: Vec<(f64, f64)> = (1..=7).map(|i| {
+            (i as f64 * 1000.0, 10.0 * (i as f64).powi(2))
+        }).collect();
+        let coast = coast_curve_from_friction(&quadratic, 7000.0, 0.0).unwrap();
+        assert_eq!((coast.reference_rpm(), coast.torque(), coast.non_linearity()), (7000, 490, 1.0));
+        // Past the end of the data the torque follows the fitted curve. 10 * 7.5^2 = 562.5
+        let coast = coast_curve_from_friction(&quadratic, 7500.0, 0.0).unwrap();
+        assert_eq!((coast.reference_rpm(), coast.torque(), coast.non_linearity()), (7500, 563, 1.0));
+
+        // Friction linear with rpm, with additional pumping losses at the reference rpm.
+        // 0.01 * 6500 + 20 = 85
+        let linear: Vec<(f64, f64)> = (1..=7).map(|i| (i as f64 * 1000.0, i as f64 * 10.0)).collect();
+        let coast = coast_curve_from_friction(&linear, 6500.0, 20.0).unwrap();
+        assert_eq!((coast.reference_rpm(), coast.torque(), coast.non_linearity()), (6500, 85, 0.0));
+
+        assert!(coast_curve_from_friction(&[(1000.0, 10.0)], 7000.0, 0.0).is_none());
+        assert!(coast_curve_from_friction(&[(0.0, 0.0), (1000.0, 0.0)], 7000.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn direct_export_coast_curve_uses_friction_data() {
+        let mut data = DataV1::new();
+        data.add_float("Tune".to_string(), "Displacement".to_string(), 2.0);
+        for i in 0..7 {
+            data.add_curve_data("RPM".to_string(), i, (i as f32 + 1.0) * 1000.0);
+            data.add_curve_data("Friction".to_string(), i, (i as f32 + 1.0) * 10.0);
+        }
+        let calculator = EngineParameterCalculatorV2 { eng_data: Data::V1(data) };
+        // 0.01 * 6500 from the friction data + (100000 * 0.002) / (4 * pi) pumping losses = 80.9
+        let coast = calculator.coast_data(6500.0).unwrap();
+        assert_eq!((coast.reference_rpm(), coast.torque(), coast.non_linearity()), (6500, 81, 0.0));
+    }
+}
@@ -0,0 +1,317 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use utils::hash::sha256_hex;
+
+use crate::assetto_corsa::Car;
+use crate::fabricator::FabricationError;
+use crate::fabricator::report::SWAP_REPORT_FILENAME;
+
+pub const BACKUP_FOLDER_NAME: &str = ".engine-crane-backup";
+const STAGING_FOLDER_NAME: &str = ".engine-crane-backup.tmp";
+/// Folders in the car root that only engine-crane uses
+pub(crate) const WORKING_FOLDER_NAMES: [&str; 2] = [BACKUP_FOLDER_NAME, STAGING_FOLDER_NAME];
+const MANIFEST_FILENAME: &str = "manifest.json";
+const DATA_FOLDER_NAME: &str = "data";
+const CAR_FOLDER_NAME: &str = "car";
+
+pub(crate) const UI_CAR_FILE: &str = "ui/ui_car.json";
+pub(crate) const UPGRADE_ICON_FILE: &str = "ui/upgrade.png";
+/// Files outside the car's data interface that a swap can modify. Paths are relative to the car
+/// root and use '/' as a separator
+const CAR_FILES: [&str; 3] = [UI_CAR_FILE, UPGRADE_ICON_FILE, SWAP_REPORT_FILENAME];
+pub(crate) const SFX_GUIDS_FILE: &str = "sfx/GUIDs.txt";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupEntry {
+    name: String,
+    /// The sha256 hash of the file contents or None if the file didn't exist before the swap
+    sha256: Option<String>
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BackupManifest {
+    data_files: Vec<BackupEntry>,
+    car_files: Vec<BackupEntry>
+}
+
+/// An in-memory copy of the car files that an engine swap will modify.
+/// Taken and persisted to the car folder before the swap writes anything
+#[derive(Debug)]
+pub(crate) struct CarBackup {
+    data_files: Vec<(String, Option<Vec<u8>>)>,
+    car_files: Vec<(String, Option<Vec<u8>>)>
+}
+
+impl CarBackup {
+    /// Copy every file in the car's data interface, as the ini files a swap updates can reference
+    /// any number of lut files, along with the other car files a swap writes. The car's sound
+    /// files are included if `include_sound` is set
+    pub(crate) fn snapshot(car: &Car, include_sound: bool) -> Result<CarBackup, FabricationError> {
+        let data_interface = car.data_interface();
+        let mut data_filenames = data_interface.original_file_names().map_err(|e| {
+            FabricationError::FailedToLoad(car.root_path().join("data").display().to_string(), e.to_string())
+        })?;
+        data_filenames.sort();
+
+        let mut data_files = Vec::new();
+        for filename in data_filenames {
+            let contents = data_interface.get_original_file_data(&filename).map_err(|e| {
+                FabricationError::FailedToLoad(filename.clone(), e.to_string())
+            })?;
+            data_files.push((filename, contents));
+        }
+
+        let mut car_filenames: Vec<String> = CAR_FILES.iter().map(|name| name.to_string()).collect();
+        if include_sound {
+            car_filenames.push(sound_bank_file(car.root_path()));
+            car_filenames.push(SFX_GUIDS_FILE.to_string());
+        }
+        let mut car_files = Vec::new();
+        for name in car_filenames {
+            let path = car_file_path(car.root_path(), &name);
+            let contents = match path.is_file() {
+                true => Some(fs::read(&path)?),
+                false => None
+            };
+            car_files.push((name, contents));
+        }
+        Ok(CarBackup { data_files, car_files })
+    }
+
+    /// Write the backup into the car folder, replacing any previous backup
+    pub(crate) fn write(&self, car_path: &Path) -> Result<(), FabricationError> {
+        let staging_path = car_path.join(STAGING_FOLDER_NAME);
+        if staging_path.exists() {
+            fs::remove_dir_all(&staging_path)?;
+        }
+        let mut manifest = BackupManifest::default();
+        for (name, contents) in &self.data_files {
+            manifest.data_files.push(write_entry(&staging_path.join(DATA_FOLDER_NAME), name, contents)?);
+        }
+        for (name, contents) in &self.car_files {
+            manifest.car_files.push(write_entry(&staging_path.join(CAR_FOLDER_NAME), name, contents)?);
+        }
+        let manifest_data = serde_json::to_vec_pretty(&manifest).map_err(|e| {
+            FabricationError::FailedToWrite(MANIFEST_FILENAME.to_string(), e.to_string())
+        })?;
+        fs::write(staging_path.join(MANIFEST_FILENAME), manifest_data)?;
+
+        let backup_path = car_path.join(BACKUP_FOLDER_NAME);
+        if backup_path.exists() {
+            fs::remove_dir_all(&backup_path)?;
+        }
+        fs::rename(&staging_path, &backup_path)?;
+        info!("Stored swap backup in {}", backup_path.display());
+        Ok(())
+    }
+}
+
+/// Whether the car at `car_path` has a backup from a previous engine swap
+pub fn has_backup(car_path: &Path) -> bool {
+    car_path.join(BACKUP_FOLDER_NAME).join(MANIFEST_FILENAME).is_file()
+}
+
+/// Restore the files modified by the last engine swap into the car at `car_path`.
+/// Every file in the backup is checked against the hash recorded when it was taken before
+/// anything is written. Data files that the swap created are removed. The backup is removed once
+/// it has been restored
+pub fn restore_backup(car_path: &Path) -> Result<(), FabricationError> {
+    use FabricationError::*;
+
+    let backup_path = car_path.join(BACKUP_FOLDER_NAME);
+    let manifest_path = backup_path.join(MANIFEST_FILENAME);
+    if !manifest_path.is_file() {
+        return Err(MissingDataSource(format!("swap backup in {}", car_path.display())));
+    }
+    let manifest: BackupManifest = serde_json::from_slice(&fs::read(&manifest_path)?).map_err(|e| {
+        FailedToLoad(manifest_path.display().to_string(), e.to_string())
+    })?;
+    let data_files = read_entries(&backup_path.join(DATA_FOLDER_NAME), &manifest.data_files)?;
+    let car_files = read_entries(&backup_path.join(CAR_FOLDER_NAME), &manifest.car_files)?;
+
+    info!("Restoring swap backup into {}", car_path.display());
+    let mut car = Car::load_from_path(car_path).map_err(|err| {
+        FailedToLoad(car_path.display().to_string(), err.to_string())
+    })?;
+    let data_interface = car.mut_data_interface();
+    let created_files: Vec<String> = data_interface.original_file_names().map_err(|e| {
+        FailedToLoad(car_path.join("data").display().to_string(), e.to_string())
+    })?.into_iter().filter(|name| !data_files.iter().any(|(backup_name, _)| backup_name == name)).collect();
+    for name in created_files {
+        data_interface.remove_file(&name);
+    }
+    for (name, contents) in data_files {
+        match contents {
+            Some(data) => data_interface.update_file_data(&name, data),
+            None => if data_interface.contains_file(&name) {
+                data_interface.remove_file(&name)
+            }
+        }
+    }
+    data_interface.write().map_err(|e| {
+        FailedToWrite(car_path.display().to_string(), e.to_string())
+    })?;
+
+    for (name, contents) in car_files {
+        let path = car_file_path(car_path, &name);
+        match contents {
+            Some(data) => fs::write(&path, data)?,
+            None => if path.is_file() {
+                fs::remove_file(&path)?
+            }
+        }
+    }
+
+    if let Err(e) = fs::remove_dir_all(&backup_path) {
+        warn!("Failed to remove swap backup {}. {}", backup_path.display(), e.to_string());
+    }
+    Ok(())
+}
+
+fn write_entry(folder: &Path, name: &str, contents: &Option<Vec<u8>>) -> Result<BackupEntry, FabricationError> {
+    let sha256 = match contents {
+        None => None,
+        Some(data) => {
+            let path = car_file_path(folder, name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, data)?;
+            Some(sha256_hex(data))
+        }
+    };
+    Ok(BackupEntry { name: name.to_string(), sha256 })
+}
+
+fn read_entries(folder: &Path, entries: &[BackupEntry]) -> Result<Vec<(String, Option<Vec<u8>>)>, FabricationError> {
+    let mut out = Vec::new();
+    for entry in entries {
+        if entry.name.split('/').any(|component| component == ".." || component.is_empty()) {
+            return Err(FabricationError::ValidationError(
+                format!("Swap backup contains an invalid path {}", entry.name)
+            ));
+        }
+        let contents = match &entry.sha256 {
+            None => None,
+            Some(expected_hash) => {
+                let data = fs::read(car_file_path(folder, &entry.name)).map_err(|e| {
+                    FabricationError::FailedToLoad(format!("{} from swap backup", entry.name), e.to_string())
+                })?;
+                if sha256_hex(&data) != *expected_hash {
+                    return Err(FabricationError::ValidationError(
+                        format!("{} in the swap backup has been modified or is corrupt", entry.name)
+                    ));
+                }
+                Some(data)
+            }
+        };
+        out.push((entry.name.clone(), contents));
+    }
+    Ok(out)
+}
+
+/// The path of the car's sound bank relative to the car root
+pub(crate) fn sound_bank_file(car_path: &Path) -> String {
+    format!("sfx/{}.bank", car_path.file_name().unwrap_or_default().to_string_lossy())
+}
+
+fn car_file_path(root: &Path, name: &str) -> PathBuf {
+    name.split('/').fold(root.to_path_buf(), |path, component| path.join(component))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use crate::assetto_corsa::Car;
+    use crate::fabricator::backup::{BACKUP_FOLDER_NAME, CarBackup, has_backup, restore_backup};
+
+    fn create_test_car(root: &Path) -> PathBuf {
+        let car_path = root.join("car");
+        fs::create_dir_all(car_path.join("data")).unwrap();
+        fs::create_dir_all(car_path.join("ui")).unwrap();
+        fs::write(car_path.join("data").join("engine.ini"), "[ENGINE_DATA]\nLIMITER=7000\n").unwrap();
+        fs::write(car_path.join("data").join("power.lut"), "0|100\n7000|300\n").unwrap();
+        fs::write(car_path.join("ui").join("ui_car.json"), "{\"name\": \"test\"}").unwrap();
+        car_path
+    }
+
+    #[test]
+    fn restore_backup_reverts_swapped_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path());
+        let car = Car::load_from_path(&car_path).unwrap();
+        let backup = CarBackup::snapshot(&car, false).unwrap();
+        backup.write(&car_path).unwrap();
+        assert!(has_backup(&car_path));
+
+        fs::write(car_path.join("data").join("engine.ini"), "[ENGINE_DATA]\nLIMITER=9000\n").unwrap();
+        fs::write(car_path.join("data").join("power.lut"), "0|200\n9000|400\n").unwrap();
+        fs::write(car_path.join("data").join("ctrl_turbo0.ini"), "[CONTROLLER_0]\n").unwrap();
+        fs::write(car_path.join("ui").join("ui_car.json"), "{\"name\": \"swapped\"}").unwrap();
+        restore_backup(&car_path).unwrap();
+
+        assert_eq!(fs::read_to_string(car_path.join("data").join("engine.ini")).unwrap(),
+                   "[ENGINE_DATA]\nLIMITER=7000\n");
+        assert_eq!(fs::read_to_string(car_path.join("data").join("power.lut")).unwrap(),
+                   "0|100\n7000|300\n");
+        assert!(!car_path.join("data").join("ctrl_turbo0.ini").exists());
+        assert_eq!(fs::read_to_string(car_path.join("ui").join("ui_car.json")).unwrap(),
+                   "{\"name\": \"test\"}");
+        assert!(!has_backup(&car_path));
+    }
+
+    #[test]
+    fn sound_files_restored_when_included() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path());
+        fs::create_dir_all(car_path.join("sfx")).unwrap();
+        fs::write(car_path.join("sfx").join("car.bank"), b"original bank").unwrap();
+        let car = Car::load_from_path(&car_path).unwrap();
+        CarBackup::snapshot(&car, true).unwrap().write(&car_path).unwrap();
+
+        fs::write(car_path.join("sfx").join("car.bank"), b"donor bank").unwrap();
+        fs::write(car_path.join("sfx").join("GUIDs.txt"), "{guid} event:/cars/car/engine_ext\n").unwrap();
+        restore_backup(&car_path).unwrap();
+
+        assert_eq!(fs::read(car_path.join("sfx").join("car.bank")).unwrap(), b"original bank");
+        assert!(!car_path.join("sfx").join("GUIDs.txt").exists());
+    }
+
+    #[test]
+    fn modified_backup_is_not_restored() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path());
+        let car = Car::load_from_path(&car_path).unwrap();
+        CarBackup::snapshot(&car, false).unwrap().write(&car_path).unwrap();
+
+        fs::write(car_path.join("data").join("engine.ini"), "[ENGINE_DATA]\nLIMITER=9000\n").unwrap();
+        fs::write(car_path.join(BACKUP_FOLDER_NAME).join("data").join("engine.ini"), "tampered").unwrap();
+        assert!(restore_backup(&car_path).is_err());
+        assert_eq!(fs::read_to_string(car_path.join("data").join("engine.ini")).unwrap(),
+                   "[ENGINE_DATA]\nLIMITER=9000\n");
+    }
+}
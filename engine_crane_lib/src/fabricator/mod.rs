@@ -0,0 +1,2785 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+mod assetto_corsa;
+mod backup;
+mod preview;
+mod report;
+mod sound;
+mod weight;
+
+use std::fmt::{Display, Formatter};
+use std::{fs, io};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde_hjson;
+use tracing::{error, info, warn};
+use automation::sandbox::{EngineV1, SandboxFinder};
+use utils::hash::sha256_hex;
+use utils::numeric::{round_float_to, round_up_to_nearest_multiple};
+use utils::time::current_utc_date_string;
+use utils::units::{calculate_power_kw, kw_to_bhp, Power, PowerUnit, Torque, TorqueUnit};
+
+use crate::assetto_corsa::car::data::engine::{CoastCurve, Damage, EngineData, PowerCurve, ThrottleMap};
+
+use crate::assetto_corsa::Car;
+use crate::assetto_corsa::car::{CloneStage, export_car_package, PackageOptions};
+use crate::assetto_corsa::car::model::PerformanceEstimator;
+use crate::assetto_corsa::car::data;
+use crate::assetto_corsa::car::data::ai::Ai;
+use crate::assetto_corsa::car::data::{CarIniData, Suspensions};
+use crate::assetto_corsa::car::data::car_ini_data::CarVersion;
+use crate::assetto_corsa::car::data::digital_instruments::DigitalInstruments;
+use crate::assetto_corsa::car::data::digital_instruments::shift_lights::ShiftLights;
+use crate::assetto_corsa::car::ui::{CarUiData, CarUpgradeIcon};
+use crate::assetto_corsa::car::data::Drivetrain;
+use crate::assetto_corsa::car::data::Engine;
+use crate::assetto_corsa::car::data::engine;
+use crate::assetto_corsa::car::data::engine::turbo_ctrl::delete_all_turbo_controllers_from_car;
+
+use crate::assetto_corsa::ini_utils::FloatPrecision;
+use crate::assetto_corsa::traits::{CarDataFile, extract_mandatory_section, extract_optional_section, OptionalDataSection, update_car_data};
+use crate::fabricator::assetto_corsa::UPGRADE_ICON_BYTES;
+use crate::fabricator::backup::CarBackup;
+use crate::fabricator::report::SwapReport;
+
+pub use self::assetto_corsa::{EngineParameterCalculator, EngineProvenance, TorqueCurveSource};
+pub use backup::{has_backup, restore_backup};
+pub use report::{swap_report_path, swap_report_source_hash};
+pub use preview::SwapPreview;
+pub use sound::suggest_sound_donors;
+pub use weight::{automation_engine_weight, crate_engine_weight, estimate_engine_weight, sandbox_engine_weight};
+
+#[derive(thiserror::Error, Debug)]
+pub enum FabricationError {
+    #[error("io error")]
+    IoError(#[from] io::Error),
+    #[error("assetto corsa data error")]
+    ACDataError(#[from] crate::assetto_corsa::error::Error),
+    #[error("BeamNG mod data error. `{0}`")]
+    BeamNGModDataError(String),
+    #[error("jbeam encoding error")]
+    JBeamError(#[from] serde_hjson::Error),
+    #[error("invalid data `{0}`. `{1}`")]
+    InvalidData(String, String),
+    #[error("missing data source `{0}`")]
+    MissingDataSource(String),
+    #[error("missing data section `{0}` from `{1}`")]
+    MissingDataSection(String, String),
+    #[error("failed to update `{0}` in `{1}`. `{2}`")]
+    FailedToUpdate(String, String, String),
+    #[error("failed to load `{0}`. `{1}`")]
+    FailedToLoad(String, String),
+    #[error("failed to write `{0}`. `{1}`")]
+    FailedToWrite(String, String),
+    #[error("Data validation failure. `{0}`")]
+    ValidationError(String),
+    #[error("swap cancelled")]
+    Cancelled,
+    #[error("fabrication error: `{0}`")]
+    Other(String)
+}
+
+/// A problem found during a swap that didn't stop the car being created, such as a file that
+/// couldn't be updated. The car may not behave as expected until it is looked at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FabricationWarning {
+    file: Option<String>,
+    message: String
+}
+
+impl FabricationWarning {
+    pub fn new(message: String) -> FabricationWarning {
+        FabricationWarning { file: None, message }
+    }
+
+    /// A warning about the car file `file`
+    pub fn for_file(file: &str, message: String) -> FabricationWarning {
+        FabricationWarning { file: Some(file.to_string()), message }
+    }
+
+    /// The car file the warning is about, if it is about a single file
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for FabricationWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}: {}", file, self.message),
+            None => write!(f, "{}", self.message)
+        }
+    }
+}
+
+pub fn swap_automation_engine_into_ac_car(beam_ng_mod_path: &Path,
+                                          ac_car_path: &Path,
+                                          sandbox_finder: SandboxFinder,
+                                          settings: AssettoCorsaCarSettings,
+                                          additional_car_data: AdditionalAcCarData) -> Result<Vec<FabricationWarning>, FabricationError> {
+    settings.progress.report(SwapPhase::CalculatingEngineParameters);
+    let calculator = assetto_corsa::EngineParameterCalculator::from_beam_ng_mod(beam_ng_mod_path, sandbox_finder)?;
+    update_ac_engine_parameters(ac_car_path,
+                                calculator,
+                                settings, additional_car_data.with_engine_source_file(beam_ng_mod_path)
+    )
+}
+
+pub fn swap_crate_engine_into_ac_car(crate_engine_path: &Path,
+                                     ac_car_path: &Path,
+                                     settings: AssettoCorsaCarSettings,
+                                     additional_car_data: AdditionalAcCarData) -> Result<Vec<FabricationWarning>, FabricationError> {
+    settings.progress.report(SwapPhase::CalculatingEngineParameters);
+    let calculator = assetto_corsa::EngineParameterCalculator::from_crate_engine(crate_engine_path)?;
+    update_ac_engine_parameters(ac_car_path,
+                                calculator,
+                                settings, additional_car_data.with_engine_source_file(crate_engine_path)
+    )
+}
+
+pub fn swap_sandbox_engine_into_ac_car(engine: EngineV1,
+                                       ac_car_path: &Path,
+                                       settings: AssettoCorsaCarSettings,
+                                       additional_car_data: AdditionalAcCarData) -> Result<Vec<FabricationWarning>, FabricationError> {
+    settings.progress.report(SwapPhase::CalculatingEngineParameters);
+    let calculator = assetto_corsa::EngineParameterCalculator::from_sandbox_engine(engine)?;
+    update_ac_engine_parameters(ac_car_path,
+                                calculator,
+                                settings, additional_car_data
+    )
+}
+
+pub fn preview_automation_engine_swap(beam_ng_mod_path: &Path,
+                                      ac_car_path: &Path,
+                                      sandbox_finder: SandboxFinder,
+                                      settings: AssettoCorsaCarSettings,
+                                      additional_car_data: AdditionalAcCarData) -> Result<SwapPreview, FabricationError> {
+    let calculator = assetto_corsa::EngineParameterCalculator::from_beam_ng_mod(beam_ng_mod_path, sandbox_finder)?;
+    preview_ac_engine_parameters(ac_car_path,
+                                 calculator,
+                                 settings, additional_car_data.with_engine_source_file(beam_ng_mod_path)
+    )
+}
+
+pub fn preview_crate_engine_swap(crate_engine_path: &Path,
+                                 ac_car_path: &Path,
+                                 settings: AssettoCorsaCarSettings,
+                                 additional_car_data: AdditionalAcCarData) -> Result<SwapPreview, FabricationError> {
+    let calculator = assetto_corsa::EngineParameterCalculator::from_crate_engine(crate_engine_path)?;
+    preview_ac_engine_parameters(ac_car_path,
+                                 calculator,
+                                 settings, additional_car_data.with_engine_source_file(crate_engine_path)
+    )
+}
+
+pub fn preview_sandbox_engine_swap(engine: EngineV1,
+                                   ac_car_path: &Path,
+                                   settings: AssettoCorsaCarSettings,
+                                   additional_car_data: AdditionalAcCarData) -> Result<SwapPreview, FabricationError> {
+    let calculator = assetto_corsa::EngineParameterCalculator::from_sandbox_engine(engine)?;
+    preview_ac_engine_parameters(ac_car_path,
+                                 calculator,
+                                 settings, additional_car_data
+    )
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AssettoCorsaPhysicsLevel {
+    BaseGame,
+    CspExtendedPhysics
+}
+
+impl AssettoCorsaPhysicsLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssettoCorsaPhysicsLevel::BaseGame => { "Base game physics"}
+            AssettoCorsaPhysicsLevel::CspExtendedPhysics => { "CSP extended physics" }
+        }
+    }
+}
+
+impl Default for AssettoCorsaPhysicsLevel {
+    fn default() -> Self {
+        AssettoCorsaPhysicsLevel::BaseGame
+    }
+}
+
+impl Display for AssettoCorsaPhysicsLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LimiterSource {
+    Automation,
+    JBeam
+}
+
+impl LimiterSource {
+    pub const ALL: &'static [LimiterSource] = &[LimiterSource::Automation, LimiterSource::JBeam];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimiterSource::Automation => { "Automation limiter" }
+            LimiterSource::JBeam => { "JBeam limiter" }
+        }
+    }
+}
+
+impl Default for LimiterSource {
+    fn default() -> Self {
+        LimiterSource::Automation
+    }
+}
+
+impl Display for LimiterSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Optional smoothing applied to the torque and power curves written to the AC car.
+/// Sparse or noisy exported curves can otherwise produce sawtooth shapes in power.lut
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PowerCurveSmoothing {
+    None,
+    /// Centred moving average over the given number of curve points
+    MovingAverage(usize),
+    /// Catmull-Rom spline through the curve points, resampled every given number of rpm
+    CatmullRom(i32)
+}
+
+impl PowerCurveSmoothing {
+    pub const ALL: &'static [PowerCurveSmoothing] = &[
+        PowerCurveSmoothing::None,
+        PowerCurveSmoothing::MovingAverage(3),
+        PowerCurveSmoothing::MovingAverage(5),
+        PowerCurveSmoothing::CatmullRom(250)
+    ];
+}
+
+impl Default for PowerCurveSmoothing {
+    fn default() -> Self {
+        PowerCurveSmoothing::None
+    }
+}
+
+impl Display for PowerCurveSmoothing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerCurveSmoothing::None => write!(f, "No curve smoothing"),
+            PowerCurveSmoothing::MovingAverage(window) => write!(f, "Moving average ({} points)", window),
+            PowerCurveSmoothing::CatmullRom(step) => write!(f, "Catmull-Rom spline ({}rpm)", step)
+        }
+    }
+}
+
+/// Where the torque curve written to power.lut and the car ui comes from
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub enum CurveSource {
+    /// Calculate the curve from the engine data
+    #[default]
+    Calculator,
+    /// Keep the donor car's power.lut and ui curves so that they can be written by hand. The rest
+    /// of the engine and drivetrain data is still swapped
+    KeepDonor,
+    /// Use the crank torque curve in a csv file of rpm,torque (Nm) pairs in place of the curve
+    /// of the engine data
+    FromCsv(PathBuf)
+}
+
+/// Parse a torque curve from csv data of `rpm,torque` pairs. Blank lines, lines starting with
+/// `#` and a leading header line are ignored. The rpm must increase on every line
+pub fn parse_torque_curve_csv(csv_data: &str) -> Result<Vec<(f64, f64)>, String> {
+    let mut curve: Vec<(f64, f64)> = Vec::new();
+    let mut header_allowed = true;
+    for (line_idx, line) in csv_data.lines().enumerate() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 2 {
+            return Err(format!("Line {}: expected 'rpm,torque' but found '{}'", line_idx + 1, line));
+        }
+        let rpm = match fields[0].parse::<f64>() {
+            Ok(rpm) if rpm.is_finite() && rpm >= 0.0 => rpm,
+            Err(_) if header_allowed => {
+                header_allowed = false;
+                continue;
+            }
+            _ => return Err(format!("Line {}: invalid rpm '{}'", line_idx + 1, fields[0]))
+        };
+        header_allowed = false;
+        let torque = match fields[1].parse::<f64>() {
+            Ok(torque) if torque.is_finite() && torque >= 0.0 => torque,
+            _ => return Err(format!("Line {}: invalid torque '{}'", line_idx + 1, fields[1]))
+        };
+        if let Some((previous_rpm, _)) = curve.last() {
+            if rpm <= *previous_rpm {
+                return Err(format!("Line {}: rpm {} doesn't increase from the previous rpm of {}",
+                                   line_idx + 1, rpm, previous_rpm));
+            }
+        }
+        curve.push((rpm, torque));
+    }
+    if curve.len() < 2 {
+        return Err("At least 2 rpm,torque pairs are needed".to_string());
+    }
+    Ok(curve)
+}
+
+/// Load a torque curve from the csv file at `path`. See [`parse_torque_curve_csv`]
+pub fn load_torque_curve_csv(path: &Path) -> Result<Vec<(f64, f64)>, FabricationError> {
+    let csv_data = fs::read_to_string(path).map_err(|e| {
+        FabricationError::FailedToLoad(path.display().to_string(), e.to_string())
+    })?;
+    parse_torque_curve_csv(&csv_data).map_err(|e| {
+        FabricationError::InvalidData(path.display().to_string(), e)
+    })
+}
+
+/// Shared flag used to request that an in-progress swap stops. The swap checks the flag before
+/// writing each set of car files and returns `FabricationError::Cancelled` once it has been set
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn check(&self) -> Result<(), FabricationError> {
+        match self.is_cancelled() {
+            true => {
+                info!("Swap cancelled");
+                Err(FabricationError::Cancelled)
+            }
+            false => Ok(())
+        }
+    }
+}
+
+/// The phases of an engine swap that are passed to a [`ProgressReporter`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SwapPhase {
+    CloningCar,
+    UnpackingData,
+    CalculatingEngineParameters,
+    WritingFiles
+}
+
+impl From<CloneStage> for SwapPhase {
+    fn from(stage: CloneStage) -> SwapPhase {
+        match stage {
+            CloneStage::CopyingFiles | CloneStage::PackingData => SwapPhase::CloningCar,
+            CloneStage::UnpackingData => SwapPhase::UnpackingData
+        }
+    }
+}
+
+impl Display for SwapPhase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwapPhase::CloningCar => write!(f, "Cloning car"),
+            SwapPhase::UnpackingData => write!(f, "Unpacking data.acd"),
+            SwapPhase::CalculatingEngineParameters => write!(f, "Calculating engine parameters"),
+            SwapPhase::WritingFiles => write!(f, "Writing files")
+        }
+    }
+}
+
+/// Callback that is told each time an in-progress swap moves into a new [`SwapPhase`].
+/// The default reporter discards the updates
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    callback: Option<Arc<dyn Fn(SwapPhase) + Send + Sync>>
+}
+
+impl ProgressReporter {
+    pub fn new(callback: impl Fn(SwapPhase) + Send + Sync + 'static) -> ProgressReporter {
+        ProgressReporter { callback: Some(Arc::new(callback)) }
+    }
+
+    pub fn report(&self, phase: SwapPhase) {
+        if let Some(callback) = &self.callback {
+            callback(phase);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AssettoCorsaCarSettings {
+    pub minimum_physics_level: AssettoCorsaPhysicsLevel,
+    /// Raise the clutch MAX_TORQUE in drivetrain.ini if it is below the peak torque of the new
+    /// engine. The clutch is the only drivetrain torque limit that is adjusted; the overrev and
+    /// gearbox inertia are controlled by `scale_downshift_overrev` and `adjust_gearbox_inertia`
+    pub auto_adjust_drivetrain: bool,
+    pub low_rpm_torque_ramp: bool,
+    pub limiter_source: LimiterSource,
+    pub power_curve_smoothing: PowerCurveSmoothing,
+    pub scale_downshift_overrev: bool,
+    pub adjust_gearbox_inertia: bool,
+    /// Swap with base game physics even if the car already uses CSP extended physics, removing
+    /// the CSP only data. Otherwise these cars are kept on CSP extended physics
+    pub force_base_game_downgrade: bool,
+    pub write_debug_values: bool,
+    /// Decimal places to write boost pressures with. Each value keeps its usual precision if unset
+    pub boost_precision: Option<usize>,
+    /// Decimal places to write engine and gearbox inertia with. Kept at 3 if unset
+    pub inertia_precision: Option<usize>,
+    /// Resample the boost controller LUTs every this many rpm from idle to the limiter.
+    /// The rpm points of the engine data are used as-is if unset
+    pub boost_lut_rpm_step: Option<i32>,
+    /// How far, as a fraction of the engine's peak torque, the peak of the jbeam torque table
+    /// can be from it before the Automation torque curve is preferred
+    pub torque_curve_tolerance: f64,
+    /// Limit the boost in 1st and 2nd gear to this fraction of the boost requested by the rpm
+    /// based controller, e.g. to reduce wheelspin. Ignored for naturally aspirated engines
+    pub low_gear_boost_fraction: Option<f64>,
+    pub curve_source: CurveSource,
+    pub cancellation_token: CancellationToken,
+    pub progress: ProgressReporter
+}
+
+impl AssettoCorsaCarSettings {
+    #[deprecated(note = "use auto_adjust_drivetrain")]
+    pub fn auto_adjust_clutch(&self) -> bool {
+        self.auto_adjust_drivetrain
+    }
+
+    #[deprecated(note = "use auto_adjust_drivetrain")]
+    pub fn set_auto_adjust_clutch(&mut self, enabled: bool) {
+        self.auto_adjust_drivetrain = enabled;
+    }
+
+    const BOOST_KEYS: [&'static str; 5] = ["MAX_BOOST", "WASTEGATE", "DISPLAY_MAX_BOOST", "TURBO_BOOST_THRESHOLD", "PRESSURE_THRESHOLD"];
+
+    /// The precision policy for float values written to the engine and drivetrain ini files
+    fn float_precision(&self) -> FloatPrecision {
+        let mut float_precision = FloatPrecision::default();
+        if let Some(precision) = self.boost_precision {
+            for key in Self::BOOST_KEYS {
+                float_precision = float_precision.with_override(key, precision);
+            }
+        }
+        if let Some(precision) = self.inertia_precision {
+            float_precision = float_precision.with_override("INERTIA", precision);
+        }
+        float_precision
+    }
+}
+
+impl Default for AssettoCorsaCarSettings {
+    fn default() -> AssettoCorsaCarSettings {
+        AssettoCorsaCarSettings {
+            minimum_physics_level: AssettoCorsaPhysicsLevel::default(),
+            auto_adjust_drivetrain: true,
+            low_rpm_torque_ramp: false,
+            limiter_source: LimiterSource::default(),
+            power_curve_smoothing: PowerCurveSmoothing::default(),
+            scale_downshift_overrev: false,
+            adjust_gearbox_inertia: false,
+            force_base_game_downgrade: false,
+            write_debug_values: false,
+            boost_precision: None,
+            inertia_precision: None,
+            boost_lut_rpm_step: Some(DEFAULT_BOOST_LUT_RPM_STEP),
+            torque_curve_tolerance: DEFAULT_TORQUE_CURVE_TOLERANCE,
+            low_gear_boost_fraction: None,
+            curve_source: CurveSource::default(),
+            cancellation_token: CancellationToken::new(),
+            progress: ProgressReporter::default()
+        }
+    }
+}
+
+const LIMITER_MISMATCH_THRESHOLD_RPM: f64 = 100.0;
+pub const DEFAULT_BOOST_LUT_RPM_STEP: i32 = 500;
+pub const DEFAULT_TORQUE_CURVE_TOLERANCE: f64 = 0.1;
+
+/// Choose the physics level to swap with for a car that `uses_extended_physics` or not.
+/// A car already on CSP extended physics is kept on it when base game physics is requested unless
+/// `force_downgrade` is set, as a partial downgrade leaves the car half working.
+/// Returns the physics level along with a warning if it differs from the requested level
+fn resolve_physics_level(requested: AssettoCorsaPhysicsLevel,
+                         uses_extended_physics: bool,
+                         force_downgrade: bool) -> (AssettoCorsaPhysicsLevel, Option<String>) {
+    match (requested, uses_extended_physics, force_downgrade) {
+        (AssettoCorsaPhysicsLevel::BaseGame, true, false) => {
+            (AssettoCorsaPhysicsLevel::CspExtendedPhysics,
+             Some("the car already uses CSP extended physics so it was kept rather than downgraded to base game physics".to_string()))
+        }
+        _ => (requested, None)
+    }
+}
+
+/// Cross-check the limiter in the Automation data against any defined in the jbeam data and
+/// return the value from the requested source
+fn choose_limiter(calculator: &assetto_corsa::EngineParameterCalculator, limiter_source: LimiterSource) -> f64 {
+    let automation_limiter = calculator.limiter();
+    let jbeam_limiter = match calculator.jbeam_limiter() {
+        None => {
+            info!("No jbeam limiter available. Using Automation limiter of {}", automation_limiter);
+            return automation_limiter;
+        }
+        Some(limiter) => limiter
+    };
+    if (automation_limiter - jbeam_limiter).abs() > LIMITER_MISMATCH_THRESHOLD_RPM {
+        warn!("Automation limiter ({}) and jbeam limiter ({}) differ by more than {}rpm. The mod may be out-of-date",
+              automation_limiter, jbeam_limiter, LIMITER_MISMATCH_THRESHOLD_RPM);
+    }
+    match limiter_source {
+        LimiterSource::Automation => automation_limiter,
+        LimiterSource::JBeam => {
+            info!("Using jbeam limiter of {}", jbeam_limiter);
+            jbeam_limiter
+        }
+    }
+}
+
+/// Pick the idle rpm to use for the new engine. The idle designed in Automation is preferred,
+/// followed by the jbeam idle and finally the existing value of the car. The new idle is never
+/// allowed below the lowest rpm of the torque curve as the engine has no data beneath it
+fn choose_idle_rpm(automation_idle: Option<f64>,
+                   jbeam_idle: Option<f64>,
+                   curve_min_rpm: Option<f64>,
+                   existing_minimum: i32) -> i32
+{
+    let at_least_curve_min = |idle: f64| {
+        match curve_min_rpm {
+            Some(min_rpm) if min_rpm > idle => {
+                info!("Raising idle rpm of {} to the torque curve minimum of {}", idle, min_rpm);
+                min_rpm.round() as i32
+            }
+            _ => idle.round() as i32
+        }
+    };
+    if let Some(idle) = automation_idle {
+        info!("Using Automation idle rpm of {}", idle);
+        return at_least_curve_min(idle);
+    }
+    if let Some(idle) = jbeam_idle {
+        info!("No Automation idle rpm available. Using jbeam idle rpm of {}", idle);
+        return at_least_curve_min(idle);
+    }
+    warn!("Failed to find idle rpm for the new engine. Keeping the existing value of {}", existing_minimum);
+    existing_minimum
+}
+
+const CLUTCH_TORQUE_HEADROOM: i32 = 30;
+const CLUTCH_TORQUE_MULTIPLE: i32 = 50;
+
+/// The clutch max torque recommended for an engine with the given peak torque. A small amount of
+/// headroom is added to the peak torque which is then rounded up to the next multiple of 50Nm
+pub fn recommended_clutch_torque(peak_torque: i32) -> i32 {
+    round_up_to_nearest_multiple(peak_torque + CLUTCH_TORQUE_HEADROOM, CLUTCH_TORQUE_MULTIPLE)
+}
+
+/// Reject user supplied limiter and idle rpm values that no engine could run at
+fn validate_rpm_overrides(limiter_override: Option<i32>, idle_override: Option<i32>) -> Result<(), FabricationError> {
+    for (name, rpm_override) in [("limiter override", limiter_override), ("idle override", idle_override)] {
+        if let Some(rpm) = rpm_override.filter(|rpm| *rpm <= 0) {
+            return Err(FabricationError::InvalidData(name.to_string(), format!("{}rpm must be above 0", rpm)));
+        }
+    }
+    Ok(())
+}
+
+/// Check any user supplied limiter and idle rpm against the new engine and return a warning
+/// for each value that will produce a car that doesn't make use of the engine's power band
+fn rpm_override_warnings(limiter_override: Option<i32>,
+                         idle_override: Option<i32>,
+                         limiter: i32,
+                         peak_power_rpm: i32) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(limiter_val) = limiter_override {
+        if limiter_val < peak_power_rpm {
+            warnings.push(format!("Limiter override of {}rpm is below the peak power rpm of {}rpm. The car will shift before reaching peak power",
+                                  limiter_val, peak_power_rpm));
+        }
+    }
+    if let Some(idle_val) = idle_override {
+        if idle_val >= limiter {
+            warnings.push(format!("Idle override of {}rpm isn't below the limiter of {}rpm", idle_val, limiter));
+        }
+    }
+    warnings
+}
+
+/// Scale the downshift protection overrev allowance in proportion to a change in limiter
+fn scale_downshift_overrev(overrev: i32, old_limiter: i32, new_limiter: i32) -> i32 {
+    if old_limiter <= 0 || new_limiter <= 0 {
+        return overrev;
+    }
+    ((overrev as f64 * new_limiter as f64) / old_limiter as f64).round().max(0.0) as i32
+}
+
+/// Scale the gearbox inertia in proportion to a change in engine inertia
+fn scale_gearbox_inertia(gearbox_inertia: f64, old_engine_inertia: f64, new_engine_inertia: f64) -> f64 {
+    if !(old_engine_inertia > 0.0 && new_engine_inertia > 0.0 && old_engine_inertia.is_finite() && new_engine_inertia.is_finite()) {
+        return gearbox_inertia;
+    }
+    round_float_to(gearbox_inertia * new_engine_inertia / old_engine_inertia, 3).max(0.001)
+}
+
+/// The fallback upshift and downshift rpm used when the gearbox can't be read
+fn limiter_shift_points(limiter: i32) -> (i32, i32) {
+    ((limiter / 100) * 97, (limiter / 100) * 70)
+}
+
+/// The power in `power_curve` at `rpm`, interpolated between the nearest points
+fn interpolate_power(power_curve: &[(i32, i32)], rpm: f64) -> f64 {
+    let (first_rpm, first_power) = power_curve[0];
+    if rpm <= first_rpm as f64 {
+        return first_power as f64;
+    }
+    for window in power_curve.windows(2) {
+        let ((rpm_a, power_a), (rpm_b, power_b)) = (window[0], window[1]);
+        if rpm <= rpm_b as f64 {
+            let t = (rpm - rpm_a as f64) / (rpm_b - rpm_a).max(1) as f64;
+            return power_a as f64 + (power_b - power_a) as f64 * t;
+        }
+    }
+    power_curve[power_curve.len() - 1].1 as f64
+}
+
+/// Upshift and downshift rpm that keep the engine making as much power as possible with the
+/// given gear ratios. For each gear the upshift is the rpm beyond which the engine would make
+/// more power in the next gear at the same road speed and the downshift is the rpm the engine
+/// drops to after that upshift. The points for each gear are averaged and clamped below the
+/// limiter. Returns None if there are fewer than two forward gears or no power curve
+fn power_curve_shift_points(power_curve: &[(i32, i32)], gear_ratios: &[f64], limiter: i32) -> Option<(i32, i32)> {
+    if power_curve.is_empty() || gear_ratios.len() < 2 || limiter <= 0 {
+        return None;
+    }
+    let peak_power_rpm = power_curve.iter().max_by_key(|(_, power)| *power).map(|(rpm, _)| *rpm)?;
+    let mut shifts = Vec::new();
+    for ratios in gear_ratios.windows(2) {
+        if ratios[0] <= 0.0 || ratios[1] <= 0.0 || ratios[1] >= ratios[0] {
+            continue;
+        }
+        let next_gear_rpm_multiple = ratios[1] / ratios[0];
+        let upshift = (peak_power_rpm.min(limiter)..=limiter).step_by(50).find(|rpm| {
+            let rpm = *rpm as f64;
+            interpolate_power(power_curve, rpm * next_gear_rpm_multiple) >= interpolate_power(power_curve, rpm)
+        }).unwrap_or(limiter);
+        shifts.push((upshift as f64, upshift as f64 * next_gear_rpm_multiple));
+    }
+    if shifts.is_empty() {
+        return None;
+    }
+    let count = shifts.len() as f64;
+    let (fallback_up, _) = limiter_shift_points(limiter);
+    let up = round_to_nearest_hundred(shifts.iter().map(|(up, _)| up).sum::<f64>() / count).min(fallback_up);
+    let down = round_to_nearest_hundred(shifts.iter().map(|(_, down)| down).sum::<f64>() / count).min(up - 500);
+    Some((up, down))
+}
+
+fn round_to_nearest_hundred(val: f64) -> i32 {
+    ((val / 100.0).round() as i32) * 100
+}
+
+fn update_gearbox_inertia(drivetrain: &mut Drivetrain,
+                          old_engine_inertia: f64,
+                          new_engine_inertia: f64) -> Result<(), FabricationError> {
+    let mut gearbox = extract_mandatory_section::<data::drivetrain::Gearbox>(drivetrain).map_err(|_|{
+        FabricationError::MissingDataSection("GEARBOX".to_string(), Drivetrain::INI_FILENAME.to_string())
+    })?;
+    let new_inertia = scale_gearbox_inertia(gearbox.inertia, old_engine_inertia, new_engine_inertia);
+    info!("Scaling gearbox inertia from {} to {}", gearbox.inertia, new_inertia);
+    gearbox.inertia = new_inertia;
+    update_car_data(drivetrain, &gearbox).map_err(|err|{
+        FabricationError::FailedToUpdate("GEARBOX".to_string(), Drivetrain::INI_FILENAME.to_string(), err.to_string())
+    })
+}
+
+const LOW_RPM_RAMP_START_FRACTION: f64 = 0.2;
+const LOW_RPM_RAMP_STEPS: i32 = 4;
+
+/// Taper the torque up from a fraction of the first data point at `minimum_rpm` to the first
+/// data point of the curve. This avoids an abrupt step in torque just above idle for engines
+/// whose exported curves start at a high value
+fn apply_low_rpm_torque_ramp(torque_curve: Vec<(i32, f64)>, minimum_rpm: i32) -> Vec<(i32, f64)> {
+    let (first_rpm, first_torque) = match torque_curve.first() {
+        None => return torque_curve,
+        Some(point) => *point
+    };
+    if minimum_rpm >= first_rpm {
+        info!("Curve starts at {}rpm which is below the minimum of {}rpm. Not applying torque ramp", first_rpm, minimum_rpm);
+        return torque_curve;
+    }
+
+    let start_torque = first_torque * LOW_RPM_RAMP_START_FRACTION;
+    let rpm_step = (first_rpm - minimum_rpm) as f64 / LOW_RPM_RAMP_STEPS as f64;
+    let mut out_vec: Vec<(i32, f64)> = Vec::new();
+    for step in 0..LOW_RPM_RAMP_STEPS {
+        let rpm = (minimum_rpm as f64 + (rpm_step * step as f64)).round() as i32;
+        if let Some((last_rpm, _)) = out_vec.last() {
+            if rpm <= *last_rpm {
+                continue;
+            }
+        }
+        // Ease into the first data point so that the gradient doesn't change abruptly
+        let t = step as f64 / LOW_RPM_RAMP_STEPS as f64;
+        let eased_t = t * t * (3.0 - (2.0 * t));
+        out_vec.push((rpm, (start_torque + ((first_torque - start_torque) * eased_t)).round()));
+    }
+    out_vec.extend(torque_curve);
+    out_vec
+}
+
+/// Resample a boost `lut` at `idle_rpm`, every multiple of `rpm_step` above it and at `limiter`.
+/// Values are interpolated linearly between the points of `lut` and held at its first and last
+/// values outside of them
+fn resample_boost_lut(lut: &[(f64, f64)], idle_rpm: i32, limiter: i32, rpm_step: i32) -> Vec<(f64, f64)> {
+    if lut.is_empty() || rpm_step <= 0 || limiter <= idle_rpm {
+        return lut.to_vec();
+    }
+    let boost_at = |rpm: f64| -> f64 {
+        let upper_idx = lut.partition_point(|(lut_rpm, _)| *lut_rpm < rpm);
+        if upper_idx == 0 {
+            return lut[0].1;
+        }
+        if upper_idx == lut.len() {
+            return lut[lut.len() - 1].1;
+        }
+        let (rpm_1, boost_1) = lut[upper_idx - 1];
+        let (rpm_2, boost_2) = lut[upper_idx];
+        boost_1 + (boost_2 - boost_1) * ((rpm - rpm_1) / (rpm_2 - rpm_1))
+    };
+    let mut rpm_points = vec![idle_rpm];
+    let mut rpm = (idle_rpm / rpm_step + 1) * rpm_step;
+    while rpm < limiter {
+        rpm_points.push(rpm);
+        rpm += rpm_step;
+    }
+    rpm_points.push(limiter);
+    rpm_points.into_iter().map(|rpm| {
+        (rpm as f64, round_float_to(boost_at(rpm as f64), 3))
+    }).collect()
+}
+
+/// Clamp any values in a boost `lut` above `boost_limit`. Returns the clamped LUT and the number
+/// of values that were changed
+fn clamp_boost_lut(lut: Vec<(f64, f64)>, boost_limit: f64) -> (Vec<(f64, f64)>, usize) {
+    let clamped_count = lut.iter().filter(|(_, boost)| *boost > boost_limit).count();
+    (lut.into_iter().map(|(rpm, boost)| (rpm, boost.min(boost_limit))).collect(), clamped_count)
+}
+
+/// Fit the LUT of the boost controller for turbo `turbo_idx` to the new engine. The LUT is
+/// resampled between `idle_rpm` and `limiter` if `rpm_step` is set and clamped to the boost
+/// limit of the turbo. Returns a warning if any values had to be clamped
+fn fit_boost_controller(turbo_ctrl: &mut engine::turbo_ctrl::TurboController,
+                        turbo_idx: usize,
+                        idle_rpm: i32,
+                        limiter: i32,
+                        rpm_step: Option<i32>,
+                        boost_limit: Option<f64>) -> Option<String> {
+    let mut lut = turbo_ctrl.get_lut().to_vec();
+    if let Some(step) = rpm_step {
+        info!("Resampling boost controller {} every {}rpm from {}rpm to {}rpm", turbo_idx, step, idle_rpm, limiter);
+        lut = resample_boost_lut(&lut, idle_rpm, limiter, step);
+    }
+    let mut warning = None;
+    if let Some(limit) = boost_limit {
+        let (clamped_lut, clamped_count) = clamp_boost_lut(lut, limit);
+        if clamped_count > 0 {
+            let msg = format!("{} values in boost controller {} were above the turbo boost limit of {} and have been clamped",
+                              clamped_count, turbo_idx, limit);
+            warn!("{}", msg);
+            warning = Some(msg);
+        }
+        lut = clamped_lut;
+    }
+    turbo_ctrl.update_lut(lut);
+    warning
+}
+
+/// The GEAR controller input counts reverse as 0 and neutral as 1
+const FIRST_GEAR_CONTROLLER_INPUT: f64 = 2.0;
+
+/// Create a controller that multiplies the boost requested by the rpm based `boost_ctrl` by
+/// `fraction` in 1st and 2nd gear. The controller uses the same limits as `boost_ctrl` so that
+/// the combined boost is never clipped by it
+fn create_low_gear_boost_controller(boost_ctrl: &engine::turbo_ctrl::TurboController,
+                                    fraction: f64) -> Result<engine::turbo_ctrl::TurboController, String> {
+    use engine::turbo_ctrl::{ControllerCombinator, ControllerInput, TurboController};
+
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        return Err(format!("the low gear boost limit must be above 0% and no more than 100%, got {}%",
+                           round_float_to(fraction * 100.0, 1)));
+    }
+    if boost_ctrl.input() != ControllerInput::Rpms || boost_ctrl.combinator() != ControllerCombinator::Add {
+        return Err(format!("the boost controller must add boost by rpm to be limited by gear, it uses {} with {}",
+                           boost_ctrl.input(), boost_ctrl.combinator()));
+    }
+    let max_boost = boost_ctrl.get_lut().to_vec().into_iter().map(|(_, boost)| boost).fold(0.0, f64::max);
+    if max_boost * fraction < boost_ctrl.down_limit() {
+        return Err(format!("limiting boost to {}% in low gears would fall below the boost controller lower limit of {}",
+                           round_float_to(fraction * 100.0, 1), boost_ctrl.down_limit()));
+    }
+    let first_gear = FIRST_GEAR_CONTROLLER_INPUT;
+    Ok(TurboController::new(
+        boost_ctrl.index() + 1,
+        ControllerInput::Gear,
+        ControllerCombinator::Mult,
+        vec![(0.0, 1.0), (1.0, 1.0), (first_gear, fraction), (first_gear + 1.0, fraction), (first_gear + 2.0, 1.0)],
+        boost_ctrl.filter(),
+        boost_ctrl.up_limit(),
+        boost_ctrl.down_limit()
+    ))
+}
+
+/// Smooth a curve of (rpm, value) points using the requested method. The result is scaled so
+/// that its maximum matches the maximum of the input curve; the smoothed curve therefore
+/// doesn't silently lose (or gain) peak power or torque. Repeated rpm points are dropped so that
+/// every segment of the curve has a non-zero width
+fn smooth_curve(mut curve: Vec<(i32, f64)>, smoothing: PowerCurveSmoothing) -> Vec<(i32, f64)> {
+    curve.dedup_by_key(|(rpm, _)| *rpm);
+    if curve.len() < 3 {
+        return curve;
+    }
+    let smoothed = match smoothing {
+        PowerCurveSmoothing::None => return curve,
+        PowerCurveSmoothing::MovingAverage(window) => moving_average(&curve, window),
+        PowerCurveSmoothing::CatmullRom(rpm_step) => catmull_rom_resample(&curve, rpm_step)
+    };
+    let original_peak = curve_peak(&curve);
+    let smoothed_peak = curve_peak(&smoothed);
+    if original_peak <= 0.0 || smoothed_peak <= 0.0 {
+        return smoothed;
+    }
+    let scale = original_peak / smoothed_peak;
+    smoothed.into_iter().map(|(rpm, val)| (rpm, val * scale)).collect()
+}
+
+/// Smooth an integer curve such as those written to the ui files
+fn smooth_int_curve(curve: Vec<(i32, i32)>, smoothing: PowerCurveSmoothing) -> Vec<(i32, i32)> {
+    if smoothing == PowerCurveSmoothing::None {
+        return curve;
+    }
+    let float_curve = curve.into_iter().map(|(rpm, val)| (rpm, val as f64)).collect();
+    smooth_curve(float_curve, smoothing).into_iter().map(|(rpm, val)| (rpm, val.round() as i32)).collect()
+}
+
+fn curve_peak(curve: &[(i32, f64)]) -> f64 {
+    curve.iter().map(|(_, val)| *val).fold(f64::MIN, f64::max)
+}
+
+fn int_curve_peak(curve: &[(i32, i32)]) -> i32 {
+    curve.iter().map(|(_, val)| *val).max().unwrap_or(0)
+}
+
+/// The crank power (bhp) and torque (Nm) curves of the new engine along with their peaks
+struct EngineCurves {
+    power: Vec<(i32, i32)>,
+    torque: Vec<(i32, i32)>,
+    peak_bhp: i32,
+    peak_torque: i32
+}
+
+impl EngineCurves {
+    fn from_calculator(calculator: &assetto_corsa::EngineParameterCalculator) -> EngineCurves {
+        EngineCurves {
+            power: calculator.engine_bhp_power_curve(),
+            torque: calculator.engine_torque_curve(),
+            peak_bhp: calculator.peak_bhp(),
+            peak_torque: calculator.peak_torque()
+        }
+    }
+
+    /// The curves for a `torque_curve` of rpm, torque (Nm) pairs
+    fn from_torque_curve(torque_curve: &[(f64, f64)]) -> EngineCurves {
+        let power: Vec<(i32, i32)> = torque_curve.iter().map(|(rpm, torque)| {
+            (rpm.round() as i32, kw_to_bhp(calculate_power_kw(*rpm as f32, *torque as f32) as f64).round() as i32)
+        }).collect();
+        let torque: Vec<(i32, i32)> = torque_curve.iter().map(|(rpm, torque)| {
+            (rpm.round() as i32, torque.round() as i32)
+        }).collect();
+        EngineCurves {
+            peak_bhp: int_curve_peak(&power),
+            peak_torque: int_curve_peak(&torque),
+            power,
+            torque
+        }
+    }
+}
+
+/// The power (bhp) and torque curves for the car ui along with the peak values to show in its
+/// specs. The peaks are taken from the smoothed curves so that the specs match the curves shown
+fn ui_curves(curves: &EngineCurves,
+             smoothing: PowerCurveSmoothing) -> (Vec<(i32, i32)>, Vec<(i32, i32)>, i32, i32) {
+    if smoothing == PowerCurveSmoothing::None {
+        return (curves.power.clone(),
+                curves.torque.clone(),
+                curves.peak_bhp,
+                curves.peak_torque);
+    }
+    let power_curve = smooth_int_curve(curves.power.clone(), smoothing);
+    let torque_curve = smooth_int_curve(curves.torque.clone(), smoothing);
+    let peak_bhp = int_curve_peak(&power_curve);
+    let peak_torque = int_curve_peak(&torque_curve);
+    (power_curve, torque_curve, peak_bhp, peak_torque)
+}
+
+/// Centred moving average. The window shrinks towards the ends of the curve so that the
+/// first and last points are kept at the same rpm
+fn moving_average(curve: &[(i32, f64)], window: usize) -> Vec<(i32, f64)> {
+    let half_window = window / 2;
+    (0..curve.len()).map(|idx| {
+        let reach = half_window.min(idx).min(curve.len() - 1 - idx);
+        let points = &curve[idx - reach..=idx + reach];
+        (curve[idx].0, points.iter().map(|(_, val)| *val).sum::<f64>() / points.len() as f64)
+    }).collect()
+}
+
+/// Resample the curve every `rpm_step` rpm along a Catmull-Rom spline that passes through
+/// each of the original points. The end points are duplicated to form the outer tangents
+fn catmull_rom_resample(curve: &[(i32, f64)], rpm_step: i32) -> Vec<(i32, f64)> {
+    let rpm_step = rpm_step.max(1);
+    let (first_rpm, _) = curve[0];
+    let (last_rpm, last_val) = curve[curve.len() - 1];
+    let mut out_vec = Vec::new();
+    let mut segment = 0;
+    let mut rpm = first_rpm;
+    while rpm < last_rpm {
+        while curve[segment + 1].0 <= rpm {
+            segment += 1;
+        }
+        let p0 = curve[segment.saturating_sub(1)].1;
+        let (x1, p1) = curve[segment];
+        let (x2, p2) = curve[segment + 1];
+        let p3 = curve[(segment + 2).min(curve.len() - 1)].1;
+        let t = match x2 - x1 {
+            0 => 0.0,
+            width => (rpm - x1) as f64 / width as f64
+        };
+        let t2 = t * t;
+        let t3 = t2 * t;
+        let val = 0.5 * ((2.0 * p1) +
+            (-p0 + p2) * t +
+            (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 +
+            (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t3);
+        out_vec.push((rpm, val));
+        rpm += rpm_step;
+    }
+    out_vec.push((last_rpm, last_val));
+    out_vec
+}
+
+#[derive(Clone)]
+pub struct AdditionalAcCarData {
+    engine_weight: Option<u32>,
+    engine_name: Option<String>,
+    engine_source_file: Option<PathBuf>,
+    limiter_override: Option<i32>,
+    idle_override: Option<i32>,
+    sound_install_path: Option<PathBuf>,
+    sound_donor_car: Option<PathBuf>,
+    update_weight_distribution: bool
+}
+
+impl AdditionalAcCarData {
+    pub fn new(engine_weight: Option<u32>) -> AdditionalAcCarData {
+        AdditionalAcCarData {
+            engine_weight,
+            engine_name: None,
+            engine_source_file: None,
+            limiter_override: None,
+            idle_override: None,
+            sound_install_path: None,
+            sound_donor_car: None,
+            update_weight_distribution: false
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn default() -> AdditionalAcCarData {
+        AdditionalAcCarData::new(None)
+    }
+
+    /// Record the engine under `name` in the swapped car rather than the name from the Automation data
+    pub fn with_engine_name(mut self, name: String) -> AdditionalAcCarData {
+        self.engine_name = Some(name);
+        self
+    }
+
+    /// The mod or crate engine file that the new engine was loaded from
+    pub fn with_engine_source_file(mut self, path: &Path) -> AdditionalAcCarData {
+        self.engine_source_file = Some(path.to_path_buf());
+        self
+    }
+
+    /// Use `limiter` rather than the limiter calculated from the engine data
+    pub fn with_limiter_override(mut self, limiter: Option<i32>) -> AdditionalAcCarData {
+        self.limiter_override = limiter;
+        self
+    }
+
+    /// Use `idle` rather than the idle rpm calculated from the engine data
+    pub fn with_idle_override(mut self, idle: Option<i32>) -> AdditionalAcCarData {
+        self.idle_override = idle;
+        self
+    }
+
+    pub fn engine_weight(&self) -> Option<u32> {
+        self.engine_weight
+    }
+
+    /// Move the car's front/rear weight distribution (CG_LOCATION in suspensions.ini) to account
+    /// for the new engine's mass differing from the existing engine weight
+    pub fn with_weight_distribution_update(mut self, enabled: bool) -> AdditionalAcCarData {
+        self.update_weight_distribution = enabled;
+        self
+    }
+
+    pub fn update_weight_distribution(&self) -> bool {
+        self.update_weight_distribution
+    }
+
+    /// Replace the car's sound with the sound of the car in the AC installation at
+    /// `ac_install_path` whose engine best matches the swapped in engine
+    pub fn with_engine_sound_matching(mut self, ac_install_path: Option<PathBuf>) -> AdditionalAcCarData {
+        self.sound_install_path = ac_install_path;
+        self.sound_donor_car = None;
+        self
+    }
+
+    /// Replace the car's sound with the sound of `donor_car`, a car folder in the AC installation
+    /// at `ac_install_path`, rather than matching a donor to the engine
+    pub fn with_engine_sound_donor(mut self, ac_install_path: PathBuf, donor_car: PathBuf) -> AdditionalAcCarData {
+        self.sound_install_path = Some(ac_install_path);
+        self.sound_donor_car = Some(donor_car);
+        self
+    }
+
+    pub fn limiter_override(&self) -> Option<i32> {
+        self.limiter_override
+    }
+
+    pub fn idle_override(&self) -> Option<i32> {
+        self.idle_override
+    }
+
+    pub fn engine_name(&self) -> Option<&str> {
+        self.engine_name.as_deref()
+    }
+
+    pub fn engine_source_file(&self) -> Option<&Path> {
+        self.engine_source_file.as_deref()
+    }
+
+    /// The AC installation to take the car's new sound from. None if the sound is left unchanged
+    pub fn sound_install_path(&self) -> Option<&Path> {
+        self.sound_install_path.as_deref()
+    }
+
+    pub fn sound_donor_car(&self) -> Option<&Path> {
+        self.sound_donor_car.as_deref()
+    }
+}
+
+/// The smallest change in front weight distribution, in percentage points, worth warning about
+const WEIGHT_DISTRIBUTION_WARNING_THRESHOLD: f64 = 0.5;
+
+/// Whether the engine is assumed to sit over the front axle of a car with a front weight fraction
+/// of `front_fraction`. The engine position isn't known so it's taken to be entirely over the axle
+/// that carries the most weight
+fn engine_assumed_at_front(front_fraction: f64) -> bool {
+    front_fraction >= 0.5
+}
+
+/// The front weight fraction of a car of `total_mass` kg with a front weight fraction of
+/// `front_fraction` once its engine mass changes by `engine_mass_delta` kg. See
+/// [`engine_assumed_at_front`] for where the engine is assumed to be
+fn front_weight_fraction_after(total_mass: u32, front_fraction: f64, engine_mass_delta: i32) -> f64 {
+    let engine_front_fraction = match engine_assumed_at_front(front_fraction) {
+        true => 1.0,
+        false => 0.0
+    };
+    let new_total = total_mass as f64 + engine_mass_delta as f64;
+    if new_total <= 0.0 {
+        return front_fraction;
+    }
+    (total_mass as f64 * front_fraction + engine_mass_delta as f64 * engine_front_fraction) / new_total
+}
+
+/// The new front weight fraction for a car of `total_mass` kg with a front weight fraction of
+/// `front_fraction` after its engine mass changes by `engine_mass_delta` kg. AC applies the mass at
+/// the CG_LOCATION so the distribution only changes if `update` is set. Nothing is returned unless
+/// the distribution moves by more than [`WEIGHT_DISTRIBUTION_WARNING_THRESHOLD`]. The warning
+/// states the assumed engine position, as the estimate is wrong for engines between the axles
+fn weight_distribution_update(total_mass: u32,
+                              front_fraction: f64,
+                              engine_mass_delta: i32,
+                              update: bool) -> (Option<f64>, Option<String>) {
+    let new_front_fraction = front_weight_fraction_after(total_mass, front_fraction, engine_mass_delta);
+    if ((new_front_fraction - front_fraction) * 100.0).abs() < WEIGHT_DISTRIBUTION_WARNING_THRESHOLD {
+        return (None, None);
+    }
+    let assumption = format!("assuming the engine sits entirely over the {} axle",
+                             if engine_assumed_at_front(front_fraction) { "front" } else { "rear" });
+    if update {
+        return (Some(new_front_fraction),
+                Some(format!("moved the weight distribution to {:.1}% front (from {:.1}%) {}. \
+                              Check CG_LOCATION if the engine sits between the axles",
+                             new_front_fraction * 100.0, front_fraction * 100.0, assumption)));
+    }
+    (None, Some(format!("the new engine's mass would move the weight distribution to {:.1}% front (from {:.1}%), {}, \
+                         but AC keeps the original distribution", new_front_fraction * 100.0, front_fraction * 100.0, assumption)))
+}
+
+/// The ui_car.json spec values to write for the swapped in engine. AC expects power in bhp and
+/// torque in Nm regardless of the units the user has chosen to display. The top speed and
+/// acceleration are left blank if they can't be estimated. Without a `peak_output` the power,
+/// torque and power to weight specs aren't returned so the existing values are kept
+fn engine_ui_specs(peak_output: Option<(Power, Torque)>,
+                   mass: Option<u32>,
+                   performance: Option<&PerformanceEstimator>,
+                   provenance: &EngineProvenance) -> Vec<(&'static str, String)> {
+    let blank = String::from("---");
+    let mut specs = Vec::new();
+    if let Some((peak_power, peak_torque)) = &peak_output {
+        specs.push(("bhp", peak_power.format_as(PowerUnit::Bhp, 0)));
+        specs.push(("torque", peak_torque.format_as(TorqueUnit::Nm, 0)));
+    }
+    specs.push(("weight", mass.map_or(blank.clone(), |mass_val| format!("{}kg", mass_val))));
+    if let Some((peak_power, _)) = &peak_output {
+        specs.push(("pwratio", mass.map_or(blank.clone(), |mass_val| {
+            format!("{}kg/hp", round_float_to(mass_val as f64 / peak_power.bhp().round(), 2))
+        })));
+    }
+    let acceleration = performance.and_then(|estimator| {
+        let time = estimator.time_to_speed(100.0);
+        if time.is_none() {
+            info!("Car can't reach 100km/h. Leaving acceleration spec blank");
+        }
+        time
+    });
+    specs.push(("acceleration", acceleration.map_or(blank.clone(), |time| format!("{:.1}s 0-100", time))));
+    specs.push(("range", blank.clone()));
+    specs.push(("topspeed", performance.map_or(blank, |estimator| {
+        format!("{}km/h", estimator.top_speed_kmh().round())
+    })));
+    if let Some(capacity) = provenance.capacity_cc {
+        specs.push(("displacement", format!("{}cc", capacity)));
+    }
+    if let Some(configuration) = &provenance.configuration {
+        specs.push(("engine", configuration.clone()));
+    }
+    specs
+}
+
+const SWAP_DESCRIPTION_PREFIX: &str = "Engine swap: ";
+const SWAP_DESCRIPTION_SEPARATOR: &str = "<br><br>";
+
+/// Append a paragraph describing the swapped in engine to a car's ui description. The paragraph
+/// from any previous swap is replaced so repeated swaps don't stack up
+fn swap_description(existing_description: Option<&str>,
+                    provenance: &EngineProvenance,
+                    swap_date: &str) -> String {
+    let existing = existing_description.unwrap_or_default();
+    let base = match existing.find(&format!("{}{}", SWAP_DESCRIPTION_SEPARATOR, SWAP_DESCRIPTION_PREFIX)) {
+        Some(idx) => &existing[..idx],
+        None if existing.starts_with(SWAP_DESCRIPTION_PREFIX) => "",
+        None => existing
+    };
+    let mut engine_details = provenance.name.clone();
+    let summary = provenance.summary();
+    if !summary.is_empty() {
+        engine_details = format!("{} ({})", engine_details, summary);
+    }
+    let version = match provenance.automation_version {
+        Some(version) => format!(" built in Automation version {}", version),
+        None => String::new()
+    };
+    let separator = match base.is_empty() {
+        true => "",
+        false => SWAP_DESCRIPTION_SEPARATOR
+    };
+    format!("{}{}{}{}{}. Swapped on {} with engine-crane",
+            base, separator, SWAP_DESCRIPTION_PREFIX, engine_details, version, swap_date)
+}
+
+/// Build the engine metadata stored alongside the car data. The source file hash lets the
+/// exact mod or crate engine used for the swap be identified later
+fn create_swap_metadata(calculator: &assetto_corsa::EngineParameterCalculator,
+                        engine_name: &str,
+                        automation_version: Option<u64>,
+                        source_file: Option<&Path>,
+                        source_hash: Option<&str>,
+                        swap_date: &str) -> engine::Metadata {
+    let mut metadata = calculator.create_metadata();
+    metadata.set_engine_name(engine_name.to_string());
+    if let Some(version) = automation_version {
+        metadata.set_int_value(String::from("automation_version"), version as i64);
+    }
+    metadata.set_string_value(String::from("swap_date"), swap_date.to_string());
+    if let Some(filename) = source_file.and_then(|path| path.file_name()) {
+        metadata.set_string_value(String::from("source_file"), filename.to_string_lossy().to_string());
+    }
+    if let Some(hash) = source_hash {
+        metadata.set_source_file_hash(hash.to_string());
+    }
+    metadata
+}
+
+fn hash_source_file(path: &Path) -> Option<String> {
+    match fs::read(path) {
+        Ok(data) => Some(sha256_hex(&data)),
+        Err(e) => {
+            warn!("Failed to hash engine source file {}. {}", path.display(), e.to_string());
+            None
+        }
+    }
+}
+
+fn turbo_section_names(turbo_count: usize) -> Vec<String> {
+    (0..turbo_count).map(engine::turbo::TurboSection::get_ini_section_name).collect()
+}
+pub fn check_automation_engine_compatibility(beam_ng_mod_path: &Path,
+                                             ac_car_path: &Path,
+                                             sandbox_finder: SandboxFinder) -> Result<(), FabricationError> {
+    let calculator = assetto_corsa::EngineParameterCalculator::from_beam_ng_mod(beam_ng_mod_path, sandbox_finder)?;
+    check_swap_compatibility(ac_car_path, calculator)
+}
+
+pub fn check_crate_engine_compatibility(crate_engine_path: &Path,
+                                        ac_car_path: &Path) -> Result<(), FabricationError> {
+    let calculator = assetto_corsa::EngineParameterCalculator::from_crate_engine(crate_engine_path)?;
+    check_swap_compatibility(ac_car_path, calculator)
+}
+
+pub fn check_sandbox_engine_compatibility(engine: EngineV1,
+                                          ac_car_path: &Path) -> Result<(), FabricationError> {
+    let calculator = assetto_corsa::EngineParameterCalculator::from_sandbox_engine(engine)?;
+    check_swap_compatibility(ac_car_path, calculator)
+}
+
+/// Check that the engine from `calculator` can be swapped into the AC car at `ac_car_path` without
+/// writing anything, so that predictable failures are found before the car is cloned.
+/// The car data must open, engine.ini, drivetrain.ini and car.ini must exist and parse, the power
+/// curve LUT must resolve and the engine must produce usable engine data
+fn check_swap_compatibility(ac_car_path: &Path,
+                            calculator: assetto_corsa::EngineParameterCalculator) -> Result<(), FabricationError> {
+    use FabricationError::*;
+
+    info!("Checking swap compatibility of {}", ac_car_path.display());
+    let mut car = Car::load_from_path(ac_car_path).map_err(|err|{
+        FailedToLoad(ac_car_path.display().to_string(), err.to_string())
+    })?;
+    for filename in [Engine::INI_FILENAME, Drivetrain::INI_FILENAME, CarIniData::FILENAME] {
+        if !car.data_interface().contains_file(filename) {
+            return Err(MissingDataSource(format!("{} in {}", filename, ac_car_path.display())));
+        }
+    }
+    {
+        let engine = Engine::from_car(&mut car).map_err(|e| {
+            FailedToLoad(Engine::INI_FILENAME.to_string(), e.to_string())
+        })?;
+        extract_mandatory_section::<PowerCurve>(&engine).map_err(|e| {
+            FailedToLoad(format!("power curve LUT from {}", Engine::INI_FILENAME), e.to_string())
+        })?;
+    }
+    {
+        let drivetrain = Drivetrain::from_car(&mut car).map_err(|e| {
+            FailedToLoad(Drivetrain::INI_FILENAME.to_string(), e.to_string())
+        })?;
+        extract_mandatory_section::<data::drivetrain::Traction>(&drivetrain).map_err(|_| {
+            MissingDataSection("Traction".to_string(), Drivetrain::INI_FILENAME.to_string())
+        })?;
+    }
+    CarIniData::from_car(&mut car).map_err(|e| {
+        FailedToLoad(CarIniData::FILENAME.to_string(), e.to_string())
+    })?;
+
+    if calculator.engine_torque_curve().is_empty() {
+        return Err(MissingDataSection("torque curve".to_string(), "engine data".to_string()));
+    }
+    calculator.inertia()?;
+    calculator.coast_data(calculator.limiter())?;
+    Ok(())
+}
+
+/// Package the AC car at `ac_car_path` into a zip or 7z archive at `out_file` for sharing.
+/// Swap backups are always left out and the swap report is only included if `include_swap_report`
+/// is set
+pub fn export_car(ac_car_path: &Path,
+                  out_file: &Path,
+                  repack_data: bool,
+                  include_swap_report: bool) -> Result<(), FabricationError> {
+    let mut opts = PackageOptions::new(repack_data);
+    for folder_name in backup::WORKING_FOLDER_NAMES {
+        opts = opts.exclude(folder_name);
+    }
+    if !include_swap_report {
+        opts = opts.exclude(report::SWAP_REPORT_FILENAME);
+    }
+    export_car_package(ac_car_path, out_file, &opts).map_err(|e| {
+        FabricationError::FailedToWrite(out_file.display().to_string(), e.to_string())
+    })
+}
+
+/// Swap the engine described by `calculator` into the AC car at `ac_car_path`.
+/// A report of the changes is written to the car folder; see [`swap_report_path`].
+/// A backup of the files the swap modifies is written before anything is changed so that the car
+/// can be restored with [`restore_backup`], even if the swap fails part way through.
+/// Returns any problems found while updating the car that didn't stop the swap
+pub fn update_ac_engine_parameters(ac_car_path: &Path,
+                                   calculator: assetto_corsa::EngineParameterCalculator,
+                                   settings: AssettoCorsaCarSettings,
+                                   additional_car_data: AdditionalAcCarData) -> Result<Vec<FabricationWarning>, FabricationError> {
+    settings.cancellation_token.check()?;
+    validate_rpm_overrides(additional_car_data.limiter_override(), additional_car_data.idle_override())?;
+    info!("Loading car {}", ac_car_path.display());
+    let car = Car::load_from_path(ac_car_path).map_err(|err|{
+        FabricationError::FailedToLoad(ac_car_path.display().to_string(), err.to_string())
+    })?;
+    let backup = CarBackup::snapshot(&car, additional_car_data.sound_install_path().is_some())?;
+    let mut warnings = Vec::new();
+    if let Err(e) = backup.write(ac_car_path) {
+        error!("Failed to store swap backup for {}. {}", ac_car_path.display(), e.to_string());
+        warnings.push(FabricationWarning::for_file(backup::BACKUP_FOLDER_NAME, format!("failed to store the backup, the car can't be restored. {}", e)));
+    }
+    let (swap_warnings, mut report, _) = apply_engine_parameters(car,
+                                                                 ac_car_path,
+                                                                 calculator,
+                                                                 settings,
+                                                                 additional_car_data,
+                                                                 false)?;
+    warnings.extend(swap_warnings);
+    report.add_warnings(&warnings);
+    if let Err(e) = report.write(ac_car_path) {
+        error!("Failed to write swap report for {}. {}", ac_car_path.display(), e.to_string());
+        warnings.push(FabricationWarning::for_file(report::SWAP_REPORT_FILENAME, format!("failed to write the swap report. {}", e)));
+    }
+    Ok(warnings)
+}
+
+/// Run the same update as [`update_ac_engine_parameters`] without writing anything to the car at
+/// `ac_car_path`. Changes to the car data are recorded in memory and returned in the preview
+pub fn preview_ac_engine_parameters(ac_car_path: &Path,
+                                    calculator: assetto_corsa::EngineParameterCalculator,
+                                    settings: AssettoCorsaCarSettings,
+                                    additional_car_data: AdditionalAcCarData) -> Result<SwapPreview, FabricationError> {
+    info!("Previewing swap into {}", ac_car_path.display());
+    let (car, change_record) = Car::load_recording_changes(ac_car_path).map_err(|err|{
+        FabricationError::FailedToLoad(ac_car_path.display().to_string(), err.to_string())
+    })?;
+    let (warnings, mut report, mut car_files) = apply_engine_parameters(car,
+                                                                        ac_car_path,
+                                                                        calculator,
+                                                                        settings,
+                                                                        additional_car_data,
+                                                                        true)?;
+    report.add_warnings(&warnings);
+    car_files.push(report::SWAP_REPORT_FILENAME.to_string());
+    car_files.push(backup::BACKUP_FOLDER_NAME.to_string());
+    Ok(SwapPreview::new(change_record.changes(), car_files, report))
+}
+
+/// Update `car` with the engine described by `calculator`. Returns any warnings about the swap,
+/// the report of what was changed and the files outside the car data that were written.
+/// When `dry_run` is set the files outside the car data are returned but not written
+fn apply_engine_parameters(mut car: Car,
+                           ac_car_path: &Path,
+                           calculator: assetto_corsa::EngineParameterCalculator,
+                           mut settings: AssettoCorsaCarSettings,
+                           additional_car_data: AdditionalAcCarData,
+                           dry_run: bool) -> Result<(Vec<FabricationWarning>, SwapReport, Vec<String>), FabricationError> {
+    use FabricationError::*;
+
+    let mut car_files = Vec::new();
+    let swap_date = current_utc_date_string();
+    let mut provenance = calculator.provenance();
+    if let Some(name) = additional_car_data.engine_name() {
+        provenance.name = name.to_string();
+    }
+    let source_hash = additional_car_data.engine_source_file().and_then(hash_source_file);
+    let mut report = SwapReport::new(&swap_date,
+                                     &provenance.name,
+                                     additional_car_data.engine_source_file(),
+                                     source_hash.clone());
+    let csv_torque_curve = match &settings.curve_source {
+        CurveSource::FromCsv(csv_path) => {
+            info!("Loading torque curve from {}", csv_path.display());
+            report.torque_curve_source_chosen(TorqueCurveSource::Csv { path: csv_path.display().to_string() });
+            Some(load_torque_curve_csv(csv_path)?)
+        }
+        CurveSource::KeepDonor => {
+            info!("Keeping the donor car's power curve");
+            report.torque_curve_source_chosen(TorqueCurveSource::DonorCurve);
+            None
+        }
+        CurveSource::Calculator => {
+            report.torque_curve_source_chosen(calculator.check_torque_curve(settings.torque_curve_tolerance));
+            None
+        }
+    };
+    let engine_curves = match &csv_torque_curve {
+        Some(torque_curve) => EngineCurves::from_torque_curve(torque_curve),
+        None => EngineCurves::from_calculator(&calculator)
+    };
+
+    let traction;
+    {
+        let drivetrain = Drivetrain::from_car(&mut car).map_err(|e|{
+            FailedToLoad(Drivetrain::INI_FILENAME.to_string(), e.to_string())
+        })?;
+        traction = extract_mandatory_section::<data::drivetrain::Traction>(&drivetrain).map_err(|_|{
+            MissingDataSection("Traction".to_string(), Drivetrain::INI_FILENAME.to_string())
+        })?
+    }
+    info!("Existing car is {} with assumed mechanical efficiency of {}", traction.drive_type, traction.mechanical_efficiency());
+
+    let mass;
+    let new_limiter = match additional_car_data.limiter_override() {
+        Some(limiter) => {
+            info!("Using limiter override of {}", limiter);
+            limiter
+        }
+        None => choose_limiter(&calculator, settings.limiter_source).round() as i32
+    };
+    let mut warnings: Vec<FabricationWarning> = rpm_override_warnings(additional_car_data.limiter_override(),
+                                                                      additional_car_data.idle_override(),
+                                                                      new_limiter,
+                                                                      calculator.peak_power_rpm().round() as i32)
+        .into_iter()
+        .map(|warning| FabricationWarning::for_file(Engine::INI_FILENAME, warning))
+        .collect();
+    let front_weight_fraction = data::suspensions::front_weight_fraction(&car).unwrap_or_else(|e| {
+        warn!("Couldn't read the weight distribution from {}. {}", Suspensions::INI_FILENAME, e.to_string());
+        warnings.push(FabricationWarning::for_file(Suspensions::INI_FILENAME,
+                                                   format!("couldn't read the weight distribution. {}", e)));
+        None
+    });
+    let uses_extended_physics;
+    let mut new_front_weight_fraction = None;
+    {
+        let mut ini_data = CarIniData::from_car(&mut car).map_err(|err|{
+            FailedToLoad(CarIniData::FILENAME.to_string(), err.to_string())
+        })?;
+        uses_extended_physics = matches!(ini_data.version(), Some(CarVersion::CspExtendedPhysics));
+        let (physics_level, physics_warning) = resolve_physics_level(settings.minimum_physics_level,
+                                                                     uses_extended_physics,
+                                                                     settings.force_base_game_downgrade);
+        if let Some(physics_warning) = physics_warning {
+            info!("Car uses CSP extended physics. Upgrading from {} to {}", settings.minimum_physics_level, physics_level);
+            warnings.push(FabricationWarning::for_file(CarIniData::FILENAME, physics_warning));
+        }
+        settings.minimum_physics_level = physics_level;
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
+        match settings.minimum_physics_level {
+            AssettoCorsaPhysicsLevel::BaseGame => {
+                info!("Using base game physics");
+                if uses_extended_physics {
+                    info!("Downgrading car from CSP extended physics");
+                    ini_data.set_version(CarVersion::Two);
+                }
+                ini_data.set_fuel_consumption(calculator.basic_fuel_consumption());
+            }
+            AssettoCorsaPhysicsLevel::CspExtendedPhysics => {
+                info!("Using CSP extended physics");
+                {
+                    ini_data.set_version(CarVersion::CspExtendedPhysics);
+                    ini_data.clear_fuel_consumption();
+                }
+            }
+        }
+
+        if let Some(current_engine_weight) = additional_car_data.engine_weight() {
+            if let Some(current_car_mass) = ini_data.total_mass() {
+                let new_engine_delta: i32 = calculator.engine_weight() as i32 - current_engine_weight as i32;
+                if new_engine_delta < 0 && new_engine_delta.abs() as u32 >= current_car_mass {
+                    error!("Invalid existing engine weight ({}). Would result in negative total mass", current_engine_weight);
+                    warnings.push(FabricationWarning::for_file(
+                        CarIniData::FILENAME,
+                        format!("total mass not updated as the existing engine weight of {}kg would make it negative", current_engine_weight)
+                    ));
+                } else {
+                    let new_mass = (current_car_mass as i32 + new_engine_delta) as u32;
+                    info!("Updating total mass to {} based off a provided existing engine weight of {}", new_mass, current_engine_weight);
+                    if let Some(front_fraction) = front_weight_fraction {
+                        let (new_fraction, distribution_warning) = weight_distribution_update(
+                            current_car_mass,
+                            front_fraction,
+                            new_engine_delta,
+                            additional_car_data.update_weight_distribution()
+                        );
+                        if let Some(new_fraction) = new_fraction {
+                            report.weight_distribution_changed(front_fraction, new_fraction);
+                            new_front_weight_fraction = Some(new_fraction);
+                        }
+                        if let Some(distribution_warning) = distribution_warning {
+                            warn!("{}", distribution_warning);
+                            warnings.push(FabricationWarning::for_file(Suspensions::INI_FILENAME, distribution_warning));
+                        }
+                    }
+                    ini_data.set_total_mass(new_mass);
+                    report.total_mass_changed(current_car_mass, new_mass);
+                }
+            } else {
+                error!("Existing car doesn't have a total mass property");
+                warnings.push(FabricationWarning::for_file(CarIniData::FILENAME,
+                                                           "total mass not updated as the car doesn't have one".to_string()));
+            }
+        }
+        settings.cancellation_token.check()?;
+        settings.progress.report(SwapPhase::WritingFiles);
+        info!("Writing car ini files");
+        mass = ini_data.total_mass();
+        ini_data.write().map_err(|e| {
+            FailedToWrite(CarIniData::FILENAME.to_string(), e.to_string())
+        })?;
+    }
+    if let Some(new_front_fraction) = new_front_weight_fraction {
+        info!("Moving the front weight distribution to {:.1}%", new_front_fraction * 100.0);
+        match Suspensions::from_car(&mut car) {
+            Ok(Some(mut suspensions)) => {
+                suspensions.set_front_weight_fraction(new_front_fraction);
+                suspensions.write().map_err(|e| {
+                    FailedToWrite(Suspensions::INI_FILENAME.to_string(), e.to_string())
+                })?;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                return Err(FailedToLoad(Suspensions::INI_FILENAME.to_string(), e.to_string()));
+            }
+        }
+    }
+
+    info!("Clearing existing turbo controllers");
+    let res = delete_all_turbo_controllers_from_car(&mut car);
+    if let Some(err) = res.err() {
+        warn!("Failed to clear turbo controllers. {}", err.to_string());
+        warnings.push(FabricationWarning::new(format!("couldn't clear the existing turbo controllers. {}", err)));
+    }
+
+    let old_limiter;
+    let old_engine_inertia;
+    let new_engine_inertia;
+    let new_idle;
+    let mut boost_limits = Vec::new();
+    {
+        let mut engine = Engine::from_car(&mut car).map_err(|err| {
+            FailedToLoad(Engine::INI_FILENAME.to_string(), err.to_string())
+        })?;
+        engine.mut_ini_data().set_float_precision(settings.float_precision());
+        let old_turbo_sections = turbo_section_names(engine.turbo_count());
+        match settings.minimum_physics_level {
+            AssettoCorsaPhysicsLevel::CspExtendedPhysics => {
+                let mut fuel_flow = calculator.fuel_flow_consumption(traction.mechanical_efficiency());
+                fuel_flow.set_log_fuel_flow(settings.write_debug_values);
+                update_car_data(&mut engine, &fuel_flow)
+                    .map_err(|err| {
+                        FailedToUpdate(engine::FuelConsumptionFlowRate::SECTION_NAME.to_string(),
+                                       Engine::INI_FILENAME.to_string(),
+                                       err.to_string())
+                    })?;
+                report.engine_ini_section_updated(engine::FuelConsumptionFlowRate::SECTION_NAME);
+            }
+            AssettoCorsaPhysicsLevel::BaseGame => {
+                if uses_extended_physics {
+                    engine::FuelConsumptionFlowRate::remove_from(&mut engine);
+                    report.engine_ini_section_updated(engine::FuelConsumptionFlowRate::SECTION_NAME);
+                }
+            }
+        }
+
+        let mut engine_data = extract_mandatory_section::<data::engine::EngineData>(&engine).map_err(|err|{
+            FailedToLoad(EngineData::SECTION_NAME.to_string(), err.to_string())
+        })?;
+
+        old_engine_inertia = engine_data.inertia;
+        match calculator.inertia() {
+            Ok(inertia) => engine_data.inertia = inertia,
+            Err(e) => {
+                warn!("Failed to calculate new inertia value. {}. existing value will be used", e.to_string());
+                warnings.push(FabricationWarning::for_file(Engine::INI_FILENAME,
+                                                           format!("kept the existing inertia as the new value couldn't be calculated. {}", e)));
+            }
+        };
+        new_engine_inertia = engine_data.inertia;
+
+        old_limiter = engine_data.limiter;
+        engine_data.limiter = new_limiter;
+        let old_idle = engine_data.minimum;
+        engine_data.minimum = match additional_car_data.idle_override() {
+            Some(idle) => {
+                info!("Using idle rpm override of {}", idle);
+                idle
+            }
+            None => choose_idle_rpm(calculator.automation_idle_rpm(),
+                                    calculator.jbeam_idle_rpm(),
+                                    calculator.curve_min_rpm(),
+                                    engine_data.minimum)
+        };
+        new_idle = engine_data.minimum;
+        update_car_data(&mut engine, &engine_data).map_err(|err|{
+            FailedToUpdate(EngineData::SECTION_NAME.to_string(),
+                           Engine::INI_FILENAME.to_string(),
+                           err.to_string())
+        })?;
+        report.engine_ini_section_updated(EngineData::SECTION_NAME);
+        report.inertia_changed(old_engine_inertia, new_engine_inertia);
+        report.limiter_changed(old_limiter, new_limiter);
+        report.idle_rpm_changed(old_idle, engine_data.minimum);
+        update_car_data(&mut engine, &calculator.damage(new_limiter as f64)).map_err(|err|{
+            FailedToUpdate(Damage::SECTION_NAME.to_string(),
+                           Engine::INI_FILENAME.to_string(),
+                           err.to_string())
+        })?;
+        report.engine_ini_section_updated(Damage::SECTION_NAME);
+
+        let coast_data = calculator.coast_data(new_limiter as f64)?;
+        update_car_data(&mut engine, &coast_data).map_err(|err|{
+            FailedToUpdate(CoastCurve::COAST_REF_SECTION_NAME.to_string(),
+                           Engine::INI_FILENAME.to_string(),
+                           err.to_string())
+        })?;
+        report.engine_ini_section_updated(CoastCurve::COAST_REF_SECTION_NAME);
+
+        if settings.curve_source != CurveSource::KeepDonor {
+            let mut power_curve = extract_mandatory_section::<engine::PowerCurve>(&engine).map_err(|_|{
+                MissingDataSection(PowerCurve::SECTION_NAME.to_string(),
+                                   Engine::INI_FILENAME.to_string())
+            })?;
+            let mut torque_curve = match &csv_torque_curve {
+                Some(csv_curve) => calculator.wheel_torque_curve_from(csv_curve, traction.mechanical_efficiency()),
+                None => calculator.naturally_aspirated_wheel_torque_curve(traction.mechanical_efficiency())
+            };
+            if settings.power_curve_smoothing != PowerCurveSmoothing::None {
+                info!("Applying {} to power curve", settings.power_curve_smoothing);
+                torque_curve = smooth_curve(torque_curve, settings.power_curve_smoothing);
+            }
+            if settings.low_rpm_torque_ramp {
+                info!("Applying low rpm torque ramp from {}rpm", engine_data.minimum);
+                torque_curve = apply_low_rpm_torque_ramp(torque_curve, engine_data.minimum);
+            }
+            power_curve.update(torque_curve);
+            update_car_data(&mut engine, &power_curve).map_err(|err|{
+                FailedToUpdate(PowerCurve::SECTION_NAME.to_string(),
+                               Engine::INI_FILENAME.to_string(),
+                               err.to_string())
+            })?;
+            report.engine_ini_section_updated(PowerCurve::SECTION_NAME);
+        }
+
+        match calculator.create_turbo() {
+            None => {
+                info!("The new engine doesn't have a turbo");
+                let old_turbo = extract_optional_section::<engine::Turbo>(&engine).map_err(|e|
+                    FailedToLoad(format!("Turbo from {}", Engine::INI_FILENAME), e.to_string())
+                )?;
+                if let Some(mut turbo) = old_turbo {
+                    info!("Removing old engine turbo parameters");
+                    turbo.clear_sections();
+                    turbo.clear_bov_threshold();
+                    update_car_data(&mut engine, &turbo).map_err(|err|{
+                        FailedToUpdate("TURBO".to_string(),
+                                       Engine::INI_FILENAME.to_string(),
+                                       err.to_string())
+                    })?;
+                }
+            }
+            Some(new_turbo) => {
+                info!("The new engine has a turbo");
+                boost_limits = new_turbo.sections().iter().map(|section| section.boost_limit()).collect();
+                update_car_data(&mut engine, &new_turbo).map_err(|err|{
+                    FailedToUpdate("TURBO".to_string(),
+                                   Engine::INI_FILENAME.to_string(),
+                                   err.to_string())
+                })?;
+            }
+        }
+        report.turbo_sections_changed(&old_turbo_sections, &turbo_section_names(engine.turbo_count()));
+
+        match calculator.throttle_map(new_limiter) {
+            None => info!("No throttle response data for the new engine. Keeping the existing throttle map"),
+            Some(throttle_map) => {
+                info!("Replacing throttle map");
+                match extract_optional_section::<ThrottleMap>(&engine) {
+                    Ok(Some(old_throttle_map)) => old_throttle_map.delete_from_car_data(&mut engine),
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!("Couldn't load existing throttle map. {}", e.to_string());
+                        warnings.push(FabricationWarning::for_file(Engine::INI_FILENAME,
+                                                                   format!("couldn't load the existing throttle map. {}", e)));
+                    }
+                }
+                update_car_data(&mut engine, &throttle_map).map_err(|err|{
+                    FailedToUpdate(ThrottleMap::SECTION_NAME.to_string(),
+                                   Engine::INI_FILENAME.to_string(),
+                                   err.to_string())
+                })?;
+                report.engine_ini_section_updated(ThrottleMap::SECTION_NAME);
+            }
+        }
+
+        settings.cancellation_token.check()?;
+        info!("Writing engine ini files");
+        engine.write().map_err(|err| {
+            FailedToWrite(Engine::INI_FILENAME.to_string(), err.to_string())
+        })?;
+    }
+
+    {
+        info!("Writing engine metadata");
+        let metadata = create_swap_metadata(&calculator,
+                                            &provenance.name,
+                                            provenance.automation_version,
+                                            additional_car_data.engine_source_file(),
+                                            source_hash.as_deref(),
+                                            &swap_date);
+        if let Err(e) = metadata.write_to_car(&mut car) {
+            warn!("Failed to write {}. {}", engine::Metadata::FILENAME, e.to_string());
+            warnings.push(FabricationWarning::for_file(engine::Metadata::FILENAME, format!("failed to write. {}", e)));
+        }
+    }
+
+    let turbo_controllers = calculator.create_turbo_controllers();
+    if settings.low_gear_boost_fraction.is_some() && turbo_controllers.is_empty() {
+        info!("Ignoring the low gear boost limit as the engine is naturally aspirated");
+    }
+    for (turbo_idx, mut turbo_ctrl) in turbo_controllers.into_iter().enumerate() {
+        settings.cancellation_token.check()?;
+        if let Some(warning) = fit_boost_controller(&mut turbo_ctrl,
+                                                    turbo_idx,
+                                                    new_idle,
+                                                    new_limiter,
+                                                    settings.boost_lut_rpm_step,
+                                                    boost_limits.get(turbo_idx).copied()) {
+            warnings.push(FabricationWarning::for_file(&engine::TurboControllerFile::get_controller_ini_filename(turbo_idx), warning));
+        }
+        info!("Writing turbo controller with index {}", turbo_idx);
+        let mut controller_file = engine::TurboControllerFile::new(&mut car, turbo_idx);
+        update_car_data(&mut controller_file, &turbo_ctrl).map_err(|err|{
+            FailedToUpdate("boost curve".to_string(),
+                           controller_file.filename(),
+                           err.to_string())
+        })?;
+        if let Some(fraction) = settings.low_gear_boost_fraction {
+            match create_low_gear_boost_controller(&turbo_ctrl, fraction) {
+                Ok(gear_ctrl) => {
+                    let idx = controller_file.add_controller(&gear_ctrl).map_err(|err| {
+                        FailedToUpdate("low gear boost limit".to_string(),
+                                       controller_file.filename(),
+                                       err.to_string())
+                    })?;
+                    info!("Added low gear boost controller {} to turbo {}", idx, turbo_idx);
+                }
+                Err(e) => {
+                    let msg = format!("Didn't limit boost in low gears for turbo {}. {}", turbo_idx, e);
+                    warn!("{}", msg);
+                    warnings.push(FabricationWarning::for_file(&controller_file.filename(), msg));
+                }
+            }
+        }
+        controller_file.write().map_err(|err| {
+            FailedToWrite(controller_file.filename(), err.to_string())
+        })?;
+        report.turbo_controller_written();
+    }
+
+    let mut shift_points = limiter_shift_points(new_limiter);
+    {
+        info!("Updating drivetrain ini files");
+        match Drivetrain::from_car(&mut car) {
+            Ok(mut drivetrain) => {
+                drivetrain.mut_ini_data().set_float_precision(settings.float_precision());
+                match extract_mandatory_section::<data::drivetrain::Gearbox>(&drivetrain) {
+                    Ok(gearbox) => {
+                        shift_points = power_curve_shift_points(&engine_curves.power, gearbox.gear_ratios(), new_limiter)
+                            .unwrap_or(shift_points);
+                        info!("Using upshift rpm {} and downshift rpm {}", shift_points.0, shift_points.1);
+                    }
+                    Err(err) => {
+                        warn!("Couldn't read gearbox, basing shift points on the limiter. {}", err.to_string());
+                        warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, format!("shift points based on the limiter as the gearbox couldn't be read. {}", err)));
+                    }
+                }
+                match extract_mandatory_section::<data::drivetrain::AutoShifter>(&drivetrain) {
+                    Ok(mut autoshifter) => {
+                        (autoshifter.up, autoshifter.down) = shift_points;
+                        if update_car_data(&mut drivetrain, &autoshifter).is_err() {
+                            error!("Failed to update drivetrain autoshifer");
+                            warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, "failed to update the autoshifter".to_string()));
+                        }
+                    }
+                    Err(err) => {
+                        error!("Failed to update drivetrain autoshifer. {}", err.to_string());
+                        warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, format!("failed to update the autoshifter. {}", err)));
+                    }
+                }
+
+                if settings.auto_adjust_drivetrain {
+                    match extract_mandatory_section::<data::drivetrain::Clutch>(&drivetrain) {
+                        Ok(mut clutch) => {
+                            let peak_torque = engine_curves.peak_torque;
+                            if peak_torque > clutch.max_torque {
+                                let new_max_torque = recommended_clutch_torque(peak_torque);
+                                info!("Raising clutch max torque from {}Nm to {}Nm", clutch.max_torque, new_max_torque);
+                                report.clutch_max_torque_changed(clutch.max_torque, new_max_torque);
+                                clutch.max_torque = new_max_torque
+                            }
+                            if update_car_data(&mut drivetrain, &clutch).is_err() {
+                                error!("Failed to update drivetrain with clutch data");
+                                warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, "failed to update the clutch".to_string()));
+                            }
+                        }
+                        Err(err) => {
+                            error!("Failed to update clutch MAX_TORQUE. {}", err.to_string());
+                            warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, format!("failed to update the clutch max torque. {}", err)));
+                        }
+                    }
+                }
+
+                if settings.scale_downshift_overrev || settings.write_debug_values {
+                    match extract_mandatory_section::<data::drivetrain::DownshiftProtection>(&drivetrain) {
+                        Ok(mut downshift_protection) => {
+                            if settings.scale_downshift_overrev {
+                                let new_overrev = scale_downshift_overrev(downshift_protection.overrev, old_limiter, new_limiter);
+                                info!("Scaling downshift protection overrev from {} to {}", downshift_protection.overrev, new_overrev);
+                                downshift_protection.overrev = new_overrev;
+                            }
+                            if settings.write_debug_values {
+                                info!("Enabling downshift protection debug output");
+                                downshift_protection.debug = 1;
+                            }
+                            if update_car_data(&mut drivetrain, &downshift_protection).is_err() {
+                                error!("Failed to update drivetrain with downshift protection data");
+                                warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, "failed to update the downshift protection".to_string()));
+                            }
+                        }
+                        Err(err) => {
+                            error!("Failed to update downshift protection. {}", err.to_string());
+                            warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, format!("failed to update the downshift protection. {}", err)));
+                        }
+                    }
+                }
+
+                if settings.adjust_gearbox_inertia {
+                    if let Err(err) = update_gearbox_inertia(&mut drivetrain, old_engine_inertia, new_engine_inertia) {
+                        error!("Failed to update gearbox inertia. {}", err.to_string());
+                        warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, format!("failed to update the gearbox inertia. {}", err)));
+                    }
+                }
+
+                settings.cancellation_token.check()?;
+                info!("Writing drivetrain ini files");
+                match drivetrain.write() {
+                    Ok(_) => {}
+                    Err(err) => {
+                        error!("Failed to write drivetrain.ini. {}", err.to_string());
+                        warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, format!("failed to write. {}", err)));
+                    }
+                }
+            }
+            Err(err) => {
+                error!("Failed to load drivetrain. {}", err.to_string());
+                warnings.push(FabricationWarning::for_file(Drivetrain::INI_FILENAME, format!("failed to load, the drivetrain wasn't updated. {}", err)));
+            }
+        };
+    };
+
+    settings.cancellation_token.check()?;
+    {
+        info!("Updating ai ini files");
+        match Ai::from_car(&mut car) {
+            Ok(ai_option) => {
+                if let Some(mut ai) = ai_option {
+                    match extract_mandatory_section::<data::ai::Gears>(&ai) {
+                        Ok(mut gears) => {
+                            (gears.up, gears.down) = shift_points;
+                            if update_car_data(&mut ai, &gears).is_err() {
+                                error!("Failed to update ai shift points");
+                                warnings.push(FabricationWarning::for_file(data::ai::INI_FILENAME, "failed to update the ai shift points".to_string()));
+                            }
+                            match ai.write() {
+                                Err(err) => {
+                                    error!("Failed to write {}. {}", data::ai::INI_FILENAME, err.to_string());
+                                    warnings.push(FabricationWarning::for_file(data::ai::INI_FILENAME, format!("failed to write. {}", err)));
+                                }
+                                _ => {}
+                            }
+                        }
+                        Err(_) => {}
+                    }
+                } else {
+                    error!("Failed to load ai data");
+                    warnings.push(FabricationWarning::for_file(data::ai::INI_FILENAME, "missing, the ai shift points weren't updated".to_string()));
+                }
+            }
+            Err(err) => {
+                error!("Failed to load ai data. {}", err.to_string());
+                warnings.push(FabricationWarning::for_file(data::ai::INI_FILENAME, format!("failed to load, the ai shift points weren't updated. {}", err)));
+            }
+        }
+    }
+
+    match DigitalInstruments::from_car(&mut car) {
+        Ok(opt) => {
+            if let Some(mut digital_instruments) = opt {
+                info!("Updating digital instruments files");
+                match ShiftLights::load_from_parent(&digital_instruments) {
+                    Ok(opt) => {
+                        if let Some(mut shift_lights) = opt {
+                            shift_lights.update_limiter(old_limiter as u32, new_limiter as u32);
+                            match update_car_data(&mut digital_instruments, &shift_lights) {
+                                Err(err) => {
+                                    warn!("Failed to shift lights in {}. {}",
+                                          DigitalInstruments::INI_FILENAME,
+                                          err.to_string());
+                                    warnings.push(FabricationWarning::for_file(DigitalInstruments::INI_FILENAME, format!("failed to update the shift lights. {}", err)));
+                                }
+                                _ => {}
+                            }
+                            match digital_instruments.write() {
+                                Err(err) => {
+                                    warn!("Failed to write digital_instruments.ini. {}", err.to_string());
+                                    warnings.push(FabricationWarning::for_file(DigitalInstruments::INI_FILENAME, format!("failed to write. {}", err)));
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        warn!("Failed to shift lights in {}. {}", DigitalInstruments::INI_FILENAME, err.to_string());
+                        warnings.push(FabricationWarning::for_file(DigitalInstruments::INI_FILENAME, format!("failed to load the shift lights. {}", err)));
+                    }
+                }
+            }
+        }
+        Err(err) => {
+            warn!("Failed to update {}. {}", DigitalInstruments::INI_FILENAME, err.to_string());
+            warnings.push(FabricationWarning::for_file(DigitalInstruments::INI_FILENAME, format!("failed to load. {}", err)));
+        }
+    }
+
+    {
+        info!("Updating ui components");
+        let performance = match PerformanceEstimator::from_car(&mut car) {
+            Ok(estimator) => {
+                info!("Estimated top speed in each gear: {:?}", estimator.gear_top_speeds_kmh().iter().map(|speed| speed.round()).collect::<Vec<_>>());
+                Some(estimator)
+            }
+            Err(e) => {
+                warn!("Can't estimate top speed and acceleration. {}", e.to_string());
+                None
+            }
+        };
+        match CarUiData::from_car(&mut car) {
+            Ok(mut ui_data) => {
+                let (power_curve, torque_curve, peak_bhp, peak_torque) = ui_curves(&engine_curves, settings.power_curve_smoothing);
+                // The donor's power.lut is kept so its ui curves and output specs still describe it
+                let peak_output = match settings.curve_source {
+                    CurveSource::KeepDonor => None,
+                    _ => {
+                        let _ = ui_data.ui_info.update_power_curve(power_curve);
+                        let _ = ui_data.ui_info.update_torque_curve(torque_curve);
+                        Some((Power::from_bhp(peak_bhp as f64), Torque::from_nm(peak_torque as f64)))
+                    }
+                };
+                let specs = engine_ui_specs(peak_output,
+                                            mass,
+                                            performance.as_ref(),
+                                            &provenance);
+                for (spec, value) in specs {
+                    if ui_data.ui_info.update_spec(spec, value.clone()).is_ok() {
+                        report.ui_spec_updated(spec, &value);
+                    }
+                }
+                let description = swap_description(ui_data.ui_info.description(), &provenance, &swap_date);
+                ui_data.ui_info.set_description(description);
+
+                match dry_run {
+                    true => car_files.push(backup::UI_CAR_FILE.to_string()),
+                    false => {
+                        info!("Writing car ui files");
+                        ui_data.ui_info.write().unwrap_or_else(|e|{
+                            error!("Failed to write ui files. {}", e.to_string());
+                            warnings.push(FabricationWarning::for_file(backup::UI_CAR_FILE, format!("failed to write. {}", e)));
+                        });
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to load ui files. {}", e.to_string());
+                warnings.push(FabricationWarning::for_file(backup::UI_CAR_FILE, format!("failed to load, the ui data wasn't updated. {}", e)));
+            }
+        }
+    }
+
+    if dry_run {
+        car_files.push(backup::UPGRADE_ICON_FILE.to_string());
+    } else {
+        info!("Updating upgrade.png");
+        let mut icon = CarUpgradeIcon::from_car(&car);
+        match icon.update(UPGRADE_ICON_BYTES) {
+            Err(e) => {
+                warn!("Failed to update upgrade icon. {}", e.to_string());
+                warnings.push(FabricationWarning::for_file(backup::UPGRADE_ICON_FILE, format!("failed to update. {}", e)));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(install_path) = additional_car_data.sound_install_path() {
+        let ac_install = crate::assetto_corsa::Installation::from_path(install_path.to_path_buf());
+        let donor_path = match additional_car_data.sound_donor_car() {
+            Some(donor_path) => Some(donor_path.to_path_buf()),
+            None => sound::suggest_sound_donor(&ac_install, &provenance)
+        };
+        let sound_result = match (donor_path, dry_run) {
+            (None, _) => Ok(None),
+            (Some(donor_path), true) => {
+                car_files.push("sfx".to_string());
+                Ok(Some(donor_path.file_name().unwrap_or_default().to_string_lossy().into_owned()))
+            }
+            (Some(donor_path), false) => sound::copy_engine_sound(&ac_install, ac_car_path, &donor_path).map(Some)
+        };
+        match sound_result {
+            Ok(Some(donor_name)) => report.engine_sound_copied_from(&donor_name),
+            Ok(None) => {
+                warn!("No installed car has an engine sound matching {}", provenance.summary());
+                warnings.push(FabricationWarning::new("no installed car with a matching engine sound was found".to_string()));
+            }
+            Err(e) => {
+                warn!("{}", e.to_string());
+                warnings.push(FabricationWarning::new(format!("couldn't match the engine sound. {}", e)));
+            }
+        }
+    }
+
+    Ok((warnings, report, car_files))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::{automation};
+    // use crate::beam_ng::get_mod_list;
+    use crate::assetto_corsa::car::model::PerformanceEstimator;
+    use crate::fabricator::assetto_corsa::{EngineParameterCalculator, EngineProvenance};
+    use crate::fabricator::{apply_low_rpm_torque_ramp, CancellationToken, choose_idle_rpm, clamp_boost_lut, engine_ui_specs, fit_boost_controller, FabricationError, recommended_clutch_torque, resample_boost_lut, rpm_override_warnings, scale_downshift_overrev, scale_gearbox_inertia, swap_description, update_gearbox_inertia, validate_rpm_overrides, weight_distribution_update};
+    use utils::units::{Power, Torque};
+
+    /// The sandbox data of the bi-turbo engine that the fabricator tests share
+    pub(crate) fn bi_turbo_engine_data() -> automation::sandbox::EngineV1 {
+        serde_json::from_str(include_str!("../../../crate_engine/src/test-data/bi_turbo_engine.json")).unwrap()
+    }
+
+    /// Write the bi-turbo test engine into `dir` as a crate engine and load a calculator from it
+    pub(crate) fn bi_turbo_calculator(dir: &Path) -> EngineParameterCalculator {
+        let eng_path = crate_engine::CrateEngine::from_sandbox_engine(bi_turbo_engine_data()).unwrap()
+            .write_to_path(dir.to_path_buf()).unwrap();
+        EngineParameterCalculator::from_crate_engine(&eng_path).unwrap()
+    }
+
+    // #[test]
+    // fn load_mods() -> Result<(), String> {
+    //     let mods = get_mod_list();
+    //     let calculator = EngineParameterCalculator::from_beam_ng_mod(mods[0].as_path())?;
+    //     std::fs::write("inertia.txt",format!("{}", calculator.inertia().unwrap()));
+    //     std::fs::write("idle.txt",format!("{}", calculator.idle_speed().unwrap()));
+    //     std::fs::write("limiter.txt",format!("{}", calculator.limiter()));
+    //     std::fs::write("fuel_cons.txt",format!("{}", calculator.basic_fuel_consumption()));
+    //     std::fs::write("torque_curve.txt",format!("{:?}", calculator.naturally_aspirated_wheel_torque_curve(0.85)));
+    //     std::fs::write("turbo_ctrl.txt",format!("{:?}", calculator.create_turbo_controllers()));
+    //     std::fs::write("turbo.txt",format!("{:?}", calculator.create_turbo().unwrap()));
+    //     std::fs::write("coast.txt",format!("{:?}", calculator.coast_data().unwrap()));
+    //     std::fs::write("metadata.txt",format!("{:?}", calculator.create_metadata()));
+    //     std::fs::write("fuel_flow.txt", format!("{:?}", calculator.fuel_flow_consumption(0.75))).unwrap();
+    //     std::fs::write("damage.txt", format!("{:?}", calculator.damage())).unwrap();
+    //     Ok(())
+    // }
+
+    // #[test]
+    // fn clone_and_swap_test() -> Result<(), String> {
+    //     let new_car_path = create_new_car_spec("zephyr_za401", "test", true).unwrap();
+    //     let mods = get_mod_list();
+    //     swap_automation_engine_into_ac_car(mods[0].as_path(),
+    //                                        new_car_path.as_path(),
+    //                                        AssettoCorsaCarSettings::default(),
+    //                                        AdditionalAcCarData::default())
+    // }
+
+    #[test]
+    fn low_rpm_torque_ramp() {
+        let curve = vec![(1000, 200.0), (2000, 250.0), (3000, 220.0)];
+        let ramped = apply_low_rpm_torque_ramp(curve.clone(), 600);
+        assert_eq!(ramped.first(), Some(&(600, 40.0)));
+        assert_eq!(&ramped[ramped.len()-3..], curve.as_slice());
+        assert!(ramped.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(ramped[..ramped.len()-3].iter().all(|(_, torque)| *torque < 200.0));
+
+        let unchanged = apply_low_rpm_torque_ramp(curve.clone(), 1000);
+        assert_eq!(unchanged, curve);
+    }
+
+    #[test]
+    fn clutch_torque_recommendation() {
+        assert_eq!(recommended_clutch_torque(0), 50);
+        assert_eq!(recommended_clutch_torque(20), 50);
+        assert_eq!(recommended_clutch_torque(21), 100);
+        assert_eq!(recommended_clutch_torque(70), 100);
+        assert_eq!(recommended_clutch_torque(71), 150);
+        assert_eq!(recommended_clutch_torque(470), 500);
+        assert_eq!(recommended_clutch_torque(471), 550);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn auto_adjust_clutch_forwards_to_auto_adjust_drivetrain() {
+        let mut settings = super::AssettoCorsaCarSettings::default();
+        settings.set_auto_adjust_clutch(false);
+        assert!(!settings.auto_adjust_drivetrain);
+        settings.auto_adjust_drivetrain = true;
+        assert!(settings.auto_adjust_clutch());
+    }
+
+    #[test]
+    fn weight_distribution_moved_with_engine_mass() {
+        // 60kg more engine over the front axle of a 1000kg car with 55% of its weight at the front
+        let (new_fraction, warning) = weight_distribution_update(1000, 0.55, 60, true);
+        assert!((new_fraction.unwrap() - 0.5755).abs() < 0.001, "{:?}", new_fraction);
+        assert!(warning.unwrap().contains("entirely over the front axle"));
+
+        let (new_fraction, warning) = weight_distribution_update(1000, 0.55, 60, false);
+        assert_eq!(new_fraction, None);
+        let warning = warning.unwrap();
+        assert!(warning.contains("57.5% front (from 55.0%)"));
+        assert!(warning.contains("entirely over the front axle"));
+
+        // Rear heavy cars are assumed to carry the engine over the rear axle so the distribution
+        // moves rearwards
+        let (new_fraction, warning) = weight_distribution_update(1200, 0.42, 50, true);
+        assert!(new_fraction.unwrap() < 0.42);
+        assert!(warning.unwrap().contains("entirely over the rear axle"));
+        assert_eq!(weight_distribution_update(1000, 0.55, 2, true), (None, None));
+        assert_eq!(weight_distribution_update(1000, 0.55, 2, false), (None, None));
+    }
+
+    #[test]
+    fn downshift_overrev_scaling() {
+        assert_eq!(scale_downshift_overrev(200, 7000, 8400), 240);
+        assert_eq!(scale_downshift_overrev(200, 8000, 6000), 150);
+        assert_eq!(scale_downshift_overrev(200, 0, 6000), 200);
+    }
+
+    #[test]
+    fn gearbox_inertia_scaling() {
+        assert_eq!(scale_gearbox_inertia(0.018, 0.1, 0.3), 0.054);
+        assert_eq!(scale_gearbox_inertia(0.02, 0.2, 0.1), 0.01);
+        assert_eq!(scale_gearbox_inertia(0.02, 0.0, 0.1), 0.02);
+        assert_eq!(scale_gearbox_inertia(0.02, 0.5, 0.001), 0.001);
+    }
+
+    #[test]
+    fn extended_physics_cars_not_downgraded_unless_forced() {
+        use crate::fabricator::{AssettoCorsaPhysicsLevel, resolve_physics_level};
+        use AssettoCorsaPhysicsLevel::*;
+
+        assert_eq!(resolve_physics_level(BaseGame, false, false), (BaseGame, None));
+        assert_eq!(resolve_physics_level(CspExtendedPhysics, false, false), (CspExtendedPhysics, None));
+        let (level, warning) = resolve_physics_level(BaseGame, true, false);
+        assert_eq!(level, CspExtendedPhysics);
+        assert!(warning.is_some());
+        assert_eq!(resolve_physics_level(BaseGame, true, true), (BaseGame, None));
+    }
+
+    #[test]
+    fn shift_points_follow_power_curve() {
+        use crate::fabricator::{limiter_shift_points, power_curve_shift_points};
+
+        // A small turbo that runs out of breath well before the limiter
+        let peaky_turbo_curve = vec![
+            (1000, 40), (2000, 80), (3000, 150), (4000, 300), (4500, 350), (5000, 370), (5500, 375),
+            (6000, 340), (6500, 290), (7000, 240), (7500, 200), (8000, 170)
+        ];
+        let gear_ratios = vec![3.5, 2.3, 1.7, 1.35, 1.1, 0.9];
+        let (up, down) = power_curve_shift_points(&peaky_turbo_curve, &gear_ratios, 8000).unwrap();
+        assert!((5500..=6500).contains(&up), "upshift at {}", up);
+        assert!(up < limiter_shift_points(8000).0);
+        assert!(down <= up - 500 && down >= 4000, "downshift at {}", down);
+
+        assert_eq!(power_curve_shift_points(&peaky_turbo_curve, &[3.5], 8000), None);
+        assert_eq!(power_curve_shift_points(&[], &gear_ratios, 8000), None);
+        assert_eq!(limiter_shift_points(8000), (7760, 5600));
+    }
+
+    #[test]
+    fn gearbox_inertia_written_to_drivetrain_ini() {
+        use crate::assetto_corsa::Car;
+        use crate::assetto_corsa::car::data::Drivetrain;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("gearbox_inertia");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        std::fs::write(car_path.join("data").join(Drivetrain::INI_FILENAME), "[GEARS]\nCOUNT=2\nGEAR_R=-3.5\nGEAR_1=2.5\nGEAR_2=1.5\nFINAL=3.1\n\n\
+            [GEARBOX]\nCHANGE_UP_TIME=130\nCHANGE_DN_TIME=180\nAUTO_CUTOFF_TIME=150\nSUPPORTS_SHIFTER=0\n\
+            VALID_SHIFT_RPM_WINDOW=800\nCONTROLS_WINDOW_GAIN=0.4\nINERTIA=0.018\n").unwrap();
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let mut drivetrain = Drivetrain::from_car(&mut car).unwrap();
+            update_gearbox_inertia(&mut drivetrain, 0.1, 0.3).unwrap();
+            drivetrain.write().unwrap();
+        }
+        let ini_output = std::fs::read_to_string(car_path.join("data").join(Drivetrain::INI_FILENAME)).unwrap();
+        assert!(ini_output.contains("INERTIA=0.054"), "{}", ini_output);
+        assert!(ini_output.contains("CHANGE_UP_TIME=130"), "{}", ini_output);
+    }
+
+    #[test]
+    fn bi_turbo_engine_writes_two_turbo_sections() {
+        use crate::assetto_corsa::Car;
+        use crate::assetto_corsa::car::data::Engine;
+        use crate::assetto_corsa::car::data::engine::TurboControllerFile;
+        use crate::assetto_corsa::traits::update_car_data;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("bi_turbo");
+        let car_path = test_path.join("car");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        std::fs::write(car_path.join("data").join(Engine::INI_FILENAME), "[HEADER]\nVERSION=1\n").unwrap();
+
+        let calculator = bi_turbo_calculator(&test_path);
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let mut engine = Engine::from_car(&mut car).unwrap();
+            update_car_data(&mut engine, &calculator.create_turbo().unwrap()).unwrap();
+            engine.write().unwrap();
+            for (turbo_idx, turbo_ctrl) in calculator.create_turbo_controllers().into_iter().enumerate() {
+                let mut controller_file = TurboControllerFile::new(&mut car, turbo_idx);
+                update_car_data(&mut controller_file, &turbo_ctrl).unwrap();
+                controller_file.write().unwrap();
+            }
+        }
+        let ini_output = std::fs::read_to_string(car_path.join("data").join(Engine::INI_FILENAME)).unwrap();
+        let ctrl_files: Vec<bool> = (0..3).map(|idx| {
+            car_path.join("data").join(TurboControllerFile::get_controller_ini_filename(idx)).is_file()
+        }).collect();
+        assert!(ini_output.contains("[TURBO_0]"), "{}", ini_output);
+        assert!(ini_output.contains("[TURBO_1]"), "{}", ini_output);
+        assert!(!ini_output.contains("[TURBO_2]"), "{}", ini_output);
+        assert_eq!(ctrl_files, vec![true, true, false]);
+    }
+
+    #[test]
+    fn eboosted_charger_has_flat_boost_and_no_lag() {
+        use crate::assetto_corsa::Car;
+        use crate::assetto_corsa::car::data::Engine;
+        use crate::assetto_corsa::traits::update_car_data;
+        use crate::fabricator::assetto_corsa::EngineParameterCalculator;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("eboost");
+        let car_path = test_path.join("car");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+        std::fs::write(car_path.join("data").join(Engine::INI_FILENAME), "[HEADER]\nVERSION=1\n").unwrap();
+
+        let mut engine_data = bi_turbo_engine_data();
+        engine_data.al_rima.aspiration_subtype_1 = Some("AspirationSubType_EBoost_Name".to_string());
+        engine_data.al_rima.eboost_max_boost = Some(0.5);
+        let calculator = EngineParameterCalculator::from_sandbox_engine(engine_data).unwrap();
+        let controllers = calculator.create_turbo_controllers();
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let mut engine = Engine::from_car(&mut car).unwrap();
+            update_car_data(&mut engine, &calculator.create_turbo().unwrap()).unwrap();
+            engine.write().unwrap();
+        }
+        let ini_output = std::fs::read_to_string(car_path.join("data").join(Engine::INI_FILENAME)).unwrap();
+
+        let eboost_lut: Vec<f64> = controllers[0].get_lut().to_vec().into_iter().map(|(_, boost)| boost).collect();
+        // The e-boost holds its own max boost rather than the engine's peak boost
+        assert!(eboost_lut[0] > 0.0 && eboost_lut[0] <= 0.5, "{:?}", eboost_lut);
+        assert!(eboost_lut.iter().all(|boost| *boost == eboost_lut[0]), "{:?}", eboost_lut);
+        let turbo_lut: Vec<f64> = controllers[1].get_lut().to_vec().into_iter().map(|(_, boost)| boost).collect();
+        assert!(turbo_lut.iter().any(|boost| *boost != turbo_lut[0]), "{:?}", turbo_lut);
+
+        let lag_ups: Vec<&str> = ini_output.lines().filter(|line| line.starts_with("LAG_UP=")).collect();
+        assert_eq!(lag_ups, vec!["LAG_UP=0.000", "LAG_UP=0.980"], "{}", ini_output);
+    }
+
+    #[test]
+    fn boost_lut_resampled_between_idle_and_limiter() {
+        let lut = vec![(1000.0, 0.0), (3000.0, 0.8), (5000.0, 1.2), (7000.0, 1.0)];
+        let resampled = resample_boost_lut(&lut, 850, 7200, 500);
+        assert_eq!(resampled.first(), Some(&(850.0, 0.0)));
+        assert_eq!(resampled[1], (1000.0, 0.0));
+        assert_eq!(resampled[4], (2500.0, 0.6));
+        assert_eq!(resampled.last(), Some(&(7200.0, 1.0)));
+        assert_eq!(resampled.len(), 15);
+        assert!(resampled.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let (clamped, clamped_count) = clamp_boost_lut(resampled, 1.1);
+        assert_eq!(clamped_count, 2);
+        assert!(clamped.iter().all(|(_, boost)| *boost <= 1.1));
+        assert_eq!(resample_boost_lut(&lut, 1000, 7000, 0), lut);
+    }
+
+    #[test]
+    fn boost_controller_fitted_to_turbo() {
+        use crate::assetto_corsa::Car;
+        use crate::assetto_corsa::car::data::engine::TurboControllerFile;
+        use crate::assetto_corsa::car::data::engine::turbo_ctrl::TurboController;
+        use crate::assetto_corsa::traits::update_car_data;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("boost_controller_fit");
+        let car_path = test_path.join("car");
+        std::fs::create_dir_all(car_path.join("data")).unwrap();
+
+        let calculator = bi_turbo_calculator(&test_path);
+        let turbo = calculator.create_turbo().unwrap();
+        let boost_limit = turbo.sections()[0].boost_limit() * 0.9;
+        {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let mut turbo_ctrl = calculator.create_turbo_controllers().into_iter().next().unwrap();
+            let warning = fit_boost_controller(&mut turbo_ctrl, 0, 1000, 7000, Some(500), Some(boost_limit));
+            assert!(warning.is_some());
+            let mut controller_file = TurboControllerFile::new(&mut car, 0);
+            update_car_data(&mut controller_file, &turbo_ctrl).unwrap();
+            controller_file.write().unwrap();
+        }
+        let lut = {
+            let mut car = Car::load_from_path(&car_path).unwrap();
+            let controller_file = TurboControllerFile::from_car(&mut car, 0).unwrap().unwrap();
+            TurboController::load_from_parent(0, &controller_file).unwrap().get_lut().to_vec()
+        };
+        assert_eq!(lut.len(), 13);
+        assert_eq!(lut.first().unwrap().0, 1000.0);
+        assert_eq!(lut.last().unwrap().0, 7000.0);
+        assert!(lut.iter().all(|(_, boost)| *boost <= boost_limit), "{:?}", lut);
+    }
+
+    #[test]
+    fn torque_curve_csv_parsed() {
+        use crate::fabricator::{CurveSource, EngineCurves, FabricationError, load_torque_curve_csv, parse_torque_curve_csv};
+
+        let curve = parse_torque_curve_csv("rpm,torque\n# dyno run 2\n1000, 150\n\n4000,320.5\n7000,280\n").unwrap();
+        assert_eq!(curve, vec![(1000.0, 150.0), (4000.0, 320.5), (7000.0, 280.0)]);
+        let curves = EngineCurves::from_torque_curve(&curve);
+        assert_eq!(curves.peak_torque, 321);
+        assert_eq!(curves.power.last().unwrap().0, 7000);
+
+        let non_numeric = parse_torque_curve_csv("rpm,torque\n1000,150\n4000,lots\n").unwrap_err();
+        assert!(non_numeric.starts_with("Line 3"), "{}", non_numeric);
+        let non_numeric_rpm = parse_torque_curve_csv("1000,150\nredline,300\n").unwrap_err();
+        assert!(non_numeric_rpm.starts_with("Line 2"), "{}", non_numeric_rpm);
+        let descending = parse_torque_curve_csv("1000,150\n4000,300\n3000,310\n").unwrap_err();
+        assert!(descending.contains("doesn't increase"), "{}", descending);
+        assert!(parse_torque_curve_csv("1000,150\n1000,160\n").is_err());
+        assert!(parse_torque_curve_csv("1000,150,90\n4000,300,200\n").is_err());
+        assert!(parse_torque_curve_csv("1000,-150\n4000,300\n").is_err());
+        assert!(parse_torque_curve_csv("rpm,torque\n1000,150\n").is_err());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let missing = temp_dir.path().join("missing_torque_curve.csv");
+        assert!(matches!(load_torque_curve_csv(&missing), Err(FabricationError::FailedToLoad(_, _))));
+        assert_eq!(CurveSource::default(), CurveSource::Calculator);
+    }
+
+    #[test]
+    fn csv_torque_curve_has_boost_removed() {
+        use crate::fabricator::assetto_corsa::EngineParameterCalculator;
+
+        let calculator = EngineParameterCalculator::from_sandbox_engine(bi_turbo_engine_data()).unwrap();
+        let boost_curve = calculator.boost_curve();
+        assert!(!boost_curve.is_empty());
+        let (boost_rpm, boost) = boost_curve.iter().copied()
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap()).unwrap();
+
+        let wheel_curve = calculator.wheel_torque_curve_from(&[(1000.0, 100.0), (boost_rpm, 400.0)], 0.8);
+        assert_eq!(wheel_curve.len(), 4);
+        assert_eq!(wheel_curve[1], (boost_rpm.round() as i32, ((400.0 / (1.0 + boost)) * 0.8).round()));
+        assert_eq!(wheel_curve.last().unwrap().1, 0.0);
+    }
+
+    #[test]
+    fn low_gear_boost_controller_added_after_boost_controller() {
+        use crate::assetto_corsa::Car;
+        use crate::assetto_corsa::car::data::engine::TurboControllerFile;
+        use crate::assetto_corsa::car::data::engine::turbo_ctrl::{ControllerCombinator, ControllerInput, TurboController};
+        use crate::assetto_corsa::traits::update_car_data;
+        use crate::fabricator::create_low_gear_boost_controller;
+
+        let boost_ctrl = TurboController::new(0, ControllerInput::Rpms, ControllerCombinator::Add,
+                                              vec![(1000.0, 0.2), (4000.0, 1.1), (7000.0, 1.0)],
+                                              0.95, 10000.0, 0.0);
+        assert!(create_low_gear_boost_controller(&boost_ctrl, 0.0).is_err());
+        assert!(create_low_gear_boost_controller(&boost_ctrl, 1.5).is_err());
+        let gas_ctrl = TurboController::new(0, ControllerInput::Gas, ControllerCombinator::Add,
+                                            vec![(0.0, 0.0), (1.0, 1.0)], 0.95, 10000.0, 0.0);
+        assert!(create_low_gear_boost_controller(&gas_ctrl, 0.6).is_err());
+        let gear_ctrl = create_low_gear_boost_controller(&boost_ctrl, 0.6).unwrap();
+        assert_eq!(gear_ctrl.combinator(), ControllerCombinator::Mult);
+        assert_eq!(gear_ctrl.up_limit(), boost_ctrl.up_limit());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("low_gear_boost_controller");
+        std::fs::create_dir_all(test_path.join("data")).unwrap();
+        {
+            let mut car = Car::load_from_path(&test_path).unwrap();
+            let mut controller_file = TurboControllerFile::new(&mut car, 0);
+            update_car_data(&mut controller_file, &boost_ctrl).unwrap();
+            assert_eq!(controller_file.add_controller(&gear_ctrl).unwrap(), 1);
+            controller_file.write().unwrap();
+        }
+        let gear_lut = {
+            let mut car = Car::load_from_path(&test_path).unwrap();
+            let controller_file = TurboControllerFile::from_car(&mut car, 0).unwrap().unwrap();
+            assert_eq!(controller_file.num_controller_sections(), 2);
+            let loaded = TurboController::load_from_parent(1, &controller_file).unwrap();
+            assert_eq!(loaded.input(), ControllerInput::Gear);
+            loaded.get_lut().to_vec()
+        };
+        assert_eq!(gear_lut, vec![(0.0, 1.0), (1.0, 1.0), (2.0, 0.6), (3.0, 0.6), (4.0, 1.0)]);
+    }
+
+    #[test]
+    fn swap_compatibility_reports_missing_car_data() {
+        use crate::fabricator::check_sandbox_engine_compatibility;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("swap_compatibility");
+        let data_path = car_path.join("data");
+        std::fs::create_dir_all(&data_path).unwrap();
+        std::fs::write(data_path.join("engine.ini"), "[HEADER]\nVERSION=1\nPOWER_CURVE=power.lut\n").unwrap();
+        std::fs::write(data_path.join("car.ini"), "[HEADER]\nVERSION=2\n").unwrap();
+        let check_compatibility = |car_path: &Path| check_sandbox_engine_compatibility(bi_turbo_engine_data(), car_path);
+
+        let missing_drivetrain = check_compatibility(&car_path).unwrap_err();
+        std::fs::write(data_path.join("drivetrain.ini"), "[HEADER]\nVERSION=3\n").unwrap();
+        let missing_power_curve = check_compatibility(&car_path).unwrap_err();
+        std::fs::write(data_path.join("power.lut"), "0|100\n7000|300\n").unwrap();
+        let missing_traction = check_compatibility(&car_path).unwrap_err();
+        std::fs::write(data_path.join("drivetrain.ini"), "[HEADER]\nVERSION=3\n\n[TRACTION]\nTYPE=RWD\n").unwrap();
+        let compatible = check_compatibility(&car_path);
+        let files_after_check = std::fs::read_dir(&data_path).unwrap().count();
+
+        assert!(missing_drivetrain.to_string().contains("drivetrain.ini"), "{}", missing_drivetrain);
+        assert!(matches!(missing_traction, FabricationError::MissingDataSection(section, _) if section == "Traction"));
+        assert!(missing_power_curve.to_string().contains("power curve"), "{}", missing_power_curve);
+        assert!(compatible.is_ok(), "{:?}", compatible);
+        assert_eq!(files_after_check, 4);
+    }
+
+    #[test]
+    fn curve_smoothing_keeps_peak() {
+        use crate::fabricator::{curve_peak, PowerCurveSmoothing, smooth_curve};
+
+        let sawtooth: Vec<(i32, f64)> = (0..20).map(|idx| {
+            (1000 + idx * 250, 200.0 + (idx as f64 * 5.0) + if idx % 2 == 0 { 15.0 } else { -15.0 })
+        }).collect();
+        let direction_changes = |curve: &[(i32, f64)]| {
+            curve.windows(3).filter(|w| (w[1].1 - w[0].1).signum() != (w[2].1 - w[1].1).signum()).count()
+        };
+
+        let averaged = smooth_curve(sawtooth.clone(), PowerCurveSmoothing::MovingAverage(3));
+        assert_eq!(averaged.len(), sawtooth.len());
+        assert_eq!(averaged.first().unwrap().0, 1000);
+        assert!(direction_changes(&averaged) < direction_changes(&sawtooth));
+        assert!((curve_peak(&averaged) - curve_peak(&sawtooth)).abs() < 1e-9);
+
+        let spline = smooth_curve(sawtooth.clone(), PowerCurveSmoothing::CatmullRom(100));
+        assert_eq!(spline.first().unwrap().0, 1000);
+        assert_eq!(spline.last().unwrap().0, sawtooth.last().unwrap().0);
+        assert!(spline.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!((curve_peak(&spline) - curve_peak(&sawtooth)).abs() < 1e-9);
+
+        assert_eq!(smooth_curve(sawtooth.clone(), PowerCurveSmoothing::None), sawtooth);
+    }
+
+    #[test]
+    fn curve_smoothing_ignores_repeated_rpm_points() {
+        use crate::fabricator::{PowerCurveSmoothing, smooth_curve};
+
+        let curve = vec![(1000, 100.0), (1000, 110.0), (1500, 150.0), (2000, 180.0),
+                         (2000, 185.0), (2500, 200.0), (3000, 190.0), (3000, 190.0)];
+        for smoothing in [PowerCurveSmoothing::MovingAverage(3), PowerCurveSmoothing::CatmullRom(100)] {
+            let smoothed = smooth_curve(curve.clone(), smoothing);
+            assert!(smoothed.iter().all(|(_, val)| val.is_finite()), "{}: {:?}", smoothing, smoothed);
+            assert!(smoothed.windows(2).all(|w| w[0].0 < w[1].0), "{}: {:?}", smoothing, smoothed);
+        }
+    }
+
+    #[test]
+    fn smoothed_ui_specs_match_curves() {
+        use crate::fabricator::{EngineCurves, int_curve_peak, PowerCurveSmoothing, ui_curves};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("curve_smoothing");
+        std::fs::create_dir_all(&test_path).unwrap();
+        let calculator = bi_turbo_calculator(&test_path);
+
+        let unsmoothed_bhp = int_curve_peak(&calculator.engine_bhp_power_curve());
+        let unsmoothed_torque = int_curve_peak(&calculator.engine_torque_curve());
+        for smoothing in PowerCurveSmoothing::ALL.iter().filter(|s| **s != PowerCurveSmoothing::None) {
+            let (power_curve, torque_curve, peak_bhp, peak_torque) = ui_curves(&EngineCurves::from_calculator(&calculator), *smoothing);
+            assert_eq!(peak_bhp, int_curve_peak(&power_curve), "{}", smoothing);
+            assert_eq!(peak_torque, int_curve_peak(&torque_curve), "{}", smoothing);
+            assert!((peak_bhp - unsmoothed_bhp).abs() <= 1, "{}", smoothing);
+            assert!((peak_torque - unsmoothed_torque).abs() <= 1, "{}", smoothing);
+        }
+    }
+
+    #[test]
+    fn automation_curve_preferred_over_mismatched_jbeam_torque() {
+        use crate::fabricator::assetto_corsa::{choose_torque_curve_source, EngineParameterCalculator, torque_table_curve};
+        use crate::fabricator::{DEFAULT_TORQUE_CURVE_TOLERANCE, EngineCurves, int_curve_peak, PowerCurveSmoothing, TorqueCurveSource, ui_curves};
+
+        let calculator = EngineParameterCalculator::from_sandbox_engine(bi_turbo_engine_data()).unwrap();
+        let peak_torque = calculator.peak_torque();
+        let peak_bhp = calculator.peak_bhp();
+        // Sandbox engines have no jbeam torque table to compare against
+        assert_eq!(calculator.check_torque_curve(DEFAULT_TORQUE_CURVE_TOLERANCE), TorqueCurveSource::EngineCurve);
+
+        // A torque table that has had drivetrain losses applied
+        let wheel_torque = |torque: i32| (torque as f64 * 0.85).round();
+        let torque_table: serde_hjson::Value = serde_hjson::from_str(&format!(
+            "[[\"rpm\", \"torque\"], [1000, {}], [4000, {}], [7000, {}]]",
+            wheel_torque(peak_torque / 2), wheel_torque(peak_torque), wheel_torque(peak_torque * 3 / 4)
+        )).unwrap();
+        let jbeam_curve = torque_table_curve(&torque_table);
+        assert_eq!(jbeam_curve.len(), 3);
+        let jbeam_peak = jbeam_curve.iter().map(|(_, torque)| *torque).max();
+        assert_eq!(choose_torque_curve_source(jbeam_peak, peak_torque, DEFAULT_TORQUE_CURVE_TOLERANCE),
+                   TorqueCurveSource::AutomationCurve { jbeam_peak: jbeam_peak.unwrap(), peak_torque });
+        assert_eq!(choose_torque_curve_source(Some(peak_torque + 5), peak_torque, DEFAULT_TORQUE_CURVE_TOLERANCE),
+                   TorqueCurveSource::EngineCurve);
+
+        // The curves written from the Automation data match its recorded peaks
+        let within_2_percent = |value: i32, expected: i32| {
+            ((value - expected).abs() as f64) <= expected as f64 * 0.02
+        };
+        for smoothing in PowerCurveSmoothing::ALL.iter() {
+            let (power_curve, _, ui_peak_bhp, _) = ui_curves(&EngineCurves::from_calculator(&calculator), *smoothing);
+            assert!(within_2_percent(ui_peak_bhp, peak_bhp), "{}", smoothing);
+            assert!(within_2_percent(int_curve_peak(&power_curve), peak_bhp), "{}", smoothing);
+        }
+    }
+
+    #[test]
+    fn charger_boost_split() {
+        use crate::fabricator::assetto_corsa::charger_boost_fractions;
+        assert_eq!(charger_boost_fractions(&[Some(0.8)]), vec![1.0]);
+        assert_eq!(charger_boost_fractions(&[None]), vec![1.0]);
+        assert_eq!(charger_boost_fractions(&[Some(0.75), Some(0.25)]), vec![0.75, 0.25]);
+        assert_eq!(charger_boost_fractions(&[Some(0.6), None]), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn rpm_override_validation() {
+        assert!(rpm_override_warnings(None, None, 7000, 6500).is_empty());
+        assert!(rpm_override_warnings(Some(7500), Some(900), 7500, 6500).is_empty());
+        assert_eq!(rpm_override_warnings(Some(6000), None, 6000, 6500).len(), 1);
+        assert_eq!(rpm_override_warnings(None, Some(7000), 7000, 6500).len(), 1);
+        assert_eq!(rpm_override_warnings(Some(6000), Some(6000), 6000, 6500).len(), 2);
+        assert!(validate_rpm_overrides(Some(7500), Some(900)).is_ok());
+        assert!(matches!(validate_rpm_overrides(Some(0), None), Err(FabricationError::InvalidData(..))));
+        assert!(matches!(validate_rpm_overrides(None, Some(0)), Err(FabricationError::InvalidData(..))));
+    }
+
+    #[test]
+    fn idle_rpm_precedence() {
+        assert_eq!(choose_idle_rpm(Some(850.4), Some(900.0), Some(800.0), 1000), 850);
+        assert_eq!(choose_idle_rpm(None, Some(900.0), Some(800.0), 1000), 900);
+        assert_eq!(choose_idle_rpm(None, None, Some(800.0), 1000), 1000);
+    }
+
+    #[test]
+    fn idle_rpm_not_below_curve_minimum() {
+        assert_eq!(choose_idle_rpm(Some(700.0), Some(900.0), Some(1000.0), 600), 1000);
+        assert_eq!(choose_idle_rpm(None, Some(750.0), Some(1000.0), 600), 1000);
+        assert_eq!(choose_idle_rpm(Some(700.0), None, None, 600), 700);
+    }
+
+    #[test]
+    fn cancellation_is_shared_between_clones() {
+        let token = CancellationToken::new();
+        let swap_token = token.clone();
+        assert!(swap_token.check().is_ok());
+        token.cancel();
+        assert!(matches!(swap_token.check(), Err(FabricationError::Cancelled)));
+    }
+
+    #[test]
+    fn dump_automation_car_file() -> Result<(), String> {
+        //let path = PathBuf::from("/home/josykes/.steam/debian-installation/steamapps/compatdata/293760/pfx/drive_c/users/steamuser/AppData/Local/BeamNG.drive/mods/");
+        let path = PathBuf::from("C:/Users/zephy/AppData/Local/BeamNG.drive/mods");
+        // C:\Users\zephy\AppData\Local\BeamNG.drive\mods\dae1.zip
+        let mod_data = beam_ng::ModData::from_path(&path.join("dawnv6.zip"))?;
+        let automation_car_file = automation::car::CarFile::from_bytes( mod_data.get_automation_car_file_data().ok_or("Couldn't find car data")?.clone())?;
+        println!("{}", automation_car_file.to_json_string_pretty()?);
+        if let Some(version) = automation_car_file.get_section("Car").unwrap().get_section("Variant").unwrap().get_attribute("GameVersion") {
+            println!("{}", version);
+        }
+        //fs::write(Path::new("car_temp.toml"), format!("{}", automation_car_file));
+        Ok(())
+    }
+
+    #[test]
+    fn swap_description_replaces_previous_swap() {
+        let provenance = EngineProvenance {
+            name: "Test Six - Race".to_string(),
+            automation_version: Some(2409),
+            build_year: Some(1995),
+            configuration: Some("Inline 6 DOHC".to_string()),
+            aspiration: Some("Turbocharged".to_string()),
+            capacity_cc: Some(2997),
+            ..Default::default()
+        };
+        let first = swap_description(Some("A fast car."), &provenance, "2024-01-01");
+        assert_eq!(first, "A fast car.<br><br>Engine swap: Test Six - Race (1995 2997cc Inline 6 DOHC Turbocharged) \
+                           built in Automation version 2409. Swapped on 2024-01-01 with engine-crane");
+
+        let second = swap_description(Some(&first), &provenance, "2024-02-01");
+        assert!(second.starts_with("A fast car.<br><br>Engine swap: "));
+        assert_eq!(second.matches("Engine swap: ").count(), 1);
+        assert!(second.ends_with("Swapped on 2024-02-01 with engine-crane"));
+
+        let unknown = EngineProvenance { name: "Mystery".to_string(), ..Default::default() };
+        let from_empty = swap_description(None, &unknown, "2024-01-01");
+        assert_eq!(from_empty, "Engine swap: Mystery. Swapped on 2024-01-01 with engine-crane");
+        assert_eq!(swap_description(Some(&from_empty), &unknown, "2024-03-01"),
+                   "Engine swap: Mystery. Swapped on 2024-03-01 with engine-crane");
+    }
+
+    #[test]
+    fn engine_ui_specs_for_known_engine() {
+        let provenance = EngineProvenance {
+            name: "Test Six - Race".to_string(),
+            configuration: Some("Inline 6 DOHC".to_string()),
+            capacity_cc: Some(2997),
+            ..Default::default()
+        };
+        let specs = engine_ui_specs(Some((Power::from_kw(335.0), Torque::from_nm(512.4))), Some(1350), None, &provenance);
+        let expected = vec![
+            ("bhp", "449bhp"),
+            ("torque", "512Nm"),
+            ("weight", "1350kg"),
+            ("pwratio", "3.01kg/hp"),
+            ("acceleration", "---"),
+            ("range", "---"),
+            ("topspeed", "---"),
+            ("displacement", "2997cc"),
+            ("engine", "Inline 6 DOHC")
+        ];
+        assert_eq!(specs.iter().map(|(k, v)| (*k, v.as_str())).collect::<Vec<_>>(), expected);
+
+        let unknown = EngineProvenance { name: "Mystery".to_string(), ..Default::default() };
+        let specs = engine_ui_specs(Some((Power::from_bhp(300.0), Torque::from_nm(400.0))), None, None, &unknown);
+        assert_eq!(specs[0].1, "300bhp");
+        assert_eq!(specs[3].1, "---");
+        assert_eq!(specs.len(), 7);
+
+        let estimator = PerformanceEstimator::new(
+            vec![(1000.0, 300.0), (4000.0, 400.0), (7000.0, 300.0)], 7000.0, vec![3.5, 2.2, 1.6, 1.2, 1.0], 3.9, 0.32, 1350.0, 0.7, 0.55
+        );
+        let specs = engine_ui_specs(Some((Power::from_bhp(300.0), Torque::from_nm(400.0))), Some(1350), Some(&estimator), &unknown);
+        let spec = |key: &str| specs.iter().find(|(k, _)| *k == key).unwrap().1.clone();
+        assert!(spec("acceleration").ends_with("s 0-100"), "{}", spec("acceleration"));
+        assert_eq!(spec("topspeed"), format!("{}km/h", estimator.top_speed_kmh().round()));
+    }
+
+    #[test]
+    fn engine_ui_specs_keep_donor_output() {
+        let unknown = EngineProvenance { name: "Mystery".to_string(), ..Default::default() };
+        let specs = engine_ui_specs(None, Some(1350), None, &unknown);
+        let keys: Vec<&str> = specs.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec!["weight", "acceleration", "range", "topspeed"]);
+        assert_eq!(specs[0].1, "1350kg");
+    }
+
+    #[test]
+    fn non_ascii_engine_name_kept_in_crate_file_and_ui_json() {
+        use crate::assetto_corsa::car::ui::car_ui_data::UiInfo;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("non_ascii_engine_name");
+        let ui_path = test_path.join("car").join("ui");
+        std::fs::create_dir_all(&ui_path).unwrap();
+
+        let mut engine_data = bi_turbo_engine_data();
+        engine_data.variant_name = "Türbo 怪物".to_string();
+        let crate_engine = crate_engine::CrateEngine::from_sandbox_engine(engine_data).unwrap();
+        let eng_path = crate_engine.write_to_path(test_path.clone()).unwrap();
+        let eng_filename = eng_path.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(eng_filename.ends_with("Türbo_怪物.eng"), "{}", eng_filename);
+
+        let calculator = EngineParameterCalculator::from_crate_engine(&eng_path).unwrap();
+        let provenance = calculator.provenance();
+        assert!(provenance.name.ends_with("Türbo 怪物"), "{}", provenance.name);
+
+        std::fs::write(ui_path.join("ui_car.json"), b"\xef\xbb\xbf{\"name\": \"Test\", \"description\": \"Stock\"}").unwrap();
+        {
+            let mut ui_info = UiInfo::from_car_path(&test_path.join("car")).unwrap();
+            let description = swap_description(ui_info.description(), &provenance, "2024-01-01");
+            ui_info.set_description(description);
+            ui_info.write().unwrap();
+        }
+        let ui_json = std::fs::read_to_string(ui_path.join("ui_car.json")).unwrap();
+        let description = UiInfo::from_car_path(&test_path.join("car")).unwrap().description().unwrap().to_string();
+        assert!(ui_json.contains("Türbo 怪物"), "{}", ui_json);
+        assert!(description.contains(&provenance.name), "{}", description);
+    }
+
+    #[test]
+    fn throttle_response_from_fuel_system() {
+        use crate::fabricator::assetto_corsa::{throttle_response_exponent, throttle_response_lut};
+
+        assert_eq!(throttle_response_exponent("Injection", "MultiPointEFI", "RaceIntake", Some(2005)), Some(1.0));
+        assert_eq!(throttle_response_exponent("Carburetor", "Carb4Barrel", "ITB", Some(1965)), Some(1.0));
+        assert_eq!(throttle_response_exponent("Carburetor", "Carb2Barrel", "Standard", Some(1955)), Some(1.6));
+        assert_eq!(throttle_response_exponent("Carburetor", "Carb2Barrel", "Standard", Some(1972)), Some(1.4));
+        assert_eq!(throttle_response_exponent("Carburetor", "Carb2Barrel", "Standard", None), Some(1.25));
+        assert_eq!(throttle_response_exponent("Injection", "MultiPointEFI", "Standard", Some(1995)), None);
+
+        let linear = throttle_response_lut(1.0);
+        assert_eq!(linear.len(), 11);
+        assert!(linear.iter().all(|(pedal, throttle)| pedal == throttle));
+        let softened = throttle_response_lut(1.6);
+        assert_eq!((softened[0], softened[5], softened[10]), ((0, 0), (50, 33), (100, 100)));
+    }
+}
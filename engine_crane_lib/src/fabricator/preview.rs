@@ -0,0 +1,102 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::fabricator::report::SwapReport;
+
+/// What an engine swap would change in a car. Produced by running the swap against an in-memory
+/// copy of the car data so that nothing is written
+#[derive(Debug)]
+pub struct SwapPreview {
+    data_files: BTreeMap<String, Option<Vec<u8>>>,
+    car_files: Vec<String>,
+    report: SwapReport
+}
+
+impl SwapPreview {
+    pub(crate) fn new(data_files: BTreeMap<String, Option<Vec<u8>>>,
+                      car_files: Vec<String>,
+                      report: SwapReport) -> SwapPreview {
+        SwapPreview { data_files, car_files, report }
+    }
+
+    /// The contents each car data file would be written with, or None for files that would be deleted
+    pub fn data_files(&self) -> &BTreeMap<String, Option<Vec<u8>>> {
+        &self.data_files
+    }
+
+    /// The files outside the car data that would be written, relative to the car folder
+    pub fn car_files(&self) -> &[String] {
+        &self.car_files
+    }
+}
+
+impl Display for SwapPreview {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.report.summary_lines() {
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f, "Data files:")?;
+        for (filename, contents) in &self.data_files {
+            match contents {
+                Some(data) => writeln!(f, "  {} ({} bytes)", filename, data.len())?,
+                None => writeln!(f, "  {} (deleted)", filename)?
+            }
+        }
+        writeln!(f, "Car files:")?;
+        for filename in &self.car_files {
+            writeln!(f, "  {}", filename)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::fabricator::preview::SwapPreview;
+    use crate::fabricator::report::SwapReport;
+
+    #[test]
+    fn preview_lists_changes() {
+        let mut report = SwapReport::new("2024-05-01", "Test V8", None, None);
+        report.limiter_changed(7000, 8200);
+        report.turbo_sections_changed(&[String::from("TURBO_0")], &[]);
+        let mut data_files = BTreeMap::new();
+        data_files.insert(String::from("engine.ini"), Some(vec![0u8; 12]));
+        data_files.insert(String::from("ctrl_turbo0.ini"), None);
+        let preview = SwapPreview::new(data_files, vec![String::from("ui/ui_car.json")], report);
+
+        let lines: Vec<String> = preview.to_string().lines().map(String::from).collect();
+        assert_eq!(lines, vec![
+            "Engine: Test V8",
+            "Limiter: 7000 -> 8200",
+            "Turbo sections removed: TURBO_0",
+            "Data files:",
+            "  ctrl_turbo0.ini (deleted)",
+            "  engine.ini (12 bytes)",
+            "Car files:",
+            "  ui/ui_car.json"
+        ]);
+    }
+}
@@ -0,0 +1,276 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use tracing::info;
+
+use crate::fabricator::{FabricationError, FabricationWarning};
+use crate::fabricator::TorqueCurveSource;
+
+pub const SWAP_REPORT_FILENAME: &str = "engine_crane_swap_report.json";
+
+#[derive(Debug, Serialize)]
+struct ValueChange<T> {
+    old: T,
+    new: T
+}
+
+#[derive(Debug, Serialize)]
+struct EngineSource {
+    engine_name: String,
+    /// The mod or crate engine file the engine was loaded from. None for engines loaded
+    /// directly from the Automation sandbox
+    file: Option<String>,
+    sha256: Option<String>
+}
+
+/// A summary of the changes an engine swap made to a car. Built up as the swap updates the car
+/// data and written into the car folder once the swap succeeds
+#[derive(Debug, Serialize)]
+pub(crate) struct SwapReport {
+    swap_date: String,
+    source: EngineSource,
+    torque_curve: TorqueCurveSource,
+    engine_ini_sections: Vec<String>,
+    limiter: Option<ValueChange<i32>>,
+    idle_rpm: Option<ValueChange<i32>>,
+    inertia: Option<ValueChange<f64>>,
+    total_mass: Option<ValueChange<u32>>,
+    /// The fraction of the car's weight on the front axle
+    front_weight_fraction: Option<ValueChange<f64>>,
+    turbo_sections_added: Vec<String>,
+    turbo_sections_removed: Vec<String>,
+    turbo_controllers: usize,
+    clutch_max_torque: Option<ValueChange<i32>>,
+    ui_specs: BTreeMap<String, String>,
+    /// The installed car the engine sound was copied from, either chosen for the swap or matched to the engine
+    engine_sound_donor: Option<String>,
+    warnings: Vec<String>
+}
+
+impl SwapReport {
+    pub(crate) fn new(swap_date: &str,
+                      engine_name: &str,
+                      source_file: Option<&Path>,
+                      source_hash: Option<String>) -> SwapReport {
+        let file = source_file.and_then(|path| path.file_name()).map(|filename| {
+            filename.to_string_lossy().to_string()
+        });
+        SwapReport {
+            swap_date: swap_date.to_string(),
+            source: EngineSource { engine_name: engine_name.to_string(), file, sha256: source_hash },
+            torque_curve: TorqueCurveSource::default(),
+            engine_ini_sections: Vec::new(),
+            limiter: None,
+            idle_rpm: None,
+            inertia: None,
+            total_mass: None,
+            front_weight_fraction: None,
+            turbo_sections_added: Vec::new(),
+            turbo_sections_removed: Vec::new(),
+            turbo_controllers: 0,
+            clutch_max_torque: None,
+            ui_specs: BTreeMap::new(),
+            engine_sound_donor: None,
+            warnings: Vec::new()
+        }
+    }
+
+    pub(crate) fn torque_curve_source_chosen(&mut self, torque_curve: TorqueCurveSource) {
+        self.torque_curve = torque_curve;
+    }
+
+    pub(crate) fn engine_ini_section_updated(&mut self, section_name: &str) {
+        if !self.engine_ini_sections.iter().any(|name| name == section_name) {
+            self.engine_ini_sections.push(section_name.to_string());
+        }
+    }
+
+    pub(crate) fn limiter_changed(&mut self, old: i32, new: i32) {
+        self.limiter = Some(ValueChange { old, new });
+    }
+
+    pub(crate) fn idle_rpm_changed(&mut self, old: i32, new: i32) {
+        self.idle_rpm = Some(ValueChange { old, new });
+    }
+
+    pub(crate) fn inertia_changed(&mut self, old: f64, new: f64) {
+        self.inertia = Some(ValueChange { old, new });
+    }
+
+    pub(crate) fn total_mass_changed(&mut self, old: u32, new: u32) {
+        self.total_mass = Some(ValueChange { old, new });
+    }
+
+    pub(crate) fn weight_distribution_changed(&mut self, old: f64, new: f64) {
+        self.front_weight_fraction = Some(ValueChange { old, new });
+    }
+
+    /// Record the turbo sections added or removed when the car went from `old_names` to `new_names`
+    pub(crate) fn turbo_sections_changed(&mut self, old_names: &[String], new_names: &[String]) {
+        self.turbo_sections_added = new_names.iter().filter(|name| !old_names.contains(name)).cloned().collect();
+        self.turbo_sections_removed = old_names.iter().filter(|name| !new_names.contains(name)).cloned().collect();
+    }
+
+    pub(crate) fn turbo_controller_written(&mut self) {
+        self.turbo_controllers += 1;
+    }
+
+    pub(crate) fn clutch_max_torque_changed(&mut self, old: i32, new: i32) {
+        self.clutch_max_torque = Some(ValueChange { old, new });
+    }
+
+    pub(crate) fn ui_spec_updated(&mut self, spec: &str, value: &str) {
+        self.ui_specs.insert(spec.to_string(), value.to_string());
+    }
+
+    pub(crate) fn engine_sound_copied_from(&mut self, car_name: &str) {
+        self.engine_sound_donor = Some(car_name.to_string());
+    }
+
+    pub(crate) fn add_warnings(&mut self, warnings: &[FabricationWarning]) {
+        self.warnings.extend(warnings.iter().map(FabricationWarning::to_string));
+    }
+
+    /// A line describing each change in the report, for showing to the user
+    pub(crate) fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Engine: {}", self.source.engine_name)];
+        match &self.torque_curve {
+            TorqueCurveSource::EngineCurve => {}
+            TorqueCurveSource::AutomationCurve { jbeam_peak, peak_torque } => {
+                lines.push(format!("Automation torque curve used as the jbeam torque table peaks at {}Nm, not the engine's {}Nm", jbeam_peak, peak_torque));
+            }
+            TorqueCurveSource::DonorCurve => lines.push("Power curve kept from the donor car".to_string()),
+            TorqueCurveSource::Csv { path } => lines.push(format!("Torque curve loaded from {}", path))
+        }
+        if !self.engine_ini_sections.is_empty() {
+            lines.push(format!("engine.ini sections: {}", self.engine_ini_sections.join(", ")));
+        }
+        if let Some(limiter) = &self.limiter {
+            lines.push(format!("Limiter: {} -> {}", limiter.old, limiter.new));
+        }
+        if let Some(idle_rpm) = &self.idle_rpm {
+            lines.push(format!("Idle rpm: {} -> {}", idle_rpm.old, idle_rpm.new));
+        }
+        if let Some(inertia) = &self.inertia {
+            lines.push(format!("Inertia: {:.3} -> {:.3}", inertia.old, inertia.new));
+        }
+        if let Some(total_mass) = &self.total_mass {
+            lines.push(format!("Total mass: {}kg -> {}kg", total_mass.old, total_mass.new));
+        }
+        if let Some(front_weight_fraction) = &self.front_weight_fraction {
+            lines.push(format!("Front weight distribution: {:.1}% -> {:.1}%",
+                               front_weight_fraction.old * 100.0, front_weight_fraction.new * 100.0));
+        }
+        if !self.turbo_sections_added.is_empty() {
+            lines.push(format!("Turbo sections added: {}", self.turbo_sections_added.join(", ")));
+        }
+        if !self.turbo_sections_removed.is_empty() {
+            lines.push(format!("Turbo sections removed: {}", self.turbo_sections_removed.join(", ")));
+        }
+        if self.turbo_controllers > 0 {
+            lines.push(format!("Turbo controllers: {}", self.turbo_controllers));
+        }
+        if let Some(clutch_max_torque) = &self.clutch_max_torque {
+            lines.push(format!("Clutch max torque: {}Nm -> {}Nm", clutch_max_torque.old, clutch_max_torque.new));
+        }
+        for (spec, value) in &self.ui_specs {
+            lines.push(format!("UI {}: {}", spec, value));
+        }
+        if let Some(donor) = &self.engine_sound_donor {
+            lines.push(format!("Engine sound from: {}", donor));
+        }
+        for warning in &self.warnings {
+            lines.push(format!("Warning: {}", warning));
+        }
+        lines
+    }
+
+    /// Write the report into the car folder, replacing any report from a previous swap
+    pub(crate) fn write(&self, car_path: &Path) -> Result<PathBuf, FabricationError> {
+        let report_path = swap_report_path(car_path);
+        let report_data = serde_json::to_vec_pretty(self).map_err(|e| {
+            FabricationError::FailedToWrite(SWAP_REPORT_FILENAME.to_string(), e.to_string())
+        })?;
+        fs::write(&report_path, report_data)?;
+        info!("Wrote swap report to {}", report_path.display());
+        Ok(report_path)
+    }
+}
+
+/// The location of the report describing the last engine swap into the car at `car_path`
+pub fn swap_report_path(car_path: &Path) -> PathBuf {
+    car_path.join(SWAP_REPORT_FILENAME)
+}
+
+/// The sha256 of the mod or crate engine file recorded in the swap report of the car at
+/// `car_path`. None if the car has no report or its engine wasn't loaded from a file
+pub fn swap_report_source_hash(car_path: &Path) -> Option<String> {
+    let report_data = fs::read(swap_report_path(car_path)).ok()?;
+    let report: serde_json::Value = serde_json::from_slice(&report_data).ok()?;
+    report["source"]["sha256"].as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use crate::fabricator::report::{swap_report_path, swap_report_source_hash, SwapReport};
+
+    #[test]
+    fn report_written_to_car_folder() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = temp_dir.path().join("swap_report");
+        fs::create_dir_all(&car_path).unwrap();
+
+        let mut report = SwapReport::new("2024-05-01",
+                                         "Test V8",
+                                         Some(Path::new("/mods/test_v8.eng")),
+                                         Some(String::from("abc123")));
+        report.engine_ini_section_updated("ENGINE_DATA");
+        report.engine_ini_section_updated("ENGINE_DATA");
+        report.limiter_changed(7000, 8200);
+        report.turbo_sections_changed(&[String::from("TURBO_0"), String::from("TURBO_1")],
+                                      &[String::from("TURBO_0")]);
+        report.ui_spec_updated("bhp", "450bhp");
+        let report_path = report.write(&car_path).unwrap();
+        assert_eq!(report_path, swap_report_path(&car_path));
+
+        let json: serde_json::Value = serde_json::from_slice(&fs::read(&report_path).unwrap()).unwrap();
+        assert_eq!(swap_report_source_hash(&car_path).as_deref(), Some("abc123"));
+        fs::remove_dir_all(&car_path).unwrap();
+        assert_eq!(swap_report_source_hash(&car_path), None);
+        assert_eq!(json["source"]["engine_name"], "Test V8");
+        assert_eq!(json["source"]["file"], "test_v8.eng");
+        assert_eq!(json["source"]["sha256"], "abc123");
+        assert_eq!(json["torque_curve"]["source"], "engine_curve");
+        assert_eq!(json["engine_ini_sections"], serde_json::json!(["ENGINE_DATA"]));
+        assert_eq!(json["limiter"]["old"], 7000);
+        assert_eq!(json["limiter"]["new"], 8200);
+        assert_eq!(json["turbo_sections_added"], serde_json::json!([]));
+        assert_eq!(json["turbo_sections_removed"], serde_json::json!(["TURBO_1"]));
+        assert_eq!(json["ui_specs"]["bhp"], "450bhp");
+    }
+}
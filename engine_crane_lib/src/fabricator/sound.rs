@@ -0,0 +1,206 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::{Path, PathBuf};
+use automation::{AspirationType, BlockConfig};
+
+use crate::assetto_corsa::Installation;
+use crate::fabricator::FabricationError;
+use crate::fabricator::assetto_corsa::EngineProvenance;
+
+/// A Kunos car whose engine sound can be used for swapped in engines with a similar layout
+struct SoundDonor {
+    car: &'static str,
+    block_config: BlockConfig,
+    turbo: bool,
+    capacity_cc: u32
+}
+
+const fn donor(car: &'static str, block_config: BlockConfig, turbo: bool, capacity_cc: u32) -> SoundDonor {
+    SoundDonor { car, block_config, turbo, capacity_cc }
+}
+
+const KUNOS_SOUND_DONORS: [SoundDonor; 23] = [
+    donor("ks_lamborghini_aventador_sv", BlockConfig::V12_60, false, 6498),
+    donor("pagani_huayra", BlockConfig::V12_60, true, 5980),
+    donor("ks_lamborghini_huracan_performante", BlockConfig::V10_90, false, 5204),
+    donor("ks_audi_r8_plus", BlockConfig::V10_90, false, 5204),
+    donor("ks_corvette_c7_stingray", BlockConfig::V8_90, false, 6162),
+    donor("ks_ford_mustang_2015", BlockConfig::V8_90, false, 4951),
+    donor("ferrari_458", BlockConfig::V8_90, false, 4497),
+    donor("ks_ferrari_488_gtb", BlockConfig::V8_90, true, 3902),
+    donor("ferrari_f40", BlockConfig::V8_90, true, 2936),
+    donor("lotus_evora_s", BlockConfig::V6_60, false, 3456),
+    donor("ks_nissan_gtr", BlockConfig::V6_60, true, 3799),
+    donor("ks_toyota_supra_mkiv", BlockConfig::I6, true, 2997),
+    donor("ks_nissan_skyline_r34", BlockConfig::I6, true, 2568),
+    donor("bmw_1m", BlockConfig::I6, true, 2979),
+    donor("ks_audi_sport_quattro", BlockConfig::I5, true, 2133),
+    donor("bmw_m3_e30", BlockConfig::I4, false, 2302),
+    donor("ks_mazda_mx5_nd", BlockConfig::I4, false, 1998),
+    donor("ks_toyota_ae86", BlockConfig::I4, false, 1587),
+    donor("ks_alfa_giulietta_qv", BlockConfig::I4, true, 1742),
+    donor("abarth500", BlockConfig::I4, true, 1368),
+    donor("ks_toyota_gt86", BlockConfig::Boxer4, false, 1998),
+    donor("ks_porsche_911_gt3_rs", BlockConfig::Boxer6, false, 3996),
+    donor("ks_porsche_935_78_moby_dick", BlockConfig::Boxer6, true, 3211)
+];
+
+/// The number of cylinders and a character identifying the cylinder arrangement of `block_config`
+fn cylinder_layout(block_config: &BlockConfig) -> Option<(u32, char)> {
+    match block_config {
+        BlockConfig::V16_90 => Some((16, 'V')),
+        BlockConfig::V12_60 => Some((12, 'V')),
+        BlockConfig::V10_90 => Some((10, 'V')),
+        BlockConfig::V8_90 | BlockConfig::V8_60 => Some((8, 'V')),
+        BlockConfig::V6_90 | BlockConfig::V6_60 => Some((6, 'V')),
+        BlockConfig::I6 => Some((6, 'I')),
+        BlockConfig::I5 => Some((5, 'I')),
+        BlockConfig::I4 => Some((4, 'I')),
+        BlockConfig::I3 => Some((3, 'I')),
+        BlockConfig::Boxer6 => Some((6, 'B')),
+        BlockConfig::Boxer4 => Some((4, 'B')),
+        BlockConfig::Unknown(_) => None
+    }
+}
+
+/// How different the sound of a `donor` engine is likely to be from a `target` engine.
+/// Lower is closer. None if the donor shouldn't be used at all
+fn layout_distance(target: &BlockConfig, donor: &BlockConfig) -> Option<u32> {
+    let (target_cylinders, target_arrangement) = cylinder_layout(target)?;
+    let (donor_cylinders, donor_arrangement) = cylinder_layout(donor)?;
+    let cylinder_difference = target_cylinders.abs_diff(donor_cylinders);
+    match (cylinder_difference, target_arrangement == donor_arrangement) {
+        (0, true) if std::mem::discriminant(target) == std::mem::discriminant(donor) => Some(0),
+        (0, true) => Some(1),
+        (0, false) => Some(2),
+        // Allow a larger difference in cylinder count for engines with more cylinders
+        (diff, _) if diff * 3 <= target_cylinders => Some(2 + diff),
+        _ => None
+    }
+}
+
+/// Kunos cars whose engine sound may suit an engine with the given layout, best match first.
+/// Donors are ranked by how close their cylinder layout is, then whether the aspiration matches,
+/// then how close their capacity is
+pub fn rank_sound_donors(block_config: &BlockConfig,
+                         capacity_cc: Option<u32>,
+                         aspiration: &AspirationType) -> Vec<&'static str> {
+    let turbo = matches!(aspiration, AspirationType::Turbo);
+    let mut ranked: Vec<(u32, bool, u32, &'static str)> = KUNOS_SOUND_DONORS.iter().filter_map(|donor| {
+        let distance = layout_distance(block_config, &donor.block_config)?;
+        let capacity_difference = capacity_cc.map_or(0, |cc| cc.abs_diff(donor.capacity_cc));
+        Some((distance, donor.turbo != turbo, capacity_difference, donor.car))
+    }).collect();
+    ranked.sort();
+    ranked.into_iter().map(|(_, _, _, car)| car).collect()
+}
+
+/// The installed cars that the engine sound could be taken from for an engine described by
+/// `provenance`, best match first. Only cars that have a sound bank and whose sound events are
+/// listed in their own GUIDs.txt or the installation sfx data are included
+pub fn suggest_sound_donors(ac_install: &Installation, provenance: &EngineProvenance) -> Vec<PathBuf> {
+    let block_config = match provenance.block_config.as_ref() {
+        None => return Vec::new(),
+        Some(block_config) => block_config
+    };
+    let aspiration = provenance.aspiration_type.clone().unwrap_or(AspirationType::NA);
+    let sfx_data = ac_install.load_sfx_data().ok();
+    let cars_path = ac_install.get_installed_car_path();
+    rank_sound_donors(block_config, provenance.capacity_cc, &aspiration).into_iter().map(|car| {
+        cars_path.join(car)
+    }).filter(|car_path| {
+        let car_name = car_path.file_name().unwrap_or_default().to_string_lossy();
+        let sfx_path = car_path.join("sfx");
+        sfx_path.join(format!("{}.bank", car_name)).is_file() &&
+            (sfx_path.join("GUIDs.txt").is_file() || sfx_data.as_ref().map_or(false, |data| data.contains_car(&car_name)))
+    }).collect()
+}
+
+/// Find the best installed car to take the engine sound from for an engine described by
+/// `provenance`. See [`suggest_sound_donors`]
+pub fn suggest_sound_donor(ac_install: &Installation, provenance: &EngineProvenance) -> Option<PathBuf> {
+    suggest_sound_donors(ac_install, provenance).into_iter().next()
+}
+
+/// Replace the sound of the car at `car_path` with the sound of the car at `donor_path`. Returns
+/// the name of the donor car
+pub fn copy_engine_sound(ac_install: &Installation,
+                         car_path: &Path,
+                         donor_path: &Path) -> Result<String, FabricationError> {
+    let donor_name = donor_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    ac_install.copy_sound_from(donor_path, car_path).map_err(|e| {
+        FabricationError::FailedToWrite(format!("sound from {}", donor_name), e.to_string())
+    })?;
+    Ok(donor_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use automation::{AspirationType, BlockConfig};
+    use crate::assetto_corsa::Installation;
+    use crate::fabricator::assetto_corsa::EngineProvenance;
+    use crate::fabricator::sound::{rank_sound_donors, suggest_sound_donors};
+
+    #[test]
+    fn sound_donors_ranked_by_layout_then_aspiration() {
+        let turbo_i4 = rank_sound_donors(&BlockConfig::I4, Some(1600), &AspirationType::Turbo);
+        assert_eq!(&turbo_i4[..5], &["ks_alfa_giulietta_qv", "abarth500", "ks_toyota_ae86", "ks_mazda_mx5_nd", "bmw_m3_e30"]);
+        assert_eq!(turbo_i4[5], "ks_toyota_gt86");
+
+        let na_v8 = rank_sound_donors(&BlockConfig::V8_60, Some(4500), &AspirationType::NA);
+        assert_eq!(&na_v8[..3], &["ferrari_458", "ks_ford_mustang_2015", "ks_corvette_c7_stingray"]);
+        assert!(!na_v8.contains(&"abarth500"));
+
+        let v16 = rank_sound_donors(&BlockConfig::V16_90, None, &AspirationType::NA);
+        assert_eq!(v16, vec!["ks_lamborghini_aventador_sv", "pagani_huayra"]);
+
+        assert!(rank_sound_donors(&BlockConfig::Unknown("Rotary".to_string()), None, &AspirationType::NA).is_empty());
+    }
+
+    #[test]
+    fn sound_donors_suggested_from_installed_cars() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ac_install = Installation::from_path(temp_dir.path().to_path_buf());
+        let cars_path = ac_install.get_installed_car_path();
+        for (car, has_guids) in [("abarth500", true), ("ks_toyota_ae86", true), ("ks_alfa_giulietta_qv", false)] {
+            let sfx_path = cars_path.join(car).join("sfx");
+            fs::create_dir_all(&sfx_path).unwrap();
+            fs::write(sfx_path.join(format!("{}.bank", car)), b"").unwrap();
+            if has_guids {
+                fs::write(sfx_path.join("GUIDs.txt"), b"").unwrap();
+            }
+        }
+        let provenance = EngineProvenance {
+            name: "Turbo Four".to_string(),
+            capacity_cc: Some(1600),
+            block_config: Some(BlockConfig::I4),
+            aspiration_type: Some(AspirationType::Turbo),
+            ..Default::default()
+        };
+        assert_eq!(suggest_sound_donors(&ac_install, &provenance),
+                   vec![cars_path.join("abarth500"), cars_path.join("ks_toyota_ae86")]);
+
+        let unknown_layout = EngineProvenance { name: "Mystery".to_string(), ..Default::default() };
+        assert!(suggest_sound_donors(&ac_install, &unknown_layout).is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::Path;
+use automation::sandbox::{EngineV1, SandboxFinder};
+
+use crate::assetto_corsa::Car;
+use crate::assetto_corsa::car::data::{CarIniData, Engine};
+use crate::assetto_corsa::car::data::engine::EngineData;
+use crate::assetto_corsa::car::ui::CarUiData;
+use crate::assetto_corsa::car::ui::car_ui_data::SpecValue;
+use crate::assetto_corsa::traits::extract_mandatory_section;
+use crate::fabricator::FabricationError;
+use crate::fabricator::assetto_corsa::EngineParameterCalculator;
+
+const MIN_ESTIMATED_WEIGHT: u32 = 60;
+const MAX_ESTIMATED_WEIGHT: u32 = 350;
+
+/// The largest share of a car's total mass that the engine is estimated to make up
+const MAX_ENGINE_MASS_FRACTION: f64 = 0.25;
+
+/// Typical engine weights for a given engine.ini inertia, from small four cylinder engines up to
+/// large V8s. Used when the car's ui specs don't list its power
+const INERTIA_WEIGHT_TABLE: [(f64, u32); 4] = [
+    (0.10, 100),
+    (0.15, 140),
+    (0.22, 180),
+    (0.30, 230)
+];
+const LARGEST_ENGINE_WEIGHT: u32 = 270;
+
+/// The number at the start of a ui spec value such as "320bhp" or "4.37kg/hp"
+fn leading_number(spec: &str) -> Option<f64> {
+    let number: String = spec.trim().chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    number.parse().ok()
+}
+
+/// Estimate the weight of an engine from its power. Engines roughly gain a third of a kg for
+/// every extra bhp on top of the weight of the block and ancillaries
+fn weight_from_power(bhp: f64) -> u32 {
+    (60.0 + bhp * 0.35).round() as u32
+}
+
+fn weight_from_inertia(inertia: f64) -> u32 {
+    INERTIA_WEIGHT_TABLE.iter()
+        .find(|(max_inertia, _)| inertia <= *max_inertia)
+        .map_or(LARGEST_ENGINE_WEIGHT, |(_, weight)| *weight)
+}
+
+/// Estimate the weight in kg of the engine in the AC car at `car_path`.
+/// The estimate is based on the power listed in the car's ui specs, or the power to weight ratio
+/// and total mass if the power isn't listed. Cars without either fall back to a lookup based on
+/// the engine inertia. Returns None if the car has none of this data
+pub fn estimate_engine_weight(car_path: &Path) -> Result<Option<u32>, FabricationError> {
+    let mut car = Car::load_from_path(car_path).map_err(|err| {
+        FabricationError::FailedToLoad(car_path.display().to_string(), err.to_string())
+    })?;
+    let total_mass = CarIniData::from_car(&mut car).ok().and_then(|ini_data| ini_data.total_mass());
+    let bhp = CarUiData::from_car(&mut car).ok().and_then(|ui_data| {
+        let specs = ui_data.ui_info.specs()?;
+        let mut weight = None;
+        let mut pw_ratio = None;
+        for value in specs.values() {
+            match value {
+                SpecValue::Bhp(bhp) => if let Some(bhp) = leading_number(bhp) {
+                    return Some(bhp);
+                }
+                SpecValue::Weight(val) => weight = leading_number(val),
+                SpecValue::PWRatio(val) => pw_ratio = leading_number(val),
+                _ => {}
+            }
+        }
+        match (weight.or(total_mass.map(f64::from)), pw_ratio) {
+            (Some(weight), Some(pw_ratio)) if pw_ratio > 0.0 => Some(weight / pw_ratio),
+            _ => None
+        }
+    });
+
+    let estimate = match bhp {
+        Some(bhp) => weight_from_power(bhp),
+        None => {
+            let engine = match Engine::from_car(&mut car) {
+                Ok(engine) => engine,
+                Err(_) => return Ok(None)
+            };
+            match extract_mandatory_section::<EngineData>(&engine) {
+                Ok(engine_data) => weight_from_inertia(engine_data.inertia),
+                Err(_) => return Ok(None)
+            }
+        }
+    };
+    let mut estimate = estimate.clamp(MIN_ESTIMATED_WEIGHT, MAX_ESTIMATED_WEIGHT);
+    if let Some(total_mass) = total_mass {
+        estimate = estimate.min((total_mass as f64 * MAX_ENGINE_MASS_FRACTION).round() as u32);
+    }
+    Ok(Some(estimate))
+}
+
+/// The weight in kg of the Automation engine in the BeamNG mod at `mod_path`
+pub fn automation_engine_weight(mod_path: &Path, sandbox_finder: SandboxFinder) -> Result<u32, FabricationError> {
+    Ok(EngineParameterCalculator::from_beam_ng_mod(mod_path, sandbox_finder)?.engine_weight())
+}
+
+/// The weight in kg of the crate engine at `crate_path`
+pub fn crate_engine_weight(crate_path: &Path) -> Result<u32, FabricationError> {
+    Ok(EngineParameterCalculator::from_crate_engine(crate_path)?.engine_weight())
+}
+
+/// The weight in kg of an engine loaded from an Automation sandbox
+pub fn sandbox_engine_weight(engine: EngineV1) -> Result<u32, FabricationError> {
+    Ok(EngineParameterCalculator::from_sandbox_engine(engine)?.engine_weight())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use crate::fabricator::weight::estimate_engine_weight;
+
+    fn create_test_car(root: &Path, name: &str, ui_car: &str) -> PathBuf {
+        let car_path = root.join(name);
+        fs::create_dir_all(car_path.join("data")).unwrap();
+        fs::create_dir_all(car_path.join("ui")).unwrap();
+        fs::write(car_path.join("data").join("car.ini"), "[BASIC]\nTOTALMASS=1200\n").unwrap();
+        fs::write(car_path.join("data").join("engine.ini"), "[ENGINE_DATA]\nALTITUDE_SENSITIVITY=0.1\nINERTIA=0.12\nLIMITER=7000\nLIMITER_HZ=30\nMINIMUM=900\n").unwrap();
+        fs::write(car_path.join("ui").join("ui_car.json"), ui_car).unwrap();
+        car_path
+    }
+
+    #[test]
+    fn engine_weight_estimated_from_specs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let car_path = create_test_car(temp_dir.path(),
+                                       "weight_bhp",
+                                       "{\"specs\": {\"bhp\": \"300bhp\", \"weight\": \"1200kg\"}}");
+        let from_bhp = estimate_engine_weight(&car_path).unwrap();
+        assert_eq!(from_bhp, Some(165));
+
+        let car_path = create_test_car(temp_dir.path(),
+                                       "weight_pwratio",
+                                       "{\"specs\": {\"weight\": \"1000kg\", \"pwratio\": \"5.00kg/hp\"}}");
+        let from_pw_ratio = estimate_engine_weight(&car_path).unwrap();
+        assert_eq!(from_pw_ratio, Some(130));
+
+        let car_path = create_test_car(temp_dir.path(), "weight_inertia", "{\"specs\": {}}");
+        let from_inertia = estimate_engine_weight(&car_path).unwrap();
+        assert_eq!(from_inertia, Some(140));
+    }
+}
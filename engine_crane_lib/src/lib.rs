@@ -0,0 +1,43 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The engine swap pipeline of engine-crane, independent of the UI so that other tools can
+//! fabricate cars
+
+pub use assetto_corsa;
+pub use automation;
+pub use utils;
+
+pub mod data;
+pub mod fabricator;
+pub mod registry;
+
+pub use fabricator::{
+    AdditionalAcCarData,
+    AssettoCorsaCarSettings,
+    EngineParameterCalculator,
+    FabricationError,
+    FabricationWarning,
+    swap_automation_engine_into_ac_car,
+    swap_crate_engine_into_ac_car,
+    swap_sandbox_engine_into_ac_car
+};
+pub use data::{find_crate_engines_in_path, get_default_crate_engine_path, get_local_app_data_path};
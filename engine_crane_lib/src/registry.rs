@@ -0,0 +1,240 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A record of the cars engine-crane has created so they can be listed along with the engine
+//! that was swapped into them
+
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use utils::time::utc_date_string;
+use crate::data::get_local_app_data_path;
+
+const REGISTRY_VERSION: u32 = 1;
+pub const CREATED_CARS_FILENAME: &str = "created_cars.json";
+
+/// Held while the registry file is read, modified and written back so that a swap finishing on
+/// a background thread can't lose the changes made by another
+static REGISTRY_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// The location of the created car registry in the engine-crane local data dir
+pub fn get_default_registry_path() -> PathBuf {
+    get_local_app_data_path().join(CREATED_CARS_FILENAME)
+}
+
+/// Where the engine swapped into a created car came from
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CreatedCarSource {
+    BeamNGMod,
+    CrateEngine,
+    AutomationSandbox
+}
+
+impl CreatedCarSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CreatedCarSource::BeamNGMod => "BeamNG mod",
+            CreatedCarSource::CrateEngine => "Crate engine",
+            CreatedCarSource::AutomationSandbox => "Automation sandbox"
+        }
+    }
+}
+
+impl Display for CreatedCarSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedCarRecord {
+    pub car_path: PathBuf,
+    /// The car that was cloned to create this one
+    pub donor_car: PathBuf,
+    pub source: CreatedCarSource,
+    /// The mod filename, crate engine name or sandbox engine name
+    pub source_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<String>,
+    /// Seconds since the unix epoch
+    pub created_at: u64,
+    pub engine_crane_version: String
+}
+
+impl CreatedCarRecord {
+    pub fn new(car_path: PathBuf,
+               donor_car: PathBuf,
+               source: CreatedCarSource,
+               source_name: String,
+               source_hash: Option<String>) -> CreatedCarRecord {
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        CreatedCarRecord {
+            car_path,
+            donor_car,
+            source,
+            source_name,
+            source_hash,
+            created_at,
+            engine_crane_version: env!("CARGO_PKG_VERSION").to_string()
+        }
+    }
+
+    /// Whether the car folder still exists. Cars that were deleted are kept in the registry until
+    /// they are purged
+    pub fn car_exists(&self) -> bool {
+        self.car_path.is_dir()
+    }
+
+    /// The UTC date the car was created in the form YYYY-MM-DD
+    pub fn created_date(&self) -> String {
+        utc_date_string(UNIX_EPOCH + Duration::from_secs(self.created_at))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatedCarRegistry {
+    version: u32,
+    records: Vec<CreatedCarRecord>
+}
+
+impl Default for CreatedCarRegistry {
+    fn default() -> Self {
+        CreatedCarRegistry { version: REGISTRY_VERSION, records: Vec::new() }
+    }
+}
+
+impl CreatedCarRegistry {
+    /// Load the registry stored at `path`. An empty registry is returned if the file doesn't exist
+    pub fn load(path: &Path) -> Result<CreatedCarRegistry, String> {
+        if !path.exists() {
+            return Ok(CreatedCarRegistry::default());
+        }
+        let data = fs::read_to_string(path).map_err(|e| {
+            format!("Failed to read {}. {}", path.display(), e)
+        })?;
+        let registry: CreatedCarRegistry = serde_json::from_str(&data).map_err(|e| {
+            format!("Failed to parse {}. {}", path.display(), e)
+        })?;
+        if registry.version > REGISTRY_VERSION {
+            return Err(format!("{} has unsupported registry version {}", path.display(), registry.version));
+        }
+        Ok(registry)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!("Failed to create {}. {}", parent.display(), e)
+            })?;
+        }
+        let data = serde_json::to_string_pretty(self).map_err(|e| {
+            format!("Failed to encode created car registry. {}", e)
+        })?;
+        fs::write(path, data).map_err(|e| {
+            format!("Failed to write {}. {}", path.display(), e)
+        })
+    }
+
+    /// The created cars, oldest first
+    pub fn records(&self) -> &[CreatedCarRecord] {
+        &self.records
+    }
+
+    /// Add `record`, replacing any earlier record for a car at the same path
+    pub fn add(&mut self, record: CreatedCarRecord) {
+        self.records.retain(|existing| existing.car_path != record.car_path);
+        self.records.push(record);
+    }
+
+    /// Remove the records of cars that no longer exist on disk. Returns the number removed
+    pub fn remove_missing(&mut self) -> usize {
+        let count = self.records.len();
+        self.records.retain(CreatedCarRecord::car_exists);
+        count - self.records.len()
+    }
+}
+
+/// Add `record` to the registry stored at `path`, creating the registry if needed
+pub fn record_created_car(path: &Path, record: CreatedCarRecord) -> Result<(), String> {
+    let _lock = REGISTRY_FILE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut registry = CreatedCarRegistry::load(path)?;
+    info!("Recording created car {} in {}", record.car_path.display(), path.display());
+    registry.add(record);
+    registry.write(path)
+}
+
+/// Remove the records of cars that no longer exist on disk from the registry stored at `path`.
+/// Returns the number of records removed
+pub fn remove_missing_cars(path: &Path) -> Result<usize, String> {
+    let _lock = REGISTRY_FILE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut registry = CreatedCarRegistry::load(path)?;
+    let removed = registry.remove_missing();
+    if removed > 0 {
+        registry.write(path)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::registry::{CreatedCarRecord, CreatedCarRegistry, CreatedCarSource, record_created_car, remove_missing_cars};
+
+    #[test]
+    fn created_cars_recorded_and_purged() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_path = temp_dir.path().join("created_car_registry");
+        let registry_path = test_path.join("created_cars.json");
+        let kept_car = test_path.join("abarth500_v8");
+        let deleted_car = test_path.join("abarth500_i4");
+        fs::create_dir_all(&kept_car).unwrap();
+        assert!(CreatedCarRegistry::load(&registry_path).unwrap().records().is_empty());
+
+        let record = |car_path: &PathBuf, name: &str| {
+            CreatedCarRecord::new(car_path.clone(),
+                                  PathBuf::from("abarth500"),
+                                  CreatedCarSource::CrateEngine,
+                                  name.to_string(),
+                                  Some(String::from("abc123")))
+        };
+        record_created_car(&registry_path, record(&kept_car, "Old V8")).unwrap();
+        record_created_car(&registry_path, record(&deleted_car, "I4")).unwrap();
+        record_created_car(&registry_path, record(&kept_car, "New V8")).unwrap();
+
+        let registry = CreatedCarRegistry::load(&registry_path).unwrap();
+        let names: Vec<&str> = registry.records().iter().map(|r| r.source_name.as_str()).collect();
+        assert_eq!(names, vec!["I4", "New V8"]);
+        assert_eq!(registry.records()[1].engine_crane_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(registry.records()[1].created_date().len(), 10);
+
+        assert_eq!(remove_missing_cars(&registry_path).unwrap(), 1);
+        assert_eq!(remove_missing_cars(&registry_path).unwrap(), 0);
+        let registry = CreatedCarRegistry::load(&registry_path).unwrap();
+        assert_eq!(registry.records().len(), 1);
+        assert_eq!(registry.records()[0].car_path, kept_car);
+    }
+}
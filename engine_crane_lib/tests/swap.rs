@@ -0,0 +1,185 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Drives a swap through the public API only, as an external tool would
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use engine_crane_lib::{AdditionalAcCarData, AssettoCorsaCarSettings, swap_crate_engine_into_ac_car};
+use engine_crane_lib::fabricator::{has_backup, restore_backup};
+use engine_crane_lib::assetto_corsa::Car;
+use engine_crane_lib::assetto_corsa::car::data::engine::EngineData;
+use engine_crane_lib::assetto_corsa::car::data::Engine;
+use engine_crane_lib::assetto_corsa::traits::{CarDataFile, extract_mandatory_section};
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        match entry.path().is_dir() {
+            true => copy_dir(&entry.path(), &to.join(entry.file_name())),
+            false => { fs::copy(entry.path(), to.join(entry.file_name())).unwrap(); }
+        }
+    }
+}
+
+fn create_test_car(test_path: &Path) -> PathBuf {
+    let fixture_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../assetto_corsa/src/test-data/car-with-turbo-with-ctrls");
+    let car_path = test_path.join("engine_crane_lib_swap_car");
+    copy_dir(&fixture_path, &car_path);
+    car_path
+}
+
+/// Write the bi-turbo test engine into `test_path` as a crate engine
+fn create_test_engine(test_path: &Path) -> PathBuf {
+    let engine_data: automation::sandbox::EngineV1 = serde_json::from_str(
+        include_str!("../../crate_engine/src/test-data/bi_turbo_engine.json")
+    ).unwrap();
+    crate_engine::CrateEngine::from_sandbox_engine(engine_data).unwrap()
+        .write_to_path(test_path.to_path_buf()).unwrap()
+}
+
+#[test]
+fn crate_engine_swapped_into_car() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let test_path = temp_dir.path().join("lib_swap");
+    fs::create_dir_all(&test_path).unwrap();
+    let car_path = create_test_car(&test_path);
+
+    let engine_path = create_test_engine(&test_path);
+
+    let warnings = swap_crate_engine_into_ac_car(&engine_path,
+                                                 &car_path,
+                                                 AssettoCorsaCarSettings::default(),
+                                                 AdditionalAcCarData::default()).unwrap();
+
+    let limiter = {
+        let mut car = Car::load_from_path(&car_path).unwrap();
+        let engine = Engine::from_car(&mut car).unwrap();
+        extract_mandatory_section::<EngineData>(&engine).unwrap().limiter
+    };
+    let has_report = engine_crane_lib::fabricator::swap_report_path(&car_path).is_file();
+    assert!(limiter > 0);
+    assert!(has_report);
+    // The test car has no ai.ini, so its shift points can't be updated
+    assert!(warnings.iter().any(|warning| warning.file() == Some("ai.ini")));
+}
+
+#[test]
+fn limiter_override_used_for_damage_and_coast() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let test_path = temp_dir.path().join("limiter_override");
+    fs::create_dir_all(&test_path).unwrap();
+    let car_path = create_test_car(&test_path);
+
+    let engine_path = create_test_engine(&test_path);
+    swap_crate_engine_into_ac_car(&engine_path,
+                                  &car_path,
+                                  AssettoCorsaCarSettings::default(),
+                                  AdditionalAcCarData::default().with_limiter_override(Some(6100))).unwrap();
+
+    let mut car = Car::load_from_path(&car_path).unwrap();
+    let engine = Engine::from_car(&mut car).unwrap();
+    assert_eq!(extract_mandatory_section::<EngineData>(&engine).unwrap().limiter, 6100);
+    assert_eq!(engine.ini_data().get_value("DAMAGE", "RPM_THRESHOLD"), Some(String::from("6300")));
+    assert_eq!(engine.ini_data().get_value("COAST_REF", "RPM"), Some(String::from("6100")));
+}
+
+#[test]
+fn car_restored_after_failed_swap() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let test_path = temp_dir.path().join("failed_swap");
+    fs::create_dir_all(&test_path).unwrap();
+    let car_path = create_test_car(&test_path);
+    let data_path = car_path.join("data");
+    // Move the boost map of the first controller into a lut file, which the swap replaces with
+    // an inline lut
+    let ctrl_turbo0 = fs::read_to_string(data_path.join("ctrl_turbo0.ini")).unwrap()
+        .replace("LUT=(|0=0|1000=0|1400=0|2100=0.605|2200=0.7|4000=0.7|4500=0.605|8000=0.605|)", "LUT=ctrl_rpms.lut");
+    fs::write(data_path.join("ctrl_turbo0.ini"), ctrl_turbo0).unwrap();
+    fs::write(data_path.join("ctrl_rpms.lut"), "0|0\n2200|0.7\n8000|0.605\n").unwrap();
+    // The swap writes car.ini, the engine files and the first turbo controller before it fails to
+    // write the second turbo controller
+    fs::remove_file(data_path.join("ctrl_turbo1.ini")).unwrap();
+    fs::create_dir(data_path.join("ctrl_turbo1.ini")).unwrap();
+    let read_files = || {
+        ["car.ini", "engine.ini", "power.lut", "ctrl_turbo0.ini", "ctrl_rpms.lut"].map(|name| {
+            fs::read(data_path.join(name)).ok()
+        })
+    };
+    let original_files = read_files();
+
+    let engine_path = create_test_engine(&test_path);
+    let result = swap_crate_engine_into_ac_car(&engine_path,
+                                               &car_path,
+                                               AssettoCorsaCarSettings::default(),
+                                               AdditionalAcCarData::default());
+    assert!(result.is_err());
+    let swapped_files = read_files();
+    for (original, swapped) in original_files.iter().zip(&swapped_files) {
+        assert_ne!(original, swapped);
+    }
+    assert!(has_backup(&car_path));
+
+    restore_backup(&car_path).unwrap();
+    assert_eq!(read_files(), original_files);
+    assert!(!has_backup(&car_path));
+}
+
+#[test]
+fn chosen_sound_donor_recorded_and_reverted() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let test_path = temp_dir.path().join("sound_donor");
+    fs::create_dir_all(&test_path).unwrap();
+    let car_path = create_test_car(&test_path);
+    let sfx_path = car_path.join("sfx");
+    fs::create_dir(&sfx_path).unwrap();
+    fs::write(sfx_path.join("engine_crane_lib_swap_car.bank"), b"original bank").unwrap();
+    fs::write(sfx_path.join("GUIDs.txt"), "{1} bank:/engine_crane_lib_swap_car\n").unwrap();
+    let read_sound_files = || {
+        ["engine_crane_lib_swap_car.bank", "GUIDs.txt"].map(|name| fs::read(sfx_path.join(name)).ok())
+    };
+    let original_sound = read_sound_files();
+
+    let ac_install_path = test_path.join("ac");
+    let donor_path = engine_crane_lib::assetto_corsa::Installation::from_path(ac_install_path.clone())
+        .get_installed_car_path().join("donor_car");
+    fs::create_dir_all(donor_path.join("sfx")).unwrap();
+    fs::write(donor_path.join("sfx").join("donor_car.bank"), b"donor bank").unwrap();
+    fs::write(donor_path.join("sfx").join("GUIDs.txt"), "{2} bank:/donor_car\n").unwrap();
+
+    let engine_path = create_test_engine(&test_path);
+    swap_crate_engine_into_ac_car(&engine_path,
+                                  &car_path,
+                                  AssettoCorsaCarSettings::default(),
+                                  AdditionalAcCarData::default().with_engine_sound_donor(ac_install_path, donor_path)).unwrap();
+
+    assert_eq!(fs::read(sfx_path.join("engine_crane_lib_swap_car.bank")).unwrap(), b"donor bank");
+    assert_eq!(fs::read_to_string(sfx_path.join("GUIDs.txt")).unwrap().trim(), "{2} bank:/engine_crane_lib_swap_car");
+    let report: serde_json::Value = serde_json::from_slice(
+        &fs::read(engine_crane_lib::fabricator::swap_report_path(&car_path)).unwrap()
+    ).unwrap();
+    assert_eq!(report["engine_sound_donor"], "donor_car");
+
+    restore_backup(&car_path).unwrap();
+    assert_eq!(read_sound_files(), original_sound);
+}
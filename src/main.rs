@@ -20,8 +20,6 @@
  */
 #![windows_subsystem = "windows"]
 mod ui;
-mod data;
-mod fabricator;
 mod settings;
 
 
@@ -34,6 +32,7 @@ use tracing::{info};
 use assetto_corsa;
 use automation;
 use utils;
+use engine_crane_lib::{data, fabricator, registry};
 
 
 // -> Result<(), iced::Error>
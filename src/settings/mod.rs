@@ -1,8 +1,11 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use config::{Config, ConfigError};
 use serde::{Deserialize, Serialize};
 use tracing::{error, warn};
+use utils::units::{PowerUnit, TorqueUnit};
 
 macro_rules! default_config_builder_helper {
     ($($config_type:ty),+) => {
@@ -57,11 +60,29 @@ pub struct GlobalSettings {
     beamng_mod_path: String,
     crate_engine_path: String,
     legacy_automation_userdata_path: String,
-    automation_userdata_path: String
+    automation_userdata_path: String,
+    #[serde(default)]
+    crate_engine_view: CrateEngineViewSettings,
+    #[serde(default)]
+    base_car_edit_acknowledged: bool,
+    #[serde(default)]
+    favorites: BTreeSet<String>,
+    #[serde(default)]
+    write_debug_values: bool,
+    #[serde(default)]
+    car_output_path: String,
+    #[serde(default)]
+    link_output_cars: bool,
+    #[serde(default)]
+    display_units: UnitSystem,
+    /// The named profile these settings are also saved to. Empty if no profile is in use
+    #[serde(default)]
+    profile: String
 }
 
 impl GlobalSettings {
     const CONFIG_FILENAME: &'static str = "engine-crane-conf";
+    const PROFILE_DIRNAME: &'static str = "engine-crane-profiles";
 
     pub fn default() -> Self {
         GlobalSettings {
@@ -70,6 +91,14 @@ impl GlobalSettings {
             crate_engine_path: CrateEnginePath::default(),
             legacy_automation_userdata_path: LegacyAutomationUserdataPath::default(),
             automation_userdata_path: AutomationUserdataPath::default(),
+            crate_engine_view: CrateEngineView::default(),
+            base_car_edit_acknowledged: BaseCarEditAcknowledged::default(),
+            favorites: Favorites::default(),
+            write_debug_values: WriteDebugValues::default(),
+            car_output_path: CarOutputPath::default(),
+            link_output_cars: LinkOutputCars::default(),
+            display_units: DisplayUnits::default(),
+            profile: String::new()
         }
     }
 
@@ -104,9 +133,119 @@ impl GlobalSettings {
     }
 
     pub fn write(&self) -> std::io::Result<()> {
-        fs::write(format!("{}.toml", GlobalSettings::CONFIG_FILENAME), toml::to_string(&self).map_err(|_e|{
+        self.write_in(&GlobalSettings::settings_dir())
+    }
+
+    fn write_in(&self, dir: &Path) -> std::io::Result<()> {
+        let settings_data = toml::to_string(&self).map_err(|_e|{
             std::io::Error::new(std::io::ErrorKind::Other, "Failed to encode settings to toml")
-        })?)
+        })?;
+        fs::write(dir.join(format!("{}.toml", GlobalSettings::CONFIG_FILENAME)), &settings_data)?;
+        if !self.profile.is_empty() {
+            fs::create_dir_all(dir.join(GlobalSettings::PROFILE_DIRNAME))?;
+            fs::write(GlobalSettings::profile_path(dir, &self.profile), &settings_data)?;
+        }
+        Ok(())
+    }
+
+    /// Load settings from a file written by [`GlobalSettings::export_to_file`]. The format is
+    /// chosen from the file extension. Paths are imported as-is even if they don't exist on this
+    /// system so that they can be corrected in the settings tab
+    pub fn import_from_file(path: &Path) -> Result<Self, ConfigError> {
+        default_config_builder!()
+            .add_source(config::File::from(path))
+            .build()?
+            .try_deserialize()
+    }
+
+    /// Write the settings to `path` so they can be imported on another system. The settings are
+    /// written as json if `path` has a json extension, otherwise as toml
+    pub fn export_to_file(&self, path: &Path) -> Result<(), String> {
+        let mut exported = self.clone();
+        exported.profile.clear();
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let settings_data = match is_json {
+            true => serde_json::to_string_pretty(&exported).map_err(|e| e.to_string()),
+            false => toml::to_string(&exported).map_err(|e| e.to_string())
+        }.map_err(|e| format!("Failed to encode settings. {}", e))?;
+        fs::write(path, settings_data).map_err(|e| {
+            format!("Failed to write {}. {}", path.display(), e.to_string())
+        })
+    }
+
+    /// The settings file and profiles are kept in the working directory
+    fn settings_dir() -> PathBuf {
+        PathBuf::new()
+    }
+
+    fn profile_path(dir: &Path, name: &str) -> PathBuf {
+        dir.join(GlobalSettings::PROFILE_DIRNAME).join(format!("{}.toml", name))
+    }
+
+    /// The name a profile called `name` is saved with, which has any characters that can't be
+    /// used in a filename removed
+    pub fn profile_name(name: &str) -> String {
+        sanitize_filename::sanitize(name.trim())
+    }
+
+    /// The names of the saved settings profiles in alphabetical order
+    pub fn list_profiles() -> Vec<String> {
+        GlobalSettings::list_profiles_in(&GlobalSettings::settings_dir())
+    }
+
+    fn list_profiles_in(dir: &Path) -> Vec<String> {
+        let mut profiles: Vec<String> = match fs::read_dir(dir.join(GlobalSettings::PROFILE_DIRNAME)) {
+            Err(_) => return Vec::new(),
+            Ok(entries) => entries.filter_map(|entry| {
+                let path = entry.ok()?.path();
+                match path.extension().is_some_and(|ext| ext == "toml") {
+                    true => path.file_stem().map(|stem| stem.to_string_lossy().into_owned()),
+                    false => None
+                }
+            }).collect()
+        };
+        profiles.sort();
+        profiles
+    }
+
+    /// Load the settings saved in the profile called `name`. The loaded settings keep saving to
+    /// the profile when they are written
+    pub fn load_profile(name: &str) -> Result<Self, ConfigError> {
+        GlobalSettings::load_profile_from(&GlobalSettings::settings_dir(), name)
+    }
+
+    fn load_profile_from(dir: &Path, name: &str) -> Result<Self, ConfigError> {
+        let mut settings = GlobalSettings::import_from_file(&GlobalSettings::profile_path(dir, name))?;
+        settings.profile = name.to_string();
+        Ok(settings)
+    }
+
+    /// Save the current settings into a profile called `name` and keep saving to it from now on.
+    /// Any existing profile with the same name is overwritten. Returns the name the profile was
+    /// saved with; see [`GlobalSettings::profile_name`]
+    pub fn save_as_profile(&mut self, name: &str) -> Result<String, String> {
+        self.save_as_profile_in(&GlobalSettings::settings_dir(), name)
+    }
+
+    fn save_as_profile_in(&mut self, dir: &Path, name: &str) -> Result<String, String> {
+        let profile_name = GlobalSettings::profile_name(name);
+        if profile_name.is_empty() {
+            return Err(format!("'{}' can't be used as a profile name", name));
+        }
+        self.profile = profile_name.clone();
+        self.write_in(dir).map_err(|e| format!("Failed to save profile {}. {}", profile_name, e.to_string()))?;
+        Ok(profile_name)
+    }
+
+    pub fn active_profile(&self) -> Option<&str> {
+        match self.profile.is_empty() {
+            true => None,
+            false => Some(&self.profile)
+        }
+    }
+
+    pub fn set_active_profile(&mut self, name: Option<String>) {
+        self.profile = name.unwrap_or_default();
     }
 }
 
@@ -211,3 +350,380 @@ impl Setting for AutomationUserdataPath {
         global_settings.automation_userdata_path = new_val
     }
 }
+
+/// Folder that new car specs are created in. Empty when unset, in which case new specs are
+/// created next to the car they were cloned from
+pub struct CarOutputPath {}
+impl PathSetting for CarOutputPath {}
+
+impl Setting for CarOutputPath {
+    type ValueType = String;
+    fn param_name() -> &'static str { "car_output_path" }
+    fn friendly_name() -> &'static str { "Car output path" }
+    fn default() -> Self::ValueType {
+        String::new()
+    }
+
+    fn get(global_settings: &GlobalSettings) -> &Self::ValueType {
+        &global_settings.car_output_path
+    }
+
+    fn set(global_settings: &mut GlobalSettings, new_val: Self::ValueType) {
+        global_settings.car_output_path = new_val
+    }
+}
+
+/// Whether cars created in the [`CarOutputPath`] are linked into the AC cars folder
+pub struct LinkOutputCars {}
+impl Setting for LinkOutputCars {
+    type ValueType = bool;
+    fn param_name() -> &'static str { "link_output_cars" }
+    fn friendly_name() -> &'static str { "Link output cars into Assetto Corsa" }
+    fn default() -> Self::ValueType {
+        false
+    }
+
+    fn get(global_settings: &GlobalSettings) -> &Self::ValueType {
+        &global_settings.link_output_cars
+    }
+
+    fn set(global_settings: &mut GlobalSettings, new_val: Self::ValueType) {
+        global_settings.link_output_cars = new_val
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+pub enum CrateEngineSortColumn {
+    Name,
+    BuildYear,
+    Capacity,
+    PeakPower,
+    PeakTorque
+}
+
+impl CrateEngineSortColumn {
+    pub const ALL: &'static [CrateEngineSortColumn] = &[
+        CrateEngineSortColumn::Name,
+        CrateEngineSortColumn::BuildYear,
+        CrateEngineSortColumn::Capacity,
+        CrateEngineSortColumn::PeakPower,
+        CrateEngineSortColumn::PeakTorque
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CrateEngineSortColumn::Name => "Name",
+            CrateEngineSortColumn::BuildYear => "Year",
+            CrateEngineSortColumn::Capacity => "Capacity",
+            CrateEngineSortColumn::PeakPower => "Power",
+            CrateEngineSortColumn::PeakTorque => "Torque"
+        }
+    }
+}
+
+impl Default for CrateEngineSortColumn {
+    fn default() -> Self {
+        CrateEngineSortColumn::Name
+    }
+}
+
+impl Display for CrateEngineSortColumn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+pub enum CrateEngineGroupMode {
+    None,
+    Aspiration,
+    BlockConfig,
+    Fuel,
+    Era,
+    Family
+}
+
+impl CrateEngineGroupMode {
+    pub const ALL: &'static [CrateEngineGroupMode] = &[
+        CrateEngineGroupMode::None,
+        CrateEngineGroupMode::Aspiration,
+        CrateEngineGroupMode::BlockConfig,
+        CrateEngineGroupMode::Fuel,
+        CrateEngineGroupMode::Era,
+        CrateEngineGroupMode::Family
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CrateEngineGroupMode::None => "None",
+            CrateEngineGroupMode::Aspiration => "Aspiration",
+            CrateEngineGroupMode::BlockConfig => "Block config",
+            CrateEngineGroupMode::Fuel => "Fuel",
+            CrateEngineGroupMode::Era => "Era",
+            CrateEngineGroupMode::Family => "Family"
+        }
+    }
+}
+
+impl Default for CrateEngineGroupMode {
+    fn default() -> Self {
+        CrateEngineGroupMode::None
+    }
+}
+
+impl Display for CrateEngineGroupMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Restricts the crate engine list to engines built in a given decade. Engines with no recorded
+/// build year are grouped as unknown
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum CrateEngineEra {
+    All,
+    Decade(u16),
+    Unknown
+}
+
+impl CrateEngineEra {
+    pub fn from_build_year(build_year: u16) -> CrateEngineEra {
+        match build_year {
+            0 => CrateEngineEra::Unknown,
+            year => CrateEngineEra::Decade(year - (year % 10))
+        }
+    }
+
+    pub fn contains(&self, build_year: u16) -> bool {
+        match self {
+            CrateEngineEra::All => true,
+            era => *era == CrateEngineEra::from_build_year(build_year)
+        }
+    }
+}
+
+impl Default for CrateEngineEra {
+    fn default() -> Self {
+        CrateEngineEra::All
+    }
+}
+
+impl Display for CrateEngineEra {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CrateEngineEra::All => write!(f, "All"),
+            CrateEngineEra::Decade(decade) => write!(f, "{}s", decade),
+            CrateEngineEra::Unknown => write!(f, "Unknown")
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+pub struct CrateEngineViewSettings {
+    #[serde(default)]
+    pub sort_column: CrateEngineSortColumn,
+    #[serde(default)]
+    pub group_mode: CrateEngineGroupMode,
+    #[serde(default)]
+    pub only_show_usable: bool,
+    #[serde(default)]
+    pub favorites_only: bool,
+    #[serde(default)]
+    pub era: CrateEngineEra
+}
+
+pub struct CrateEngineView {}
+impl Setting for CrateEngineView {
+    type ValueType = CrateEngineViewSettings;
+    fn param_name() -> &'static str { "crate_engine_view" }
+    fn friendly_name() -> &'static str { "Crate engine view" }
+    fn default() -> Self::ValueType {
+        CrateEngineViewSettings::default()
+    }
+
+    fn get(global_settings: &GlobalSettings) -> &Self::ValueType {
+        &global_settings.crate_engine_view
+    }
+
+    fn set(global_settings: &mut GlobalSettings, new_val: Self::ValueType) {
+        global_settings.crate_engine_view = new_val
+    }
+}
+
+pub struct BaseCarEditAcknowledged {}
+impl Setting for BaseCarEditAcknowledged {
+    type ValueType = bool;
+    fn param_name() -> &'static str { "base_car_edit_acknowledged" }
+    fn friendly_name() -> &'static str { "Base car edit warning acknowledged" }
+    fn default() -> Self::ValueType {
+        false
+    }
+
+    fn get(global_settings: &GlobalSettings) -> &Self::ValueType {
+        &global_settings.base_car_edit_acknowledged
+    }
+
+    fn set(global_settings: &mut GlobalSettings, new_val: Self::ValueType) {
+        global_settings.base_car_edit_acknowledged = new_val
+    }
+}
+
+/// Identifiers of engines the user has starred. Crate engines are identified by their crate
+/// filename and BeamNG mods by their full path.
+pub struct Favorites {}
+impl Setting for Favorites {
+    type ValueType = BTreeSet<String>;
+    fn param_name() -> &'static str { "favorites" }
+    fn friendly_name() -> &'static str { "Favorite engines" }
+    fn default() -> Self::ValueType {
+        BTreeSet::new()
+    }
+
+    fn get(global_settings: &GlobalSettings) -> &Self::ValueType {
+        &global_settings.favorites
+    }
+
+    fn set(global_settings: &mut GlobalSettings, new_val: Self::ValueType) {
+        global_settings.favorites = new_val
+    }
+}
+
+/// Turn on the debug logging flags AC supports (e.g. `LOG_FUEL_FLOW` and downshift protection
+/// `DEBUG`) in the files written by swaps and edits
+pub struct WriteDebugValues {}
+impl Setting for WriteDebugValues {
+    type ValueType = bool;
+    fn param_name() -> &'static str { "write_debug_values" }
+    fn friendly_name() -> &'static str { "Write debug-friendly values" }
+    fn default() -> Self::ValueType {
+        false
+    }
+
+    fn get(global_settings: &GlobalSettings) -> &Self::ValueType {
+        &global_settings.write_debug_values
+    }
+
+    fn set(global_settings: &mut GlobalSettings, new_val: Self::ValueType) {
+        global_settings.write_debug_values = new_val
+    }
+}
+
+/// The units that power and torque figures are shown in
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+pub enum UnitSystem {
+    Metric,
+    Imperial
+}
+
+impl UnitSystem {
+    pub const ALL: &'static [UnitSystem] = &[
+        UnitSystem::Metric,
+        UnitSystem::Imperial
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "Metric (kW, Nm)",
+            UnitSystem::Imperial => "Imperial (bhp, lb-ft)"
+        }
+    }
+
+    pub fn power_unit(&self) -> PowerUnit {
+        match self {
+            UnitSystem::Metric => PowerUnit::Kw,
+            UnitSystem::Imperial => PowerUnit::Bhp
+        }
+    }
+
+    pub fn torque_unit(&self) -> TorqueUnit {
+        match self {
+            UnitSystem::Metric => TorqueUnit::Nm,
+            UnitSystem::Imperial => TorqueUnit::LbFt
+        }
+    }
+}
+
+impl Default for UnitSystem {
+    fn default() -> Self {
+        UnitSystem::Metric
+    }
+}
+
+impl Display for UnitSystem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+pub struct DisplayUnits {}
+impl Setting for DisplayUnits {
+    type ValueType = UnitSystem;
+    fn param_name() -> &'static str { "display_units" }
+    fn friendly_name() -> &'static str { "Display units" }
+    fn default() -> Self::ValueType {
+        UnitSystem::default()
+    }
+
+    fn get(global_settings: &GlobalSettings) -> &Self::ValueType {
+        &global_settings.display_units
+    }
+
+    fn set(global_settings: &mut GlobalSettings, new_val: Self::ValueType) {
+        global_settings.display_units = new_val
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::settings::{AcInstallPath, CrateEnginePath, GlobalSettings};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("engine-crane-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn save_and_switch_profiles() {
+        let dir = test_dir("profiles");
+        let mut laptop = GlobalSettings::default();
+        laptop.set::<AcInstallPath>(String::from("/games/laptop/assettocorsa"));
+        assert_eq!(laptop.save_as_profile_in(&dir, " Lap/top "), Ok(String::from("Laptop")));
+        let mut desktop = GlobalSettings::default();
+        desktop.set::<AcInstallPath>(String::from("D:/Steam/steamapps/common/assettocorsa"));
+        desktop.save_as_profile_in(&dir, "Desktop").unwrap();
+        assert_eq!(GlobalSettings::list_profiles_in(&dir), vec!["Desktop", "Laptop"]);
+
+        let switched = GlobalSettings::load_profile_from(&dir, "Laptop").unwrap();
+        assert_eq!(switched.active_profile(), Some("Laptop"));
+        assert_eq!(switched.get::<AcInstallPath>(), "/games/laptop/assettocorsa");
+
+        desktop.set::<AcInstallPath>(String::from("E:/assettocorsa"));
+        desktop.save_as_profile_in(&dir, "Desktop").unwrap();
+        let switched = GlobalSettings::load_profile_from(&dir, "Desktop").unwrap();
+        assert_eq!(switched.get::<AcInstallPath>(), "E:/assettocorsa");
+        assert!(GlobalSettings::default().save_as_profile_in(&dir, " / ").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let dir = test_dir("export");
+        let mut settings = GlobalSettings::default();
+        settings.set::<AcInstallPath>(String::from("/path/that/does/not/exist"));
+        settings.set::<CrateEnginePath>(String::from("/games/crate"));
+        settings.set_active_profile(Some(String::from("Laptop")));
+        for filename in ["settings.toml", "settings.json"] {
+            let path = dir.join(filename);
+            settings.export_to_file(&path).unwrap();
+            let imported = GlobalSettings::import_from_file(&path).unwrap();
+            assert_eq!(imported.get::<AcInstallPath>(), "/path/that/does/not/exist", "{}", filename);
+            assert_eq!(imported.get::<CrateEnginePath>(), "/games/crate", "{}", filename);
+            assert_eq!(imported.active_profile(), None, "{}", filename);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
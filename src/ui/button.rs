@@ -3,7 +3,7 @@ use iced::theme;
 use iced::widget::{svg, Svg};
 use iced_native::svg::Handle;
 use iced::widget::Button;
-use crate::ui::image_data::{ADD_SVG, DELETE_SVG};
+use crate::ui::image_data::{ADD_SVG, DELETE_SVG, STAR_FILL_SVG, STAR_SVG};
 
 pub fn create_add_button<Message>(on_press: Message) -> Button<'static, Message> {
     let img =
@@ -46,3 +46,18 @@ pub fn create_disabled_delete_button<Message>() -> Button<'static, Message> {
         .style(theme::Button::Destructive)
         .padding(2)
 }
+
+pub fn create_favorite_button<Message>(is_favorite: bool, on_press: Message) -> Button<'static, Message> {
+    let img_data = match is_favorite {
+        true => STAR_FILL_SVG,
+        false => STAR_SVG
+    };
+    let img =
+        Svg::new(Handle::from_memory(img_data))
+            .style(theme::Svg::custom_fn(|_| { svg::Appearance{color: Some(Color::from_rgb(0.96, 0.73, 0.0))} }))
+            .content_fit(ContentFit::Fill);
+    iced::widget::button(img)
+        .on_press(on_press)
+        .style(theme::Button::Text)
+        .padding(2)
+}
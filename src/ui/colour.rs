@@ -11,3 +11,11 @@ pub fn warn_yellow() -> Color {
 pub fn fail_red() -> Color {
     Color::from_rgb8(237, 67, 55)
 }
+
+pub fn highlight_orange() -> Color {
+    Color::from_rgb8(214, 110, 0)
+}
+
+pub fn inactive_grey() -> Color {
+    Color::from_rgb(0.5, 0.5, 0.5)
+}
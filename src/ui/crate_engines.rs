@@ -19,25 +19,43 @@
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
 use std::path::PathBuf;
 use iced::{Alignment, Background, Color, Element, Length, Padding, Renderer, Theme, theme};
 use iced::alignment::{Horizontal, Vertical};
 use iced::Length::Fill;
-use iced::widget::{Button, Column, Container, Row, Text};
+use iced::widget::{Button, checkbox, Column, Container, Row, svg, Text, TextInput};
 use iced_aw::style::colors::WHITE;
 use iced_aw::TabLabel;
-use iced_native::widget::{button, container, text, vertical_rule};
+use iced_native::widget::{button, container, pick_list, scrollable, text, vertical_rule, Svg};
+use iced_native::svg::Handle;
+use rfd::FileDialog;
 use tracing::{error, info};
+use automation::sandbox::{SandboxEngineListing, SandboxVersion};
+use utils::units::{PowerUnit, Torque};
 
 use crate::data::{CrateEngine, CrateEngineMetadata, FromBeamNGModOptions};
+use crate_engine::direct_export;
+use crate_engine::metadata::{DiffEntry, QualitySummary};
+use crate_engine::source::BEAM_NG_MOD_SOURCE_ID;
+use crate::settings::{CrateEngineEra, CrateEngineGroupMode, CrateEngineSortColumn, CrateEngineViewSettings, UnitSystem};
 
 use crate::ui::{ListPath, Message, Tab};
+use crate::ui::button::create_favorite_button;
+use crate::ui::colour::{highlight_orange, warn_yellow};
 use crate::ui::data::ApplicationData;
 use crate::ui::elements::{create_drop_down_list, create_text_with_units};
 use crate::ui::elements::modal::Modal;
+use crate::ui::image_data::ALERT_TRIANGLE;
+use crate::ui::import_worker::{BulkImportProgress, BulkImportSummary};
+use crate::ui::plot::create_engine_curve_svg;
 
 
 
+/// The most torque changes to list when comparing two engines
+const TORQUE_DELTA_POINTS: usize = 10;
+
 #[derive(Debug, Clone)]
 pub enum CrateTabMessage {
     EngineSelected(String),
@@ -46,7 +64,18 @@ pub enum CrateTabMessage {
     ImportCancelled,
     ImportConfirmation,
     DeleteCrateEngineRequest,
-    DeleteCancelled
+    DeleteCancelled,
+    RenameTextChanged(String),
+    SandboxVersionSelected(SandboxVersion),
+    SandboxEngineSelected(SandboxEngineListing),
+    SandboxUidChanged(String),
+    ImportFromSandbox,
+    ImportFromJson,
+    ExportDetails,
+    ExportBeamNGMod,
+    CompareEngineSelected(String),
+    CompareCleared,
+    FamilyToggled(String)
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -68,24 +97,145 @@ pub struct CrateEngineTab {
     selected_engine: Option<String>,
     pub(crate) selected_beam_ng_mod: Option<ListPath>,
     modal: ModalState,
-    action_result_string: Option<String>
+    action_result_string: Option<String>,
+    displayed_engines: Vec<String>,
+    available_eras: Vec<CrateEngineEra>,
+    sandbox_version: SandboxVersion,
+    sandbox_engines: Vec<SandboxEngineListing>,
+    sandbox_list_error: Option<String>,
+    sandbox_uid: String,
+    rename_text: String,
+    curve_preview: Option<Result<Handle, String>>,
+    compare_engine: Option<String>,
+    comparison: Option<EngineComparison>,
+    /// Families whose engines are hidden when the list is grouped by family
+    collapsed_families: BTreeSet<String>,
+    /// Progress of the running bulk import from the BeamNG mod folder, if any
+    bulk_import_progress: Option<String>
+}
+
+/// The differences between the selected crate engine and the engine it is being compared with
+struct EngineComparison {
+    fields: Vec<DiffEntry>,
+    /// Pairs of RPM, torque change (Nm) from the selected engine to the compared engine
+    torque_delta: Result<Vec<(f64, f64)>, String>
 }
 
 impl CrateEngineTab {
-    pub(crate) fn new(_app_data: &ApplicationData) -> Self {
-        CrateEngineTab {
+    pub(crate) fn new(app_data: &ApplicationData) -> Self {
+        let mut tab = CrateEngineTab {
             selected_engine: None,
             selected_beam_ng_mod: None,
             modal: ModalState::Hidden,
-            action_result_string: None
+            action_result_string: None,
+            displayed_engines: Vec::new(),
+            available_eras: Vec::new(),
+            sandbox_version: SandboxVersion::default(),
+            sandbox_engines: Vec::new(),
+            sandbox_list_error: None,
+            sandbox_uid: String::new(),
+            rename_text: String::new(),
+            curve_preview: None,
+            compare_engine: None,
+            comparison: None,
+            collapsed_families: BTreeSet::new(),
+            bulk_import_progress: None
+        };
+        tab.refresh_displayed_engines(app_data);
+        tab
+    }
+
+    fn refresh_displayed_engines(&mut self, app_data: &ApplicationData) {
+        self.displayed_engines = app_data.crate_engine_data.get_engines_for_view(
+            &app_data.get_crate_engine_view(),
+            app_data.get_favorites(),
+            &app_data.create_sandbox_finder()
+        );
+        self.available_eras = app_data.crate_engine_data.get_available_eras();
+        if let Some(name) = self.selected_engine.as_ref() {
+            if !self.displayed_engines.contains(name) {
+                self.selected_engine = None;
+                self.curve_preview = None;
+                self.comparison = None;
+            }
+        }
+        if let Some(name) = self.compare_engine.as_ref() {
+            if !self.displayed_engines.contains(name) {
+                self.compare_engine = None;
+                self.comparison = None;
+            }
+        }
+    }
+
+    fn load_crate_engine(app_data: &ApplicationData, name: &str) -> Result<CrateEngine, String> {
+        let path = app_data.crate_engine_data.get_path_for(name).ok_or_else(|| {
+            format!("No crate engine file found for {}", name)
+        })?;
+        let mut file = File::open(path).map_err(|e| {
+            format!("Failed to open {}. {}", path.display(), e.to_string())
+        })?;
+        CrateEngine::deserialize_from(&mut file)
+    }
+
+    /// Load the selected crate engine and render its torque and power curves
+    fn refresh_curve_preview(&mut self, app_data: &ApplicationData) {
+        self.curve_preview = self.selected_engine.as_ref().map(|name| {
+            let crate_eng = Self::load_crate_engine(app_data, name)?;
+            let svg_data = create_engine_curve_svg(
+                &crate_eng.data().torque_curve(),
+                &crate_eng.data().power_curve(),
+                (600, 350)
+            )?;
+            Ok(Handle::from_memory(svg_data))
+        });
+        if let Some(Err(e)) = &self.curve_preview {
+            error!("Couldn't create curve preview. {}", e);
+        }
+    }
+
+    /// Compare the metadata and torque curves of the selected engine and the compared engine
+    fn refresh_comparison(&mut self, app_data: &ApplicationData) {
+        self.comparison = None;
+        let (name, other_name) = match (&self.selected_engine, &self.compare_engine) {
+            (Some(name), Some(other_name)) => (name, other_name),
+            _ => return
+        };
+        let metadata = app_data.crate_engine_data.get_metadata_for(name);
+        let other_metadata = app_data.crate_engine_data.get_metadata_for(other_name);
+        if let (Some(metadata), Some(other_metadata)) = (metadata, other_metadata) {
+            let torque_delta = Self::load_crate_engine(app_data, name).and_then(|crate_eng| {
+                let other_crate_eng = Self::load_crate_engine(app_data, other_name)?;
+                Ok(crate_eng.data().torque_curve_delta(other_crate_eng.data()))
+            });
+            if let Err(e) = &torque_delta {
+                error!("Couldn't compare torque curves of {} and {}. {}", name, other_name, e);
+            }
+            let units = app_data.display_units();
+            self.comparison = Some(EngineComparison { fields: metadata.diff(other_metadata, units.power_unit(), units.torque_unit()), torque_delta });
         }
     }
 
     pub fn update(&mut self, message: CrateTabMessage, app_data: &ApplicationData) {
         match message {
             CrateTabMessage::EngineSelected(name) => {
-                self.selected_engine = Some(name)
+                self.rename_text = name.clone();
+                self.selected_engine = Some(name);
+                self.refresh_curve_preview(app_data);
+                self.refresh_comparison(app_data);
             },
+            CrateTabMessage::CompareEngineSelected(name) => {
+                self.compare_engine = Some(name);
+                self.refresh_comparison(app_data);
+            }
+            CrateTabMessage::CompareCleared => {
+                self.compare_engine = None;
+                self.comparison = None;
+            }
+            CrateTabMessage::FamilyToggled(family) => {
+                if !self.collapsed_families.remove(&family) {
+                    self.collapsed_families.insert(family);
+                }
+            }
             CrateTabMessage::BeamNGModSelected(name) => {
                 self.selected_beam_ng_mod = Some(name)
             }
@@ -105,6 +255,108 @@ impl CrateEngineTab {
             CrateTabMessage::DeleteCancelled => {
                 self.modal = ModalState::Hidden
             }
+            CrateTabMessage::RenameTextChanged(text) => {
+                self.rename_text = text
+            }
+            CrateTabMessage::SandboxVersionSelected(version) => {
+                self.sandbox_version = version;
+                self.refresh_sandbox_engines(app_data);
+            }
+            CrateTabMessage::SandboxEngineSelected(listing) => {
+                self.sandbox_uid = listing.uuid
+            }
+            CrateTabMessage::SandboxUidChanged(uid) => {
+                self.sandbox_uid = uid
+            }
+            CrateTabMessage::ImportFromSandbox => {
+                self.import_sandbox_engine(app_data);
+                self.modal = ModalState::ShowActionResult
+            }
+            CrateTabMessage::ImportFromJson => {
+                if self.import_json_export(app_data) {
+                    self.modal = ModalState::ShowActionResult
+                }
+            }
+            CrateTabMessage::ExportDetails => {
+                if self.export_engine_details(app_data) {
+                    self.modal = ModalState::ShowActionResult
+                }
+            }
+            CrateTabMessage::ExportBeamNGMod => {
+                if self.export_beam_ng_mod(app_data) {
+                    self.modal = ModalState::ShowActionResult
+                }
+            }
+        }
+    }
+
+    /// Write the metadata of the selected engine to a JSON file chosen by the user.
+    /// Returns false if nothing was attempted
+    fn export_engine_details(&mut self, app_data: &ApplicationData) -> bool {
+        let eng_name = match &self.selected_engine {
+            None => return false,
+            Some(name) => name.clone()
+        };
+        let metadata = match app_data.crate_engine_data.get_metadata_for(&eng_name) {
+            None => {
+                self.set_error_status(format!("No metadata found for {}", eng_name));
+                return true;
+            }
+            Some(m) => m
+        };
+        let file_name = format!("{}.json", sanitize_filename::sanitize(metadata.name()));
+        let json_path = match FileDialog::new().add_filter("JSON", &["json"]).set_file_name(&file_name).save_file() {
+            None => return false,
+            Some(path) => path
+        };
+        match metadata.write_json_to_path(&json_path) {
+            Ok(_) => self.set_success_status(format!("Exported details of {} to {}", eng_name, json_path.display())),
+            Err(e) => self.set_error_status(format!("Failed to export details of {}. {}", eng_name, e))
+        }
+        true
+    }
+
+    /// Recreate the BeamNG mod that the selected engine was imported from in a folder chosen
+    /// by the user. Returns false if nothing was attempted
+    fn export_beam_ng_mod(&mut self, app_data: &ApplicationData) -> bool {
+        let eng_name = match &self.selected_engine {
+            None => return false,
+            Some(name) => name.clone()
+        };
+        let crate_path = match app_data.crate_engine_data.get_path_for(&eng_name) {
+            None => {
+                self.set_error_status(format!("No crate engine file found for {}", eng_name));
+                return true;
+            }
+            Some(path) => path
+        };
+        let out_dir = match FileDialog::new().pick_folder() {
+            None => return false,
+            Some(path) => path
+        };
+        let result = File::open(crate_path)
+            .map_err(|e| format!("Failed to open {}. {}", crate_path.display(), e.to_string()))
+            .and_then(|mut file| CrateEngine::deserialize_from(&mut file))
+            .and_then(|crate_eng| crate_eng.export_to_beamng_zip(&out_dir));
+        match result {
+            Ok(zip_path) => self.set_success_status(format!("Exported {} to {}", eng_name, zip_path.display())),
+            Err(e) => self.set_error_status(format!("Failed to export {} as a BeamNG mod. {}", eng_name, e))
+        }
+        true
+    }
+
+    fn refresh_sandbox_engines(&mut self, app_data: &ApplicationData) {
+        let sandbox_lookup = app_data.create_sandbox_finder().find_sandbox_db(self.sandbox_version);
+        match automation::sandbox::list_engines(&sandbox_lookup) {
+            Ok(engines) => {
+                self.sandbox_engines = engines;
+                self.sandbox_list_error = None;
+            }
+            Err(e) => {
+                error!("Failed to list Automation engines. {}", &e);
+                self.sandbox_engines.clear();
+                self.sandbox_list_error = Some(e);
+            }
         }
     }
 
@@ -115,29 +367,34 @@ impl CrateEngineTab {
                     self.modal = ModalState::Hidden
                 }
             }
+            Message::DisplayUnitsSelected(_) => self.refresh_comparison(app_data),
             _ => {}
         }
         if let Some(path) = self.selected_beam_ng_mod.as_ref() {
-            if !app_data.beam_ng_data.available_mods.contains(path) {
+            if !app_data.beam_ng_data.available_mods.iter().any(|m| m.full_path == path.full_path) {
                 self.selected_beam_ng_mod = None;
             }
         }
-        if let Some(name) = self.selected_engine.as_ref() {
-            if !app_data.crate_engine_data.available_engines.contains(name) {
-                self.selected_engine = None;
-            }
-        }
+        self.refresh_displayed_engines(app_data);
     }
 
     pub fn notify_action_success(&mut self, action_event: &Message) {
         match action_event {
             Message::DeleteCrateEngine(eng_name) => {
                 self.selected_engine = None;
+                self.curve_preview = None;
+                self.comparison = None;
                 if self.modal == ModalState::VerifyDelete {
                     self.set_success_status(format!("Successfully deleted {}", eng_name));
                     self.modal = ModalState::ShowActionResult
                 }
             }
+            Message::RenameCrateEngine(old_name, new_name) => {
+                self.selected_engine = Some(new_name.trim().to_string());
+                self.rename_text = new_name.trim().to_string();
+                self.set_success_status(format!("Renamed {} to {}", old_name, new_name.trim()));
+                self.modal = ModalState::ShowActionResult
+            }
             _ => {}
         }
     }
@@ -150,11 +407,17 @@ impl CrateEngineTab {
                     self.modal = ModalState::ShowActionResult;
                 }
             }
+            Message::RenameCrateEngine(old_name, _) => {
+                self.set_error_status(format!("Failed to rename {}. {}", old_name, reason));
+                self.modal = ModalState::ShowActionResult;
+            }
             _ => {}
         }
     }
 
-    fn create_metadata_container(data: Option<&CrateEngineMetadata>) -> Column<'_, Message> {
+    fn create_metadata_container(data: Option<&CrateEngineMetadata>, units: UnitSystem) -> Column<'_, Message> {
+        let power_unit = units.power_unit();
+        let torque_unit = units.torque_unit();
         let mut metadata_container = Column::new().padding(Padding::from([20,0,0,0]));
         match data {
             None => {
@@ -181,18 +444,39 @@ impl CrateEngineTab {
 
                 title_col = title_col.push(Text::new("Power:"));
                 let mut power_container = Row::new().align_items(Alignment::End);
-                power_container = power_container.push(create_text_with_units(format!("{}", m.peak_power()), "kW"));
+                power_container = power_container.push(create_text_with_units(
+                    format!("{:.0}", m.peak_power_figure().value_in(power_unit)), power_unit.symbol()
+                ));
                 power_container = power_container.push(Text::new("@").size(18));
                 power_container = power_container.push(create_text_with_units(format!("{}", m.peak_power_rpm()), "rpm"));
                 value_col = value_col.push(power_container);
 
                 title_col = title_col.push(Text::new("Torque:"));
                 let mut torque_container = Row::new().align_items(Alignment::End);
-                torque_container = torque_container.push(create_text_with_units(format!("{}", m.peak_torque()), "Nm"));
+                torque_container = torque_container.push(create_text_with_units(
+                    format!("{:.0}", m.peak_torque_figure().value_in(torque_unit)), torque_unit.symbol()
+                ));
                 torque_container = torque_container.push(Text::new("@").size(18));
                 torque_container = torque_container.push(create_text_with_units(format!("{}", m.peak_torque_rpm()), "rpm"));
                 value_col = value_col.push(torque_container);
 
+                if let Some(specific_output) = m.specific_output_per_litre(power_unit) {
+                    title_col = title_col.push(Text::new("Specific output:"));
+                    let specific_unit = match power_unit {
+                        PowerUnit::Kw => "kW/L",
+                        PowerUnit::Bhp => "bhp/L",
+                        PowerUnit::Ps => "PS/L"
+                    };
+                    value_col = value_col.push(create_text_with_units(format!("{:.1}", specific_output), specific_unit));
+                }
+
+                if let Some(quality) = m.quality() {
+                    for (area, value) in quality.areas() {
+                        title_col = title_col.push(Text::new(format!("{} quality:", area)));
+                        value_col = value_col.push(Text::new(QualitySummary::format_area(value)));
+                    }
+                }
+
                 let table_holder =
                     Row::with_children(vec![title_col.into(), value_col.into()]).spacing(10).padding([0, 0, 10, 0]);
                 metadata_container = metadata_container.push(table_holder);
@@ -201,16 +485,216 @@ impl CrateEngineTab {
                 let version_string = m.data_version().to_string();
                 metadata_container = metadata_container.push(Text::new(format!("Version: {}", version_string)));
                 metadata_container = metadata_container.push(Text::new(format!("Automation Version: {}", m.automation_version())));
+                if let Some(script_version) = m.exporter_script_version() {
+                    metadata_container = metadata_container.push(Text::new(format!("Exporter Script Version: {}", script_version)));
+                }
+                let mut export_mod_button = Button::new("Export BeamNG mod");
+                if m.get_source().source_id == BEAM_NG_MOD_SOURCE_ID {
+                    export_mod_button = export_mod_button.on_press(Message::CrateTab(CrateTabMessage::ExportBeamNGMod));
+                }
                 metadata_container = metadata_container.push(
-                    Button::new("Delete")
-                        .style(theme::Button::Destructive)
-                        .on_press(Message::CrateTab(CrateTabMessage::DeleteCrateEngineRequest))
+                    Row::new()
+                        .spacing(5)
+                        .push(
+                            Button::new("Export details")
+                                .on_press(Message::CrateTab(CrateTabMessage::ExportDetails))
+                        )
+                        .push(export_mod_button)
+                        .push(
+                            Button::new("Delete")
+                                .style(theme::Button::Destructive)
+                                .on_press(Message::CrateTab(CrateTabMessage::DeleteCrateEngineRequest))
+                        )
                 );
             }
         };
         metadata_container
     }
 
+    fn create_rename_row(&self, eng_name: &str) -> Row<'_, Message> {
+        let new_name = self.rename_text.trim();
+        let mut rename_button = Button::new("Rename");
+        if !new_name.is_empty() && new_name != eng_name {
+            rename_button = rename_button.on_press(
+                Message::RenameCrateEngine(eng_name.to_string(), self.rename_text.clone())
+            );
+        }
+        Row::new()
+            .align_items(Alignment::Center)
+            .spacing(5)
+            .padding(Padding::from([5, 0, 0, 0]))
+            .push(
+                TextInput::new(
+                    "New name",
+                    &self.rename_text,
+                    |val| Message::CrateTab(CrateTabMessage::RenameTextChanged(val))
+                ).width(Length::Units(300))
+            )
+            .push(rename_button)
+    }
+
+    /// List the displayed engines under a header for each Automation family. Selecting a header
+    /// collapses or expands the engines in that family
+    fn create_family_list(&self, app_data: &ApplicationData) -> Column<'_, Message> {
+        // The displayed engines are sorted by family when grouping by family so each family is contiguous
+        let mut families: Vec<(String, Vec<&String>)> = Vec::new();
+        for name in &self.displayed_engines {
+            let family = app_data.crate_engine_data.get_metadata_for(name)
+                .map_or(String::new(), |m| m.family_name().to_string());
+            match families.last_mut() {
+                Some((last_family, engines)) if last_family.to_lowercase() == family.to_lowercase() => engines.push(name),
+                _ => families.push((family, vec![name]))
+            }
+        }
+
+        let mut list = Column::new().spacing(2);
+        for (family, engines) in families {
+            let collapsed = self.collapsed_families.contains(&family);
+            let label = match family.is_empty() {
+                true => "Unknown family",
+                false => family.as_str()
+            };
+            let marker = match collapsed {
+                true => "+",
+                false => "-"
+            };
+            list = list.push(
+                Button::new(Text::new(format!("{} {} ({})", marker, label, engines.len())))
+                    .width(Length::Fill)
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::CrateTab(CrateTabMessage::FamilyToggled(family.clone())))
+            );
+            if collapsed {
+                continue;
+            }
+            for name in engines {
+                let style = match self.selected_engine.as_ref() == Some(name) {
+                    true => theme::Button::Primary,
+                    false => theme::Button::Text
+                };
+                list = list.push(
+                    Button::new(Text::new(name.as_str()))
+                        .width(Length::Fill)
+                        .padding(Padding::from([2, 20]))
+                        .style(style)
+                        .on_press(Message::CrateTab(CrateTabMessage::EngineSelected(name.clone())))
+                );
+            }
+        }
+        Column::new()
+            .align_items(Alignment::Start)
+            .push(Text::new("Crate Engines"))
+            .push(scrollable(list).height(Length::Units(250)))
+    }
+
+    fn create_invalid_engine_list(invalid_engines: &BTreeMap<PathBuf, String>) -> Column<'_, Message> {
+        let mut invalid_list = Column::new()
+            .spacing(5)
+            .padding(Padding::from([10, 0, 0, 0]))
+            .push(Text::new("Crate engines that couldn't be loaded"));
+        for (path, reason) in invalid_engines {
+            let icon = Svg::new(Handle::from_memory(ALERT_TRIANGLE))
+                .style(theme::Svg::custom_fn(|_| {
+                    svg::Appearance{color: Some(warn_yellow())}
+                }))
+                .height(Length::Units(15))
+                .width(Length::Units(15));
+            let filename = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+            invalid_list = invalid_list.push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .push(icon)
+                    .push(Text::new(format!("{}: {}", filename, reason)).size(14))
+            );
+        }
+        invalid_list
+    }
+
+    fn create_curve_preview(&self) -> Column<'_, Message> {
+        let mut preview_container = Column::new()
+            .spacing(5)
+            .padding(Padding::from([20, 0, 0, 0]))
+            .push(Text::new("Torque and power curves"));
+        match &self.curve_preview {
+            None => {}
+            Some(Ok(handle)) => {
+                preview_container = preview_container.push(
+                    Svg::new(handle.clone()).width(Length::Units(600)).height(Length::Units(350))
+                );
+            }
+            Some(Err(e)) => {
+                preview_container = preview_container.push(Text::new(format!("Can't show curves. {}", e)).size(14));
+            }
+        }
+        preview_container
+    }
+
+    fn create_comparison(&self, units: UnitSystem) -> Column<'_, Message> {
+        let mut compare_row = Row::new()
+            .align_items(Alignment::Center)
+            .spacing(5)
+            .push(pick_list(
+                &self.displayed_engines,
+                self.compare_engine.clone(),
+                |val| Message::CrateTab(CrateTabMessage::CompareEngineSelected(val))
+            ).placeholder("Select an engine to compare with"));
+        if self.compare_engine.is_some() {
+            compare_row = compare_row.push(Button::new("Clear").on_press(Message::CrateTab(CrateTabMessage::CompareCleared)));
+        }
+        let mut comparison_container = Column::new()
+            .spacing(5)
+            .padding(Padding::from([20, 0, 0, 0]))
+            .push(Text::new("Compare with"))
+            .push(compare_row);
+        let comparison = match &self.comparison {
+            None => return comparison_container,
+            Some(comparison) => comparison
+        };
+
+        let mut field_col = Column::new();
+        let mut value_col = Column::new();
+        let mut other_value_col = Column::new();
+        for entry in &comparison.fields {
+            let style = |text: Text<'static>| match entry.is_changed() {
+                true => text.style(highlight_orange()),
+                false => text
+            };
+            field_col = field_col.push(style(Text::new(format!("{}:", entry.field))));
+            value_col = value_col.push(style(Text::new(entry.value.clone())));
+            other_value_col = other_value_col.push(style(Text::new(entry.other_value.clone())));
+        }
+        comparison_container = comparison_container.push(
+            Row::with_children(vec![field_col.into(), value_col.into(), other_value_col.into()]).spacing(10)
+        );
+
+        let torque_unit = units.torque_unit();
+        match &comparison.torque_delta {
+            Ok(delta) if delta.is_empty() => {
+                comparison_container = comparison_container.push(Text::new("No torque curve data to compare").size(14));
+            }
+            Ok(delta) => {
+                comparison_container = comparison_container.push(Text::new("Torque change"));
+                let step = (delta.len() / TORQUE_DELTA_POINTS).max(1);
+                let mut rpm_col = Column::new();
+                let mut delta_col = Column::new();
+                for (rpm, torque_change) in delta.iter().step_by(step) {
+                    rpm_col = rpm_col.push(create_text_with_units(format!("{:.0}", rpm), "rpm"));
+                    delta_col = delta_col.push(create_text_with_units(
+                        format!("{:+.0}", Torque::from_nm(*torque_change).value_in(torque_unit)), torque_unit.symbol()
+                    ));
+                }
+                comparison_container = comparison_container.push(
+                    Row::with_children(vec![rpm_col.into(), delta_col.into()]).spacing(10)
+                );
+            }
+            Err(e) => {
+                comparison_container = comparison_container.push(Text::new(format!("Can't compare torque curves. {}", e)).size(14));
+            }
+        }
+        comparison_container
+    }
+
     fn get_modal_content(&self) -> Option<Element<'_, Message>> {
         match &self.modal {
             ModalState::Hidden => None,
@@ -362,6 +846,86 @@ impl CrateEngineTab {
         }
     }
 
+    fn import_sandbox_engine(&mut self, app_data: &ApplicationData) {
+        let uid = self.sandbox_uid.trim().to_string();
+        if uid.is_empty() {
+            self.set_error_status("Cannot import crate engine as no Automation engine selected".to_string());
+            return;
+        }
+        let crate_engine_path = match app_data.get_crate_engine_path() {
+            Some(path) => path,
+            None => {
+                self.set_error_status("Cannot import crate engine as path not set/accessible".to_string());
+                return;
+            }
+        };
+        let sandbox_lookup = app_data.create_sandbox_finder().find_sandbox_db(self.sandbox_version);
+        let engine = match automation::sandbox::load_engine_by_uuid(&uid, sandbox_lookup) {
+            Ok(Some(engine)) => engine,
+            Ok(None) => {
+                self.set_error_status(format!("No engine found in the {} sandbox with uid {}", self.sandbox_version, uid));
+                return;
+            }
+            Err(e) => {
+                self.set_error_status(format!("Failed to load engine {} from the Automation sandbox. {}", uid, e));
+                return;
+            }
+        };
+        match CrateEngine::from_sandbox_engine(engine) {
+            Ok(crate_eng) => {
+                match crate_eng.write_to_path(crate_engine_path) {
+                    Ok(written_path) => {
+                        self.set_success_status(format!("Successfully created crate engine {}", written_path.display()));
+                    }
+                    Err(e) => {
+                        self.set_error_status(format!("Failed to write crate engine. {}", e));
+                    }
+                }
+            }
+            Err(e) => {
+                self.set_error_status(format!("Failed to create crate engine from Automation engine {}. {}", uid, e));
+            }
+        }
+    }
+
+    /// Create a crate engine from an exporter JSON dump chosen by the user.
+    /// Returns false if nothing was attempted
+    fn import_json_export(&mut self, app_data: &ApplicationData) -> bool {
+        let crate_engine_path = match app_data.get_crate_engine_path() {
+            Some(path) => path,
+            None => {
+                self.set_error_status("Cannot import crate engine as path not set/accessible".to_string());
+                return true;
+            }
+        };
+        let json_path = match FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+            None => return false,
+            Some(path) => path
+        };
+        let result = direct_export::Data::from_json_file(&json_path)
+            .and_then(CrateEngine::from_exporter_data)
+            .and_then(|crate_eng| crate_eng.write_to_path(crate_engine_path).map_err(|e| e.to_string()));
+        match result {
+            Ok(written_path) => self.set_success_status(format!("Successfully created crate engine {}", written_path.display())),
+            Err(e) => self.set_error_status(format!("Failed to create crate engine from {}. {}", json_path.display(), e))
+        }
+        true
+    }
+
+    pub fn update_bulk_import_progress(&mut self, progress: BulkImportProgress) {
+        self.bulk_import_progress = Some(progress.to_string());
+    }
+
+    /// Show the summary of a finished bulk import, or why it couldn't be started
+    pub fn finish_bulk_import(&mut self, result: Result<BulkImportSummary, String>) {
+        self.bulk_import_progress = None;
+        match result {
+            Ok(summary) => self.set_success_status(format!("Bulk import finished. {}", summary)),
+            Err(e) => self.set_error_status(e)
+        }
+        self.modal = ModalState::ShowActionResult;
+    }
+
     fn set_success_status(&mut self, error_str: String) {
         info!("{}",&error_str);
         self.action_result_string = Some(error_str);
@@ -389,16 +953,77 @@ impl Tab for CrateEngineTab {
     {
         let mut crate_layout = Column::new()
             .width(Length::FillPortion(2));
-        let list = create_drop_down_list(
-            "Crate Engines",
-            &app_data.crate_engine_data.available_engines,
-            self.selected_engine.clone(),
-            move |new_val| Message::CrateTab(CrateTabMessage::EngineSelected(new_val))
+        let view_settings = app_data.get_crate_engine_view();
+        let list = match view_settings.group_mode {
+            CrateEngineGroupMode::Family => self.create_family_list(app_data),
+            _ => create_drop_down_list(
+                "Crate Engines",
+                &self.displayed_engines,
+                self.selected_engine.clone(),
+                move |new_val| Message::CrateTab(CrateTabMessage::EngineSelected(new_val))
+            )
+        };
+        let mut list_row = Row::new().align_items(Alignment::End).spacing(5).push(list);
+        if let Some(favorite_id) = self.selected_engine.as_ref().and_then(|name| app_data.crate_engine_data.favorite_id_for(name)) {
+            list_row = list_row.push(
+                create_favorite_button(app_data.is_favorite(&favorite_id), Message::ToggleFavorite(favorite_id))
+            );
+        }
+        crate_layout = crate_layout.push(list_row);
+
+        let sort_list = Column::new()
+            .align_items(Alignment::Start)
+            .push(Text::new("Sort by"))
+            .push(pick_list(
+                CrateEngineSortColumn::ALL,
+                Some(view_settings.sort_column),
+                move |val| Message::CrateEngineViewChanged(CrateEngineViewSettings { sort_column: val, ..view_settings })
+            ));
+        let group_list = Column::new()
+            .align_items(Alignment::Start)
+            .push(Text::new("Group by"))
+            .push(pick_list(
+                CrateEngineGroupMode::ALL,
+                Some(view_settings.group_mode),
+                move |val| Message::CrateEngineViewChanged(CrateEngineViewSettings { group_mode: val, ..view_settings })
+            ));
+        let era_list = Column::new()
+            .align_items(Alignment::Start)
+            .push(Text::new("Era"))
+            .push(pick_list(
+                &self.available_eras,
+                Some(view_settings.era),
+                move |val| Message::CrateEngineViewChanged(CrateEngineViewSettings { era: val, ..view_settings })
+            ));
+        let usable_checkbox = checkbox(
+            "Only show usable".to_string(),
+            view_settings.only_show_usable,
+            move |val| Message::CrateEngineViewChanged(CrateEngineViewSettings { only_show_usable: val, ..view_settings })
+        ).spacing(3);
+        let favorites_checkbox = checkbox(
+            "Favorites only".to_string(),
+            view_settings.favorites_only,
+            move |val| Message::CrateEngineViewChanged(CrateEngineViewSettings { favorites_only: val, ..view_settings })
+        ).spacing(3);
+        crate_layout = crate_layout.push(
+            Row::new()
+                .align_items(Alignment::End)
+                .spacing(10)
+                .push(sort_list)
+                .push(group_list)
+                .push(era_list)
+                .push(usable_checkbox)
+                .push(favorites_checkbox)
         );
-        crate_layout = crate_layout.push(list);
         crate_layout = crate_layout.push(Button::new("Refresh").on_press(Message::RefreshCrateEngines));
+        if !app_data.crate_engine_data.invalid_engines.is_empty() {
+            crate_layout = crate_layout.push(Self::create_invalid_engine_list(&app_data.crate_engine_data.invalid_engines));
+        }
         if let Some(name) = &self.selected_engine {
-            crate_layout = crate_layout.push(Self::create_metadata_container(app_data.crate_engine_data.get_metadata_for(name)))
+            crate_layout = crate_layout.push(Self::create_metadata_container(app_data.crate_engine_data.get_metadata_for(name), app_data.display_units()));
+            crate_layout = crate_layout.push(self.create_rename_row(name));
+            crate_layout = crate_layout.push(self.create_curve_preview());
+            crate_layout = crate_layout.push(self.create_comparison(app_data.display_units()));
         }
 
         let mut import_layout = Column::new().width(Length::FillPortion(1)).align_items(Alignment::Center);
@@ -409,12 +1034,74 @@ impl Tab for CrateEngineTab {
             move |new_val| Message::CrateTab(CrateTabMessage::BeamNGModSelected(new_val))
         );
         let mut import_button = Button::new(Text::new("Import")).width(Length::Units(70));
-        if self.selected_beam_ng_mod.is_some() {
-            import_button = import_button.on_press(Message::CrateTab(CrateTabMessage::VerifyImport))
+        let mut import_row = Row::new().align_items(Alignment::Center).spacing(5);
+        if let Some(selected_mod) = &self.selected_beam_ng_mod {
+            import_button = import_button.on_press(Message::CrateTab(CrateTabMessage::VerifyImport));
+            let favorite_id = selected_mod.favorite_id();
+            import_row = import_row.push(import_button).push(
+                create_favorite_button(app_data.is_favorite(&favorite_id), Message::ToggleFavorite(favorite_id))
+            );
+        } else {
+            import_row = import_row.push(import_button);
+        }
+        drop_down_list = drop_down_list.push(import_row);
+        let mut bulk_import_button = Button::new(Text::new("Create from all mods in folder"));
+        match &self.bulk_import_progress {
+            Some(progress) => {
+                drop_down_list = drop_down_list.push(bulk_import_button).push(Text::new(progress).size(14));
+            }
+            None => {
+                if !app_data.beam_ng_data.available_mods.is_empty() {
+                    bulk_import_button = bulk_import_button.on_press(Message::BulkImportRequested);
+                }
+                drop_down_list = drop_down_list.push(bulk_import_button);
+            }
         }
-        drop_down_list = drop_down_list.push(import_button);
         import_layout = import_layout.push(drop_down_list);
 
+        let current_sandbox_engine = self.sandbox_engines.iter().find(|listing| {
+            listing.uuid == self.sandbox_uid
+        }).cloned();
+        let mut sandbox_import = Column::new()
+            .align_items(Alignment::Start)
+            .spacing(5)
+            .padding(Padding::from([20, 0, 0, 0]))
+            .push(Text::new("Import from Automation"))
+            .push(pick_list(
+                &SandboxVersion::ALL[..],
+                Some(self.sandbox_version),
+                |val| Message::CrateTab(CrateTabMessage::SandboxVersionSelected(val))
+            ))
+            .push(pick_list(
+                &self.sandbox_engines,
+                current_sandbox_engine,
+                |val| Message::CrateTab(CrateTabMessage::SandboxEngineSelected(val))
+            ).placeholder("Select a sandbox version to list engines"));
+        if let Some(err) = &self.sandbox_list_error {
+            sandbox_import = sandbox_import.push(Text::new(err).size(14));
+        }
+        let mut sandbox_import_button = Button::new(Text::new("Import")).width(Length::Units(70));
+        if !self.sandbox_uid.trim().is_empty() {
+            sandbox_import_button = sandbox_import_button.on_press(Message::CrateTab(CrateTabMessage::ImportFromSandbox));
+        }
+        sandbox_import = sandbox_import
+            .push(TextInput::new(
+                "Or paste an engine variant UID",
+                &self.sandbox_uid,
+                |val| Message::CrateTab(CrateTabMessage::SandboxUidChanged(val))
+            ))
+            .push(sandbox_import_button)
+            .push(Text::new("Sandbox engines have no BeamNG data so some values will be estimated when swapped").size(14));
+        import_layout = import_layout.push(sandbox_import);
+        import_layout = import_layout.push(
+            Column::new()
+                .align_items(Alignment::Start)
+                .spacing(5)
+                .padding(Padding::from([20, 0, 0, 0]))
+                .push(Text::new("Import from exporter JSON"))
+                .push(Button::new("Import from JSON").on_press(Message::CrateTab(CrateTabMessage::ImportFromJson)))
+        );
+
         let layout = Row::new()
             .push(crate_layout)
             .push(vertical_rule(4))
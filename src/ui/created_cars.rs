@@ -0,0 +1,144 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use iced::{Alignment, Element, Length, theme};
+use iced::widget::{Button, Column, Row, Text};
+use iced_aw::TabLabel;
+use iced_native::widget::scrollable;
+
+use super::{Message, Tab};
+use crate::registry::CreatedCarRecord;
+use crate::ui::ApplicationData;
+use crate::ui::colour::inactive_grey;
+
+/// Lists the cars created by engine swaps along with the engine that was swapped into them
+#[derive(Default)]
+pub struct CreatedCarsTab {
+    status_message: String
+}
+
+impl CreatedCarsTab {
+    pub(crate) fn new() -> Self {
+        CreatedCarsTab::default()
+    }
+
+    pub fn app_data_update(&mut self, _app_data: &ApplicationData, _update_event: &Message) {
+    }
+
+    pub fn notify_action_success(&mut self, action_event: &Message) {
+        if let Message::PurgeMissingCreatedCars = action_event {
+            self.status_message = String::from("Removed deleted cars from the list");
+        }
+    }
+
+    pub fn notify_action_failure(&mut self, action_event: &Message, reason: &str) {
+        if let Message::PurgeMissingCreatedCars = action_event {
+            self.status_message = format!("Failed to remove deleted cars. {}", reason);
+        }
+    }
+}
+
+fn created_car_row(record: &CreatedCarRecord) -> Row<'_, Message> {
+    let car_exists = record.car_exists();
+    let folder_name = |path: &std::path::Path| {
+        path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+    };
+    let mut car_name = folder_name(&record.car_path);
+    if !car_exists {
+        car_name += " (deleted)";
+    }
+    let details = format!("{}: {}. Cloned from {} on {} with Engine Crane v{}",
+                          record.source,
+                          record.source_name,
+                          folder_name(&record.donor_car),
+                          record.created_date(),
+                          record.engine_crane_version);
+    let mut name_text = Text::new(car_name);
+    let mut details_text = Text::new(details).size(14);
+    let mut edit_button = Button::new(Text::new("Edit"));
+    let mut open_button = Button::new(Text::new("Open folder"));
+    match car_exists {
+        true => {
+            edit_button = edit_button.on_press(Message::EditCreatedCar(record.car_path.clone()));
+            open_button = open_button.on_press(Message::OpenCarFolder(record.car_path.clone()));
+        }
+        false => {
+            name_text = name_text.style(inactive_grey());
+            details_text = details_text.style(inactive_grey());
+        }
+    }
+    Row::new()
+        .align_items(Alignment::Center)
+        .spacing(10)
+        .push(Column::new().spacing(2).width(Length::Fill).push(name_text).push(details_text))
+        .push(edit_button)
+        .push(open_button)
+}
+
+impl Tab for CreatedCarsTab {
+    type Message = Message;
+
+    fn title(&self) -> String {
+        String::from("My Cars")
+    }
+
+    fn tab_label(&self) -> TabLabel {
+        TabLabel::Text(self.title())
+    }
+
+    fn content<'a, 'b>(
+        &'a self,
+        app_data: &'b ApplicationData
+    ) -> Element<'_, Self::Message>
+    where 'b: 'a
+    {
+        let records = app_data.created_cars.records();
+        let missing_count = records.iter().filter(|record| !record.car_exists()).count();
+        let mut header_row = Row::new()
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .push(Text::new(format!("{} cars created with Engine Crane", records.len())));
+        if missing_count > 0 {
+            header_row = header_row.push(
+                Button::new(Text::new(format!("Remove {} deleted", missing_count)))
+                    .style(theme::Button::Destructive)
+                    .on_press(Message::PurgeMissingCreatedCars)
+            );
+        }
+
+        let mut car_list = Column::new().spacing(10).padding([0, 15, 0, 0]);
+        if records.is_empty() {
+            car_list = car_list.push(Text::new("Cars created by engine swaps will be listed here").size(14));
+        }
+        for record in records.iter().rev() {
+            car_list = car_list.push(created_car_row(record));
+        }
+
+        let mut layout = Column::new()
+            .align_items(Alignment::Start)
+            .spacing(15)
+            .push(header_row);
+        if !self.status_message.is_empty() {
+            layout = layout.push(Text::new(self.status_message.as_str()).size(14));
+        }
+        layout.push(scrollable(car_list).height(Length::Fill)).into()
+    }
+}
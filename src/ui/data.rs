@@ -19,15 +19,22 @@
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::{Display, Formatter};
 use std::fs::create_dir;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tracing::{error, info, Level, span, warn};
-use crate::data::{CrateEngineMetadata, find_crate_engines_in_path, get_default_crate_engine_path, get_local_app_data_path};
-use crate::settings::{AcInstallPath, AutomationUserdataPath, BeamNGModPath, CrateEnginePath, LegacyAutomationUserdataPath};
-use crate::ui::{GlobalSettings, ListPath, settings};
+use assetto_corsa::car::ui::car_ui_data::UiInfo;
+use automation::car::CarFile;
+use automation::sandbox::SandboxFinder;
+use beam_ng::ModInfo;
+use crate::data::{CrateEngineMetadata, CrateEngineScan, find_crate_engines_in_path, get_default_crate_engine_path, get_local_app_data_path};
+use crate_engine::source::SANDBOX_SOURCE_ID;
+use crate::registry::{CreatedCarRegistry, get_default_registry_path, remove_missing_cars};
+use crate::settings::{AcInstallPath, AutomationUserdataPath, BaseCarEditAcknowledged, BeamNGModPath, CarOutputPath, CrateEnginePath, CrateEngineEra, CrateEngineGroupMode, CrateEngineSortColumn, CrateEngineView, CrateEngineViewSettings, DisplayUnits, Favorites, LegacyAutomationUserdataPath, LinkOutputCars, UnitSystem, WriteDebugValues};
+use crate::ui::{GlobalSettings, ListPath, matches_filter, settings};
 use crate::ui::settings::Setting;
 use crate::utils::filesystem;
 
@@ -56,12 +63,21 @@ fn create_local_data_dirs_if_missing() {
     }
 }
 
+fn load_created_cars() -> CreatedCarRegistry {
+    CreatedCarRegistry::load(&get_default_registry_path()).unwrap_or_else(|e| {
+        warn!("Failed to load created cars. {}", e);
+        CreatedCarRegistry::default()
+    })
+}
+
 pub struct ApplicationData {
     pub(crate) settings: GlobalSettings,
     pub(crate) assetto_corsa_data: AssettoCorsaData,
     pub(crate) beam_ng_data: BeamNGData,
     pub(crate) crate_engine_data: CrateEngineData,
-    pub(crate) permissions: HashMap<&'static str, (PathState, PathState)>
+    pub(crate) permissions: HashMap<&'static str, (PathState, PathState)>,
+    pub(crate) steam_install: steam::InstallState,
+    pub(crate) created_cars: CreatedCarRegistry
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -108,24 +124,45 @@ impl ApplicationData {
             warn!("Failed to load settings. {}", e.to_string());
             GlobalSettings::default()
         });
+        let steam_install = steam::detect_install();
+        match steam_install.is_detected() {
+            true => info!("{}", steam_install),
+            false => warn!("{}. Game paths will need to be set manually in settings", steam_install)
+        }
         let mut data = ApplicationData {
             settings,
             assetto_corsa_data: AssettoCorsaData::new(),
             beam_ng_data: BeamNGData::new(),
             crate_engine_data: CrateEngineData::new(),
-            permissions: HashMap::new()
+            permissions: HashMap::new(),
+            steam_install,
+            created_cars: load_created_cars()
         };
-        data.set_path_permission_data::<AcInstallPath>();
-        data.assetto_corsa_data.property_update(&data.settings);
-        data.set_path_permission_data::<BeamNGModPath>();
-        data.beam_ng_data.property_update(&data.settings);
-        data.set_path_permission_data::<CrateEnginePath>();
-        data.crate_engine_data.property_update(&data.settings);
-        data.set_path_permission_data::<LegacyAutomationUserdataPath>();
-        data.set_path_permission_data::<AutomationUserdataPath>();
+        data.refresh_path_data();
         data
     }
 
+    /// Check each configured path and reload the cars, mods and crate engines found in them
+    fn refresh_path_data(&mut self) {
+        self.set_path_permission_data::<AcInstallPath>();
+        self.assetto_corsa_data.property_update(&self.settings);
+        self.set_path_permission_data::<BeamNGModPath>();
+        self.beam_ng_data.property_update(&self.settings);
+        self.set_path_permission_data::<CrateEnginePath>();
+        self.crate_engine_data.property_update(&self.settings);
+        self.set_path_permission_data::<LegacyAutomationUserdataPath>();
+        self.set_path_permission_data::<AutomationUserdataPath>();
+        self.set_path_permission_data::<CarOutputPath>();
+        self.refresh_newest_sandbox_version();
+    }
+
+    /// Replace every setting, e.g. with an imported settings file or a profile, and reload
+    /// everything that depends on the configured paths
+    pub(crate) fn replace_settings(&mut self, settings: GlobalSettings) {
+        self.settings = settings;
+        self.refresh_path_data();
+    }
+
     pub(crate) fn revert_to_default(&mut self, setting: settings::Setting) {
         match setting {
             Setting::AcPath => {
@@ -142,9 +179,14 @@ impl ApplicationData {
             }
             Setting::LegacyAutomationUserdataPath => {
                 self.revert_to_default_path::<LegacyAutomationUserdataPath>();
+                self.refresh_newest_sandbox_version();
             }
             Setting::AutomationUserdataPath => {
                 self.revert_to_default_path::<AutomationUserdataPath>();
+                self.refresh_newest_sandbox_version();
+            }
+            Setting::CarOutputPath => {
+                self.revert_to_default_path::<CarOutputPath>();
             }
         }
     }
@@ -195,6 +237,7 @@ impl ApplicationData {
 
     pub(crate) fn update_legacy_automation_userdata_path(&mut self, new_path: PathBuf) {
         self.set_path::<LegacyAutomationUserdataPath>(new_path.to_string_lossy().into_owned());
+        self.refresh_newest_sandbox_version();
     }
 
     pub(crate) fn get_automation_userdata_path(&self) -> Option<PathBuf> {
@@ -203,6 +246,112 @@ impl ApplicationData {
 
     pub(crate) fn update_automation_userdata_path(&mut self, new_path: PathBuf) {
         self.set_path::<AutomationUserdataPath>(new_path.to_string_lossy().into_owned());
+        self.refresh_newest_sandbox_version();
+    }
+
+    pub(crate) fn get_car_output_path(&self) -> Option<PathBuf> {
+        self.get_path::<CarOutputPath>()
+    }
+
+    /// The warning to show if new cars will be created in an AC cars folder that can't be
+    /// written to. Cars are created in the car output folder instead when one is set
+    pub(crate) fn cars_folder_warning(&self) -> Option<&str> {
+        match self.get_car_output_path() {
+            Some(_) => None,
+            None => self.assetto_corsa_data.cars_folder_warning.as_deref()
+        }
+    }
+
+    /// Whether a car output path has been set, regardless of whether it currently exists
+    pub(crate) fn is_car_output_path_set(&self) -> bool {
+        !self.settings.get::<CarOutputPath>().is_empty()
+    }
+
+    pub(crate) fn update_car_output_path(&mut self, new_path: PathBuf) {
+        self.set_path::<CarOutputPath>(new_path.to_string_lossy().into_owned());
+    }
+
+    pub(crate) fn link_output_cars(&self) -> bool {
+        *self.settings.get::<LinkOutputCars>()
+    }
+
+    pub(crate) fn update_link_output_cars(&mut self, enabled: bool) {
+        self.settings.set::<LinkOutputCars>(enabled);
+    }
+
+    pub(crate) fn get_crate_engine_view(&self) -> CrateEngineViewSettings {
+        *self.settings.get::<CrateEngineView>()
+    }
+
+    pub(crate) fn update_crate_engine_view(&mut self, view_settings: CrateEngineViewSettings) {
+        self.settings.set::<CrateEngineView>(view_settings);
+    }
+
+    pub(crate) fn is_base_car_edit_acknowledged(&self) -> bool {
+        *self.settings.get::<BaseCarEditAcknowledged>()
+    }
+
+    pub(crate) fn update_base_car_edit_acknowledged(&mut self, acknowledged: bool) {
+        self.settings.set::<BaseCarEditAcknowledged>(acknowledged);
+    }
+
+    pub(crate) fn write_debug_values(&self) -> bool {
+        *self.settings.get::<WriteDebugValues>()
+    }
+
+    pub(crate) fn update_write_debug_values(&mut self, enabled: bool) {
+        self.settings.set::<WriteDebugValues>(enabled);
+    }
+
+    pub(crate) fn display_units(&self) -> UnitSystem {
+        *self.settings.get::<DisplayUnits>()
+    }
+
+    pub(crate) fn update_display_units(&mut self, units: UnitSystem) {
+        self.settings.set::<DisplayUnits>(units);
+    }
+
+    pub(crate) fn get_favorites(&self) -> &BTreeSet<String> {
+        self.settings.get::<Favorites>()
+    }
+
+    pub(crate) fn is_favorite(&self, favorite_id: &str) -> bool {
+        self.get_favorites().contains(favorite_id)
+    }
+
+    pub(crate) fn toggle_favorite(&mut self, favorite_id: String) {
+        let mut favorites = self.get_favorites().clone();
+        if !favorites.remove(&favorite_id) {
+            favorites.insert(favorite_id);
+        }
+        self.settings.set::<Favorites>(favorites);
+    }
+
+    /// A prompt to set paths manually if Steam wasn't found in its default location
+    pub(crate) fn steam_not_detected_hint(&self) -> Option<String> {
+        match &self.steam_install {
+            steam::InstallState::Detected(_) => None,
+            steam::InstallState::NotDetected(path) => Some(format!(
+                "Steam wasn't detected at {}. If your games aren't installed through Steam set their paths manually in Settings",
+                path.display()
+            ))
+        }
+    }
+
+    pub(crate) fn create_sandbox_finder(&self) -> SandboxFinder {
+        let mut sandbox_finder = SandboxFinder::default();
+        if let Some(path) = self.get_legacy_automation_userdata_path() {
+            sandbox_finder.set_legacy_userdata_path(path)
+        }
+        if let Some(path) = self.get_automation_userdata_path() {
+            sandbox_finder.set_userdata_path(path)
+        }
+        sandbox_finder
+    }
+
+    fn refresh_newest_sandbox_version(&mut self) {
+        let sandbox_finder = self.create_sandbox_finder();
+        self.beam_ng_data.update_newest_sandbox_version(sandbox_finder.newest_game_version());
     }
 
     pub(crate) fn refresh_available_cars(&mut self) {
@@ -213,6 +362,18 @@ impl ApplicationData {
         self.crate_engine_data.refresh_available_engines(self.get_path::<CrateEnginePath>())
     }
 
+    pub(crate) fn refresh_created_cars(&mut self) {
+        self.created_cars = load_created_cars();
+    }
+
+    /// Remove the cars that no longer exist on disk from the created car registry. Returns the
+    /// number of records removed
+    pub(crate) fn purge_missing_created_cars(&mut self) -> Result<usize, String> {
+        let removed = remove_missing_cars(&get_default_registry_path())?;
+        self.refresh_created_cars();
+        Ok(removed)
+    }
+
     pub fn get_permission_data<T: crate::settings::PathSetting>(&self) -> (PathState, PathState) {
         match self.permissions.get(T::param_name()) {
             None => (PathState::Invalid, PathState::Invalid),
@@ -276,12 +437,17 @@ impl ApplicationData {
 
 pub struct AssettoCorsaData {
     pub(crate) available_cars: Vec<ListPath>,
+    cars_folder_warning: Option<String>,
+    /// The AC install path whose cars folder was last checked for write access
+    cars_folder_checked_path: Option<PathBuf>
 }
 
 impl AssettoCorsaData {
     fn new() -> AssettoCorsaData {
         AssettoCorsaData {
-            available_cars: Vec::new()
+            available_cars: Vec::new(),
+            cars_folder_warning: None,
+            cars_folder_checked_path: None
         }
     }
 
@@ -297,17 +463,43 @@ impl AssettoCorsaData {
 
     pub fn refresh_available_cars(&mut self, ac_install_path: Option<PathBuf>) {
         self.available_cars.clear();
-        match ac_install_path {
+        match &ac_install_path {
             None => warn!("No AC install path set when refreshing car list"),
             Some(path) => {
                 if path.is_dir() {
-                    self.available_cars = Self::load_available_cars(&path);
+                    self.available_cars = Self::load_available_cars(path);
                     self.available_cars.sort();
                 } else {
                     warn!("Invalid AC install path set when refreshing car list. {}", path.display())
                 }
             }
         }
+        self.update_cars_folder_warning(ac_install_path);
+    }
+
+    /// Check the cars folder of `ac_install_path` can be written to. The check is only repeated
+    /// when the install path changes
+    fn update_cars_folder_warning(&mut self, ac_install_path: Option<PathBuf>) {
+        let ac_install_path = ac_install_path.filter(|path| path.is_dir());
+        if self.cars_folder_checked_path == ac_install_path {
+            return;
+        }
+        self.cars_folder_warning = ac_install_path.as_deref().and_then(Self::check_cars_folder_writable);
+        self.cars_folder_checked_path = ac_install_path;
+    }
+
+    fn check_cars_folder_writable(ac_install_path: &Path) -> Option<String> {
+        match assetto_corsa::Installation::from_path(ac_install_path.to_path_buf()).check_cars_folder_writable() {
+            Ok(_) => None,
+            Err(e) if e.kind() == assetto_corsa::error::ErrorKind::PermissionDenied => {
+                warn!("{}", e);
+                Some(e.to_string())
+            }
+            Err(e) => {
+                warn!("Couldn't check whether the AC cars folder is writable. {}", e);
+                None
+            }
+        }
     }
 
     fn load_available_cars(ac_install_path: &PathBuf) -> Vec<ListPath> {
@@ -316,7 +508,11 @@ impl AssettoCorsaData {
         return match assetto_corsa::Installation::from_path(ac_install_path.clone()).get_list_of_installed_cars() {
             Ok(vec) => {
                 info!("Found {} cars", vec.len());
-                ListPath::convert_path_vec(vec)
+                vec.into_iter().map(|path| {
+                    let screen_name = UiInfo::from_car_path(&path).ok()
+                        .and_then(|ui_info| ui_info.name().map(str::to_string));
+                    ListPath::from_path(path).with_search_text(screen_name)
+                }).collect()
             }
             Err(err) => {
                 error!("{}", err.to_string());
@@ -326,14 +522,48 @@ impl AssettoCorsaData {
     }
 }
 
+/// The details read from a mod when listing it. These are kept until the mod is modified so that
+/// each archive only has to be opened again when it changes
+struct ModListing {
+    modified: Option<SystemTime>,
+    name: Option<String>,
+    game_version: Option<u64>
+}
+
+impl ModListing {
+    fn read(mod_path: &Path, modified: Option<SystemTime>) -> ModListing {
+        match ModInfo::from_path(mod_path) {
+            Ok(mod_info) => ModListing {
+                modified,
+                name: mod_info.name().map(str::to_string),
+                game_version: BeamNGData::read_game_version(&mod_info)
+            },
+            Err(e) => {
+                warn!("Couldn't read mod info from {}. {}", mod_path.display(), e);
+                ModListing { modified, name: None, game_version: None }
+            }
+        }
+    }
+}
+
 pub struct BeamNGData {
-    pub(crate) available_mods: Vec<ListPath>
+    pub(crate) available_mods: Vec<ListPath>,
+    pub(crate) mod_path_hint: Option<String>,
+    /// The Automation GameVersion that each mod's .car file was exported from
+    mod_game_versions: BTreeMap<PathBuf, u64>,
+    /// The newest GameVersion held in the sandbox dbs that can be found
+    newest_sandbox_version: Option<u64>,
+    mod_listings: BTreeMap<PathBuf, ModListing>
 }
 
 impl BeamNGData {
     fn new() -> BeamNGData {
         BeamNGData {
-            available_mods: Vec::new()
+            available_mods: Vec::new(),
+            mod_path_hint: None,
+            mod_game_versions: BTreeMap::new(),
+            newest_sandbox_version: None,
+            mod_listings: BTreeMap::new()
         }
     }
 
@@ -349,28 +579,87 @@ impl BeamNGData {
         } else {
             info!("Update to GlobalSettings contains no BeamNG data path");
             self.available_mods.clear();
+            self.mod_game_versions.clear();
+            self.mod_listings.clear();
+            self.mod_path_hint = None;
+        }
+    }
+
+    fn update_newest_sandbox_version(&mut self, newest_sandbox_version: Option<u64>) {
+        match newest_sandbox_version {
+            Some(version) => info!("Newest sandbox db game version is {}", version),
+            None => warn!("No sandbox db game version found")
+        }
+        self.newest_sandbox_version = newest_sandbox_version;
+    }
+
+    /// Why the engine in the mod at `mod_path` won't be found, if it was exported from a newer
+    /// version of Automation than any of the sandbox dbs hold
+    pub(crate) fn version_mismatch(&self, mod_path: &Path) -> Option<String> {
+        let mod_version = *self.mod_game_versions.get(mod_path)?;
+        let newest_version = self.newest_sandbox_version?;
+        match mod_version > newest_version {
+            true => Some(format!("Exported from Automation {}, sandbox DB only has {}", mod_version, newest_version)),
+            false => None
         }
     }
 
     fn refresh_available_mods(&mut self, beam_install_path: &PathBuf) {
         self.available_mods.clear();
+        self.mod_game_versions.clear();
+        self.mod_path_hint = None;
         if beam_install_path.is_dir() {
-            self.available_mods = Self::load_available_mods(beam_install_path);
+            self.load_available_mods(beam_install_path);
             self.available_mods.sort();
+            if self.available_mods.is_empty() {
+                self.mod_path_hint = beam_ng::check_mod_path(beam_install_path);
+                if let Some(hint) = &self.mod_path_hint {
+                    warn!("{}", hint);
+                }
+            }
         }
     }
 
-    fn load_available_mods(beamng_mod_path: &PathBuf) -> Vec<ListPath> {
+    /// List the mods in `beamng_mod_path`. Only mods that are new or have been modified since the
+    /// last refresh are opened; the rest reuse the details read previously
+    fn load_available_mods(&mut self, beamng_mod_path: &PathBuf) {
         let span = span!(Level::INFO, "Loading beamNG mods");
         let _enter = span.enter();
-        let mods = ListPath::convert_path_vec(beam_ng::get_mod_list_in(beamng_mod_path));
-        info!("Found {} mods", mods.len());
-        mods
+        let mut previous_listings = std::mem::take(&mut self.mod_listings);
+        let mut reopened_count = 0;
+        for path in beam_ng::get_mod_list_in(beamng_mod_path) {
+            let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+            let listing = match previous_listings.remove(&path) {
+                Some(listing) if modified.is_some() && listing.modified == modified => listing,
+                _ => {
+                    reopened_count += 1;
+                    ModListing::read(&path, modified)
+                }
+            };
+            if let Some(version) = listing.game_version {
+                self.mod_game_versions.insert(path.clone(), version);
+            }
+            self.available_mods.push(ListPath::relative_to(path.clone(), beamng_mod_path).with_search_text(listing.name.clone()));
+            self.mod_listings.insert(path, listing);
+        }
+        info!("Found {} mods, {} read from disk", self.available_mods.len(), reopened_count);
+    }
+
+    fn read_game_version(mod_info: &ModInfo) -> Option<u64> {
+        match mod_info.read_car_file_data() {
+            Ok(car_data) => car_data.and_then(|data| CarFile::peek_game_version(&data)).map(|version| version as u64),
+            Err(e) => {
+                warn!("Couldn't read the .car file from {}. {}", mod_info.path().display(), e);
+                None
+            }
+        }
     }
 }
 
 pub struct CrateEngineData {
     pub(crate) available_engines: Vec<String>,
+    /// Crate engine files that failed to load, with the reason why
+    pub(crate) invalid_engines: BTreeMap<PathBuf, String>,
     metadata: BTreeMap<String, CrateEngineMetadata>,
     locations: BTreeMap<String, PathBuf>
 }
@@ -379,6 +668,7 @@ impl CrateEngineData {
     fn new() -> CrateEngineData {
         CrateEngineData {
             available_engines: Vec::new(),
+            invalid_engines: BTreeMap::new(),
             metadata: BTreeMap::new(),
             locations: BTreeMap::new()
         }
@@ -398,6 +688,11 @@ impl CrateEngineData {
         self.locations.get(name)
     }
 
+    /// The Automation data hashes of the loaded crate engines, used to spot duplicate imports
+    pub(crate) fn automation_data_hashes(&self) -> BTreeSet<[u8; 32]> {
+        self.metadata.values().filter_map(|m| m.get_source().automation_data_hash()).collect()
+    }
+
     pub fn get_metadata_for(&self, name: &str) -> Option<&CrateEngineMetadata> {
         self.metadata.get(name)
     }
@@ -406,8 +701,91 @@ impl CrateEngineData {
         self.locations.get(name)
     }
 
+    /// Returns the identifier used to mark the named engine as a favorite. This is the crate
+    /// filename so that it survives the engine being renamed in the list
+    pub fn favorite_id_for(&self, name: &str) -> Option<String> {
+        self.locations.get(name)
+            .and_then(|path| path.file_name())
+            .map(|filename| filename.to_string_lossy().into_owned())
+    }
+
+    /// Whether the named engine, or the Automation family or variant it was created from,
+    /// contains `filter`, ignoring case
+    pub fn matches_filter(&self, name: &str, filter: &str) -> bool {
+        matches_filter(name, filter) || self.metadata.get(name).map_or(false, |m| {
+            matches_filter(m.family_name(), filter) || matches_filter(m.variant_name(), filter)
+        })
+    }
+
+    /// Returns true if the Automation sandbox db that matches the version the engine was
+    /// created with can be found. Engines imported directly from the sandbox carry all of the
+    /// data they need so are always usable
+    pub fn is_engine_usable(&self, name: &str, sandbox_finder: &SandboxFinder) -> bool {
+        match self.metadata.get(name) {
+            None => false,
+            Some(m) => {
+                m.get_source().source_id == SANDBOX_SOURCE_ID ||
+                    sandbox_finder.find_sandbox_db_for_version(m.automation_version()).found()
+            }
+        }
+    }
+
+    pub fn get_engines_for_view(&self,
+                                view_settings: &CrateEngineViewSettings,
+                                favorites: &BTreeSet<String>,
+                                sandbox_finder: &SandboxFinder) -> Vec<String> {
+        let mut engines: Vec<String> = self.available_engines.iter().filter(|name| {
+            !view_settings.only_show_usable || self.is_engine_usable(name, sandbox_finder)
+        }).filter(|name| {
+            match self.metadata.get(*name) {
+                None => view_settings.era == CrateEngineEra::All,
+                Some(m) => view_settings.era.contains(m.build_year())
+            }
+        }).filter(|name| {
+            !view_settings.favorites_only ||
+                self.favorite_id_for(name).map_or(false, |id| favorites.contains(&id))
+        }).cloned().collect();
+        engines.sort_by(|a, b| {
+            let (a_meta, b_meta) = match (self.metadata.get(a), self.metadata.get(b)) {
+                (Some(a_meta), Some(b_meta)) => (a_meta, b_meta),
+                _ => return a.cmp(b)
+            };
+            Self::group_key(a_meta, view_settings.group_mode)
+                .cmp(&Self::group_key(b_meta, view_settings.group_mode))
+                .then_with(|| match view_settings.sort_column {
+                    CrateEngineSortColumn::Name => a_meta.name().to_lowercase().cmp(&b_meta.name().to_lowercase()),
+                    CrateEngineSortColumn::BuildYear => a_meta.build_year().cmp(&b_meta.build_year()),
+                    CrateEngineSortColumn::Capacity => a_meta.capacity().cmp(&b_meta.capacity()),
+                    CrateEngineSortColumn::PeakPower => a_meta.peak_power().cmp(&b_meta.peak_power()),
+                    CrateEngineSortColumn::PeakTorque => a_meta.peak_torque().cmp(&b_meta.peak_torque())
+                })
+                .then_with(|| a.cmp(b))
+        });
+        engines
+    }
+
+    fn group_key(metadata: &CrateEngineMetadata, group_mode: CrateEngineGroupMode) -> String {
+        match group_mode {
+            CrateEngineGroupMode::None => String::new(),
+            CrateEngineGroupMode::Aspiration => metadata.aspiration().to_string(),
+            CrateEngineGroupMode::BlockConfig => metadata.block_config().to_string(),
+            CrateEngineGroupMode::Fuel => metadata.fuel().to_string(),
+            CrateEngineGroupMode::Era => CrateEngineEra::from_build_year(metadata.build_year()).to_string(),
+            CrateEngineGroupMode::Family => metadata.family_name().to_lowercase()
+        }
+    }
+
+    /// Returns the eras that the available engines were built in, preceded by `CrateEngineEra::All`
+    pub fn get_available_eras(&self) -> Vec<CrateEngineEra> {
+        let eras: BTreeSet<CrateEngineEra> = self.metadata.values()
+            .map(|m| CrateEngineEra::from_build_year(m.build_year()))
+            .collect();
+        std::iter::once(CrateEngineEra::All).chain(eras.into_iter()).collect()
+    }
+
     fn clear_data(&mut self) {
         self.available_engines.clear();
+        self.invalid_engines.clear();
         self.locations.clear();
         self.metadata.clear();
     }
@@ -429,12 +807,16 @@ impl CrateEngineData {
     fn load_available_engines(&mut self, crate_eng_path: &PathBuf) {
         let span = span!(Level::INFO, "Loading crate engines");
         let _enter = span.enter();
-        let found_engs = find_crate_engines_in_path(crate_eng_path).unwrap_or_else(|e| {
+        let scan = find_crate_engines_in_path(crate_eng_path).unwrap_or_else(|e| {
             warn!("Failed to read {}. {}", crate_eng_path.display(), e.to_string());
-            BTreeMap::new()
+            CrateEngineScan::default()
         });
-        info!("Found {} crate engines", found_engs.len());
-        for (path, metadatum) in found_engs.into_iter() {
+        info!("Found {} crate engines", scan.engines.len());
+        if !scan.invalid_engines.is_empty() {
+            warn!("{} crate engine files couldn't be loaded", scan.invalid_engines.len());
+        }
+        self.invalid_engines = scan.invalid_engines.into_iter().map(|(path, e)| (path, e.to_string())).collect();
+        for (path, metadatum) in scan.engines.into_iter() {
             let x = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
             let id_name =
                 format!("{} ({})",
@@ -0,0 +1,143 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::Path;
+use iced::{Alignment, Length, Padding};
+use iced::widget::{checkbox, Column, Row, Text, TextInput};
+use tracing::{error, info};
+use assetto_corsa::Car;
+use assetto_corsa::car::data::Drivetrain;
+use assetto_corsa::car::data::drivetrain::DownshiftProtection;
+use assetto_corsa::traits::{extract_mandatory_section, update_car_data};
+use crate::ui::edit::EditMessage;
+
+#[derive(Debug, Clone)]
+pub enum DownshiftProtectionUpdate {
+    ActiveToggled(bool),
+    OverrevUpdate(String),
+    LockNUpdate(String)
+}
+
+pub struct DownshiftProtectionConfig {
+    active: bool,
+    overrev: String,
+    lock_n: String
+}
+
+impl DownshiftProtectionConfig {
+    pub fn from_car_path(car_path: &Path) -> Result<DownshiftProtectionConfig, String> {
+        let mut car = Car::load_from_path(car_path).map_err(|err| {
+            format!("Failed to load {}. {}", car_path.display(), err.to_string())
+        })?;
+        let drivetrain = Drivetrain::from_car(&mut car).map_err(|err| {
+            format!("Failed to load {}. {}", Drivetrain::INI_FILENAME, err.to_string())
+        })?;
+        let downshift_protection = extract_mandatory_section::<DownshiftProtection>(&drivetrain).map_err(|err| {
+            format!("Failed to load downshift protection data. {}", err.to_string())
+        })?;
+        Ok(DownshiftProtectionConfig {
+            active: downshift_protection.active != 0,
+            overrev: downshift_protection.overrev.to_string(),
+            lock_n: downshift_protection.lock_n.to_string()
+        })
+    }
+
+    pub fn handle_update(&mut self, update: DownshiftProtectionUpdate) {
+        match update {
+            DownshiftProtectionUpdate::ActiveToggled(is_active) => self.active = is_active,
+            DownshiftProtectionUpdate::OverrevUpdate(new_value) => self.overrev = new_value,
+            DownshiftProtectionUpdate::LockNUpdate(new_value) => self.lock_n = new_value
+        }
+    }
+
+    pub(crate) fn add_editable_list<'a, 'b>(
+        &'a self,
+        layout: Column<'b, EditMessage>
+    ) -> Column<'b, EditMessage>
+    where 'b: 'a
+    {
+        let active_checkbox = checkbox(
+            "Active".to_string(),
+            self.active,
+            |val| EditMessage::DownshiftProtectionUpdate(DownshiftProtectionUpdate::ActiveToggled(val))
+        ).spacing(3);
+        let overrev_row = Row::new().spacing(5).align_items(Alignment::Center)
+            .push(Text::new("Overrev (rpm)").width(Length::Units(100)))
+            .push(TextInput::new(
+                "",
+                &self.overrev,
+                |val| EditMessage::DownshiftProtectionUpdate(DownshiftProtectionUpdate::OverrevUpdate(val))
+            ).width(Length::Units(80)));
+        let lock_n_row = Row::new().spacing(5).align_items(Alignment::Center)
+            .push(Text::new("Lock N").width(Length::Units(100)))
+            .push(TextInput::new(
+                "",
+                &self.lock_n,
+                |val| EditMessage::DownshiftProtectionUpdate(DownshiftProtectionUpdate::LockNUpdate(val))
+            ).width(Length::Units(80)));
+        layout.push(
+            Column::new()
+                .padding(Padding::from([0, 10]))
+                .spacing(10)
+                .push(active_checkbox)
+                .push(overrev_row)
+                .push(lock_n_row)
+        )
+    }
+
+    pub fn write_to_car(&self, car_path: &Path) -> Result<(), String> {
+        let overrev = parse_non_negative("OVERREV", &self.overrev)?;
+        let lock_n = parse_non_negative("LOCK_N", &self.lock_n)?;
+
+        let mut car = match Car::load_from_path(car_path) {
+            Ok(c) => { c }
+            Err(err) => {
+                let err_str = format!("Failed to load {}. {}", car_path.display(), err.to_string());
+                error!("{}", &err_str);
+                return Err(err_str);
+            }
+        };
+        let mut drivetrain = Drivetrain::from_car(&mut car).map_err(|err| {
+            format!("Failed to load {}. {}", Drivetrain::INI_FILENAME, err.to_string())
+        })?;
+        let mut downshift_protection = extract_mandatory_section::<DownshiftProtection>(&drivetrain).map_err(|err| {
+            format!("Failed to load downshift protection data. {}", err.to_string())
+        })?;
+        downshift_protection.active = self.active as i32;
+        downshift_protection.overrev = overrev;
+        downshift_protection.lock_n = lock_n;
+        update_car_data(&mut drivetrain, &downshift_protection).map_err(|err| {
+            format!("Failed to update downshift protection data. {}", err.to_string())
+        })?;
+        info!("Writing drivetrain ini files");
+        drivetrain.write().map_err(|err| {
+            format!("Failed to write {}. {}", Drivetrain::INI_FILENAME, err.to_string())
+        })
+    }
+}
+
+fn parse_non_negative(name: &str, value: &str) -> Result<i32, String> {
+    match value.trim().parse::<i32>() {
+        Ok(val) if val >= 0 => Ok(val),
+        Ok(_) => Err(format!("{} must not be negative", name)),
+        Err(_) => Err(format!("{} must be a whole number. Got '{}'", name, value))
+    }
+}
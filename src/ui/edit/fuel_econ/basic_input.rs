@@ -0,0 +1,136 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::path::PathBuf;
+use iced::{Alignment, Length, Padding};
+use iced::widget::{Column, Row, Text, TextInput};
+use tracing::{error, info};
+use assetto_corsa::Car;
+use assetto_corsa::car::data::CarIniData;
+use crate::ui::edit::EditMessage;
+
+/// Base game consumption has no rpm dependence so it is exchanged with tuning profiles as a
+/// single value at this rpm
+const SINGLE_VALUE_RPM: i32 = 0;
+const MAX_PLAUSIBLE_CONSUMPTION: f64 = 1.0;
+
+/// Editor for the base game [FUEL] CONSUMPTION value in car.ini. Used for cars that don't
+/// have the CSP extended fuel consumption data
+pub struct BaseConsumptionInput {
+    original_value: Option<f64>,
+    updated_value: Option<String>
+}
+
+impl BaseConsumptionInput {
+    pub fn from_car(car: &mut Car) -> Result<BaseConsumptionInput, String> {
+        let ini_data = CarIniData::from_car(car).map_err(|e| {
+            format!("Failed to load {}. {}", CarIniData::FILENAME, e.to_string())
+        })?;
+        Ok(BaseConsumptionInput {
+            original_value: ini_data.fuel_consumption(),
+            updated_value: None
+        })
+    }
+
+    pub(crate) fn add_editable_list<'a, 'b>(
+        &'a self,
+        layout: Column<'b, EditMessage>
+    ) -> Column<'b, EditMessage>
+    where 'b: 'a
+    {
+        let current = match self.original_value {
+            None => "Not set".to_string(),
+            Some(val) => format!("{}", val)
+        };
+        let val = match &self.updated_value {
+            None => String::new(),
+            Some(v) => v.clone()
+        };
+        layout.push(
+            Column::new().width(Length::Shrink).spacing(7).padding(Padding::from([0, 10]))
+                .push(Text::new("Base game fuel consumption (car.ini [FUEL] CONSUMPTION)").size(16))
+                .push(Row::new().spacing(10).align_items(Alignment::Center)
+                    .push(Text::new(format!("Current: {}", current)))
+                    .push(Text::new("New:"))
+                    .push(TextInput::new("", &val, EditMessage::BaseFuelConsumptionUpdate)
+                        .width(Length::Units(80))))
+        )
+    }
+
+    pub fn update_value(&mut self, new_value: String) {
+        if new_value.is_empty() {
+            self.updated_value = None;
+        } else if new_value.parse::<f64>().is_ok() {
+            self.updated_value = Some(new_value);
+        }
+    }
+
+    pub fn import_values(&mut self, values: &Vec<(i32, f64)>) -> Result<usize, String> {
+        match values.as_slice() {
+            [(_, consumption)] => {
+                validate_consumption(*consumption)?;
+                self.updated_value = Some(format!("{}", consumption));
+                Ok(1)
+            }
+            _ => Err("Base game fuel consumption is a single value. \
+                      Switch the car to CSP fuel consumption to use rpm based values".to_string())
+        }
+    }
+
+    pub fn export_values(&self) -> Vec<(i32, String)> {
+        match (&self.updated_value, self.original_value) {
+            (Some(val), _) => vec![(SINGLE_VALUE_RPM, val.clone())],
+            (None, Some(val)) => vec![(SINGLE_VALUE_RPM, format!("{}", val))],
+            (None, None) => Vec::new()
+        }
+    }
+
+    pub fn write_car_updates(&self, ac_car_path: &PathBuf) -> Result<(), String> {
+        let consumption = match &self.updated_value {
+            None => return Ok(()),
+            Some(val) => val.parse::<f64>().map_err(|_| format!("Invalid fuel consumption {}", val))?
+        };
+        validate_consumption(consumption)?;
+        let mut car = match Car::load_from_path(ac_car_path) {
+            Ok(c) => { c }
+            Err(err) => {
+                let err_str = format!("Failed to load {}. {}", ac_car_path.display(), err.to_string());
+                error!("{}", &err_str);
+                return Err(err_str);
+            }
+        };
+        let mut ini_data = CarIniData::from_car(&mut car).map_err(|e|{
+            e.to_string()
+        })?;
+        ini_data.set_fuel_consumption(consumption);
+        info!("Writing car ini files");
+        ini_data.write().map_err(|e| {
+            format!("Failed to write car.ini. {}", e.to_string())
+        })
+    }
+}
+
+fn validate_consumption(consumption: f64) -> Result<(), String> {
+    if !consumption.is_finite() || consumption <= 0.0 || consumption > MAX_PLAUSIBLE_CONSUMPTION {
+        return Err(format!("Implausible fuel consumption of {}. Value must be between 0 and {}",
+                           consumption, MAX_PLAUSIBLE_CONSUMPTION));
+    }
+    Ok(())
+}
@@ -7,18 +7,19 @@ use tracing::{error, info, warn};
 use assetto_corsa::Car;
 use assetto_corsa::car::data::{CarIniData, Engine};
 use assetto_corsa::car::data::car_ini_data::CarVersion;
-use assetto_corsa::car::data::engine::{EngineData, FuelConsumptionFlowRate, Turbo, TurboControllerFile};
+use assetto_corsa::car::data::engine::{EngineData, FuelConsumptionFlowRate, TurboControllerFile};
 use assetto_corsa::car::data::engine::turbo_ctrl::TurboController;
 use assetto_corsa::car::lut_utils::LutInterpolator;
-use assetto_corsa::traits::{update_car_data, CarDataFile, MandatoryDataSection, OptionalDataSection};
+use assetto_corsa::traits::{update_car_data, CarDataFile, MandatoryDataSection};
 use crate::ui::edit::EditMessage;
 use crate::ui::edit::EditMessage::FuelConsumptionUpdate;
-use crate::ui::edit::fuel_econ::helpers::{create_engine_power_interpolator, get_fuel_use_kg_per_hour, get_fuel_use_per_sec_at_rpm, get_min_max_rpms, load_drive_type};
+use crate::ui::edit::fuel_econ::helpers::{create_engine_power_interpolator, get_fuel_use_kg_per_hour, get_fuel_use_per_sec_at_rpm, get_min_max_rpms, interpolate_onto_rpms, load_drive_type};
 
 
 // kW⋅h/g
 const GASOLINE_LHV: f64 = 0.01204;
 const RPM_STEP: usize = 500;
+const MAX_PLAUSIBLE_EFFICIENCY: f64 = 60.0;
 
 pub struct ThermalEfficiencyInput {
     mechanical_efficiency: f64,
@@ -38,17 +39,7 @@ impl ThermalEfficiencyInput {
             let engine = Engine::from_car(car).map_err(|err| {
                 err.to_string()
             })?;
-
-            {
-                is_turbo = match Turbo::load_from_parent(&engine) {
-                    Ok(opt) => opt.is_some(),
-                    Err(e) => {
-                        warn!("Couldn't determine if turbo engine. {}", e.to_string());
-                        info!("Assuming NA");
-                        false
-                    }
-                };
-            }
+            is_turbo = engine.is_forced_induction();
         }
 
         let mut boost_interpolator_opt: Option<LutInterpolator<f64, f64>> = None;
@@ -194,6 +185,25 @@ impl ThermalEfficiencyInput {
         }
     }
 
+    pub fn import_values(&mut self, values: &Vec<(i32, f64)>) -> Result<usize, String> {
+        if let Some((rpm, eff)) = values.iter().find(|(_, eff)| *eff <= 0.0 || *eff > MAX_PLAUSIBLE_EFFICIENCY) {
+            return Err(format!("Implausible efficiency of {}% @{}rpm. Values must be between 0 and {}",
+                               eff, rpm, MAX_PLAUSIBLE_EFFICIENCY));
+        }
+        let rpms = self.updated_data.keys().copied().collect();
+        let interpolated = interpolate_onto_rpms(values, &rpms)?;
+        for (rpm, eff) in &interpolated {
+            self.update_for_rpm(*rpm, format!("{}", eff.round() as i32));
+        }
+        Ok(interpolated.len())
+    }
+
+    pub fn export_values(&self) -> Vec<(i32, String)> {
+        self.updated_data.iter().filter_map(|(rpm, val_opt)| {
+            val_opt.as_ref().map(|val| (*rpm, val.clone()))
+        }).collect()
+    }
+
     fn update_projected_fuel_flow(&mut self, rpm: i32) {
         if let Some(eff_opt) = self.updated_data.get(&rpm) {
             if let Some(eff_str) = eff_opt {
@@ -210,7 +220,7 @@ impl ThermalEfficiencyInput {
         }
     }
 
-    pub fn write_car_updates(&self, ac_car_path: &PathBuf) -> Result<(), String> {
+    pub fn write_car_updates(&self, ac_car_path: &PathBuf, log_fuel_flow: bool) -> Result<(), String> {
         let mut car = match Car::load_from_path(ac_car_path) {
             Ok(c) => { c }
             Err(err) => {
@@ -290,13 +300,14 @@ impl ThermalEfficiencyInput {
                 return Err("Not enough efficiency data to create fuel consumption data".to_string())
             }
 
-            let fuel_flow = FuelConsumptionFlowRate::new(
+            let mut fuel_flow = FuelConsumptionFlowRate::new(
                 0.03,
                 idle + 100,
                 self.mechanical_efficiency,
                 Some(max_flow_lut),
                 max_fuel_flow
             );
+            fuel_flow.set_log_fuel_flow(log_fuel_flow);
             update_car_data(&mut engine, &fuel_flow).map_err(|err| {
                 err.to_string()
             })?;
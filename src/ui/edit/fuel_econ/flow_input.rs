@@ -33,7 +33,7 @@ use assetto_corsa::traits::{update_car_data, CarDataFile, MandatoryDataSection};
 use utils::units::g_min_to_kg_hour;
 use crate::ui::edit::EditMessage;
 use crate::ui::edit::EditMessage::FuelConsumptionUpdate;
-use crate::ui::edit::fuel_econ::helpers::{get_min_max_rpms, load_drive_type};
+use crate::ui::edit::fuel_econ::helpers::{get_min_max_rpms, interpolate_onto_rpms, load_drive_type};
 
 pub struct FuelFlowInput {
     mechanical_efficiency: f64,
@@ -42,6 +42,7 @@ pub struct FuelFlowInput {
 }
 
 const RPM_STEP: usize = 500;
+const MAX_PLAUSIBLE_FLOW_G_MIN: f64 = 5000.0;
 
 impl FuelFlowInput {
     pub fn from_car(car: &mut Car) -> Result<FuelFlowInput, String> {
@@ -167,7 +168,26 @@ impl FuelFlowInput {
         }
     }
 
-    pub fn write_car_updates(&self, ac_car_path: &PathBuf) -> Result<(), String> {
+    pub fn import_values(&mut self, values: &Vec<(i32, f64)>) -> Result<usize, String> {
+        if let Some((rpm, flow)) = values.iter().find(|(_, flow)| *flow <= 0.0 || *flow > MAX_PLAUSIBLE_FLOW_G_MIN) {
+            return Err(format!("Implausible fuel flow of {}g/min @{}rpm. Values must be between 0 and {}",
+                               flow, rpm, MAX_PLAUSIBLE_FLOW_G_MIN));
+        }
+        let rpms = self.updated_data.keys().copied().collect();
+        let interpolated = interpolate_onto_rpms(values, &rpms)?;
+        for (rpm, flow) in &interpolated {
+            self.update_for_rpm(*rpm, format!("{:.2}", flow));
+        }
+        Ok(interpolated.len())
+    }
+
+    pub fn export_values(&self) -> Vec<(i32, String)> {
+        self.updated_data.iter().filter_map(|(rpm, val_opt)| {
+            val_opt.as_ref().map(|val| (*rpm, val.clone()))
+        }).collect()
+    }
+
+    pub fn write_car_updates(&self, ac_car_path: &PathBuf, log_fuel_flow: bool) -> Result<(), String> {
         let mut car = match Car::load_from_path(ac_car_path) {
             Ok(c) => { c }
             Err(err) => {
@@ -217,13 +237,14 @@ impl FuelFlowInput {
                 return Err("Not enough efficiency data to create fuel consumption data".to_string())
             }
 
-            let fuel_flow = FuelConsumptionFlowRate::new(
+            let mut fuel_flow = FuelConsumptionFlowRate::new(
                 0.03,
                 idle + 100,
                 self.mechanical_efficiency,
                 Some(max_flow_lut),
                 max_fuel_flow.round() as i32
             );
+            fuel_flow.set_log_fuel_flow(log_fuel_flow);
             update_car_data(&mut engine, &fuel_flow).map_err(|err| {
                 err.to_string()
             })?;
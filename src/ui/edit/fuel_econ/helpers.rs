@@ -2,9 +2,9 @@ use assetto_corsa::Car;
 use assetto_corsa::car::data;
 use assetto_corsa::car::data::drivetrain::traction::DriveType;
 use assetto_corsa::car::data::{Drivetrain, Engine};
-use assetto_corsa::car::data::engine::{EngineData, PowerCurve};
+use assetto_corsa::car::data::engine::{EngineData, FuelConsumptionFlowRate, PowerCurve};
 use assetto_corsa::car::lut_utils::LutInterpolator;
-use assetto_corsa::traits::{extract_mandatory_section, MandatoryDataSection};
+use assetto_corsa::traits::{extract_mandatory_section, CarDataFile, MandatoryDataSection};
 
 pub(crate) fn get_fuel_use_per_sec_at_rpm(eff_percentage: i32, fuel_lhv: f64, power_kw: f64) -> f64 {
     // fuel lhv in kWh/g
@@ -25,6 +25,71 @@ pub(crate) fn get_fuel_use_kg_per_hour(eff_percentage: i32, fuel_lhv: f64, power
 }
 
 
+/// How far outside of the engine's rpm range imported values are allowed to be
+const IMPORT_RPM_TOLERANCE: i32 = 500;
+
+/// Parse `rpm,value` pairs from csv data. Blank lines, lines starting with `#` and a
+/// leading header line are ignored. The returned pairs are sorted by rpm
+pub(crate) fn parse_rpm_value_csv(csv_data: &str) -> Result<Vec<(i32, f64)>, String> {
+    let mut values = Vec::new();
+    let mut header_allowed = true;
+    for (line_idx, line) in csv_data.lines().enumerate() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(',').map(str::trim);
+        let (rpm_str, value_str) = match (fields.next(), fields.next()) {
+            (Some(rpm_str), Some(value_str)) => (rpm_str, value_str),
+            _ => return Err(format!("Line {}: expected 'rpm,value' but found '{}'", line_idx + 1, line))
+        };
+        let rpm = match rpm_str.parse::<f64>() {
+            Ok(rpm) => rpm.round() as i32,
+            Err(_) if header_allowed => {
+                header_allowed = false;
+                continue;
+            }
+            Err(_) => return Err(format!("Line {}: invalid rpm '{}'", line_idx + 1, rpm_str))
+        };
+        header_allowed = false;
+        let value = value_str.parse::<f64>().map_err(|_| {
+            format!("Line {}: invalid value '{}'", line_idx + 1, value_str)
+        })?;
+        values.push((rpm, value));
+    }
+    if values.is_empty() {
+        return Err("No rpm,value pairs found".to_string());
+    }
+    values.sort_by_key(|(rpm, _)| *rpm);
+    Ok(values)
+}
+
+pub(crate) fn rpm_value_csv_string(value_name: &str, values: &Vec<(i32, String)>) -> String {
+    let mut out = format!("rpm,{}\n", value_name);
+    for (rpm, value) in values {
+        out.push_str(&format!("{},{}\n", rpm, value));
+    }
+    out
+}
+
+/// Interpolate imported `values` onto the provided rpm points. Fails if any of the imported
+/// values lie well outside the rpm range covered by `rpms`
+pub(crate) fn interpolate_onto_rpms(values: &Vec<(i32, f64)>, rpms: &Vec<i32>) -> Result<Vec<(i32, f64)>, String> {
+    let (min_rpm, max_rpm) = match (rpms.iter().min(), rpms.iter().max()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return Err("Engine has no rpm range to import into".to_string())
+    };
+    if let Some((rpm, _)) = values.iter().find(|(rpm, _)| {
+        *rpm < min_rpm - IMPORT_RPM_TOLERANCE || *rpm > max_rpm + IMPORT_RPM_TOLERANCE
+    }) {
+        return Err(format!("Imported rpm {} is outside of the engine's range of {}-{}rpm", rpm, min_rpm, max_rpm));
+    }
+    let interpolator = LutInterpolator::from_vec(values.clone());
+    Ok(rpms.iter().filter_map(|rpm| {
+        interpolator.get_value(*rpm).map(|value| (*rpm, value))
+    }).collect())
+}
+
 // TODO this would be a useful func on one of the engine structs; come back and refactor
 pub(crate) fn get_min_max_rpms(engine_ini: &Engine) -> Result<(i32, i32), String> {
     match EngineData::load_from_parent(engine_ini) {
@@ -37,6 +102,19 @@ pub(crate) fn get_min_max_rpms(engine_ini: &Engine) -> Result<(i32, i32), String
     }
 }
 
+/// Whether the car has the CSP extended fuel consumption data rather than relying on the
+/// base game [FUEL] CONSUMPTION value in car.ini
+pub(crate) fn uses_extended_fuel_consumption(car: &mut Car) -> Result<bool, String> {
+    let engine = Engine::from_car(car).map_err(|err| {
+        err.to_string()
+    })?;
+    FuelConsumptionFlowRate::load_from_data(&engine.ini_data(), engine.data_interface()).map(|rate_opt| {
+        rate_opt.is_some()
+    }).map_err(|e| {
+        format!("Failed to read fuel consumption data. {}", e.to_string())
+    })
+}
+
 pub(crate) fn load_drive_type(car: &mut Car) -> Result<DriveType, String> {
     let drivetrain = Drivetrain::from_car(car).map_err(|e|{
         format!("Failed to load {}. {}", Drivetrain::INI_FILENAME.to_string(), e.to_string())
@@ -19,22 +19,37 @@
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
 use std::fmt::{Display, Formatter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use iced::widget::Column;
+use serde::{Deserialize, Serialize};
 use tracing::error;
 use assetto_corsa::Car;
 use crate::ui::edit::EditMessage;
 pub use crate::ui::edit::fuel_econ::eff_input::ThermalEfficiencyInput;
+use crate::ui::edit::fuel_econ::basic_input::BaseConsumptionInput;
 use crate::ui::edit::fuel_econ::flow_input::FuelFlowInput;
+use crate::ui::edit::fuel_econ::helpers::{parse_rpm_value_csv, rpm_value_csv_string, uses_extended_fuel_consumption};
 
+mod basic_input;
 mod eff_input;
 mod helpers;
 mod flow_input;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FuelEfficiencyConfigType {
     ByThermalEfficiency,
-    ByFuelFlow
+    ByFuelFlow,
+    BaseGame
+}
+
+impl FuelEfficiencyConfigType {
+    /// Whether this config writes the CSP extended fuel consumption data
+    pub fn is_csp_extended(&self) -> bool {
+        match self {
+            FuelEfficiencyConfigType::ByThermalEfficiency | FuelEfficiencyConfigType::ByFuelFlow => true,
+            FuelEfficiencyConfigType::BaseGame => false
+        }
+    }
 }
 
 impl Display for FuelEfficiencyConfigType {
@@ -42,20 +57,23 @@ impl Display for FuelEfficiencyConfigType {
         match &self {
             FuelEfficiencyConfigType::ByThermalEfficiency => { write!(f, "Thermal Efficiency") }
             FuelEfficiencyConfigType::ByFuelFlow => { write!(f, "Fuel Flow") }
+            FuelEfficiencyConfigType::BaseGame => { write!(f, "Base Game") }
         }
     }
 }
 
 pub enum FuelEfficiencyConfig {
     ThermalEff(ThermalEfficiencyInput),
-    FuelFlow(FuelFlowInput)
+    FuelFlow(FuelFlowInput),
+    BaseGame(BaseConsumptionInput)
 }
 
 impl FuelEfficiencyConfig {
     pub fn get_config_type(&self) -> FuelEfficiencyConfigType {
         match self {
             FuelEfficiencyConfig::ThermalEff(_e) => FuelEfficiencyConfigType::ByThermalEfficiency,
-            FuelEfficiencyConfig::FuelFlow(_e) => FuelEfficiencyConfigType::ByFuelFlow
+            FuelEfficiencyConfig::FuelFlow(_e) => FuelEfficiencyConfigType::ByFuelFlow,
+            FuelEfficiencyConfig::BaseGame(_e) => FuelEfficiencyConfigType::BaseGame
         }
     }
     
@@ -67,7 +85,8 @@ impl FuelEfficiencyConfig {
     {
         match &self {
             FuelEfficiencyConfig::ThermalEff(e) => e.add_editable_list(layout),
-            FuelEfficiencyConfig::FuelFlow(e) => e.add_editable_list(layout)
+            FuelEfficiencyConfig::FuelFlow(e) => e.add_editable_list(layout),
+            FuelEfficiencyConfig::BaseGame(e) => e.add_editable_list(layout)
         }
     }
 
@@ -75,17 +94,87 @@ impl FuelEfficiencyConfig {
         match self {
             FuelEfficiencyConfig::ThermalEff(e) => e.update_for_rpm(rpm, new_value),
             FuelEfficiencyConfig::FuelFlow(e) => e.update_for_rpm(rpm, new_value),
+            FuelEfficiencyConfig::BaseGame(_) => {}
+        }
+    }
+
+    pub fn update_base_consumption(&mut self, new_value: String) {
+        if let FuelEfficiencyConfig::BaseGame(e) = self {
+            e.update_value(new_value);
         }
     }
     
-    pub fn write_car_updates(&self, ac_car_path: &PathBuf) -> Result<(), String> {
+    /// Load `rpm,value` pairs from the csv file at `csv_path` into the editable data. The values
+    /// are interpolated onto the rpm points already shown for the car. Returns the number of
+    /// rpm points that were updated
+    pub fn import_csv(&mut self, csv_path: &Path) -> Result<usize, String> {
+        let csv_data = std::fs::read_to_string(csv_path).map_err(|e| {
+            format!("Failed to read {}. {}", csv_path.display(), e.to_string())
+        })?;
+        let values = parse_rpm_value_csv(&csv_data)?;
+        self.import_values(&values)
+    }
+
+    /// Interpolate `rpm,value` pairs onto the rpm points shown for the car. Returns the number of
+    /// rpm points that were updated
+    pub fn import_values(&mut self, values: &Vec<(i32, f64)>) -> Result<usize, String> {
+        match self {
+            FuelEfficiencyConfig::ThermalEff(e) => e.import_values(values),
+            FuelEfficiencyConfig::FuelFlow(e) => e.import_values(values),
+            FuelEfficiencyConfig::BaseGame(e) => e.import_values(values)
+        }
+    }
+
+    /// The rpm points that currently have a valid value set
+    pub fn export_values(&self) -> Vec<(i32, f64)> {
+        let values = match &self {
+            FuelEfficiencyConfig::ThermalEff(e) => e.export_values(),
+            FuelEfficiencyConfig::FuelFlow(e) => e.export_values(),
+            FuelEfficiencyConfig::BaseGame(e) => e.export_values()
+        };
+        values.into_iter().filter_map(|(rpm, val)| {
+            val.parse::<f64>().ok().map(|v| (rpm, v))
+        }).collect()
+    }
+
+    pub fn export_csv(&self, csv_path: &Path) -> Result<(), String> {
+        let csv_data = match &self {
+            FuelEfficiencyConfig::ThermalEff(e) => rpm_value_csv_string("efficiency", &e.export_values()),
+            FuelEfficiencyConfig::FuelFlow(e) => rpm_value_csv_string("fuel_flow_g_min", &e.export_values()),
+            FuelEfficiencyConfig::BaseGame(e) => rpm_value_csv_string("consumption", &e.export_values())
+        };
+        std::fs::write(csv_path, csv_data).map_err(|e| {
+            format!("Failed to write {}. {}", csv_path.display(), e.to_string())
+        })
+    }
+
+    pub fn write_car_updates(&self, ac_car_path: &PathBuf, log_fuel_flow: bool) -> Result<(), String> {
         match &self {
-            FuelEfficiencyConfig::ThermalEff(e) => e.write_car_updates(ac_car_path),
-            FuelEfficiencyConfig::FuelFlow(e) => e.write_car_updates(ac_car_path)
+            FuelEfficiencyConfig::ThermalEff(e) => e.write_car_updates(ac_car_path, log_fuel_flow),
+            FuelEfficiencyConfig::FuelFlow(e) => e.write_car_updates(ac_car_path, log_fuel_flow),
+            FuelEfficiencyConfig::BaseGame(e) => e.write_car_updates(ac_car_path)
         }
     }
 }
 
+/// Build the editor for the fuel consumption model the car currently uses. Cars without the
+/// CSP extended fuel consumption data get the base game editor so that they are only switched
+/// to CSP fuel modelling when explicitly requested
+pub fn current_consumption_configuration(ac_car_path: &PathBuf) -> Result<FuelEfficiencyConfig, String> {
+    let mut car = match Car::load_from_path(ac_car_path) {
+        Ok(c) => { c }
+        Err(err) => {
+            let err_str = format!("Failed to load {}. {}", ac_car_path.display(), err.to_string());
+            error!("{}", &err_str);
+            return Err(err_str);
+        }
+    };
+    match uses_extended_fuel_consumption(&mut car)? {
+        true => Ok(FuelEfficiencyConfig::FuelFlow(FuelFlowInput::from_car(&mut car)?)),
+        false => Ok(FuelEfficiencyConfig::BaseGame(BaseConsumptionInput::from_car(&mut car)?))
+    }
+}
+
 pub fn consumption_configuration_builder(config_type: FuelEfficiencyConfigType,
                                          ac_car_path: &PathBuf) -> Result<FuelEfficiencyConfig, String> {
     let mut car = match Car::load_from_path(ac_car_path) {
@@ -103,6 +192,13 @@ pub fn consumption_configuration_builder(config_type: FuelEfficiencyConfigType,
         FuelEfficiencyConfigType::ByThermalEfficiency => {
             Ok(FuelEfficiencyConfig::ThermalEff(ThermalEfficiencyInput::from_car(&mut car)?))
         }
+        FuelEfficiencyConfigType::BaseGame => {
+            if uses_extended_fuel_consumption(&mut car)? {
+                return Err(format!("{} uses CSP extended fuel consumption so the base game value isn't used",
+                                   ac_car_path.display()));
+            }
+            Ok(FuelEfficiencyConfig::BaseGame(BaseConsumptionInput::from_car(&mut car)?))
+        }
     }
     
 }
@@ -58,6 +58,8 @@ pub enum CustomizedGearUpdate {
     DiscardNewRatio(),
 }
 
+const SUGGESTED_RATIO_NAME: &str = "suggested";
+
 pub struct CustomizableGears {
     original_drivetrain_data: Vec<f64>,
     original_setup_data: Option<GearConfig>,
@@ -215,6 +217,39 @@ impl CustomizableGears {
         self.gearing_calculator.take()
     }
 
+    pub(crate) fn get_gearing_calculator(&self) -> Option<&GearingCalculator> {
+        self.gearing_calculator.as_ref()
+    }
+
+    /// Make `ratios` the default ratio for each gear, adding or removing gears as required.
+    /// Each ratio is stored as an entry named `SUGGESTED_RATIO_NAME` which is replaced if it
+    /// already exists
+    pub(crate) fn set_gear_ratios(&mut self, ratios: &[f64]) {
+        while self.new_setup_data.len() > ratios.len() {
+            self.new_setup_data.pop_last();
+        }
+        while self.new_setup_data.len() < ratios.len() {
+            let next_idx = match self.new_setup_data.last_key_value() {
+                Some((l, _)) => l.idx + 1,
+                None => 1
+            };
+            self.new_setup_data.insert(GearLabel{idx: next_idx}, RatioSet::new());
+        }
+        for (ratio_set, ratio) in self.new_setup_data.values_mut().zip(ratios) {
+            let existing_idx = ratio_set.entries().iter()
+                .find(|entry| entry.name == SUGGESTED_RATIO_NAME)
+                .map(|entry| entry.idx);
+            let ratio_idx = match existing_idx {
+                Some(idx) => {
+                    ratio_set.update_ratio_value(idx, *ratio);
+                    idx
+                }
+                None => ratio_set.insert(SUGGESTED_RATIO_NAME.to_string(), *ratio)
+            };
+            let _ = ratio_set.set_default(ratio_idx);
+        }
+    }
+
     fn create_gear_ratio_column(&self, gear_idx: &GearLabel, ratio_set: &RatioSet ) -> Column<'static, EditMessage>
     {
         let mut inner_col = Column::new()
@@ -320,6 +355,22 @@ impl CustomizableGears {
         }).collect()
     }
 
+    pub(crate) fn get_original_drivetrain_data(&self) -> &Vec<f64> {
+        &self.original_drivetrain_data
+    }
+
+    pub(crate) fn get_final_drive_data(&self) -> &FinalDrive {
+        &self.final_drive_data
+    }
+
+    /// Make `ratio` the default final drive ratio
+    pub(crate) fn set_default_final_drive(&mut self, ratio: f64) {
+        self.final_drive_data.set_default_ratio(ratio);
+        if let Some(calc) = &mut self.gearing_calculator {
+            calc.set_final_drive(self.final_drive_data.get_default_ratio_val());
+        }
+    }
+
     pub(crate) fn extract_original_drivetrain_data(&mut self) -> Vec<f64> {
         std::mem::take(&mut self.original_drivetrain_data)
     }
@@ -80,6 +80,18 @@ impl FinalDrive {
         }
     }
 
+    /// Make `ratio` the default final drive, adding it as a new entry if it isn't already present
+    pub fn set_default_ratio(&mut self, ratio: f64) {
+        let existing_idx = self.new_setup_data.entries().iter()
+            .find(|entry| entry.ratio() == ratio)
+            .map(|entry| entry.idx);
+        let idx = match existing_idx {
+            Some(idx) => idx,
+            None => self.new_setup_data.insert(ratio.to_string(), ratio)
+        };
+        let _ = self.new_setup_data.set_default(idx);
+    }
+
     pub fn handle_update(&mut self, update: FinalDriveUpdate) {
         match update {
             FinalDriveUpdate::AddRatioPressed() => {
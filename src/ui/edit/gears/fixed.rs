@@ -157,6 +157,10 @@ impl FixedGears {
         self.gearing_calculator.take()
     }
 
+    pub(crate) fn get_gearing_calculator(&self) -> Option<&GearingCalculator> {
+        self.gearing_calculator.as_ref()
+    }
+
     fn create_gear_ratio_column(&self) -> Column<'static, EditMessage>
     {
         let mut row_vals = Vec::new();
@@ -256,6 +260,32 @@ impl FixedGears {
         displayed_ratios
     }
 
+    pub(crate) fn get_final_drive_data(&self) -> &FinalDrive {
+        &self.final_drive_data
+    }
+
+    /// Make `ratio` the default final drive ratio
+    pub(crate) fn set_default_final_drive(&mut self, ratio: f64) {
+        self.final_drive_data.set_default_ratio(ratio);
+        if let Some(calc) = &mut self.gearing_calculator {
+            calc.set_final_drive(self.final_drive_data.get_default_ratio_val());
+        }
+    }
+
+    /// Replace the gear ratios with `ratios`, adding or removing gears as required
+    pub(crate) fn set_gear_ratios(&mut self, ratios: &[f64]) {
+        self.updated_drivetrain_data = ratios.iter().enumerate().map(|(idx, ratio)| {
+            match self.original_drivetrain_data.get(idx) {
+                Some(og_ratio) if *og_ratio == *ratio => (idx, None),
+                _ => (idx, Some(ratio.to_string()))
+            }
+        }).collect();
+        if self.gearing_calculator.is_some() {
+            let updated_ratios = self.get_updated_gear_values();
+            self.gearing_calculator.as_mut().unwrap().set_gear_ratios(updated_ratios);
+        }
+    }
+
     pub(crate) fn extract_original_drivetrain_data(&mut self) -> Vec<f64> {
         std::mem::take(&mut self.original_drivetrain_data)
     }
@@ -200,6 +200,66 @@ impl GearConfiguration for GearConfig {
     }
 }
 
+impl GearConfig {
+    /// The ratio of each gear that will be used by default, regardless of how the gearing is configured
+    pub fn default_gear_ratios(&self) -> Vec<f64> {
+        match self {
+            GearConfig::Fixed(f) => f.get_updated_gear_values(),
+            GearConfig::GearSets(g) => {
+                let original = g.get_original_drivetrain_data();
+                g.get_default_ratios().into_iter().enumerate().map(|(idx, ratio)| {
+                    ratio.and_then(|r| r.parse::<f64>().ok())
+                        .or_else(|| original.get(idx).copied())
+                        .unwrap_or(0f64)
+                }).collect()
+            }
+            GearConfig::Customizable(c) => {
+                let original = c.get_original_drivetrain_data();
+                c.get_default_gear_ratios().into_iter().enumerate().map(|(idx, ratio)| {
+                    ratio.or_else(|| original.get(idx).copied()).unwrap_or(0f64)
+                }).collect()
+            }
+        }
+    }
+
+    pub fn default_final_drive(&self) -> f64 {
+        match self {
+            GearConfig::Fixed(f) => f.get_final_drive_data().get_default_ratio_val(),
+            GearConfig::GearSets(g) => g.get_final_drive_data().get_default_ratio_val(),
+            GearConfig::Customizable(c) => c.get_final_drive_data().get_default_ratio_val()
+        }
+    }
+
+    pub fn gearing_calculator(&self) -> Option<&GearingCalculator> {
+        match self {
+            GearConfig::Fixed(f) => f.get_gearing_calculator(),
+            GearConfig::GearSets(g) => g.get_gearing_calculator(),
+            GearConfig::Customizable(c) => c.get_gearing_calculator()
+        }
+    }
+
+    /// Use `ratios` as the default gear ratios. Only the editable values are updated; nothing
+    /// is written to the car until the changes are applied
+    pub fn set_default_gear_ratios(&mut self, ratios: &[f64]) {
+        match self {
+            GearConfig::Fixed(f) => f.set_gear_ratios(ratios),
+            GearConfig::GearSets(g) => g.set_gear_ratios(ratios),
+            GearConfig::Customizable(c) => c.set_gear_ratios(ratios)
+        }
+    }
+
+    /// Use `ratios` and `final_drive` as the default gearing while keeping the current
+    /// configuration type
+    pub fn set_default_gearing(&mut self, ratios: &[f64], final_drive: f64) {
+        self.set_default_gear_ratios(ratios);
+        match self {
+            GearConfig::Fixed(f) => f.set_default_final_drive(final_drive),
+            GearConfig::GearSets(g) => g.set_default_final_drive(final_drive),
+            GearConfig::Customizable(c) => c.set_default_final_drive(final_drive)
+        }
+    }
+}
+
 pub fn gear_configuration_builder(ac_car_path: &PathBuf) -> Result<GearConfig, String> {
     let mut car = match Car::load_from_path(ac_car_path) {
         Ok(c) => { c }
@@ -310,3 +370,43 @@ pub fn convert_gear_configuration(from: GearConfig, to: GearConfigType)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE_RATIOS: [f64; 5] = [3.1, 2.2, 1.6, 1.2, 0.95];
+    const PROFILE_FINAL_DRIVE: f64 = 4.1;
+
+    fn fixed_config() -> GearConfig {
+        GearConfig::Fixed(FixedGears::from_gear_data(vec![2.9, 2.0, 1.5, 1.1],
+                                                     None,
+                                                     FinalDrive::from_gear_data(3.7, None)))
+    }
+
+    fn assert_default_gearing_applied(config_type: GearConfigType) {
+        let mut config = match config_type {
+            GearConfigType::Fixed => fixed_config(),
+            _ => convert_gear_configuration(fixed_config(), config_type).map_err(|(_, e)| e).unwrap()
+        };
+        config.set_default_gearing(&PROFILE_RATIOS, PROFILE_FINAL_DRIVE);
+        assert_eq!(config.get_config_type(), config_type);
+        assert_eq!(config.default_gear_ratios(), PROFILE_RATIOS.to_vec());
+        assert_eq!(config.default_final_drive(), PROFILE_FINAL_DRIVE);
+    }
+
+    #[test]
+    fn set_default_gearing_keeps_fixed_gearing() {
+        assert_default_gearing_applied(GearConfigType::Fixed);
+    }
+
+    #[test]
+    fn set_default_gearing_keeps_gear_sets() {
+        assert_default_gearing_applied(GearConfigType::GearSets);
+    }
+
+    #[test]
+    fn set_default_gearing_keeps_customizable_gears() {
+        assert_default_gearing_applied(GearConfigType::PerGearConfig);
+    }
+}
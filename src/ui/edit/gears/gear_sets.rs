@@ -148,6 +148,28 @@ impl GearSetContainer {
         self.entries.len() == 0
     }
 
+    /// Replace the ratios of the default gearset with `ratios`, adding or removing gears from
+    /// every gearset as required. A gearset is created if there aren't any
+    fn set_default_ratios(&mut self, ratios: &[f64]) {
+        while self.num_gears < ratios.len() {
+            self.add_gear();
+        }
+        while self.num_gears > ratios.len() {
+            self.remove_gear();
+        }
+        let label = match &self.default {
+            Some(label) => label.clone(),
+            None => match self.entries.first_key_value() {
+                Some((label, _)) => label.clone(),
+                None => self.add_gearset()
+            }
+        };
+        for (gear_idx, ratio) in ratios.iter().enumerate() {
+            self.update_ratio(&label, gear_idx, Some(ratio.to_string()));
+        }
+        self.default = Some(label);
+    }
+
 
     fn set_default_gearset(&mut self, label: &GearsetLabel) {
         if self.entries.contains_key(label) {
@@ -501,6 +523,21 @@ impl GearSets {
         self.updated_gearsets.extract_gearing_calculator()
     }
 
+    pub(crate) fn get_gearing_calculator(&self) -> Option<&GearingCalculator> {
+        self.updated_gearsets.gearing_calculator.as_ref()
+    }
+
+    /// Replace the ratios of the default gearset with `ratios`
+    pub(crate) fn set_gear_ratios(&mut self, ratios: &[f64]) {
+        self.updated_gearsets.set_default_ratios(ratios);
+    }
+
+    /// Make `ratio` the default final drive ratio
+    pub(crate) fn set_default_final_drive(&mut self, ratio: f64) {
+        self.final_drive_data.set_default_ratio(ratio);
+        self.updated_gearsets.final_drive_updated(self.final_drive_data.get_default_ratio_val())
+    }
+
     pub(crate) fn extract_original_drivetrain_data(&mut self) -> Vec<f64> {
         std::mem::take(&mut self.original_drivetrain_data)
     }
@@ -513,6 +550,14 @@ impl GearSets {
         std::mem::take(&mut self.final_drive_data)
     }
 
+    pub(crate) fn get_original_drivetrain_data(&self) -> &Vec<f64> {
+        &self.original_drivetrain_data
+    }
+
+    pub(crate) fn get_final_drive_data(&self) -> &FinalDrive {
+        &self.final_drive_data
+    }
+
     pub(crate) fn get_default_ratios(&self) -> Vec<Option<String>> {
         self.updated_gearsets.default_ratios()
     }
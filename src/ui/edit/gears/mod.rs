@@ -25,6 +25,7 @@ mod final_drive;
 mod gear_sets;
 mod customizable;
 mod ratio_set;
+mod suggestion;
 
 use iced::{Alignment, Length};
 use iced::alignment::Vertical;
@@ -40,6 +41,7 @@ pub use final_drive::FinalDriveUpdate;
 pub use fixed::FixedGearUpdate;
 pub use gear_sets::GearsetUpdate;
 pub use customizable::CustomizedGearUpdate;
+pub use suggestion::RatioSuggestion;
 use crate::ui::edit::EditMessage;
 
 fn create_max_ratio_speed_element(ratio: &str, gear_calc: &GearingCalculator) -> Row<'static, EditMessage> {
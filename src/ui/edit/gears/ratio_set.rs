@@ -114,7 +114,6 @@ impl RatioSet {
         }
     }
 
-    #[allow(dead_code)]
     pub fn update_ratio_value(&mut self, idx: usize, new_value: f64) {
         match self.entries.get_mut(&idx) {
             None => {}
@@ -0,0 +1,118 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+
+use iced::{Alignment, Length, theme};
+use iced::widget::{Column, Row, Text};
+use iced_native::widget::{button, text_input};
+use assetto_corsa::car::model::GearingCalculator;
+use crate::ui::edit::EditMessage;
+
+const MAX_SUGGESTED_GEARS: usize = 10;
+
+/// The inputs for suggesting a set of gear ratios that reach a target top speed
+#[derive(Debug, Clone)]
+pub struct RatioSuggestion {
+    top_speed: String,
+    num_gears: String
+}
+
+impl RatioSuggestion {
+    pub fn new(gearing_calculator: &GearingCalculator) -> RatioSuggestion {
+        RatioSuggestion {
+            top_speed: format!("{}", gearing_calculator.max_speed().round()),
+            num_gears: format!("{}", gearing_calculator.max_gear_idx() + 1)
+        }
+    }
+
+    pub fn set_top_speed(&mut self, top_speed: String) {
+        self.top_speed = top_speed;
+    }
+
+    pub fn set_num_gears(&mut self, num_gears: String) {
+        self.num_gears = num_gears;
+    }
+
+    /// Calculate the suggested ratios, lowest gear first
+    pub fn suggested_ratios(&self, gearing_calculator: &GearingCalculator) -> Result<Vec<f64>, String> {
+        let top_speed = match self.top_speed.parse::<f64>() {
+            Ok(speed) if speed.is_finite() && speed > 0.0 => speed,
+            _ => return Err(format!("{} isn't a valid top speed", self.top_speed))
+        };
+        let num_gears = match self.num_gears.parse::<usize>() {
+            Ok(num) if (1..=MAX_SUGGESTED_GEARS).contains(&num) => num,
+            _ => return Err(format!("Number of gears must be between 1 and {}", MAX_SUGGESTED_GEARS))
+        };
+        Ok(gearing_calculator.suggest_gear_ratios(top_speed, num_gears))
+    }
+
+    pub fn create_modal_content(&self, gearing_calculator: &GearingCalculator) -> Column<'_, EditMessage> {
+        let inputs = Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(Text::new("Top speed (km/h)").size(14))
+            .push(text_input(
+                "",
+                &self.top_speed,
+                EditMessage::RatioSuggestionTopSpeedUpdate
+            ).width(Length::Units(60)))
+            .push(Text::new("Gears").size(14))
+            .push(text_input(
+                "",
+                &self.num_gears,
+                EditMessage::RatioSuggestionGearCountUpdate
+            ).width(Length::Units(40)));
+
+        let mut layout = Column::new()
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .push(Text::new("Suggest gear ratios"))
+            .push(inputs);
+
+        let mut use_button = button("Use ratios").style(theme::Button::Positive);
+        match self.suggested_ratios(gearing_calculator) {
+            Ok(ratios) => {
+                let table = ratios.iter().enumerate().fold(
+                    Column::new().spacing(3).align_items(Alignment::Start),
+                    |col, (idx, ratio)| {
+                        col.push(
+                            Row::new()
+                                .spacing(10)
+                                .push(Text::new(format!("Gear {}", idx + 1)).size(14).width(Length::Units(50)))
+                                .push(Text::new(format!("{:.3}", ratio)).size(14).width(Length::Units(50)))
+                                .push(Text::new(format!("{} km/h", gearing_calculator.max_speed_for_ratio(*ratio).round())).size(14))
+                        )
+                    });
+                layout = layout.push(table);
+                use_button = use_button.on_press(EditMessage::UseSuggestedRatios());
+            }
+            Err(e) => {
+                layout = layout.push(Text::new(e).size(14));
+            }
+        }
+        layout.push(
+            Row::new()
+                .spacing(10)
+                .push(use_button)
+                .push(button("Cancel").on_press(EditMessage::CloseRatioSuggestion()))
+        )
+    }
+}
@@ -21,10 +21,12 @@
 
 mod gears;
 mod fuel_econ;
+mod downshift;
+mod profile;
 
 use std::fmt::{Display, Formatter};
 use super::{Message, Tab};
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 
 use iced::{Alignment, Background, ContentFit, Element, Length, Padding, theme, Theme};
 use iced::alignment::{Horizontal, Vertical};
@@ -33,25 +35,29 @@ use iced_aw::{TabLabel};
 use iced_aw::style::colors::WHITE;
 use iced_native::{Color};
 use iced_native::widget::scrollable::Properties;
-use iced_native::widget::{button, checkbox, container, Svg, text};
+use iced_native::widget::{button, checkbox, container, Svg, text, text_input};
 use iced_native::svg::Handle;
+use rfd::FileDialog;
 use tracing::{error, info};
 use crate::assetto_corsa::Car;
-use crate::assetto_corsa::car::ENGINE_CRANE_CAR_TAG;
-use crate::assetto_corsa::car::ui::CarUiData;
+use crate::fabricator;
 
 use crate::ui::{ApplicationData, ListPath};
-use crate::ui::colour::warn_yellow;
-use crate::ui::edit::fuel_econ::{consumption_configuration_builder, FuelEfficiencyConfig, FuelEfficiencyConfigType};
-use crate::ui::edit::gears::{gear_configuration_builder, convert_gear_configuration, FinalDriveUpdate, GearConfig, GearConfigType, GearUpdateType, GearConfiguration};
+use crate::ui::colour::{fail_red, warn_yellow};
+use crate::ui::edit::downshift::{DownshiftProtectionConfig, DownshiftProtectionUpdate};
+use crate::ui::edit::fuel_econ::{consumption_configuration_builder, current_consumption_configuration, FuelEfficiencyConfig, FuelEfficiencyConfigType};
+use crate::ui::edit::profile::CarTuningProfile;
+use crate::ui::edit::gears::{gear_configuration_builder, convert_gear_configuration, FinalDriveUpdate, GearConfig, GearConfigType, GearUpdateType, GearConfiguration, RatioSuggestion};
 use crate::ui::elements::modal::Modal;
-use crate::ui::image_data::{ALERT_TRIANGLE, ICE_CREAM_SVG};
+use crate::ui::image_data::{ALERT_TRIANGLE, CIRCLE_CROSS, ICE_CREAM_SVG};
 use crate::ui::settings::Setting;
+use crate::utils::filesystem;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum EditOption {
     Gears,
-    FuelEcon
+    FuelEcon,
+    DownshiftProtection
 }
 
 impl EditOption {
@@ -59,11 +65,12 @@ impl EditOption {
         match self {
             EditOption::Gears => "Gears",
             EditOption::FuelEcon => "Fuel Consumption",
+            EditOption::DownshiftProtection => "Downshift Protection",
         }
     }
 
     pub fn all() -> Vec<EditOption> {
-        vec![EditOption::Gears, EditOption::FuelEcon,]
+        vec![EditOption::Gears, EditOption::FuelEcon, EditOption::DownshiftProtection,]
     }
 }
 
@@ -78,36 +85,93 @@ pub struct EditTab {
     edit_types: Vec<EditOption>,
     current_edit_type: EditOption,
     editable_car_paths: Vec<ListPath>,
+    car_filter: String,
     current_car_path: Option<PathBuf>,
     gear_configuration: Option<GearConfig>,
     fuel_eff_data: Option<FuelEfficiencyConfig>,
+    downshift_data: Option<DownshiftProtectionConfig>,
     update_successful: bool,
     modal_state: ModalState,
-    show_all_cars: bool
+    show_all_cars: bool,
+    base_car_edit_confirmed: bool,
+    remember_acknowledgement: bool,
+    sfx_issue: Option<String>,
+    car_data_issue: Option<String>,
+    ratio_suggestion: Option<RatioSuggestion>,
+    has_swap_backup: bool,
+    /// Repack the data folder into data.acd when exporting
+    export_repack_data: bool,
+    /// Include the swap report in exported packages
+    export_swap_report: bool,
+    /// An export chosen by the user that hasn't been started yet
+    pending_export: Option<CarExport>,
+    export_in_progress: bool
+}
+
+/// Everything needed to package a car for sharing, captured from the edit tab so that the export
+/// can be run away from the UI thread
+#[derive(Debug, Clone)]
+pub struct CarExport {
+    pub car_path: PathBuf,
+    pub out_file: PathBuf,
+    pub repack_data: bool,
+    pub include_swap_report: bool
+}
+
+impl CarExport {
+    pub fn run(&self) -> Result<PathBuf, String> {
+        fabricator::export_car(&self.car_path, &self.out_file, self.repack_data, self.include_swap_report)
+            .map(|_| self.out_file.clone())
+            .map_err(|e| {
+                error!("Failed to export {}. {}", self.car_path.display(), e);
+                e.to_string()
+            })
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
 enum ModalState {
     Hidden,
     AfterUpdate,
-    AllCarsSelected
+    AllCarsSelected,
+    RatioSuggestion
 }
 
 #[derive(Debug, Clone)]
 pub enum EditMessage {
     EditTypeSelected(EditOption),
     CarSelected(ListPath),
+    CarFilterEntered(String),
     GearConfigSelected(GearConfigType),
     GearUpdate(GearUpdateType),
+    OpenRatioSuggestion(),
+    RatioSuggestionTopSpeedUpdate(String),
+    RatioSuggestionGearCountUpdate(String),
+    UseSuggestedRatios(),
+    CloseRatioSuggestion(),
     FinalDriveUpdate(FinalDriveUpdate),
     EfficiencyConfigSelected(FuelEfficiencyConfigType),
     FuelConsumptionUpdate(i32, String),
+    BaseFuelConsumptionUpdate(String),
+    SwitchToCspFuelConsumption(),
+    ImportFuelConsumptionCsv(),
+    ExportFuelConsumptionCsv(),
+    ImportTuningProfile(),
+    ExportTuningProfile(),
+    DownshiftProtectionUpdate(DownshiftProtectionUpdate),
     ApplyChanges(),
     ResetChanges(),
     ChangeConfirmation(),
     ShowAllCarsSelected(bool),
     ConfirmAllCars(),
-    DeclineAllCars()
+    DeclineAllCars(),
+    RememberAcknowledgementToggled(bool),
+    OpenCarFolder(),
+    ExportCar(),
+    ExportRepackToggled(bool),
+    ExportSwapReportToggled(bool),
+    RepairSfx(),
+    RevertLastSwap()
 }
 
 impl EditTab {
@@ -117,12 +181,24 @@ impl EditTab {
             edit_types: EditOption::all(),
             current_edit_type: EditOption::Gears,
             editable_car_paths: Vec::new(),
+            car_filter: String::new(),
             current_car_path: None,
             gear_configuration: None,
             fuel_eff_data: None,
+            downshift_data: None,
             update_successful: true,
             modal_state: ModalState::Hidden,
-            show_all_cars: false
+            show_all_cars: false,
+            base_car_edit_confirmed: false,
+            remember_acknowledgement: false,
+            sfx_issue: None,
+            car_data_issue: None,
+            ratio_suggestion: None,
+            has_swap_backup: false,
+            export_repack_data: true,
+            export_swap_report: false,
+            pending_export: None,
+            export_in_progress: false
         };
         e.load_car_list(&app_data);
         e
@@ -130,9 +206,8 @@ impl EditTab {
 
     fn load_car_list(&mut self, app_data: &ApplicationData) {
         self.editable_car_paths.clear();
-        self.gear_configuration = None;
-        self.fuel_eff_data = None;
-        self.current_car_path = None;
+        self.car_filter.clear();
+        self.clear_selected_car();
         if self.show_all_cars {
             self.editable_car_paths = app_data.assetto_corsa_data.available_cars.clone();
         } else {
@@ -140,17 +215,8 @@ impl EditTab {
             for car_path in &app_data.assetto_corsa_data.available_cars {
                 match Car::load_from_path(&car_path.full_path) {
                     Ok(mut car) => {
-                        if car.is_ac_car_tuner_tune() {
+                        if car.has_edit_marker() {
                             self.editable_car_paths.push(car_path.clone());
-                        } else {
-                            match CarUiData::from_car(&mut car) {
-                                Ok(ui_data) => {
-                                    if ui_data.ui_info.has_tag(ENGINE_CRANE_CAR_TAG) {
-                                        self.editable_car_paths.push(car_path.clone())
-                                    }
-                                }
-                                Err(_) => skip_count += 1
-                            } 
                         }
                     }
                     Err(_) => skip_count += 1
@@ -177,6 +243,16 @@ impl EditTab {
                     Some(selected_option),
                     EditMessage::GearConfigSelected).spacing(3).size(20).text_size(18))
             });
+        let has_calculator = match &self.gear_configuration {
+            Some(config) => config.gearing_calculator().is_some(),
+            None => false
+        };
+        let gear_config_row = match has_calculator {
+            true => gear_config_row.push(
+                Button::new(Text::new("Suggest ratios").size(14)).on_press(EditMessage::OpenRatioSuggestion())
+            ),
+            false => gear_config_row
+        };
         layout.push(horizontal_rule(5)).push(gear_config_row)
     }
 
@@ -198,6 +274,12 @@ impl EditTab {
     ) -> Column<'b, EditMessage>
     where 'b: 'a
     {
+        if !selected_option.is_csp_extended() {
+            let switch_row = Row::new().padding(Padding::from([0, 10])).spacing(10).align_items(Alignment::Center)
+                .push(Text::new("Car uses base game fuel consumption").size(18))
+                .push(Button::new("Switch to CSP fuel consumption").on_press(EditMessage::SwitchToCspFuelConsumption()));
+            return layout.push(horizontal_rule(5)).push(switch_row);
+        }
         let eff_config_row = 
             [FuelEfficiencyConfigType::ByFuelFlow, FuelEfficiencyConfigType::ByThermalEfficiency,]
             .iter().fold(
@@ -223,13 +305,96 @@ impl EditTab {
         }
     }
 
+    fn setup_current_fuel_econ_data(&mut self) {
+        if let Some(path_ref) = &self.current_car_path {
+            match current_consumption_configuration(path_ref) {
+                Ok(config) => { self.fuel_eff_data = Some(config) }
+                Err(e) => {
+                    error!(e)
+                }
+            }
+        }
+    }
+
+    /// Apply the gear and fuel consumption data in the tuning profile at `profile_path` to the
+    /// selected car. Every section is validated against the car before anything is written
+    fn import_tuning_profile(&mut self, profile_path: &Path, app_data: &ApplicationData) -> Result<(), String> {
+        let car_path = match &self.current_car_path {
+            None => return Err("No car selected".to_string()),
+            Some(path) => path.clone()
+        };
+        let profile = CarTuningProfile::from_file(profile_path)?;
+        let allow_unmarked = self.base_car_edit_confirmed || app_data.is_base_car_edit_acknowledged();
+        Car::load_from_path(&car_path).and_then(|mut car| {
+            car.check_editable(allow_unmarked)
+        }).map_err(|e| e.to_string())?;
+        let applied = profile.apply_to_car(&car_path)?;
+        if let Some(config) = &applied.gears {
+            config.write_to_car(&car_path).map_err(|e| {
+                format!("Failed to update gear data: {}", e)
+            })?;
+        }
+        if let Some(config) = &applied.fuel_consumption {
+            config.write_car_updates(&car_path, app_data.write_debug_values()).map_err(|e| {
+                format!("Failed to update fuel consumption data: {}", e)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn setup_downshift_data(&mut self) {
+        if let Some(path_ref) = &self.current_car_path {
+            match DownshiftProtectionConfig::from_car_path(path_ref) {
+                Ok(config) => { self.downshift_data = Some(config) }
+                Err(e) => {
+                    error!(e)
+                }
+            }
+        }
+    }
+
+    /// The export the user has chosen, if any. The export is marked as in progress until
+    /// [`EditTab::export_finished`] is called
+    pub fn take_pending_export(&mut self) -> Option<CarExport> {
+        let export = self.pending_export.take()?;
+        self.export_in_progress = true;
+        Some(export)
+    }
+
+    pub fn export_finished(&mut self, result: Result<PathBuf, String>) {
+        self.export_in_progress = false;
+        match result {
+            Ok(out_file) => {
+                self.update_successful = true;
+                info!("Exported car to {}", out_file.display());
+            }
+            Err(e) => {
+                self.update_successful = false;
+                self.status_message = format!("Failed to export car: {}", e);
+            }
+        }
+        self.modal_state = ModalState::AfterUpdate;
+    }
+
     pub fn update(&mut self, message: EditMessage, app_data: &ApplicationData) {
         match message {
             EditMessage::CarSelected(path_ref) => {
                 self.current_car_path = Some(path_ref.full_path.clone());
+                self.check_car_data();
+                self.check_sfx();
+                self.check_swap_backup();
                 match self.current_edit_type {
                     EditOption::Gears => self.setup_gear_data(),
-                    EditOption::FuelEcon => self.setup_fuel_econ_data(FuelEfficiencyConfigType::ByFuelFlow),
+                    EditOption::FuelEcon => self.setup_current_fuel_econ_data(),
+                    EditOption::DownshiftProtection => self.setup_downshift_data(),
+                }
+            }
+            EditMessage::CarFilterEntered(filter) => {
+                self.car_filter = filter;
+                if let Some(path) = &self.current_car_path {
+                    if !ListPath::find_in(&self.editable_car_paths, path).matches_filter(&self.car_filter) {
+                        self.clear_selected_car();
+                    }
                 }
             }
             EditMessage::EditTypeSelected(ty) => {
@@ -242,16 +407,19 @@ impl EditTab {
 
                 match ty {
                     EditOption::Gears => {
-                        if self.fuel_eff_data.is_some() {
-                            self.fuel_eff_data = None;
-                        }
+                        self.fuel_eff_data = None;
+                        self.downshift_data = None;
                         self.setup_gear_data()
                     },
                     EditOption::FuelEcon => {
-                        if self.gear_configuration.is_some() {
-                            self.gear_configuration = None;
-                        }
-                        self.setup_fuel_econ_data(FuelEfficiencyConfigType::ByFuelFlow)
+                        self.gear_configuration = None;
+                        self.downshift_data = None;
+                        self.setup_current_fuel_econ_data()
+                    }
+                    EditOption::DownshiftProtection => {
+                        self.gear_configuration = None;
+                        self.fuel_eff_data = None;
+                        self.setup_downshift_data()
                     }
                 }
             },
@@ -284,6 +452,43 @@ impl EditTab {
                     config.handle_gear_update(update_type);
                 }
             }
+            EditMessage::OpenRatioSuggestion() => {
+                if let Some(calc) = self.gear_configuration.as_ref().and_then(|c| c.gearing_calculator()) {
+                    self.ratio_suggestion = Some(RatioSuggestion::new(calc));
+                    self.modal_state = ModalState::RatioSuggestion;
+                }
+            }
+            EditMessage::RatioSuggestionTopSpeedUpdate(new_value) => {
+                if let Some(suggestion) = &mut self.ratio_suggestion {
+                    suggestion.set_top_speed(new_value);
+                }
+            }
+            EditMessage::RatioSuggestionGearCountUpdate(new_value) => {
+                if let Some(suggestion) = &mut self.ratio_suggestion {
+                    suggestion.set_num_gears(new_value);
+                }
+            }
+            EditMessage::UseSuggestedRatios() => {
+                if let (Some(suggestion), Some(config)) = (&self.ratio_suggestion, &mut self.gear_configuration) {
+                    let ratios = match config.gearing_calculator() {
+                        Some(calc) => suggestion.suggested_ratios(calc),
+                        None => Err("No gearing data available".to_string())
+                    };
+                    match ratios {
+                        Ok(ratios) => config.set_default_gear_ratios(&ratios),
+                        Err(e) => {
+                            error!("Couldn't use suggested ratios. {}", e);
+                            return;
+                        }
+                    }
+                }
+                self.ratio_suggestion = None;
+                self.modal_state = ModalState::Hidden;
+            }
+            EditMessage::CloseRatioSuggestion() => {
+                self.ratio_suggestion = None;
+                self.modal_state = ModalState::Hidden;
+            }
             EditMessage::EfficiencyConfigSelected(choice) => {
                 let current_config_type =
                     if let Some(config) = &self.fuel_eff_data {
@@ -304,6 +509,103 @@ impl EditTab {
                     config.update_for_rpm(rpm, new_value);
                 }
             }
+            EditMessage::BaseFuelConsumptionUpdate(new_value) => {
+                if let Some(config) = &mut self.fuel_eff_data {
+                    config.update_base_consumption(new_value);
+                }
+            }
+            EditMessage::SwitchToCspFuelConsumption() => {
+                self.setup_fuel_econ_data(FuelEfficiencyConfigType::ByFuelFlow);
+            }
+            EditMessage::ImportFuelConsumptionCsv() => {
+                if let Some(config) = &mut self.fuel_eff_data {
+                    let csv_path = match FileDialog::new().add_filter("CSV", &["csv"]).pick_file() {
+                        None => return,
+                        Some(path) => path
+                    };
+                    match config.import_csv(&csv_path) {
+                        Ok(num_updated) => info!("Imported {} fuel consumption values from {}", num_updated, csv_path.display()),
+                        Err(e) => {
+                            error!("Failed to import {}. {}", csv_path.display(), e);
+                            self.update_successful = false;
+                            self.status_message = format!("Failed to import fuel consumption data: {}", e);
+                            self.modal_state = ModalState::AfterUpdate;
+                        }
+                    }
+                }
+            }
+            EditMessage::ExportFuelConsumptionCsv() => {
+                if let Some(config) = &self.fuel_eff_data {
+                    let csv_path = match FileDialog::new().add_filter("CSV", &["csv"]).set_file_name("fuel_consumption.csv").save_file() {
+                        None => return,
+                        Some(path) => path
+                    };
+                    match config.export_csv(&csv_path) {
+                        Ok(_) => info!("Exported fuel consumption values to {}", csv_path.display()),
+                        Err(e) => {
+                            error!("Failed to export {}. {}", csv_path.display(), e);
+                            self.update_successful = false;
+                            self.status_message = format!("Failed to export fuel consumption data: {}", e);
+                            self.modal_state = ModalState::AfterUpdate;
+                        }
+                    }
+                }
+            }
+            EditMessage::ImportTuningProfile() => {
+                if self.current_car_path.is_none() {
+                    return;
+                }
+                let profile_path = match FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+                    None => return,
+                    Some(path) => path
+                };
+                self.modal_state = ModalState::AfterUpdate;
+                match self.import_tuning_profile(&profile_path, app_data) {
+                    Ok(_) => {
+                        self.update_successful = true;
+                        info!("Applied tuning profile {}", profile_path.display());
+                    }
+                    Err(e) => {
+                        error!("Failed to apply tuning profile {}. {}", profile_path.display(), e);
+                        self.update_successful = false;
+                        self.status_message = format!("Failed to import tuning profile: {}", e);
+                    }
+                }
+            }
+            EditMessage::ExportTuningProfile() => {
+                if let Some(car_path) = &self.current_car_path {
+                    let profile = match CarTuningProfile::from_car(car_path,
+                                                                   self.gear_configuration.as_ref(),
+                                                                   self.fuel_eff_data.as_ref()) {
+                        Ok(profile) => profile,
+                        Err(e) => {
+                            error!("Failed to create tuning profile. {}", e);
+                            self.update_successful = false;
+                            self.status_message = format!("Failed to export tuning profile: {}", e);
+                            self.modal_state = ModalState::AfterUpdate;
+                            return;
+                        }
+                    };
+                    let profile_path = match FileDialog::new().add_filter("JSON", &["json"]).set_file_name("tuning_profile.json").save_file() {
+                        None => return,
+                        Some(path) => path
+                    };
+                    match profile.write_to_file(&profile_path) {
+                        Ok(_) => info!("Exported tuning profile to {}", profile_path.display()),
+                        Err(e) => {
+                            error!("Failed to export {}. {}", profile_path.display(), e);
+                            self.update_successful = false;
+                            self.status_message = format!("Failed to export tuning profile: {}", e);
+                            self.modal_state = ModalState::AfterUpdate;
+                        }
+                    }
+                }
+            }
+            EditMessage::DownshiftProtectionUpdate(update) => {
+                if let Some(config) = &mut self.downshift_data {
+                    config.handle_update(update);
+                }
+            }
             EditMessage::FinalDriveUpdate(update_type) => {
                 if let Some(config) = &mut self.gear_configuration {
                     config.handle_final_drive_update(update_type);
@@ -312,6 +614,18 @@ impl EditTab {
             EditMessage::ApplyChanges() => {
                 self.status_message = "Updating...".to_string();
                 self.modal_state = ModalState::AfterUpdate;
+                if let Some(car_path) = &self.current_car_path {
+                    let allow_unmarked = self.base_car_edit_confirmed || app_data.is_base_car_edit_acknowledged();
+                    let check_result = Car::load_from_path(car_path).and_then(|mut car| {
+                        car.check_editable(allow_unmarked)
+                    });
+                    if let Err(e) = check_result {
+                        self.update_successful = false;
+                        self.status_message = e.to_string();
+                        error!("Refusing to update {}. {}", car_path.display(), e);
+                        return;
+                    }
+                }
                 match self.current_edit_type {
                     EditOption::Gears => {
                         if let Some(config) = &mut self.gear_configuration {
@@ -333,7 +647,7 @@ impl EditTab {
                     EditOption::FuelEcon => {
                         if let Some(config) = &mut self.fuel_eff_data {
                             if let Some(car_path) = &self.current_car_path {
-                                match config.write_car_updates(car_path) {
+                                match config.write_car_updates(car_path, app_data.write_debug_values()) {
                                     Ok(_) => {
                                         self.update_successful = true;
                                         info!("Successfully updated fuel consumption data for {}", car_path.display())
@@ -347,6 +661,23 @@ impl EditTab {
                             }
                         }
                     }
+                    EditOption::DownshiftProtection => {
+                        if let Some(config) = &self.downshift_data {
+                            if let Some(car_path) = &self.current_car_path {
+                                match config.write_to_car(car_path) {
+                                    Ok(_) => {
+                                        self.update_successful = true;
+                                        info!("Successfully updated downshift protection data for {}", car_path.display())
+                                    },
+                                    Err(e) => {
+                                        self.update_successful = false;
+                                        self.status_message = format!("Failed to update downshift protection data: {}", e);
+                                        error!("Failed to update downshift protection data for {}. {}", car_path.display(), e);
+                                    }
+                                }
+                            }
+                        }
+                    }
                 }
             }
             EditMessage::ResetChanges() => {
@@ -361,25 +692,147 @@ impl EditTab {
             }
             EditMessage::ShowAllCarsSelected(is_selected) => {
                 match is_selected {
-                    true => self.modal_state = ModalState::AllCarsSelected,
+                    true => {
+                        if self.base_car_edit_confirmed || app_data.is_base_car_edit_acknowledged() {
+                            self.show_all_cars = true;
+                            self.load_car_list(&app_data);
+                        } else {
+                            self.modal_state = ModalState::AllCarsSelected
+                        }
+                    },
                     false => {
                         self.show_all_cars = false;
                         self.load_car_list(&app_data);
                     }
                 }
             }
-            EditMessage::ConfirmAllCars() => {
-                self.show_all_cars = true;
-                self.modal_state = ModalState::Hidden;
-                self.load_car_list(&app_data);
-            }
+            EditMessage::ConfirmAllCars() => self.confirm_all_cars(app_data),
             EditMessage::DeclineAllCars() => {
                 self.show_all_cars = false;
                 self.modal_state = ModalState::Hidden;
             }
+            EditMessage::RememberAcknowledgementToggled(remember) => {
+                self.remember_acknowledgement = remember;
+            }
+            EditMessage::OpenCarFolder() => {
+                if let Some(car_path) = &self.current_car_path {
+                    if let Err(e) = filesystem::open_in_file_manager(car_path) {
+                        error!("Failed to open {}. {}", car_path.display(), e.to_string());
+                    }
+                }
+            }
+            EditMessage::ExportCar() => {
+                if let Some(car_path) = &self.current_car_path {
+                    let car_name = car_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                    let out_file = match FileDialog::new()
+                        .add_filter("Zip", &["zip"])
+                        .add_filter("7-Zip", &["7z"])
+                        .set_file_name(&format!("{}.zip", car_name))
+                        .save_file() {
+                        None => return,
+                        Some(path) => path
+                    };
+                    self.pending_export = Some(CarExport {
+                        car_path: car_path.clone(),
+                        out_file,
+                        repack_data: self.export_repack_data,
+                        include_swap_report: self.export_swap_report
+                    });
+                }
+            }
+            EditMessage::ExportRepackToggled(repack) => self.export_repack_data = repack,
+            EditMessage::ExportSwapReportToggled(include) => self.export_swap_report = include,
+            EditMessage::RepairSfx() => {
+                if let Some(car_path) = &self.current_car_path {
+                    let repair_result = match app_data.get_ac_install_path() {
+                        None => Err("The Assetto Corsa install path isn't set".to_string()),
+                        Some(install_path) => assetto_corsa::car::repair_car_sfx(
+                            &assetto_corsa::Installation::from_path(install_path),
+                            car_path
+                        ).map_err(|e| e.to_string())
+                    };
+                    match repair_result {
+                        Ok(_) => {
+                            self.update_successful = true;
+                            info!("Regenerated sfx GUIDs for {}", car_path.display())
+                        }
+                        Err(e) => {
+                            self.update_successful = false;
+                            self.status_message = format!("Failed to repair sounds: {}", e);
+                            error!("Failed to repair sounds for {}. {}", car_path.display(), e);
+                        }
+                    }
+                    self.modal_state = ModalState::AfterUpdate;
+                    self.check_sfx();
+                }
+            }
+            EditMessage::RevertLastSwap() => {
+                if let Some(car_path) = &self.current_car_path {
+                    match fabricator::restore_backup(car_path) {
+                        Ok(_) => {
+                            self.update_successful = true;
+                            info!("Reverted last engine swap for {}", car_path.display())
+                        }
+                        Err(e) => {
+                            self.update_successful = false;
+                            self.status_message = format!("Failed to revert last swap: {}", e);
+                            error!("Failed to revert last engine swap for {}. {}", car_path.display(), e);
+                        }
+                    }
+                    self.modal_state = ModalState::AfterUpdate;
+                    self.check_swap_backup();
+                }
+            }
         }
     }
 
+    fn check_sfx(&mut self) {
+        self.sfx_issue = match &self.current_car_path {
+            None => None,
+            Some(car_path) => assetto_corsa::car::validate_car_sfx(car_path).err().map(|e| e.to_string())
+        };
+    }
+
+    /// Check that the selected car's data can be read. A data.acd packed for a different car
+    /// folder can't be decoded so none of the edit options will load for it
+    fn check_car_data(&mut self) {
+        self.car_data_issue = match &self.current_car_path {
+            None => None,
+            Some(car_path) => match Car::load_from_path(car_path) {
+                Ok(_) => None,
+                Err(e) => {
+                    error!("Failed to load {}. {}", car_path.display(), e);
+                    Some(e.to_string())
+                }
+            }
+        };
+    }
+
+    fn clear_selected_car(&mut self) {
+        self.gear_configuration = None;
+        self.fuel_eff_data = None;
+        self.downshift_data = None;
+        self.current_car_path = None;
+        self.sfx_issue = None;
+        self.car_data_issue = None;
+        self.has_swap_backup = false;
+        self.ratio_suggestion = None;
+    }
+
+    fn check_swap_backup(&mut self) {
+        self.has_swap_backup = match &self.current_car_path {
+            None => false,
+            Some(car_path) => fabricator::has_backup(car_path)
+        };
+    }
+
+    fn confirm_all_cars(&mut self, app_data: &ApplicationData) {
+        self.show_all_cars = true;
+        self.base_car_edit_confirmed = true;
+        self.modal_state = ModalState::Hidden;
+        self.load_car_list(app_data);
+    }
+
     fn reload_selected_car(&mut self) {
         match &self.current_car_path {
             None => error!("Reload requested when no car selected"),
@@ -391,9 +844,16 @@ impl EditTab {
                             error!(e)
                         }
                     }
-                    EditOption::FuelEcon => {
-                        if self.gear_configuration.is_some() {
-                            self.gear_configuration = None;
+                    EditOption::FuelEcon => match current_consumption_configuration(&current_car_path) {
+                        Ok(config) => { self.fuel_eff_data = Some(config) }
+                        Err(e) => {
+                            error!(e)
+                        }
+                    }
+                    EditOption::DownshiftProtection => match DownshiftProtectionConfig::from_car_path(&current_car_path) {
+                        Ok(config) => { self.downshift_data = Some(config) }
+                        Err(e) => {
+                            error!(e)
                         }
                     }
                 }
@@ -411,7 +871,10 @@ impl EditTab {
                 Setting::AcPath => self.load_car_list(app_data),
                 _ => {}
             }
-            Message::EngineSwapRequested => self.load_car_list(app_data),
+            Message::EngineSwapRequested |
+            Message::ImportSettingsRequested |
+            Message::ProfileSelected(_) => self.load_car_list(app_data),
+            Message::BaseCarEditAcknowledged => self.confirm_all_cars(app_data),
             _ => {}
         }
     }
@@ -479,6 +942,11 @@ impl EditTab {
                     button(text("I've changed my mind").size(20))
                         .style(theme::Button::Positive)
                         .on_press(EditMessage::DeclineAllCars());
+                let remember = checkbox(
+                    "Don't warn me again",
+                    self.remember_acknowledgement,
+                    |new_val| EditMessage::RememberAcknowledgementToggled(new_val)
+                ).spacing(3).text_size(14);
                 let modal_contents = container(
                     Column::new()
                         .align_items(Alignment::Center)
@@ -486,6 +954,26 @@ impl EditTab {
                         .push(container(text(modal_message)))
                         .push(confirm)
                         .push(decline)
+                        .push(remember)
+                ).style(theme::Container::Custom(
+                    Box::new(f)
+                )).padding(20);
+                Some(modal_contents.into())
+            }
+            ModalState::RatioSuggestion => {
+                let calc = self.gear_configuration.as_ref().and_then(|c| c.gearing_calculator())?;
+                let suggestion = self.ratio_suggestion.as_ref()?;
+                let f: fn(&Theme) -> container::Appearance = |_theme: &Theme| {
+                    container::Appearance{
+                        text_color: None,
+                        background: Some(Background::Color(WHITE)),
+                        border_radius: 1.0,
+                        border_width: 1.0,
+                        border_color: Color::BLACK,
+                    }
+                };
+                let modal_contents = container(
+                    suggestion.create_modal_content(calc)
                 ).style(theme::Container::Custom(
                     Box::new(f)
                 )).padding(20);
@@ -515,7 +1003,10 @@ impl Tab for EditTab {
                 Some(self.current_edit_type),
                 EditMessage::EditTypeSelected,
             ));
-        if self.current_edit_type == EditOption::FuelEcon {
+        let uses_csp_fuel_data = self.fuel_eff_data.as_ref().map_or(true, |config| {
+            config.get_config_type().is_csp_extended()
+        });
+        if self.current_edit_type == EditOption::FuelEcon && uses_csp_fuel_data {
             let svg = Svg::new(Handle::from_memory(ALERT_TRIANGLE))
                 .style(theme::Svg::custom_fn(|_| {
                     svg::Appearance{color: Some(warn_yellow())}
@@ -535,12 +1026,12 @@ impl Tab for EditTab {
             .push(Text::new("Type"))
             .push(edit_type_selector);
         
-        let current_car = match &self.current_car_path {
-            None => { None }
-            Some(path) => {
-                Some(ListPath {full_path: path.clone()})
-            }
-        };
+        let current_car = self.current_car_path.as_ref().map(|path| {
+            ListPath::find_in(&self.editable_car_paths, path)
+        });
+        let filtered_car_paths: Vec<ListPath> = self.editable_car_paths.iter().filter(|car| {
+            car.matches_filter(&self.car_filter)
+        }).cloned().collect();
 
         let mut command_row = Row::new().spacing(5);
         let mut apply_but = Button::new("Apply")
@@ -548,14 +1039,36 @@ impl Tab for EditTab {
         let mut reset_but =
             Button::new("Undo")
                 .style(theme::Button::Destructive);
+        let mut open_folder_but = Button::new("Open folder");
+        let mut export_car_but = Button::new("Export car");
+        let mut import_profile_but = Button::new("Import profile");
+        let mut export_profile_but = Button::new("Export profile");
+        let mut revert_swap_but = Button::new("Revert last swap")
+            .style(theme::Button::Destructive);
         if let Some(_) = current_car {
             apply_but = apply_but.on_press(EditMessage::ApplyChanges());
             reset_but = reset_but.on_press(EditMessage::ResetChanges());
+            open_folder_but = open_folder_but.on_press(EditMessage::OpenCarFolder());
+            if !self.export_in_progress {
+                export_car_but = export_car_but.on_press(EditMessage::ExportCar());
+            }
+            import_profile_but = import_profile_but.on_press(EditMessage::ImportTuningProfile());
+            export_profile_but = export_profile_but.on_press(EditMessage::ExportTuningProfile());
+            if self.has_swap_backup {
+                revert_swap_but = revert_swap_but.on_press(EditMessage::RevertLastSwap());
+            }
         }
-        command_row = command_row.push(apply_but).push(reset_but);
+        command_row = command_row
+            .push(apply_but)
+            .push(reset_but)
+            .push(open_folder_but)
+            .push(export_car_but)
+            .push(import_profile_but)
+            .push(export_profile_but)
+            .push(revert_swap_but);
         let car_select_row = Row::new().padding(0).spacing(8).align_items(Alignment::Center)
             .push(pick_list(
-                &self.editable_car_paths,
+                filtered_car_paths,
                 current_car,
                 EditMessage::CarSelected,
             ))
@@ -567,14 +1080,54 @@ impl Tab for EditTab {
         let car_select_container = Column::new()
             .align_items(Alignment::Start)
             .push(Text::new("Assetto Corsa car"))
+            .push(text_input("Filter", &self.car_filter, EditMessage::CarFilterEntered).width(Length::Units(250)))
             .push(car_select_row);
 
-        let select_container = Column::new()
+        let export_options_row = Row::new().spacing(10)
+            .push(checkbox(
+                "Repack data.acd on export",
+                self.export_repack_data,
+                EditMessage::ExportRepackToggled
+            ).spacing(3).text_size(14))
+            .push(checkbox(
+                "Include swap report on export",
+                self.export_swap_report,
+                EditMessage::ExportSwapReportToggled
+            ).spacing(3).text_size(14));
+        let mut select_container = Column::new()
             .padding(Padding::from([0, 10]))
             .spacing(5)
             .push(edit_select_container)
             .push(car_select_container)
-            .push(command_row);
+            .push(command_row)
+            .push(export_options_row);
+        if let Some(car_data_issue) = &self.car_data_issue {
+            let svg = Svg::new(Handle::from_memory(CIRCLE_CROSS))
+                .style(theme::Svg::custom_fn(|_| {
+                    svg::Appearance{color: Some(fail_red())}
+                }))
+                .height(Length::Units(25))
+                .width(Length::Units(25));
+            select_container = select_container.push(
+                Row::new().spacing(5).align_items(Alignment::Center)
+                    .push(svg)
+                    .push(Text::new(format!("Can't read the car data. {}", car_data_issue)).size(14))
+            );
+        }
+        if let Some(sfx_issue) = &self.sfx_issue {
+            let svg = Svg::new(Handle::from_memory(ALERT_TRIANGLE))
+                .style(theme::Svg::custom_fn(|_| {
+                    svg::Appearance{color: Some(warn_yellow())}
+                }))
+                .height(Length::Units(25))
+                .width(Length::Units(25));
+            select_container = select_container.push(
+                Row::new().spacing(5).align_items(Alignment::Center)
+                    .push(svg)
+                    .push(Text::new(format!("Car may have no sound. {}", sfx_issue)).size(14))
+                    .push(Button::new("Repair sounds").on_press(EditMessage::RepairSfx()))
+            );
+        }
 
         let mut layout = Column::new()
             .align_items(Alignment::Fill)
@@ -593,9 +1146,21 @@ impl Tab for EditTab {
             EditOption::FuelEcon => {
                 if let Some(fuel_econ_data) = &self.fuel_eff_data {
                     layout = self.add_fuel_econ_config_selector_row(layout, fuel_econ_data.get_config_type());
+                    if fuel_econ_data.get_config_type().is_csp_extended() {
+                        layout = layout.push(
+                            Row::new().padding(Padding::from([0, 10])).spacing(5)
+                                .push(Button::new("Import CSV").on_press(EditMessage::ImportFuelConsumptionCsv()))
+                                .push(Button::new("Export CSV").on_press(EditMessage::ExportFuelConsumptionCsv()))
+                        );
+                    }
                     layout = fuel_econ_data.add_editable_list(layout);
                 }
             }
+            EditOption::DownshiftProtection => {
+                if let Some(downshift_data) = &self.downshift_data {
+                    layout = downshift_data.add_editable_list(layout.push(horizontal_rule(5)));
+                }
+            }
         }
 
         let content : Element<'_, EditMessage> =
@@ -606,12 +1171,19 @@ impl Tab for EditTab {
                     .padding(20)
             ).horizontal_scroll(Properties::default()).into();
 
+        // A remembered confirmation has to be persisted in the settings so it is raised to the
+        // top level rather than being handled by this tab
+        let remember_acknowledgement = self.remember_acknowledgement;
+        let to_app_message = move |message: EditMessage| match message {
+            EditMessage::ConfirmAllCars() if remember_acknowledgement => Message::BaseCarEditAcknowledged,
+            _ => Message::Edit(message)
+        };
         return match self.get_modal_content() {
-            None => content.map(Message::Edit),
+            None => content.map(to_app_message),
             Some(modal_content) => {
                 let r : Element<'_, EditMessage> =
                     Modal::new(content, modal_content).into();
-                r.map(Message::Edit)
+                r.map(to_app_message)
             }
         }
     }
@@ -0,0 +1,171 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use crate::ui::edit::fuel_econ::{consumption_configuration_builder, current_consumption_configuration, FuelEfficiencyConfig, FuelEfficiencyConfigType};
+use crate::ui::edit::gears::{gear_configuration_builder, GearConfig};
+
+const PROFILE_VERSION: u32 = 1;
+/// Matches the maximum number of gears that can be added in the gear editor
+const MAX_PROFILE_GEARS: usize = 10;
+
+/// A combined gearing and fuel consumption tune that can be exported from one car and applied
+/// to another
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CarTuningProfile {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gears: Option<GearProfile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fuel_consumption: Option<FuelConsumptionProfile>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GearProfile {
+    ratios: Vec<f64>,
+    final_drive: f64
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FuelConsumptionProfile {
+    config_type: FuelEfficiencyConfigType,
+    values: Vec<(i32, f64)>
+}
+
+/// The configs that result from applying a [`CarTuningProfile`] to a car
+pub struct AppliedProfile {
+    pub gears: Option<GearConfig>,
+    pub fuel_consumption: Option<FuelEfficiencyConfig>
+}
+
+impl GearProfile {
+    fn from_config(config: &GearConfig) -> GearProfile {
+        GearProfile { ratios: config.default_gear_ratios(), final_drive: config.default_final_drive() }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.ratios.is_empty() {
+            return Err("Gear profile contains no gears".to_string());
+        }
+        if self.ratios.len() > MAX_PROFILE_GEARS {
+            return Err(format!("Gear profile contains {} gears. A maximum of {} is supported",
+                               self.ratios.len(), MAX_PROFILE_GEARS));
+        }
+        if let Some((idx, ratio)) = self.ratios.iter().enumerate().find(|(_, r)| !r.is_finite() || **r <= 0.0) {
+            return Err(format!("Gear {} has invalid ratio {}", idx + 1, ratio));
+        }
+        if !self.final_drive.is_finite() || self.final_drive <= 0.0 {
+            return Err(format!("Invalid final drive ratio {}", self.final_drive));
+        }
+        Ok(())
+    }
+}
+
+impl CarTuningProfile {
+    pub fn new(gears: Option<&GearConfig>, fuel_consumption: Option<&FuelEfficiencyConfig>) -> CarTuningProfile {
+        CarTuningProfile {
+            version: PROFILE_VERSION,
+            gears: gears.map(GearProfile::from_config),
+            fuel_consumption: fuel_consumption.map(|config| FuelConsumptionProfile {
+                config_type: config.get_config_type(),
+                values: config.export_values()
+            })
+        }
+    }
+
+    /// Build a profile for the car at `car_path`, preferring any unsaved edits that are passed in
+    /// over the values currently stored in the car
+    pub fn from_car(car_path: &PathBuf,
+                    gears: Option<&GearConfig>,
+                    fuel_consumption: Option<&FuelEfficiencyConfig>) -> Result<CarTuningProfile, String>
+    {
+        let loaded_gears = match gears {
+            Some(_) => None,
+            None => gear_configuration_builder(car_path).map_err(|e| {
+                warn!("Gear data won't be included in tuning profile. {}", e);
+            }).ok()
+        };
+        let loaded_fuel = match fuel_consumption {
+            Some(_) => None,
+            None => current_consumption_configuration(car_path).map_err(|e| {
+                warn!("Fuel consumption data won't be included in tuning profile. {}", e);
+            }).ok()
+        };
+        let profile = CarTuningProfile::new(gears.or(loaded_gears.as_ref()),
+                                            fuel_consumption.or(loaded_fuel.as_ref()));
+        if profile.gears.is_none() && profile.fuel_consumption.is_none() {
+            return Err(format!("No gear or fuel consumption data could be loaded from {}", car_path.display()));
+        }
+        Ok(profile)
+    }
+
+    pub fn from_file(path: &Path) -> Result<CarTuningProfile, String> {
+        let data = std::fs::read_to_string(path).map_err(|e| {
+            format!("Failed to read {}. {}", path.display(), e.to_string())
+        })?;
+        let profile: CarTuningProfile = serde_json::from_str(&data).map_err(|e| {
+            format!("Failed to parse {}. {}", path.display(), e.to_string())
+        })?;
+        if profile.version > PROFILE_VERSION {
+            return Err(format!("{} has unsupported profile version {}", path.display(), profile.version));
+        }
+        Ok(profile)
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(self).map_err(|e| {
+            format!("Failed to encode tuning profile. {}", e.to_string())
+        })?;
+        std::fs::write(path, data).map_err(|e| {
+            format!("Failed to write {}. {}", path.display(), e.to_string())
+        })
+    }
+
+    /// Validate each section of the profile against the car at `car_path` and return the updated
+    /// configs. Nothing is written to the car
+    pub fn apply_to_car(&self, car_path: &PathBuf) -> Result<AppliedProfile, String> {
+        let gears = match &self.gears {
+            None => None,
+            Some(gear_profile) => {
+                gear_profile.validate().map_err(|e| format!("Invalid gear data. {}", e))?;
+                let mut config = gear_configuration_builder(car_path)?;
+                config.set_default_gearing(&gear_profile.ratios, gear_profile.final_drive);
+                Some(config)
+            }
+        };
+        let fuel_consumption = match &self.fuel_consumption {
+            None => None,
+            Some(fuel_profile) => {
+                if fuel_profile.values.is_empty() {
+                    return Err("Invalid fuel consumption data. No rpm values found".to_string());
+                }
+                let mut config = consumption_configuration_builder(fuel_profile.config_type, car_path)?;
+                config.import_values(&fuel_profile.values).map_err(|e| {
+                    format!("Invalid fuel consumption data. {}", e)
+                })?;
+                Some(config)
+            }
+        };
+        Ok(AppliedProfile { gears, fuel_consumption })
+    }
+}
@@ -26,3 +26,5 @@ pub(crate) const WINDOW_LOGO: &[u8] = include_bytes!("resources/eng-logo.ico");
 pub(crate) const CIRCLE_TICK: &[u8] = include_bytes!("resources/check-circle-fill.svg");
 pub(crate) const CIRCLE_CROSS: &[u8] = include_bytes!("resources/x-circle-fill.svg");
 pub(crate) const ALERT_TRIANGLE: &[u8] = include_bytes!("resources/exclamation-triangle.svg");
+pub(crate) const STAR_SVG: &[u8] = include_bytes!("resources/star.svg");
+pub(crate) const STAR_FILL_SVG: &[u8] = include_bytes!("resources/star-fill.svg");
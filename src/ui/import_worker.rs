@@ -0,0 +1,214 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use iced::{subscription, Subscription};
+use iced::futures::channel::mpsc;
+use iced::futures::{future, StreamExt};
+use tracing::{info, warn};
+
+use crate::data::{CrateEngine, FromBeamNGModOptions};
+use crate::ui::Message;
+
+/// Everything needed to create crate engines from a set of BeamNG mods, captured from the crate
+/// engine tab when the import is requested so that it can be run away from the UI thread
+pub struct BulkImportJob {
+    pub id: u64,
+    pub mod_paths: Vec<PathBuf>,
+    pub crate_engine_path: PathBuf,
+    pub options: FromBeamNGModOptions,
+    /// The Automation data hashes of the crate engines that already exist. Mods whose engine
+    /// matches one of these are skipped so that repeated imports don't create duplicates
+    pub existing_hashes: BTreeSet<[u8; 32]>
+}
+
+/// Where an in-progress bulk import has got to
+#[derive(Debug, Clone)]
+pub struct BulkImportProgress {
+    pub mod_number: usize,
+    pub mod_count: usize,
+    pub mod_path: PathBuf
+}
+
+impl Display for BulkImportProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Importing mod {} of {}: {}",
+               self.mod_number, self.mod_count, display_name(&self.mod_path))
+    }
+}
+
+/// The outcome of each mod in a finished bulk import
+#[derive(Debug, Clone, Default)]
+pub struct BulkImportSummary {
+    pub created: Vec<PathBuf>,
+    /// Mods whose engine is already in the crate
+    pub skipped: Vec<PathBuf>,
+    /// Mods that a crate engine couldn't be created from, with the reason why
+    pub failed: Vec<(PathBuf, String)>
+}
+
+impl Display for BulkImportSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Created {} crate engines. Skipped {} mods already in the crate. {} failed",
+               self.created.len(), self.skipped.len(), self.failed.len())?;
+        for (mod_path, reason) in &self.failed {
+            write!(f, "\n{}: {}", display_name(mod_path), reason)?;
+        }
+        Ok(())
+    }
+}
+
+fn display_name(mod_path: &Path) -> String {
+    mod_path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+#[derive(Debug, Clone)]
+pub enum BulkImportEvent {
+    Progress(BulkImportProgress),
+    Finished(BulkImportSummary)
+}
+
+enum WorkerState {
+    Starting(Arc<BulkImportJob>),
+    Running(mpsc::UnboundedReceiver<BulkImportEvent>),
+    Finished
+}
+
+/// Run `job` on a background thread, producing a [`Message::BulkImportUpdate`] as each mod is
+/// started and once more with the summary when every mod has been tried
+pub fn bulk_import_worker(job: Option<Arc<BulkImportJob>>) -> Subscription<Message> {
+    match job {
+        None => Subscription::none(),
+        // Tagged so the id can't match that of a swap worker subscription
+        Some(job) => subscription::unfold(("bulk-import", job.id), WorkerState::Starting(job), next_event)
+    }
+}
+
+async fn next_event(state: WorkerState) -> (Option<Message>, WorkerState) {
+    match state {
+        WorkerState::Starting(job) => {
+            let (sender, receiver) = mpsc::unbounded();
+            thread::spawn(move || {
+                let summary = job.run(&sender);
+                let _ = sender.unbounded_send(BulkImportEvent::Finished(summary));
+            });
+            (None, WorkerState::Running(receiver))
+        }
+        WorkerState::Running(mut receiver) => match receiver.next().await {
+            Some(event) => (Some(Message::BulkImportUpdate(event)), WorkerState::Running(receiver)),
+            None => (None, WorkerState::Finished)
+        }
+        WorkerState::Finished => {
+            future::pending::<()>().await;
+            (None, WorkerState::Finished)
+        }
+    }
+}
+
+impl BulkImportJob {
+    fn run(&self, sender: &mpsc::UnboundedSender<BulkImportEvent>) -> BulkImportSummary {
+        self.run_with(sender, |mod_path| CrateEngine::from_beamng_mod_zip(mod_path, self.options.clone()))
+    }
+
+    fn run_with(&self,
+                sender: &mpsc::UnboundedSender<BulkImportEvent>,
+                create_engine: impl Fn(&Path) -> Result<CrateEngine, String>) -> BulkImportSummary {
+        let mod_count = self.mod_paths.len();
+        info!("Creating crate engines from {} BeamNG mods", mod_count);
+        let mut known_hashes = self.existing_hashes.clone();
+        let mut summary = BulkImportSummary::default();
+        for (idx, mod_path) in self.mod_paths.iter().enumerate() {
+            let _ = sender.unbounded_send(BulkImportEvent::Progress(BulkImportProgress {
+                mod_number: idx + 1,
+                mod_count,
+                mod_path: mod_path.clone()
+            }));
+            let crate_eng = match create_engine(mod_path) {
+                Ok(crate_eng) => crate_eng,
+                Err(e) => {
+                    warn!("Failed to create crate engine from {}. {}", mod_path.display(), e);
+                    summary.failed.push((mod_path.clone(), e));
+                    continue;
+                }
+            };
+            let hash = crate_eng.metadata().get_source().automation_data_hash();
+            if hash.is_some_and(|hash| known_hashes.contains(&hash)) {
+                info!("Skipping {} as its engine is already in the crate", mod_path.display());
+                summary.skipped.push(mod_path.clone());
+                continue;
+            }
+            match crate_eng.write_to_path(self.crate_engine_path.clone()) {
+                Ok(written_path) => {
+                    info!("Created crate engine {}", written_path.display());
+                    known_hashes.extend(hash);
+                    summary.created.push(written_path);
+                }
+                Err(e) => {
+                    warn!("Failed to write crate engine for {}. {}", mod_path.display(), e);
+                    summary.failed.push((mod_path.clone(), format!("Failed to write crate engine. {}", e)));
+                }
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::PathBuf;
+    use iced::futures::channel::mpsc;
+    use crate::data::{CrateEngine, FromBeamNGModOptions};
+    use crate::ui::import_worker::BulkImportJob;
+
+    #[test]
+    fn importing_the_same_engine_twice_creates_one_crate_file() {
+        let crate_engine_path = std::env::temp_dir().join(format!("bulk-import-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&crate_engine_path).unwrap();
+        let mod_path = PathBuf::from("dawnv6.zip");
+        let job = BulkImportJob {
+            id: 0,
+            mod_paths: vec![mod_path.clone(), mod_path.clone()],
+            crate_engine_path: crate_engine_path.clone(),
+            options: FromBeamNGModOptions::default(),
+            existing_hashes: BTreeSet::new()
+        };
+        let (sender, _receiver) = mpsc::unbounded();
+        let summary = job.run_with(&sender, |_| {
+            let engine: automation::sandbox::EngineV1 = serde_json::from_str(
+                include_str!("../../crate_engine/src/test-data/bi_turbo_engine.json")
+            ).map_err(|e| e.to_string())?;
+            CrateEngine::from_sandbox_engine(engine)
+        });
+
+        let crate_files = fs::read_dir(&crate_engine_path).unwrap().count();
+        fs::remove_dir_all(&crate_engine_path).unwrap();
+        assert_eq!(summary.created.len(), 1);
+        assert_eq!(summary.skipped, vec![mod_path]);
+        assert!(summary.failed.is_empty());
+        assert_eq!(crate_files, 1);
+    }
+}
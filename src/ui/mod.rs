@@ -28,13 +28,25 @@ mod data;
 mod crate_engines;
 mod elements;
 mod colour;
+mod plot;
+mod watcher;
+mod swap_worker;
+mod import_worker;
+mod created_cars;
 
-use swap::{EngineSwapMessage, EngineSwapTab};
+use swap::{BatchSwapResult, EngineSwapMessage, EngineSwapTab};
 use edit::{EditMessage, EditTab};
 use settings::{SettingsMessage, SettingsTab};
+use swap_worker::{SwapEvent, SwapJob, SwapSource};
+use import_worker::{BulkImportEvent, BulkImportJob};
+use created_cars::CreatedCarsTab;
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use iced::{Element, Sandbox, Error, Settings, Background, Color, Padding};
+use std::sync::Arc;
+use std::thread;
+use iced::futures::channel::oneshot;
+use iced::{Application, Command, Element, Error, executor, Settings, Background, Color, Padding, Subscription};
 use iced::widget::{Column, Text, Container};
 use iced_aw::{TabLabel, Tabs};
 use iced::alignment::{Horizontal, Vertical};
@@ -43,24 +55,50 @@ use iced_aw::style::tab_bar::Appearance;
 use iced_aw::style::TabBarStyles;
 use iced_aw::tab_bar::StyleSheet;
 use crate::{assetto_corsa, fabricator};
-use tracing::{span, Level, info, error};
+use tracing::{span, Level, info, error, warn};
 use rfd::FileDialog;
 use arboard::Clipboard;
 use iced::window::Icon;
 
-use assetto_corsa::car::delete_car;
-use automation::sandbox::SandboxFinder;
+use assetto_corsa::car::data::engine::export_engine_files;
+use assetto_corsa::Car;
 
-use crate::fabricator::{AdditionalAcCarData, AssettoCorsaCarSettings};
-use crate::settings::GlobalSettings;
+use crate::fabricator::{AdditionalAcCarData, AssettoCorsaCarSettings, CancellationToken};
+use crate::settings::{CrateEngineViewSettings, GlobalSettings, UnitSystem};
 use crate::ui::crate_engines::{CrateEngineTab, CrateTabMessage};
+use crate::data::{CrateEngine, FromBeamNGModOptions};
 use crate::ui::data::{ApplicationData, AssettoCorsaData, BeamNGData, CrateEngineData};
 use crate::ui::settings::Setting;
 use crate::ui::swap::EngineSource;
+use crate::utils::filesystem;
 
 const HEADER_SIZE: u16 = 32;
 const TAB_PADDING: u16 = 16;
 
+/// The tabs of the main window in the order they're shown
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum AppTab {
+    EngineSwap,
+    CrateEngines,
+    Edit,
+    CreatedCars,
+    Settings
+}
+
+impl AppTab {
+    const ALL: &'static [AppTab] = &[
+        AppTab::EngineSwap,
+        AppTab::CrateEngines,
+        AppTab::Edit,
+        AppTab::CreatedCars,
+        AppTab::Settings
+    ];
+
+    fn index(self) -> usize {
+        AppTab::ALL.iter().position(|tab| *tab == self).unwrap_or_default()
+    }
+}
+
 pub fn launch() -> Result<(), Error> {
     let mut settings = Settings::default();
 
@@ -88,38 +126,117 @@ pub enum Message {
     CopySettingToClipboard(Setting),
     EngineSwap(EngineSwapMessage),
     EngineSwapRequested,
+    EngineSwapPreviewRequested,
+    /// The description of what the swap would change, or why it couldn't be worked out
+    SwapPreviewFinished(Result<String, String>),
+    SwapUpdate(SwapEvent),
     CrateTab(CrateTabMessage),
+    /// Create crate engines from every BeamNG mod in the mod folder
+    BulkImportRequested,
+    BulkImportUpdate(BulkImportEvent),
+    /// The weight of the engine with the given key, looked up after it was selected in the swap tab
+    NewEngineWeightLoaded(String, Result<u32, String>),
+    /// The installed cars whose engine sound suits the engine with the given key
+    SoundSuggestionsLoaded(String, Result<Vec<PathBuf>, String>),
     Edit(EditMessage),
-    #[allow(dead_code)]
+    /// A car export started from the edit tab finished, with the path of the package on success
+    CarExported(Result<PathBuf, String>),
     Settings(SettingsMessage),
+    ImportSettingsRequested,
+    ExportSettingsRequested,
+    /// Switch to the settings profile with the given name
+    ProfileSelected(String),
+    /// Save the current settings as a profile with the given name
+    SaveProfileRequested(String),
     DeleteCrateEngine(String),
-    RefreshCrateEngines
+    /// Rename the crate engine with the first name to the second
+    RenameCrateEngine(String, String),
+    RefreshCrateEngines,
+    CrateEngineViewChanged(CrateEngineViewSettings),
+    OpenCarFolder(PathBuf),
+    /// Switch to the edit tab with the created car at the path selected
+    EditCreatedCar(PathBuf),
+    /// Remove cars that have been deleted from the created car list
+    PurgeMissingCreatedCars,
+    BaseCarEditAcknowledged,
+    ExportEngineFiles(PathBuf),
+    ToggleFavorite(String),
+    WriteDebugValuesToggled(bool),
+    LinkOutputCarsToggled(bool),
+    BeamNGModPathCandidateSelected(String),
+    DisplayUnitsSelected(UnitSystem)
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct ListPath {
     full_path: PathBuf,
+    /// Shown in place of the filename when set, e.g. to include the subfolder a mod was found in
+    label: Option<String>,
+    /// Additional text that list filters match against, e.g. the screen name of an AC car
+    search_text: Option<String>
 }
 
 impl ListPath {
     fn from_path(path: PathBuf) -> ListPath {
-        ListPath {full_path: path}
+        ListPath {full_path: path, label: None, search_text: None}
     }
 
-    fn convert_path_vec(path_vec: Vec<PathBuf>) -> Vec<ListPath> {
-        path_vec.into_iter().fuse().map(|path|{
-            ListPath::from_path(path)
-        }).collect()
+    fn with_search_text(mut self, search_text: Option<String>) -> ListPath {
+        self.search_text = search_text;
+        self
+    }
+
+    /// Whether the displayed name or search text contains `filter`, ignoring case
+    pub(crate) fn matches_filter(&self, filter: &str) -> bool {
+        matches_filter(&self.to_string(), filter) ||
+            self.search_text.as_ref().is_some_and(|text| matches_filter(text, filter))
+    }
+
+    /// Create a [`ListPath`] that displays `path` relative to `root`. Falls back to the filename
+    /// if `path` isn't within `root`
+    fn relative_to(path: PathBuf, root: &Path) -> ListPath {
+        let label = path.strip_prefix(root).ok().map(|relative_path| {
+            let name_parts: Vec<String> = relative_path.components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            name_parts.join("/")
+        });
+        ListPath {full_path: path, label, search_text: None}
+    }
+
+    /// Find the entry for `path` in `list` so that its label and search text are kept.
+    /// Falls back to a plain entry if `path` isn't in the list
+    pub(crate) fn find_in(list: &[ListPath], path: &Path) -> ListPath {
+        list.iter()
+            .find(|list_path| list_path.full_path == path)
+            .cloned()
+            .unwrap_or_else(|| ListPath::from_path(path.to_path_buf()))
+    }
+
+    pub(crate) fn file_name(&self) -> String {
+        match &self.full_path.file_name() {
+            None => { "".to_string() }
+            Some(filename) => { filename.to_string_lossy().into_owned() }
+        }
+    }
+
+    pub(crate) fn favorite_id(&self) -> String {
+        self.full_path.to_string_lossy().into_owned()
     }
 }
 
+/// Case-insensitive substring match used by the list filter inputs. An empty filter matches everything
+pub(crate) fn matches_filter(candidate: &str, filter: &str) -> bool {
+    let filter = filter.trim();
+    filter.is_empty() || candidate.to_lowercase().contains(&filter.to_lowercase())
+}
+
 impl std::fmt::Display for ListPath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let out = match &self.full_path.file_name() {
-            None => { "".to_string() }
-            Some(filename) => { filename.to_string_lossy().into_owned() }
-        };
-        write!(f, "{}", out)
+        match &self.label {
+            Some(label) => write!(f, "{}", label),
+            None => write!(f, "{}", self.file_name())
+        }
     }
 }
 
@@ -170,7 +287,15 @@ pub struct UIMain {
     engine_swap_tab: EngineSwapTab,
     crate_engine_tab: CrateEngineTab,
     edit_tab: EditTab,
-    settings_tab: SettingsTab
+    created_cars_tab: CreatedCarsTab,
+    settings_tab: SettingsTab,
+    swap_job: Option<Arc<SwapJob>>,
+    next_swap_id: u64,
+    import_job: Option<Arc<BulkImportJob>>,
+    next_import_id: u64,
+    /// Results of checking that an engine can be swapped into a car keyed by
+    /// (engine key, car path). Cleared whenever the application data changes
+    swap_compatibility_cache: HashMap<(String, PathBuf), Result<(), String>>
 }
 
 impl UIMain {
@@ -193,6 +318,7 @@ impl UIMain {
     pub fn get_crate_engine_data(&self) -> &CrateEngineData { &self.app_data.crate_engine_data }
 
     pub fn notify_app_data_update(&mut self, update_event: &Message) {
+        self.swap_compatibility_cache.clear();
         match self.app_data.settings.write() {
             Ok(_) => { info!("Wrote settings successfully"); }
             Err(e) => { error!("Failed to write settings. {}", e.to_string()); }
@@ -201,6 +327,7 @@ impl UIMain {
         self.engine_swap_tab.app_data_update(&self.app_data, update_event);
         self.crate_engine_tab.app_data_update(&self.app_data, update_event);
         self.edit_tab.app_data_update(&self.app_data, update_event);
+        self.created_cars_tab.app_data_update(&self.app_data, update_event);
     }
 
     pub fn notify_action_success(&mut self, action_event: &Message) {
@@ -208,6 +335,7 @@ impl UIMain {
         self.engine_swap_tab.notify_action_success(action_event);
         self.crate_engine_tab.notify_action_success(action_event);
         self.edit_tab.notify_action_success(action_event);
+        self.created_cars_tab.notify_action_success(action_event);
     }
 
     pub fn notify_action_failure(&mut self, action_event: &Message, reason: String) {
@@ -215,13 +343,300 @@ impl UIMain {
         self.engine_swap_tab.notify_action_failure(action_event, &reason);
         self.crate_engine_tab.notify_action_failure(action_event, &reason);
         self.edit_tab.notify_action_failure(action_event, &reason);
+        self.created_cars_tab.notify_action_failure(action_event, &reason);
+    }
+
+    /// Capture the options from the swap tab into a [`SwapJob`] that swaps the currently selected
+    /// engine into a new spec of each of `targets`. The new specs are created in `output_path`
+    /// if set, otherwise next to the car they are cloned from
+    fn create_swap_job(&mut self,
+                       ac_install_path: PathBuf,
+                       output_path: Option<PathBuf>,
+                       targets: Vec<PathBuf>,
+                       source: SwapSource) -> SwapJob {
+        let mut new_spec_opts = 0;
+        if self.engine_swap_tab.unpack_physics_data {
+            new_spec_opts |= assetto_corsa::car::UNPACK_DATA_BIT;
+        }
+        if self.engine_swap_tab.ac_car_tuner_compat {
+            new_spec_opts |= assetto_corsa::car::AC_CAR_TUNER_COMPAT_BIT;
+        }
+        if output_path.is_some() && self.app_data.link_output_cars() {
+            new_spec_opts |= assetto_corsa::car::LINK_INTO_INSTALLATION_BIT;
+        }
+
+        let mut car_settings = AssettoCorsaCarSettings::default();
+        car_settings.minimum_physics_level = self.engine_swap_tab.current_minimum_physics;
+        car_settings.low_rpm_torque_ramp = self.engine_swap_tab.low_rpm_torque_ramp;
+        car_settings.limiter_source = self.engine_swap_tab.limiter_source;
+        car_settings.power_curve_smoothing = self.engine_swap_tab.power_curve_smoothing;
+        car_settings.curve_source = self.engine_swap_tab.curve_source().unwrap_or_default();
+        car_settings.scale_downshift_overrev = self.engine_swap_tab.scale_downshift_overrev;
+        car_settings.force_base_game_downgrade = self.engine_swap_tab.force_base_game_downgrade;
+        car_settings.adjust_gearbox_inertia = self.engine_swap_tab.adjust_gearbox_inertia;
+        car_settings.low_gear_boost_fraction = self.engine_swap_tab.current_low_gear_boost_limit.as_ref()
+            .and_then(|percentage| percentage.parse::<u32>().ok())
+            .map(|percentage| percentage as f64 / 100.0);
+        car_settings.write_debug_values = self.app_data.write_debug_values();
+        car_settings.cancellation_token = self.engine_swap_tab.cancellation_token.clone();
+        let current_engine_weight =
+            if let Some(weight_string) = &self.engine_swap_tab.current_engine_weight {
+                match weight_string.parse::<u32>() {
+                    Ok(val) => {
+                        Some(val)
+                    }
+                    Err(_) => {
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+        let parse_override = |value: &Option<String>| {
+            value.as_ref().and_then(|rpm_string| rpm_string.parse::<i32>().ok())
+        };
+        let mut additional_car_data = AdditionalAcCarData::new(current_engine_weight)
+            .with_limiter_override(parse_override(&self.engine_swap_tab.current_limiter_override))
+            .with_idle_override(parse_override(&self.engine_swap_tab.current_idle_override))
+            .with_weight_distribution_update(self.engine_swap_tab.update_weight_distribution);
+        if let Some(donor_car) = &self.engine_swap_tab.sound_donor_car {
+            additional_car_data = additional_car_data.with_engine_sound_donor(ac_install_path.clone(), donor_car.clone());
+        } else if self.engine_swap_tab.match_engine_sound {
+            additional_car_data = additional_car_data.with_engine_sound_matching(Some(ac_install_path.clone()));
+        }
+
+        self.next_swap_id += 1;
+        SwapJob {
+            id: self.next_swap_id,
+            ac_install_path,
+            output_path,
+            targets,
+            new_spec_name: self.engine_swap_tab.current_new_spec_name.clone(),
+            new_spec_opts,
+            source,
+            car_settings,
+            additional_car_data
+        }
+    }
+
+    /// The engine selected in the swap tab. Updates the swap tab status and returns None when no
+    /// usable engine is selected
+    fn selected_swap_source(&mut self) -> Option<SwapSource> {
+        let source = match self.engine_swap_tab.current_source {
+            EngineSource::BeamNGMod => match &self.engine_swap_tab.current_mod {
+                None => {
+                    self.engine_swap_tab.update_status(String::from("Please select an BeamNG mod"));
+                    return None;
+                }
+                Some(mod_path) => SwapSource::BeamNGMod(mod_path.clone(), self.app_data.create_sandbox_finder())
+            }
+            EngineSource::CrateEngine => {
+                let crate_eng_name = match &self.engine_swap_tab.current_crate_eng {
+                    None => {
+                        self.engine_swap_tab.update_status(String::from("Please select a crate engine"));
+                        return None;
+                    }
+                    Some(name) => name
+                };
+                let crate_path = self.app_data.crate_engine_data.get_path_for(crate_eng_name);
+                if let Some(crate_path) = crate_path {
+                    if let Err(e) = crate::data::validate_crate_engine_file(crate_path) {
+                        let err_str = format!("Can't use crate engine {}. {}", crate_eng_name, e);
+                        error!("{}", &err_str);
+                        self.engine_swap_tab.update_status(err_str);
+                        return None;
+                    }
+                }
+                SwapSource::CrateEngine(crate_eng_name.clone(), crate_path.cloned())
+            }
+            EngineSource::AutomationSandbox => match &self.engine_swap_tab.current_sandbox_engine {
+                None => {
+                    self.engine_swap_tab.update_status(String::from("Please select an Automation engine"));
+                    return None;
+                }
+                Some(listing) => SwapSource::AutomationSandbox(listing.clone(),
+                                                               self.engine_swap_tab.sandbox_version,
+                                                               self.app_data.create_sandbox_finder())
+            }
+        };
+        Some(source)
+    }
+
+    /// Look up the weight of the engine selected in the swap tab in the background so it can be
+    /// shown next to the existing engine weight once it has loaded
+    fn refresh_new_engine_weight(&mut self) -> Command<Message> {
+        self.engine_swap_tab.set_new_engine_weight(None);
+        let engine_key = match self.engine_swap_tab.selected_engine_key() {
+            None => return Command::none(),
+            Some(key) => key
+        };
+        let source = match self.selected_swap_source() {
+            None => return Command::none(),
+            Some(source) => source
+        };
+        run_in_background(move || source.engine_weight(),
+                          move |result| Message::NewEngineWeightLoaded(engine_key.clone(), result))
+    }
+
+    /// Look up the installed cars whose engine sound suits the engine selected in the swap tab.
+    /// Only needed when the swap tab is set to match the engine sound
+    fn refresh_sound_suggestions(&mut self) -> Command<Message> {
+        self.engine_swap_tab.set_sound_suggestions(Vec::new());
+        if !self.engine_swap_tab.match_engine_sound {
+            return Command::none();
+        }
+        let (engine_key, source, ac_install_path) = match (self.engine_swap_tab.selected_engine_key(),
+                                                           self.selected_swap_source(),
+                                                           self.app_data.get_ac_install_path()) {
+            (Some(key), Some(source), Some(path)) => (key, source, path),
+            _ => return Command::none()
+        };
+        run_in_background(move || source.sound_suggestions(&ac_install_path),
+                          move |result| Message::SoundSuggestionsLoaded(engine_key.clone(), result))
+    }
+
+    /// Check that the engine selected in the swap tab can be swapped into each of the cars that
+    /// would be swapped so that predictable failures show up before any car is cloned.
+    /// Results are cached per engine and car so only new combinations are checked
+    fn refresh_swap_compatibility(&mut self) {
+        let targets = self.engine_swap_tab.swap_targets();
+        let engine_key = match self.engine_swap_tab.selected_engine_key() {
+            Some(key) if !targets.is_empty() => key,
+            _ => {
+                self.engine_swap_tab.set_compatibility(None);
+                return;
+            }
+        };
+        let unchecked: Vec<&PathBuf> = targets.iter().filter(|car_path| {
+            !self.swap_compatibility_cache.contains_key(&(engine_key.clone(), car_path.to_path_buf()))
+        }).collect();
+        if !unchecked.is_empty() {
+            let source = match self.selected_swap_source() {
+                None => {
+                    self.engine_swap_tab.set_compatibility(None);
+                    return;
+                }
+                Some(source) => source
+            };
+            for car_path in unchecked {
+                let result = source.check_compatibility(car_path);
+                if let Err(e) = &result {
+                    warn!("Engine can't be swapped into {}. {}", car_path.display(), e);
+                }
+                self.swap_compatibility_cache.insert((engine_key.clone(), car_path.clone()), result);
+            }
+        }
+        let result = targets.iter().try_for_each(|car_path| {
+            match &self.swap_compatibility_cache[&(engine_key.clone(), car_path.clone())] {
+                Ok(_) => Ok(()),
+                Err(e) => match targets.len() > 1 {
+                    true => Err(format!("{}: {}", car_path.file_name().unwrap_or_default().to_string_lossy(), e)),
+                    false => Err(e.clone())
+                }
+            }
+        });
+        self.engine_swap_tab.set_compatibility(Some(result));
+    }
+
+    /// Create a [`BulkImportJob`] for every mod in the BeamNG mod folder
+    fn create_bulk_import_job(&mut self) -> Result<BulkImportJob, String> {
+        let mod_path = self.app_data.get_beam_ng_mod_path().ok_or_else(|| {
+            "Cannot import crate engines as the BeamNG mod path is not set/accessible".to_string()
+        })?;
+        let crate_engine_path = self.app_data.get_crate_engine_path().ok_or_else(|| {
+            "Cannot import crate engines as the crate engine path is not set/accessible".to_string()
+        })?;
+        let mod_paths = beam_ng::get_mod_list_in(&mod_path);
+        if mod_paths.is_empty() {
+            return Err(format!("No BeamNG mods found in {}", mod_path.display()));
+        }
+        self.next_import_id += 1;
+        Ok(BulkImportJob {
+            id: self.next_import_id,
+            mod_paths,
+            crate_engine_path,
+            options: FromBeamNGModOptions::default(),
+            existing_hashes: self.app_data.crate_engine_data.automation_data_hashes()
+        })
+    }
+
+    /// Report the results of a finished [`SwapJob`] in the swap tab
+    fn finish_swap(&mut self, mut results: Vec<BatchSwapResult>) {
+        self.swap_job = None;
+        let swap_event = Message::EngineSwapRequested;
+        if results.len() > 1 {
+            let failures = results.iter().filter(|r| !r.is_success()).count();
+            self.engine_swap_tab.update_status(format!("Batch swap finished. {} of {} cars created successfully",
+                                                       results.len() - failures, results.len()));
+            self.engine_swap_tab.show_batch_results(results);
+            self.app_data.refresh_available_cars();
+            self.app_data.refresh_created_cars();
+            self.notify_app_data_update(&swap_event);
+            self.notify_action_success(&swap_event);
+            return;
+        }
+
+        match results.pop().map(BatchSwapResult::into_outcome) {
+            Some(Ok((new_car_path, warnings))) => {
+                let mut status = format!("Created {} successfully", new_car_path.display());
+                let report_path = fabricator::swap_report_path(&new_car_path);
+                if report_path.is_file() {
+                    status += &format!(". Swap report written to {}", report_path.display());
+                }
+                if !warnings.is_empty() {
+                    status += &format!(". {} warning(s) found", warnings.len());
+                    self.engine_swap_tab.show_swap_warnings(new_car_path.clone(), warnings);
+                }
+                self.engine_swap_tab.update_status(status);
+                self.engine_swap_tab.created_car = Some(new_car_path);
+                self.app_data.refresh_available_cars();
+                self.app_data.refresh_created_cars();
+                self.notify_app_data_update(&swap_event);
+                self.notify_action_success(&swap_event);
+            }
+            Some(Err(err_str)) => {
+                self.engine_swap_tab.update_status(err_str.clone());
+                self.notify_action_failure(&swap_event, err_str);
+            }
+            None => {
+                let err_str = String::from("Swap failed: No cars were swapped");
+                error!("{}", &err_str);
+                self.engine_swap_tab.update_status(err_str.clone());
+                self.notify_action_failure(&swap_event, err_str);
+            }
+        }
+    }
+
+    /// Rename the crate engine `old_name` to `new_name`, keeping it marked as a favorite if it was one
+    fn rename_crate_engine(&mut self, old_name: &str, new_name: &str) -> Result<PathBuf, String> {
+        let crate_data = &self.app_data.crate_engine_data;
+        let path = crate_data.get_location_for(old_name).ok_or_else(|| {
+            format!("No crate engine file found for {}", old_name)
+        })?.clone();
+        if crate_data.get_location_for(new_name).is_some() {
+            return Err(format!("A crate engine called {} already exists", new_name));
+        }
+        let old_favorite_id = crate_data.favorite_id_for(old_name);
+        let new_path = CrateEngine::rename_file(&path, new_name)?;
+        info!("Renamed crate engine {} to {}", old_name, new_name);
+        if let (Some(old_id), Some(new_id)) = (old_favorite_id, new_path.file_name()) {
+            let new_id = new_id.to_string_lossy().into_owned();
+            if self.app_data.is_favorite(&old_id) && old_id != new_id {
+                self.app_data.toggle_favorite(old_id);
+                self.app_data.toggle_favorite(new_id);
+            }
+        }
+        Ok(new_path)
     }
 }
 
-impl Sandbox for UIMain {
+impl Application for UIMain {
+    type Executor = executor::Default;
     type Message = Message;
+    type Theme = Theme;
+    type Flags = ();
 
-    fn new() -> Self {
+    fn new(_flags: ()) -> (Self, Command<Message>) {
         span!(Level::INFO, "Creating UIMain");
         let app_data = ApplicationData::new();
         info!("Initialised settings successfully");
@@ -233,26 +648,85 @@ impl Sandbox for UIMain {
         info!("Created crate engine tab");
         let edit_tab = EditTab::new(&app_data);
         info!("Created edit tab");
-        UIMain {
+        let created_cars_tab = CreatedCarsTab::new();
+        info!("Created my cars tab");
+        let ui = UIMain {
             app_data,
             active_tab: 0,
             engine_swap_tab,
             crate_engine_tab,
             edit_tab,
-            settings_tab
-        }
+            created_cars_tab,
+            settings_tab,
+            swap_job: None,
+            next_swap_id: 0,
+            import_job: None,
+            next_import_id: 0,
+            swap_compatibility_cache: HashMap::new()
+        };
+        (ui, Command::none())
     }
 
     fn title(&self) -> String {
         String::from("Engine Crane")
     }
 
-    fn update(&mut self, message: Self::Message) {
+    fn update(&mut self, message: Self::Message) -> Command<Message> {
         match message {
             Message::TabSelected(selected) => self.active_tab = selected,
-            Message::EngineSwap(message) => self.engine_swap_tab.update(message, &self.app_data),
+            Message::EngineSwap(message) => {
+                let engine_changed = matches!(message,
+                    EngineSwapMessage::SourceChanged(_) |
+                    EngineSwapMessage::ModSelected(_) |
+                    EngineSwapMessage::CrateEngineSelected(_) |
+                    EngineSwapMessage::SandboxVersionSelected(_) |
+                    EngineSwapMessage::SandboxEngineSelected(_));
+                let sound_matching_enabled = matches!(message, EngineSwapMessage::MatchEngineSoundToggled(true));
+                let selection_changed = engine_changed || matches!(message,
+                    EngineSwapMessage::CarSelected(_) |
+                    EngineSwapMessage::CarFilterEntered(_) |
+                    EngineSwapMessage::AddCarToBatch |
+                    EngineSwapMessage::BatchCarRemoved(_) |
+                    EngineSwapMessage::BatchCleared |
+                    EngineSwapMessage::ModFilterEntered(_) |
+                    EngineSwapMessage::CrateEngineFilterEntered(_));
+                self.engine_swap_tab.update(message, &self.app_data);
+                if selection_changed {
+                    self.refresh_swap_compatibility();
+                }
+                if engine_changed {
+                    return Command::batch([self.refresh_new_engine_weight(), self.refresh_sound_suggestions()]);
+                }
+                if sound_matching_enabled {
+                    return self.refresh_sound_suggestions();
+                }
+            }
+            Message::NewEngineWeightLoaded(engine_key, result) => {
+                // Ignore lookups for an engine that is no longer selected
+                if self.engine_swap_tab.selected_engine_key().as_ref() == Some(&engine_key) {
+                    let weight = result.map_err(|e| {
+                        warn!("Couldn't get the weight of the selected engine. {}", e);
+                    }).ok();
+                    self.engine_swap_tab.set_new_engine_weight(weight);
+                }
+            }
+            Message::SoundSuggestionsLoaded(engine_key, result) => {
+                if self.engine_swap_tab.selected_engine_key().as_ref() == Some(&engine_key) {
+                    let suggestions = result.unwrap_or_else(|e| {
+                        warn!("Couldn't find engine sounds to suggest for the selected engine. {}", e);
+                        Vec::new()
+                    });
+                    self.engine_swap_tab.set_sound_suggestions(suggestions);
+                }
+            }
             Message::CrateTab(message) => self.crate_engine_tab.update(message, &self.app_data),
-            Message::Edit(message) => self.edit_tab.update(message, &self.app_data),
+            Message::Edit(message) => {
+                self.edit_tab.update(message, &self.app_data);
+                if let Some(export) = self.edit_tab.take_pending_export() {
+                    return run_in_background(move || export.run(), Message::CarExported);
+                }
+            }
+            Message::CarExported(result) => self.edit_tab.export_finished(result),
             Message::Settings(message) => self.settings_tab.update(message, &self.app_data),
             Message::RequestPathSelect(setting) => {
                 let current_path = match setting {
@@ -260,7 +734,8 @@ impl Sandbox for UIMain {
                     Setting::BeamNGModPath => self.app_data.get_beam_ng_mod_path(),
                     Setting::CrateEnginePath => self.app_data.get_crate_engine_path(),
                     Setting::LegacyAutomationUserdataPath => self.app_data.get_legacy_automation_userdata_path(),
-                    Setting::AutomationUserdataPath => self.app_data.get_automation_userdata_path()
+                    Setting::AutomationUserdataPath => self.app_data.get_automation_userdata_path(),
+                    Setting::CarOutputPath => self.app_data.get_car_output_path()
                 };
                 if let Some(path) = open_dir_select_dialog(current_path.as_ref()) {
                     match setting {
@@ -268,7 +743,8 @@ impl Sandbox for UIMain {
                         Setting::BeamNGModPath => self.app_data.update_beamng_mod_path(path),
                         Setting::CrateEnginePath => self.app_data.update_crate_engine_path(path),
                         Setting::LegacyAutomationUserdataPath => self.app_data.update_legacy_automation_userdata_path(path),
-                        Setting::AutomationUserdataPath => self.app_data.update_automation_userdata_path(path)
+                        Setting::AutomationUserdataPath => self.app_data.update_automation_userdata_path(path),
+                        Setting::CarOutputPath => self.app_data.update_car_output_path(path)
                     }
                     self.notify_app_data_update(&message);
                 }
@@ -277,13 +753,79 @@ impl Sandbox for UIMain {
                 self.app_data.revert_to_default(setting);
                 self.notify_app_data_update(&message);
             }
+            Message::ImportSettingsRequested => {
+                let settings_file = match FileDialog::new().add_filter("Settings", &["toml", "json"]).pick_file() {
+                    None => return Command::none(),
+                    Some(file) => file
+                };
+                match GlobalSettings::import_from_file(&settings_file) {
+                    Ok(mut settings) => {
+                        info!("Imported settings from {}", settings_file.display());
+                        settings.set_active_profile(None);
+                        self.app_data.replace_settings(settings);
+                        self.notify_action_success(&message);
+                        self.notify_app_data_update(&message);
+                    }
+                    Err(e) => {
+                        error!("Failed to import settings from {}. {}", settings_file.display(), e.to_string());
+                        self.notify_action_failure(&message, format!("Failed to import settings. {}", e));
+                    }
+                }
+            }
+            Message::ExportSettingsRequested => {
+                let settings_file = match FileDialog::new()
+                    .add_filter("Settings", &["toml", "json"])
+                    .set_file_name("engine-crane-settings.toml")
+                    .save_file() {
+                    None => return Command::none(),
+                    Some(file) => file
+                };
+                match self.app_data.settings.export_to_file(&settings_file) {
+                    Ok(_) => {
+                        info!("Exported settings to {}", settings_file.display());
+                        self.notify_action_success(&message);
+                    }
+                    Err(e) => {
+                        error!("Failed to export settings to {}. {}", settings_file.display(), &e);
+                        self.notify_action_failure(&message, format!("Failed to export settings. {}", e));
+                    }
+                }
+            }
+            Message::ProfileSelected(ref name) => {
+                match GlobalSettings::load_profile(name) {
+                    Ok(settings) => {
+                        info!("Switched to settings profile {}", name);
+                        self.app_data.replace_settings(settings);
+                        self.notify_action_success(&message);
+                        self.notify_app_data_update(&message);
+                    }
+                    Err(e) => {
+                        error!("Failed to load settings profile {}. {}", name, e.to_string());
+                        self.notify_action_failure(&message, format!("Failed to load profile {}. {}", name, e));
+                    }
+                }
+            }
+            Message::SaveProfileRequested(ref name) => {
+                match self.app_data.settings.save_as_profile(name) {
+                    Ok(profile) => {
+                        info!("Saved settings profile {}", profile);
+                        self.notify_action_success(&message);
+                        self.notify_app_data_update(&message);
+                    }
+                    Err(e) => {
+                        error!("Failed to save settings profile {}. {}", name, &e);
+                        self.notify_action_failure(&message, e);
+                    }
+                }
+            }
             Message::CopySettingToClipboard(setting) => {
                 let setting_to_copy = match setting {
                     Setting::AcPath => self.app_data.get_ac_install_path(),
                     Setting::BeamNGModPath => self.app_data.get_beam_ng_mod_path(),
                     Setting::CrateEnginePath => self.app_data.get_crate_engine_path(),
                     Setting::LegacyAutomationUserdataPath => self.app_data.get_legacy_automation_userdata_path(),
-                    Setting::AutomationUserdataPath => self.app_data.get_automation_userdata_path()
+                    Setting::AutomationUserdataPath => self.app_data.get_automation_userdata_path(),
+                    Setting::CarOutputPath => self.app_data.get_car_output_path()
                 };
                 if let Some(setting_value) = setting_to_copy {
                     match Clipboard::new() {
@@ -299,168 +841,186 @@ impl Sandbox for UIMain {
                 }
             }
             Message::EngineSwapRequested => {
-                let ac_install = match &self.app_data.get_ac_install_path() {
+                if self.swap_job.is_some() {
+                    return Command::none();
+                }
+                self.engine_swap_tab.created_car = None;
+                let ac_install_path = match &self.app_data.get_ac_install_path() {
                     None => {
                         self.engine_swap_tab.update_status(String::from("Please set the Assetto Corsa install path in the settings tab"));
-                        return;
+                        return Command::none();
                     }
-                    Some(path) => assetto_corsa::Installation::from_path(path.clone())
+                    Some(path) => path.clone()
                 };
 
-                if self.engine_swap_tab.current_car.is_none() {
+                if self.engine_swap_tab.swap_targets().is_empty() {
                     self.engine_swap_tab.update_status(String::from("Please select an Assetto Corsa car"));
-                    return;
+                    return Command::none();
                 }
 
-                match self.engine_swap_tab.current_source {
-                    EngineSource::BeamNGMod => {
-                        if self.engine_swap_tab.current_mod.is_none() {
-                            self.engine_swap_tab.update_status(String::from("Please select an BeamNG mod"));
-                            return;
-                        }
-                    }
-                    EngineSource::CrateEngine => {
-                        if self.engine_swap_tab.current_crate_eng.is_none() {
-                            self.engine_swap_tab.update_status(String::from("Please select a crate engine"));
-                            return;
-                        }
-                    }
+                let output_path = self.app_data.get_car_output_path();
+                if self.app_data.is_car_output_path_set() && output_path.is_none() {
+                    self.engine_swap_tab.update_status(String::from("The car output path doesn't exist. Please update it in the settings tab"));
+                    return Command::none();
+                }
+
+                let source = match self.selected_swap_source() {
+                    None => return Command::none(),
+                    Some(source) => source
+                };
+                if self.engine_swap_tab.curve_source().is_none() {
+                    self.engine_swap_tab.update_status(String::from("Please choose a torque curve csv file"));
+                    return Command::none();
+                }
+                if !self.engine_swap_tab.compatibility_check_passed() {
+                    self.engine_swap_tab.update_status(String::from("The selected engine can't be swapped into the selected car. Tick 'Swap anyway' to try regardless"));
+                    return Command::none();
                 }
 
                 let new_spec_name = self.engine_swap_tab.current_new_spec_name.as_str();
                 if new_spec_name.is_empty() {
                     self.engine_swap_tab.update_status(String::from("Please enter a spec name"));
-                    return;
+                    return Command::none();
+                }
+                if assetto_corsa::car::spec_folder_suffix(new_spec_name).is_empty() {
+                    self.engine_swap_tab.update_status(String::from("The spec name must contain characters that can be used in a folder name"));
+                    return Command::none();
                 }
-                let new_car_path = {
-                    let span = span!(Level::INFO, "Creating new car spec");
-                    let _enter = span.enter();
 
-                    let current_car_path = match self.engine_swap_tab.current_car.as_ref() {
-                        None => {
-                            let err_str = "Swap failed: Cannot determine current car path";
-                            error!("{}", &err_str);
-                            self.engine_swap_tab.update_status(err_str.to_string());
-                            return;
-                        }
-                        Some(path_ref) => {
-                            path_ref
-                        }
-                    };
-                    let mut opts = 0;
-                    if self.engine_swap_tab.unpack_physics_data {
-                        opts |= assetto_corsa::car::UNPACK_DATA_BIT;
-                    }
-                    if self.engine_swap_tab.ac_car_tuner_compat {
-                        opts |= assetto_corsa::car::AC_CAR_TUNER_COMPAT_BIT;
+                self.engine_swap_tab.cancellation_token = CancellationToken::new();
+                let targets = self.engine_swap_tab.swap_targets();
+                let job = self.create_swap_job(ac_install_path, output_path, targets, source);
+                self.swap_job = Some(Arc::new(job));
+                self.engine_swap_tab.swap_started();
+            },
+            Message::EngineSwapPreviewRequested => {
+                if self.swap_job.is_some() || self.engine_swap_tab.is_preview_in_progress() {
+                    return Command::none();
+                }
+                let ac_install_path = match &self.app_data.get_ac_install_path() {
+                    None => {
+                        self.engine_swap_tab.update_status(String::from("Please set the Assetto Corsa install path in the settings tab"));
+                        return Command::none();
                     }
-                    match assetto_corsa::car::create_new_car_spec(&ac_install,
-                                                                  current_car_path,
-                                                                  new_spec_name,
-                                                                  opts)
-                    {
-                        Ok(path) => { path }
-                        Err(e) => {
-                            error!("Swap failed: {}", e.to_string());
-                            self.engine_swap_tab.update_status(format!("Swap failed: {}", e.to_string()));
-                            return;
-                        }
+                    Some(path) => path.clone()
+                };
+                let car_path = match self.engine_swap_tab.swap_targets().into_iter().next() {
+                    None => {
+                        self.engine_swap_tab.update_status(String::from("Please select an Assetto Corsa car"));
+                        return Command::none();
                     }
+                    Some(path) => path
                 };
-
-                let mut car_settings = AssettoCorsaCarSettings::default();
-                car_settings.minimum_physics_level = self.engine_swap_tab.current_minimum_physics;
-                let current_engine_weight =
-                    if let Some(weight_string) = &self.engine_swap_tab.current_engine_weight {
-                        match weight_string.parse::<u32>() {
-                            Ok(val) => {
-                                Some(val)
-                            }
-                            Err(_) => {
-                                None
-                            }
-                        }
-                    } else {
-                        None
-                    };
-                let additional_car_settings = AdditionalAcCarData::new(current_engine_weight);
-
-                let res = match self.engine_swap_tab.current_source {
-                    EngineSource::BeamNGMod => {
-                        let mod_path = match self.engine_swap_tab.current_mod.as_ref() {
-                            Some(p) => p,
-                            None => {
-                                let err_str = "Swap failed: Couldn't get ref to current mod";
-                                error!(err_str);
-                                self.engine_swap_tab.update_status(format!("{}", err_str));
-                                return;
-                            }
-                        };
-                        let span = span!(Level::INFO, "Updating car physics from BeamNG mod");
-                        let _enter = span.enter();
-
-                        let mut sandbox_finder = SandboxFinder::default();
-                        if let Some(path) = self.app_data.get_legacy_automation_userdata_path() {
-                            sandbox_finder.set_legacy_userdata_path(path)
-                        }
-                        if let Some(path) = self.app_data.get_automation_userdata_path() {
-                            sandbox_finder.set_userdata_path(path)
-                        }
-                        fabricator::swap_automation_engine_into_ac_car(mod_path.as_path(),
-                                                                       new_car_path.as_path(),
-                                                                       sandbox_finder,
-                                                                       car_settings,
-                                                                       additional_car_settings)
+                let source = match self.selected_swap_source() {
+                    None => return Command::none(),
+                    Some(source) => source
+                };
+                if self.engine_swap_tab.curve_source().is_none() {
+                    self.engine_swap_tab.update_status(String::from("Please choose a torque curve csv file"));
+                    return Command::none();
+                }
+                let job = self.create_swap_job(ac_install_path, None, vec![car_path.clone()], source);
+                self.engine_swap_tab.preview_started();
+                return run_in_background(move || job.preview(&car_path).map(|preview| preview.to_string()),
+                                         Message::SwapPreviewFinished);
+            }
+            Message::SwapPreviewFinished(result) => self.engine_swap_tab.preview_finished(result),
+            Message::SwapUpdate(event) => match event {
+                SwapEvent::Progress(progress) => self.engine_swap_tab.update_swap_progress(progress),
+                SwapEvent::Finished(results) => self.finish_swap(results)
+            },
+            Message::BulkImportRequested => {
+                if self.import_job.is_some() {
+                    warn!("Ignoring bulk import request as an import is already running");
+                    return Command::none();
+                }
+                match self.create_bulk_import_job() {
+                    Ok(job) => self.import_job = Some(Arc::new(job)),
+                    Err(e) => self.crate_engine_tab.finish_bulk_import(Err(e))
+                }
+            }
+            Message::BulkImportUpdate(event) => match event {
+                BulkImportEvent::Progress(progress) => self.crate_engine_tab.update_bulk_import_progress(progress),
+                BulkImportEvent::Finished(summary) => {
+                    self.import_job = None;
+                    self.app_data.refresh_crate_engines();
+                    self.notify_app_data_update(&Message::RefreshCrateEngines);
+                    self.crate_engine_tab.finish_bulk_import(Ok(summary));
+                }
+            },
+            Message::RefreshCrateEngines => {
+                self.app_data.refresh_crate_engines();
+                self.notify_app_data_update(&message);
+            }
+            Message::BaseCarEditAcknowledged => {
+                self.app_data.update_base_car_edit_acknowledged(true);
+                self.notify_app_data_update(&message);
+            }
+            Message::CrateEngineViewChanged(view_settings) => {
+                self.app_data.update_crate_engine_view(view_settings);
+                self.notify_app_data_update(&message);
+            }
+            Message::ToggleFavorite(ref favorite_id) => {
+                self.app_data.toggle_favorite(favorite_id.clone());
+                self.notify_app_data_update(&message);
+            }
+            Message::WriteDebugValuesToggled(enabled) => {
+                self.app_data.update_write_debug_values(enabled);
+                self.notify_app_data_update(&message);
+            }
+            Message::LinkOutputCarsToggled(enabled) => {
+                self.app_data.update_link_output_cars(enabled);
+                self.notify_app_data_update(&message);
+            }
+            Message::BeamNGModPathCandidateSelected(ref path) => {
+                self.app_data.update_beamng_mod_path(PathBuf::from(path));
+                self.notify_app_data_update(&message);
+            }
+            Message::DisplayUnitsSelected(units) => {
+                self.app_data.update_display_units(units);
+                self.notify_app_data_update(&message);
+            }
+            Message::OpenCarFolder(ref car_path) => {
+                if let Err(e) = filesystem::open_in_file_manager(car_path) {
+                    error!("Failed to open {}. {}", car_path.display(), e.to_string());
+                }
+            }
+            Message::EditCreatedCar(ref car_path) => {
+                self.active_tab = AppTab::Edit.index();
+                let car = ListPath::find_in(&self.app_data.assetto_corsa_data.available_cars, car_path);
+                self.edit_tab.update(EditMessage::CarSelected(car), &self.app_data);
+            }
+            Message::PurgeMissingCreatedCars => {
+                match self.app_data.purge_missing_created_cars() {
+                    Ok(count) => {
+                        info!("Removed {} deleted cars from the created car registry", count);
+                        self.notify_action_success(&message);
                     }
-                    EngineSource::CrateEngine => {
-                        let crate_eng_name = match self.engine_swap_tab.current_crate_eng.as_ref() {
-                            Some(c) => c,
-                            None => {
-                                let err_str = "Couldn't get currently selected crate engine name";
-                                error!(err_str);
-                                self.engine_swap_tab.update_status(format!("{}", err_str));
-                                return;
-                            }
-                        };
-                        let crate_path = match self.app_data.crate_engine_data.get_path_for(crate_eng_name) {
-                            Some(p) => p,
-                            None => {
-                                let err_str = format!("Path for crate engine {} not found", crate_eng_name);
-                                error!(err_str);
-                                self.engine_swap_tab.update_status(format!("{}", err_str));
-                                return;
-                            }
-                        };
-                        let span = span!(Level::INFO, "Updating car physics from crate engine");
-                        let _enter = span.enter();
-                        fabricator::swap_crate_engine_into_ac_car(crate_path.as_path(),
-                                                                  new_car_path.as_path(),
-                                                                  car_settings,
-                                                                  additional_car_settings)
+                    Err(e) => {
+                        error!("Failed to remove deleted cars from the created car registry. {}", &e);
+                        self.notify_action_failure(&message, e);
                     }
+                }
+                self.notify_app_data_update(&message);
+            }
+            Message::ExportEngineFiles(ref car_path) => {
+                let export_dir = match open_dir_select_dialog(Some(car_path)) {
+                    None => return Command::none(),
+                    Some(dir) => dir
                 };
+                let res = Car::load_from_path(car_path).and_then(|mut car| {
+                    export_engine_files(&mut car, &export_dir)
+                });
                 match res {
                     Ok(_) => {
-                        self.engine_swap_tab.update_status(format!("Created {} successfully", new_car_path.display()));
-                        self.app_data.refresh_available_cars();
-                        self.notify_app_data_update(&message);
+                        info!("Exported engine files from {} to {}", car_path.display(), export_dir.display());
+                        self.engine_swap_tab.update_status(format!("Exported engine files to {}", export_dir.display()));
                     }
-                    Err(err_str) => {
-                        if let Some(car_folder_name) = new_car_path.file_name() {
-                            delete_car(&ac_install, Path::new(car_folder_name)).unwrap_or_else(|e|{
-                                error!("Failed to delete {}. {}", new_car_path.display(), e.to_string());
-                            });
-                        } else {
-                            error!("Failed to delete {}. Couldn't get car folder name", new_car_path.display());
-                        }
-                        error!("{}", &err_str);
-                        self.engine_swap_tab.update_status(err_str.to_string())
+                    Err(e) => {
+                        error!("Failed to export engine files from {}. {}", car_path.display(), e.to_string());
+                        self.engine_swap_tab.update_status(format!("Failed to export engine files. {}", e.to_string()));
                     }
                 }
-            },
-            Message::RefreshCrateEngines => {
-                self.app_data.refresh_crate_engines();
-                self.notify_app_data_update(&message);
             }
             Message::DeleteCrateEngine(ref eng_id) => {
                 if let Some(path) = self.app_data.crate_engine_data.get_location_for(eng_id.as_str()) {
@@ -476,33 +1036,61 @@ impl Sandbox for UIMain {
                 }
                 self.notify_app_data_update(&message);
             }
+            Message::RenameCrateEngine(ref old_name, ref new_name) => {
+                match self.rename_crate_engine(old_name, new_name.trim()) {
+                    Ok(_) => {
+                        self.app_data.refresh_crate_engines();
+                        self.notify_action_success(&message);
+                    }
+                    Err(e) => {
+                        error!("Failed to rename crate engine {}. {}", old_name, &e);
+                        self.notify_action_failure(&message, e);
+                    }
+                }
+                self.notify_app_data_update(&message);
+            }
         }
+        Command::none()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch([
+            watcher::crate_engine_folder_watcher(self.app_data.get_crate_engine_path()),
+            swap_worker::swap_worker(self.swap_job.clone()),
+            import_worker::bulk_import_worker(self.import_job.clone())
+        ])
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
-        Tabs::new(self.active_tab, Message::TabSelected)
-            .push(
+        AppTab::ALL.iter().fold(Tabs::new(self.active_tab, Message::TabSelected), |tabs, tab| match tab {
+            AppTab::EngineSwap => tabs.push(
                 self.engine_swap_tab.tab_label(),
                 self.engine_swap_tab.view(&self.app_data)
-            )
-            .push(
+            ),
+            AppTab::CrateEngines => tabs.push(
                 self.crate_engine_tab.tab_label(),
                 self.crate_engine_tab.view(&self.app_data)
-            )
-            .push(
+            ),
+            AppTab::Edit => tabs.push(
                 self.edit_tab.tab_label(),
                 self.edit_tab.view(&self.app_data)
-            )
-            .push(
+            ),
+            AppTab::CreatedCars => tabs.push(
+                self.created_cars_tab.tab_label(),
+                self.created_cars_tab.view(&self.app_data)
+            ),
+            AppTab::Settings => tabs.push(
                 self.settings_tab.tab_label(),
                 self.settings_tab.view(&self.app_data)
             )
+        })
             .tab_bar_style(TabBarStyles::Custom(Box::new(CustomStyleSheet)))
             .tab_bar_position(iced_aw::TabBarPosition::Top)
             .into()
     }
 }
 
+
 fn open_dir_select_dialog(starting_path: Option<&PathBuf>) -> Option<PathBuf> {
     let root_dir = PathBuf::from("/");
     let path = starting_path.unwrap_or(&root_dir);
@@ -511,6 +1099,22 @@ fn open_dir_select_dialog(starting_path: Option<&PathBuf>) -> Option<PathBuf> {
         .pick_folder()
 }
 
+/// Run `task` on its own thread so that slow file and db access doesn't block the UI. The result
+/// is delivered as the message created by `to_message` once `task` finishes
+fn run_in_background<T, F, M>(task: F, to_message: M) -> Command<Message>
+    where T: Send + 'static,
+          F: FnOnce() -> Result<T, String> + Send + 'static,
+          M: Fn(Result<T, String>) -> Message + Send + 'static
+{
+    let (sender, receiver) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = sender.send(task());
+    });
+    Command::perform(receiver, move |result| {
+        to_message(result.unwrap_or_else(|_| Err("the background task stopped unexpectedly".to_string())))
+    })
+}
+
 
 trait Tab {
     type Message;
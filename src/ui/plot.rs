@@ -0,0 +1,101 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use plotters::chart::{ChartBuilder, LabelAreaPosition, SeriesLabelPosition};
+use plotters::drawing::IntoDrawingArea;
+use plotters::element::PathElement;
+use plotters::prelude::{BLACK, LineSeries, SVGBackend, WHITE};
+use plotters::style::{Color, RGBColor};
+
+const TORQUE_COLOUR: RGBColor = RGBColor(230, 159, 0);
+const POWER_COLOUR: RGBColor = RGBColor(86, 180, 233);
+
+/// Render the torque (Nm) and power (kW) curves of an engine against RPM as an SVG image.
+/// Torque is plotted against the left axis and power against the right
+pub fn create_engine_curve_svg(torque_curve: &[(f64, f64)],
+                               power_curve: &[(f64, f64)],
+                               size: (u32, u32)) -> Result<Vec<u8>, String> {
+    if torque_curve.is_empty() && power_curve.is_empty() {
+        return Err("No curve data available".to_string());
+    }
+    let max_of = |curve: &[(f64, f64)], pick: fn(&(f64, f64)) -> f64| {
+        curve.iter().map(pick).filter(|v| v.is_finite()).fold(0f64, f64::max)
+    };
+    let x_axis_limit = max_of(torque_curve, |p| p.0).max(max_of(power_curve, |p| p.0)) + 250f64;
+    let torque_axis_limit = (max_of(torque_curve, |p| p.1) * 1.1).max(1f64);
+    let power_axis_limit = (max_of(power_curve, |p| p.1) * 1.1).max(1f64);
+
+    let mut svg_data = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg_data, size).into_drawing_area();
+        root.fill(&BLACK.mix(0.9)).map_err(|e| e.to_string())?;
+        let mut context = ChartBuilder::on(&root)
+            .margin(10)
+            .set_label_area_size(LabelAreaPosition::Bottom, 35)
+            .set_label_area_size(LabelAreaPosition::Left, 50)
+            .set_label_area_size(LabelAreaPosition::Right, 50)
+            .build_cartesian_2d(0f64..x_axis_limit, 0f64..torque_axis_limit)
+            .map_err(|e| e.to_string())?
+            .set_secondary_coord(0f64..x_axis_limit, 0f64..power_axis_limit);
+
+        context.configure_mesh()
+            .x_label_formatter(&as_usize)
+            .x_desc("RPM")
+            .y_label_formatter(&as_usize)
+            .y_desc("Torque (Nm)")
+            .label_style(&WHITE)
+            .axis_desc_style(&WHITE)
+            .bold_line_style(WHITE.mix(0.2))
+            .light_line_style(WHITE.mix(0.1))
+            .draw()
+            .map_err(|e| e.to_string())?;
+        context.configure_secondary_axes()
+            .y_label_formatter(&as_usize)
+            .y_desc("Power (kW)")
+            .label_style(&WHITE)
+            .axis_desc_style(&WHITE)
+            .draw()
+            .map_err(|e| e.to_string())?;
+
+        context.draw_series(LineSeries::new(torque_curve.iter().cloned(), TORQUE_COLOUR.stroke_width(2)))
+            .map_err(|e| e.to_string())?
+            .label("Torque")
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], TORQUE_COLOUR));
+        context.draw_secondary_series(LineSeries::new(power_curve.iter().cloned(), POWER_COLOUR.stroke_width(2)))
+            .map_err(|e| e.to_string())?
+            .label("Power")
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], POWER_COLOUR));
+
+        context.configure_series_labels()
+            .position(SeriesLabelPosition::LowerRight)
+            .label_font(&WHITE)
+            .background_style(BLACK.mix(0.8))
+            .border_style(WHITE.mix(0.5))
+            .draw()
+            .map_err(|e| e.to_string())?;
+        root.present().map_err(|e| e.to_string())?;
+    }
+    Ok(svg_data.into_bytes())
+}
+
+fn as_usize(val: &f64) -> String {
+    format!("{}", val.round() as usize)
+}
@@ -21,27 +21,38 @@
 use super::{Message, Tab};
 use iced::{Alignment, Element, Length, Padding, theme};
 use iced::alignment::Vertical;
-use iced::widget::{Button, Column, Container, svg, Text};
+use iced::widget::{Button, checkbox, Column, Container, svg, Text, TextInput};
 use iced_aw::{TabLabel};
-use iced_native::widget::{Row, scrollable, Svg};
+use iced_native::widget::{pick_list, Row, scrollable, Svg};
 use iced_native::svg::Handle;
 use iced_native::widget::scrollable::Properties;
 use crate::settings::Setting as AppSettings;
-use crate::settings::{AcInstallPath, AutomationUserdataPath, BeamNGModPath, CrateEnginePath, LegacyAutomationUserdataPath};
+use crate::settings::{AcInstallPath, AutomationUserdataPath, BeamNGModPath, CarOutputPath, CrateEnginePath, DisplayUnits, GlobalSettings, LegacyAutomationUserdataPath, LinkOutputCars, UnitSystem, WriteDebugValues};
 use crate::ui::{ApplicationData};
-use crate::ui::colour::{fail_red, success_green};
+use crate::ui::colour::{fail_red, success_green, warn_yellow};
 use crate::ui::data::PathState;
-use crate::ui::image_data::{CIRCLE_CROSS, CIRCLE_TICK};
+use crate::ui::image_data::{ALERT_TRIANGLE, CIRCLE_CROSS, CIRCLE_TICK};
 
 #[derive(Default)]
 pub struct SettingsTab {
-
+    /// The folders BeamNG mods may be stored in on this system, offered as alternatives to the
+    /// default BeamNG mod path
+    beam_ng_mod_path_candidates: Vec<String>,
+    profiles: Vec<String>,
+    new_profile_name: String,
+    /// Whether the user has been asked to confirm that the profile with the entered name should
+    /// be overwritten
+    confirm_overwrite: bool,
+    /// The result of the last import, export or profile action and whether it succeeded
+    status: Option<(bool, String)>
 }
 
 #[derive(Debug, Clone)]
 pub enum SettingsMessage {
-    #[allow(dead_code)]
-    ThingSelected(String)
+    ProfileNameEntered(String),
+    /// Ask for confirmation before saving over the existing profile with the entered name
+    OverwriteProfileRequested,
+    OverwriteProfileCancelled
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -50,7 +61,8 @@ pub enum Setting {
     BeamNGModPath,
     CrateEnginePath,
     LegacyAutomationUserdataPath,
-    AutomationUserdataPath
+    AutomationUserdataPath,
+    CarOutputPath
 }
 
 impl Setting {
@@ -66,6 +78,7 @@ impl Setting {
             }
             Setting::BeamNGModPath => {
                 (is_valid, value, aux_text) = get_path_data::<BeamNGModPath>(app_data, false);
+                aux_text = aux_text.or(app_data.beam_ng_data.mod_path_hint.clone());
                 title = BeamNGModPath::friendly_name();
             }
             Setting::CrateEnginePath => {
@@ -80,6 +93,15 @@ impl Setting {
                 (is_valid, value, aux_text) = get_path_data::<AutomationUserdataPath>(app_data, false);
                 title = AutomationUserdataPath::friendly_name();
             }
+            Setting::CarOutputPath => {
+                (is_valid, value, aux_text) = match app_data.is_car_output_path_set() {
+                    true => get_path_data::<CarOutputPath>(app_data, true),
+                    false => (true,
+                              "Not Set".to_string(),
+                              Some("New cars are created in the Assetto Corsa cars folder".to_string()))
+                };
+                title = CarOutputPath::friendly_name();
+            }
         };
         create_path_select(*self, title, value, is_valid, aux_text)
     }
@@ -88,21 +110,117 @@ impl Setting {
 impl SettingsTab {
     pub(crate) fn new() -> Self {
         SettingsTab {
-            ..Default::default()
+            beam_ng_mod_path_candidates: beam_ng::get_candidate_mod_paths().into_iter().map(|path| {
+                path.to_string_lossy().into_owned()
+            }).collect(),
+            profiles: GlobalSettings::list_profiles(),
+            new_profile_name: String::new(),
+            confirm_overwrite: false,
+            status: None
         }
     }
 
     pub fn update(&mut self, message: SettingsMessage, _app_data: &ApplicationData) {
-        match message { SettingsMessage::ThingSelected(_) => {} }
+        match message {
+            SettingsMessage::ProfileNameEntered(name) => {
+                self.new_profile_name = name;
+                self.confirm_overwrite = false;
+            }
+            SettingsMessage::OverwriteProfileRequested => self.confirm_overwrite = true,
+            SettingsMessage::OverwriteProfileCancelled => self.confirm_overwrite = false
+        }
+    }
+
+    pub fn app_data_update(&mut self, _app_data: &ApplicationData, update_event: &Message) {
+        match update_event {
+            Message::ImportSettingsRequested |
+            Message::ProfileSelected(_) |
+            Message::SaveProfileRequested(_) => self.profiles = GlobalSettings::list_profiles(),
+            _ => {}
+        }
     }
 
-    pub fn app_data_update(&mut self, _app_data: &ApplicationData, _update_event: &Message) {
+    pub fn notify_action_success(&mut self, action_event: &Message) {
+        let status = match action_event {
+            Message::ImportSettingsRequested => String::from("Settings imported"),
+            Message::ExportSettingsRequested => String::from("Settings exported"),
+            Message::ProfileSelected(name) => format!("Switched to the {} profile", name),
+            Message::SaveProfileRequested(_) => {
+                self.new_profile_name.clear();
+                self.confirm_overwrite = false;
+                String::from("Profile saved")
+            }
+            _ => return
+        };
+        self.status = Some((true, status));
     }
 
-    pub fn notify_action_success(&mut self, _action_event: &Message) {
+    pub fn notify_action_failure(&mut self, action_event: &Message, reason: &str) {
+        match action_event {
+            Message::ImportSettingsRequested |
+            Message::ExportSettingsRequested |
+            Message::ProfileSelected(_) |
+            Message::SaveProfileRequested(_) => {
+                self.confirm_overwrite = false;
+                self.status = Some((false, reason.to_string()))
+            }
+            _ => {}
+        }
     }
 
-    pub fn notify_action_failure(&mut self, _action_event: &Message, _reason: &str) {
+    fn create_profile_select(&self, app_data: &ApplicationData) -> Column<'_, Message> {
+        let profile_name = GlobalSettings::profile_name(&self.new_profile_name);
+        let mut save_button = Button::new(Text::new("Save as profile"));
+        if !profile_name.is_empty() && !self.confirm_overwrite {
+            save_button = save_button.on_press(match self.profiles.contains(&profile_name) {
+                true => Message::Settings(SettingsMessage::OverwriteProfileRequested),
+                false => Message::SaveProfileRequested(self.new_profile_name.clone())
+            });
+        }
+        let profile_row = Row::new()
+            .align_items(Alignment::Center)
+            .spacing(5)
+            .push(pick_list(
+                &self.profiles,
+                app_data.settings.active_profile().map(str::to_string),
+                Message::ProfileSelected
+            ).placeholder("No profile"))
+            .push(TextInput::new(
+                "New profile name",
+                &self.new_profile_name,
+                |val| Message::Settings(SettingsMessage::ProfileNameEntered(val))
+            ).width(Length::Units(200)))
+            .push(save_button);
+        let file_row = Row::new()
+            .align_items(Alignment::Center)
+            .spacing(5)
+            .push(Button::new(Text::new("Import settings")).on_press(Message::ImportSettingsRequested))
+            .push(Button::new(Text::new("Export settings")).on_press(Message::ExportSettingsRequested));
+        let mut profile_select = Column::new()
+            .align_items(Alignment::Start)
+            .spacing(5)
+            .push(Text::new("Profile").size(24))
+            .push(profile_row);
+        if self.confirm_overwrite {
+            profile_select = profile_select.push(Row::new()
+                .align_items(Alignment::Center)
+                .spacing(5)
+                .push(Text::new(format!("Overwrite the existing {} profile?", profile_name)).size(14))
+                .push(Button::new(Text::new("Overwrite"))
+                    .style(theme::Button::Destructive)
+                    .on_press(Message::SaveProfileRequested(self.new_profile_name.clone())))
+                .push(Button::new(Text::new("Cancel"))
+                    .on_press(Message::Settings(SettingsMessage::OverwriteProfileCancelled))));
+        }
+        profile_select = profile_select.push(file_row);
+        if let Some((success, status)) = &self.status {
+            let colour = match success {
+                true => success_green(),
+                false => fail_red()
+            };
+            profile_select = profile_select.push(Text::new(status.as_str()).size(14).style(colour));
+        }
+        profile_select
     }
 }
 
@@ -123,13 +241,29 @@ impl Tab for SettingsTab {
     ) -> Element<'_, Self::Message>
     where 'b: 'a
     {
+        let profile_select = self.create_profile_select(app_data)
+            .padding(Padding::from([15, 3, 0, 3]));
+
         let ac_path_selector =
             Setting::AcPath.create_path_select(app_data)
-                .padding(Padding::from([15, 3, 0, 3]));
+                .padding(Padding::from([0, 3, 0, 3]));
 
-        let mod_path_selector =
+        let mut mod_path_selector =
             Setting::BeamNGModPath.create_path_select(app_data)
                 .padding(Padding::from([0, 3, 0, 3]));
+        if self.beam_ng_mod_path_candidates.len() > 1 {
+            let current_path = app_data.get_beam_ng_mod_path().map(|path| path.to_string_lossy().into_owned());
+            let selected = self.beam_ng_mod_path_candidates.iter().find(|candidate| {
+                Some(*candidate) == current_path.as_ref()
+            }).cloned();
+            mod_path_selector = mod_path_selector.push(
+                pick_list(
+                    &self.beam_ng_mod_path_candidates,
+                    selected,
+                    Message::BeamNGModPathCandidateSelected
+                ).placeholder("Choose a detected BeamNG mod folder")
+            );
+        }
 
         let crate_path_selector = Setting::CrateEnginePath.create_path_select(app_data)
             .padding(Padding::from([0, 3, 0, 3]));
@@ -140,13 +274,62 @@ impl Tab for SettingsTab {
         let auto_path_selector = Setting::AutomationUserdataPath.create_path_select(app_data)
             .padding(Padding::from([0, 3, 0, 3]));
 
+        let car_output_selector = Setting::CarOutputPath.create_path_select(app_data)
+            .push(checkbox(
+                "Link cars created in the output path into the Assetto Corsa cars folder so they can be used in game",
+                app_data.link_output_cars(),
+                Message::LinkOutputCarsToggled
+            ).spacing(3))
+            .padding(Padding::from([0, 3, 0, 3]));
+
+        let debug_values_select = Column::new()
+            .align_items(Alignment::Start)
+            .spacing(5)
+            .padding(Padding::from([0, 3, 0, 3]))
+            .push(Text::new(WriteDebugValues::friendly_name()).size(24))
+            .push(checkbox(
+                "Enable AC debug logging (e.g. LOG_FUEL_FLOW) in cars written by swaps and edits",
+                app_data.write_debug_values(),
+                Message::WriteDebugValuesToggled
+            ).spacing(3));
+
+        let display_units_select = Column::new()
+            .align_items(Alignment::Start)
+            .spacing(5)
+            .padding(Padding::from([0, 3, 0, 3]))
+            .push(Text::new(DisplayUnits::friendly_name()).size(24))
+            .push(pick_list(
+                UnitSystem::ALL,
+                Some(app_data.display_units()),
+                Message::DisplayUnitsSelected
+            ));
+
+        let mut settings_list = Column::new();
+        if let Some(hint) = app_data.steam_not_detected_hint() {
+            let svg = Svg::new(Handle::from_memory(ALERT_TRIANGLE))
+                .style(theme::Svg::custom_fn(|_| {
+                    svg::Appearance{color: Some(warn_yellow())}
+                }))
+                .height(Length::Units(25))
+                .width(Length::Units(25));
+            settings_list = settings_list.push(
+                Row::new().spacing(5).align_items(Alignment::Center)
+                    .padding(Padding::from([15, 3, 0, 3]))
+                    .push(svg)
+                    .push(Text::new(hint).size(14))
+            );
+        }
         let container : Element<'_, Message> = scrollable(Container::new(
-            Column::new()
+            settings_list
+                .push(profile_select)
                 .push(ac_path_selector)
                 .push(mod_path_selector)
                 .push(crate_path_selector)
                 .push(legacy_auto_path_selector)
                 .push(auto_path_selector)
+                .push(car_output_selector)
+                .push(display_units_select)
+                .push(debug_values_select)
                 .spacing(25)
         ).width(Length::Fill)).vertical_scroll(Properties::default()).into();
         container
@@ -162,7 +345,14 @@ fn get_path_data<T: crate::settings::PathSetting>(app_data: &ApplicationData,
     let base_path_str = match &app_data.get_path::<T>() {
         None => {
             valid = false;
-            "Not Set".to_string()
+            let configured_path = app_data.settings.get::<T>();
+            match configured_path.is_empty() {
+                true => "Not Set".to_string(),
+                false => {
+                    invalid_text = Some("Directory doesn't exist".to_owned());
+                    configured_path.clone()
+                }
+            }
         }
         Some(path) => {
             let (read_state, write_state) = app_data.get_permission_data::<T>();
@@ -21,45 +21,126 @@
 
 use std::fmt::{Display, Formatter};
 use super::{Message, Tab};
-use std::path::{PathBuf};
-use iced::{Alignment, Element, Length, Padding, Renderer};
-use iced::widget::{Button, checkbox, Column, Container, pick_list, PickList, Row, Text, TextInput};
+use std::path::{Path, PathBuf};
+use rfd::FileDialog;
+use iced::{Alignment, Background, Element, Length, Padding, Renderer, theme, Theme};
+use iced::widget::{Button, checkbox, Column, Container, pick_list, PickList, Row, scrollable, svg, Svg, Text, TextInput};
+use iced_aw::style::colors::WHITE;
 use iced_aw::{TabLabel};
 use iced::alignment::Horizontal;
-use iced_native::widget::radio;
+use iced_native::Color;
+use iced_native::svg::Handle;
+use iced_native::widget::{container, radio};
+use tracing::error;
+use automation::sandbox::{SandboxEngineListing, SandboxVersion};
 
-use crate::fabricator::{AssettoCorsaPhysicsLevel};
-use crate::ui::{ApplicationData, ListPath};
+use crate::fabricator;
+use crate::fabricator::{AssettoCorsaPhysicsLevel, CancellationToken, CurveSource, FabricationWarning, LimiterSource, PowerCurveSmoothing};
+use crate::ui::{ApplicationData, ListPath, matches_filter};
+use crate::assetto_corsa;
+use crate::ui::button::create_favorite_button;
+use crate::ui::colour::{fail_red, success_green, warn_yellow};
+use crate::ui::elements::modal::Modal;
+use crate::ui::image_data::{CIRCLE_CROSS, CIRCLE_TICK};
 use crate::ui::settings::Setting;
+use crate::ui::swap_worker::SwapProgress;
 
 #[derive(Debug, Clone)]
 pub enum EngineSwapMessage {
     CarSelected(ListPath),
+    CarFilterEntered(String),
+    AddCarToBatch,
+    BatchCarRemoved(PathBuf),
+    BatchCleared,
+    BatchResultsDismissed,
+    CancelSwapRequested,
+    PreviewDismissed,
+    SwapWarningsDismissed,
     SourceChanged(EngineSource),
     NameEntered(String),
     ModSelected(ListPath),
+    ModFilterEntered(String),
     CrateEngineSelected(String),
+    CrateEngineFilterEntered(String),
+    SandboxVersionSelected(SandboxVersion),
+    SandboxFilterEntered(String),
+    SandboxEngineSelected(SandboxEngineListing),
     PhysicsLevelSelected(AssettoCorsaPhysicsLevel),
     OldEngineWeightEntered(String),
+    LimiterOverrideEntered(String),
+    IdleOverrideEntered(String),
+    LowGearBoostLimitEntered(String),
     UnpackToggled(bool),
-    ACCarTunerCompatToggled(bool)
+    ACCarTunerCompatToggled(bool),
+    LowRpmTorqueRampToggled(bool),
+    ScaleDownshiftOverrevToggled(bool),
+    ForceBaseGameDowngradeToggled(bool),
+    AdjustGearboxInertiaToggled(bool),
+    LimiterSourceSelected(LimiterSource),
+    PowerCurveSmoothingSelected(PowerCurveSmoothing),
+    CurveSourceSelected(CurveSourceSelection),
+    CurveCsvRequested,
+    FavoritesOnlyToggled(bool),
+    SoundDonorSelected(ListPath),
+    SoundDonorCleared,
+    MatchEngineSoundToggled(bool),
+    CompatibilityOverrideToggled(bool),
+    WeightDistributionUpdateToggled(bool)
+}
+
+/// The outcome of swapping the engine into one of the cars in a batch swap
+#[derive(Debug, Clone)]
+pub struct BatchSwapResult {
+    source_car: PathBuf,
+    outcome: Result<(PathBuf, Vec<FabricationWarning>), String>
+}
+
+impl BatchSwapResult {
+    pub fn new(source_car: PathBuf, outcome: Result<(PathBuf, Vec<FabricationWarning>), String>) -> BatchSwapResult {
+        BatchSwapResult { source_car, outcome }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.outcome.is_ok()
+    }
+
+    pub fn into_outcome(self) -> Result<(PathBuf, Vec<FabricationWarning>), String> {
+        self.outcome
+    }
+
+    fn describe(&self) -> String {
+        let car_name = self.source_car.file_name().map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.source_car.display().to_string());
+        match &self.outcome {
+            Ok((new_car_path, warnings)) => {
+                let mut description = format!("{}: created {}", car_name, new_car_path.display());
+                for warning in warnings {
+                    description += &format!(". Warning: {}", warning);
+                }
+                description
+            }
+            Err(e) => format!("{}: failed. {}", car_name, e)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum EngineSource {
     BeamNGMod,
-    CrateEngine
+    CrateEngine,
+    AutomationSandbox
 }
 
 impl EngineSource {
-    pub fn all_options() -> [EngineSource; 2] {
-        [EngineSource::BeamNGMod, EngineSource::CrateEngine]
+    pub fn all_options() -> [EngineSource; 3] {
+        [EngineSource::BeamNGMod, EngineSource::CrateEngine, EngineSource::AutomationSandbox]
     }
 
     pub fn as_str(&self) -> &'static str {
         match self {
             EngineSource::BeamNGMod => "BeamNG Mod",
-            EngineSource::CrateEngine => "Crate Engine"
+            EngineSource::CrateEngine => "Crate Engine",
+            EngineSource::AutomationSandbox => "Automation Sandbox"
         }
     }
 }
@@ -76,18 +157,97 @@ impl Display for EngineSource {
     }
 }
 
+/// Where the power curve of the swapped car comes from, as offered in the swap tab. The csv file
+/// for [`CurveSourceSelection::FromCsv`] is chosen separately
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum CurveSourceSelection {
+    #[default]
+    Calculated,
+    KeepDonor,
+    FromCsv
+}
+
+impl CurveSourceSelection {
+    pub const ALL: &'static [CurveSourceSelection] = &[
+        CurveSourceSelection::Calculated,
+        CurveSourceSelection::KeepDonor,
+        CurveSourceSelection::FromCsv
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CurveSourceSelection::Calculated => "Calculated power curve",
+            CurveSourceSelection::KeepDonor => "Keep donor power curve",
+            CurveSourceSelection::FromCsv => "Torque curve from csv"
+        }
+    }
+}
+
+impl Display for CurveSourceSelection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[derive(Default)]
 pub struct EngineSwapTab {
     available_physics: Vec<AssettoCorsaPhysicsLevel>,
     pub(crate) current_source: EngineSource,
     pub(crate) current_car: Option<PathBuf>,
+    batch_cars: Vec<PathBuf>,
+    batch_results: Option<Vec<BatchSwapResult>>,
+    /// A description of what the swap would change, shown until it is dismissed
+    preview: Option<String>,
+    /// Whether a preview is being worked out in the background
+    preview_in_progress: bool,
+    /// The car created by the last swap along with the problems found while creating it, shown
+    /// until it is dismissed
+    swap_warnings: Option<(PathBuf, Vec<FabricationWarning>)>,
+    /// The result of checking the selected engine against the selected cars, None until both
+    /// have been selected
+    compatibility: Option<Result<(), String>>,
+    /// Allow a swap to run even though the compatibility check failed
+    override_compatibility: bool,
     pub(crate) current_mod: Option<PathBuf>,
     pub(crate) current_crate_eng: Option<String>,
+    pub(crate) sandbox_version: SandboxVersion,
+    pub(crate) current_sandbox_engine: Option<SandboxEngineListing>,
+    sandbox_engines: Vec<SandboxEngineListing>,
+    sandbox_filter: String,
+    sandbox_list_error: Option<String>,
     pub(crate) current_new_spec_name: String,
     pub(crate) current_engine_weight: Option<String>,
+    /// Whether `current_engine_weight` was estimated from the selected car rather than entered
+    engine_weight_estimated: bool,
+    new_engine_weight: Option<u32>,
+    /// Installed cars whose engine sound suits the selected engine, best match first
+    sound_suggestions: Vec<PathBuf>,
+    pub(crate) update_weight_distribution: bool,
+    pub(crate) current_limiter_override: Option<String>,
+    pub(crate) current_idle_override: Option<String>,
+    /// The percentage of boost allowed in 1st and 2nd gear for turbo engines
+    pub(crate) current_low_gear_boost_limit: Option<String>,
     pub(crate) current_minimum_physics: AssettoCorsaPhysicsLevel,
     pub(crate) unpack_physics_data: bool,
     pub(crate) ac_car_tuner_compat: bool,
+    pub(crate) low_rpm_torque_ramp: bool,
+    pub(crate) scale_downshift_overrev: bool,
+    pub(crate) force_base_game_downgrade: bool,
+    pub(crate) adjust_gearbox_inertia: bool,
+    pub(crate) limiter_source: LimiterSource,
+    pub(crate) power_curve_smoothing: PowerCurveSmoothing,
+    curve_source: CurveSourceSelection,
+    /// The rpm,torque csv used when `curve_source` is [`CurveSourceSelection::FromCsv`]
+    curve_csv_path: Option<PathBuf>,
+    pub(crate) created_car: Option<PathBuf>,
+    pub(crate) sound_donor_car: Option<PathBuf>,
+    pub(crate) match_engine_sound: bool,
+    pub(crate) cancellation_token: CancellationToken,
+    swap_in_progress: bool,
+    favorites_only: bool,
+    car_filter: String,
+    mod_filter: String,
+    crate_engine_filter: String,
     status_message: String
 }
 
@@ -97,60 +257,184 @@ impl EngineSwapTab {
             available_physics: vec![AssettoCorsaPhysicsLevel::BaseGame],
             current_source: EngineSource::BeamNGMod,
             current_car: None,
+            batch_cars: Vec::new(),
+            batch_results: None,
+            preview: None,
+            preview_in_progress: false,
+            swap_warnings: None,
+            compatibility: None,
+            override_compatibility: false,
             current_mod: None,
             current_crate_eng: None,
+            sandbox_version: SandboxVersion::default(),
+            current_sandbox_engine: None,
+            sandbox_engines: Vec::new(),
+            sandbox_filter: String::new(),
+            sandbox_list_error: None,
             current_new_spec_name: "".to_string(),
             current_engine_weight: None,
+            engine_weight_estimated: false,
+            new_engine_weight: None,
+            sound_suggestions: Vec::new(),
+            update_weight_distribution: false,
+            current_limiter_override: None,
+            current_idle_override: None,
+            current_low_gear_boost_limit: None,
             current_minimum_physics: Default::default(),
             unpack_physics_data: false,
             ac_car_tuner_compat: false,
+            low_rpm_torque_ramp: false,
+            scale_downshift_overrev: false,
+            force_base_game_downgrade: false,
+            adjust_gearbox_inertia: false,
+            limiter_source: LimiterSource::default(),
+            power_curve_smoothing: PowerCurveSmoothing::default(),
+            curve_source: CurveSourceSelection::default(),
+            curve_csv_path: None,
+            created_car: None,
+            sound_donor_car: None,
+            match_engine_sound: false,
+            cancellation_token: CancellationToken::new(),
+            swap_in_progress: false,
+            favorites_only: false,
+            car_filter: String::new(),
+            mod_filter: String::new(),
+            crate_engine_filter: String::new(),
             status_message: "".to_string()
         }
     }
 
-    pub fn app_data_update(&mut self, _app_data: &ApplicationData, update_event: &Message) {
+    pub fn app_data_update(&mut self, app_data: &ApplicationData, update_event: &Message) {
         match update_event {
-            Message::RequestPathSelect(setting) => match setting {
+            Message::RequestPathSelect(setting) | Message::RevertSettingToDefault(setting) => match setting {
                 Setting::AcPath | Setting::BeamNGModPath | Setting::CrateEnginePath => self.refresh(),
-                _ => {}
+                Setting::LegacyAutomationUserdataPath | Setting::AutomationUserdataPath => {
+                    if self.current_source == EngineSource::AutomationSandbox {
+                        self.refresh_sandbox_engines(app_data);
+                    }
+                }
+                Setting::CarOutputPath => {}
             }
-            Message::RevertSettingToDefault(setting) => match setting {
-                Setting::AcPath | Setting::BeamNGModPath | Setting::CrateEnginePath => self.refresh(),
-                _ => {}
+            Message::ImportSettingsRequested | Message::ProfileSelected(_) => {
+                self.refresh();
+                if self.current_source == EngineSource::AutomationSandbox {
+                    self.refresh_sandbox_engines(app_data);
+                }
+            }
+            Message::EngineSwapRequested => self.car_filter.clear(),
+            Message::RefreshCrateEngines => {
+                // Refreshes are triggered by changes to the crate engine folder so the filter is
+                // kept; only a selected engine that has been removed is cleared
+                if let Some(name) = &self.current_crate_eng {
+                    if app_data.crate_engine_data.get_path_for(name).is_none() {
+                        self.current_crate_eng = None;
+                    }
+                }
             }
-            Message::EngineSwapRequested => {}
             _ => {}
         }
     }
 
-    pub fn update(&mut self, message: EngineSwapMessage, _app_data: &ApplicationData) {
+    pub fn update(&mut self, message: EngineSwapMessage, app_data: &ApplicationData) {
         match message {
             EngineSwapMessage::CarSelected(path_ref) => {
                 self.current_car = Some(path_ref.full_path.clone());
+                self.estimate_current_engine_weight();
             },
+            EngineSwapMessage::AddCarToBatch => {
+                if let Some(path) = &self.current_car {
+                    if !self.batch_cars.contains(path) {
+                        self.batch_cars.push(path.clone());
+                    }
+                }
+            }
+            EngineSwapMessage::BatchCarRemoved(path) => {
+                self.batch_cars.retain(|batch_path| *batch_path != path);
+            }
+            EngineSwapMessage::BatchCleared => {
+                self.batch_cars.clear();
+            }
+            EngineSwapMessage::CancelSwapRequested => {
+                if self.swap_in_progress {
+                    self.cancellation_token.cancel();
+                    self.status_message = String::from("Cancelling swap");
+                }
+            }
+            EngineSwapMessage::BatchResultsDismissed => {
+                self.batch_results = None;
+            }
+            EngineSwapMessage::PreviewDismissed => {
+                self.preview = None;
+            }
+            EngineSwapMessage::SwapWarningsDismissed => {
+                self.swap_warnings = None;
+            }
+            EngineSwapMessage::CarFilterEntered(filter) => {
+                self.car_filter = filter;
+                if let Some(path) = &self.current_car {
+                    if !ListPath::find_in(&app_data.assetto_corsa_data.available_cars, path).matches_filter(&self.car_filter) {
+                        self.current_car = None;
+                    }
+                }
+            }
             EngineSwapMessage::SourceChanged(e) => {
                 self.current_source = e;
                 self.current_mod = None;
                 self.current_crate_eng = None;
+                self.current_sandbox_engine = None;
                 self.current_new_spec_name = String::from("");
+                if e == EngineSource::AutomationSandbox {
+                    self.refresh_sandbox_engines(app_data);
+                }
             },
             EngineSwapMessage::ModSelected(path_ref) => {
-                const ZIP_PREFIX: &str = ".zip";
-                let mut spec_name = path_ref.to_string();
-                if spec_name.ends_with(ZIP_PREFIX) {
-                    spec_name.truncate(spec_name.len() - ZIP_PREFIX.len())
+                let mut spec_name = path_ref.file_name();
+                for ext in beam_ng::MOD_ARCHIVE_EXTENSIONS {
+                    let suffix = format!(".{}", ext);
+                    if spec_name.ends_with(&suffix) {
+                        spec_name.truncate(spec_name.len() - suffix.len());
+                        break;
+                    }
                 }
                 self.current_new_spec_name = spec_name;
                 self.current_mod = Some(path_ref.full_path.clone())
             },
+            EngineSwapMessage::ModFilterEntered(filter) => {
+                self.mod_filter = filter;
+                if let Some(path) = &self.current_mod {
+                    if !ListPath::find_in(&app_data.beam_ng_data.available_mods, path).matches_filter(&self.mod_filter) {
+                        self.current_mod = None;
+                    }
+                }
+            }
             EngineSwapMessage::CrateEngineSelected(name) => {
-                if let Some(metadata) = _app_data.crate_engine_data.get_metadata_for(&name) {
+                if let Some(metadata) = app_data.crate_engine_data.get_metadata_for(&name) {
                     self.current_new_spec_name = metadata.name().to_string()
                 } else {
                     self.current_new_spec_name = name.clone();
                 }
                 self.current_crate_eng = Some(name)
             },
+            EngineSwapMessage::CrateEngineFilterEntered(filter) => {
+                self.crate_engine_filter = filter;
+                if let Some(name) = &self.current_crate_eng {
+                    if !app_data.crate_engine_data.matches_filter(name, &self.crate_engine_filter) {
+                        self.current_crate_eng = None;
+                    }
+                }
+            }
+            EngineSwapMessage::SandboxVersionSelected(version) => {
+                self.sandbox_version = version;
+                self.current_sandbox_engine = None;
+                self.refresh_sandbox_engines(app_data);
+            }
+            EngineSwapMessage::SandboxFilterEntered(filter) => {
+                self.sandbox_filter = filter;
+            }
+            EngineSwapMessage::SandboxEngineSelected(listing) => {
+                self.current_new_spec_name = format!("{} {}", listing.family_name, listing.variant_name);
+                self.current_sandbox_engine = Some(listing);
+            }
             EngineSwapMessage::NameEntered(new_car_name) => {
                 self.current_new_spec_name = new_car_name
             },
@@ -158,6 +442,7 @@ impl EngineSwapTab {
                 self.current_minimum_physics = new_physics_level;
             }
             EngineSwapMessage::OldEngineWeightEntered(old_weight) => {
+                self.engine_weight_estimated = false;
                 if old_weight.is_empty() {
                     self.current_engine_weight = None;
                     return;
@@ -172,6 +457,15 @@ impl EngineSwapTab {
                     }
                 }
             }
+            EngineSwapMessage::LowGearBoostLimitEntered(limit) => {
+                self.current_low_gear_boost_limit = parse_boost_limit_percentage(limit, &mut self.status_message);
+            }
+            EngineSwapMessage::LimiterOverrideEntered(limiter) => {
+                self.current_limiter_override = parse_rpm_override(limiter, "Limiter", &mut self.status_message);
+            }
+            EngineSwapMessage::IdleOverrideEntered(idle) => {
+                self.current_idle_override = parse_rpm_override(idle, "Idle rpm", &mut self.status_message);
+            }
             EngineSwapMessage::UnpackToggled(bool_val) => {
                 if self.ac_car_tuner_compat == true {
                     self.unpack_physics_data = true;
@@ -185,6 +479,50 @@ impl EngineSwapTab {
                 }
                 self.ac_car_tuner_compat = bool_val;
             }
+            EngineSwapMessage::LowRpmTorqueRampToggled(bool_val) => {
+                self.low_rpm_torque_ramp = bool_val;
+            }
+            EngineSwapMessage::ScaleDownshiftOverrevToggled(bool_val) => {
+                self.scale_downshift_overrev = bool_val;
+            }
+            EngineSwapMessage::ForceBaseGameDowngradeToggled(bool_val) => {
+                self.force_base_game_downgrade = bool_val;
+            }
+            EngineSwapMessage::AdjustGearboxInertiaToggled(bool_val) => {
+                self.adjust_gearbox_inertia = bool_val;
+            }
+            EngineSwapMessage::LimiterSourceSelected(limiter_source) => {
+                self.limiter_source = limiter_source;
+            }
+            EngineSwapMessage::PowerCurveSmoothingSelected(smoothing) => {
+                self.power_curve_smoothing = smoothing;
+            }
+            EngineSwapMessage::CurveSourceSelected(curve_source) => {
+                self.curve_source = curve_source;
+            }
+            EngineSwapMessage::CurveCsvRequested => {
+                if let Some(csv_path) = FileDialog::new().add_filter("CSV", &["csv"]).pick_file() {
+                    self.curve_csv_path = Some(csv_path);
+                }
+            }
+            EngineSwapMessage::FavoritesOnlyToggled(bool_val) => {
+                self.favorites_only = bool_val;
+            }
+            EngineSwapMessage::SoundDonorSelected(path_ref) => {
+                self.sound_donor_car = Some(path_ref.full_path.clone());
+            }
+            EngineSwapMessage::SoundDonorCleared => {
+                self.sound_donor_car = None;
+            }
+            EngineSwapMessage::MatchEngineSoundToggled(bool_val) => {
+                self.match_engine_sound = bool_val;
+            }
+            EngineSwapMessage::CompatibilityOverrideToggled(bool_val) => {
+                self.override_compatibility = bool_val;
+            }
+            EngineSwapMessage::WeightDistributionUpdateToggled(bool_val) => {
+                self.update_weight_distribution = bool_val;
+            }
         }
     }
 
@@ -192,25 +530,261 @@ impl EngineSwapTab {
         match action_event {
             Message::DeleteCrateEngine(_) => {
                 self.current_crate_eng = None;
+                self.set_compatibility(None);
                 if self.current_source == EngineSource::CrateEngine {
                     self.current_new_spec_name.clear();
                 }
             }
+            Message::EngineSwapRequested => self.swap_in_progress = false,
             _ => {}
         }
     }
 
-    pub fn notify_action_failure(&mut self, _action_event: &Message, _reason: &str) {
+    pub fn notify_action_failure(&mut self, action_event: &Message, _reason: &str) {
+        if let Message::EngineSwapRequested = action_event {
+            self.swap_in_progress = false;
+        }
     }
 
     pub fn update_status(&mut self, status: String) {
         self.status_message = status;
     }
 
+    /// Show that a swap is running. The swap button stays disabled until the swap's success or
+    /// failure is notified
+    pub fn swap_started(&mut self) {
+        self.swap_in_progress = true;
+        self.status_message = String::from("Starting swap");
+    }
+
+    pub fn update_swap_progress(&mut self, progress: SwapProgress) {
+        self.status_message = format!("Swapping. {}", progress);
+    }
+
+    /// The cars to swap the engine into. This is the batch list if any cars have been added to it,
+    /// otherwise the currently selected car
+    pub fn swap_targets(&self) -> Vec<PathBuf> {
+        match self.batch_cars.is_empty() {
+            true => self.current_car.iter().cloned().collect(),
+            false => self.batch_cars.clone()
+        }
+    }
+
+    pub fn show_batch_results(&mut self, results: Vec<BatchSwapResult>) {
+        self.batch_results = Some(results);
+    }
+
+    /// Show the folder name the new car will be created with, warning if the spec name contains
+    /// characters that can't be used in a folder name
+    fn create_folder_name_preview(&self) -> Text<'_> {
+        let suffix = assetto_corsa::car::spec_folder_suffix(&self.current_new_spec_name);
+        if suffix.is_empty() {
+            return Text::new("The spec name must contain characters that can be used in a folder name")
+                .size(14)
+                .style(fail_red());
+        }
+        let existing_car_name = match self.batch_cars.is_empty() {
+            true => self.current_car.as_ref().and_then(|path| path.file_name()).map(|name| name.to_string_lossy().into_owned()),
+            false => None
+        }.unwrap_or_else(|| String::from("<car>"));
+        let folder_name = assetto_corsa::car::new_car_spec_folder_name(&existing_car_name, &self.current_new_spec_name);
+        let unsanitized_suffix = self.current_new_spec_name.to_lowercase().split_whitespace().collect::<Vec<&str>>().join("_");
+        match suffix == unsanitized_suffix {
+            true => Text::new(format!("Car folder: {}", folder_name)).size(14),
+            false => Text::new(format!("Car folder: {} (characters that can't be used in a folder name were removed)", folder_name))
+                .size(14)
+                .style(warn_yellow())
+        }
+    }
+
+    /// Fill in the existing engine weight with an estimate for the selected car unless a weight
+    /// has been entered
+    fn estimate_current_engine_weight(&mut self) {
+        if self.current_engine_weight.is_some() && !self.engine_weight_estimated {
+            return;
+        }
+        let estimate = self.current_car.as_ref().and_then(|car_path| {
+            match fabricator::estimate_engine_weight(car_path) {
+                Ok(estimate) => estimate,
+                Err(e) => {
+                    error!("Couldn't estimate the engine weight of {}. {}", car_path.display(), e);
+                    None
+                }
+            }
+        });
+        self.engine_weight_estimated = estimate.is_some();
+        self.current_engine_weight = estimate.map(|weight| weight.to_string());
+    }
+
+    /// Identifies the engine selected for the current engine source, or None if no engine is
+    /// selected
+    pub fn selected_engine_key(&self) -> Option<String> {
+        let engine_id = match self.current_source {
+            EngineSource::BeamNGMod => self.current_mod.as_ref()?.display().to_string(),
+            EngineSource::CrateEngine => self.current_crate_eng.clone()?,
+            EngineSource::AutomationSandbox => {
+                format!("{:?}:{}", self.sandbox_version, self.current_sandbox_engine.as_ref()?.uuid)
+            }
+        };
+        Some(format!("{}:{}", self.current_source, engine_id))
+    }
+
+    /// Record the result of checking that the selected engine can be swapped into the selected
+    /// cars. An override of a previous failed check is only cleared if the result changes
+    pub fn set_compatibility(&mut self, result: Option<Result<(), String>>) {
+        if self.compatibility != result {
+            self.override_compatibility = false;
+        }
+        self.compatibility = result;
+    }
+
+    /// The power curve source to swap with, or None if a csv curve was selected without
+    /// choosing the csv file
+    pub fn curve_source(&self) -> Option<CurveSource> {
+        match self.curve_source {
+            CurveSourceSelection::Calculated => Some(CurveSource::Calculator),
+            CurveSourceSelection::KeepDonor => Some(CurveSource::KeepDonor),
+            CurveSourceSelection::FromCsv => self.curve_csv_path.clone().map(CurveSource::FromCsv)
+        }
+    }
+
+    /// Whether the swap can go ahead; the compatibility check passed or was overridden
+    pub fn compatibility_check_passed(&self) -> bool {
+        match &self.compatibility {
+            Some(Err(_)) => self.override_compatibility,
+            _ => true
+        }
+    }
+
+    fn create_compatibility_row(&self) -> Option<Row<'_, Message>> {
+        let (img, style, text) = match self.compatibility.as_ref()? {
+            Ok(_) => (CIRCLE_TICK, theme::Svg::custom_fn(|_| {
+                svg::Appearance{color: Some(success_green())}
+            }), Text::new("Compatible").size(14)),
+            Err(reason) => (CIRCLE_CROSS, theme::Svg::custom_fn(|_| {
+                svg::Appearance{color: Some(fail_red())}
+            }), Text::new(reason.as_str()).size(14).style(fail_red()))
+        };
+        let mut row = Row::new()
+            .align_items(Alignment::Center)
+            .spacing(6)
+            .push(Svg::new(Handle::from_memory(img)).style(style).height(Length::Units(15)).width(Length::Units(15)))
+            .push(text);
+        if matches!(self.compatibility, Some(Err(_))) {
+            row = row.push(checkbox(
+                "Swap anyway".to_string(),
+                self.override_compatibility,
+                move |val| { Message::EngineSwap(EngineSwapMessage::CompatibilityOverrideToggled(val)) }
+            ).spacing(3));
+        }
+        Some(row)
+    }
+
+    pub fn set_new_engine_weight(&mut self, weight: Option<u32>) {
+        self.new_engine_weight = weight;
+    }
+
+    pub fn set_sound_suggestions(&mut self, suggestions: Vec<PathBuf>) {
+        self.sound_suggestions = suggestions;
+    }
+
+    fn create_engine_weight_container(&self) -> Column<'_, Message> {
+        let current_weight_value = match &self.current_engine_weight {
+            None => { "" }
+            Some(string) => {
+                string.as_str()
+            }
+        };
+        let mut weight_row = Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(TextInput::new(
+                "",
+                current_weight_value,
+                move |val| { Message::EngineSwap(EngineSwapMessage::OldEngineWeightEntered(val)) },
+            ).width(Length::Units(100)));
+        if let Some(new_weight) = self.new_engine_weight {
+            let new_weight_text = match current_weight_value.parse::<u32>() {
+                Ok(current_weight) => format!("New engine: {}kg ({:+}kg)", new_weight, new_weight as i64 - current_weight as i64),
+                Err(_) => format!("New engine: {}kg", new_weight)
+            };
+            weight_row = weight_row.push(Text::new(new_weight_text).size(14));
+        }
+        let mut weight_input_container = Column::new()
+            .push(Text::new("Existing engine weight in Kgs (Optional)"))
+            .push(weight_row)
+            .push(checkbox(
+                "Move weight distribution with the engine mass".to_string(),
+                self.update_weight_distribution,
+                move |val| { Message::EngineSwap(EngineSwapMessage::WeightDistributionUpdateToggled(val)) }
+            ).spacing(3).text_size(14));
+        if self.engine_weight_estimated {
+            weight_input_container = weight_input_container.push(
+                Text::new("Estimated from the car's specs, edit if you know the actual weight").size(14).style(warn_yellow())
+            );
+        }
+        weight_input_container
+    }
+
+    pub fn preview_started(&mut self) {
+        self.preview_in_progress = true;
+    }
+
+    pub fn is_preview_in_progress(&self) -> bool {
+        self.preview_in_progress
+    }
+
+    /// Show the preview once it has been worked out, or why it couldn't be
+    pub fn preview_finished(&mut self, result: Result<String, String>) {
+        self.preview_in_progress = false;
+        match result {
+            Ok(preview) => self.preview = Some(preview),
+            Err(e) => self.update_status(format!("Preview failed. {}", e))
+        }
+    }
+
+    /// List the problems found while creating the car at `car_path` until they are dismissed
+    pub fn show_swap_warnings(&mut self, car_path: PathBuf, warnings: Vec<FabricationWarning>) {
+        self.swap_warnings = Some((car_path, warnings));
+    }
+
     pub fn refresh(&mut self) {
         self.current_car = None;
+        self.set_compatibility(None);
+        self.batch_cars.clear();
+        self.sound_donor_car = None;
         self.current_mod = None;
-        self.current_crate_eng = None
+        self.current_crate_eng = None;
+        if self.engine_weight_estimated {
+            self.current_engine_weight = None;
+            self.engine_weight_estimated = false;
+        }
+        self.new_engine_weight = None;
+        self.sound_suggestions.clear();
+        self.car_filter.clear();
+        self.mod_filter.clear();
+        self.crate_engine_filter.clear();
+    }
+
+    fn refresh_sandbox_engines(&mut self, app_data: &ApplicationData) {
+        let sandbox_lookup = app_data.create_sandbox_finder().find_sandbox_db(self.sandbox_version);
+        match automation::sandbox::list_engines(&sandbox_lookup) {
+            Ok(engines) => {
+                self.sandbox_engines = engines;
+                self.sandbox_list_error = None;
+            }
+            Err(e) => {
+                error!("Failed to list Automation engines. {}", &e);
+                self.sandbox_engines.clear();
+                self.sandbox_list_error = Some(e);
+            }
+        }
+    }
+
+    fn filtered_sandbox_engines(&self) -> Vec<SandboxEngineListing> {
+        self.sandbox_engines.iter().filter(|listing| {
+            matches_filter(&listing.to_string(), &self.sandbox_filter)
+        }).cloned().collect()
     }
 }
 
@@ -228,19 +802,63 @@ impl Tab for EngineSwapTab {
     fn content<'a, 'b>(&'a self, app_data: &'b ApplicationData ) -> Element<'_, Self::Message, Renderer>
         where 'b: 'a
     {
-        let current_car = match &self.current_car {
-            None => { None }
-            Some(path) => {
-                Some(ListPath {full_path: path.clone()})
+        let current_car = self.current_car.as_ref().map(|path| {
+            ListPath::find_in(&app_data.assetto_corsa_data.available_cars, path)
+        });
+        let available_cars: Vec<ListPath> = app_data.assetto_corsa_data.available_cars.iter().filter(|car| {
+            car.matches_filter(&self.car_filter)
+        }).cloned().collect();
+        let mut open_car_button = Button::new(Text::new("Open folder"));
+        if let Some(path) = &self.current_car {
+            open_car_button = open_car_button.on_press(Message::OpenCarFolder(path.clone()));
+        }
+        let mut add_to_batch_button = Button::new(Text::new("Add to batch"));
+        if let Some(path) = &self.current_car {
+            if !self.batch_cars.contains(path) {
+                add_to_batch_button = add_to_batch_button.on_press(Message::EngineSwap(EngineSwapMessage::AddCarToBatch));
             }
-        };
-        let car_select_container = Column::new()
-            .push(Text::new("Assetto Corsa car"))
+        }
+        let car_select_row = Row::new()
+            .align_items(Alignment::Center)
+            .spacing(8)
             .push(pick_list(
-                &app_data.assetto_corsa_data.available_cars,
+                available_cars,
                 current_car,
                 move |val| { Message::EngineSwap(EngineSwapMessage::CarSelected(val)) },
-            ));
+            ))
+            .push(open_car_button)
+            .push(add_to_batch_button);
+        let mut car_select_container = Column::new()
+            .push(Text::new("Assetto Corsa car"))
+            .push(create_filter_input(&self.car_filter, EngineSwapMessage::CarFilterEntered))
+            .push(car_select_row);
+        if !self.batch_cars.is_empty() {
+            let batch_list = self.batch_cars.iter().fold(
+                Column::new().spacing(3).padding(Padding::from([5, 0, 0, 10])),
+                |col, path| {
+                    col.push(Row::new()
+                        .align_items(Alignment::Center)
+                        .spacing(8)
+                        .push(Text::new(format!("{}", ListPath::find_in(&app_data.assetto_corsa_data.available_cars, path))).size(16))
+                        .push(Button::new(Text::new("Remove").size(14))
+                            .on_press(Message::EngineSwap(EngineSwapMessage::BatchCarRemoved(path.clone())))))
+                }
+            );
+            car_select_container = car_select_container
+                .push(Row::new()
+                    .align_items(Alignment::Center)
+                    .spacing(8)
+                    .padding(Padding::from([5, 0, 0, 0]))
+                    .push(Text::new(format!("Batch swap into {} cars", self.batch_cars.len())))
+                    .push(Button::new(Text::new("Clear batch").size(14))
+                        .on_press(Message::EngineSwap(EngineSwapMessage::BatchCleared))))
+                .push(batch_list);
+        }
+        if app_data.assetto_corsa_data.available_cars.is_empty() {
+            if let Some(hint) = app_data.steam_not_detected_hint() {
+                car_select_container = car_select_container.push(Text::new(hint).size(14));
+            }
+        }
 
         let mut source_select_container = Column::new()
             .spacing(3)
@@ -265,19 +883,44 @@ impl Tab for EngineSwapTab {
 
         let engine_source_selector = match self.current_source {
             EngineSource::BeamNGMod => {
-                let current_mod = match &self.current_mod {
-                    None => { None }
-                    Some(path) => {
-                        Some(ListPath {full_path: path.clone()})
-                    }
-                };
-                Column::new()
-                    .push(Text::new("BeamNG mod"))
+                let current_mod = self.current_mod.as_ref().map(|path| {
+                    ListPath::find_in(&app_data.beam_ng_data.available_mods, path)
+                });
+                let available_mods: Vec<ListPath> = app_data.beam_ng_data.available_mods.iter().filter(|m| {
+                    (!self.favorites_only || app_data.is_favorite(&m.favorite_id())) && m.matches_filter(&self.mod_filter)
+                }).cloned().collect();
+                let mut mod_select_row = Row::new()
+                    .align_items(Alignment::Center)
+                    .spacing(5)
                     .push(PickList::new(
-                        &app_data.beam_ng_data.available_mods,
-                        current_mod,
+                        available_mods,
+                        current_mod.clone(),
                         move |val| { Message::EngineSwap(EngineSwapMessage::ModSelected(val)) }
-                    ))
+                    ));
+                if let Some(selected_mod) = &current_mod {
+                    let favorite_id = selected_mod.favorite_id();
+                    mod_select_row = mod_select_row.push(
+                        create_favorite_button(app_data.is_favorite(&favorite_id), Message::ToggleFavorite(favorite_id))
+                    );
+                    if app_data.beam_ng_data.version_mismatch(&selected_mod.full_path).is_some() {
+                        mod_select_row = mod_select_row.push(Text::new("newer than sandbox").size(14));
+                    }
+                }
+                let mut mod_select_container = Column::new()
+                    .push(Text::new("BeamNG mod"))
+                    .push(create_filter_input(&self.mod_filter, EngineSwapMessage::ModFilterEntered))
+                    .push(mod_select_row);
+                if let Some(mismatch) = self.current_mod.as_ref().and_then(|path| app_data.beam_ng_data.version_mismatch(path)) {
+                    mod_select_container = mod_select_container.push(Text::new(mismatch).size(14));
+                }
+                if let Some(hint) = &app_data.beam_ng_data.mod_path_hint {
+                    mod_select_container = mod_select_container.push(Text::new(hint.as_str()).size(14));
+                } else if app_data.beam_ng_data.available_mods.is_empty() {
+                    if let Some(hint) = app_data.steam_not_detected_hint() {
+                        mod_select_container = mod_select_container.push(Text::new(hint).size(14));
+                    }
+                }
+                mod_select_container
             }
             EngineSource::CrateEngine => {
                 let current_crate_eng = match &self.current_crate_eng {
@@ -286,15 +929,66 @@ impl Tab for EngineSwapTab {
                         Some(path.clone())
                     }
                 };
+                let available_engines: Vec<String> = app_data.crate_engine_data.available_engines.iter().filter(|name| {
+                    (!self.favorites_only ||
+                        app_data.crate_engine_data.favorite_id_for(name).map_or(false, |id| app_data.is_favorite(&id))) &&
+                        app_data.crate_engine_data.matches_filter(name, &self.crate_engine_filter)
+                }).cloned().collect();
+                let mut crate_select_row = Row::new()
+                    .align_items(Alignment::Center)
+                    .spacing(5)
+                    .push(PickList::new(
+                        available_engines,
+                        current_crate_eng.clone(),
+                        move |val| { Message::EngineSwap(EngineSwapMessage::CrateEngineSelected(val)) }
+                    ));
+                if let Some(favorite_id) = current_crate_eng.as_ref().and_then(|name| app_data.crate_engine_data.favorite_id_for(name)) {
+                    crate_select_row = crate_select_row.push(
+                        create_favorite_button(app_data.is_favorite(&favorite_id), Message::ToggleFavorite(favorite_id))
+                    );
+                }
                 Column::new()
                     .push(Text::new("Crate Engine"))
+                    .push(create_filter_input(&self.crate_engine_filter, EngineSwapMessage::CrateEngineFilterEntered))
+                    .push(crate_select_row)
+            }
+            EngineSource::AutomationSandbox => {
+                let sandbox_select_row = Row::new()
+                    .align_items(Alignment::Center)
+                    .spacing(5)
                     .push(PickList::new(
-                        &app_data.crate_engine_data.available_engines,
-                        current_crate_eng,
-                        move |val| { Message::EngineSwap(EngineSwapMessage::CrateEngineSelected(val)) }
+                        &SandboxVersion::ALL[..],
+                        Some(self.sandbox_version),
+                        move |val| { Message::EngineSwap(EngineSwapMessage::SandboxVersionSelected(val)) }
                     ))
+                    .push(TextInput::new(
+                        "Search engines",
+                        &self.sandbox_filter,
+                        move |val| { Message::EngineSwap(EngineSwapMessage::SandboxFilterEntered(val)) }
+                    ).width(Length::Units(250)));
+                let mut sandbox_select_container = Column::new()
+                    .spacing(5)
+                    .push(Text::new("Automation engine"))
+                    .push(sandbox_select_row)
+                    .push(PickList::new(
+                        self.filtered_sandbox_engines(),
+                        self.current_sandbox_engine.clone(),
+                        move |val| { Message::EngineSwap(EngineSwapMessage::SandboxEngineSelected(val)) }
+                    ).placeholder("Select an engine"));
+                if let Some(err) = &self.sandbox_list_error {
+                    sandbox_select_container = sandbox_select_container.push(Text::new(err.as_str()).size(14));
+                }
+                sandbox_select_container
             }
         };
+        let engine_source_selector = match self.current_source {
+            EngineSource::AutomationSandbox => engine_source_selector,
+            _ => engine_source_selector.push(checkbox(
+                "Favorites only".to_string(),
+                self.favorites_only,
+                move |val| { Message::EngineSwap(EngineSwapMessage::FavoritesOnlyToggled(val)) }
+            ).spacing(3))
+        };
 
         let placeholder = match self.current_new_spec_name.as_str() {
             "" => { "Enter new spec name" }
@@ -305,39 +999,116 @@ impl Tab for EngineSwapTab {
             &self.current_new_spec_name,
             move|val| { Message::EngineSwap(EngineSwapMessage::NameEntered(val)) },
         ).width(Length::Units(500));
-        let car_name_container = Column::new()
+        let mut car_name_container = Column::new()
             .push(Text::new("New spec name (this will be appended to the created car)"))
             .push(new_spec_input);
+        if !self.current_new_spec_name.is_empty() {
+            car_name_container = car_name_container.push(self.create_folder_name_preview());
+        }
 
-        let current_weight_value = match &self.current_engine_weight {
-            None => { "" }
-            Some(string) => {
-                string.as_str()
-            }
-        };
-        let weight_input_container = Column::new()
-            .push(Text::new("Existing engine weight in Kgs (Optional)"))
+        let weight_input_container = self.create_engine_weight_container();
+        let limiter_input_container = Column::new()
+            .push(Text::new("Limiter override in rpm (Optional)"))
             .push(TextInput::new(
                 "",
-                current_weight_value,
-                move |val| { Message::EngineSwap(EngineSwapMessage::OldEngineWeightEntered(val)) },
+                self.current_limiter_override.as_deref().unwrap_or(""),
+                move |val| { Message::EngineSwap(EngineSwapMessage::LimiterOverrideEntered(val)) },
+            ).width(Length::Units(100)));
+        let idle_input_container = Column::new()
+            .push(Text::new("Idle rpm override (Optional)"))
+            .push(TextInput::new(
+                "",
+                self.current_idle_override.as_deref().unwrap_or(""),
+                move |val| { Message::EngineSwap(EngineSwapMessage::IdleOverrideEntered(val)) },
+            ).width(Length::Units(100)));
+        let boost_limit_input_container = Column::new()
+            .push(Text::new("Low gear boost limit in % (Optional)"))
+            .push(TextInput::new(
+                "",
+                self.current_low_gear_boost_limit.as_deref().unwrap_or(""),
+                move |val| { Message::EngineSwap(EngineSwapMessage::LowGearBoostLimitEntered(val)) },
             ).width(Length::Units(100)));
+        let numeric_input_row = Row::new()
+            .spacing(20)
+            .push(weight_input_container)
+            .push(limiter_input_container)
+            .push(idle_input_container)
+            .push(boost_limit_input_container);
+        let current_sound_donor = self.sound_donor_car.as_ref().map(|path| ListPath::from_path(path.clone()));
+        let mut clear_donor_button = Button::new(Text::new("Clear"));
+        if current_sound_donor.is_some() {
+            clear_donor_button = clear_donor_button.on_press(Message::EngineSwap(EngineSwapMessage::SoundDonorCleared));
+        }
+        let mut sound_donor_container = Column::new()
+            .push(Text::new("Copy engine sound from car (Optional)"))
+            .push(Row::new()
+                .align_items(Alignment::Center)
+                .spacing(8)
+                .push(pick_list(
+                    &app_data.assetto_corsa_data.available_cars,
+                    current_sound_donor,
+                    move |val| { Message::EngineSwap(EngineSwapMessage::SoundDonorSelected(val)) },
+                ))
+                .push(clear_donor_button))
+            .push(checkbox(
+                "Otherwise use the sound of an installed Kunos car with a similar engine layout".to_string(),
+                self.match_engine_sound,
+                move |val| { Message::EngineSwap(EngineSwapMessage::MatchEngineSoundToggled(val)) }
+            ).spacing(3));
+        if self.match_engine_sound && self.sound_donor_car.is_none() && !self.sound_suggestions.is_empty() {
+            let suggestion_row = self.sound_suggestions.iter().take(3).fold(
+                Row::new().align_items(Alignment::Center).spacing(5).push(Text::new("Best matches:").size(14)),
+                |row, car_path| row.push(
+                    Button::new(Text::new(car_path.file_name().unwrap_or_default().to_string_lossy()).size(14))
+                        .on_press(Message::EngineSwap(EngineSwapMessage::SoundDonorSelected(ListPath::from_path(car_path.clone()))))
+                )
+            );
+            sound_donor_container = sound_donor_container.push(suggestion_row);
+        }
         let select_container = Column::new()
             .spacing(20)
             .push(car_select_container)
             .push(source_select_container)
             .push(engine_source_selector)
             .push(car_name_container)
-            .push(weight_input_container);
+            .push(numeric_input_row)
+            .push(sound_donor_container);
 
-        let swap_button = Button::new(Text::new("Swap"))
-            .width(Length::Units(60))
-            .on_press(Message::EngineSwapRequested);
+        let mut swap_button = Button::new(Text::new("Swap"))
+            .width(Length::Units(60));
+        if !self.swap_in_progress && self.compatibility_check_passed() {
+            swap_button = swap_button.on_press(Message::EngineSwapRequested);
+        }
+        let mut preview_button = Button::new(Text::new("Preview"))
+            .width(Length::Units(80));
+        if !self.swap_in_progress && !self.preview_in_progress {
+            preview_button = preview_button.on_press(Message::EngineSwapPreviewRequested);
+        }
+        let mut cancel_button = Button::new(Text::new("Cancel"))
+            .width(Length::Units(70));
+        if self.swap_in_progress && !self.cancellation_token.is_cancelled() {
+            cancel_button = cancel_button.on_press(Message::EngineSwap(EngineSwapMessage::CancelSwapRequested));
+        }
         let physics_pick_list = PickList::new(
             &self.available_physics,
             Some(self.current_minimum_physics),
             move |val| { Message::EngineSwap(EngineSwapMessage::PhysicsLevelSelected(val)) }
         );
+        let limiter_pick_list = PickList::new(
+            LimiterSource::ALL,
+            Some(self.limiter_source),
+            move |val| { Message::EngineSwap(EngineSwapMessage::LimiterSourceSelected(val)) }
+        );
+        let smoothing_pick_list = PickList::new(
+            PowerCurveSmoothing::ALL,
+            Some(self.power_curve_smoothing),
+            move |val| { Message::EngineSwap(EngineSwapMessage::PowerCurveSmoothingSelected(val)) }
+        );
+        let curve_source_pick_list = PickList::new(
+            CurveSourceSelection::ALL,
+            Some(self.curve_source),
+            move |val| { Message::EngineSwap(EngineSwapMessage::CurveSourceSelected(val)) }
+        );
         let ac_car_tuner_checkbox = checkbox(
             "AC Car Tuner compatibility".to_string(),
             self.ac_car_tuner_compat,
@@ -348,42 +1119,231 @@ impl Tab for EngineSwapTab {
             self.unpack_physics_data,
             move |val| { Message::EngineSwap(EngineSwapMessage::UnpackToggled(val)) }
         ).spacing(3);
+        let torque_ramp_checkbox = checkbox(
+            "Low rpm torque ramp".to_string(),
+            self.low_rpm_torque_ramp,
+            move |val| { Message::EngineSwap(EngineSwapMessage::LowRpmTorqueRampToggled(val)) }
+        ).spacing(3);
+        let overrev_checkbox = checkbox(
+            "Scale downshift overrev".to_string(),
+            self.scale_downshift_overrev,
+            move |val| { Message::EngineSwap(EngineSwapMessage::ScaleDownshiftOverrevToggled(val)) }
+        ).spacing(3);
+        let gearbox_inertia_checkbox = checkbox(
+            "Scale gearbox inertia".to_string(),
+            self.adjust_gearbox_inertia,
+            move |val| { Message::EngineSwap(EngineSwapMessage::AdjustGearboxInertiaToggled(val)) }
+        ).spacing(3);
         
-        let options_row = Row::new()
+        let mut options_row = Row::new()
             .align_items(Alignment::Center)
             .spacing(10)
             .push(ac_car_tuner_checkbox)
-            .push(unpack_checkbox);
+            .push(unpack_checkbox)
+            .push(torque_ramp_checkbox)
+            .push(overrev_checkbox)
+            .push(gearbox_inertia_checkbox);
+        if self.current_minimum_physics == AssettoCorsaPhysicsLevel::BaseGame {
+            options_row = options_row.push(checkbox(
+                "Force base-game downgrade".to_string(),
+                self.force_base_game_downgrade,
+                move |val| { Message::EngineSwap(EngineSwapMessage::ForceBaseGameDowngradeToggled(val)) }
+            ).spacing(3));
+        }
 
-        let control_row = Row::new()
+        let mut control_row = Row::new()
             .align_items(Alignment::Center)
             .padding(Padding::from([5, 0, 0, 0]))
             .spacing(10)
             .push(swap_button)
-            .push(physics_pick_list);
+            .push(cancel_button)
+            .push(preview_button)
+            .push(physics_pick_list)
+            .push(limiter_pick_list)
+            .push(smoothing_pick_list)
+            .push(curve_source_pick_list);
+        if self.curve_source == CurveSourceSelection::FromCsv {
+            let csv_label = match &self.curve_csv_path {
+                None => String::from("No csv chosen"),
+                Some(path) => path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+            };
+            control_row = control_row
+                .push(Button::new(Text::new("Choose csv"))
+                    .on_press(Message::EngineSwap(EngineSwapMessage::CurveCsvRequested)))
+                .push(Text::new(csv_label));
+        }
         
-        let swap_col = Column::new().width(Length::Fill)
+        let mut swap_col = Column::new().width(Length::Fill)
             .align_items(Alignment::Start)
             .spacing(5)
-            .push(control_row)
-            .push(options_row);
+            .push(control_row);
+        if let Some(compatibility_row) = self.create_compatibility_row() {
+            swap_col = swap_col.push(compatibility_row);
+        }
+        swap_col = swap_col.push(options_row);
 
         let mut layout = Column::new().width(Length::Fill)
             .align_items(Alignment::Start)
             .padding(Padding::from([0, 10]))
-            .spacing(30)
+            .spacing(30);
+        if let Some(warning) = app_data.cars_folder_warning() {
+            layout = layout.push(Text::new(warning).style(warn_yellow()));
+        }
+        layout = layout
             .push(select_container)
             .push(swap_col);
-            
+
 
         if !self.status_message.is_empty() {
-            layout = layout.push(
-                Row::new()
-                    .align_items(Alignment::Center)
-                    .push(Text::new(self.status_message.as_str()).horizontal_alignment(Horizontal::Center))
-            )
+            let mut status_row = Row::new()
+                .align_items(Alignment::Center)
+                .spacing(10)
+                .push(Text::new(self.status_message.as_str()).horizontal_alignment(Horizontal::Center));
+            if let Some(path) = &self.created_car {
+                status_row = status_row
+                    .push(
+                        Button::new(Text::new("Open created car"))
+                            .on_press(Message::OpenCarFolder(path.clone()))
+                    )
+                    .push(
+                        Button::new(Text::new("Export engine files"))
+                            .on_press(Message::ExportEngineFiles(path.clone()))
+                    );
+            }
+            layout = layout.push(status_row)
+        }
+        let content = Container::new(layout).padding(20);
+        if let Some(preview) = &self.preview {
+            return Modal::new(content, create_preview_modal(preview)).into();
+        }
+        if let Some((car_path, warnings)) = &self.swap_warnings {
+            return Modal::new(content, create_swap_warnings_modal(car_path, warnings)).into();
+        }
+        match &self.batch_results {
+            None => content.into(),
+            Some(results) => Modal::new(content, create_batch_results_modal(results)).into()
+        }
+    }
+}
+
+fn create_batch_results_modal<'a>(results: &[BatchSwapResult]) -> Element<'a, Message> {
+    let f: fn(&Theme) -> container::Appearance = |_theme: &Theme| {
+        container::Appearance{
+            text_color: None,
+            background: Some(Background::Color(WHITE)),
+            border_radius: 1.0,
+            border_width: 1.0,
+            border_color: Color::BLACK,
+        }
+    };
+    let result_list = results.iter().fold(Column::new().spacing(5), |col, result| {
+        col.push(Text::new(result.describe()).size(16))
+    });
+    let failures = results.iter().filter(|result| !result.is_success()).count();
+    Container::new(
+        Column::new()
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .push(Text::new(format!("Batch swap complete. {} succeeded, {} failed", results.len() - failures, failures)))
+            .push(scrollable(result_list).height(Length::Units(300)))
+            .push(Button::new(Text::new("Ok"))
+                .style(theme::Button::Positive)
+                .on_press(Message::EngineSwap(EngineSwapMessage::BatchResultsDismissed)))
+    ).style(theme::Container::Custom(
+        Box::new(f)
+    )).padding(20).into()
+}
+
+fn create_swap_warnings_modal<'a>(car_path: &Path, warnings: &[FabricationWarning]) -> Element<'a, Message> {
+    let f: fn(&Theme) -> container::Appearance = |_theme: &Theme| {
+        container::Appearance{
+            text_color: None,
+            background: Some(Background::Color(WHITE)),
+            border_radius: 1.0,
+            border_width: 1.0,
+            border_color: Color::BLACK,
+        }
+    };
+    let warning_list = warnings.iter().fold(Column::new().spacing(5), |col, warning| {
+        col.push(Row::new()
+            .spacing(10)
+            .push(Text::new(warning.file().unwrap_or("-").to_string()).size(16).width(Length::Units(220)))
+            .push(Text::new(warning.message().to_string()).size(16)))
+    });
+    let car_name = car_path.file_name().map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| car_path.display().to_string());
+    Container::new(
+        Column::new()
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .push(Text::new(format!("Created {} with {} warning(s). Check the affected files before driving the car", car_name, warnings.len())))
+            .push(scrollable(warning_list).height(Length::Units(300)))
+            .push(Button::new(Text::new("Ok"))
+                .style(theme::Button::Positive)
+                .on_press(Message::EngineSwap(EngineSwapMessage::SwapWarningsDismissed)))
+    ).style(theme::Container::Custom(
+        Box::new(f)
+    )).padding(20).into()
+}
+
+fn create_preview_modal<'a>(preview: &str) -> Element<'a, Message> {
+    let f: fn(&Theme) -> container::Appearance = |_theme: &Theme| {
+        container::Appearance{
+            text_color: None,
+            background: Some(Background::Color(WHITE)),
+            border_radius: 1.0,
+            border_width: 1.0,
+            border_color: Color::BLACK,
+        }
+    };
+    Container::new(
+        Column::new()
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .push(Text::new("Swap preview. Nothing has been written"))
+            .push(scrollable(Text::new(preview.to_string()).size(16)).height(Length::Units(300)))
+            .push(Button::new(Text::new("Ok"))
+                .style(theme::Button::Positive)
+                .on_press(Message::EngineSwap(EngineSwapMessage::PreviewDismissed)))
+    ).style(theme::Container::Custom(
+        Box::new(f)
+    )).padding(20).into()
+}
+
+fn create_filter_input<'a>(filter: &str, on_change: fn(String) -> EngineSwapMessage) -> Element<'a, Message> {
+    TextInput::new(
+        "Filter",
+        filter,
+        move |val| { Message::EngineSwap(on_change(val)) },
+    ).width(Length::Units(250)).into()
+}
+
+/// Keep `input` if it is a whole percentage from 1 to 100. A limit of 0% would leave no boost at
+/// all in the low gears
+fn parse_boost_limit_percentage(input: String, status_message: &mut String) -> Option<String> {
+    if input.is_empty() {
+        return None;
+    }
+    match input.parse::<u32>() {
+        Ok(percentage) if (1..=100).contains(&percentage) => Some(input),
+        _ => {
+            *status_message = String::from("Low gear boost limit must be a whole percentage from 1 to 100");
+            None
         }
-        Container::new(layout).padding(20).into()
     }
 }
 
+/// Parse an optional rpm override input. An empty input means the value calculated from the
+/// engine data will be used. An rpm of 0 is rejected as the engine couldn't run
+fn parse_rpm_override(input: String, name: &str, status_message: &mut String) -> Option<String> {
+    if input.is_empty() {
+        return None;
+    }
+    match input.parse::<u32>() {
+        Ok(rpm) if rpm > 0 => Some(input),
+        _ => {
+            *status_message = format!("{} override must be a whole number of rpm above 0", name);
+            None
+        }
+    }
+}
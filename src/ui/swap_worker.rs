@@ -0,0 +1,373 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use iced::{subscription, Subscription};
+use iced::futures::channel::mpsc;
+use iced::futures::{future, StreamExt};
+use tracing::{span, Level, info, error, warn};
+use automation::sandbox::{EngineV1, SandboxEngineListing, SandboxFinder, SandboxVersion};
+
+use crate::assetto_corsa;
+use crate::assetto_corsa::car::{CloneStage, delete_car_at};
+use crate::fabricator;
+use crate::fabricator::{AdditionalAcCarData, AssettoCorsaCarSettings, EngineParameterCalculator, FabricationError, FabricationWarning, ProgressReporter, SwapPhase, SwapPreview};
+use crate::registry::{self, CreatedCarRecord, CreatedCarSource};
+use crate::ui::Message;
+use crate::ui::swap::BatchSwapResult;
+
+/// Where the engine for a [`SwapJob`] is loaded from
+pub enum SwapSource {
+    BeamNGMod(PathBuf, SandboxFinder),
+    CrateEngine(String, Option<PathBuf>),
+    AutomationSandbox(SandboxEngineListing, SandboxVersion, SandboxFinder)
+}
+
+impl SwapSource {
+    /// The weight in kg of the engine that will be swapped in
+    pub fn engine_weight(&self) -> Result<u32, String> {
+        let res = match self {
+            SwapSource::BeamNGMod(mod_path, sandbox_finder) => {
+                fabricator::automation_engine_weight(mod_path.as_path(), sandbox_finder.clone())
+            }
+            SwapSource::CrateEngine(crate_eng_name, crate_path) => match crate_path {
+                Some(crate_path) => fabricator::crate_engine_weight(crate_path.as_path()),
+                None => Err(FabricationError::MissingDataSource(format!("path for crate engine {}", crate_eng_name)))
+            }
+            SwapSource::AutomationSandbox(listing, sandbox_version, sandbox_finder) => {
+                load_sandbox_engine(listing, *sandbox_version, sandbox_finder).and_then(fabricator::sandbox_engine_weight)
+            }
+        };
+        res.map_err(|err| err.to_string())
+    }
+
+    /// Check that this engine can be swapped into the AC car at `car_path` without writing
+    /// anything
+    pub fn check_compatibility(&self, car_path: &Path) -> Result<(), String> {
+        let res = match self {
+            SwapSource::BeamNGMod(mod_path, sandbox_finder) => {
+                fabricator::check_automation_engine_compatibility(mod_path.as_path(), car_path, sandbox_finder.clone())
+            }
+            SwapSource::CrateEngine(crate_eng_name, crate_path) => match crate_path {
+                Some(crate_path) => fabricator::check_crate_engine_compatibility(crate_path.as_path(), car_path),
+                None => Err(FabricationError::MissingDataSource(format!("path for crate engine {}", crate_eng_name)))
+            }
+            SwapSource::AutomationSandbox(listing, sandbox_version, sandbox_finder) => {
+                load_sandbox_engine(listing, *sandbox_version, sandbox_finder).and_then(|engine| {
+                    fabricator::check_sandbox_engine_compatibility(engine, car_path)
+                })
+            }
+        };
+        res.map_err(|err| err.to_string())
+    }
+
+    /// The cars in the AC install at `ac_install_path` whose engine sound best suits this engine,
+    /// best match first
+    pub fn sound_suggestions(&self, ac_install_path: &Path) -> Result<Vec<PathBuf>, String> {
+        let calculator = match self {
+            SwapSource::BeamNGMod(mod_path, sandbox_finder) => {
+                EngineParameterCalculator::from_beam_ng_mod(mod_path.as_path(), sandbox_finder.clone())
+            }
+            SwapSource::CrateEngine(crate_eng_name, crate_path) => match crate_path {
+                Some(crate_path) => EngineParameterCalculator::from_crate_engine(crate_path.as_path()),
+                None => Err(FabricationError::MissingDataSource(format!("path for crate engine {}", crate_eng_name)))
+            }
+            SwapSource::AutomationSandbox(listing, sandbox_version, sandbox_finder) => {
+                load_sandbox_engine(listing, *sandbox_version, sandbox_finder).and_then(EngineParameterCalculator::from_sandbox_engine)
+            }
+        }.map_err(|err| err.to_string())?;
+        let ac_install = assetto_corsa::Installation::from_path(ac_install_path.to_path_buf());
+        Ok(fabricator::suggest_sound_donors(&ac_install, &calculator.provenance()))
+    }
+}
+
+/// Everything needed to swap an engine into a set of cars, captured from the swap tab when the
+/// swap is requested so that it can be run away from the UI thread
+pub struct SwapJob {
+    pub id: u64,
+    pub ac_install_path: PathBuf,
+    /// Folder to create the new cars in. They are created next to the existing car when unset
+    pub output_path: Option<PathBuf>,
+    pub targets: Vec<PathBuf>,
+    pub new_spec_name: String,
+    pub new_spec_opts: u64,
+    pub source: SwapSource,
+    pub car_settings: AssettoCorsaCarSettings,
+    pub additional_car_data: AdditionalAcCarData
+}
+
+/// Where an in-progress swap has got to
+#[derive(Debug, Clone, Copy)]
+pub struct SwapProgress {
+    pub car_number: usize,
+    pub car_count: usize,
+    pub phase: SwapPhase
+}
+
+impl Display for SwapProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.car_count > 1 {
+            true => write!(f, "Car {} of {}: {}", self.car_number, self.car_count, self.phase),
+            false => write!(f, "{}", self.phase)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SwapEvent {
+    Progress(SwapProgress),
+    Finished(Vec<BatchSwapResult>)
+}
+
+enum WorkerState {
+    Starting(Arc<SwapJob>),
+    Running(mpsc::UnboundedReceiver<SwapEvent>),
+    Finished
+}
+
+/// Run `job` on a background thread, producing a [`Message::SwapUpdate`] each time the swap moves
+/// into a new phase and once more with the results when every car has been swapped
+pub fn swap_worker(job: Option<Arc<SwapJob>>) -> Subscription<Message> {
+    match job {
+        None => Subscription::none(),
+        Some(job) => subscription::unfold(job.id, WorkerState::Starting(job), next_event)
+    }
+}
+
+async fn next_event(state: WorkerState) -> (Option<Message>, WorkerState) {
+    match state {
+        WorkerState::Starting(job) => {
+            let (sender, receiver) = mpsc::unbounded();
+            thread::spawn(move || {
+                let results = job.run(&sender);
+                let _ = sender.unbounded_send(SwapEvent::Finished(results));
+            });
+            (None, WorkerState::Running(receiver))
+        }
+        WorkerState::Running(mut receiver) => match receiver.next().await {
+            Some(event) => (Some(Message::SwapUpdate(event)), WorkerState::Running(receiver)),
+            None => (None, WorkerState::Finished)
+        }
+        WorkerState::Finished => {
+            future::pending::<()>().await;
+            (None, WorkerState::Finished)
+        }
+    }
+}
+
+impl SwapJob {
+    fn run(&self, sender: &mpsc::UnboundedSender<SwapEvent>) -> Vec<BatchSwapResult> {
+        let ac_install = assetto_corsa::Installation::from_path(self.ac_install_path.clone());
+        let car_count = self.targets.len();
+        if car_count > 1 {
+            info!("Batch swapping engine into {} cars", car_count);
+        }
+        let mut results = Vec::new();
+        for (idx, car_path) in self.targets.iter().enumerate() {
+            let progress_sender = sender.clone();
+            let progress = ProgressReporter::new(move |phase| {
+                let _ = progress_sender.unbounded_send(SwapEvent::Progress(SwapProgress {
+                    car_number: idx + 1,
+                    car_count,
+                    phase
+                }));
+            });
+            let outcome = self.swap_into_new_spec(&ac_install, car_path, progress);
+            results.push(BatchSwapResult::new(car_path.clone(), outcome));
+        }
+        results
+    }
+
+    /// Create a new spec of the AC car at `current_car_path` and swap the engine into it.
+    /// Returns the path of the created car along with any warnings about it, or a status message
+    /// describing why the swap failed. A car that was created for a failed swap is removed
+    fn swap_into_new_spec(&self,
+                          ac_install: &assetto_corsa::Installation,
+                          current_car_path: &Path,
+                          progress: ProgressReporter) -> Result<(PathBuf, Vec<FabricationWarning>), String> {
+        let new_car_path = {
+            let span = span!(Level::INFO, "Creating new car spec");
+            let _enter = span.enter();
+
+            let report_clone_stage = |stage: CloneStage| progress.report(stage.into());
+            let create_result = match &self.output_path {
+                None => assetto_corsa::car::create_new_car_spec(ac_install,
+                                                                &current_car_path.to_path_buf(),
+                                                                self.new_spec_name.as_str(),
+                                                                self.new_spec_opts,
+                                                                &report_clone_stage),
+                Some(output_path) => assetto_corsa::car::create_new_car_spec_in(ac_install,
+                                                                                &current_car_path.to_path_buf(),
+                                                                                output_path,
+                                                                                self.new_spec_name.as_str(),
+                                                                                self.new_spec_opts,
+                                                                                &report_clone_stage)
+            };
+            match create_result {
+                Ok(path) => { path }
+                Err(e) => {
+                    error!("Swap failed: {}", e.to_string());
+                    return Err(format!("Swap failed: {}", e.to_string()));
+                }
+            }
+        };
+
+        let mut car_settings = self.car_settings.clone();
+        car_settings.progress = progress;
+        let additional_car_settings = self.additional_car_data.clone();
+
+        let res = match &self.source {
+            SwapSource::BeamNGMod(mod_path, sandbox_finder) => {
+                let span = span!(Level::INFO, "Updating car physics from BeamNG mod");
+                let _enter = span.enter();
+                fabricator::swap_automation_engine_into_ac_car(mod_path.as_path(),
+                                                               new_car_path.as_path(),
+                                                               sandbox_finder.clone(),
+                                                               car_settings,
+                                                               additional_car_settings)
+            }
+            SwapSource::CrateEngine(crate_eng_name, crate_path) => {
+                match crate_path {
+                    Some(crate_path) => {
+                        let span = span!(Level::INFO, "Updating car physics from crate engine");
+                        let _enter = span.enter();
+                        fabricator::swap_crate_engine_into_ac_car(crate_path.as_path(),
+                                                                  new_car_path.as_path(),
+                                                                  car_settings,
+                                                                  additional_car_settings.with_engine_name(crate_eng_name.clone()))
+                    }
+                    None => Err(FabricationError::MissingDataSource(format!("path for crate engine {}", crate_eng_name)))
+                }
+            }
+            SwapSource::AutomationSandbox(listing, sandbox_version, sandbox_finder) => {
+                let span = span!(Level::INFO, "Updating car physics from Automation sandbox");
+                let _enter = span.enter();
+                load_sandbox_engine(listing, *sandbox_version, sandbox_finder).and_then(|engine| {
+                    fabricator::swap_sandbox_engine_into_ac_car(engine,
+                                                                new_car_path.as_path(),
+                                                                car_settings,
+                                                                additional_car_settings)
+                })
+            }
+        };
+        match res {
+            Ok(mut warnings) => {
+                if let Err(e) = assetto_corsa::car::validate_car_sfx(&new_car_path) {
+                    warn!("{}", e.to_string());
+                    warnings.push(FabricationWarning::new("the car may have no sound, use 'Repair sounds' in the edit tab to fix".to_string()));
+                }
+                self.record_created_car(current_car_path, &new_car_path);
+                Ok((new_car_path, warnings))
+            }
+            Err(err) => {
+                remove_partially_created_car(ac_install, &new_car_path);
+                match err {
+                    FabricationError::Cancelled => {
+                        info!("Swap into {} cancelled", new_car_path.display());
+                        Err(String::from("Swap cancelled"))
+                    }
+                    _ => {
+                        error!("{}", &err);
+                        Err(err.to_string())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Add the car created at `new_car_path` to the created car registry. Failures are only logged
+    /// as the car itself was created successfully
+    fn record_created_car(&self, donor_car: &Path, new_car_path: &Path) {
+        let (source, source_name) = match &self.source {
+            SwapSource::BeamNGMod(mod_path, _) => {
+                (CreatedCarSource::BeamNGMod, mod_path.file_name().unwrap_or_default().to_string_lossy().into_owned())
+            }
+            SwapSource::CrateEngine(crate_eng_name, _) => (CreatedCarSource::CrateEngine, crate_eng_name.clone()),
+            SwapSource::AutomationSandbox(listing, _, _) => (CreatedCarSource::AutomationSandbox, listing.to_string())
+        };
+        let record = CreatedCarRecord::new(new_car_path.to_path_buf(),
+                                           donor_car.to_path_buf(),
+                                           source,
+                                           source_name,
+                                           fabricator::swap_report_source_hash(new_car_path));
+        if let Err(e) = registry::record_created_car(&registry::get_default_registry_path(), record) {
+            warn!("Failed to record created car {}. {}", new_car_path.display(), e);
+        }
+    }
+
+    /// Work out what swapping the engine into the AC car at `car_path` would change without
+    /// modifying the car
+    pub fn preview(&self, car_path: &Path) -> Result<SwapPreview, String> {
+        let car_settings = self.car_settings.clone();
+        let additional_car_settings = self.additional_car_data.clone();
+        let res = match &self.source {
+            SwapSource::BeamNGMod(mod_path, sandbox_finder) => {
+                fabricator::preview_automation_engine_swap(mod_path.as_path(),
+                                                           car_path,
+                                                           sandbox_finder.clone(),
+                                                           car_settings,
+                                                           additional_car_settings)
+            }
+            SwapSource::CrateEngine(crate_eng_name, crate_path) => match crate_path {
+                Some(crate_path) => {
+                    fabricator::preview_crate_engine_swap(crate_path.as_path(),
+                                                          car_path,
+                                                          car_settings,
+                                                          additional_car_settings.with_engine_name(crate_eng_name.clone()))
+                }
+                None => Err(FabricationError::MissingDataSource(format!("path for crate engine {}", crate_eng_name)))
+            }
+            SwapSource::AutomationSandbox(listing, sandbox_version, sandbox_finder) => {
+                load_sandbox_engine(listing, *sandbox_version, sandbox_finder).and_then(|engine| {
+                    fabricator::preview_sandbox_engine_swap(engine, car_path, car_settings, additional_car_settings)
+                })
+            }
+        };
+        res.map_err(|err| {
+            error!("{}", &err);
+            err.to_string()
+        })
+    }
+}
+
+fn load_sandbox_engine(listing: &SandboxEngineListing,
+                       sandbox_version: SandboxVersion,
+                       sandbox_finder: &SandboxFinder) -> Result<EngineV1, FabricationError> {
+    let sandbox_lookup = sandbox_finder.find_sandbox_db(sandbox_version);
+    match automation::sandbox::load_engine_by_uuid(&listing.uuid, sandbox_lookup) {
+        Ok(Some(engine)) => Ok(engine),
+        Ok(None) => Err(FabricationError::MissingDataSource(
+            format!("Automation engine {} in the {} sandbox", listing, sandbox_version)
+        )),
+        Err(e) => Err(FabricationError::FailedToLoad(format!("Sandbox db engine {}", listing.uuid), e))
+    }
+}
+
+/// Remove a car that was cloned for a swap that then failed or was cancelled so that no
+/// half-created car is left behind
+fn remove_partially_created_car(ac_install: &assetto_corsa::Installation, new_car_path: &Path) {
+    if let Err(e) = delete_car_at(ac_install, new_car_path) {
+        error!("Failed to delete {}. {}", new_car_path.display(), e.to_string());
+    }
+}
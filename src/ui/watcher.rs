@@ -0,0 +1,102 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use iced::{subscription, Subscription};
+use iced::futures::channel::mpsc;
+use iced::futures::{future, StreamExt};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, DebouncedEventKind, Debouncer};
+use tracing::{error, info};
+use crate::ui::Message;
+
+/// How long a crate engine file has to go without changing before the crate engines are
+/// refreshed. Stops a partially written engine from triggering a refresh for each write
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(750);
+
+enum WatchState {
+    Starting(PathBuf),
+    Watching(Debouncer<RecommendedWatcher>, mpsc::UnboundedReceiver<()>),
+    Stopped
+}
+
+/// Watch `path` for crate engine files being created, modified or deleted and request a
+/// refresh of the crate engines once they stop changing.
+/// A new watcher is created whenever `path` changes
+pub fn crate_engine_folder_watcher(path: Option<PathBuf>) -> Subscription<Message> {
+    match path {
+        None => Subscription::none(),
+        Some(path) => subscription::unfold(path.clone(), WatchState::Starting(path), next_event)
+    }
+}
+
+async fn next_event(state: WatchState) -> (Option<Message>, WatchState) {
+    match state {
+        WatchState::Starting(path) => match start_watcher(&path) {
+            Ok((debouncer, receiver)) => {
+                info!("Watching {} for crate engine changes", path.display());
+                (None, WatchState::Watching(debouncer, receiver))
+            }
+            Err(e) => {
+                error!("Failed to watch {} for crate engine changes. {}", path.display(), e.to_string());
+                (None, WatchState::Stopped)
+            }
+        }
+        WatchState::Watching(debouncer, mut receiver) => match receiver.next().await {
+            Some(()) => (Some(Message::RefreshCrateEngines), WatchState::Watching(debouncer, receiver)),
+            None => (None, WatchState::Stopped)
+        }
+        WatchState::Stopped => {
+            future::pending::<()>().await;
+            (None, WatchState::Stopped)
+        }
+    }
+}
+
+fn start_watcher(path: &Path) -> notify::Result<(Debouncer<RecommendedWatcher>, mpsc::UnboundedReceiver<()>)> {
+    let (sender, receiver) = mpsc::unbounded();
+    let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, move |result: DebounceEventResult| {
+        match result {
+            Ok(events) => {
+                // AnyContinuous events are raised while a file is still being written so they
+                // are ignored; an Any event follows once the file stops changing
+                let engines_changed = events.iter().any(|event| {
+                    event.kind == DebouncedEventKind::Any && is_crate_engine_file(&event.path)
+                });
+                if engines_changed {
+                    let _ = sender.unbounded_send(());
+                }
+            }
+            Err(e) => error!("Error while watching for crate engine changes. {}", e.to_string())
+        }
+    })?;
+    debouncer.watcher().watch(path, RecursiveMode::NonRecursive)?;
+    Ok((debouncer, receiver))
+}
+
+fn is_crate_engine_file(path: &Path) -> bool {
+    match path.extension() {
+        Some(ext) => ext == crate_engine::CRATE_ENGINE_FILE_SUFFIX,
+        None => false
+    }
+}
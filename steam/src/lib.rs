@@ -19,15 +19,54 @@
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
 
 #[cfg(target_os = "linux")]
 use directories::UserDirs;
 
+/// Whether a Steam installation was found in the location that default game paths are derived from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallState {
+    Detected(PathBuf),
+    NotDetected(PathBuf)
+}
+
+impl InstallState {
+    pub fn is_detected(&self) -> bool {
+        matches!(self, InstallState::Detected(_))
+    }
+}
+
+impl Display for InstallState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallState::Detected(path) => write!(f, "Steam detected at {}", path.display()),
+            InstallState::NotDetected(path) => write!(f, "Steam not detected at {}", path.display())
+        }
+    }
+}
+
+pub fn detect_install() -> InstallState {
+    let install_dir = get_install_dir();
+    match install_dir.is_dir() {
+        true => InstallState::Detected(install_dir),
+        false => InstallState::NotDetected(install_dir)
+    }
+}
+
+pub fn is_installed() -> bool {
+    detect_install().is_detected()
+}
+
 #[cfg(target_os = "linux")]
 pub fn get_install_dir() -> PathBuf {
-    let user_dirs = UserDirs::new().unwrap();
-    let mut install_path = PathBuf::from(user_dirs.home_dir());
+    // Without a home dir there's nowhere to look for Steam; return a path that won't exist
+    // so callers fall back to the paths set by the user
+    let mut install_path = match UserDirs::new() {
+        Some(user_dirs) => PathBuf::from(user_dirs.home_dir()),
+        None => PathBuf::new()
+    };
     for path in [".steam", "debian-installation"] {
         install_path.push(path);
     }
@@ -59,8 +59,7 @@ pub fn get_filetypes_in_path(path: &Path, file_type: &str) -> io::Result<Vec<Pat
 /// in the path then the next filename returned would be test_2.txt
 ///
 pub fn create_safe_filename_in_path(path: &Path, name: &str, extension: &str) -> PathBuf {
-    let mut sanitized_name = sanitize_filename::sanitize(name);
-    sanitized_name = sanitized_name.replace(" ", "_");
+    let sanitized_name = sanitize_name(name);
     let mut file_path = path.join(format!("{}.{}", sanitized_name, extension));
     let mut extra_num = 2;
     while file_path.exists() {
@@ -70,6 +69,13 @@ pub fn create_safe_filename_in_path(path: &Path, name: &str, extension: &str) ->
     file_path
 }
 
+/// Strip any characters from `name` that would be illegal to use in a filesystem path and
+/// replace spaces with underscores. This is the name used by [`create_safe_filename_in_path`]
+/// before any number is appended to make it unique
+pub fn sanitize_name(name: &str) -> String {
+    sanitize_filename::sanitize(name).replace(" ", "_")
+}
+
 pub fn is_directory_read_writable(path: &Path) -> io::Result<(bool, bool)> {
     if !path.exists() || !path.is_dir() {
         return Err(io::Error::from(io::ErrorKind::NotFound));
@@ -89,4 +95,56 @@ pub fn is_directory_read_writable(path: &Path) -> io::Result<(bool, bool)> {
     };
 
     Ok((read_permission, write_permission))
-}
\ No newline at end of file
+}
+/// Open the provided path in the platform's file manager. The opener process is spawned and
+/// left to run so this returns as soon as it has been launched
+pub fn open_in_file_manager(path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Err(io::Error::from(io::ErrorKind::NotFound));
+    }
+    #[cfg(target_os = "windows")]
+    let opener = "explorer";
+    #[cfg(target_os = "macos")]
+    let opener = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let opener = "xdg-open";
+
+    std::process::Command::new(opener).arg(path).spawn()?;
+    Ok(())
+}
+
+/// Create a link at `link` that points to the directory `target`. A directory junction is used
+/// on Windows as, unlike a symlink, it can be created without admin rights
+pub fn link_directory(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        let output = std::process::Command::new("cmd")
+            .arg("/C").arg("mklink").arg("/J").arg(link).arg(target)
+            .output()?;
+        match output.status.success() {
+            true => Ok(()),
+            false => Err(io::Error::new(io::ErrorKind::Other,
+                                        String::from_utf8_lossy(&output.stderr).trim().to_string()))
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        std::os::unix::fs::symlink(target, link)
+    }
+}
+
+/// Remove a link created with [`link_directory`] without touching the directory it points to
+pub fn remove_directory_link(link: &Path) -> io::Result<()> {
+    if !fs::symlink_metadata(link)?.file_type().is_symlink() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  format!("{} isn't a link", link.display())));
+    }
+    #[cfg(target_os = "windows")]
+    {
+        fs::remove_dir(link)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        fs::remove_file(link)
+    }
+}
@@ -18,7 +18,7 @@
  * You should have received a copy of the GNU General Public License
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
-use sha2::Digest;
+use sha2::{Digest, Sha256};
 
 pub fn create_sha256_hash_array(hasher: impl Digest) -> Option<[u8; 32]> {
     let hash: Vec<u8> = hasher.finalize().iter().map(|b| *b).collect();
@@ -29,3 +29,10 @@ pub fn create_sha256_hash_array(hasher: impl Digest) -> Option<[u8; 32]> {
         }
     }
 }
+
+/// The sha256 hash of `data` as a lowercase hex string
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -23,3 +23,4 @@ pub mod numeric;
 pub mod filesystem;
 pub mod units;
 pub mod hash;
+pub mod time;
@@ -18,6 +18,7 @@
  * You should have received a copy of the GNU General Public License
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
+use statrs::distribution::{ContinuousCDF, Normal};
 
 pub fn round_float_to(float: f64, decimal_places: u32) -> f64 {
     let precision_base: u64 = 10;
@@ -49,9 +50,54 @@ pub fn is_valid_percentage(val: i32) -> bool {
     false
 }
 
+pub fn normal_lerp(min: f32, max: f32, input: f32, standard_deviation: f64) -> f32 {
+    // Ensure t is between 0 and 1
+    let t = input.clamp(0.0, 1.0);
+
+    // Define the normal distribution with mean = 0.5 and standard deviation = 0.15
+    // These values can be adjusted based on how spread out you want the distribution
+    let normal = Normal::new(0.5, standard_deviation).unwrap();
+
+    // Compute the CDF to map t to a normal distribution
+    let mapped_t = normal.cdf(t as f64) as f32;
+
+    // Perform the interpolation
+    min + mapped_t * (max - min)
+}
+
+/// The value of `curve` at `x`, interpolated between the surrounding points. Values of `x`
+/// beyond the ends of the curve are held at the end values when `clamp` is set and are `None`
+/// otherwise. An empty curve is always `None`
+pub fn interpolate_curve(curve: &[(f64, f64)], x: f64, clamp: bool) -> Option<f64> {
+    let (first, last) = (curve.first()?, curve.last()?);
+    if x <= first.0 || x >= last.0 {
+        let end = if x <= first.0 { first } else { last };
+        return (clamp || x == end.0).then_some(end.1);
+    }
+    curve.windows(2).find(|points| x <= points[1].0).map(|points| {
+        let ((x0, y0), (x1, y1)) = (points[0], points[1]);
+        match x1 > x0 {
+            true => y0 + (y1 - y0) * (x - x0) / (x1 - x0),
+            false => y0
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::numeric::{is_valid_percentage, round_up_to_nearest_multiple};
+    use crate::numeric::{interpolate_curve, is_valid_percentage, round_up_to_nearest_multiple};
+
+    #[test]
+    fn curve_interpolation() {
+        let curve = [(1000.0, 100.0), (2000.0, 200.0), (4000.0, 100.0)];
+        assert_eq!(interpolate_curve(&curve, 1500.0, false), Some(150.0));
+        assert_eq!(interpolate_curve(&curve, 3000.0, true), Some(150.0));
+        assert_eq!(interpolate_curve(&curve, 4000.0, false), Some(100.0));
+        assert_eq!(interpolate_curve(&curve, 500.0, false), None);
+        assert_eq!(interpolate_curve(&curve, 500.0, true), Some(100.0));
+        assert_eq!(interpolate_curve(&curve, 5000.0, true), Some(100.0));
+        assert_eq!(interpolate_curve(&[], 1000.0, true), None);
+    }
 
     #[test]
     fn round_multiple_tests()  {
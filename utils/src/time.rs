@@ -0,0 +1,66 @@
+/*
+ * Copyright (c):
+ * 2024 zephyrj
+ * zephyrj@protonmail.com
+ *
+ * This file is part of engine-crane.
+ *
+ * engine-crane is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * engine-crane is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Format `time` as a UTC date in the form YYYY-MM-DD
+pub fn utc_date_string(time: SystemTime) -> String {
+    let days = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => (duration.as_secs() / 86400) as i64,
+        Err(_) => 0
+    };
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Today's date in UTC in the form YYYY-MM-DD
+pub fn current_utc_date_string() -> String {
+    utc_date_string(SystemTime::now())
+}
+
+/// Convert a count of days since 1970-01-01 into a (year, month, day) date in the
+/// proleptic Gregorian calendar
+fn civil_from_days(days_since_epoch: i64) -> (i64, u32, u32) {
+    let z = days_since_epoch + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+    use crate::time::utc_date_string;
+
+    #[test]
+    fn utc_date_formatting() {
+        assert_eq!(utc_date_string(UNIX_EPOCH), "1970-01-01");
+        assert_eq!(utc_date_string(UNIX_EPOCH + Duration::from_secs(951782400)), "2000-02-29");
+        assert_eq!(utc_date_string(UNIX_EPOCH + Duration::from_secs(1704067199)), "2023-12-31");
+        assert_eq!(utc_date_string(UNIX_EPOCH + Duration::from_secs(1704067200)), "2024-01-01");
+    }
+}
@@ -19,8 +19,138 @@
  * along with engine-crane. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::fmt::{Display, Formatter};
+
+/// Mechanical horsepower in kW
+pub const KW_PER_BHP: f64 = 0.745699872;
+/// Metric horsepower in kW
+pub const KW_PER_PS: f64 = 0.73549875;
+/// Pound-feet in Nm
+pub const NM_PER_LB_FT: f64 = 1.3558179483;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUnit {
+    Kw,
+    Bhp,
+    Ps
+}
+
+impl PowerUnit {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            PowerUnit::Kw => "kW",
+            PowerUnit::Bhp => "bhp",
+            PowerUnit::Ps => "PS"
+        }
+    }
+}
+
+impl Display for PowerUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TorqueUnit {
+    Nm,
+    LbFt
+}
+
+impl TorqueUnit {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            TorqueUnit::Nm => "Nm",
+            TorqueUnit::LbFt => "lb-ft"
+        }
+    }
+}
+
+impl Display for TorqueUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// A power figure. Stored in kW and converted on request
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Power(f64);
+
+impl Power {
+    pub fn from_kw(kw: f64) -> Power {
+        Power(kw)
+    }
+
+    pub fn from_bhp(bhp: f64) -> Power {
+        Power(bhp * KW_PER_BHP)
+    }
+
+    pub fn from_ps(ps: f64) -> Power {
+        Power(ps * KW_PER_PS)
+    }
+
+    pub fn kw(&self) -> f64 {
+        self.0
+    }
+
+    pub fn bhp(&self) -> f64 {
+        self.0 / KW_PER_BHP
+    }
+
+    pub fn ps(&self) -> f64 {
+        self.0 / KW_PER_PS
+    }
+
+    pub fn value_in(&self, unit: PowerUnit) -> f64 {
+        match unit {
+            PowerUnit::Kw => self.kw(),
+            PowerUnit::Bhp => self.bhp(),
+            PowerUnit::Ps => self.ps()
+        }
+    }
+
+    /// The value in `unit` to `decimals` decimal places followed by the unit symbol, e.g. "450bhp"
+    pub fn format_as(&self, unit: PowerUnit, decimals: usize) -> String {
+        format!("{:.*}{}", decimals, self.value_in(unit), unit.symbol())
+    }
+}
+
+/// A torque figure. Stored in Nm and converted on request
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Torque(f64);
+
+impl Torque {
+    pub fn from_nm(nm: f64) -> Torque {
+        Torque(nm)
+    }
+
+    pub fn from_lb_ft(lb_ft: f64) -> Torque {
+        Torque(lb_ft * NM_PER_LB_FT)
+    }
+
+    pub fn nm(&self) -> f64 {
+        self.0
+    }
+
+    pub fn lb_ft(&self) -> f64 {
+        self.0 / NM_PER_LB_FT
+    }
+
+    pub fn value_in(&self, unit: TorqueUnit) -> f64 {
+        match unit {
+            TorqueUnit::Nm => self.nm(),
+            TorqueUnit::LbFt => self.lb_ft()
+        }
+    }
+
+    /// The value in `unit` to `decimals` decimal places followed by the unit symbol, e.g. "500Nm"
+    pub fn format_as(&self, unit: TorqueUnit, decimals: usize) -> String {
+        format!("{:.*}{}", decimals, self.value_in(unit), unit.symbol())
+    }
+}
+
 pub fn kw_to_bhp(power_kw: f64) -> f64 {
-    power_kw * 1.341
+    Power::from_kw(power_kw).bhp()
 }
 
 pub fn calculate_power_kw(rpm: f32, torque: f32) -> f32 {
@@ -35,3 +165,32 @@ pub fn kg_hour_to_g_min(kg_per_hour: f64) -> f64 {
     (kg_per_hour * 1000.0) / 60.0
 }
 
+
+#[cfg(test)]
+mod tests {
+    use crate::units::{Power, PowerUnit, Torque, TorqueUnit};
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn power_conversions() {
+        assert_close(Power::from_bhp(1.0).kw(), 0.745699872);
+        assert_close(Power::from_ps(1.0).kw(), 0.73549875);
+        assert_close(Power::from_kw(100.0).bhp(), 134.102209);
+        assert_close(Power::from_kw(100.0).ps(), 135.962162);
+        assert_close(Power::from_bhp(450.0).value_in(PowerUnit::Bhp), 450.0);
+        assert_eq!(Power::from_kw(300.0).format_as(PowerUnit::Bhp, 0), "402bhp");
+        assert_eq!(Power::from_kw(300.0).format_as(PowerUnit::Kw, 1), "300.0kW");
+        assert_eq!(Power::from_kw(300.0).format_as(PowerUnit::Ps, 0), "408PS");
+    }
+
+    #[test]
+    fn torque_conversions() {
+        assert_close(Torque::from_lb_ft(1.0).nm(), 1.3558179483);
+        assert_close(Torque::from_nm(500.0).lb_ft(), 368.781075);
+        assert_eq!(Torque::from_nm(500.0).format_as(TorqueUnit::Nm, 0), "500Nm");
+        assert_eq!(Torque::from_nm(500.0).format_as(TorqueUnit::LbFt, 0), "369lb-ft");
+    }
+}